@@ -1,51 +1,120 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // oxid - A fast, keyboard-driven note manager TUI for Linux
 
-use crate::config::{expand_path, key_display_string, load_config, Config, ResolvedKeys};
-use crate::git::{get_git_status, GitStatus};
+use crate::backup::BackupJob;
+use crate::capture;
+use crate::clipboard::{self, CopyMethod};
+use crate::config::{
+    expand_path, key_display_string, load_config, Config, ResolvedKeys, StatusSegment,
+    MIN_EDITOR_PERCENT,
+};
+use crate::content::{self, ContentLoader, NoteContent};
+use crate::crypt;
+use crate::diff::{self, LineKind as DiffLineKind};
+use crate::export::{collect_markdown, export_html, export_pdf, ExportScope, ExportSummary};
+use crate::frontmatter::{self, parse_tags};
+use crate::git::{self, get_git_status, FileStatus, GitStatus};
+use crate::graph::{GraphExport, GraphFormat, GraphOptions};
 use crate::handlers::key_matches;
-use crate::search::{filter_notes, get_match_indices};
+use crate::jumps::{Jump, JumpList};
+use crate::keymap::{check_keybindings, KeyContext, Keymap, WHICH_KEY_DELAY};
+use crate::links::LinkGraph;
+use crate::markdown::{EmbedSource, RenderCache};
+use crate::random::Rng;
+use crate::recent::RecentFiles;
+use crate::replace::{apply_file, ReplaceFile, ReplaceScan};
+use crate::scan::{self, vault_ignore, TaskEntry};
+use crate::search::{
+    filter_notes, first_matching_line, get_match_indices, list_match_indices, narrows,
+    regex_match_indices, regex_query, visible_rows, CaseMode, RowHighlight, RowHighlights,
+    SearchMode, REGEX_PREFIX,
+};
+use crate::shell::RunningCommand;
+use crate::snippets::{self, SnippetSession};
 use crate::spellcheck::Spellchecker;
+use crate::tasks;
 use crate::telescope::{
-    filter_telescope_notes, find_md_files_recursive, get_telescope_match_indices,
+    filter_telescope_notes, find_md_files_recursive, get_telescope_match_indices, search_buffers,
+    BufferMatch, TelescopeMode,
 };
 use crate::templates::Template;
-use crate::theme::{load_theme, ResolvedTheme};
+use crate::text_input::{InputChange, TextInput};
+use crate::theme::{
+    load_theme, preset_variant, ColorMode, ResolvedTheme, Theme, ThemeMode, THEME_PRESETS,
+};
+use crate::vault::{self, Ignore, Walk, ARCHIVE_FOLDER, IGNORE_FILE_NAME};
+use crate::wrap;
+use crate::zettel;
 use anyhow::Result;
 use chrono::Local;
-use nucleo_matcher::{Config as MatcherConfig, Matcher};
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config as MatcherConfig, Matcher, Utf32Str};
 use regex::Regex;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
-use std::io::Read;
-use std::path::PathBuf;
-use std::process::Command;
-use std::time::{Duration, Instant};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
 use tui_textarea::{CursorMove, Scrolling, TextArea};
 use walkdir::WalkDir;
 
-/// Maximum bytes to read from a note file for indexing and preview.
-const MAX_CONTENT_BYTES: usize = 100_000;
+/// Number of recently closed tabs kept for reopening.
+const MAX_CLOSED_TABS: usize = 20;
+
+/// Largest Normal-mode count; motions stop at the buffer's edges well before it.
+const MAX_COUNT: usize = 99_999;
+
+/// Maximum number of entries in the spelling suggestion popup.
+const MAX_SPELL_SUGGESTIONS: usize = 10;
 
 /// Default date format for daily notes.
 const DAILY_NOTE_DATE_FORMAT: &str = "%Y-%m-%d";
 
 /// File extension for markdown notes.
-#[allow(dead_code)]
 const MARKDOWN_EXT: &str = "md";
 
+/// Extensions shown in the explorer but never loaded into the editor.
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "ico", "pdf", "zip", "gz", "tar", "mp3", "mp4",
+    "ogg", "wav", "odt", "docx", "xlsx",
+];
+
+/// Messages kept for *Show Messages*; older ones are dropped.
+const MAX_MESSAGES: usize = 200;
+
+/// How long a new message stays in the footer.
+const MESSAGE_DURATION: Duration = Duration::from_secs(8);
+
+/// How often the git status is read again when nothing in oxid changed files.
+const GIT_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often config.toml and theme.toml are checked for changes made outside oxid.
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Config file name.
-#[allow(dead_code)]
 const CONFIG_FILE_NAME: &str = "config.toml";
 
 /// Theme file name.
-#[allow(dead_code)]
 const THEME_FILE_NAME: &str = "theme.toml";
 
 /// Layout mode for editor panes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EditorLayout {
     Single,
+    /// Two panes side by side.
     SplitVertical,
+    /// Two panes stacked top/bottom.
+    SplitHorizontal,
+}
+
+impl EditorLayout {
+    pub fn is_split(self) -> bool {
+        self != EditorLayout::Single
+    }
 }
 
 /// Which pane or popup has focus.
@@ -69,13 +138,79 @@ pub enum Focus {
     TaskView,
     /// Delete confirmation popup (N/y).
     DeleteConfirm,
+    /// Move-to-directory picker (m).
+    MovePicker,
+    /// Add Tag: tag for the selected or marked notes (#).
+    TagPrompt,
+    /// Directory duplicate confirmation popup (N/y).
+    DuplicateConfirm,
+    /// Recently opened files popup.
+    RecentFiles,
+    /// Choose the buffer shown in the split's second pane.
+    BufferPicker,
+    SpellSuggest,
+    ReplacePrompt,
+    ReplaceReview,
+    /// Keybinding conflicts and invalid key strings.
+    KeybindingReport,
+    /// Config or theme parse errors, shown once at startup.
+    ConfigError,
+    /// Confirmation for a `[[commands]]` entry with `confirm = true` (N/y).
+    UserCommandConfirm,
+    /// Output of a running or finished `[[commands]]` entry.
+    CommandOutput,
+    /// Insert Image: path of the image file to copy into the assets folder.
+    ImagePathPrompt,
+    /// Git Init: optional URL of the `origin` remote.
+    GitRemotePrompt,
+    /// HTML export: choose what to export.
+    ExportScope,
+    /// HTML export: output directory prompt.
+    ExportDir,
+    /// HTML export: files written and links skipped.
+    ExportSummary,
+    /// Link graph export: format, orphans and neighborhood.
+    GraphOptions,
+    /// Link graph export: output file prompt.
+    GraphPath,
+    /// Wiki link matching the aliases of several notes: pick one.
+    AliasPicker,
+    /// Quick capture: one line for the inbox note.
+    QuickCapture,
+    /// Add Task: a task for today's daily note or the note named with `@`.
+    AddTask,
+    /// Open Random Note: optional tag and scope.
+    RandomNote,
+    /// Message history (Show Messages).
+    Messages,
+    /// Unified diff of unsaved changes or of `git diff`.
+    DiffView,
 }
 
+/// Source of stable buffer ids.
+static NEXT_BUFFER_ID: AtomicU64 = AtomicU64::new(0);
+
 /// Single editor buffer (tab).
 #[derive(Clone)]
 pub struct EditorBuffer {
+    /// Stable identity; survives reordering and removal of other buffers.
+    pub id: u64,
     pub path: Option<PathBuf>,
     pub textarea: TextArea<'static>,
+    /// Column `j`/`k` return to, with the cursor position they left: until the cursor moves
+    /// some other way, passing over short lines keeps the column from before them.
+    goal: Option<(usize, (usize, usize))>,
+    /// Text columns and rows of the pane the buffer was last drawn in (0 before the first
+    /// draw).
+    pub text_width: Cell<u16>,
+    pub text_height: Cell<u16>,
+    /// First display row of the soft-wrapped view: a line and a row within it.
+    pub wrap_top: Cell<(usize, usize)>,
+    /// Replacements (`r`, `~`) in a row on top of the undo history. Each is a delete and an
+    /// insert in the textarea, which `u` undoes together; any other edit resets the count.
+    joined_undos: usize,
+    /// Edited since it was loaded or last saved; auto-save writes only these buffers.
+    pub dirty: bool,
 }
 
 impl EditorBuffer {
@@ -85,7 +220,237 @@ impl EditorBuffer {
         } else {
             TextArea::new(lines)
         };
-        Self { path, textarea }
+        let id = NEXT_BUFFER_ID.fetch_add(1, Ordering::Relaxed);
+        Self {
+            id,
+            path,
+            textarea,
+            goal: None,
+            text_width: Cell::new(0),
+            text_height: Cell::new(0),
+            wrap_top: Cell::new((0, 0)),
+            joined_undos: 0,
+            dirty: false,
+        }
+    }
+
+    /// `u`: undo the last edit, both halves of a replacement.
+    pub fn undo(&mut self) {
+        if self.joined_undos > 0 {
+            self.joined_undos -= 1;
+            self.textarea.undo();
+        }
+        self.textarea.undo();
+        self.clamp_to_line();
+    }
+
+    /// Replace the whole text as one undo step, keeping the cursor where possible.
+    fn replace_text(&mut self, text: &str) {
+        let (row, col) = self.textarea.cursor();
+        let old_empty =
+            self.textarea.lines().iter().all(String::is_empty) && self.textarea.lines().len() == 1;
+        self.textarea.select_all();
+        self.textarea.insert_str(text);
+        // Deleting the old text and inserting the new are two steps in the textarea.
+        if !old_empty && !text.is_empty() {
+            self.joined_undos += 1;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        self.textarea
+            .move_cursor(CursorMove::Jump(row as u16, col as u16));
+    }
+
+    /// Move the cursor `count` times, stopping early once it no longer moves.
+    fn repeat_move(&mut self, motion: CursorMove, count: usize) {
+        for _ in 0..count {
+            let before = self.textarea.cursor();
+            self.textarea.move_cursor(motion);
+            if self.textarea.cursor() == before {
+                break;
+            }
+        }
+    }
+
+    /// Home with `editor.smart_indent`: to the first non-blank character of the line, or to
+    /// column 0 when the cursor is already there.
+    pub fn smart_home(&mut self) {
+        let (row, col) = self.textarea.cursor();
+        let indent = leading_whitespace(&self.textarea.lines()[row])
+            .chars()
+            .count();
+        let col = if col == indent { 0 } else { indent };
+        self.textarea
+            .move_cursor(CursorMove::Jump(row as u16, col as u16));
+    }
+
+    /// Replace up to `count` characters from the cursor with `with(replaced)`, leaving the
+    /// cursor on the last character put in. Returns false when there is no character under
+    /// the cursor.
+    fn replace_chars(&mut self, count: usize, with: impl FnOnce(&str) -> String) -> bool {
+        let (row, col) = self.textarea.cursor();
+        let replaced: String = self.textarea.lines()[row]
+            .chars()
+            .skip(col)
+            .take(count)
+            .collect();
+        if replaced.is_empty() {
+            return false;
+        }
+        self.textarea.delete_str(replaced.chars().count());
+        self.textarea.insert_str(with(&replaced));
+        self.textarea.move_cursor(CursorMove::Back);
+        true
+    }
+
+    /// Keep the cursor on a character in Normal mode: back off the end of a non-empty line.
+    fn clamp_to_line(&mut self) {
+        let (row, col) = self.textarea.cursor();
+        if col > 0 && col >= self.line_len(row) {
+            self.textarea.move_cursor(CursorMove::Back);
+        }
+    }
+
+    /// The goal column if the cursor is still where the last `j`/`k` left it, else the
+    /// cursor's column.
+    fn goal_col(&self) -> usize {
+        let cursor = self.textarea.cursor();
+        match self.goal {
+            Some((col, at)) if at == cursor => col,
+            _ => cursor.1,
+        }
+    }
+
+    fn jump_keeping_goal(&mut self, row: usize, col: usize, goal: usize) {
+        #[allow(clippy::cast_possible_truncation)]
+        self.textarea
+            .move_cursor(CursorMove::Jump(row as u16, col as u16));
+        self.goal = Some((goal, self.textarea.cursor()));
+    }
+
+    fn line_len(&self, row: usize) -> usize {
+        self.textarea
+            .lines()
+            .get(row)
+            .map_or(0, |l| l.chars().count())
+    }
+
+    /// `j`/`k`: `count` lines down or up (stopping at the first or last line), as close to
+    /// the goal column as the line allows.
+    pub fn move_line(&mut self, down: bool, count: usize) {
+        let (row, _) = self.textarea.cursor();
+        let last = self.textarea.lines().len().saturating_sub(1);
+        let target = if down {
+            row.saturating_add(count).min(last)
+        } else {
+            row.saturating_sub(count)
+        };
+        if target == row {
+            return;
+        }
+        let goal = self.goal_col();
+        self.jump_keeping_goal(target, goal.min(self.line_len(target)), goal);
+    }
+
+    /// Lines of the paragraph around line `row`: the lines between the blank ones before and
+    /// after it. A heading is a paragraph of its own, and so is a blank line.
+    pub fn paragraph_around(&self, row: usize) -> Range<usize> {
+        let lines = self.textarea.lines();
+        let stands_alone =
+            |line: &str| line.trim().is_empty() || capture::heading_level(line).is_some();
+        if lines.get(row).is_none_or(|l| stands_alone(l)) {
+            return row..row + 1;
+        }
+        let start = lines[..row]
+            .iter()
+            .rposition(|l| stands_alone(l))
+            .map_or(0, |i| i + 1);
+        let end = lines[row..]
+            .iter()
+            .position(|l| stands_alone(l))
+            .map_or(lines.len(), |i| row + i);
+        start..end
+    }
+
+    /// Where the display rows of line `row` start, wrapped at the pane's text width.
+    pub fn row_starts(&self, row: usize) -> Vec<usize> {
+        let line = self.textarea.lines().get(row).map_or("", String::as_str);
+        let width = usize::from(self.text_width.get());
+        wrap::row_starts(line, width, usize::from(self.textarea.tab_length()))
+    }
+
+    /// Display column of character `col` of line `row`, counted from `start`.
+    fn display_col(&self, row: usize, start: usize, col: usize) -> usize {
+        let line = &self.textarea.lines()[row];
+        let widths = wrap::char_widths(line, usize::from(self.textarea.tab_length()));
+        widths
+            .get(start..col.min(widths.len()))
+            .map_or(0, |w| w.iter().sum())
+    }
+
+    /// The character of display row `piece` of line `row` that covers display column `x`,
+    /// or the row's last character when it is shorter.
+    fn col_in_row(&self, row: usize, piece: usize, x: usize) -> usize {
+        let starts = self.row_starts(row);
+        let start = starts[piece];
+        let end = starts
+            .get(piece + 1)
+            .map_or(self.line_len(row), |&next| next - 1);
+        let tab_len = usize::from(self.textarea.tab_length());
+        let widths = wrap::char_widths(&self.textarea.lines()[row], tab_len);
+        let mut used = 0;
+        for (col, width) in widths.iter().enumerate().take(end).skip(start) {
+            used += width;
+            if used > x {
+                return col;
+            }
+        }
+        end
+    }
+
+    /// `gj`/`gk`: one display line down or up. A line wider than the pane is a display
+    /// line per row it wraps into, so the cursor steps through it before the next line.
+    pub fn move_display_line(&mut self, down: bool) {
+        let (row, col) = self.textarea.cursor();
+        let starts = self.row_starts(row);
+        let piece = wrap::row_of(&starts, col);
+        let x = match self.goal {
+            Some((x, at)) if at == (row, col) => x,
+            _ => self.display_col(row, starts[piece], col),
+        };
+        let (row, piece) = if down {
+            if piece + 1 < starts.len() {
+                (row, piece + 1)
+            } else if row + 1 < self.textarea.lines().len() {
+                (row + 1, 0)
+            } else {
+                return;
+            }
+        } else if piece > 0 {
+            (row, piece - 1)
+        } else if row > 0 {
+            (row - 1, self.row_starts(row - 1).len() - 1)
+        } else {
+            return;
+        };
+        let col = self.col_in_row(row, piece, x);
+        self.jump_keeping_goal(row, col, x);
+    }
+
+    /// `g0` / `g$`: the start or the end of the cursor's display row.
+    pub fn move_to_row_edge(&mut self, end: bool) {
+        let (row, col) = self.textarea.cursor();
+        let starts = self.row_starts(row);
+        let piece = wrap::row_of(&starts, col);
+        let col = if end {
+            starts
+                .get(piece + 1)
+                .map_or(self.line_len(row), |&next| next - 1)
+        } else {
+            starts[piece]
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        self.textarea
+            .move_cursor(CursorMove::Jump(row as u16, col as u16));
     }
 
     pub fn display_name(&self) -> String {
@@ -118,9 +483,15 @@ pub enum Mode {
 pub struct NoteEntry {
     pub path: PathBuf,
     pub display: String,
-    pub content: String,
+    /// First heading of the note, shown instead of `display` with `ui.display_titles`.
+    pub title: Option<String>,
+    pub content: NoteContent,
+    /// Modification time when the entry was listed or its content read.
+    pub modified: Option<SystemTime>,
     pub(crate) searchable: String,
     pub is_directory: bool,
+    /// Folders between the listed folder and this entry, for indenting the tree view.
+    pub depth: usize,
 }
 
 impl NoteEntry {
@@ -128,9 +499,12 @@ impl NoteEntry {
         Self {
             path,
             display,
-            content,
+            title: None,
+            content: NoteContent::Loaded(content),
+            modified: None,
             searchable,
             is_directory: false,
+            depth: 0,
         }
     }
 
@@ -139,11 +513,77 @@ impl NoteEntry {
         Self {
             path,
             display,
-            content: String::new(),
+            title: None,
+            content: NoteContent::Skipped,
+            modified: None,
             searchable,
             is_directory: true,
+            depth: 0,
+        }
+    }
+
+    /// A file whose content is read later, on first use. Until then it matches by name only.
+    pub(crate) fn file(
+        path: PathBuf,
+        display: String,
+        content: NoteContent,
+        modified: Option<SystemTime>,
+    ) -> Self {
+        let mut entry = Self {
+            path,
+            searchable: String::new(),
+            display,
+            title: None,
+            content: NoteContent::Pending,
+            modified,
+            is_directory: false,
+            depth: 0,
+        };
+        entry.set_content(content, modified);
+        entry
+    }
+
+    /// What the list shows for the entry: its title when one is set, else its name.
+    pub fn label(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.display)
+    }
+
+    /// What a search result shows: a title is followed by the name, as either may match.
+    pub fn search_label(&self) -> String {
+        match &self.title {
+            Some(title) => format!("{title} ({})", self.display),
+            None => self.display.clone(),
         }
     }
+
+    /// Name and title, which the search text starts with.
+    fn names(&self) -> String {
+        match &self.title {
+            Some(title) => format!("{}\n{title}", self.display),
+            None => self.display.clone(),
+        }
+    }
+
+    /// Show `title` instead of the name; searching then matches both.
+    pub fn set_title(&mut self, title: Option<String>) {
+        let old = self.names();
+        self.title = title;
+        let rest = self
+            .searchable
+            .strip_prefix(&old)
+            .unwrap_or_default()
+            .to_string();
+        self.searchable = self.names() + &rest;
+    }
+
+    fn set_content(&mut self, content: NoteContent, modified: Option<SystemTime>) {
+        self.searchable = match &content {
+            NoteContent::Loaded(text) => format!("{}\n{text}", self.names()),
+            _ => self.names(),
+        };
+        self.content = content;
+        self.modified = modified;
+    }
 }
 
 impl AsRef<str> for NoteEntry {
@@ -152,49 +592,177 @@ impl AsRef<str> for NoteEntry {
     }
 }
 
-/// Unchecked task from a markdown file (`- [ ] ...`).
-#[derive(Clone, Debug)]
-pub struct TaskEntry {
-    pub path: PathBuf,
-    pub line_number: usize,
-    pub content: String,
+/// Declares `CommandAction` from one list of `Variant => "Label"` entries, each optionally
+/// followed by the config key of its shortcut, so `label()`, `key_name()` and `all()` can
+/// never miss a variant.
+macro_rules! command_actions {
+    ($($variant:ident => $label:literal $(, $key:literal)?;)*) => {
+        /// Command palette action.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum CommandAction {
+            $($variant,)*
+        }
+
+        impl CommandAction {
+            pub fn label(&self) -> &'static str {
+                match self {
+                    $(CommandAction::$variant => $label,)*
+                }
+            }
+
+            /// Config key of the shortcut that triggers the same action, if it has one.
+            pub fn key_name(&self) -> Option<&'static str> {
+                match self {
+                    $(CommandAction::$variant => command_actions!(@key $($key)?),)*
+                }
+            }
+
+            /// Every action, in palette order.
+            pub fn all() -> &'static [CommandAction] {
+                &[$(CommandAction::$variant,)*]
+            }
+        }
+    };
+    (@key $key:literal) => {
+        Some($key)
+    };
+    (@key) => {
+        None
+    };
 }
 
-/// Command palette action.
+command_actions! {
+    NewNote => "New Note", "list_create_note";
+    NewDirectory => "New Directory", "list_create_dir";
+    RenameFile => "Rename File", "list_rename";
+    DeleteFile => "Delete", "list_delete";
+    MoveFile => "Move File", "list_move";
+    AddTag => "Add Tag", "list_tag";
+    DuplicateFile => "Duplicate File", "list_duplicate";
+    ArchiveNote => "Archive Note", "list_archive";
+    UnarchiveNote => "Unarchive Note";
+    ToggleMark => "Mark File", "list_mark";
+    ClearMarks => "Clear Marks";
+    GoToParent => "Go to Parent Directory", "list_parent";
+    GoToVaultRoot => "Go to Vault Root", "list_root";
+    SearchNotes => "Search Notes", "search";
+    RecentFiles => "Recent Files";
+    DailyNote => "Open Daily Note", "daily_note";
+    RandomNote => "Open Random Note", "random_note";
+    ShowMessages => "Show Messages";
+    QuickCapture => "Quick Capture", "quick_capture";
+    AddTask => "Add Task", "add_task";
+    TaskBoard => "Task Board", "task_board";
+    TagExplorer => "Tag Explorer", "list_tag_explorer";
+    InsertDate => "Insert Date";
+    FollowLink => "Follow Link", "editor_follow_link";
+    SpellSuggest => "Spelling Suggestions", "editor_spell_suggest";
+    AddWordToDictionary => "Add Word to Dictionary", "editor_spell_add";
+    OpenInExternalEditor => "Open in External Editor", "editor_external";
+    ToggleTask => "Toggle Task", "editor_toggle_task";
+    ExportPdf => "Export to PDF", "editor_pdf";
+    ExportHtml => "Export to HTML";
+    ExportGraph => "Export Link Graph";
+    InsertImage => "Insert Image";
+    CopyPath => "Copy Path";
+    CopyWikiLink => "Copy Wiki Link";
+    CopyNoteContent => "Copy Note Content";
+    CopyNoteId => "Copy Note ID";
+    GitPush => "Git Push", "git_push";
+    GitInit => "Git Init";
+    GitDiffFile => "Git Diff File";
+    DiffBuffer => "Diff Unsaved Changes";
+    ReplaceInVault => "Replace in Vault";
+    NextTab => "Next Tab", "editor_next_tab";
+    PrevTab => "Previous Tab", "editor_prev_tab";
+    CloseTab => "Close Tab", "editor_close_tab";
+    CloseOtherTabs => "Close Other Tabs", "editor_close_others";
+    CloseAllTabs => "Close All Tabs", "editor_close_all";
+    ReopenClosedTab => "Reopen Closed Tab", "editor_reopen_tab";
+    ToggleSplitView => "Toggle Split View", "editor_toggle_split";
+    OpenInSplit => "Open in Split";
+    SwitchSplitFocus => "Switch Split Focus", "editor_split_focus";
+    ToggleZenMode => "Toggle Zen Mode", "zen_mode";
+    TogglePreview => "Toggle Preview";
+    ToggleTreeView => "Toggle Tree View", "list_tree";
+    ToggleBacklinks => "Toggle Backlinks Pane";
+    ToggleSoftWrap => "Toggle Soft Wrap";
+    ToggleFocusMode => "Toggle Focus Mode";
+    FocusBacklinks => "Focus Backlinks", "editor_backlinks";
+    GrowEditorPane => "Grow Editor Pane", "pane_grow";
+    ShrinkEditorPane => "Shrink Editor Pane", "pane_shrink";
+    CycleTheme => "Cycle Theme";
+    ToggleLightDark => "Toggle Light/Dark";
+    OpenConfig => "Open Config", "list_edit_config";
+    OpenTheme => "Open Theme";
+    ReloadConfig => "Reload Config";
+    CheckKeybindings => "Check Keybindings";
+    Suspend => "Suspend to Shell", "suspend";
+    Quit => "Quit", "quit";
+}
+
+/// What the clipboard commands copy from the current note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyWhat {
+    Path,
+    WikiLink,
+    Content,
+    Id,
+}
+
+/// A telescope result: a file, or a line (buffer ID and row) of an open buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TelescopeEntry {
+    File(PathBuf),
+    Line(u64, usize),
+}
+
+/// One command palette row: a built-in action or a `[[commands]]` entry (by index).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum CommandAction {
-    RenameFile,
-    DeleteFile,
-    InsertDate,
-    ToggleZenMode,
-    ToggleSplitView,
-    GitPush,
-    ExportPdf,
+pub enum PaletteItem {
+    Action(CommandAction),
+    User(usize),
+}
+
+/// A config or theme file that failed to load; oxid runs with defaults (or, after a
+/// reload, the previous settings) for that file until it is fixed.
+#[derive(Debug, Clone)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub message: String,
+    /// One line for the footer: the file, the line at fault and what is wrong there.
+    pub summary: String,
 }
 
-impl CommandAction {
-    pub fn label(&self) -> &'static str {
-        match self {
-            CommandAction::RenameFile => "Rename File",
-            CommandAction::DeleteFile => "Delete",
-            CommandAction::InsertDate => "Insert Date",
-            CommandAction::ToggleZenMode => "Toggle Zen Mode",
-            CommandAction::ToggleSplitView => "Toggle Split View",
-            CommandAction::GitPush => "Git Push",
-            CommandAction::ExportPdf => "Export to PDF",
-        }
-    }
-
-    pub fn all() -> &'static [CommandAction] {
-        &[
-            CommandAction::RenameFile,
-            CommandAction::DeleteFile,
-            CommandAction::InsertDate,
-            CommandAction::ToggleZenMode,
-            CommandAction::ToggleSplitView,
-            CommandAction::GitPush,
-            CommandAction::ExportPdf,
-        ]
+impl ConfigError {
+    fn new(path: PathBuf, error: &anyhow::Error) -> Self {
+        let message = format!("{error:#}");
+        let name = path.file_name().map_or(path.display().to_string(), |n| {
+            n.to_string_lossy().into_owned()
+        });
+        let summary = match error
+            .chain()
+            .find_map(|e| e.downcast_ref::<toml::de::Error>())
+        {
+            Some(toml) => {
+                let reason: Vec<&str> = toml.message().lines().map(str::trim).collect();
+                let reason = reason.join(", ");
+                let line = toml.span().and_then(|span| {
+                    let text = fs::read_to_string(&path).ok()?;
+                    Some(text.get(..span.start)?.matches('\n').count() + 1)
+                });
+                match line {
+                    Some(line) => format!("{name} line {line}: {reason}"),
+                    None => format!("{name}: {reason}"),
+                }
+            }
+            None => message.lines().next().unwrap_or_default().to_string(),
+        };
+        Self {
+            path,
+            message,
+            summary,
+        }
     }
 }
 
@@ -204,55 +772,156 @@ pub struct App {
     pub resolved_keys: ResolvedKeys,
     pub theme: ResolvedTheme,
     pub notes_dir: PathBuf,
+    /// `ignore` from config.toml plus `.oxidignore`, applied to every vault walk.
+    pub ignore: Ignore,
     /// Directory currently being browsed in the file explorer.
     pub current_dir: PathBuf,
     pub all_notes: Vec<NoteEntry>,
     pub filtered_notes: Vec<NoteEntry>,
     pub selected: usize,
+    /// First visible row of the notes list, kept between frames so it only scrolls when the
+    /// selection leaves the view.
+    pub notes_scroll: Cell<usize>,
+    /// Rows the notes list had on the last draw.
+    pub notes_rows: Cell<u16>,
     pub mode: Mode,
-    pub search_query: String,
-    pub create_filename: String,
-    pub message: Option<String>,
+    pub search_query: TextInput,
+    /// Why the `re:` list query does not compile; the list keeps the last results meanwhile.
+    pub search_error: Option<String>,
+    /// Compiled pattern of a `re:` list query, for highlighting.
+    pub search_regex: Option<Regex>,
+    /// How the list search and telescope treat case.
+    pub search_case: CaseMode,
+    /// Entry selected when the list search started, selected again when it is cancelled.
+    search_origin: Option<PathBuf>,
+    /// Query of the last list search, for `keys.list_filter_repeat`.
+    pub last_search: String,
+    pub create_filename: TextInput,
+    /// Recent footer messages, oldest first; the newest is shown until `message_until`.
+    pub messages: VecDeque<(Instant, String)>,
+    message_until: Option<Instant>,
+    /// Set after a save failed on quit: the next quit goes ahead anyway.
+    quit_discards_changes: bool,
+    pub messages_scroll: usize,
+    /// Active theme preset; starts as `theme.preset` and changes with Cycle Theme.
+    pub theme_preset: String,
+    /// Light or dark variant of the preset; starts from `theme.mode` and changes with Toggle
+    /// Light/Dark.
+    pub theme_mode: ThemeMode,
+    /// Config and theme files that failed to parse, shown in the footer and (at startup) a popup.
+    pub config_errors: Vec<ConfigError>,
     matcher: Matcher,
-    pub match_indices: Vec<Vec<u32>>,
+    /// Matched characters and first matching line of the filtered notes shown while searching.
+    pub list_highlights: RowHighlights,
+    /// Query `filtered_notes` was filtered with, while its notes are unchanged since.
+    filtered_for: Option<String>,
+    /// The search changed but has not been applied yet (`ui.debounce_search`).
+    search_pending: bool,
 
     // Focus and editor state
     pub focus: Focus,
     pub editor_mode: EditorMode,
+    /// `r` was pressed, with this count: the next key is the replacement character.
+    pub replace_pending: Option<usize>,
+    /// Count typed before a Normal-mode key (`5j`), shown in the footer until a key uses it.
+    pub normal_count: Option<usize>,
+    /// Expanded snippet whose tab stops Tab and Shift-Tab visit in Insert mode.
+    snippet: Option<SnippetSession>,
     /// Open buffers (tabs).
     pub buffers: Vec<EditorBuffer>,
     /// Active tab index.
     pub active_tab: usize,
-    /// Split view: right pane shows this tab.
-    pub split_right_tab: Option<usize>,
-    /// Which pane receives input when split.
+    /// Split view: right pane shows the buffer with this id.
+    pub split_right_id: Option<u64>,
+    /// Which pane receives input when split (left, or top when stacked).
     pub split_focus_left: bool,
+    /// Selection in the split buffer picker (index into `buffer_picker_candidates`).
+    pub buffer_picker_selected: usize,
+    /// Recently closed tabs (path and cursor), most recent last.
+    pub closed_tabs: Vec<(PathBuf, (usize, usize))>,
+    /// Positions left by following links, backlinks, tasks and search results.
+    pub jumps: JumpList,
     pub editor_layout: EditorLayout,
 
     // Zen mode
     pub zen_mode: bool,
+    /// Focus mode: the editor dims all but the paragraph with the cursor.
+    pub focus_mode: bool,
+
+    // Column widths (percent) and preview visibility, adjustable at runtime
+    pub list_width_percent: u16,
+    pub preview_width_percent: u16,
+    pub show_preview: bool,
+    /// File list shown as a tree: folders in `expanded` list their entries below them.
+    pub tree_view: bool,
+    pub expanded: HashSet<PathBuf>,
+    /// Rendered preview, reused across frames while the content is unchanged.
+    pub preview_cache: RefCell<RenderCache>,
 
     // Telescope (/)
     pub telescope_notes: Vec<NoteEntry>,
     pub telescope_filtered: Vec<NoteEntry>,
-    pub telescope_query: String,
+    pub telescope_query: TextInput,
     pub telescope_selected: usize,
-    pub telescope_match_indices: Vec<Vec<u32>>,
+    pub telescope_highlights: RowHighlights,
+    /// Rows the telescope results had on the last draw.
+    pub telescope_rows: Cell<u16>,
+    /// Query `telescope_filtered` was filtered with.
+    telescope_filtered_for: String,
+    telescope_pending: bool,
     telescope_matcher: Matcher,
+    pub telescope_mode: TelescopeMode,
+    /// Results of the open-buffer search, used instead of `telescope_filtered` in
+    /// `TelescopeMode::Buffers`.
+    pub telescope_buffer_matches: Vec<BufferMatch>,
+    /// Why the `re:` telescope query does not compile.
+    pub telescope_error: Option<String>,
+    telescope_regex: Option<Regex>,
+
+    // Recently opened files (MRU)
+    pub recent: RecentFiles,
+    pub recent_selected: usize,
 
     // Command palette
-    pub command_palette_query: String,
-    pub command_palette_filtered: Vec<CommandAction>,
+    pub command_palette_query: TextInput,
+    pub command_palette_filtered: Vec<PaletteItem>,
+    pub command_palette_match_indices: Vec<Vec<u32>>,
     pub command_palette_selected: usize,
 
     // Rename popup
-    pub rename_input: String,
+    pub rename_input: TextInput,
 
     // Create directory popup (Shift+n)
-    pub directory_input: String,
+    pub directory_input: TextInput,
+
+    // Multi-select marks in the file list (cleared on directory navigation)
+    pub marked: HashSet<PathBuf>,
+
+    // Delete confirmation (pending entries)
+    pub delete_pending: Vec<NoteEntry>,
+
+    // Move picker (entries being moved and candidate target directories)
+    pub move_pending: Vec<NoteEntry>,
 
-    // Delete confirmation (pending entry)
-    pub delete_pending: Option<NoteEntry>,
+    // Add Tag popup (#): the tag and the notes it is added to
+    pub tag_input: TextInput,
+    pub tag_pending: Vec<PathBuf>,
+    pub move_targets: Vec<PathBuf>,
+    pub move_target_selected: usize,
+
+    // Vault-wide replace: prompt inputs, scanned matches, and review selection
+    // (index into `replace_rows`)
+    pub replace_pattern: TextInput,
+    pub replace_with: TextInput,
+    pub replace_editing_with: bool,
+    pub replace_files: Vec<ReplaceFile>,
+    pub replace_selected: usize,
+    pub replace_dry_run: bool,
+    /// Scan started from the prompt; the review opens when it is done.
+    replace_scan: Option<ReplaceScan>,
+
+    // Duplicate confirmation for directories (pending entry)
+    pub duplicate_pending: Option<NoteEntry>,
 
     // Template picker for new files
     pub template_picker_active: bool,
@@ -261,17 +930,78 @@ pub struct App {
     // Spellchecker (lazy-loaded)
     pub spellchecker: Option<Spellchecker>,
 
-    // g-pending for gt/gT tab switch
-    pub g_pending: bool,
+    // Spelling suggestions (z=) for the word at `spell_target` (row, start col, end col)
+    pub spell_suggestions: Vec<String>,
+    pub spell_suggestion_selected: usize,
+    spell_target: Option<(usize, usize, usize)>,
+
+    // Key sequence bindings and the keys typed so far toward one (with the time of the last)
+    pub keymap: Keymap,
+    pub pending_keys: Vec<crossterm::event::KeyEvent>,
+    pub pending_since: Option<Instant>,
+
+    // Insert Image path prompt
+    pub image_path_input: TextInput,
+    pub git_remote_input: TextInput,
+    /// Diff view: what is compared, and the diff's lines.
+    pub diff_title: String,
+    pub diff_lines: Vec<(DiffLineKind, String)>,
+    pub diff_scroll: usize,
+
+    // HTML export: scope choice (index into `ExportScope::ALL`), output directory, result
+    pub export_scope_selected: usize,
+    pub export_dir: TextInput,
+    pub export_summary: Option<ExportSummary>,
+    pub export_summary_scroll: usize,
+    // Link graph export: options (row selected in the popup), output file, running export
+    pub graph_options: GraphOptions,
+    pub graph_option_selected: usize,
+    pub graph_path: TextInput,
+    graph_export: Option<GraphExport>,
+    // Vault snapshots (`[backup]`): the one being taken and when the next is due
+    backup: Option<BackupJob>,
+    backup_due: Instant,
+    /// Held while notes are written, so a snapshot never copies one half-saved.
+    save_lock: Arc<Mutex<()>>,
+    /// Reads the content of listed notes for search in the background.
+    content_loader: Option<ContentLoader>,
+    // Alias picker: the link followed and the notes claiming it as an alias
+    pub alias_pending: String,
+    pub alias_candidates: Vec<PathBuf>,
+    pub alias_selected: usize,
+    // Quick capture: the line being typed and where focus returns afterwards
+    pub capture_input: TextInput,
+    capture_return_focus: Focus,
+    // Add Task: the task being typed and where focus returns afterwards
+    pub task_input: TextInput,
+    task_return_focus: Focus,
+    // Open Random Note: tag filter, current-directory scope, and the last note picked (not
+    // picked again right away)
+    pub random_tag: TextInput,
+    pub random_in_current_dir: bool,
+    random_last: Option<PathBuf>,
+    rng: Rng,
+
+    // File to hand to the external editor; main suspends the TUI for it
+    pub external_edit: Option<PathBuf>,
+    // Suspend key pressed; main stops oxid until the shell resumes it
+    pub suspend_requested: bool,
+
+    // User command from `[[commands]]`: awaiting confirmation, and the one running or finished
+    pub user_command_pending: Option<usize>,
+    pub user_command: Option<RunningCommand>,
+
+    // Keybinding check report (conflicts and invalid key strings)
+    pub keybinding_report: Vec<String>,
+    pub keybinding_report_scroll: usize,
 
     // Backlinks (cached, invalidated on save)
     pub backlinks: Vec<PathBuf>,
     pub backlinks_selected: usize,
-    backlinks_cache_valid: bool,
-    cached_backlink_target: Option<PathBuf>,
+    /// Wiki links across the vault, answering backlink queries.
+    pub(crate) links: LinkGraph,
 
     // Tag Explorer
-    pub tag_explorer_active: bool,
     pub all_tags: Vec<String>,
     pub tag_selected: usize,
     pub tag_files: Vec<PathBuf>,
@@ -281,12 +1011,28 @@ pub struct App {
     // Auto-save
     pub last_keystroke_time: Option<Instant>,
     pub editor_dirty: bool,
+    /// Auto-save wrote a config file that is applied once focus leaves it.
+    config_reload_pending: bool,
+    /// Modification times of config.toml and theme.toml as last loaded, and when they were
+    /// last compared with the files.
+    config_stamps: [Option<SystemTime>; 2],
+    config_checked: Option<Instant>,
     pub save_indicator_until: Option<Instant>,
 
+    /// Something on screen may have changed since the last draw; idle ticks don't redraw.
+    pub needs_redraw: bool,
+    /// Git status of the vault, `None` outside a repository.
+    pub git: Option<GitStatus>,
+    /// When `git` was read; `None` reads it again on the next tick.
+    git_checked: Option<Instant>,
+
     // Global Task Board
-    pub task_view_active: bool,
     pub tasks: Vec<TaskEntry>,
     pub task_selected: usize,
+    /// Task board column that has the focus.
+    pub task_column: usize,
+    /// Row last selected in each board column, restored when moving back to it.
+    task_column_rows: Vec<usize>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -298,17 +1044,16 @@ pub enum TagExplorerView {
 impl App {
     /// Index of the buffer that receives input.
     pub fn focused_buffer_index(&self) -> usize {
-        if self.editor_layout == EditorLayout::SplitVertical && self.split_right_tab.is_some() {
-            if self.split_focus_left {
-                self.active_tab
-            } else {
-                self.split_right_tab
-                    .unwrap_or(0)
-                    .min(self.buffers.len().saturating_sub(1))
-            }
-        } else {
-            self.active_tab
+        if !self.editor_layout.is_split() || self.split_focus_left {
+            return self.active_tab;
         }
+        self.split_right_tab().unwrap_or(self.active_tab)
+    }
+
+    /// Index of the buffer shown in the split's right pane.
+    pub fn split_right_tab(&self) -> Option<usize> {
+        let id = self.split_right_id?;
+        self.buffers.iter().position(|b| b.id == id)
     }
 
     pub fn focused_buffer_mut(&mut self) -> Option<&mut EditorBuffer> {
@@ -325,6 +1070,68 @@ impl App {
         self.focused_buffer()?.path.clone()
     }
 
+    /// `ui.window_title` for the focused buffer, `None` when it is empty.
+    pub fn window_title(&self) -> Option<String> {
+        let template = &self.config.ui.window_title;
+        if template.trim().is_empty() {
+            return None;
+        }
+        let vault = self
+            .notes_dir
+            .file_name()
+            .map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+        let buf = self.focused_buffer();
+        let file = buf
+            .filter(|b| b.path.is_some())
+            .map_or_else(|| vault.clone(), EditorBuffer::display_name);
+        let dirty = if buf.is_some_and(|b| b.dirty) {
+            "*"
+        } else {
+            ""
+        };
+        Some(
+            template
+                .replace("{file}", &file)
+                .replace("{vault}", &vault)
+                .replace("{dirty}", dirty),
+        )
+    }
+
+    /// The focused note and the cursor's line, for the jump list.
+    fn current_position(&self) -> Option<Jump> {
+        let buf = self.focused_buffer()?;
+        Some((buf.path.clone()?, buf.textarea.cursor().0))
+    }
+
+    /// Remember where the cursor is before navigating to another file.
+    pub fn record_jump(&mut self) {
+        if let Some((path, line)) = self.current_position() {
+            self.jumps.record(&path, line);
+        }
+    }
+
+    /// `editor_jump_back` / `editor_jump_forward`: go to the previous or next position in
+    /// the jump list, reopening its note if it was closed.
+    pub fn walk_jump_list(&mut self, back: bool) {
+        let target = if back {
+            let current = self.current_position();
+            self.jumps.back(current)
+        } else {
+            self.jumps.forward()
+        };
+        let Some((path, line)) = target else {
+            let end = if back { "oldest" } else { "newest" };
+            self.push_message(format!("Already at the {end} jump"));
+            return;
+        };
+        if let Err(e) = self.save_editor() {
+            self.report_err("Save", e);
+        }
+        if let Err(e) = self.load_file_into_editor_at_line(path, Some(line)) {
+            self.report_err("Open", e);
+        }
+    }
+
     pub fn has_open_buffers(&self) -> bool {
         !self.buffers.is_empty()
     }
@@ -337,13 +1144,21 @@ impl App {
             "search" => &self.config.keys.search,
             "command_palette" => &self.config.keys.command_palette,
             "daily_note" => &self.config.keys.daily_note,
+            "quick_capture" => &self.config.keys.quick_capture,
+            "add_task" => &self.config.keys.add_task,
             "task_board" => &self.config.keys.task_board,
+            "pane_grow" => &self.config.keys.pane_grow,
+            "pane_shrink" => &self.config.keys.pane_shrink,
+            "git_push" => &self.config.keys.git_push,
+            "random_note" => &self.config.keys.random_note,
+            "suspend" => &self.config.keys.suspend,
             "escape" => &self.config.keys.escape,
             "enter" => &self.config.keys.enter,
             "backspace" => &self.config.keys.backspace,
             "move_up" => &self.config.keys.move_up,
             "move_down" => &self.config.keys.move_down,
             "move_left" => &self.config.keys.move_left,
+            "move_right" => &self.config.keys.move_right,
             "delete" => &self.config.keys.delete,
             "list_create_note" => &self.config.keys.list_create_note,
             "list_create_dir" => &self.config.keys.list_create_dir,
@@ -351,37 +1166,136 @@ impl App {
             "list_rename" => &self.config.keys.list_rename,
             "list_edit_config" => &self.config.keys.list_edit_config,
             "list_delete" => &self.config.keys.list_delete,
+            "list_move" => &self.config.keys.list_move,
+            "list_duplicate" => &self.config.keys.list_duplicate,
+            "list_archive" => &self.config.keys.list_archive,
+            "list_filter" => &self.config.keys.list_filter,
+            "list_filter_repeat" => &self.config.keys.list_filter_repeat,
+            "list_tree" => &self.config.keys.list_tree,
+            "list_mark" => &self.config.keys.list_mark,
+            "list_tag" => &self.config.keys.list_tag,
             "list_parent" => &self.config.keys.list_parent,
             "list_parent_alt" => &self.config.keys.list_parent_alt,
+            "list_root" => &self.config.keys.list_root,
+            "list_jump_level" => &self.config.keys.list_jump_level,
             "editor_back" => &self.config.keys.editor_back,
             "editor_pdf" => &self.config.keys.editor_pdf,
             "editor_backlinks" => &self.config.keys.editor_backlinks,
             "editor_wiki_link" => &self.config.keys.editor_wiki_link,
+            "editor_jump_back" => &self.config.keys.editor_jump_back,
+            "editor_jump_forward" => &self.config.keys.editor_jump_forward,
             "editor_insert" => &self.config.keys.editor_insert,
             "editor_append" => &self.config.keys.editor_append,
+            "editor_open_below" => &self.config.keys.editor_open_below,
+            "editor_open_above" => &self.config.keys.editor_open_above,
             "editor_split_focus" => &self.config.keys.editor_split_focus,
+            "editor_paste" => &self.config.keys.editor_paste,
+            "telescope_open_split" => &self.config.keys.telescope_open_split,
+            "telescope_mode" => &self.config.keys.telescope_mode,
+            "search_case" => &self.config.keys.search_case,
+            "task_toggle" => &self.config.keys.task_toggle,
+            "task_advance" => &self.config.keys.task_advance,
+            "replace_dry_run" => &self.config.keys.replace_dry_run,
+            "editor_next_tab" => &self.config.keys.editor_next_tab,
+            "editor_prev_tab" => &self.config.keys.editor_prev_tab,
+            "editor_toggle_split" => &self.config.keys.editor_toggle_split,
+            "editor_close_tab" => &self.config.keys.editor_close_tab,
+            "editor_close_others" => &self.config.keys.editor_close_others,
+            "editor_close_all" => &self.config.keys.editor_close_all,
+            "editor_reopen_tab" => &self.config.keys.editor_reopen_tab,
+            "editor_follow_link" => &self.config.keys.editor_follow_link,
+            "editor_spell_suggest" => &self.config.keys.editor_spell_suggest,
+            "editor_spell_add" => &self.config.keys.editor_spell_add,
+            "editor_external" => &self.config.keys.editor_external,
+            "editor_toggle_task" => &self.config.keys.editor_toggle_task,
+            "editor_display_down" => &self.config.keys.editor_display_down,
+            "editor_display_up" => &self.config.keys.editor_display_up,
+            "editor_row_start" => &self.config.keys.editor_row_start,
+            "editor_row_end" => &self.config.keys.editor_row_end,
             "move_up_alt" => &self.config.keys.move_up_alt,
             "move_down_alt" => &self.config.keys.move_down_alt,
             "move_left_alt" => &self.config.keys.move_left_alt,
+            "move_right_alt" => &self.config.keys.move_right_alt,
             _ => return String::new(),
         };
         key_display_string(s)
     }
 
+    /// Which sequence bindings apply in the current focus and mode.
+    pub fn key_context(&self) -> KeyContext {
+        match self.focus {
+            Focus::Editor if self.editor_mode == EditorMode::Normal => KeyContext::EditorNormal,
+            Focus::List if self.template_picker_active => KeyContext::Other,
+            Focus::List if self.mode == Mode::Normal => KeyContext::Navigation,
+            // The editor in Insert mode, the list's filter and new note name, and prompts.
+            Focus::Editor
+            | Focus::List
+            | Focus::Search
+            | Focus::CommandPalette
+            | Focus::Rename
+            | Focus::CreatingDirectory
+            | Focus::ReplacePrompt
+            | Focus::ImagePathPrompt
+            | Focus::GitRemotePrompt
+            | Focus::ExportDir
+            | Focus::GraphPath
+            | Focus::RandomNote
+            | Focus::TagPrompt
+            | Focus::QuickCapture
+            | Focus::AddTask => KeyContext::TextInput,
+            _ => KeyContext::Other,
+        }
+    }
+
+    /// Continuations of the pending key prefix for the which-key popup, once the prefix has
+    /// been pending for `WHICH_KEY_DELAY`; empty otherwise.
+    pub fn which_key_hints(&self) -> Vec<(String, &'static str)> {
+        if self
+            .pending_since
+            .is_none_or(|t| t.elapsed() < WHICH_KEY_DELAY)
+        {
+            return Vec::new();
+        }
+        self.keymap
+            .continuations(&self.pending_keys, self.key_context())
+    }
+
     pub fn new() -> Result<Self> {
-        let config = load_config()?;
+        // A broken config.toml or theme.toml must not keep oxid from starting, since the
+        // easiest place to fix it is oxid's own editor.
         let config_dir = crate::config::ensure_config_dir()?;
-        let theme_raw = load_theme(&config_dir)?;
-        let theme = ResolvedTheme::resolve(&theme_raw, Some(&config.theme))?;
+        let mut config_errors = Vec::new();
+        let config = load_config().unwrap_or_else(|e| {
+            config_errors.push(ConfigError::new(config_dir.join(CONFIG_FILE_NAME), &e));
+            Config::default()
+        });
+        let theme_mode = ThemeMode::from_config(&config.theme);
+        let theme_raw =
+            load_theme(&config_dir, &config.theme.preset, theme_mode).unwrap_or_else(|e| {
+                config_errors.push(ConfigError::new(config_dir.join(THEME_FILE_NAME), &e));
+                Theme::default()
+            });
+        let theme =
+            match ResolvedTheme::resolve(&theme_raw, Some(&config.theme), config.ui.color_mode()) {
+                Ok(theme) => theme,
+                Err(e) => {
+                    config_errors.push(ConfigError::new(config_dir.join(THEME_FILE_NAME), &e));
+                    ResolvedTheme::resolve(&Theme::default(), None, config.ui.color_mode())?
+                }
+            };
+        let theme_preset = config.theme.preset.clone();
         let notes_dir = expand_path(&config.notes_directory);
 
         fs::create_dir_all(&notes_dir)
             .map_err(|e| anyhow::anyhow!("Failed to create notes directory: {e}"))?;
+        let ignore = vault_ignore(&notes_dir, &config);
+        let walk = vault::walk(&notes_dir, &ignore);
+        let links = LinkGraph::open(&notes_dir, &walk, &config.zettel_id_format);
 
         let current_dir = notes_dir.clone();
-        let all_notes = load_entries(&current_dir)?;
+        let mut all_notes = load_entries(&current_dir, config.ui.show_all_files)?;
+        set_titles(&mut all_notes, &links, config.ui.display_titles);
         let filtered_notes = all_notes.clone();
-        let match_indices = vec![Vec::new(); filtered_notes.len()];
         let matcher = Matcher::new(MatcherConfig::DEFAULT.match_paths());
 
         let mut buf = EditorBuffer::new(None, vec![String::new()]);
@@ -396,50 +1310,146 @@ impl App {
             };
 
         let resolved_keys = ResolvedKeys::from_config(&config.keys);
+        let search_case = CaseMode::new(config.search.smart_case);
+        let keymap = Keymap::from_config(&config.keys);
+        let (list_width_percent, preview_width_percent) = config.ui.pane_percents();
+        let show_preview = config.ui.show_preview;
+        let tree_view = config.ui.tree_view;
+        let backup_due = Instant::now() + config.backup.interval();
         let mut app = Self {
             config,
             resolved_keys,
             theme,
             notes_dir,
+            ignore,
             current_dir,
             all_notes,
             filtered_notes,
             selected: 0,
+            notes_scroll: Cell::new(0),
+            notes_rows: Cell::new(0),
             mode: Mode::Normal,
-            search_query: String::new(),
-            create_filename: String::new(),
-            message: None,
+            search_query: TextInput::default(),
+            search_error: None,
+            search_regex: None,
+            search_case,
+            search_origin: None,
+            last_search: String::new(),
+            create_filename: TextInput::default(),
+            messages: VecDeque::new(),
+            message_until: None,
+            quit_discards_changes: false,
+            messages_scroll: 0,
+            theme_preset,
+            theme_mode,
+            config_errors,
             matcher,
-            match_indices,
+            list_highlights: RowHighlights::default(),
+            filtered_for: None,
+            search_pending: false,
             focus: Focus::List,
             editor_mode: EditorMode::Normal,
+            replace_pending: None,
+            normal_count: None,
+            snippet: None,
             buffers,
             active_tab: 0,
-            split_right_tab: None,
+            split_right_id: None,
             split_focus_left: true,
+            buffer_picker_selected: 0,
+            closed_tabs: Vec::new(),
+            jumps: JumpList::default(),
             editor_layout: EditorLayout::Single,
             zen_mode: false,
+            focus_mode: false,
+            list_width_percent,
+            preview_width_percent,
+            show_preview,
+            tree_view,
+            expanded: HashSet::new(),
             telescope_notes: Vec::new(),
             telescope_filtered: Vec::new(),
-            telescope_query: String::new(),
+            telescope_query: TextInput::default(),
             telescope_selected: 0,
-            telescope_match_indices: Vec::new(),
+            telescope_highlights: RowHighlights::default(),
+            telescope_rows: Cell::new(0),
+            telescope_filtered_for: String::new(),
+            telescope_pending: false,
             telescope_matcher: Matcher::new(MatcherConfig::DEFAULT.match_paths()),
-            command_palette_query: String::new(),
-            command_palette_filtered: CommandAction::all().to_vec(),
+            telescope_mode: TelescopeMode::Files,
+            telescope_buffer_matches: Vec::new(),
+            telescope_error: None,
+            telescope_regex: None,
+            recent: RecentFiles::load(),
+            recent_selected: 0,
+            preview_cache: RefCell::new(RenderCache::default()),
+            command_palette_query: TextInput::default(),
+            command_palette_filtered: Vec::new(),
+            command_palette_match_indices: Vec::new(),
             command_palette_selected: 0,
-            rename_input: String::new(),
-            directory_input: String::new(),
-            delete_pending: None,
+            rename_input: TextInput::default(),
+            directory_input: TextInput::default(),
+            marked: HashSet::new(),
+            delete_pending: Vec::new(),
+            move_pending: Vec::new(),
+            tag_input: TextInput::default(),
+            tag_pending: Vec::new(),
+            move_targets: Vec::new(),
+            move_target_selected: 0,
+            duplicate_pending: None,
             template_picker_active: false,
             template_picker_selected: 0,
             spellchecker,
-            g_pending: false,
+            replace_pattern: TextInput::default(),
+            replace_with: TextInput::default(),
+            replace_editing_with: false,
+            replace_files: Vec::new(),
+            replace_selected: 0,
+            replace_dry_run: false,
+            replace_scan: None,
+            spell_suggestions: Vec::new(),
+            spell_suggestion_selected: 0,
+            spell_target: None,
+            keymap,
+            keybinding_report: Vec::new(),
+            keybinding_report_scroll: 0,
+            image_path_input: TextInput::default(),
+            git_remote_input: TextInput::default(),
+            diff_title: String::new(),
+            diff_lines: Vec::new(),
+            diff_scroll: 0,
+            export_scope_selected: 0,
+            export_dir: TextInput::default(),
+            export_summary: None,
+            export_summary_scroll: 0,
+            graph_options: GraphOptions::default(),
+            graph_option_selected: 0,
+            graph_path: TextInput::default(),
+            graph_export: None,
+            backup: None,
+            backup_due,
+            save_lock: Arc::default(),
+            content_loader: None,
+            alias_pending: String::new(),
+            alias_candidates: Vec::new(),
+            alias_selected: 0,
+            capture_input: TextInput::default(),
+            capture_return_focus: Focus::List,
+            task_input: TextInput::default(),
+            task_return_focus: Focus::List,
+            random_tag: TextInput::default(),
+            random_in_current_dir: false,
+            random_last: None,
+            rng: Rng::from_time(),
+            external_edit: None,
+            suspend_requested: false,
+            user_command_pending: None,
+            user_command: None,
+            pending_keys: Vec::new(),
+            pending_since: None,
             backlinks: Vec::new(),
             backlinks_selected: 0,
-            backlinks_cache_valid: false,
-            cached_backlink_target: None,
-            tag_explorer_active: false,
+            links,
             all_tags: Vec::new(),
             tag_selected: 0,
             tag_files: Vec::new(),
@@ -447,1243 +1457,4976 @@ impl App {
             tag_explorer_view: TagExplorerView::TagList,
             last_keystroke_time: None,
             editor_dirty: false,
+            config_reload_pending: false,
+            config_stamps: config_stamps(&config_dir),
+            config_checked: None,
             save_indicator_until: None,
-            task_view_active: false,
+            needs_redraw: true,
+            git: None,
+            git_checked: None,
             tasks: Vec::new(),
             task_selected: 0,
+            task_column: 0,
+            task_column_rows: Vec::new(),
         };
         app.apply_editor_theme_to_all();
+        app.report_missing_dictionaries();
+        app.report_keybinding_problems();
+        app.report_theme_warnings();
+        app.report_statusline_warnings();
+        if !app.config_errors.is_empty() {
+            app.report_config_errors();
+            app.focus = Focus::ConfigError;
+        }
         Ok(app)
     }
 
-    pub fn refresh_notes(&mut self) -> Result<()> {
-        self.all_notes = load_entries(&self.current_dir)?;
-        if !self.config.ui.show_hidden {
-            self.all_notes.retain(|e| !e.display.starts_with('.'));
+    /// Footer message naming spellcheck languages without a dictionary file, so an empty
+    /// spellchecker is not silent.
+    fn report_missing_dictionaries(&mut self) {
+        let Some(checker) = self.spellchecker.as_ref() else {
+            return;
+        };
+        if !checker.missing_languages.is_empty() {
+            self.push_message(format!(
+                "Spellcheck: no dictionary found for {} (looked in /usr/share/dict, /usr/share/hunspell, /usr/share/myspell/dicts)",
+                checker.missing_languages.join(", ")
+            ));
         }
-        self.apply_filter();
-        self.clamp_selection();
-        Ok(())
     }
 
-    /// Returns Nerd Font icon for path/extension when config.ui.icons is true, else empty string.
-    pub fn file_icon(&self, path: &std::path::Path) -> &'static str {
-        if !self.config.ui.icons {
-            return "";
-        }
-        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-        match ext.to_lowercase().as_str() {
-            "md" | "markdown" => "\u{f48a} ",                      // md
-            "rs" => "\u{e79b} ",                                   // rust
-            "toml" | "yaml" | "yml" => "\u{f718} ",                // config
-            "json" => "\u{e60b} ",                                 // json
-            "txt" => "\u{f15c} ",                                  // text
-            "pdf" => "\u{f1c1} ",                                  // pdf
-            "png" | "jpg" | "jpeg" | "gif" | "svg" => "\u{f1c5} ", // image
-            _ if path.is_dir() => "\u{f115} ",                     // folder
-            _ => "\u{f016} ",                                      // file
+    /// Footer message when `[keys]` has conflicts or invalid key strings; the details are in
+    /// the Check Keybindings report.
+    fn report_keybinding_problems(&mut self) {
+        let count = check_keybindings(&self.config.keys).len();
+        if count > 0 {
+            self.push_message(format!(
+                "{count} keybinding problem(s) found; run Check Keybindings from the command palette"
+            ));
         }
     }
 
-    /// Enter the selected directory. Returns true if we navigated.
-    pub fn enter_selected_directory(&mut self) -> bool {
-        let entry = match self.filtered_notes.get(self.selected) {
-            Some(e) if e.is_directory => e,
-            _ => return false,
-        };
-        match fs::metadata(&entry.path) {
-            Ok(m) if m.is_dir() => {}
-            _ => return false,
-        }
-        self.current_dir = entry.path.clone();
-        if let Err(e) = self.refresh_notes() {
-            self.message = Some(format!("Cannot read directory: {e}"));
+    /// Show `message` in the footer and keep it in the message log.
+    pub fn push_message(&mut self, message: String) {
+        if self.messages.len() == MAX_MESSAGES {
+            self.messages.pop_front();
         }
-        true
+        let now = Instant::now();
+        self.messages.push_back((now, message));
+        self.message_until = Some(now + MESSAGE_DURATION);
+        self.needs_redraw = true;
     }
 
-    /// Go to parent directory. Returns true if we navigated. Never goes above notes_dir.
-    pub fn go_to_parent_dir(&mut self) -> bool {
-        if self.current_dir == self.notes_dir {
-            return false;
-        }
-        let parent = match self.current_dir.parent() {
-            Some(p) => p.to_path_buf(),
-            None => return false,
-        };
-        if !parent.starts_with(&self.notes_dir) {
-            return false;
-        }
-        let prev_folder_name = self
-            .current_dir
-            .file_name()
-            .and_then(|n| n.to_str())
-            .map(|s| format!("{s}/"));
-        self.current_dir = parent;
-        if let Err(e) = self.refresh_notes() {
-            self.message = Some(format!("Cannot read directory: {e}"));
-            return true;
-        }
-        if let Some(name) = prev_folder_name {
-            if let Some(idx) = self.filtered_notes.iter().position(|e| e.display == name) {
-                self.selected = idx;
-            }
-        }
-        true
+    /// Show a failed action's error in the footer and the message log, e.g. "Save failed:
+    /// Permission denied (os error 13)".
+    pub fn report_err(&mut self, context: &str, err: anyhow::Error) {
+        self.push_message(format!("{context} failed: {err:#}"));
     }
 
-    /// Check if we can go up (not at notes root).
-    #[allow(dead_code)]
-    pub fn can_go_up(&self) -> bool {
-        self.current_dir != self.notes_dir
+    /// The newest message, while it has not expired.
+    pub fn footer_message(&self) -> Option<&str> {
+        let until = self.message_until?;
+        if Instant::now() >= until {
+            return None;
+        }
+        self.messages.back().map(|(_, m)| m.as_str())
     }
 
-    fn apply_filter(&mut self) {
-        if self.search_query.is_empty() {
-            self.filtered_notes = self.all_notes.clone();
-            self.match_indices = vec![Vec::new(); self.filtered_notes.len()];
-        } else {
-            self.filtered_notes =
-                filter_notes(&self.all_notes, &self.search_query, &mut self.matcher);
-            self.match_indices = self
-                .filtered_notes
-                .iter()
-                .map(|n| get_match_indices(&n.display, &self.search_query, &mut self.matcher))
-                .collect();
+    /// Save before quitting. Returns false when the save failed, so the edits are not lost
+    /// silently; quitting again then discards them.
+    pub fn save_before_quit(&mut self) -> bool {
+        match self.save_all_buffers() {
+            Ok(()) => true,
+            Err(_) if self.quit_discards_changes => true,
+            Err(e) => {
+                self.report_err("Save", e.context("quit again to discard unsaved changes"));
+                self.quit_discards_changes = true;
+                false
+            }
         }
     }
 
-    fn clamp_selection(&mut self) {
-        if self.filtered_notes.is_empty() {
-            self.selected = 0;
-        } else if self.selected >= self.filtered_notes.len() {
-            self.selected = self.filtered_notes.len() - 1;
-        }
+    /// Take the current message out of the footer (it stays in the log).
+    pub fn clear_message(&mut self) {
+        self.message_until = None;
     }
 
-    pub fn move_selection_up(&mut self) {
-        if self.selected > 0 {
-            self.selected -= 1;
+    pub fn open_messages(&mut self) {
+        if self.messages.is_empty() {
+            self.push_message("No messages yet".to_string());
+            return;
         }
+        self.messages_scroll = 0;
+        self.focus = Focus::Messages;
     }
 
-    pub fn move_selection_down(&mut self) {
-        if self.selected + 1 < self.filtered_notes.len() {
-            self.selected += 1;
-        }
+    pub fn exit_messages(&mut self) {
+        self.focus = if self.has_open_buffers() {
+            Focus::Editor
+        } else {
+            Focus::List
+        };
     }
 
-    pub fn enter_search_mode(&mut self) {
-        self.mode = Mode::Search;
-        self.search_query.clear();
+    pub fn messages_scroll_up(&mut self) {
+        self.messages_scroll = self.messages_scroll.saturating_sub(1);
     }
 
-    pub fn exit_search_mode(&mut self) {
-        self.mode = Mode::Normal;
-        self.search_query.clear();
-        self.apply_filter();
-        self.clamp_selection();
+    pub fn messages_scroll_down(&mut self) {
+        if self.messages_scroll + 1 < self.messages.len() {
+            self.messages_scroll += 1;
+        }
     }
 
-    pub fn search_add_char(&mut self, c: char) {
-        self.search_query.push(c);
-        self.apply_filter();
-        self.selected = 0;
+    // Keybinding report (Check Keybindings)
+    pub fn open_keybinding_report(&mut self) {
+        self.keybinding_report = check_keybindings(&self.config.keys);
+        if self.keybinding_report.is_empty() {
+            self.push_message("No keybinding conflicts".to_string());
+            return;
+        }
+        self.keybinding_report_scroll = 0;
+        self.focus = Focus::KeybindingReport;
     }
 
-    pub fn search_backspace(&mut self) {
-        self.search_query.pop();
-        self.apply_filter();
-        self.clamp_selection();
+    pub fn exit_keybinding_report(&mut self) {
+        self.keybinding_report.clear();
+        self.focus = if self.has_open_buffers() {
+            Focus::Editor
+        } else {
+            Focus::List
+        };
     }
 
-    pub fn enter_create_mode(&mut self) {
-        self.mode = Mode::Create;
-        self.create_filename.clear();
+    pub fn keybinding_report_scroll_up(&mut self) {
+        self.keybinding_report_scroll = self.keybinding_report_scroll.saturating_sub(1);
     }
 
-    pub fn exit_create_mode(&mut self) {
-        self.mode = Mode::Normal;
-        self.create_filename.clear();
+    pub fn keybinding_report_scroll_down(&mut self) {
+        if self.keybinding_report_scroll + 1 < self.keybinding_report.len() {
+            self.keybinding_report_scroll += 1;
+        }
     }
 
-    pub fn create_add_char(&mut self, c: char) {
-        self.create_filename.push(c);
+    /// Reload the current folder. The selected entry stays selected if it still exists;
+    /// otherwise the selection stays at the same index, clamped to the new list.
+    pub fn refresh_notes(&mut self) -> Result<()> {
+        self.git_checked = None;
+        let selected = self.get_selected_path();
+        let mut notes = if self.lists_subtree() {
+            self.subtree_notes()
+        } else {
+            let entries = self.folder_entries(&self.current_dir)?;
+            if self.tree_view {
+                self.expand_tree(entries, 0)
+            } else {
+                entries
+            }
+        };
+        set_titles(&mut notes, &self.links, self.config.ui.display_titles);
+        // Keep content already read for notes that have not changed since.
+        let mut previous: HashMap<PathBuf, NoteEntry> = std::mem::take(&mut self.all_notes)
+            .into_iter()
+            .filter(|e| e.content != NoteContent::Pending)
+            .map(|e| (e.path.clone(), e))
+            .collect();
+        for entry in &mut notes {
+            if entry.content != NoteContent::Pending {
+                continue;
+            }
+            if let Some(old) = previous.remove(&entry.path) {
+                if old.modified.is_some() && old.modified == entry.modified {
+                    entry.set_content(old.content, old.modified);
+                }
+            }
+        }
+        self.all_notes = notes;
+        // Its paths may belong to another folder; `apply_filter` starts a new one if needed.
+        self.content_loader = None;
+        let all_notes = &self.all_notes;
+        self.marked
+            .retain(|p| all_notes.iter().any(|e| &e.path == p));
+        self.apply_filter();
+        if !selected.is_some_and(|p| self.select_path(&p)) {
+            self.clamp_selection();
+        }
+        Ok(())
     }
 
-    pub fn create_backspace(&mut self) {
-        self.create_filename.pop();
+    /// Whether the list holds every note below the current folder: while filtering in tree
+    /// view.
+    fn lists_subtree(&self) -> bool {
+        self.tree_view && self.mode == Mode::Search && !self.search_query.is_empty()
     }
 
-    pub fn get_selected_path(&self) -> Option<PathBuf> {
-        self.filtered_notes
-            .get(self.selected)
-            .map(|n| n.path.clone())
+    fn folder_entries(&self, dir: &PathBuf) -> Result<Vec<NoteEntry>> {
+        let mut entries = load_entries(dir, self.config.ui.show_all_files)?;
+        if !self.config.ui.show_hidden {
+            entries.retain(|e| !e.display.starts_with('.'));
+        }
+        Ok(entries)
     }
 
-    /// Get preview content: from textarea when editing, else from selected note.
-    pub fn get_preview_content(&self) -> String {
-        if self.focus == Focus::Editor {
-            if let Some(buf) = self.focused_buffer() {
-                return buf.textarea.lines().join("\n");
+    /// `entries` at `depth`, each expanded folder followed by its own entries. A folder that
+    /// cannot be read shows as empty.
+    fn expand_tree(&self, entries: Vec<NoteEntry>, depth: usize) -> Vec<NoteEntry> {
+        let mut tree = Vec::new();
+        for mut entry in entries {
+            entry.depth = depth;
+            let children = if entry.is_directory && self.expanded.contains(&entry.path) {
+                self.folder_entries(&entry.path).ok()
+            } else {
+                None
+            };
+            tree.push(entry);
+            if let Some(children) = children {
+                tree.extend(self.expand_tree(children, depth + 1));
             }
         }
-        if let Some(note) = self.filtered_notes.get(self.selected) {
-            note.content.clone()
-        } else {
-            String::new()
+        tree
+    }
+
+    /// Every note below the current folder, named by its path from there.
+    fn subtree_notes(&self) -> Vec<NoteEntry> {
+        let walk = vault::walk(&self.current_dir, &self.ignore);
+        let mut notes: Vec<NoteEntry> = walk
+            .files()
+            .filter(|path| {
+                self.config.ui.show_all_files
+                    || path.extension().is_some_and(|e| e == MARKDOWN_EXT)
+                    || crypt::is_encrypted(path)
+            })
+            .filter_map(|path| {
+                let rel = path.strip_prefix(&self.current_dir).ok()?;
+                let hidden = rel
+                    .components()
+                    .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+                if hidden && !self.config.ui.show_hidden {
+                    return None;
+                }
+                let content = if is_binary_file(path) || crypt::is_encrypted(path) {
+                    NoteContent::Skipped
+                } else {
+                    NoteContent::Pending
+                };
+                Some(NoteEntry::file(
+                    path.to_path_buf(),
+                    rel.display().to_string(),
+                    content,
+                    content::modified(path),
+                ))
+            })
+            .collect();
+        notes.sort_by_key(|n| n.display.to_lowercase());
+        notes
+    }
+
+    /// Switch the file list between one folder at a time and the tree view.
+    pub fn toggle_tree_view(&mut self) {
+        self.tree_view = !self.tree_view;
+        if let Err(e) = self.refresh_notes() {
+            self.report_err("Refresh", e);
         }
+        self.push_message(format!(
+            "Tree view {}",
+            if self.tree_view { "on" } else { "off" }
+        ));
     }
 
-    pub fn get_preview_placeholder(&self) -> Option<&str> {
-        if self.focus == Focus::Editor {
-            return None;
+    /// Right on a folder: expand it in the tree view, or move to its first entry if it is
+    /// already expanded. Outside the tree view, enter it.
+    pub fn expand_selected(&mut self) {
+        let Some(entry) = self.filtered_notes.get(self.selected) else {
+            return;
+        };
+        if !entry.is_directory {
+            return;
         }
-        let note = self.filtered_notes.get(self.selected)?;
-        if note.content.is_empty() && !note.display.is_empty() {
-            Some("(Preview unavailable: file unreadable)")
-        } else {
-            None
+        if !self.tree_view {
+            self.enter_selected_directory();
+        } else if self.expanded.insert(entry.path.clone()) {
+            if let Err(e) = self.refresh_notes() {
+                self.report_err("Refresh", e);
+            }
+        } else if self
+            .filtered_notes
+            .get(self.selected + 1)
+            .is_some_and(|next| next.path.parent() == Some(entry.path.as_path()))
+        {
+            self.selected += 1;
         }
     }
 
-    pub fn reload_config(&mut self) -> Result<()> {
-        self.config = load_config()?;
-        self.resolved_keys = ResolvedKeys::from_config(&self.config.keys);
-        let config_dir = crate::config::ensure_config_dir()?;
-        let theme_raw = load_theme(&config_dir)?;
-        self.theme = ResolvedTheme::resolve(&theme_raw, Some(&self.config.theme))?;
-        self.notes_dir = expand_path(&self.config.notes_directory);
-        if !self.current_dir.starts_with(&self.notes_dir) {
-            self.current_dir = self.notes_dir.clone();
+    /// Left in the tree view: collapse the selected folder if it is expanded, else the folder
+    /// the selected entry is in, selecting it. At the top of the tree (and outside the tree
+    /// view), go to the parent folder.
+    pub fn collapse_selected(&mut self) {
+        if !self.tree_view || self.lists_subtree() {
+            self.go_to_parent_dir();
+            return;
         }
-        self.apply_editor_theme_to_all();
-        self.spellchecker = if self.config.editor.enable_spellcheck
-            && !self.config.editor.spellcheck_languages.is_empty()
-        {
-            Some(Spellchecker::new(&self.config.editor.spellcheck_languages))
+        let Some(entry) = self.filtered_notes.get(self.selected) else {
+            self.go_to_parent_dir();
+            return;
+        };
+        let folder = if entry.is_directory && self.expanded.contains(&entry.path) {
+            entry.path.clone()
+        } else if entry.depth > 0 {
+            match entry.path.parent() {
+                Some(parent) => parent.to_path_buf(),
+                None => return,
+            }
         } else {
-            None
+            self.go_to_parent_dir();
+            return;
         };
-        Ok(())
+        self.expanded.remove(&folder);
+        if let Err(e) = self.refresh_notes() {
+            self.report_err("Refresh", e);
+        }
+        self.select_path(&folder);
     }
 
-    /// Open or create today's daily note and switch editor to it.
-    pub fn open_daily_note(&mut self) -> Result<()> {
-        let date = Local::now().format(DAILY_NOTE_DATE_FORMAT).to_string();
-        let folder = self.notes_dir.join(self.config.daily_notes_folder.trim());
-        fs::create_dir_all(&folder)?;
-        let path = folder.join(format!("{date}.md"));
-        if !path.exists() {
-            let header = format!("# Daily Note: {date}\n\n");
-            fs::write(&path, header)?;
+    /// Select the listed entry at `path`. Returns false (leaving the selection alone) if it is
+    /// not in the list.
+    pub fn select_path(&mut self, path: &Path) -> bool {
+        match self.filtered_notes.iter().position(|e| e.path == path) {
+            Some(idx) => {
+                self.selected = idx;
+                true
+            }
+            None => false,
         }
-        self.load_file_into_editor(path)
     }
 
-    /// Load file content into a new or existing tab and switch focus to Editor.
-    pub fn load_file_into_editor(&mut self, path: PathBuf) -> Result<()> {
-        self.load_file_into_editor_at_line(path, None)
+    /// Returns Nerd Font icon for path/extension when config.ui.icons is true, else empty string.
+    pub fn file_icon(&self, path: &std::path::Path) -> &'static str {
+        if !self.config.ui.icons {
+            return "";
+        }
+        if crypt::is_encrypted(path) {
+            return "\u{f023} "; // lock
+        }
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        match ext.to_lowercase().as_str() {
+            "md" | "markdown" => "\u{f48a} ",                      // md
+            "rs" => "\u{e79b} ",                                   // rust
+            "toml" | "yaml" | "yml" => "\u{f718} ",                // config
+            "json" => "\u{e60b} ",                                 // json
+            "txt" => "\u{f15c} ",                                  // text
+            "pdf" => "\u{f1c1} ",                                  // pdf
+            "png" | "jpg" | "jpeg" | "gif" | "svg" => "\u{f1c5} ", // image
+            _ if path.is_dir() => "\u{f115} ",                     // folder
+            _ => "\u{f016} ",                                      // file
+        }
     }
 
-    /// Load file and optionally move cursor to the given 0-based line.
-    #[allow(clippy::unnecessary_wraps)]
-    pub fn load_file_into_editor_at_line(
-        &mut self,
-        path: PathBuf,
-        goto_line: Option<usize>,
-    ) -> Result<()> {
-        // Check if already open
-        if let Some(idx) = self
-            .buffers
-            .iter()
-            .position(|b| b.path.as_ref() == Some(&path))
-        {
-            self.active_tab = idx;
-            self.focus = Focus::Editor;
-            self.editor_mode = EditorMode::Normal;
-            if let Some(line) = goto_line {
-                if let Some(buf) = self.buffers.get_mut(idx) {
-                    let row = line.min(buf.textarea.lines().len().saturating_sub(1));
-                    #[allow(clippy::cast_possible_truncation)]
-                #[allow(clippy::cast_possible_truncation)]
-                buf.textarea.move_cursor(CursorMove::Jump(row as u16, 0));
-                }
+    /// Enter the selected directory. Returns true if we navigated.
+    pub fn enter_selected_directory(&mut self) -> bool {
+        let entry = match self.filtered_notes.get(self.selected) {
+            Some(e) if e.is_directory => e,
+            _ => return false,
+        };
+        // The tree view expands and collapses folders in place instead.
+        if self.tree_view {
+            if !self.expanded.remove(&entry.path) {
+                self.expanded.insert(entry.path.clone());
             }
-            return Ok(());
+            if let Err(e) = self.refresh_notes() {
+                self.report_err("Refresh", e);
+            }
+            return true;
         }
-        let content = fs::read_to_string(&path).unwrap_or_default();
-        let lines: Vec<String> = if content.is_empty() {
-            vec![String::new()]
-        } else {
-            content.lines().map(std::string::ToString::to_string).collect()
-        };
-        let mut buf = EditorBuffer::new(Some(path), lines);
-        buf.textarea.set_max_histories(50);
-        if let Some(line) = goto_line {
-            let row = line.min(buf.textarea.lines().len().saturating_sub(1));
-            buf.textarea.move_cursor(CursorMove::Jump(row as u16, 0));
+        match fs::metadata(&entry.path) {
+            Ok(m) if m.is_dir() => {}
+            _ => return false,
         }
-        Self::apply_theme_to_textarea(&self.theme, &mut buf.textarea, &self.config.editor);
-        self.buffers.push(buf);
-        self.active_tab = self.buffers.len() - 1;
-        self.focus = Focus::Editor;
-        self.editor_mode = EditorMode::Normal;
-        if self.config.editor.show_backlinks {
-            self.scan_backlinks();
+        self.current_dir = entry.path.clone();
+        self.marked.clear();
+        self.selected = 0;
+        self.notes_scroll.set(0);
+        if let Err(e) = self.refresh_notes() {
+            self.push_message(format!("Cannot read directory: {e}"));
         }
-        Ok(())
-    }
-
-    /// Switch focus back to List. Auto-saves before switching.
-    pub fn focus_list(&mut self) {
-        let _ = self.save_all_buffers();
-        self.focus = Focus::List;
+        true
     }
 
-    /// Enter delete confirmation. Shows N/y prompt.
-    pub fn enter_delete_confirm(&mut self) {
-        let entry = match self.filtered_notes.get(self.selected) {
-            Some(e) => e.clone(),
-            None => return,
+    /// Go to parent directory. Returns true if we navigated. Never goes above notes_dir.
+    pub fn go_to_parent_dir(&mut self) -> bool {
+        if self.current_dir == self.notes_dir {
+            return false;
+        }
+        let parent = match self.current_dir.parent() {
+            Some(p) => p.to_path_buf(),
+            None => return false,
         };
-        if !entry.is_directory
-            && (entry.path.ends_with("config.toml") || entry.path.ends_with("theme.toml"))
-        {
-            self.message = Some("Cannot delete config files".to_string());
-            return;
+        if !parent.starts_with(&self.notes_dir) {
+            return false;
         }
-        self.delete_pending = Some(entry);
-        self.focus = Focus::DeleteConfirm;
+        self.go_up_to(parent);
+        true
     }
 
-    /// Cancel delete confirmation.
-    pub fn exit_delete_confirm(&mut self) {
-        self.delete_pending = None;
-        self.focus = Focus::List;
+    /// Folder names from the vault root down to the current folder; the root is shown by its
+    /// own name.
+    pub fn breadcrumb(&self) -> Vec<String> {
+        let root = self
+            .notes_dir
+            .file_name()
+            .map_or_else(|| "notes".to_string(), |n| n.to_string_lossy().into_owned());
+        let rel = self
+            .current_dir
+            .strip_prefix(&self.notes_dir)
+            .unwrap_or(Path::new(""));
+        std::iter::once(root)
+            .chain(
+                rel.components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+            )
+            .collect()
     }
 
-    /// Perform delete after user confirmed with y.
-    pub fn confirm_delete(&mut self) -> Result<()> {
-        let Some(entry) = self.delete_pending.take() else { return Ok(()) };
-        let path = entry.path.clone();
-        let is_directory = entry.is_directory;
-        self.focus = Focus::List;
-
-        if is_directory {
-            self.buffers.retain(|b| {
-                b.path
-                    .as_ref()
-                    .is_none_or(|p| p.strip_prefix(&path).is_err())
-            });
-        } else {
-            self.buffers.retain(|b| b.path.as_ref() != Some(&path));
-        }
-        if self.active_tab >= self.buffers.len() {
-            self.active_tab = self.buffers.len().saturating_sub(1);
-        }
-        if self.split_right_tab.is_some_and(|i| i >= self.buffers.len()) {
-            self.split_right_tab = None;
+    /// Go to the folder at `level` of the breadcrumb path (0 is the vault root).
+    pub fn jump_to_level(&mut self, level: usize) {
+        let Ok(rel) = self.current_dir.strip_prefix(&self.notes_dir) else {
+            return;
+        };
+        let depth = rel.components().count();
+        if level > depth {
+            self.push_message(format!("No folder at level {level}"));
+        } else if level < depth {
+            let target = self
+                .notes_dir
+                .join(rel.components().take(level).collect::<PathBuf>());
+            self.go_up_to(target);
         }
-        if is_directory {
-            fs::remove_dir_all(&path)?;
-        } else {
-            fs::remove_file(&path)?;
+    }
+
+    /// Browse `dir`, an ancestor of the current folder, selecting the subfolder we came from.
+    fn go_up_to(&mut self, dir: PathBuf) {
+        let from = self
+            .current_dir
+            .ancestors()
+            .find(|a| a.parent() == Some(dir.as_path()))
+            .map(Path::to_path_buf);
+        self.current_dir = dir;
+        self.marked.clear();
+        self.selected = 0;
+        self.notes_scroll.set(0);
+        if let Err(e) = self.refresh_notes() {
+            self.push_message(format!("Cannot read directory: {e}"));
+            return;
         }
-        self.refresh_notes()?;
-        if self.buffers.is_empty() {
-            self.buffers
-                .push(EditorBuffer::new(None, vec![String::new()]));
-            self.active_tab = 0;
-            self.focus = Focus::List;
-            self.apply_editor_theme_to_all();
+        if let Some(from) = from {
+            self.select_path(&from);
         }
-
-        self.message = Some("Deleted".to_string());
-        Ok(())
     }
 
-    /// Save all buffers to disk (auto-save, no user message).
-    pub fn save_all_buffers(&mut self) -> Result<()> {
-        let mut need_reload = false;
-        for buf in &mut self.buffers {
-            if let Some(path) = &buf.path {
-                let content = buf.textarea.lines().join("\n");
+    /// Check if we can go up (not at notes root).
+    #[allow(dead_code)]
+    pub fn can_go_up(&self) -> bool {
+        self.current_dir != self.notes_dir
+    }
+
+    /// Filter the listed notes by the search. A query that only adds to the last one filters
+    /// the last results instead of every note.
+    fn apply_filter(&mut self) {
+        self.search_pending = false;
+        match regex_query(&self.search_query, self.search_case) {
+            Some(Err(error)) => {
+                self.search_error = Some(error);
+                return;
+            }
+            regex => {
+                self.search_error = None;
+                self.search_regex = regex.and_then(Result::ok);
+            }
+        }
+        if self.search_query.is_empty() {
+            self.filtered_notes = self.all_notes.clone();
+            self.filtered_for = None;
+        } else {
+            self.load_all_content();
+            let (mode, query) = SearchMode::parse(&self.search_query);
+            let narrowed = self.filtered_for.as_deref().is_some_and(|previous| {
+                let (previous_mode, previous) = SearchMode::parse(previous);
+                previous_mode == mode && mode != SearchMode::Regex && narrows(previous, query)
+            });
+            let source = if narrowed {
+                &self.filtered_notes
+            } else {
+                &self.all_notes
+            };
+            self.filtered_notes =
+                filter_notes(source, query, mode, self.search_case, &mut self.matcher);
+            self.filtered_for = Some(self.search_query.to_string());
+        }
+        self.list_highlights.reset(self.filtered_notes.len());
+        self.fill_highlights();
+    }
+
+    /// Text of the list search that the preview highlights: the query without its mode
+    /// prefix, or nothing for a name search. Regex matches come from `search_regex`.
+    pub fn search_terms(&self) -> &str {
+        match SearchMode::parse(&self.search_query) {
+            (SearchMode::Name | SearchMode::Regex, _) => "",
+            (_, terms) => terms,
+        }
+    }
+
+    /// Work out the search highlights of the list rows and telescope results that the next
+    /// draw may show and that have none yet.
+    pub fn fill_highlights(&mut self) {
+        if !self.search_query.is_empty() {
+            let rows = visible_rows(
+                self.notes_scroll.get(),
+                self.selected,
+                self.notes_rows.get(),
+            );
+            let (notes, query, matcher) =
+                (&self.filtered_notes, &self.search_query, &mut self.matcher);
+            let (mode, terms) = SearchMode::parse(query);
+            let case = self.search_case;
+            if let Some(re) = &self.search_regex {
+                self.list_highlights.fill(rows, |i| RowHighlight {
+                    indices: regex_match_indices(&notes[i].search_label(), re),
+                    content: notes[i]
+                        .content
+                        .text()
+                        .lines()
+                        .enumerate()
+                        .find(|(_, line)| re.is_match(line))
+                        .map(|(row, line)| (row, line.trim().to_string())),
+                });
+            } else {
+                self.list_highlights.fill(rows, |i| RowHighlight {
+                    indices: list_match_indices(&notes[i].search_label(), query, case, matcher),
+                    content: (mode != SearchMode::Name)
+                        .then(|| first_matching_line(notes[i].content.text(), terms, case, matcher))
+                        .flatten()
+                        .map(|(row, line)| (row, line.trim().to_string())),
+                });
+            }
+        }
+        if !self.telescope_query.is_empty() && self.telescope_mode == TelescopeMode::Files {
+            let rows = visible_rows(0, self.telescope_selected, self.telescope_rows.get());
+            let (notes, query, matcher) = (
+                &self.telescope_filtered,
+                &self.telescope_query,
+                &mut self.telescope_matcher,
+            );
+            let (regex, case) = (self.telescope_regex.as_ref(), self.search_case);
+            self.telescope_highlights.fill(rows, |i| RowHighlight {
+                indices: match regex {
+                    Some(re) => regex_match_indices(&notes[i].search_label(), re),
+                    None => {
+                        get_telescope_match_indices(&notes[i].search_label(), query, case, matcher)
+                    }
+                },
+                content: None,
+            });
+        }
+    }
+
+    /// Apply search edits held back by `ui.debounce_search`; called once the typed keys have
+    /// been handled. The selected entry stays selected while it still matches; otherwise the
+    /// best match is.
+    pub fn apply_pending_search(&mut self) {
+        if self.search_pending {
+            let selected = self.get_selected_path();
+            self.apply_filter();
+            if !selected.is_some_and(|p| self.select_path(&p)) {
+                self.selected = 0;
+            }
+        }
+        if self.telescope_pending {
+            let selected = self.telescope_entry(self.telescope_selected);
+            self.apply_telescope_filter();
+            self.telescope_selected = (0..self.telescope_len())
+                .find(|&i| selected.is_some() && self.telescope_entry(i) == selected)
+                .unwrap_or(0);
+        }
+    }
+
+    fn clamp_selection(&mut self) {
+        if self.filtered_notes.is_empty() {
+            self.selected = 0;
+        } else if self.selected >= self.filtered_notes.len() {
+            self.selected = self.filtered_notes.len() - 1;
+        }
+    }
+
+    /// Start reading every listed note that has not been read yet, so search can match its
+    /// content. Until then a note matches by name only.
+    fn load_all_content(&mut self) {
+        if self.content_loader.is_some() {
+            return;
+        }
+        let pending: Vec<PathBuf> = self
+            .all_notes
+            .iter()
+            .filter(|e| e.content == NoteContent::Pending)
+            .map(|e| e.path.clone())
+            .collect();
+        if !pending.is_empty() {
+            self.content_loader = Some(ContentLoader::spawn(pending));
+        }
+    }
+
+    /// Take in the notes read by the background loader, filtering again while searching so
+    /// their content can match. Returns true while the loader is still running.
+    pub fn poll_note_content(&mut self) -> bool {
+        let Some(loader) = &self.content_loader else {
+            return false;
+        };
+        let (loaded, done) = loader.poll();
+        if done {
+            self.content_loader = None;
+        }
+        if loaded.is_empty() {
+            return !done;
+        }
+        for note in loaded {
+            self.set_note_content(&note.path, &note.content, note.modified);
+        }
+        if !self.search_query.is_empty() {
+            let selected = self.get_selected_path();
+            self.apply_filter();
+            if !selected.is_some_and(|p| self.select_path(&p)) {
+                self.clamp_selection();
+            }
+        }
+        !done
+    }
+
+    /// Read the selected note for the preview if it has not been read yet or has changed on
+    /// disk since.
+    pub fn load_selected_content(&mut self) {
+        if self.focus == Focus::Editor {
+            return;
+        }
+        let Some(note) = self.filtered_notes.get(self.selected) else {
+            return;
+        };
+        if note.content == NoteContent::Skipped {
+            return;
+        }
+        let modified = content::modified(&note.path);
+        if note.content != NoteContent::Pending && modified == note.modified {
+            return;
+        }
+        let path = note.path.clone();
+        self.set_note_content(&path, &content::read(&path), modified);
+    }
+
+    fn set_note_content(
+        &mut self,
+        path: &Path,
+        content: &NoteContent,
+        modified: Option<SystemTime>,
+    ) {
+        for entry in self.all_notes.iter_mut() {
+            if entry.path == path {
+                entry.set_content(content.clone(), modified);
+            }
+        }
+        for (i, entry) in self.filtered_notes.iter_mut().enumerate() {
+            if entry.path == path {
+                entry.set_content(content.clone(), modified);
+                self.list_highlights.forget(i);
+            }
+        }
+        // The new content may match queries the old one did not.
+        self.filtered_for = None;
+        self.needs_redraw = true;
+    }
+
+    pub fn move_selection_up(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn move_selection_down(&mut self) {
+        if self.selected + 1 < self.filtered_notes.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Filter the current folder in place; the `search` key opens telescope instead.
+    pub fn enter_search_mode(&mut self) {
+        self.mode = Mode::Search;
+        self.search_query.clear();
+        self.search_origin = self.get_selected_path();
+    }
+
+    /// Search again with the query of the last list search.
+    pub fn repeat_last_search(&mut self) {
+        self.enter_search_mode();
+        let query = self.last_search.clone();
+        self.update_search(|app| app.search_query.set(query));
+    }
+
+    /// Leave the search keeping the selected entry selected, as after opening it.
+    pub fn exit_search_mode(&mut self) {
+        // Leaving a subtree search in the tree view: reveal the selected note in the tree.
+        if self.lists_subtree() {
+            if let Some(path) = self.get_selected_path() {
+                let folders = path
+                    .ancestors()
+                    .skip(1)
+                    .take_while(|a| *a != self.current_dir && a.starts_with(&self.current_dir))
+                    .map(Path::to_path_buf)
+                    .collect::<Vec<_>>();
+                self.expanded.extend(folders);
+            }
+        }
+        self.clear_search();
+    }
+
+    /// Leave the search with the entry selected before it selected again.
+    pub fn cancel_search_mode(&mut self) {
+        let origin = self.search_origin.take();
+        self.clear_search();
+        // Filter now, so a debounced search does not keep the result selected instead.
+        self.apply_pending_search();
+        if !origin.is_some_and(|p| self.select_path(&p)) {
+            self.clamp_selection();
+        }
+    }
+
+    fn clear_search(&mut self) {
+        if !self.search_query.is_empty() {
+            self.last_search = self.search_query.to_string();
+        }
+        self.update_search(|app| {
+            app.mode = Mode::Normal;
+            app.search_query.clear();
+        });
+    }
+
+    pub fn search_key(&mut self, key: crossterm::event::KeyEvent) {
+        let mut query = self.search_query.clone();
+        if query.handle_key(key) == InputChange::Edited {
+            self.update_search(|app| app.search_query = query);
+        } else {
+            self.search_query = query;
+        }
+    }
+
+    pub fn search_backspace(&mut self) {
+        self.update_search(|app| {
+            app.search_query.backspace();
+        });
+    }
+
+    /// Change the search and filter again. In the tree view, starting or ending a search
+    /// switches the list between the tree and every note below the current folder.
+    fn update_search(&mut self, edit: impl FnOnce(&mut Self)) {
+        let was_subtree = self.lists_subtree();
+        edit(self);
+        if was_subtree == self.lists_subtree() {
+            self.search_pending = true;
+            if !self.config.ui.debounce_search {
+                self.apply_pending_search();
+            }
+        } else if let Err(e) = self.refresh_notes() {
+            self.report_err("Refresh", e);
+        }
+    }
+
+    pub fn enter_create_mode(&mut self) {
+        self.mode = Mode::Create;
+        self.create_filename.clear();
+    }
+
+    pub fn exit_create_mode(&mut self) {
+        self.mode = Mode::Normal;
+        self.create_filename.clear();
+    }
+
+    pub fn create_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.create_filename.handle_key(key);
+    }
+
+    pub fn create_backspace(&mut self) {
+        self.create_filename.backspace();
+    }
+
+    pub fn get_selected_path(&self) -> Option<PathBuf> {
+        self.filtered_notes
+            .get(self.selected)
+            .map(|n| n.path.clone())
+    }
+
+    /// Get preview content: from textarea when editing, else from selected note.
+    pub fn get_preview_content(&self) -> String {
+        if self.focus == Focus::Editor {
+            if let Some(buf) = self.focused_buffer() {
+                return buf.textarea.lines().join("\n");
+            }
+        }
+        if let Some(note) = self.filtered_notes.get(self.selected) {
+            note.content.text().to_string()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Where the note `name` that the previewed note embeds with `![[name]]` is read from: its
+    /// open buffer, else the file. `None` for the previewed note itself, encrypted notes and
+    /// names no note has.
+    pub fn embedded_note(&self, name: &str) -> Option<EmbedSource> {
+        let from = match self.editing_path() {
+            Some(path) if self.focus == Focus::Editor => path,
+            _ => self.filtered_notes.get(self.selected)?.path.clone(),
+        };
+        let path = self.links.resolve(name, &from)?;
+        if path == from || crypt::is_encrypted(path) {
+            return None;
+        }
+        if let Some(buf) = self
+            .buffers
+            .iter()
+            .find(|b| b.path.as_deref() == Some(path))
+        {
+            return Some(EmbedSource::Buffer(buf.textarea.lines().join("\n")));
+        }
+        Some(EmbedSource::File(path.to_path_buf()))
+    }
+
+    pub fn get_preview_placeholder(&self) -> Option<&str> {
+        if self.focus == Focus::Editor {
+            return None;
+        }
+        let note = self.filtered_notes.get(self.selected)?;
+        if !note.is_directory && is_binary_file(&note.path) {
+            Some("(Preview unavailable: binary file)")
+        } else if crypt::is_encrypted(&note.path) {
+            Some("(Preview unavailable: encrypted note)")
+        } else {
+            match note.content {
+                NoteContent::Pending => Some("(Loading preview...)"),
+                NoteContent::Unreadable => Some("(Preview unavailable: file unreadable)"),
+                NoteContent::Loaded(_) | NoteContent::Skipped => None,
+            }
+        }
+    }
+
+    /// Reload config.toml and theme.toml. A file that fails to parse keeps its previous
+    /// settings and is recorded in `config_errors`.
+    pub fn reload_config(&mut self) -> Result<()> {
+        let config_dir = crate::config::ensure_config_dir()?;
+        // Taken before reading, so a file that fails to parse is not read again until it
+        // changes once more.
+        self.config_stamps = config_stamps(&config_dir);
+        self.config_errors.clear();
+        match load_config() {
+            Ok(config) => self.config = config,
+            Err(e) => self
+                .config_errors
+                .push(ConfigError::new(config_dir.join(CONFIG_FILE_NAME), &e)),
+        }
+        self.resolved_keys = ResolvedKeys::from_config(&self.config.keys);
+        self.keymap = Keymap::from_config(&self.config.keys);
+        let mode = ThemeMode::from_config(&self.config.theme);
+        match load_theme(&config_dir, &self.config.theme.preset, mode).and_then(|raw| {
+            ResolvedTheme::resolve(&raw, Some(&self.config.theme), self.config.ui.color_mode())
+        }) {
+            Ok(theme) => {
+                self.theme = theme;
+                self.theme_preset = self.config.theme.preset.clone();
+                self.theme_mode = mode;
+            }
+            Err(e) => self
+                .config_errors
+                .push(ConfigError::new(config_dir.join(THEME_FILE_NAME), &e)),
+        }
+        self.preview_cache.borrow_mut().invalidate();
+        self.notes_dir = expand_path(&self.config.notes_directory);
+        self.ignore = vault_ignore(&self.notes_dir, &self.config);
+        let walk = vault::walk(&self.notes_dir, &self.ignore);
+        self.links = LinkGraph::open(&self.notes_dir, &walk, &self.config.zettel_id_format);
+        (self.list_width_percent, self.preview_width_percent) = self.config.ui.pane_percents();
+        self.show_preview = self.config.ui.show_preview;
+        self.tree_view = self.config.ui.tree_view;
+        self.search_case = CaseMode::new(self.config.search.smart_case);
+        if !self.current_dir.starts_with(&self.notes_dir) {
+            self.current_dir = self.notes_dir.clone();
+        }
+        self.apply_editor_theme_to_all();
+        self.spellchecker = if self.config.editor.enable_spellcheck
+            && !self.config.editor.spellcheck_languages.is_empty()
+        {
+            Some(Spellchecker::new(&self.config.editor.spellcheck_languages))
+        } else {
+            None
+        };
+        self.report_missing_dictionaries();
+        self.report_keybinding_problems();
+        self.report_theme_warnings();
+        self.report_statusline_warnings();
+        self.report_config_errors();
+        Ok(())
+    }
+
+    /// Footer warning for an unknown `theme.preset`, `theme.mode` or `ui.color_mode`.
+    fn report_theme_warnings(&mut self) {
+        if ColorMode::parse(&self.config.ui.color_mode).is_none() {
+            self.push_message(format!(
+                "Unknown ui.color_mode \"{}\" (use truecolor, 256 or 16); using truecolor",
+                self.config.ui.color_mode
+            ));
+        }
+        if Theme::preset(&self.theme_preset).is_none() {
+            self.push_message(format!(
+                "Unknown theme preset \"{}\" (available: {}); using the default theme",
+                self.theme_preset,
+                THEME_PRESETS.join(", ")
+            ));
+        }
+        if !matches!(
+            self.config.theme.mode.trim(),
+            "" | "dark" | "light" | "auto"
+        ) {
+            self.push_message(format!(
+                "Unknown theme.mode \"{}\" (use dark, light or auto); using the preset's variant",
+                self.config.theme.mode
+            ));
+        }
+    }
+
+    /// Footer warning for names in `statusline.segments` that are not segments; the footer
+    /// skips them.
+    fn report_statusline_warnings(&mut self) {
+        let unknown = self.config.statusline.unknown();
+        if unknown.is_empty() {
+            return;
+        }
+        let message = format!(
+            "Unknown statusline segment{} {} (available: {})",
+            if unknown.len() == 1 { "" } else { "s" },
+            unknown
+                .iter()
+                .map(|n| format!("\"{n}\""))
+                .collect::<Vec<_>>()
+                .join(", "),
+            StatusSegment::NAMES.join(", ")
+        );
+        self.push_message(message);
+    }
+
+    /// Switch to the next built-in color scheme for this session, in the current light or
+    /// dark variant, keeping theme.toml and config.toml overrides on top. The preset in
+    /// config.toml is not changed.
+    pub fn cycle_theme(&mut self) {
+        let schemes = THEME_PRESETS.len() / 2;
+        let next = THEME_PRESETS
+            .iter()
+            .position(|p| *p == self.theme_preset.trim())
+            .map_or(0, |i| (i / 2 + 1) % schemes);
+        let preset = preset_variant(THEME_PRESETS[next * 2], self.theme_mode);
+        match self.switch_theme(preset, self.theme_mode) {
+            Ok(()) => self.push_message(format!(
+                "Theme: {preset} (set theme.preset in config.toml to keep it)"
+            )),
+            Err(e) => self.push_message(format!("Theme {preset} failed: {e:#}")),
+        }
+    }
+
+    /// Switch between the light and dark variants of the preset and theme.toml for this
+    /// session. `theme.mode` in config.toml is not changed.
+    pub fn toggle_light_dark(&mut self) {
+        let mode = self.theme_mode.toggled();
+        let preset = preset_variant(&self.theme_preset, mode);
+        match self.switch_theme(preset, mode) {
+            Ok(()) => self.push_message(format!(
+                "Theme: {} (set theme.mode in config.toml to keep it)",
+                mode.name()
+            )),
+            Err(e) => self.push_message(format!("{} theme failed: {e:#}", mode.name())),
+        }
+    }
+
+    /// Resolve `preset` in `mode` with theme.toml and config.toml on top, and apply it to
+    /// every pane and buffer as a config reload does.
+    fn switch_theme(&mut self, preset: &str, mode: ThemeMode) -> Result<()> {
+        let raw = load_theme(&crate::config::ensure_config_dir()?, preset, mode)?;
+        self.theme =
+            ResolvedTheme::resolve(&raw, Some(&self.config.theme), self.config.ui.color_mode())?;
+        self.theme_preset = preset.to_string();
+        self.theme_mode = mode;
+        self.preview_cache.borrow_mut().invalidate();
+        self.apply_editor_theme_to_all();
+        Ok(())
+    }
+
+    /// Footer message for the first config or theme file that failed to parse.
+    fn report_config_errors(&mut self) {
+        if let Some(error) = self.config_errors.first() {
+            self.push_message(format!("{}; fix it and save to reload", error.summary));
+        }
+    }
+
+    /// Leave the startup config error popup.
+    pub fn exit_config_error(&mut self) {
+        self.focus = Focus::List;
+    }
+
+    /// Open the first broken config or theme file in the editor.
+    pub fn open_config_error_file(&mut self) {
+        let Some(path) = self.config_errors.first().map(|e| e.path.clone()) else {
+            self.exit_config_error();
+            return;
+        };
+        self.focus = Focus::List;
+        if let Err(e) = self.load_file_into_editor(path) {
+            self.report_err("Open", e);
+        }
+    }
+
+    /// Quick capture: ask for one line to add to the inbox note, from wherever focus is.
+    /// Opened from Add Task, it takes that prompt's place.
+    pub fn enter_quick_capture(&mut self) {
+        if self.focus == Focus::QuickCapture {
+            return;
+        }
+        self.capture_return_focus = self.underlying_focus();
+        self.capture_input.clear();
+        self.focus = Focus::QuickCapture;
+    }
+
+    pub fn exit_quick_capture(&mut self) {
+        self.focus = self.capture_return_focus;
+    }
+
+    pub fn capture_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.capture_input.handle_key(key);
+    }
+
+    pub fn capture_backspace(&mut self) {
+        self.capture_input.backspace();
+    }
+
+    /// Append the typed line to `capture_note` as a timestamped bullet and return to where
+    /// focus was. If the inbox is open in a buffer the line goes into that buffer (saved
+    /// with it), so unsaved edits there are neither lost nor overwritten.
+    pub fn submit_capture(&mut self) {
+        let text = self.capture_input.trim().to_string();
+        self.exit_quick_capture();
+        if text.is_empty() {
+            return;
+        }
+        let path = self.notes_dir.join(self.config.capture_note.trim());
+        let line = capture::capture_line(&text);
+        let name = path
+            .file_name()
+            .map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+        if let Some(buf) = self
+            .buffers
+            .iter_mut()
+            .find(|b| b.path.as_ref() == Some(&path))
+        {
+            buf.dirty = true;
+            let (row, col) = buf.textarea.cursor();
+            buf.textarea.move_cursor(CursorMove::Bottom);
+            buf.textarea.move_cursor(CursorMove::End);
+            if !buf.textarea.lines().last().is_some_and(String::is_empty) {
+                buf.textarea.insert_newline();
+            }
+            buf.textarea.insert_str(&line);
+            #[allow(clippy::cast_possible_truncation)]
+            buf.textarea
+                .move_cursor(CursorMove::Jump(row as u16, col as u16));
+            self.mark_editor_dirty();
+            self.push_message(format!("Captured to {name}"));
+            return;
+        }
+        match capture::append_line(&path, &line) {
+            Ok(()) => {
+                self.links.update(&path, &self.ignore);
+                if let Err(e) = self.refresh_notes() {
+                    self.report_err("Refresh", e);
+                }
+                self.push_message(format!("Captured to {name}"));
+            }
+            Err(e) => self.push_message(format!("Capture failed: {e:#}")),
+        }
+    }
+
+    /// Add Task: ask for a task to add to today's daily note, or to the note named with
+    /// `@folder/note` in front of it, from wherever focus is. Opened from Quick Capture, it
+    /// takes that prompt's place.
+    pub fn enter_add_task(&mut self) {
+        if self.focus == Focus::AddTask {
+            return;
+        }
+        self.task_return_focus = self.underlying_focus();
+        self.task_input.clear();
+        self.focus = Focus::AddTask;
+    }
+
+    pub fn exit_add_task(&mut self) {
+        self.focus = self.task_return_focus;
+    }
+
+    /// Focus the screen is drawn for: Quick Capture and Add Task are prompts drawn over the
+    /// view they were opened from, and return to it. Any other popup replaces the one open.
+    pub fn underlying_focus(&self) -> Focus {
+        match self.focus {
+            Focus::QuickCapture => self.capture_return_focus,
+            Focus::AddTask => self.task_return_focus,
+            focus => focus,
+        }
+    }
+
+    pub fn task_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.task_input.handle_key(key);
+    }
+
+    pub fn task_backspace(&mut self) {
+        self.task_input.backspace();
+    }
+
+    /// Add the typed task as `- [ ] task` under `task_heading` and return to where focus
+    /// was. As with quick capture, a note open in a buffer gets the task in the buffer.
+    pub fn submit_task(&mut self) {
+        let input = self.task_input.trim().to_string();
+        self.exit_add_task();
+        let (target, text) = match input.strip_prefix('@') {
+            Some(rest) => {
+                let (target, text) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                (Some(target), text.trim())
+            }
+            None => (None, input.as_str()),
+        };
+        if text.is_empty() {
+            return;
+        }
+        let path = match target {
+            Some(target) => self.task_note(target),
+            None => match self.todays_daily_note() {
+                Ok(path) => path,
+                Err(e) => return self.report_err("Add task", e),
+            },
+        };
+        let line = format!("- [ ] {text}");
+        let heading = self.config.task_heading.trim().to_string();
+        let name = path
+            .file_name()
+            .map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+        if let Some(buf) = self
+            .buffers
+            .iter_mut()
+            .find(|b| b.path.as_ref() == Some(&path))
+        {
+            buf.dirty = true;
+            let (row, col) = buf.textarea.cursor();
+            let (at, insert) = capture::under_heading(buf.textarea.lines(), &heading, &line);
+            #[allow(clippy::cast_possible_truncation)]
+            if at < buf.textarea.lines().len() {
+                buf.textarea.move_cursor(CursorMove::Jump(at as u16, 0));
+                buf.textarea.insert_str(insert.join("\n") + "\n");
+            } else {
+                buf.textarea.move_cursor(CursorMove::Bottom);
+                buf.textarea.move_cursor(CursorMove::End);
+                buf.textarea.insert_newline();
+                buf.textarea.insert_str(insert.join("\n"));
+            }
+            let row = if row >= at { row + insert.len() } else { row };
+            #[allow(clippy::cast_possible_truncation)]
+            buf.textarea
+                .move_cursor(CursorMove::Jump(row as u16, col as u16));
+            self.mark_editor_dirty();
+        } else {
+            let created = !path.exists();
+            if let Err(e) = capture::insert_under_heading(&path, &heading, &line) {
+                return self.push_message(format!("Add task failed: {e:#}"));
+            }
+            self.links.update(&path, &self.ignore);
+            if created {
+                if let Err(e) = self.refresh_notes() {
+                    self.report_err("Refresh", e);
+                }
+            }
+        }
+        self.push_message(format!("Added task to {name}"));
+        if self.focus == Focus::TaskView {
+            // The board reads the notes from disk.
+            if let Err(e) = self.save_edited_buffers() {
+                self.report_err("Save", e);
+            }
+            self.rescan_tasks();
+        }
+    }
+
+    /// Note that `@target` names for Add Task: `target` from the vault root, else the note
+    /// a `[[target]]` link would open, else a new note at `target`.
+    fn task_note(&self, target: &str) -> PathBuf {
+        let file = if target.ends_with(".md") {
+            target.to_string()
+        } else {
+            format!("{target}.md")
+        };
+        let path = self.notes_dir.join(file);
+        if path.exists() {
+            return path;
+        }
+        self.links
+            .resolve(target, &path)
+            .map_or(path, Path::to_path_buf)
+    }
+
+    /// Open Random Note: ask for a tag and scope, keeping the last ones so Enter repeats.
+    pub fn enter_random_note(&mut self) {
+        self.focus = Focus::RandomNote;
+    }
+
+    pub fn exit_random_note(&mut self) {
+        self.exit_command_palette();
+    }
+
+    pub fn random_tag_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.random_tag.handle_key(key);
+    }
+
+    pub fn random_tag_backspace(&mut self) {
+        self.random_tag.backspace();
+    }
+
+    pub fn toggle_random_scope(&mut self) {
+        self.random_in_current_dir = !self.random_in_current_dir;
+    }
+
+    /// Open a uniformly random note from the link graph's notes in the chosen scope, with
+    /// the tag if one was typed. The note opened last time is skipped unless it is the only
+    /// candidate.
+    pub fn open_random_note(&mut self) {
+        let tag = self
+            .random_tag
+            .trim()
+            .trim_start_matches('#')
+            .to_lowercase();
+        let mut candidates: Vec<PathBuf> = self
+            .links
+            .notes()
+            .filter(|p| !self.random_in_current_dir || p.starts_with(&self.current_dir))
+            .filter(|p| tag.is_empty() || note_has_tag(p, &tag))
+            .map(Path::to_path_buf)
+            .collect();
+        if candidates.len() > 1 {
+            if let Some(last) = &self.random_last {
+                candidates.retain(|p| p != last);
+            }
+        }
+        if candidates.is_empty() {
+            self.push_message(if tag.is_empty() {
+                "No notes to pick from".to_string()
+            } else {
+                format!("No notes tagged #{tag}")
+            });
+            return;
+        }
+        let path = candidates.swap_remove(self.rng.below(candidates.len()));
+        self.random_last = Some(path.clone());
+        self.exit_random_note();
+        if let Err(e) = self.load_file_into_editor(path) {
+            self.push_message(format!("Cannot open note: {e}"));
+        }
+    }
+
+    /// Open or create today's daily note and switch editor to it.
+    pub fn open_daily_note(&mut self) -> Result<()> {
+        let path = self.todays_daily_note()?;
+        self.load_file_into_editor(path)
+    }
+
+    /// Today's daily note, created with its title if missing.
+    fn todays_daily_note(&self) -> Result<PathBuf> {
+        let date = Local::now().format(DAILY_NOTE_DATE_FORMAT).to_string();
+        let folder = self.notes_dir.join(self.config.daily_notes_folder.trim());
+        fs::create_dir_all(&folder)?;
+        let path = folder.join(format!("{date}.md"));
+        if !path.exists() {
+            let header = format!("# Daily Note: {date}\n\n");
+            fs::write(&path, header)?;
+        }
+        Ok(path)
+    }
+
+    /// Open a file from the explorer or telescope. Text files go to the editor; binary files
+    /// are handed to `ui.external_opener` or reported in the footer.
+    pub fn open_file(&mut self, path: PathBuf) -> Result<()> {
+        if !is_binary_file(&path) && !sniffs_binary(&path) {
+            return self.load_file_into_editor(path);
+        }
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string();
+        let opener = self.config.ui.external_opener.trim().to_string();
+        if opener.is_empty() {
+            self.push_message(format!("Cannot edit binary file: {name}"));
+            return Ok(());
+        }
+        let spawned = Command::new(&opener)
+            .arg(&path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        self.push_message(match spawned {
+            Ok(_) => format!("Opened {name} with {opener}"),
+            Err(e) => format!("Failed to launch {opener}: {e}"),
+        });
+        Ok(())
+    }
+
+    /// Load file content into a new or existing tab and switch focus to Editor.
+    pub fn load_file_into_editor(&mut self, path: PathBuf) -> Result<()> {
+        self.load_file_into_editor_at_line(path, None)
+    }
+
+    /// Load file and optionally move cursor to the given 0-based line.
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn load_file_into_editor_at_line(
+        &mut self,
+        path: PathBuf,
+        goto_line: Option<usize>,
+    ) -> Result<()> {
+        if let Err(e) = self.recent.record(&path) {
+            self.push_message(format!("Could not update recent files: {e}"));
+        }
+        // Check if already open
+        if let Some(idx) = self
+            .buffers
+            .iter()
+            .position(|b| b.path.as_ref() == Some(&path))
+        {
+            self.active_tab = idx;
+            self.focus = Focus::Editor;
+            self.editor_mode = EditorMode::Normal;
+            if let Some(line) = goto_line {
+                if let Some(buf) = self.buffers.get_mut(idx) {
+                    let row = line.min(buf.textarea.lines().len().saturating_sub(1));
+                    #[allow(clippy::cast_possible_truncation)]
+                    #[allow(clippy::cast_possible_truncation)]
+                    buf.textarea.move_cursor(CursorMove::Jump(row as u16, 0));
+                }
+            }
+            return Ok(());
+        }
+        let content = if crypt::is_encrypted(&path) {
+            // No buffer at all on failure: an empty one would be encrypted over the note.
+            match crypt::decrypt(&path, &self.config.encryption) {
+                Ok(content) => content,
+                Err(e) => {
+                    self.push_message(format!("Cannot decrypt {}: {e:#}", path.display()));
+                    return Ok(());
+                }
+            }
+        } else {
+            // Likewise: an empty buffer would be saved over the file.
+            let bytes = match fs::read(&path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    self.report_err(
+                        "Open",
+                        anyhow::anyhow!("Cannot read {}: {e}", path.display()),
+                    );
+                    return Ok(());
+                }
+            };
+            if has_binary_content(&bytes) {
+                self.push_message(format!("Cannot edit binary file: {}", path.display()));
+                return Ok(());
+            }
+            match String::from_utf8(bytes) {
+                Ok(content) => content,
+                Err(e) => {
+                    let e = anyhow::anyhow!("{} is not UTF-8 text: {e}", path.display());
+                    self.report_err("Open", e);
+                    return Ok(());
+                }
+            }
+        };
+        let lines: Vec<String> = if content.is_empty() {
+            vec![String::new()]
+        } else {
+            content
+                .lines()
+                .map(std::string::ToString::to_string)
+                .collect()
+        };
+        let mut buf = EditorBuffer::new(Some(path), lines);
+        buf.textarea.set_max_histories(50);
+        if let Some(line) = goto_line {
+            let row = line.min(buf.textarea.lines().len().saturating_sub(1));
+            buf.textarea.move_cursor(CursorMove::Jump(row as u16, 0));
+        }
+        Self::apply_theme_to_textarea(&self.theme, &mut buf.textarea, &self.config.editor);
+        self.buffers.push(buf);
+        self.active_tab = self.buffers.len() - 1;
+        self.focus = Focus::Editor;
+        self.editor_mode = EditorMode::Normal;
+        if self.config.editor.show_backlinks {
+            self.scan_backlinks();
+        }
+        Ok(())
+    }
+
+    /// Switch focus back to List. Auto-saves before switching.
+    pub fn focus_list(&mut self) {
+        if let Err(e) = self.save_all_buffers() {
+            self.report_err("Save", e);
+        }
+        self.focus = Focus::List;
+    }
+
+    /// Entries an operation applies to: all marked entries, or the highlighted one.
+    pub fn operation_targets(&self) -> Vec<NoteEntry> {
+        if self.marked.is_empty() {
+            return self
+                .filtered_notes
+                .get(self.selected)
+                .cloned()
+                .into_iter()
+                .collect();
+        }
+        self.all_notes
+            .iter()
+            .filter(|e| self.marked.contains(&e.path))
+            .cloned()
+            .collect()
+    }
+
+    /// Toggle the multi-select mark on the highlighted entry.
+    pub fn toggle_mark(&mut self) {
+        let Some(path) = self.get_selected_path() else {
+            return;
+        };
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// Enter delete confirmation. Shows N/y prompt.
+    pub fn enter_delete_confirm(&mut self) {
+        let entries = self.operation_targets();
+        if entries.is_empty() {
+            return;
+        }
+        if entries.iter().any(|e| {
+            !e.is_directory && (e.path.ends_with("config.toml") || e.path.ends_with("theme.toml"))
+        }) {
+            self.push_message("Cannot delete config files".to_string());
+            return;
+        }
+        self.delete_pending = entries;
+        self.focus = Focus::DeleteConfirm;
+    }
+
+    /// Cancel delete confirmation.
+    pub fn exit_delete_confirm(&mut self) {
+        self.delete_pending.clear();
+        self.focus = Focus::List;
+    }
+
+    /// Perform delete after user confirmed with y.
+    pub fn confirm_delete(&mut self) -> Result<()> {
+        let entries = std::mem::take(&mut self.delete_pending);
+        if entries.is_empty() {
+            return Ok(());
+        }
+        self.focus = Focus::List;
+
+        // Stop at the first failure, but keep the buffers of everything not deleted and
+        // leave the app consistent for what was.
+        let mut failure = None;
+        for entry in &entries {
+            let path = &entry.path;
+            let removed = if entry.is_directory {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_file(path)
+            };
+            if let Err(e) = removed {
+                failure = Some(anyhow::anyhow!("Cannot delete {}: {e}", path.display()));
+                break;
+            }
+            // A file's path starts with itself, so this covers files and folders alike.
+            self.retain_buffers(|b| b.path.as_ref().is_none_or(|p| !p.starts_with(path)));
+            self.closed_tabs.retain(|(p, _)| !p.starts_with(path));
+            self.links.update(path, &self.ignore);
+        }
+        self.marked.clear();
+        if self.buffers.is_empty() {
+            self.buffers
+                .push(EditorBuffer::new(None, vec![String::new()]));
+            self.active_tab = 0;
+            self.focus = Focus::List;
+            self.apply_editor_theme_to_all();
+        }
+        self.refresh_notes()?;
+        if let Some(e) = failure {
+            return Err(e);
+        }
+
+        self.push_message(if entries.len() == 1 {
+            "Deleted".to_string()
+        } else {
+            format!("Deleted {} entries", entries.len())
+        });
+        Ok(())
+    }
+
+    /// Show the first problem a vault walk ran into (symlink loop, nesting too deep).
+    fn report_walk_warnings(&mut self, walk: &Walk) {
+        let Some(first) = walk.warnings.first() else {
+            return;
+        };
+        self.push_message(match walk.warnings.len() {
+            1 => first.clone(),
+            n => format!("{first} (and {} more)", n - 1),
+        });
+    }
+
+    /// Open the move picker for the selected (or marked) entries, listing every folder
+    /// under notes_dir.
+    pub fn enter_move_picker(&mut self) {
+        let entries = self.operation_targets();
+        if entries.is_empty() {
+            return;
+        }
+        let walk = vault::walk(&self.notes_dir, &self.ignore);
+        self.report_walk_warnings(&walk);
+        let mut targets: Vec<PathBuf> = std::iter::once(self.notes_dir.clone())
+            .chain(
+                walk.entries
+                    .into_iter()
+                    .filter(|e| e.file_type().is_dir())
+                    .map(walkdir::DirEntry::into_path),
+            )
+            .filter(|p| {
+                let hidden = p.strip_prefix(&self.notes_dir).is_ok_and(|rel| {
+                    rel.components()
+                        .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+                });
+                let inside_moved = entries
+                    .iter()
+                    .any(|e| e.is_directory && p.starts_with(&e.path));
+                !hidden && !inside_moved
+            })
+            .collect();
+        targets.sort();
+        self.move_target_selected = targets
+            .iter()
+            .position(|p| *p == self.current_dir)
+            .unwrap_or(0);
+        self.move_targets = targets;
+        self.move_pending = entries;
+        self.focus = Focus::MovePicker;
+    }
+
+    pub fn exit_move_picker(&mut self) {
+        self.move_pending.clear();
+        self.move_targets.clear();
+        self.focus = Focus::List;
+    }
+
+    pub fn move_picker_move_up(&mut self) {
+        if self.move_target_selected > 0 {
+            self.move_target_selected -= 1;
+        }
+    }
+
+    pub fn move_picker_move_down(&mut self) {
+        if self.move_target_selected + 1 < self.move_targets.len() {
+            self.move_target_selected += 1;
+        }
+    }
+
+    /// Move the pending entries into the selected directory. Aborts without moving
+    /// anything if a name is already taken in the target.
+    pub fn confirm_move(&mut self) -> Result<()> {
+        let Some(target_dir) = self.move_targets.get(self.move_target_selected).cloned() else {
+            self.exit_move_picker();
+            return Ok(());
+        };
+        let entries = std::mem::take(&mut self.move_pending);
+        let mut moves = Vec::new();
+        for entry in &entries {
+            let Some(file_name) = entry.path.file_name() else {
+                continue;
+            };
+            let new_path = target_dir.join(file_name);
+            if new_path == entry.path {
+                continue;
+            }
+            if new_path.exists() {
+                self.push_message(format!(
+                    "Cannot move: {} already exists",
+                    new_path
+                        .strip_prefix(&self.notes_dir)
+                        .unwrap_or(&new_path)
+                        .display()
+                ));
+                self.exit_move_picker();
+                return Ok(());
+            }
+            moves.push((entry.path.clone(), new_path));
+        }
+        self.exit_move_picker();
+        if moves.is_empty() {
+            return Ok(());
+        }
+        if let Err(e) = self.save_all_buffers() {
+            self.report_err("Save", e);
+        }
+        for (old_path, new_path) in &moves {
+            fs::rename(old_path, new_path)?;
+            self.retarget_paths(old_path, new_path);
+            self.links.update(old_path, &self.ignore);
+            self.links.update(new_path, &self.ignore);
+        }
+        self.marked.clear();
+        self.refresh_notes()?;
+        let rel = target_dir.strip_prefix(&self.notes_dir).map_or_else(
+            |_| target_dir.display().to_string(),
+            |p| format!("/{}", p.display()),
+        );
+        self.push_message(if moves.len() == 1 {
+            format!("Moved to {rel}")
+        } else {
+            format!("Moved {} entries to {rel}", moves.len())
+        });
+        Ok(())
+    }
+
+    /// Ask for a tag to add to the selected (or marked) notes. Folders and encrypted notes
+    /// among them are left out.
+    pub fn enter_tag_prompt(&mut self) {
+        let notes: Vec<PathBuf> = self
+            .operation_targets()
+            .into_iter()
+            .filter(|e| {
+                !e.is_directory && is_markdown_path(&e.path) && !crypt::is_encrypted(&e.path)
+            })
+            .map(|e| e.path)
+            .collect();
+        if notes.is_empty() {
+            self.push_message("No notes to tag".to_string());
+            return;
+        }
+        self.tag_input.clear();
+        self.tag_pending = notes;
+        self.focus = Focus::TagPrompt;
+    }
+
+    pub fn exit_tag_prompt(&mut self) {
+        self.tag_pending.clear();
+        self.tag_input.clear();
+        self.focus = Focus::List;
+    }
+
+    pub fn tag_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.tag_input.handle_key(key);
+    }
+
+    pub fn tag_backspace(&mut self) {
+        self.tag_input.backspace();
+    }
+
+    /// Add the typed tag to the frontmatter of each pending note that does not have it yet,
+    /// and reload the open ones.
+    pub fn confirm_tag(&mut self) -> Result<()> {
+        let tag = self.tag_input.trim().trim_start_matches('#').to_string();
+        if tag.is_empty() || !tag.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            self.push_message(format!("Not a tag: #{tag}"));
+            return Ok(());
+        }
+        let notes = std::mem::take(&mut self.tag_pending);
+        self.exit_tag_prompt();
+        if let Err(e) = self.save_all_buffers() {
+            self.report_err("Save", e);
+        }
+        let mut tagged = 0;
+        for path in &notes {
+            if note_has_tag(path, &tag.to_lowercase()) {
+                continue;
+            }
+            let content = fs::read_to_string(path)?;
+            fs::write(path, frontmatter::with_tag(&content, &tag))?;
+            self.reload_buffer_from_disk(path);
+            tagged += 1;
+        }
+        self.editor_dirty = false;
+        self.marked.clear();
+        self.refresh_notes()?;
+        self.push_message(match (tagged, notes.len() - tagged) {
+            (0, _) => format!("Already tagged #{tag}"),
+            (1, 0) => format!("Tagged #{tag}"),
+            (n, 0) => format!("Tagged {n} notes #{tag}"),
+            (n, skipped) => format!("Tagged {n} notes #{tag} ({skipped} already were)"),
+        });
+        Ok(())
+    }
+
+    /// Where `path` goes when (un)archived: `archive/<path>` for a note outside the archive,
+    /// and back to its original place for one inside it. `None` outside the vault and for the
+    /// archive folder itself.
+    fn archive_destination(&self, path: &Path) -> Option<PathBuf> {
+        let rel = path.strip_prefix(&self.notes_dir).ok()?;
+        match rel.strip_prefix(ARCHIVE_FOLDER) {
+            Ok(original) if original.as_os_str().is_empty() => None,
+            Ok(original) => Some(self.notes_dir.join(original)),
+            Err(_) if rel.as_os_str().is_empty() => None,
+            Err(_) => Some(self.notes_dir.join(ARCHIVE_FOLDER).join(rel)),
+        }
+    }
+
+    fn is_archived(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.notes_dir)
+            .is_ok_and(|rel| rel.starts_with(ARCHIVE_FOLDER))
+    }
+
+    /// The focused note when the editor has focus, else the marked or selected entries.
+    fn archive_targets(&self) -> Vec<PathBuf> {
+        if self.focus == Focus::Editor {
+            if let Some(path) = self.focused_buffer().and_then(|b| b.path.clone()) {
+                return vec![path];
+            }
+        }
+        self.operation_targets()
+            .into_iter()
+            .map(|e| e.path)
+            .collect()
+    }
+
+    /// Archive the targets, or unarchive those already in the archive (list key `a`).
+    pub fn toggle_archive(&mut self) -> Result<()> {
+        let targets = self.archive_targets();
+        self.move_archive(targets)
+    }
+
+    /// Move the targets into `archive/`, keeping their path below the vault root.
+    pub fn archive(&mut self) -> Result<()> {
+        let (archived, targets): (Vec<_>, Vec<_>) = self
+            .archive_targets()
+            .into_iter()
+            .partition(|p| self.is_archived(p));
+        if targets.is_empty() && !archived.is_empty() {
+            self.push_message("Already archived".to_string());
+            return Ok(());
+        }
+        self.move_archive(targets)
+    }
+
+    /// Move archived targets back to where they were before archiving.
+    pub fn unarchive(&mut self) -> Result<()> {
+        let targets: Vec<PathBuf> = self
+            .archive_targets()
+            .into_iter()
+            .filter(|p| self.is_archived(p))
+            .collect();
+        if targets.is_empty() {
+            self.push_message(format!("Not in {ARCHIVE_FOLDER}/"));
+            return Ok(());
+        }
+        self.move_archive(targets)
+    }
+
+    fn move_archive(&mut self, paths: Vec<PathBuf>) -> Result<()> {
+        let mut moves = Vec::new();
+        for path in paths {
+            let Some(new_path) = self.archive_destination(&path) else {
+                continue;
+            };
+            if new_path.exists() {
+                self.push_message(format!(
+                    "Cannot move: {} already exists",
+                    new_path
+                        .strip_prefix(&self.notes_dir)
+                        .unwrap_or(&new_path)
+                        .display()
+                ));
+                return Ok(());
+            }
+            moves.push((path, new_path));
+        }
+        if moves.is_empty() {
+            return Ok(());
+        }
+        if let Err(e) = self.save_all_buffers() {
+            self.report_err("Save", e);
+        }
+        let mut archived = 0;
+        for (old_path, new_path) in &moves {
+            if let Some(dir) = new_path.parent() {
+                vault::create_dirs(&self.notes_dir, dir)?;
+            }
+            fs::rename(old_path, new_path)?;
+            self.retarget_paths(old_path, new_path);
+            self.links.update(old_path, &self.ignore);
+            self.links.update(new_path, &self.ignore);
+            if self.is_archived(new_path) {
+                archived += 1;
+            }
+        }
+        self.marked.clear();
+        self.refresh_notes()?;
+        let first = moves[0]
+            .1
+            .strip_prefix(&self.notes_dir)
+            .unwrap_or(&moves[0].1);
+        self.push_message(match (archived, moves.len() - archived) {
+            (1, 0) => format!("Archived to {}", first.display()),
+            (0, 1) => format!("Restored to {}", first.display()),
+            (n, 0) => format!("Archived {n} entries"),
+            (0, n) => format!("Restored {n} entries"),
+            (a, r) => format!("Archived {a}, restored {r} entries"),
+        });
+        Ok(())
+    }
+
+    /// Duplicate the selected entry. Files are copied right away; directories ask first.
+    pub fn duplicate_selected(&mut self) -> Result<()> {
+        let entry = match self.filtered_notes.get(self.selected) {
+            Some(e) => e.clone(),
+            None => return Ok(()),
+        };
+        if entry.is_directory {
+            self.duplicate_pending = Some(entry);
+            self.focus = Focus::DuplicateConfirm;
+            return Ok(());
+        }
+        self.duplicate_entry(&entry)
+    }
+
+    /// Cancel directory duplicate confirmation.
+    pub fn exit_duplicate_confirm(&mut self) {
+        self.duplicate_pending = None;
+        self.focus = Focus::List;
+    }
+
+    /// Copy the directory after user confirmed with y.
+    pub fn confirm_duplicate(&mut self) -> Result<()> {
+        let Some(entry) = self.duplicate_pending.take() else {
+            return Ok(());
+        };
+        self.focus = Focus::List;
+        self.duplicate_entry(&entry)
+    }
+
+    fn duplicate_entry(&mut self, entry: &NoteEntry) -> Result<()> {
+        if let Err(e) = self.save_all_buffers() {
+            self.report_err("Save", e);
+        }
+        let new_path = duplicate_path(&entry.path);
+        if entry.is_directory {
+            copy_dir_recursive(&entry.path, &new_path)?;
+        } else {
+            fs::copy(&entry.path, &new_path)?;
+        }
+        self.links.update(&new_path, &self.ignore);
+        self.refresh_notes()?;
+        self.select_path(&new_path);
+        let name = new_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string();
+        self.push_message(format!("Duplicated as {name}"));
+        Ok(())
+    }
+
+    /// Point open buffers, closed tabs, expanded tree folders and the folder being listed at
+    /// `new` after `old` (file or directory) was moved on disk. The split keeps its buffer,
+    /// which it refers to by id.
+    fn retarget_paths(&mut self, old: &Path, new: &Path) {
+        let retarget = |path: &mut PathBuf| {
+            if let Ok(rest) = path.strip_prefix(old) {
+                *path = new.join(rest);
+            }
+        };
+        for buf in &mut self.buffers {
+            if let Some(path) = buf.path.as_mut() {
+                retarget(path);
+            }
+        }
+        for (path, _) in &mut self.closed_tabs {
+            retarget(path);
+        }
+        retarget(&mut self.current_dir);
+        if let Some(path) = self.search_origin.as_mut() {
+            retarget(path);
+        }
+        self.expanded = std::mem::take(&mut self.expanded)
+            .into_iter()
+            .map(|mut path| {
+                retarget(&mut path);
+                path
+            })
+            .collect();
+    }
+
+    /// Save all buffers to disk (no user message).
+    pub fn save_all_buffers(&mut self) -> Result<()> {
+        let (_, need_reload) = self.write_buffers(false)?;
+        self.editor_dirty = false;
+        if need_reload {
+            if let Err(e) = self.reload_config() {
+                self.report_err("Reload config", e);
+            }
+        }
+        self.refresh_notes()?;
+        Ok(())
+    }
+
+    /// Save the buffers edited since they were last saved, without getting in the way of
+    /// typing: the file list is only re-read when a new file was written, and a written
+    /// config file is only applied once it is no longer being edited.
+    fn auto_save_buffers(&mut self) -> Result<()> {
+        let (created, config_written) = self.write_buffers(true)?;
+        self.editor_dirty = false;
+        self.config_reload_pending |= config_written;
+        if created {
+            self.refresh_notes()?;
+        }
+        Ok(())
+    }
+
+    /// Write buffers with a file to disk, only the edited ones with `only_dirty`. Returns
+    /// whether a new file was created and whether a config file was written.
+    fn write_buffers(&mut self, only_dirty: bool) -> Result<(bool, bool)> {
+        let (mut created, mut config_written) = (false, false);
+        let _saving = self
+            .save_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for buf in &mut self.buffers {
+            let Some(path) = &buf.path else { continue };
+            if only_dirty && !buf.dirty {
+                continue;
+            }
+            created |= !path.exists();
+            let content = buf.textarea.lines().join("\n");
+            if crypt::is_encrypted(path) {
+                crypt::encrypt(path, &content, &self.config.encryption)?;
+            } else {
                 fs::write(path, content)?;
-                if path.ends_with("config.toml") || path.ends_with("theme.toml") {
-                    need_reload = true;
+            }
+            buf.dirty = false;
+            self.links.update(path, &self.ignore);
+            config_written |= is_config_file(path);
+        }
+        Ok((created, config_written))
+    }
+
+    /// Save the buffers edited since they were last saved, and nothing else, e.g. before
+    /// exiting on a signal.
+    pub fn save_edited_buffers(&mut self) -> Result<()> {
+        self.write_buffers(true)?;
+        self.editor_dirty = false;
+        Ok(())
+    }
+
+    /// Reload config.toml and theme.toml when they changed outside oxid, such as a theme
+    /// swapped by a light/dark switcher. Pending keys and other state of the moment are kept.
+    fn reload_changed_config(&mut self) {
+        let Ok(config_dir) = crate::config::ensure_config_dir() else {
+            return;
+        };
+        if config_stamps(&config_dir) == self.config_stamps {
+            return;
+        }
+        match self.reload_config() {
+            Ok(()) if !self.config_errors.is_empty() => {}
+            Ok(()) => self.push_message("Config reloaded".to_string()),
+            Err(e) => self.report_err("Reload config", e),
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Apply a config file auto-save wrote once its buffer is saved and no longer focused.
+    fn apply_pending_config_reload(&mut self) {
+        if !self.config_reload_pending {
+            return;
+        }
+        let editing =
+            self.focus == Focus::Editor && self.editing_path().is_some_and(|p| is_config_file(&p));
+        let unsaved = self
+            .buffers
+            .iter()
+            .any(|b| b.dirty && b.path.as_deref().is_some_and(is_config_file));
+        if editing || unsaved {
+            return;
+        }
+        self.config_reload_pending = false;
+        if let Err(e) = self.reload_config() {
+            self.report_err("Reload config", e);
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Focus and focused buffer, to notice a switch to another pane or tab.
+    pub fn focus_target(&self) -> (Focus, Option<u64>) {
+        (self.focus, self.focused_buffer().map(|b| b.id))
+    }
+
+    /// With `editor.auto_save_on_focus_change`, save edited buffers when focus leaves the
+    /// editor or moves to another tab or pane, whatever the auto-save timer says.
+    pub fn save_on_focus_change(&mut self, before: (Focus, Option<u64>)) {
+        if !self.config.editor.auto_save_on_focus_change
+            || !self.editor_dirty
+            || before.0 != Focus::Editor
+            || self.focus_target() == before
+        {
+            return;
+        }
+        match self.auto_save_buffers() {
+            Ok(()) => self.save_indicator_until = Some(Instant::now() + Duration::from_secs(2)),
+            Err(e) => self.report_err("Auto-save", e),
+        }
+    }
+
+    /// Mark that the editor content has changed (for auto-save tracking).
+    pub fn mark_editor_dirty(&mut self) {
+        self.editor_dirty = true;
+        self.last_keystroke_time = Some(Instant::now());
+        for buf in &mut self.buffers {
+            buf.joined_undos = 0;
+        }
+        if let Some(buf) = self.focused_buffer_mut() {
+            buf.dirty = true;
+        }
+    }
+
+    /// `x`: delete up to `count` characters from the cursor, within the line.
+    pub fn delete_chars_under_cursor(&mut self, count: usize) {
+        let Some(buf) = self.focused_buffer_mut() else {
+            return;
+        };
+        let (row, col) = buf.textarea.cursor();
+        let count = count.min(buf.line_len(row).saturating_sub(col));
+        if count == 0 || !buf.textarea.delete_str(count) {
+            return;
+        }
+        buf.clamp_to_line();
+        self.mark_editor_dirty();
+    }
+
+    /// `r<char>` and `~`: replace up to `count` characters from the cursor with
+    /// `with(replaced)` as one undo step. `~` then moves on to the next character.
+    fn replace_chars_under_cursor(
+        &mut self,
+        count: usize,
+        with: impl FnOnce(&str) -> String,
+        advance: bool,
+    ) {
+        let Some(buf) = self.focused_buffer_mut() else {
+            return;
+        };
+        if !buf.replace_chars(count, with) {
+            return;
+        }
+        if advance {
+            buf.textarea.move_cursor(CursorMove::Forward);
+            buf.clamp_to_line();
+        }
+        let joined = buf.joined_undos + 1;
+        self.mark_editor_dirty();
+        if let Some(buf) = self.focused_buffer_mut() {
+            buf.joined_undos = joined;
+        }
+    }
+
+    /// Check auto-save condition and save if needed. Returns true if a save was performed.
+    pub fn check_auto_save(&mut self) -> Result<bool> {
+        if !self.config.editor.auto_save || !self.editor_dirty {
+            return Ok(false);
+        }
+        let Some(last) = self.last_keystroke_time else {
+            return Ok(false);
+        };
+        let interval = Duration::from_secs(self.config.editor.auto_save_interval);
+        if Instant::now().duration_since(last) < interval {
+            return Ok(false);
+        }
+        if let Err(e) = self.auto_save_buffers() {
+            // Try again after another interval instead of on every tick.
+            self.last_keystroke_time = Some(Instant::now());
+            return Err(e);
+        }
+        self.save_indicator_until = Some(Instant::now() + Duration::from_secs(2));
+        self.needs_redraw = true;
+        Ok(true)
+    }
+
+    /// Clear the "Saved..." indicator and the footer message once they expire, asking for a
+    /// redraw to take them off the screen.
+    pub fn tick_timers(&mut self) {
+        let now = Instant::now();
+        for until in [&mut self.save_indicator_until, &mut self.message_until] {
+            if until.is_some_and(|t| now >= t) {
+                *until = None;
+                self.needs_redraw = true;
+            }
+        }
+        self.apply_pending_config_reload();
+        if !self.config_reload_pending
+            && self
+                .config_checked
+                .is_none_or(|t| now.duration_since(t) >= CONFIG_CHECK_INTERVAL)
+        {
+            self.config_checked = Some(now);
+            self.reload_changed_config();
+        }
+        self.start_backup_if_due(now);
+        // Changes made outside oxid (a commit in another terminal) show up after a while.
+        if self
+            .git_checked
+            .is_none_or(|t| now.duration_since(t) >= GIT_REFRESH_INTERVAL)
+        {
+            let status = get_git_status(&self.notes_dir, self.config.git.use_binary);
+            if status != self.git {
+                self.git = status;
+                self.needs_redraw = true;
+            }
+            self.git_checked = Some(now);
+        }
+    }
+
+    /// Save the current editor content to disk.
+    pub fn save_editor(&mut self) -> Result<()> {
+        self.save_all_buffers()
+    }
+
+    fn apply_theme_to_textarea(
+        theme: &ResolvedTheme,
+        textarea: &mut TextArea<'static>,
+        editor_config: &crate::config::EditorConfig,
+    ) {
+        let editor_style = theme.editor_fg_style.patch(theme.editor_bg_style);
+        textarea.set_style(editor_style);
+        textarea.set_cursor_style(theme.editor_cursor_style);
+        textarea.set_selection_style(theme.editor_selection_style);
+        textarea.set_cursor_line_style(
+            ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::UNDERLINED),
+        );
+        if editor_config.line_numbers {
+            textarea.set_line_number_style(theme.editor_line_number_style);
+        } else {
+            textarea.remove_line_number();
+        }
+        let tab_len = editor_config.tab_width.clamp(1, 16);
+        textarea.set_tab_length(tab_len);
+        // Headers (# ), list markers (- ), unchecked (- [ ]), checked (- [x]), code blocks (```).
+        // The textarea supports a single search pattern and style, so heading levels are not
+        // distinguished here; per-level colors apply to the preview only.
+        let _ = textarea
+            .set_search_pattern(r"(^#{1,6} )|(^[-*] )|(^[-*] \[ \])|(^[-*] \[[xX]\])|(^```)");
+        textarea.set_search_style(
+            theme
+                .editor_header_style
+                .patch(theme.editor_list_style)
+                .patch(theme.editor_checkbox_style)
+                .patch(theme.editor_checkbox_checked_style)
+                .patch(theme.editor_code_block_style),
+        );
+    }
+
+    fn apply_editor_theme_to_all(&mut self) {
+        for buf in self.buffers.iter_mut() {
+            Self::apply_theme_to_textarea(&self.theme, &mut buf.textarea, &self.config.editor);
+        }
+    }
+
+    /// Handle editor input in Normal mode (vim-like).
+    pub fn editor_normal_input(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        use crossterm::event::{KeyCode, KeyModifiers};
+        let soft_wrap = self.config.editor.soft_wrap;
+        let smart_indent = self.config.editor.smart_indent;
+        if let Some(count) = self.replace_pending.take() {
+            // Esc, or any other non-character key, cancels the `r`.
+            if let KeyCode::Char(c) = key.code {
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                {
+                    let with = |replaced: &str| c.to_string().repeat(replaced.chars().count());
+                    self.replace_chars_under_cursor(count, with, false);
+                }
+            }
+            return true;
+        }
+        let count = self.take_count();
+        if key_matches(key, &[self.resolved_keys.escape]) {
+            self.editor_mode = EditorMode::Normal;
+            return true;
+        }
+        if key_matches(key, &[self.resolved_keys.editor_back]) {
+            self.focus_list();
+            return true;
+        }
+        if key_matches(key, &[self.resolved_keys.editor_insert]) {
+            self.editor_mode = EditorMode::Insert;
+            return true;
+        }
+        if key_matches(key, &[self.resolved_keys.editor_append]) {
+            if let Some(buf) = self.focused_buffer_mut() {
+                buf.textarea.move_cursor(CursorMove::Forward);
+            }
+            self.editor_mode = EditorMode::Insert;
+            return true;
+        }
+        if key_matches(key, &[self.resolved_keys.editor_open_below]) {
+            self.open_line(true);
+            return true;
+        }
+        if key_matches(key, &[self.resolved_keys.editor_open_above]) {
+            self.open_line(false);
+            return true;
+        }
+        match key.code {
+            KeyCode::Char('x') => {
+                self.delete_chars_under_cursor(count);
+                return true;
+            }
+            KeyCode::Char('r') => {
+                self.replace_pending = Some(count);
+                return true;
+            }
+            KeyCode::Char('~') => {
+                let with = |replaced: &str| replaced.chars().map(toggle_case).collect();
+                self.replace_chars_under_cursor(count, with, true);
+                return true;
+            }
+            _ => {}
+        }
+        let Some(buf) = self.focused_buffer_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Char('u') => {
+                for _ in 0..count {
+                    buf.undo();
+                }
+                return true;
+            }
+            KeyCode::Char('h') | KeyCode::Left => buf.repeat_move(CursorMove::Back, count),
+            KeyCode::Char('j') | KeyCode::Down => buf.move_line(true, count),
+            KeyCode::Char('k') | KeyCode::Up => buf.move_line(false, count),
+            KeyCode::Char('l') | KeyCode::Right => buf.repeat_move(CursorMove::Forward, count),
+            KeyCode::Char('w') => buf.repeat_move(CursorMove::WordForward, count),
+            KeyCode::Char('b') => buf.repeat_move(CursorMove::WordBack, count),
+            KeyCode::Home if smart_indent => buf.smart_home(),
+            KeyCode::Char('0') | KeyCode::Home => buf.textarea.move_cursor(CursorMove::Head),
+            KeyCode::End => buf.textarea.move_cursor(CursorMove::End),
+            // The wrapped view does its own scrolling: move a pane's height of display rows.
+            KeyCode::PageUp | KeyCode::PageDown if soft_wrap => {
+                let rows = usize::from(buf.text_height.get()).max(1) * count;
+                for _ in 0..rows {
+                    buf.move_display_line(key.code == KeyCode::PageDown);
+                }
+            }
+            KeyCode::PageUp => (0..count).for_each(|_| buf.textarea.scroll(Scrolling::PageUp)),
+            KeyCode::PageDown => {
+                (0..count).for_each(|_| buf.textarea.scroll(Scrolling::PageDown));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// A digit typed in Normal mode: start or extend the count. `0` only extends one, so on
+    /// its own it still goes to the start of the line. Returns false for other keys.
+    pub fn push_count_digit(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        if self.focus != Focus::Editor
+            || self.editor_mode != EditorMode::Normal
+            || !key.modifiers.is_empty()
+        {
+            return false;
+        }
+        let crossterm::event::KeyCode::Char(c) = key.code else {
+            return false;
+        };
+        let Some(digit) = c.to_digit(10) else {
+            return false;
+        };
+        if digit == 0 && self.normal_count.is_none() {
+            return false;
+        }
+        let count = self.normal_count.unwrap_or(0);
+        self.normal_count = Some((count * 10 + digit as usize).min(MAX_COUNT));
+        true
+    }
+
+    /// The pending count for the key being handled, 1 without one.
+    pub fn take_count(&mut self) -> usize {
+        self.normal_count.take().unwrap_or(1)
+    }
+
+    /// `o` / `O`: open an empty line below or above the cursor's line and switch to Insert
+    /// mode, as one undoable edit. With `editor.continue_lists`, `o` on a list item starts
+    /// the new line with the item's marker.
+    pub fn open_line(&mut self, below: bool) {
+        let continue_lists = self.config.editor.continue_lists;
+        let Some(buf) = self.focused_buffer_mut() else {
+            return;
+        };
+        let (row, _) = buf.textarea.cursor();
+        if below {
+            let marker = continue_lists
+                .then(|| list_continuation(&buf.textarea.lines()[row]))
+                .flatten()
+                .map_or_else(String::new, |(marker, _)| marker);
+            buf.textarea.move_cursor(CursorMove::End);
+            buf.textarea.insert_str(format!("\n{marker}"));
+        } else if row > 0 {
+            buf.textarea.move_cursor(CursorMove::Up);
+            buf.textarea.move_cursor(CursorMove::End);
+            buf.textarea.insert_str("\n");
+        } else {
+            buf.textarea.move_cursor(CursorMove::Head);
+            buf.textarea.insert_str("\n");
+            buf.textarea.move_cursor(CursorMove::Up);
+        }
+        self.editor_mode = EditorMode::Insert;
+        self.mark_editor_dirty();
+    }
+
+    /// Enter in Insert mode with `editor.continue_lists`: on a list item, break the line and
+    /// start the new one with the item's marker; on an item that is only a marker, clear it
+    /// to end the list. Returns false outside list items, for a plain newline.
+    pub fn insert_newline_continuing_list(&mut self) -> bool {
+        if !self.config.editor.continue_lists {
+            return false;
+        }
+        let Some(buf) = self.focused_buffer_mut() else {
+            return false;
+        };
+        let (row, _) = buf.textarea.cursor();
+        let Some((marker, empty)) = list_continuation(&buf.textarea.lines()[row]) else {
+            return false;
+        };
+        if empty {
+            buf.textarea.move_cursor(CursorMove::Head);
+            buf.textarea.delete_line_by_end();
+        } else {
+            buf.textarea.insert_str(format!("\n{marker}"));
+        }
+        self.mark_editor_dirty();
+        true
+    }
+
+    /// Enter in Insert mode with `editor.smart_indent`: break the line and start the new one
+    /// with the current line's leading spaces and tabs, up to the cursor. Returns false
+    /// without any, for a plain newline.
+    pub fn insert_newline_keeping_indent(&mut self) -> bool {
+        if !self.config.editor.smart_indent {
+            return false;
+        }
+        let Some(buf) = self.focused_buffer_mut() else {
+            return false;
+        };
+        let (row, col) = buf.textarea.cursor();
+        let indent: String = leading_whitespace(&buf.textarea.lines()[row])
+            .chars()
+            .take(col)
+            .collect();
+        if indent.is_empty() {
+            return false;
+        }
+        buf.textarea.insert_str(format!("\n{indent}"));
+        self.mark_editor_dirty();
+        true
+    }
+
+    /// Whether Tab or Shift-Tab (`back`) in Insert mode is for a snippet: inside a tab stop
+    /// of an expanded one, or (Tab only) after a `[snippets]` trigger.
+    pub fn snippet_takes_tab(&self, back: bool) -> bool {
+        self.in_snippet_stop() || (!back && self.snippet_trigger().is_some())
+    }
+
+    /// Tab or Shift-Tab (`back`) for a snippet, see `snippet_takes_tab`: go to the next or
+    /// previous tab stop, or expand the trigger before the cursor.
+    pub fn snippet_tab(&mut self, back: bool) {
+        self.check_snippet();
+        if let Some(session) = &self.snippet {
+            let index = if back {
+                session.current().saturating_sub(1)
+            } else {
+                session.current() + 1
+            };
+            self.enter_snippet_stop(index);
+        } else if !back {
+            self.expand_snippet();
+        }
+    }
+
+    /// End the snippet session once its buffer is no longer focused, Insert mode was left,
+    /// or the cursor left the current tab stop. A placeholder still selected stays as it is.
+    pub fn check_snippet(&mut self) {
+        if self.snippet.is_none() || self.in_snippet_stop() {
+            return;
+        }
+        self.snippet = None;
+        if let Some(buf) = self.focused_buffer_mut() {
+            buf.textarea.cancel_selection();
+        }
+    }
+
+    fn in_snippet_stop(&self) -> bool {
+        let Some(session) = &self.snippet else {
+            return false;
+        };
+        self.editor_mode == EditorMode::Insert
+            && self.focused_buffer().is_some_and(|buf| {
+                let (start, end) = session.current_range(buf.textarea.lines());
+                buf.id == session.buffer_id && (start..=end).contains(&buf.textarea.cursor())
+            })
+    }
+
+    /// The `[snippets]` trigger right before the cursor (after whitespace or at the start of
+    /// the line), with the column it starts at.
+    fn snippet_trigger(&self) -> Option<(usize, &str)> {
+        let buf = self.focused_buffer()?;
+        let (row, col) = buf.textarea.cursor();
+        let before: Vec<char> = buf.textarea.lines()[row].chars().take(col).collect();
+        let start = before
+            .iter()
+            .rposition(|c| c.is_whitespace())
+            .map_or(0, |i| i + 1);
+        let trigger: String = before[start..].iter().collect();
+        let body = self
+            .config
+            .snippets
+            .get(&trigger)
+            .filter(|_| !trigger.is_empty())?;
+        Some((start, body))
+    }
+
+    /// Replace the trigger before the cursor with its snippet, as one undo step, and go to
+    /// the first tab stop.
+    fn expand_snippet(&mut self) {
+        let Some((start, body)) = self.snippet_trigger() else {
+            return;
+        };
+        let expansion = snippets::expand(body);
+        let Some(buf) = self.focused_buffer_mut() else {
+            return;
+        };
+        let (row, col) = buf.textarea.cursor();
+        let stops: Vec<snippets::Range> = expansion
+            .stops
+            .iter()
+            .map(|&range| {
+                let at = |(r, c): (usize, usize)| (row + r, if r == 0 { start + c } else { c });
+                (at(range.0), at(range.1))
+            })
+            .collect();
+        buf.textarea
+            .move_cursor(CursorMove::Jump(row as u16, start as u16));
+        buf.textarea.delete_str(col - start);
+        buf.textarea.insert_str(&expansion.text);
+        let (id, joined) = (buf.id, buf.joined_undos + 1);
+        self.mark_editor_dirty();
+        if let Some(buf) = self.focused_buffer_mut() {
+            buf.joined_undos = joined;
+        }
+        self.snippet = Some(SnippetSession::new(id, &stops));
+        self.enter_snippet_stop(0);
+    }
+
+    /// Go to tab stop `index` of the snippet, selecting its placeholder so typing replaces
+    /// it. The last stop ends the session.
+    fn enter_snippet_stop(&mut self, index: usize) {
+        let Some(mut session) = self.snippet.take() else {
+            return;
+        };
+        let Some(buf) = self.focused_buffer_mut() else {
+            return;
+        };
+        let index = index.min(session.stop_count() - 1);
+        let (start, end) = session.enter(index, buf.textarea.lines());
+        buf.textarea.cancel_selection();
+        buf.textarea
+            .move_cursor(CursorMove::Jump(start.0 as u16, start.1 as u16));
+        if end != start {
+            buf.textarea.start_selection();
+            buf.textarea
+                .move_cursor(CursorMove::Jump(end.0 as u16, end.1 as u16));
+        }
+        if index + 1 < session.stop_count() {
+            self.snippet = Some(session);
+        }
+    }
+
+    /// Insert mode with `editor.auto_pairs`: an opening delimiter also inserts its closing
+    /// one, a closing delimiter right before the same character steps over it, and Backspace
+    /// inside an empty pair deletes both. Pairs are not opened in front of a letter or digit,
+    /// and `` ` ``, `*`, `_` and `"` only open one at the start of a line or after a space,
+    /// `(` or `[`. Returns false when the key is left to the textarea.
+    pub fn insert_auto_pair(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        use crossterm::event::{KeyCode, KeyModifiers};
+        if !self.config.editor.auto_pairs
+            || key
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return false;
+        }
+        let Some(buf) = self.focused_buffer_mut() else {
+            return false;
+        };
+        let (row, col) = buf.textarea.cursor();
+        let line = &buf.textarea.lines()[row];
+        let prev = col.checked_sub(1).and_then(|i| line.chars().nth(i));
+        let next = line.chars().nth(col);
+        match key.code {
+            KeyCode::Backspace => {
+                let Some(open) = prev else { return false };
+                if auto_pair_close(open).is_none() || next != auto_pair_close(open) {
+                    return false;
+                }
+                buf.textarea.delete_next_char();
+                buf.textarea.delete_char();
+            }
+            KeyCode::Char(c) if next == Some(c) && AUTO_PAIRS.iter().any(|&(_, cl)| cl == c) => {
+                buf.textarea.move_cursor(CursorMove::Forward);
+                return true;
+            }
+            KeyCode::Char(c) => {
+                let Some(close) = auto_pair_close(c) else {
+                    return false;
+                };
+                let symmetric = close == c;
+                let after_text = prev.is_some_and(|p| !p.is_whitespace() && !"([".contains(p));
+                if next.is_some_and(char::is_alphanumeric) || (symmetric && after_text) {
+                    return false;
+                }
+                buf.textarea.insert_char(c);
+                buf.textarea.insert_char(close);
+                buf.textarea.move_cursor(CursorMove::Back);
+            }
+            _ => return false,
+        }
+        self.mark_editor_dirty();
+        true
+    }
+
+    // Telescope (Space+f)
+    /// Open telescope. It replaces a popup that is open, so closing it returns to the editor
+    /// or the file list rather than to that popup.
+    pub fn enter_telescope(&mut self) {
+        self.focus = Focus::Search;
+        let walk = vault::walk(&self.notes_dir, &self.ignore);
+        self.report_walk_warnings(&walk);
+        self.telescope_notes =
+            find_md_files_recursive(&self.notes_dir, &walk, self.config.ui.show_all_files);
+        set_titles(
+            &mut self.telescope_notes,
+            &self.links,
+            self.config.ui.display_titles,
+        );
+        self.telescope_filtered = self.telescope_notes.clone();
+        self.telescope_filtered_for.clear();
+        self.telescope_query.clear();
+        self.telescope_selected = 0;
+        self.telescope_mode = TelescopeMode::Files;
+        self.apply_telescope_filter();
+    }
+
+    /// Switch between searching files and the open buffers, keeping the query.
+    pub fn cycle_telescope_mode(&mut self) {
+        self.telescope_mode = self.telescope_mode.next();
+        self.telescope_selected = 0;
+        // The file results may be narrowed from a query since changed in the other mode.
+        self.telescope_filtered = self.telescope_notes.clone();
+        self.telescope_filtered_for.clear();
+        self.apply_telescope_filter();
+    }
+
+    /// Switch the list search and telescope between matching case exactly and the
+    /// `search.smart_case` default, and filter again.
+    pub fn toggle_search_case(&mut self) {
+        self.search_case = match self.search_case {
+            CaseMode::Sensitive => CaseMode::new(self.config.search.smart_case),
+            _ => CaseMode::Sensitive,
+        };
+        self.filtered_for = None;
+        self.telescope_filtered_for.clear();
+        if self.mode == Mode::Search {
+            self.apply_filter();
+        }
+        if self.focus == Focus::Search {
+            self.apply_telescope_filter();
+        }
+    }
+
+    pub fn exit_telescope(&mut self) {
+        self.focus = if self.has_open_buffers() {
+            Focus::Editor
+        } else {
+            Focus::List
+        };
+    }
+
+    pub fn telescope_key(&mut self, key: crossterm::event::KeyEvent) {
+        if self.telescope_query.handle_key(key) == InputChange::Edited {
+            self.update_telescope_filter();
+        }
+    }
+
+    pub fn telescope_backspace(&mut self) {
+        if self.telescope_query.backspace() {
+            self.update_telescope_filter();
+        }
+    }
+
+    fn update_telescope_filter(&mut self) {
+        self.telescope_pending = true;
+        if !self.config.ui.debounce_search {
+            self.apply_pending_search();
+        }
+    }
+
+    /// Filter the telescope notes by the query. A query that only adds to the last one
+    /// filters the last results instead of every note; tag and regex queries can match
+    /// notes the shorter query did not, so they always start over. An invalid regex keeps
+    /// the last results and sets `telescope_error`.
+    fn apply_telescope_filter(&mut self) {
+        self.telescope_pending = false;
+        match regex_query(&self.telescope_query, self.search_case) {
+            Some(Err(error)) => {
+                self.telescope_error = Some(error);
+                return;
+            }
+            regex => {
+                self.telescope_error = None;
+                self.telescope_regex = regex.and_then(Result::ok);
+            }
+        }
+        if self.telescope_mode == TelescopeMode::Buffers {
+            // The textareas, not the files: unsaved edits are found too.
+            let buffers = self
+                .buffers
+                .iter()
+                .map(|b| (b.id, b.display_name(), b.textarea.lines()));
+            self.telescope_buffer_matches =
+                search_buffers(buffers, &self.telescope_query, self.search_case);
+            self.telescope_selected = self
+                .telescope_selected
+                .min(self.telescope_buffer_matches.len().saturating_sub(1));
+            return;
+        }
+        let previous = &self.telescope_filtered_for;
+        let narrowed = !previous.starts_with('#')
+            && ![previous, &*self.telescope_query]
+                .iter()
+                .any(|q| q.starts_with(REGEX_PREFIX))
+            && narrows(previous, &self.telescope_query);
+        let source = if narrowed {
+            &self.telescope_filtered
+        } else {
+            &self.telescope_notes
+        };
+        self.telescope_filtered = filter_telescope_notes(
+            source,
+            &self.telescope_query,
+            self.search_case,
+            &mut self.telescope_matcher,
+            &self.recent,
+        );
+        self.telescope_filtered_for = self.telescope_query.to_string();
+        self.telescope_highlights
+            .reset(self.telescope_filtered.len());
+        if self.telescope_selected >= self.telescope_filtered.len() {
+            self.telescope_selected = self.telescope_filtered.len().saturating_sub(1);
+        }
+        self.fill_highlights();
+    }
+
+    pub fn telescope_move_up(&mut self) {
+        if self.telescope_selected > 0 {
+            self.telescope_selected -= 1;
+        }
+    }
+
+    /// Number of telescope results in the current mode.
+    fn telescope_len(&self) -> usize {
+        match self.telescope_mode {
+            TelescopeMode::Files => self.telescope_filtered.len(),
+            TelescopeMode::Buffers => self.telescope_buffer_matches.len(),
+        }
+    }
+
+    /// What telescope result `index` is, to find it again after filtering.
+    fn telescope_entry(&self, index: usize) -> Option<TelescopeEntry> {
+        match self.telescope_mode {
+            TelescopeMode::Files => self
+                .telescope_filtered
+                .get(index)
+                .map(|n| TelescopeEntry::File(n.path.clone())),
+            TelescopeMode::Buffers => self
+                .telescope_buffer_matches
+                .get(index)
+                .map(|m| TelescopeEntry::Line(m.buffer_id, m.row)),
+        }
+    }
+
+    pub fn telescope_move_down(&mut self) {
+        let len = self.telescope_len();
+        if self.telescope_selected + 1 < len {
+            self.telescope_selected += 1;
+        }
+    }
+
+    /// The selected file; `None` in `TelescopeMode::Buffers`.
+    pub fn get_telescope_selected_path(&self) -> Option<PathBuf> {
+        if self.telescope_mode != TelescopeMode::Files {
+            return None;
+        }
+        self.telescope_filtered
+            .get(self.telescope_selected)
+            .map(|n| n.path.clone())
+    }
+
+    /// Close telescope and put the cursor on the selected buffer search match, in its tab
+    /// (or the split's right pane, if it is shown there).
+    pub fn open_telescope_buffer_match(&mut self) {
+        let Some(found) = self
+            .telescope_buffer_matches
+            .get(self.telescope_selected)
+            .cloned()
+        else {
+            return;
+        };
+        let Some(idx) = self.buffers.iter().position(|b| b.id == found.buffer_id) else {
+            return;
+        };
+        self.record_jump();
+        if self.editor_layout.is_split() && self.split_right_tab() == Some(idx) {
+            self.split_focus_left = false;
+        } else {
+            self.active_tab = idx;
+            self.split_focus_left = true;
+        }
+        self.focus = Focus::Editor;
+        self.editor_mode = EditorMode::Normal;
+        if let Some(buf) = self.buffers.get_mut(idx) {
+            #[allow(clippy::cast_possible_truncation)]
+            buf.textarea
+                .move_cursor(CursorMove::Jump(found.row as u16, found.start as u16));
+        }
+    }
+
+    // Recent files popup
+    pub fn enter_recent_files(&mut self) {
+        self.recent.paths.retain(|p| p.exists());
+        self.recent_selected = 0;
+        self.focus = Focus::RecentFiles;
+    }
+
+    pub fn exit_recent_files(&mut self) {
+        self.focus = if self.has_open_buffers() {
+            Focus::Editor
+        } else {
+            Focus::List
+        };
+    }
+
+    pub fn recent_move_up(&mut self) {
+        if self.recent_selected > 0 {
+            self.recent_selected -= 1;
+        }
+    }
+
+    pub fn recent_move_down(&mut self) {
+        if self.recent_selected + 1 < self.recent.paths.len() {
+            self.recent_selected += 1;
+        }
+    }
+
+    pub fn open_selected_recent(&mut self) -> Result<()> {
+        if let Some(path) = self.recent.paths.get(self.recent_selected).cloned() {
+            self.load_file_into_editor(path)?;
+        }
+        Ok(())
+    }
+
+    /// Word under the editor cursor as (row, start col, end col, word); columns are char
+    /// indices, so multibyte words are handled.
+    fn word_under_cursor(&self) -> Option<(usize, usize, usize, String)> {
+        let buf = self.focused_buffer()?;
+        let (row, col) = buf.textarea.cursor();
+        let chars: Vec<char> = buf.textarea.lines().get(row)?.chars().collect();
+        let is_word = |c: char| c.is_alphabetic() || c == '\'';
+        if !chars.get(col).is_some_and(|c| c.is_alphabetic()) {
+            return None;
+        }
+        let mut start = col;
+        while start > 0 && is_word(chars[start - 1]) {
+            start -= 1;
+        }
+        let mut end = col;
+        while end < chars.len() && is_word(chars[end]) {
+            end += 1;
+        }
+        while start < col && chars[start] == '\'' {
+            start += 1;
+        }
+        while end > col + 1 && chars[end - 1] == '\'' {
+            end -= 1;
+        }
+        let word: String = chars[start..end].iter().collect();
+        Some((row, start, end, word))
+    }
+
+    // Spelling suggestions (z=)
+    pub fn enter_spell_suggest(&mut self) {
+        let Some((row, start, end, word)) = self.word_under_cursor() else {
+            self.push_message("No word under cursor".to_string());
+            return;
+        };
+        let Some(checker) = self.spellchecker.as_ref() else {
+            self.push_message("Spellcheck is disabled (editor.enable_spellcheck)".to_string());
+            return;
+        };
+        if checker.check(&word) {
+            self.push_message(format!("\"{word}\" is spelled correctly"));
+            return;
+        }
+        let suggestions = checker.suggest(&word, MAX_SPELL_SUGGESTIONS);
+        if suggestions.is_empty() {
+            self.push_message(format!("No suggestions for \"{word}\""));
+            return;
+        }
+        self.spell_suggestions = suggestions;
+        self.spell_suggestion_selected = 0;
+        self.spell_target = Some((row, start, end));
+        self.focus = Focus::SpellSuggest;
+    }
+
+    pub fn exit_spell_suggest(&mut self) {
+        self.spell_suggestions.clear();
+        self.spell_target = None;
+        self.focus = Focus::Editor;
+    }
+
+    pub fn spell_suggest_move_up(&mut self) {
+        if self.spell_suggestion_selected > 0 {
+            self.spell_suggestion_selected -= 1;
+        }
+    }
+
+    pub fn spell_suggest_move_down(&mut self) {
+        if self.spell_suggestion_selected + 1 < self.spell_suggestions.len() {
+            self.spell_suggestion_selected += 1;
+        }
+    }
+
+    /// Replace the target word with the selected suggestion.
+    pub fn confirm_spell_suggestion(&mut self) {
+        let replacement = self
+            .spell_suggestions
+            .get(self.spell_suggestion_selected)
+            .cloned();
+        if let (Some((row, start, end)), Some(replacement)) = (self.spell_target, replacement) {
+            if let Some(buf) = self.focused_buffer_mut() {
+                #[allow(clippy::cast_possible_truncation)]
+                buf.textarea
+                    .move_cursor(CursorMove::Jump(row as u16, start as u16));
+                buf.textarea.delete_str(end - start);
+                buf.textarea.insert_str(&replacement);
+                self.mark_editor_dirty();
+            }
+        }
+        self.exit_spell_suggest();
+    }
+
+    /// Add the word under the cursor to the personal dictionary (zg).
+    pub fn add_word_under_cursor_to_dictionary(&mut self) {
+        let Some((_, _, _, word)) = self.word_under_cursor() else {
+            self.push_message("No word under cursor".to_string());
+            return;
+        };
+        let Some(checker) = self.spellchecker.as_mut() else {
+            self.push_message("Spellcheck is disabled (editor.enable_spellcheck)".to_string());
+            return;
+        };
+        let message = match checker.add_word(&word) {
+            Ok(()) => format!("Added \"{word}\" to dictionary"),
+            Err(e) => format!("Cannot add word: {e}"),
+        };
+        self.push_message(message);
+    }
+
+    // Vault-wide replace
+    pub fn enter_replace_prompt(&mut self) {
+        self.replace_pattern.clear();
+        self.replace_with.clear();
+        self.replace_editing_with = false;
+        self.replace_files.clear();
+        self.focus = Focus::ReplacePrompt;
+    }
+
+    pub fn exit_replace(&mut self) {
+        self.replace_files.clear();
+        self.replace_scan = None;
+        self.focus = if self.has_open_buffers() {
+            Focus::Editor
+        } else {
+            Focus::List
+        };
+    }
+
+    /// The replace prompt's field being edited.
+    fn replace_field(&mut self) -> &mut TextInput {
+        if self.replace_editing_with {
+            &mut self.replace_with
+        } else {
+            &mut self.replace_pattern
+        }
+    }
+
+    pub fn replace_prompt_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.replace_field().handle_key(key);
+    }
+
+    pub fn replace_prompt_backspace(&mut self) {
+        self.replace_field().backspace();
+    }
+
+    pub fn replace_prompt_switch_field(&mut self) {
+        self.replace_editing_with = !self.replace_editing_with;
+    }
+
+    /// Save open buffers (so the scan sees their content) and collect matches for review on
+    /// a background thread; `poll_replace_scan` opens the review.
+    pub fn start_replace_scan(&mut self) -> Result<()> {
+        if self.replace_pattern.is_empty() {
+            self.push_message("Enter a search pattern".to_string());
+            return Ok(());
+        }
+        if self.replace_scan.is_some() {
+            return Ok(());
+        }
+        self.save_all_buffers()?;
+        self.replace_scan = Some(ReplaceScan::spawn(
+            self.notes_dir.clone(),
+            self.ignore.clone(),
+            self.replace_pattern.to_string(),
+            self.replace_with.to_string(),
+        ));
+        Ok(())
+    }
+
+    /// Whether the replace prompt is waiting for its scan.
+    pub fn replace_scanning(&self) -> bool {
+        self.replace_scan.is_some()
+    }
+
+    /// Open the review once the replace scan is done. Returns true while it is running.
+    pub fn poll_replace_scan(&mut self) -> bool {
+        let Some(scan) = &self.replace_scan else {
+            return false;
+        };
+        let Some(result) = scan.poll() else {
+            return true;
+        };
+        self.replace_scan = None;
+        self.needs_redraw = true;
+        let (files, warnings) = match result {
+            Ok(found) => found,
+            Err(e) => {
+                self.report_err("Replace", e);
+                self.exit_replace();
+                return false;
+            }
+        };
+        self.report_walk_warnings(&Walk {
+            warnings,
+            ..Walk::default()
+        });
+        self.replace_files = files;
+        if self.replace_files.is_empty() {
+            self.push_message(format!("No matches for \"{}\"", self.replace_pattern));
+            self.exit_replace();
+            return false;
+        }
+        self.replace_selected = 0;
+        self.focus = Focus::ReplaceReview;
+        false
+    }
+
+    /// Review rows: (file index, None) for a file header, (file index, Some(line index)) for
+    /// each matching line under it.
+    pub fn replace_rows(&self) -> Vec<(usize, Option<usize>)> {
+        self.replace_files
+            .iter()
+            .enumerate()
+            .flat_map(|(fi, f)| {
+                std::iter::once((fi, None)).chain((0..f.lines.len()).map(move |li| (fi, Some(li))))
+            })
+            .collect()
+    }
+
+    pub fn replace_move_up(&mut self) {
+        if self.replace_selected > 0 {
+            self.replace_selected -= 1;
+        }
+    }
+
+    pub fn replace_move_down(&mut self) {
+        if self.replace_selected + 1 < self.replace_rows().len() {
+            self.replace_selected += 1;
+        }
+    }
+
+    /// Toggle the selected file or line on or off.
+    pub fn replace_toggle_selected(&mut self) {
+        let Some(&(fi, li)) = self.replace_rows().get(self.replace_selected) else {
+            return;
+        };
+        let Some(file) = self.replace_files.get_mut(fi) else {
+            return;
+        };
+        match li {
+            None => file.enabled = !file.enabled,
+            Some(li) => {
+                if let Some(line) = file.lines.get_mut(li) {
+                    line.enabled = !line.enabled;
+                }
+            }
+        }
+    }
+
+    pub fn replace_toggle_dry_run(&mut self) {
+        self.replace_dry_run = !self.replace_dry_run;
+    }
+
+    /// Rewrite the enabled lines, stopping at the first file that fails. In dry-run mode only
+    /// the counts are reported.
+    pub fn confirm_replace(&mut self) {
+        let files: Vec<ReplaceFile> = std::mem::take(&mut self.replace_files)
+            .into_iter()
+            .filter(|f| f.enabled_lines().next().is_some())
+            .collect();
+        let planned_lines: usize = files.iter().map(|f| f.enabled_lines().count()).sum();
+        if self.replace_dry_run {
+            self.push_message(format!(
+                "Dry run: would rewrite {planned_lines} lines in {} files",
+                files.len()
+            ));
+            self.exit_replace();
+            return;
+        }
+
+        let mut done_files = 0;
+        let mut done_lines = 0;
+        let mut error = None;
+        for file in &files {
+            match apply_file(file) {
+                Ok(n) => {
+                    done_files += 1;
+                    done_lines += n;
+                    self.reload_buffer_from_disk(&file.path);
+                    self.links.update(&file.path, &self.ignore);
+                }
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        self.push_message(match error {
+            Some(e) => format!(
+                "Replace stopped: {e} ({done_lines} lines in {done_files} files already rewritten)"
+            ),
+            None => format!("Replaced {done_lines} lines in {done_files} files"),
+        });
+        if let Err(e) = self.refresh_notes() {
+            self.report_err("Refresh", e);
+        }
+        self.exit_replace();
+    }
+
+    /// Replace the content of the buffer showing `path` with the file on disk as one edit,
+    /// which `u` undoes, keeping the cursor where possible. A file that cannot be read
+    /// leaves the buffer as it was.
+    fn reload_buffer_from_disk(&mut self, path: &Path) {
+        let Some(buf) = self
+            .buffers
+            .iter_mut()
+            .find(|b| b.path.as_deref() == Some(path))
+        else {
+            return;
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.push_message(format!("Cannot reload {}: {e}", path.display()));
+                return;
+            }
+        };
+        buf.replace_text(content.strip_suffix('\n').unwrap_or(&content));
+    }
+
+    // Command palette (Ctrl+p)
+    /// Open the command palette; like telescope it replaces a popup that is open.
+    pub fn enter_command_palette(&mut self) {
+        self.focus = Focus::CommandPalette;
+        self.command_palette_query.clear();
+        self.apply_command_palette_filter();
+        self.command_palette_selected = 0;
+    }
+
+    pub fn exit_command_palette(&mut self) {
+        self.focus = if self.has_open_buffers() {
+            Focus::Editor
+        } else {
+            Focus::List
+        };
+    }
+
+    pub fn command_palette_key(&mut self, key: crossterm::event::KeyEvent) {
+        if self.command_palette_query.handle_key(key) == InputChange::Edited {
+            self.apply_command_palette_filter();
+        }
+    }
+
+    pub fn command_palette_backspace(&mut self) {
+        if self.command_palette_query.backspace() {
+            self.apply_command_palette_filter();
+        }
+    }
+
+    /// Fuzzy-match the query against action labels, best match first, with user commands
+    /// after the built-ins. The selected entry stays selected while it still matches;
+    /// otherwise the top result is.
+    fn apply_command_palette_filter(&mut self) {
+        let selected = self
+            .command_palette_filtered
+            .get(self.command_palette_selected)
+            .copied();
+        let items = CommandAction::all()
+            .iter()
+            .map(|&a| PaletteItem::Action(a))
+            .chain((0..self.config.commands.len()).map(PaletteItem::User));
+        let query = &self.command_palette_query;
+        if query.is_empty() {
+            self.command_palette_filtered = items.collect();
+        } else {
+            let pattern = Pattern::parse(query, CaseMatching::Ignore, Normalization::Smart);
+            let mut buf = Vec::new();
+            let mut scored: Vec<(PaletteItem, u32)> = items
+                .filter_map(|item| {
+                    let label = palette_label(&self.config, item);
+                    let haystack = Utf32Str::new(label, &mut buf);
+                    pattern
+                        .score(haystack, &mut self.matcher)
+                        .map(|s| (item, s))
+                })
+                .collect();
+            scored.sort_by(|(_, a), (_, b)| b.cmp(a));
+            self.command_palette_filtered = scored.into_iter().map(|(item, _)| item).collect();
+        }
+        self.command_palette_match_indices = self
+            .command_palette_filtered
+            .iter()
+            .map(|&item| {
+                get_match_indices(
+                    palette_label(&self.config, item),
+                    query,
+                    CaseMode::Ignore,
+                    &mut self.matcher,
+                )
+            })
+            .collect();
+        self.command_palette_selected = selected
+            .and_then(|item| {
+                self.command_palette_filtered
+                    .iter()
+                    .position(|&i| i == item)
+            })
+            .unwrap_or(0);
+    }
+
+    /// Text shown for a palette row.
+    pub fn palette_label(&self, item: PaletteItem) -> &str {
+        palette_label(&self.config, item)
+    }
+
+    pub fn command_palette_move_up(&mut self) {
+        if self.command_palette_selected > 0 {
+            self.command_palette_selected -= 1;
+        }
+    }
+
+    pub fn command_palette_move_down(&mut self) {
+        if self.command_palette_selected + 1 < self.command_palette_filtered.len() {
+            self.command_palette_selected += 1;
+        }
+    }
+
+    pub fn get_command_palette_action(&self) -> Option<PaletteItem> {
+        self.command_palette_filtered
+            .get(self.command_palette_selected)
+            .copied()
+    }
+
+    /// Run a command palette action. List actions move focus to the file list and editor
+    /// actions to the editor first. Returns true when the action quits oxid.
+    pub fn execute_command(&mut self, action: CommandAction) -> bool {
+        match action {
+            CommandAction::NewNote => {
+                self.focus = Focus::List;
+                self.enter_create_mode();
+            }
+            CommandAction::NewDirectory => {
+                self.focus = Focus::List;
+                self.enter_create_directory();
+            }
+            CommandAction::RenameFile => {
+                self.focus = Focus::List;
+                self.enter_rename();
+            }
+            CommandAction::DeleteFile => {
+                self.focus = Focus::List;
+                self.enter_delete_confirm();
+            }
+            CommandAction::MoveFile => {
+                self.focus = Focus::List;
+                self.enter_move_picker();
+            }
+            CommandAction::AddTag => {
+                self.focus = Focus::List;
+                self.enter_tag_prompt();
+            }
+            CommandAction::DuplicateFile => {
+                self.focus = Focus::List;
+                if let Err(e) = self.duplicate_selected() {
+                    self.push_message(format!("Duplicate failed: {e}"));
+                }
+            }
+            CommandAction::ArchiveNote => {
+                if let Err(e) = self.archive() {
+                    self.push_message(format!("Archive failed: {e}"));
+                }
+            }
+            CommandAction::UnarchiveNote => {
+                if let Err(e) = self.unarchive() {
+                    self.push_message(format!("Unarchive failed: {e}"));
+                }
+            }
+            CommandAction::ToggleMark => {
+                self.focus = Focus::List;
+                self.toggle_mark();
+            }
+            CommandAction::ClearMarks => self.clear_marks(),
+            CommandAction::GoToParent => {
+                self.focus = Focus::List;
+                self.go_to_parent_dir();
+            }
+            CommandAction::GoToVaultRoot => {
+                self.focus = Focus::List;
+                self.jump_to_level(0);
+            }
+            CommandAction::SearchNotes => self.enter_telescope(),
+            CommandAction::RecentFiles => self.enter_recent_files(),
+            CommandAction::RandomNote => self.enter_random_note(),
+            CommandAction::ShowMessages => self.open_messages(),
+            CommandAction::DailyNote => {
+                if let Err(e) = self.open_daily_note() {
+                    self.push_message(format!("Cannot open daily note: {e}"));
+                }
+            }
+            CommandAction::QuickCapture => self.enter_quick_capture(),
+            CommandAction::AddTask => self.enter_add_task(),
+            CommandAction::TaskBoard => self.enter_task_view(),
+            CommandAction::TagExplorer => self.enter_tag_explorer(),
+            CommandAction::InsertDate => {
+                self.focus = Focus::Editor;
+                self.mark_editor_dirty();
+                self.insert_date_at_cursor();
+            }
+            CommandAction::FollowLink => {
+                if let Some(link) = self.get_wiki_link_under_cursor() {
+                    if let Err(e) = self.open_wiki_link(&link) {
+                        self.report_err("Open link", e);
+                    }
+                }
+            }
+            CommandAction::SpellSuggest => self.enter_spell_suggest(),
+            CommandAction::AddWordToDictionary => self.add_word_under_cursor_to_dictionary(),
+            CommandAction::OpenInExternalEditor => self.request_external_edit(),
+            CommandAction::ToggleTask => self.toggle_task_at_cursor(),
+            CommandAction::ExportPdf => self.export_to_pdf(),
+            CommandAction::ExportHtml => self.enter_export_html(),
+            CommandAction::ExportGraph => self.enter_export_graph(),
+            CommandAction::InsertImage => self.insert_image(),
+            CommandAction::CopyPath => self.copy_note(CopyWhat::Path),
+            CommandAction::CopyWikiLink => self.copy_note(CopyWhat::WikiLink),
+            CommandAction::CopyNoteContent => self.copy_note(CopyWhat::Content),
+            CommandAction::CopyNoteId => self.copy_note(CopyWhat::Id),
+            CommandAction::GitPush => {
+                if let Err(e) = self.git_push() {
+                    self.report_err("Git push", e);
+                }
+            }
+            CommandAction::GitInit => self.git_init(),
+            CommandAction::GitDiffFile => self.show_git_diff(),
+            CommandAction::DiffBuffer => self.show_buffer_diff(),
+            CommandAction::ReplaceInVault => self.enter_replace_prompt(),
+            CommandAction::NextTab => self.next_tab(),
+            CommandAction::PrevTab => self.prev_tab(),
+            CommandAction::CloseTab => self.close_tab(),
+            CommandAction::CloseOtherTabs => self.close_other_tabs(),
+            CommandAction::CloseAllTabs => {
+                self.close_all_tabs();
+                self.focus = Focus::List;
+            }
+            CommandAction::ReopenClosedTab => {
+                if let Err(e) = self.reopen_closed_tab() {
+                    self.push_message(format!("Cannot reopen tab: {e}"));
+                }
+            }
+            CommandAction::ToggleSplitView => self.toggle_split_view(),
+            CommandAction::OpenInSplit => self.enter_buffer_picker(),
+            CommandAction::SwitchSplitFocus => {
+                if self.editor_layout.is_split() && self.split_right_tab().is_some() {
+                    self.split_focus_left = !self.split_focus_left;
+                }
+            }
+            CommandAction::ToggleZenMode => self.toggle_zen_mode(),
+            CommandAction::Suspend => self.suspend_requested = true,
+            CommandAction::TogglePreview => self.toggle_preview(),
+            CommandAction::ToggleSoftWrap => self.toggle_soft_wrap(),
+            CommandAction::ToggleFocusMode => self.toggle_focus_mode(),
+            CommandAction::ToggleTreeView => {
+                self.focus = Focus::List;
+                self.toggle_tree_view();
+            }
+            CommandAction::ToggleBacklinks => self.toggle_backlinks(),
+            CommandAction::FocusBacklinks => {
+                if self.config.editor.show_backlinks {
+                    self.focus = Focus::Backlinks;
+                }
+            }
+            CommandAction::GrowEditorPane => self.resize_editor(5),
+            CommandAction::ShrinkEditorPane => self.resize_editor(-5),
+            CommandAction::CycleTheme => self.cycle_theme(),
+            CommandAction::ToggleLightDark => self.toggle_light_dark(),
+            CommandAction::OpenConfig => self.open_config_file(CONFIG_FILE_NAME),
+            CommandAction::OpenTheme => self.open_config_file(THEME_FILE_NAME),
+            CommandAction::ReloadConfig => {
+                match self.reload_config() {
+                    // Parse errors are reported instead of the confirmation.
+                    Ok(()) if !self.config_errors.is_empty() => {}
+                    Ok(()) => self.push_message("Config reloaded".to_string()),
+                    Err(e) => self.push_message(format!("Cannot reload config: {e}")),
+                }
+            }
+            CommandAction::CheckKeybindings => self.open_keybinding_report(),
+            CommandAction::Quit => {
+                if self.save_before_quit() {
+                    return true;
                 }
             }
         }
+        false
+    }
+
+    /// Copy the focused buffer's note (or, with no buffer open, the selected file) to the
+    /// system clipboard. Content comes from the buffer, so unsaved edits are included.
+    pub fn copy_note(&mut self, what: CopyWhat) {
+        let buffer = self.focused_buffer().filter(|b| b.path.is_some());
+        let Some(path) = buffer.and_then(|b| b.path.clone()).or_else(|| {
+            self.get_selected_path()
+                .filter(|p| p.is_file() && !is_binary_file(p))
+        }) else {
+            self.push_message("No note to copy".to_string());
+            return;
+        };
+        let (label, text) = match what {
+            CopyWhat::Path => ("path", path.display().to_string()),
+            CopyWhat::WikiLink => {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                ("wiki link", format!("[[{stem}]]"))
+            }
+            CopyWhat::Id => match self.links.note_id(&path) {
+                Some(id) => ("note ID", id),
+                None => {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    self.push_message(format!("{name} has no note ID"));
+                    return;
+                }
+            },
+            CopyWhat::Content => match buffer {
+                Some(buf) => ("content", buf.textarea.lines().join("\n")),
+                None => match fs::read_to_string(&path) {
+                    Ok(content) => ("content", content),
+                    Err(e) => {
+                        self.push_message(format!("Cannot read {}: {e}", path.display()));
+                        return;
+                    }
+                },
+            },
+        };
+        self.push_message(
+            match clipboard::copy(&text, &self.config.ui.clipboard_command) {
+                Ok(CopyMethod::Osc52) => format!("Copied {label} ({} bytes)", text.len()),
+                Ok(CopyMethod::Command(cmd)) => {
+                    format!("Copied {label} ({} bytes) with {cmd}", text.len())
+                }
+                Err(e) => format!("Copy failed: {e:#}"),
+            },
+        );
+    }
+
+    /// Insert pasted text into the focused buffer as one undoable edit. Line endings are
+    /// normalized to LF. A paste in Normal mode switches to Insert mode first, so it lands
+    /// the same way whether it came from the terminal or `editor_paste`. In the quick
+    /// capture popup the text is joined into one line.
+    pub fn paste_text(&mut self, text: &str) {
+        if self.focus == Focus::QuickCapture {
+            self.capture_input
+                .insert_str(&text.split_whitespace().collect::<Vec<_>>().join(" "));
+            return;
+        }
+        if self.focus == Focus::AddTask {
+            self.task_input
+                .insert_str(&text.split_whitespace().collect::<Vec<_>>().join(" "));
+            return;
+        }
+        if self.focus != Focus::Editor || text.is_empty() {
+            return;
+        }
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let Some(buf) = self.focused_buffer_mut() else {
+            return;
+        };
+        buf.textarea.insert_str(&text);
+        self.editor_mode = EditorMode::Insert;
+        self.mark_editor_dirty();
+    }
+
+    /// `editor_paste`: read the system clipboard and paste it.
+    pub fn paste_from_clipboard(&mut self) {
+        match clipboard::paste(&self.config.ui.paste_command) {
+            Ok(text) => self.paste_text(&text),
+            Err(e) => self.push_message(format!("Paste failed: {e:#}")),
+        }
+    }
+
+    /// Insert Image: save a clipboard image into the assets folder, or ask for an image file
+    /// to copy there when the clipboard has none. Either way a link goes in at the cursor.
+    pub fn insert_image(&mut self) {
+        let Some(stem) = self
+            .editing_path()
+            .and_then(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        else {
+            self.push_message("Open a note to insert an image".to_string());
+            return;
+        };
+        self.focus = Focus::Editor;
+        let Some(bytes) = clipboard::paste_image(&self.config.editor.image_paste_command) else {
+            self.image_path_input.clear();
+            self.focus = Focus::ImagePathPrompt;
+            return;
+        };
+        let name = format!("{stem}-{}.png", Local::now().format("%Y%m%d-%H%M%S"));
+        let target = self.assets_dir().join(name);
+        let written =
+            fs::create_dir_all(self.assets_dir()).and_then(|()| fs::write(&target, &bytes));
+        match written {
+            Ok(()) => self.insert_image_link(&target),
+            Err(e) => self.push_message(format!("Cannot save image: {e}")),
+        }
+    }
+
+    pub fn image_path_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.image_path_input.handle_key(key);
+    }
+
+    pub fn image_path_backspace(&mut self) {
+        self.image_path_input.backspace();
+    }
+
+    pub fn exit_image_path_prompt(&mut self) {
+        self.image_path_input.clear();
+        self.focus = Focus::Editor;
+    }
+
+    /// Copy the file named in the prompt into the assets folder (under a new name if one
+    /// with the same name is already there) and link to it.
+    pub fn confirm_image_path(&mut self) {
+        let source = expand_path(&self.image_path_input);
+        let Some(name) = source.file_name().filter(|_| source.is_file()) else {
+            self.push_message(format!("Not a file: {}", source.display()));
+            return;
+        };
+        let mut target = self.assets_dir().join(name);
+        if target.exists() && target != source {
+            let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+            let stamp = Local::now().format("%Y%m%d-%H%M%S");
+            target.set_file_name(match source.extension() {
+                Some(ext) => format!("{stem}-{stamp}.{}", ext.to_string_lossy()),
+                None => format!("{stem}-{stamp}"),
+            });
+        }
+        let copied = fs::create_dir_all(self.assets_dir()).and_then(|()| {
+            if target == source {
+                Ok(0)
+            } else {
+                fs::copy(&source, &target)
+            }
+        });
+        self.exit_image_path_prompt();
+        match copied {
+            Ok(_) => self.insert_image_link(&target),
+            Err(e) => self.push_message(format!("Cannot copy image: {e}")),
+        }
+    }
+
+    fn assets_dir(&self) -> PathBuf {
+        self.notes_dir.join(self.config.editor.assets_folder.trim())
+    }
+
+    /// Insert `![](…)` for `asset` at the cursor, relative to the focused note.
+    fn insert_image_link(&mut self, asset: &Path) {
+        let note_dir = self
+            .editing_path()
+            .and_then(|p| p.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+        let link = match (
+            note_dir.strip_prefix(&self.notes_dir),
+            asset.strip_prefix(&self.notes_dir),
+        ) {
+            (Ok(from), Ok(to)) => format!(
+                "{}{}",
+                "../".repeat(from.components().count()),
+                to.display()
+            ),
+            _ => asset.display().to_string(),
+        };
+        let link = if link.contains(' ') {
+            format!("![](<{link}>)")
+        } else {
+            format!("![]({link})")
+        };
+        if let Some(buf) = self.focused_buffer_mut() {
+            buf.textarea.insert_str(&link);
+            self.mark_editor_dirty();
+            self.push_message(format!("Inserted {}", asset.display()));
+        }
+    }
+
+    /// Save and queue the focused file for the external editor. Refuses when the focused
+    /// buffer has no file.
+    pub fn request_external_edit(&mut self) {
+        let Some(path) = self.focused_buffer().and_then(|b| b.path.clone()) else {
+            self.push_message("No file to open in an external editor".to_string());
+            return;
+        };
+        if crypt::is_encrypted(&path) {
+            self.push_message("Encrypted notes cannot be opened in an external editor".to_string());
+            return;
+        }
+        if let Err(e) = self.save_all_buffers() {
+            self.push_message(format!("Save failed: {e}"));
+            return;
+        }
+        self.external_edit = Some(path);
+    }
+
+    /// Shell command for the external editor: `ui.external_editor`, else `$EDITOR`, else vi.
+    pub fn external_editor_command(&self) -> String {
+        let configured = self.config.ui.external_editor.trim();
+        if !configured.is_empty() {
+            return configured.to_string();
+        }
+        std::env::var("EDITOR")
+            .ok()
+            .filter(|e| !e.trim().is_empty())
+            .unwrap_or_else(|| "vi".to_string())
+    }
+
+    /// Pick up the external editor's changes to `path`.
+    pub fn finish_external_edit(&mut self, path: &Path) {
+        self.reload_buffer_from_disk(path);
         self.editor_dirty = false;
-        self.backlinks_cache_valid = false;
-        if need_reload {
-            let _ = self.reload_config();
+        self.links.update(path, &self.ignore);
+        if let Err(e) = self.refresh_notes() {
+            self.report_err("Refresh", e);
+        }
+    }
+
+    /// Run the `[[commands]]` entry at `index`, asking first if it has `confirm = true`.
+    pub fn start_user_command(&mut self, index: usize) {
+        if self.config.commands.get(index).is_some_and(|c| c.confirm) {
+            self.user_command_pending = Some(index);
+            self.focus = Focus::UserCommandConfirm;
+        } else {
+            self.run_user_command(index);
+        }
+    }
+
+    pub fn confirm_user_command(&mut self) {
+        if let Some(index) = self.user_command_pending.take() {
+            self.run_user_command(index);
+        }
+    }
+
+    pub fn exit_user_command_confirm(&mut self) {
+        self.user_command_pending = None;
+        self.exit_command_palette();
+    }
+
+    fn run_user_command(&mut self, index: usize) {
+        let Some(cmd) = self.config.commands.get(index) else {
+            return;
+        };
+        if let Some(running) = self.user_command.as_ref().filter(|c| c.is_running()) {
+            self.push_message(format!("{} is still running", running.name));
+            self.exit_command_palette();
+            return;
+        }
+        let file = self.focused_buffer().and_then(|b| b.path.clone());
+        match RunningCommand::spawn(cmd, &self.notes_dir, file.as_deref()) {
+            Ok(running) => {
+                self.user_command = Some(running);
+                self.focus = Focus::CommandOutput;
+            }
+            Err(e) => {
+                self.push_message(format!("{e:#}"));
+                self.exit_command_palette();
+            }
+        }
+    }
+
+    /// Collect output from the running user command. Returns true while it is running.
+    pub fn poll_user_command(&mut self) -> bool {
+        let Some(cmd) = self.user_command.as_mut() else {
+            return false;
+        };
+        let (was_running, lines) = (cmd.is_running(), cmd.output.len());
+        cmd.poll();
+        // Redraw for the spinner, new output and the exit status.
+        self.needs_redraw |= was_running || cmd.output.len() != lines;
+        cmd.is_running()
+    }
+
+    /// Esc in the output popup: cancel the command if it is running, else close the popup.
+    pub fn cancel_or_close_user_command(&mut self) {
+        match self.user_command.as_mut() {
+            Some(cmd) if cmd.is_running() => cmd.cancel(),
+            _ => self.close_user_command(),
+        }
+    }
+
+    /// Close the output popup once the command has finished, picking up any files it changed.
+    pub fn close_user_command(&mut self) {
+        if self
+            .user_command
+            .as_ref()
+            .is_some_and(RunningCommand::is_running)
+        {
+            return;
+        }
+        self.user_command = None;
+        self.exit_command_palette();
+        if let Err(e) = self.refresh_notes() {
+            self.report_err("Refresh", e);
+        }
+    }
+
+    /// Open `name` from the config directory in the editor.
+    fn open_config_file(&mut self, name: &str) {
+        let result = crate::config::ensure_config_dir()
+            .and_then(|dir| self.load_file_into_editor(dir.join(name)));
+        if let Err(e) = result {
+            self.push_message(format!("Cannot open {name}: {e}"));
+        }
+    }
+
+    // Rename popup (r)
+    /// Ask for a new name for the selected entry. A note starts from its name without `.md`,
+    /// which renaming adds back.
+    pub fn enter_rename(&mut self) {
+        if let Some(entry) = self.filtered_notes.get(self.selected) {
+            let path = if entry.is_directory || !is_markdown_path(&entry.path) {
+                entry.path.file_name()
+            } else {
+                entry.path.file_stem()
+            };
+            let name = path.and_then(|n| n.to_str()).unwrap_or("").to_string();
+            self.rename_input.set(name);
+            self.focus = Focus::Rename;
+        }
+    }
+
+    pub fn exit_rename(&mut self) {
+        self.focus = Focus::List;
+        self.rename_input.clear();
+    }
+
+    pub fn rename_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.rename_input.handle_key(key);
+    }
+
+    pub fn rename_backspace(&mut self) {
+        self.rename_input.backspace();
+    }
+
+    pub fn rename_selected_note(&mut self) -> Result<()> {
+        let Some(entry) = self.filtered_notes.get(self.selected) else {
+            return Ok(());
+        };
+        let old_path = entry.path.clone();
+        let is_dir = entry.is_directory;
+        let name = self.rename_input.trim();
+        if name.is_empty() {
+            self.push_message("Name cannot be empty".to_string());
+            return Ok(());
+        }
+        let name = if is_dir || !is_markdown_path(&old_path) || is_markdown_path(Path::new(name)) {
+            name.to_string()
+        } else {
+            format!("{name}.md")
+        };
+        let parent = old_path.parent().unwrap_or(&self.current_dir).to_path_buf();
+        let Some(new_path) = vault::resolve_path(&self.notes_dir, &parent, &name) else {
+            self.push_message("Path must stay inside the notes directory".to_string());
+            return Ok(());
+        };
+        if is_taken(&old_path, &new_path) {
+            self.push_message("File already exists".to_string());
+            return Ok(());
         }
+        if is_dir && new_path.starts_with(&old_path) && new_path != old_path {
+            self.push_message("Cannot move a directory into itself".to_string());
+            return Ok(());
+        }
+        if let Some(dir) = new_path.parent() {
+            vault::create_dirs(&self.notes_dir, dir)?;
+        }
+        fs::rename(&old_path, &new_path)?;
+        self.retarget_paths(&old_path, &new_path);
+        self.links.update(&old_path, &self.ignore);
+        self.links.update(&new_path, &self.ignore);
         self.refresh_notes()?;
+        self.select_path(&new_path);
+        self.exit_rename();
+        self.push_message("Renamed".to_string());
         Ok(())
     }
 
-    /// Mark that the editor content has changed (for auto-save tracking).
-    pub fn mark_editor_dirty(&mut self) {
-        self.editor_dirty = true;
-        self.last_keystroke_time = Some(Instant::now());
+    // Create directory popup (Shift+n)
+    pub fn enter_create_directory(&mut self) {
+        self.directory_input.clear();
+        self.focus = Focus::CreatingDirectory;
     }
 
-    /// Check auto-save condition and save if needed. Returns true if a save was performed.
-    pub fn check_auto_save(&mut self) -> Result<bool> {
-        if !self.config.editor.auto_save || !self.editor_dirty {
-            return Ok(false);
+    pub fn exit_create_directory(&mut self) {
+        self.focus = Focus::List;
+        self.directory_input.clear();
+    }
+
+    pub fn directory_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.directory_input.handle_key(key);
+    }
+
+    pub fn directory_backspace(&mut self) {
+        self.directory_input.backspace();
+    }
+
+    pub fn create_directory(&mut self) -> Result<()> {
+        let name = self.directory_input.trim().to_string();
+        if name.is_empty() {
+            self.push_message("Directory name cannot be empty".to_string());
+            return Ok(());
         }
-        let Some(last) = self.last_keystroke_time else { return Ok(false) };
-        let interval = Duration::from_secs(self.config.editor.auto_save_interval);
-        if Instant::now().duration_since(last) < interval {
-            return Ok(false);
+        let path = self.current_dir.join(&name);
+        if path.exists() {
+            self.push_message("Directory already exists".to_string());
+            return Ok(());
         }
-        self.save_all_buffers()?;
-        self.save_indicator_until = Some(Instant::now() + Duration::from_secs(2));
-        Ok(true)
+        fs::create_dir(&path).map_err(|e| anyhow::anyhow!("Failed to create directory: {e}"))?;
+        self.exit_create_directory();
+        self.refresh_notes()?;
+        self.select_path(&path);
+        self.push_message(format!("Created directory: {name}"));
+        Ok(())
     }
 
-    /// Clear "Saved..." indicator when expired.
-    pub fn tick_save_indicator(&mut self) {
-        if let Some(until) = self.save_indicator_until {
-            if Instant::now() >= until {
-                self.save_indicator_until = None;
-            }
+    // Zen mode
+    pub fn toggle_zen_mode(&mut self) {
+        self.zen_mode = !self.zen_mode;
+    }
+
+    /// Dim the editor text outside the paragraph being written, for this session.
+    pub fn toggle_focus_mode(&mut self) {
+        self.focus_mode = !self.focus_mode;
+    }
+
+    pub fn toggle_preview(&mut self) {
+        self.show_preview = !self.show_preview;
+    }
+
+    /// Wrap long editor lines or scroll sideways, for this session.
+    pub fn toggle_soft_wrap(&mut self) {
+        self.config.editor.soft_wrap = !self.config.editor.soft_wrap;
+    }
+
+    /// Show or hide the backlinks pane for this session.
+    pub fn toggle_backlinks(&mut self) {
+        self.config.editor.show_backlinks = !self.config.editor.show_backlinks;
+        if self.config.editor.show_backlinks {
+            self.scan_backlinks();
+        } else if self.focus == Focus::Backlinks {
+            self.focus = Focus::Editor;
         }
     }
 
-    /// Save the current editor content to disk.
-    pub fn save_editor(&mut self) -> Result<()> {
-        self.save_all_buffers()
+    /// Width of the editor column in percent, after the list and (if shown) the preview.
+    pub fn editor_width_percent(&self) -> u16 {
+        let preview = if self.show_preview {
+            self.preview_width_percent
+        } else {
+            0
+        };
+        100 - self.list_width_percent - preview
     }
 
-    fn apply_theme_to_textarea(
-        theme: &ResolvedTheme,
-        textarea: &mut TextArea<'static>,
-        editor_config: &crate::config::EditorConfig,
-    ) {
-        let editor_style = theme.editor_fg_style.patch(theme.editor_bg_style);
-        textarea.set_style(editor_style);
-        textarea.set_cursor_style(theme.editor_cursor_style);
-        textarea.set_cursor_line_style(
-            ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::UNDERLINED),
-        );
-        if editor_config.line_numbers {
-            textarea.set_line_number_style(theme.editor_line_number_style);
+    /// Grow (positive) or shrink (negative) the editor column by `step` percent, taking the
+    /// space from the preview, or from the list when the preview is hidden.
+    pub fn resize_editor(&mut self, step: i16) {
+        let editor = self.editor_width_percent().saturating_add_signed(step);
+        if !(MIN_EDITOR_PERCENT..=80).contains(&editor) {
+            return;
+        }
+        let side = if self.show_preview {
+            &mut self.preview_width_percent
         } else {
-            textarea.remove_line_number();
+            &mut self.list_width_percent
+        };
+        let new_side = side.saturating_add_signed(-step);
+        if !(10..=60).contains(&new_side) {
+            return;
         }
-        let tab_len = editor_config.tab_width.clamp(1, 16);
-        textarea.set_tab_length(tab_len);
-        // Headers (# ), list markers (- ), unchecked (- [ ]), checked (- [x]), code blocks (```)
-        let _ = textarea
-            .set_search_pattern(r"(^#{1,6} )|(^[-*] )|(^[-*] \[ \])|(^[-*] \[[xX]\])|(^```)");
-        textarea.set_search_style(
-            theme
-                .editor_header_style
-                .patch(theme.editor_list_style)
-                .patch(theme.editor_checkbox_style)
-                .patch(theme.editor_checkbox_checked_style)
-                .patch(theme.editor_code_block_style),
-        );
+        *side = new_side;
+    }
+
+    /// Git status of `path` for the file list.
+    pub fn git_file_status(&self, path: &Path) -> Option<FileStatus> {
+        self.git.as_ref()?.file(path)
+    }
+
+    /// Task counts of the note at `path` from the link index, as of its last save.
+    pub fn task_counts(&self, path: &Path) -> tasks::TaskCounts {
+        self.links.tasks(path)
+    }
+
+    /// Check or uncheck the task on the cursor's line (`editor_toggle_task`). Checking a
+    /// recurring task adds the next one above it; the cursor stays on the checked task.
+    pub fn toggle_task_at_cursor(&mut self) {
+        let recurring = self.config.editor.recurring_tasks;
+        let Some(buf) = self.focused_buffer_mut() else {
+            return;
+        };
+        let (row, col) = buf.textarea.cursor();
+        let Some(line) = buf.textarea.lines().get(row) else {
+            return;
+        };
+        let Some(lines) = tasks::toggle(line, tasks::today(), recurring) else {
+            return;
+        };
+        replace_line(&mut buf.textarea, row, &lines);
+        #[allow(clippy::cast_possible_truncation)]
+        buf.textarea
+            .move_cursor(CursorMove::Jump((row + lines.len() - 1) as u16, col as u16));
+        self.mark_editor_dirty();
+    }
+
+    // Wiki link: [[Filename]] under cursor
+    pub fn get_wiki_link_under_cursor(&self) -> Option<String> {
+        let buf = self.focused_buffer()?;
+        let (row, col) = buf.textarea.cursor();
+        let lines = buf.textarea.lines();
+        let line = lines.get(row)?;
+        let re = Regex::new(r"\[\[([^\]]+)\]\]").ok()?;
+        for cap in re.captures_iter(line) {
+            let m = cap.get(0)?;
+            let start = m.start();
+            let end = m.end();
+            if col >= start && col <= end {
+                return Some(cap.get(1)?.as_str().to_string());
+            }
+        }
+        None
+    }
+
+    /// Open the note a wiki link points at: next to the current note, then in the current
+    /// directory, then a note with that ID, then a note listing the link as an alias (with a
+    /// picker when several do). Only a link matching none of these creates a new note.
+    pub fn open_wiki_link(&mut self, link: &str) -> Result<()> {
+        if let Err(e) = self.save_editor() {
+            self.report_err("Save", e);
+        }
+        self.record_jump();
+        let link = link.split('|').next().unwrap_or(link).trim();
+        let name = if std::path::Path::new(link)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+        {
+            link.to_string()
+        } else {
+            format!("{link}.md")
+        };
+        let path = self
+            .editing_path()
+            .as_ref()
+            .and_then(|p| p.parent())
+            .unwrap_or(&self.current_dir)
+            .join(&name);
+        if path.exists() {
+            self.load_file_into_editor(path)?;
+        } else {
+            let path = self.current_dir.join(&name);
+            if path.exists() {
+                self.load_file_into_editor(path)?;
+            } else if let Some(note) = self.links.id_matches(link).first() {
+                let note = note.to_path_buf();
+                self.load_file_into_editor(note)?;
+            } else {
+                match self.links.notes_with_alias(link) {
+                    [] => {
+                        let (path, content) = self.with_zettel_id(path, String::new());
+                        fs::write(&path, content)?;
+                        self.links.update(&path, &self.ignore);
+                        self.load_file_into_editor(path)?;
+                    }
+                    [note] => {
+                        let note = note.clone();
+                        self.load_file_into_editor(note)?;
+                    }
+                    notes => {
+                        self.alias_candidates = notes.to_vec();
+                        self.alias_selected = 0;
+                        self.alias_pending = link.to_string();
+                        self.focus = Focus::AliasPicker;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn exit_alias_picker(&mut self) {
+        self.alias_candidates.clear();
+        self.exit_command_palette();
     }
 
-    fn apply_editor_theme_to_all(&mut self) {
-        for buf in self.buffers.iter_mut() {
-            Self::apply_theme_to_textarea(&self.theme, &mut buf.textarea, &self.config.editor);
-        }
+    pub fn alias_picker_move_up(&mut self) {
+        self.alias_selected = self.alias_selected.saturating_sub(1);
     }
 
-    /// Handle editor input in Normal mode (vim-like).
-    pub fn editor_normal_input(&mut self, key: crossterm::event::KeyEvent) -> bool {
-        use crossterm::event::KeyCode;
-        if key_matches(key, &[self.resolved_keys.escape]) {
-            self.editor_mode = EditorMode::Normal;
-            self.g_pending = false;
-            return true;
-        }
-        if self.g_pending {
-            self.g_pending = false;
-            match key.code {
-                KeyCode::Char('t') => {
-                    self.next_tab();
-                    return true;
-                }
-                KeyCode::Char('T') => {
-                    self.prev_tab();
-                    return true;
-                }
-                KeyCode::Char('s') => {
-                    self.toggle_split_view();
-                    return true;
-                }
-                KeyCode::Char('q') => {
-                    self.close_tab();
-                    return true;
-                }
-                KeyCode::Char('d') => {
-                    if let Some(link) = self.get_wiki_link_under_cursor() {
-                        let _ = self.open_wiki_link(&link);
-                    }
-                    return true;
-                }
-                _ => {}
-            }
+    pub fn alias_picker_move_down(&mut self) {
+        if self.alias_selected + 1 < self.alias_candidates.len() {
+            self.alias_selected += 1;
         }
-        if key.code == KeyCode::Char('g') {
-            self.g_pending = true;
-            return true;
+    }
+
+    pub fn open_selected_alias(&mut self) -> Result<()> {
+        let note = self.alias_candidates.get(self.alias_selected).cloned();
+        self.exit_alias_picker();
+        if let Some(note) = note {
+            self.load_file_into_editor(note)?;
         }
-        if key_matches(key, &[self.resolved_keys.editor_back]) {
-            self.focus_list();
-            return true;
+        Ok(())
+    }
+
+    /// Title to show for the note at `path` in the backlinks and tag lists, with
+    /// `ui.display_titles`.
+    pub fn note_title(&self, path: &Path) -> Option<&str> {
+        if !self.config.ui.display_titles {
+            return None;
         }
-        if key_matches(key, &[self.resolved_keys.editor_insert]) {
-            self.editor_mode = EditorMode::Insert;
-            return true;
+        self.links.title(path)
+    }
+
+    /// Notes linking to the current file with `[[name]]`, from the link graph.
+    pub fn scan_backlinks(&mut self) {
+        self.backlinks = self
+            .editing_path()
+            .map(|path| self.links.backlinks(&path))
+            .unwrap_or_default();
+        self.backlinks_selected = 0;
+    }
+
+    pub fn backlinks_move_up(&mut self) {
+        if self.backlinks_selected > 0 {
+            self.backlinks_selected -= 1;
         }
-        if key_matches(key, &[self.resolved_keys.editor_append]) {
-            if let Some(buf) = self.focused_buffer_mut() {
-                buf.textarea.move_cursor(CursorMove::Forward);
-            }
-            self.editor_mode = EditorMode::Insert;
-            return true;
+    }
+
+    pub fn backlinks_move_down(&mut self) {
+        if self.backlinks_selected + 1 < self.backlinks.len() {
+            self.backlinks_selected += 1;
         }
-        let Some(buf) = self.focused_buffer_mut() else {
-            return false;
-        };
-        match key.code {
-            KeyCode::Char('u') => {
-                buf.textarea.undo();
-                return true;
-            }
-            KeyCode::Char('h') | KeyCode::Left => buf.textarea.move_cursor(CursorMove::Back),
-            KeyCode::Char('j') | KeyCode::Down => buf.textarea.move_cursor(CursorMove::Down),
-            KeyCode::Char('k') | KeyCode::Up => buf.textarea.move_cursor(CursorMove::Up),
-            KeyCode::Char('l') | KeyCode::Right => buf.textarea.move_cursor(CursorMove::Forward),
-            KeyCode::Home => buf.textarea.move_cursor(CursorMove::Head),
-            KeyCode::End => buf.textarea.move_cursor(CursorMove::End),
-            KeyCode::PageUp => buf.textarea.scroll(Scrolling::PageUp),
-            KeyCode::PageDown => buf.textarea.scroll(Scrolling::PageDown),
-            _ => return false,
+    }
+
+    pub fn open_selected_backlink(&mut self) -> Result<()> {
+        if let Some(path) = self.backlinks.get(self.backlinks_selected).cloned() {
+            self.record_jump();
+            self.load_file_into_editor(path)?;
         }
-        true
+        Ok(())
     }
 
-    // Telescope (Space+f)
-    pub fn enter_telescope(&mut self) {
-        self.focus = Focus::Search;
-        self.telescope_notes = find_md_files_recursive(&self.notes_dir);
-        self.telescope_filtered = self.telescope_notes.clone();
-        self.telescope_query.clear();
-        self.telescope_selected = 0;
-        self.apply_telescope_filter();
+    // Tag Explorer
+    pub fn enter_tag_explorer(&mut self) {
+        self.tag_explorer_view = TagExplorerView::TagList;
+        self.focus = Focus::TagExplorer;
+        self.scan_all_tags();
     }
 
-    pub fn exit_telescope(&mut self) {
-        self.focus = if self.has_open_buffers() {
-            Focus::Editor
-        } else {
-            Focus::List
-        };
+    pub fn exit_tag_explorer(&mut self) {
+        self.focus = Focus::List;
     }
 
-    pub fn telescope_add_char(&mut self, c: char) {
-        self.telescope_query.push(c);
-        self.apply_telescope_filter();
-        self.telescope_selected = 0;
+    pub fn scan_all_tags(&mut self) {
+        let walk = vault::walk(&self.notes_dir, &self.ignore);
+        self.report_walk_warnings(&walk);
+        self.all_tags = scan::tags(&walk).into_iter().map(|t| t.tag).collect();
+        self.all_tags.dedup();
+        self.tag_selected = 0;
+        self.tag_files.clear();
+        self.tag_file_selected = 0;
     }
 
-    pub fn telescope_backspace(&mut self) {
-        self.telescope_query.pop();
-        self.apply_telescope_filter();
-        self.telescope_selected = self
-            .telescope_selected
-            .saturating_sub(1)
-            .min(self.telescope_filtered.len().saturating_sub(1));
+    pub fn tag_list_move_up(&mut self) {
+        if self.tag_selected > 0 {
+            self.tag_selected -= 1;
+        }
     }
 
-    fn apply_telescope_filter(&mut self) {
-        self.telescope_filtered = filter_telescope_notes(
-            &self.telescope_notes,
-            &self.telescope_query,
-            &mut self.telescope_matcher,
-        );
-        self.telescope_match_indices = self
-            .telescope_filtered
-            .iter()
-            .map(|n| {
-                get_telescope_match_indices(
-                    &n.display,
-                    &self.telescope_query,
-                    &mut self.telescope_matcher,
-                )
-            })
-            .collect();
-        if self.telescope_selected >= self.telescope_filtered.len() {
-            self.telescope_selected = self.telescope_filtered.len().saturating_sub(1);
+    pub fn tag_list_move_down(&mut self) {
+        if self.tag_selected + 1 < self.all_tags.len() {
+            self.tag_selected += 1;
         }
     }
 
-    pub fn telescope_move_up(&mut self) {
-        if self.telescope_selected > 0 {
-            self.telescope_selected -= 1;
+    pub fn tag_file_move_up(&mut self) {
+        if self.tag_file_selected > 0 {
+            self.tag_file_selected -= 1;
         }
     }
 
-    pub fn telescope_move_down(&mut self) {
-        if self.telescope_selected + 1 < self.telescope_filtered.len() {
-            self.telescope_selected += 1;
+    pub fn tag_file_move_down(&mut self) {
+        if self.tag_file_selected + 1 < self.tag_files.len() {
+            self.tag_file_selected += 1;
         }
     }
 
-    pub fn get_telescope_selected_path(&self) -> Option<PathBuf> {
-        self.telescope_filtered
-            .get(self.telescope_selected)
-            .map(|n| n.path.clone())
+    pub fn load_files_for_selected_tag(&mut self) {
+        if let Some(tag) = self.all_tags.get(self.tag_selected) {
+            self.tag_files.clear();
+            self.tag_file_selected = 0;
+            let pattern = format!("#{tag}");
+
+            let walk = vault::walk(&self.notes_dir, &self.ignore);
+            for path in walk.markdown_files() {
+                if let Ok(content) = fs::read_to_string(path) {
+                    if content.contains(&pattern) {
+                        self.tag_files.push(path.to_path_buf());
+                    }
+                }
+            }
+            self.tag_files.sort();
+            self.tag_explorer_view = TagExplorerView::FileList;
+        }
     }
 
-    // Command palette (Ctrl+p)
-    pub fn enter_command_palette(&mut self) {
-        self.focus = Focus::CommandPalette;
-        self.command_palette_query.clear();
-        self.command_palette_filtered = CommandAction::all().to_vec();
-        self.command_palette_selected = 0;
+    pub fn open_selected_tag_file(&mut self) -> Result<()> {
+        if let Some(path) = self.tag_files.get(self.tag_file_selected).cloned() {
+            self.exit_tag_explorer();
+            self.load_file_into_editor(path)?;
+        }
+        Ok(())
     }
 
-    pub fn exit_command_palette(&mut self) {
-        self.focus = if self.has_open_buffers() {
-            Focus::Editor
-        } else {
-            Focus::List
-        };
+    // Global Task Board
+    pub fn enter_task_view(&mut self) {
+        self.focus = Focus::TaskView;
+        self.task_column = 0;
+        self.task_column_rows.clear();
+        self.scan_tasks();
     }
 
-    pub fn command_palette_add_char(&mut self, c: char) {
-        self.command_palette_query.push(c);
-        self.apply_command_palette_filter();
+    pub fn exit_task_view(&mut self) {
+        self.focus = Focus::List;
     }
 
-    pub fn command_palette_backspace(&mut self) {
-        self.command_palette_query.pop();
-        self.apply_command_palette_filter();
+    /// Scan the vault for unchecked tasks.
+    pub fn scan_tasks(&mut self) {
+        let walk = vault::walk(&self.notes_dir, &self.ignore);
+        self.report_walk_warnings(&walk);
+        self.tasks = scan::tasks(&walk, &self.config.task_columns);
+        self.task_column = self.task_column.min(self.task_column_count() - 1);
+        self.task_selected = self.task_column_range(self.task_column).start;
     }
 
-    fn apply_command_palette_filter(&mut self) {
-        let q = self.command_palette_query.to_lowercase();
-        self.command_palette_filtered = CommandAction::all()
-            .iter()
-            .filter(|a| a.label().to_lowercase().contains(&q))
-            .copied()
-            .collect();
-        self.command_palette_selected = 0;
+    /// Columns on the task board; one when `task_columns` is empty.
+    pub fn task_column_count(&self) -> usize {
+        self.config.task_columns.len().max(1)
     }
 
-    pub fn command_palette_move_up(&mut self) {
-        if self.command_palette_selected > 0 {
-            self.command_palette_selected -= 1;
-        }
+    /// Indices in `tasks` of the tasks in board column `column`.
+    pub fn task_column_range(&self, column: usize) -> std::ops::Range<usize> {
+        let start = self.tasks.partition_point(|t| t.column < column);
+        let end = self.tasks.partition_point(|t| t.column <= column);
+        start..end
     }
 
-    pub fn command_palette_move_down(&mut self) {
-        if self.command_palette_selected + 1 < self.command_palette_filtered.len() {
-            self.command_palette_selected += 1;
+    /// The selected task; `None` when its column is empty.
+    pub fn selected_task(&self) -> Option<&TaskEntry> {
+        self.tasks
+            .get(self.task_selected)
+            .filter(|t| t.column == self.task_column)
+    }
+
+    pub fn task_move_up(&mut self) {
+        if self.task_selected > self.task_column_range(self.task_column).start {
+            self.task_selected -= 1;
         }
     }
 
-    pub fn get_command_palette_action(&self) -> Option<CommandAction> {
-        self.command_palette_filtered
-            .get(self.command_palette_selected)
-            .copied()
+    pub fn task_move_down(&mut self) {
+        if self.task_selected + 1 < self.task_column_range(self.task_column).end {
+            self.task_selected += 1;
+        }
     }
 
-    // Rename popup (r)
-    pub fn enter_rename(&mut self) {
-        if let Some(entry) = self.filtered_notes.get(self.selected) {
-            let name = entry
-                .path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("")
-                .to_string();
-            self.rename_input = name;
-            self.focus = Focus::Rename;
+    /// Move to the board column left (`-1`) or right (`1`) of the current one, back to the
+    /// row that was selected when it was left.
+    pub fn task_move_column(&mut self, delta: isize) {
+        let Some(column) = self
+            .task_column
+            .checked_add_signed(delta)
+            .filter(|&c| c < self.task_column_count())
+        else {
+            return;
+        };
+        let row = self
+            .task_selected
+            .saturating_sub(self.task_column_range(self.task_column).start);
+        if self.task_column_rows.len() <= self.task_column {
+            self.task_column_rows.resize(self.task_column + 1, 0);
         }
+        self.task_column_rows[self.task_column] = row;
+        self.task_column = column;
+        let row = self.task_column_rows.get(column).copied().unwrap_or(0);
+        self.select_task_row(row);
     }
 
-    pub fn exit_rename(&mut self) {
-        self.focus = Focus::List;
-        self.rename_input.clear();
+    /// Select row `row` of the current column, or its last row when it is shorter.
+    fn select_task_row(&mut self, row: usize) {
+        let range = self.task_column_range(self.task_column);
+        self.task_selected = (range.start + row)
+            .min(range.end.saturating_sub(1))
+            .max(range.start);
     }
 
-    pub fn rename_add_char(&mut self, c: char) {
-        self.rename_input.push(c);
+    /// Scan the tasks again, keeping the same row of the current column selected.
+    fn rescan_tasks(&mut self) {
+        let row = self
+            .task_selected
+            .saturating_sub(self.task_column_range(self.task_column).start);
+        self.scan_tasks();
+        self.select_task_row(row);
     }
 
-    pub fn rename_backspace(&mut self) {
-        self.rename_input.pop();
+    /// Check or uncheck the selected task on the board.
+    pub fn toggle_selected_task(&mut self) -> Result<()> {
+        let (today, recurring) = (tasks::today(), self.config.editor.recurring_tasks);
+        self.edit_selected_task(|line| tasks::toggle(line, today, recurring))
     }
 
-    pub fn rename_selected_note(&mut self) -> Result<()> {
-        let Some(entry) = self.filtered_notes.get(self.selected) else {
+    /// Move the selected task to the next column of the board by rewriting its status tag.
+    pub fn advance_selected_task(&mut self) -> Result<()> {
+        let Some(task) = self.selected_task() else {
             return Ok(());
         };
-        let old_path = entry.path.clone();
-        let is_dir = entry.is_directory;
-        let name = self.rename_input.trim();
-        if name.is_empty() {
-            self.message = Some("Name cannot be empty".to_string());
+        let next = task.column + 1;
+        if next >= self.config.task_columns.len() {
+            self.push_message("The task is in the last column".to_string());
             return Ok(());
         }
-        let name = if is_dir || std::path::Path::new(name).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("md")) {
-            name.to_string()
-        } else {
-            format!("{name}.md")
-        };
-        let parent = old_path.parent().unwrap_or(&self.current_dir);
-        let new_path = parent.join(&name);
-        if new_path.exists() && new_path != old_path {
-            self.message = Some("File already exists".to_string());
+        let columns = self.config.task_columns.clone();
+        self.edit_selected_task(|line| Some(vec![tasks::set_column(line, &columns, next)]))
+    }
+
+    /// Replace the selected task's line with the lines `edit` makes of it, in its buffer if
+    /// the note is open (then saved, as the board reads the notes from disk), else in the
+    /// file, and scan the tasks again.
+    fn edit_selected_task(&mut self, edit: impl Fn(&str) -> Option<Vec<String>>) -> Result<()> {
+        let Some(task) = self.selected_task() else {
             return Ok(());
-        }
-        let was_editing = self
+        };
+        let (path, row, content) = (task.path.clone(), task.line_number, task.content.clone());
+        // The note may have changed since the board was filled.
+        let is_task = |line: &str| {
+            let line = line.trim_start();
+            line.starts_with("- [ ]") && line.trim_start_matches("- [ ]").trim() == content
+        };
+        if let Some(buf) = self
             .buffers
-            .iter()
-            .any(|b| b.path.as_ref() == Some(&old_path));
-        fs::rename(&old_path, &new_path)?;
-        self.refresh_notes()?;
-        if was_editing {
-            let _ = self.load_file_into_editor(new_path);
+            .iter_mut()
+            .find(|b| b.path.as_ref() == Some(&path))
+        {
+            let line = buf.textarea.lines().get(row).filter(|l| is_task(l));
+            let Some(lines) = line.and_then(|l| edit(l)) else {
+                anyhow::bail!("The task has moved; reopen the task board");
+            };
+            let (cursor_row, col) = buf.textarea.cursor();
+            replace_line(&mut buf.textarea, row, &lines);
+            let cursor_row = if cursor_row >= row {
+                cursor_row + lines.len() - 1
+            } else {
+                cursor_row
+            };
+            #[allow(clippy::cast_possible_truncation)]
+            buf.textarea
+                .move_cursor(CursorMove::Jump(cursor_row as u16, col as u16));
+            buf.dirty = true;
+            self.save_edited_buffers()?;
+        } else {
+            let content = fs::read_to_string(&path)?;
+            let mut lines: Vec<String> = content.lines().map(String::from).collect();
+            let line = lines.get(row).filter(|l| is_task(l));
+            let Some(edited) = line.and_then(|l| edit(l)) else {
+                anyhow::bail!("The task has moved; reopen the task board");
+            };
+            lines.splice(row..=row, edited);
+            let mut content = lines.join("\n");
+            content.push('\n');
+            fs::write(&path, content)?;
+            self.links.update(&path, &self.ignore);
+        }
+        self.rescan_tasks();
+        Ok(())
+    }
+
+    pub fn open_selected_task(&mut self) -> Result<()> {
+        if let Some(task) = self.selected_task() {
+            let path = task.path.clone();
+            let line = task.line_number;
+            self.exit_task_view();
+            self.record_jump();
+            self.load_file_into_editor_at_line(path, Some(line))?;
         }
-        self.exit_rename();
-        self.message = Some("Renamed".to_string());
         Ok(())
     }
 
-    // Create directory popup (Shift+n)
-    pub fn enter_create_directory(&mut self) {
-        self.directory_input.clear();
-        self.focus = Focus::CreatingDirectory;
+    // Templates
+    pub fn enter_template_picker(&mut self) {
+        self.template_picker_active = true;
+        self.template_picker_selected = 0;
+    }
+
+    pub fn exit_template_picker(&mut self) {
+        self.template_picker_active = false;
+    }
+
+    pub fn template_picker_move_up(&mut self) {
+        if self.template_picker_selected > 0 {
+            self.template_picker_selected -= 1;
+        }
     }
 
-    pub fn exit_create_directory(&mut self) {
-        self.focus = Focus::List;
-        self.directory_input.clear();
+    pub fn template_picker_move_down(&mut self) {
+        let max = Template::all().len().saturating_sub(1);
+        if self.template_picker_selected < max {
+            self.template_picker_selected += 1;
+        }
     }
 
-    pub fn directory_add_char(&mut self, c: char) {
-        self.directory_input.push(c);
+    pub fn get_selected_template(&self) -> Template {
+        Template::all()
+            .get(self.template_picker_selected)
+            .copied()
+            .unwrap_or(Template::Empty)
     }
 
-    pub fn directory_backspace(&mut self) {
-        self.directory_input.pop();
+    pub fn create_note_with_template(&mut self, template: Template) -> Result<Option<PathBuf>> {
+        let name = self.create_filename.clone();
+        let path = self.create_note_from_filename(&name, template);
+        self.exit_create_mode();
+        self.exit_template_picker();
+        path
     }
 
-    pub fn create_directory(&mut self) -> Result<()> {
-        let name = self.directory_input.trim().to_string();
+    fn create_note_from_filename(
+        &mut self,
+        name: &str,
+        template: Template,
+    ) -> Result<Option<PathBuf>> {
+        let name = name.trim();
         if name.is_empty() {
-            self.message = Some("Directory name cannot be empty".to_string());
-            return Ok(());
+            return Ok(None);
         }
-        let path = self.current_dir.join(&name);
+        let has_extension = Path::new(name)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+            || crypt::is_encrypted(Path::new(name));
+        let name = if has_extension {
+            name.to_string()
+        } else {
+            format!("{name}.md")
+        };
+        let current_dir = self.current_dir.clone();
+        let Some(path) = vault::resolve_path(&self.notes_dir, &current_dir, &name) else {
+            self.push_message("Path must stay inside the notes directory".to_string());
+            return Ok(None);
+        };
+        let (path, content) = self.with_zettel_id(path, template.content());
         if path.exists() {
-            self.message = Some("Directory already exists".to_string());
-            return Ok(());
+            self.push_message("File already exists".to_string());
+            return Ok(None);
         }
-        fs::create_dir(&path).map_err(|e| anyhow::anyhow!("Failed to create directory: {e}"))?;
-        self.exit_create_directory();
-        self.refresh_notes()?;
-        self.message = Some(format!("Created directory: {name}"));
-        Ok(())
-    }
-
-    // Zen mode
-    pub fn toggle_zen_mode(&mut self) {
-        self.zen_mode = !self.zen_mode;
+        if let Some(dir) = path.parent() {
+            vault::create_dirs(&self.notes_dir, dir)?;
+        }
+        if crypt::is_encrypted(&path) {
+            crypt::encrypt(&path, &content, &self.config.encryption)?;
+        } else {
+            fs::write(&path, content)?;
+        }
+        self.links.update(&path, &self.ignore);
+        self.clear_message();
+        if let Err(e) = self.refresh_notes() {
+            self.report_err("Refresh", e);
+        }
+        self.select_path(&path);
+        Ok(Some(path))
     }
 
-    // Git status
-    pub fn git_status(&self) -> GitStatus {
-        get_git_status(&self.notes_dir)
+    /// With `zettel_ids`, the path and content of a new note with a fresh ID: in front of the
+    /// file name, or in the frontmatter with `zettel_id_in_frontmatter`.
+    fn with_zettel_id(&self, path: PathBuf, content: String) -> (PathBuf, String) {
+        if !self.config.zettel_ids {
+            return (path, content);
+        }
+        let id = zettel::new_id(&self.config.zettel_id_format);
+        if self.config.zettel_id_in_frontmatter {
+            return (path, zettel::with_frontmatter_id(&content, &id));
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let path = path.with_file_name(format!("{id}-{name}"));
+        (path, content)
     }
 
-    // Checkbox toggle (Ctrl+Space)
-    #[allow(dead_code)]
-    fn toggle_checkbox_at_cursor(&mut self) {
+    pub fn insert_date_at_cursor(&mut self) {
         let idx = self.focused_buffer_index();
-        let (row, col, lines) = {
-            let Some(buf) = self.buffers.get_mut(idx) else { return };
-            let (r, c) = buf.textarea.cursor();
-            let l = buf.textarea.lines().to_vec();
-            (r, c, l)
-        };
-        let Some(line) = lines.get(row) else { return };
-        let line = line.clone();
-        let Ok(re_unchecked) = Regex::new(r"^(\s*[-*]\s+)\[\s?\]") else { return };
-        let Ok(re_checked) = Regex::new(r"^(\s*[-*]\s+)\[[xX]\]") else { return };
-        let new_line = if re_unchecked.is_match(&line) {
-            re_unchecked.replace(&line, "${1}[x]").into_owned()
-        } else if re_checked.is_match(&line) {
-            re_checked.replace(&line, "${1}[ ]").into_owned()
-        } else {
+        let Some(buf) = self.buffers.get_mut(idx) else {
+            return;
+        };
+        let date = Local::now().format(DAILY_NOTE_DATE_FORMAT).to_string();
+        let (r, c) = buf.textarea.cursor();
+        let l = buf.textarea.lines().to_vec();
+        let (date, row, col, mut lines) = (date, r, c, l);
+        let Some(line) = lines.get_mut(row) else {
             return;
         };
-        let mut new_lines = lines;
-        new_lines[row].clone_from(&new_line);
-        let new_col = col.min(new_line.len());
+        let mut s = line.clone();
+        if col <= s.len() {
+            s.insert_str(col, &date);
+        } else {
+            s.push_str(&date);
+        }
+        lines[row] = s;
         let theme = self.theme.clone();
         if let Some(buf) = self.buffers.get_mut(idx) {
-            buf.textarea = TextArea::new(new_lines);
+            buf.textarea = TextArea::new(lines);
             buf.textarea.set_max_histories(50);
             Self::apply_theme_to_textarea(&theme, &mut buf.textarea, &self.config.editor);
             #[allow(clippy::cast_possible_truncation)]
             let r = row as u16;
             #[allow(clippy::cast_possible_truncation)]
-            let c = new_col.min(u16::MAX as usize) as u16;
+            let c = (col + date.len()).min(u16::MAX as usize) as u16;
             buf.textarea.move_cursor(CursorMove::Jump(r, c));
         }
     }
 
-    // Wiki link: [[Filename]] under cursor
-    pub fn get_wiki_link_under_cursor(&self) -> Option<String> {
-        let buf = self.focused_buffer()?;
-        let (row, col) = buf.textarea.cursor();
-        let lines = buf.textarea.lines();
-        let line = lines.get(row)?;
-        let re = Regex::new(r"\[\[([^\]]+)\]\]").ok()?;
-        for cap in re.captures_iter(line) {
-            let m = cap.get(0)?;
-            let start = m.start();
-            let end = m.end();
-            if col >= start && col <= end {
-                return Some(cap.get(1)?.as_str().to_string());
+    pub fn git_push(&mut self) -> Result<()> {
+        self.git_checked = None;
+        git::run(&self.notes_dir, &["push"])?;
+        self.push_message("Git push done".to_string());
+        Ok(())
+    }
+
+    /// Git Init: make the vault a repository with a `.gitignore` and a first commit of the
+    /// notes, then ask for a remote to push to.
+    pub fn git_init(&mut self) {
+        if self.notes_dir.join(".git").exists() {
+            self.push_message("The vault is already a git repository".to_string());
+            return;
+        }
+        if let Err(e) = git::run(&self.notes_dir, &["init"]) {
+            self.report_err("Git init", e);
+            return;
+        }
+        self.git_checked = None;
+        self.push_message(format!(
+            "Initialized a git repository in {}",
+            self.notes_dir.display()
+        ));
+        let gitignore = self.notes_dir.join(".gitignore");
+        if !gitignore.exists() {
+            let assets = self.config.editor.assets_folder.trim().trim_matches('/');
+            let content =
+                format!(".trash/\n# Keep pasted images out of the repository:\n# {assets}/\n");
+            if let Err(e) = fs::write(&gitignore, content) {
+                self.push_message(format!("Cannot write .gitignore: {e}"));
             }
         }
-        None
+        let committed = git::run(&self.notes_dir, &["add", "-A"]).and_then(|_| {
+            git::run(
+                &self.notes_dir,
+                &["commit", "--allow-empty", "-m", "Initial commit"],
+            )
+        });
+        match committed {
+            Ok(_) => self.push_message("Committed the existing notes".to_string()),
+            Err(e) => self.report_err("Initial commit", e),
+        }
+        if let Err(e) = self.refresh_notes() {
+            self.report_err("Refresh", e);
+        }
+        self.git_remote_input.clear();
+        self.focus = Focus::GitRemotePrompt;
     }
 
-    pub fn open_wiki_link(&mut self, link: &str) -> Result<()> {
-        let _ = self.save_editor();
-        let name = if std::path::Path::new(link).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("md")) {
-            link.to_string()
-        } else {
-            format!("{link}.md")
+    pub fn git_remote_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.git_remote_input.handle_key(key);
+    }
+
+    pub fn git_remote_backspace(&mut self) {
+        self.git_remote_input.backspace();
+    }
+
+    pub fn exit_git_remote_prompt(&mut self) {
+        self.git_remote_input.clear();
+        self.focus = Focus::List;
+    }
+
+    /// Diff Unsaved Changes: what saving the focused buffer would change in its file.
+    pub fn show_buffer_diff(&mut self) {
+        let Some(buf) = self.focused_buffer().filter(|b| b.path.is_some()) else {
+            self.push_message("Open a note to diff".to_string());
+            return;
         };
-        let path = self
-            .editing_path()
-            .as_ref()
-            .and_then(|p| p.parent())
-            .unwrap_or(&self.current_dir)
-            .join(&name);
-        if path.exists() {
-            self.load_file_into_editor(path)?;
+        let path = buf.path.clone().unwrap_or_default();
+        let content = buf.textarea.lines().join("\n");
+        let on_disk = if !path.exists() {
+            Ok(String::new())
+        } else if crypt::is_encrypted(&path) {
+            crypt::decrypt(&path, &self.config.encryption)
         } else {
-            let path = self.current_dir.join(&name);
-            if path.exists() {
-                self.load_file_into_editor(path)?;
-            } else {
-                fs::File::create(&path)?;
-                self.load_file_into_editor(path)?;
+            fs::read_to_string(&path).map_err(Into::into)
+        };
+        let on_disk = match on_disk {
+            Ok(text) => text,
+            Err(e) => {
+                self.report_err("Diff", e);
+                return;
             }
+        };
+        let name = self.vault_relative(&path);
+        let mut lines = diff::unified(&on_disk, &content);
+        if lines.is_empty() {
+            self.push_message(format!("No unsaved changes in {name}"));
+            return;
         }
-        Ok(())
+        lines.splice(
+            0..0,
+            [
+                (DiffLineKind::Header, format!("--- {name} (on disk)")),
+                (DiffLineKind::Header, format!("+++ {name} (buffer)")),
+            ],
+        );
+        self.open_diff_view(format!("Unsaved Changes │ {name}"), lines);
     }
 
-    /// Scan for backlinks to the current file. Returns paths of files containing [[`current_file_name`]].
-    /// Uses a cache to avoid re-scanning on every call.
-    pub fn scan_backlinks(&mut self) {
-        let current_path = self.editing_path();
-        if self.backlinks_cache_valid
-            && self.cached_backlink_target.as_ref() == current_path.as_ref()
-        {
+    /// Git Diff File: `git diff` of the open note, or the selected file, against HEAD.
+    pub fn show_git_diff(&mut self) {
+        let buffer = self.focused_buffer().filter(|b| b.path.is_some());
+        let Some(path) = buffer
+            .and_then(|b| b.path.clone())
+            .or_else(|| self.get_selected_path().filter(|p| p.is_file()))
+        else {
+            self.push_message("No file to diff".to_string());
             return;
-        }
-        self.backlinks.clear();
-        self.backlinks_selected = 0;
-        self.cached_backlink_target = current_path.clone();
-
-        let current_file_name = match current_path.as_ref() {
-            Some(p) => p
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .map(std::string::ToString::to_string),
-            None => return,
         };
-        let Some(target_name) = current_file_name else {
+        if !self.notes_dir.join(".git").exists() {
+            self.push_message("The vault is not a git repository (see Git Init)".to_string());
             return;
-        };
-        let pattern = format!("[[{target_name}]]");
-
-        for entry in WalkDir::new(&self.notes_dir)
-            .follow_links(true)
-            .into_iter()
-            .filter_map(std::result::Result::ok)
-        {
-            let path = entry.path();
-            if !path.is_file() || path.extension().is_none_or(|e| e != "md") {
-                continue;
-            }
-            if current_path.as_ref() == Some(&path.to_path_buf()) {
-                continue;
-            }
-            if let Ok(content) = fs::read_to_string(path) {
-                if content.contains(&pattern) {
-                    self.backlinks.push(path.to_path_buf());
-                }
+        }
+        let name = self.vault_relative(&path);
+        let output = match git::run(&self.notes_dir, &["diff", "HEAD", "--", &name]) {
+            Ok(output) => output,
+            Err(e) => {
+                self.report_err("Git diff", e);
+                return;
             }
+        };
+        if output.trim().is_empty() {
+            self.push_message(format!("No changes to {name} since the last commit"));
+            return;
         }
-        self.backlinks.sort();
+        let lines = output
+            .lines()
+            .map(|l| (diff::classify(l), l.to_string()))
+            .collect();
+        self.open_diff_view(format!("Git Diff │ {name}"), lines);
     }
 
-    pub fn backlinks_move_up(&mut self) {
-        if self.backlinks_selected > 0 {
-            self.backlinks_selected -= 1;
-        }
+    fn open_diff_view(&mut self, title: String, lines: Vec<(DiffLineKind, String)>) {
+        self.diff_title = title;
+        self.diff_lines = lines;
+        self.diff_scroll = 0;
+        self.focus = Focus::DiffView;
     }
 
-    pub fn backlinks_move_down(&mut self) {
-        if self.backlinks_selected + 1 < self.backlinks.len() {
-            self.backlinks_selected += 1;
-        }
+    pub fn exit_diff_view(&mut self) {
+        self.diff_lines.clear();
+        self.focus = if self.has_open_buffers() {
+            Focus::Editor
+        } else {
+            Focus::List
+        };
     }
 
-    pub fn open_selected_backlink(&mut self) -> Result<()> {
-        if let Some(path) = self.backlinks.get(self.backlinks_selected).cloned() {
-            self.load_file_into_editor(path)?;
-        }
-        Ok(())
+    pub fn diff_scroll_by(&mut self, lines: isize) {
+        self.diff_scroll = self
+            .diff_scroll
+            .saturating_add_signed(lines)
+            .min(self.diff_lines.len().saturating_sub(1));
     }
 
-    // Tag Explorer
-    pub fn enter_tag_explorer(&mut self) {
-        self.tag_explorer_active = true;
-        self.tag_explorer_view = TagExplorerView::TagList;
-        self.focus = Focus::TagExplorer;
-        self.scan_all_tags();
+    /// `path` relative to the vault, for messages and git.
+    fn vault_relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.notes_dir)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned()
     }
 
-    pub fn exit_tag_explorer(&mut self) {
-        self.tag_explorer_active = false;
-        self.focus = Focus::List;
+    /// Set the URL in the prompt as `origin`, so Git Push has somewhere to push to. An
+    /// empty prompt leaves the repository without a remote.
+    pub fn confirm_git_remote(&mut self) {
+        let url = self.git_remote_input.trim().to_string();
+        self.exit_git_remote_prompt();
+        if url.is_empty() {
+            return;
+        }
+        match git::run(&self.notes_dir, &["remote", "add", "origin", &url]) {
+            Ok(_) => self.push_message(format!("Remote origin set to {url}")),
+            Err(e) => self.report_err("Git remote", e),
+        }
+        self.git_checked = None;
     }
 
-    pub fn scan_all_tags(&mut self) {
-        use std::collections::HashSet;
-        let mut tags = HashSet::new();
-        let Ok(re) = Regex::new(r"#(\w+)") else { return };
-
-        for entry in WalkDir::new(&self.notes_dir)
-            .follow_links(true)
-            .into_iter()
-            .filter_map(std::result::Result::ok)
-        {
-            let path = entry.path();
-            if !path.is_file() || path.extension().is_none_or(|e| e != "md") {
-                continue;
-            }
-            if let Ok(content) = fs::read_to_string(path) {
-                for cap in re.captures_iter(&content) {
-                    if let Some(tag) = cap.get(1) {
-                        tags.insert(tag.as_str().to_string());
-                    }
+    /// Cycle split view: Single -> side by side -> stacked -> Single.
+    /// Entering the split asks which buffer to show when there is more than one candidate.
+    pub fn toggle_split_view(&mut self) {
+        if self.editor_layout == EditorLayout::Single && self.buffers.len() > 2 {
+            self.enter_buffer_picker();
+            return;
+        }
+        self.editor_layout = match self.editor_layout {
+            EditorLayout::Single => {
+                if self.buffers.len() >= 2 {
+                    let right = (self.active_tab + 1) % self.buffers.len();
+                    self.split_right_id = self.buffers.get(right).map(|b| b.id);
+                    self.split_focus_left = true;
+                    EditorLayout::SplitVertical
+                } else {
+                    EditorLayout::Single
                 }
             }
+            EditorLayout::SplitVertical => EditorLayout::SplitHorizontal,
+            EditorLayout::SplitHorizontal => {
+                self.split_right_id = None;
+                EditorLayout::Single
+            }
+        };
+    }
+
+    /// Buffers that can go in the split's second pane (everything but the active tab).
+    pub fn buffer_picker_candidates(&self) -> Vec<usize> {
+        split_picker(self.buffers.len(), self.active_tab, None).0
+    }
+
+    /// Open the buffer picker for the split's second pane.
+    pub fn enter_buffer_picker(&mut self) {
+        if self.buffers.len() < 2 {
+            self.push_message("Open another file to use split view".to_string());
+            return;
         }
+        let shown = self.split_right_tab();
+        self.buffer_picker_selected = split_picker(self.buffers.len(), self.active_tab, shown).1;
+        self.focus = Focus::BufferPicker;
+    }
 
-        self.all_tags = tags.into_iter().collect();
-        self.all_tags.sort();
-        self.tag_selected = 0;
-        self.tag_files.clear();
-        self.tag_file_selected = 0;
+    /// Cancel the picker. When no split is shown yet, fall back to the quick toggle.
+    pub fn exit_buffer_picker(&mut self) {
+        self.focus = Focus::Editor;
+        if self.editor_layout == EditorLayout::Single {
+            let right = (self.active_tab + 1) % self.buffers.len().max(1);
+            self.show_in_split(right);
+        }
     }
 
-    pub fn tag_list_move_up(&mut self) {
-        if self.tag_selected > 0 {
-            self.tag_selected -= 1;
+    pub fn buffer_picker_move_up(&mut self) {
+        if self.buffer_picker_selected > 0 {
+            self.buffer_picker_selected -= 1;
         }
     }
 
-    pub fn tag_list_move_down(&mut self) {
-        if self.tag_selected + 1 < self.all_tags.len() {
-            self.tag_selected += 1;
+    pub fn buffer_picker_move_down(&mut self) {
+        if self.buffer_picker_selected + 1 < self.buffer_picker_candidates().len() {
+            self.buffer_picker_selected += 1;
         }
     }
 
-    pub fn tag_file_move_up(&mut self) {
-        if self.tag_file_selected > 0 {
-            self.tag_file_selected -= 1;
+    /// Show the picked buffer in the split's second pane.
+    pub fn confirm_buffer_picker(&mut self) {
+        self.focus = Focus::Editor;
+        if let Some(&idx) = self
+            .buffer_picker_candidates()
+            .get(self.buffer_picker_selected)
+        {
+            self.show_in_split(idx);
         }
     }
 
-    pub fn tag_file_move_down(&mut self) {
-        if self.tag_file_selected + 1 < self.tag_files.len() {
-            self.tag_file_selected += 1;
+    /// Put buffer `idx` in the second pane, creating a side-by-side split if needed.
+    fn show_in_split(&mut self, idx: usize) {
+        if idx == self.active_tab {
+            return;
+        }
+        let Some(buf) = self.buffers.get(idx) else {
+            return;
+        };
+        self.split_right_id = Some(buf.id);
+        self.split_focus_left = true;
+        if self.editor_layout == EditorLayout::Single {
+            self.editor_layout = EditorLayout::SplitVertical;
         }
     }
 
-    pub fn load_files_for_selected_tag(&mut self) {
-        if let Some(tag) = self.all_tags.get(self.tag_selected) {
-            self.tag_files.clear();
-            self.tag_file_selected = 0;
-            let pattern = format!("#{tag}");
+    /// Open `path` in the split's second pane and focus it, keeping the current tab on the left.
+    pub fn open_in_split(&mut self, path: PathBuf) -> Result<()> {
+        let left = self.active_tab;
+        let had_real_buffer = self.buffers.get(left).is_some_and(|b| b.path.is_some());
+        self.load_file_into_editor(path)?;
+        let opened = self.active_tab;
+        if !had_real_buffer || opened == left {
+            // Nothing worth keeping on the left; the file simply opens as the only pane.
+            return Ok(());
+        }
+        self.active_tab = left;
+        self.show_in_split(opened);
+        self.split_focus_left = false;
+        Ok(())
+    }
 
-            for entry in WalkDir::new(&self.notes_dir)
-                .follow_links(true)
-                .into_iter()
-                .filter_map(std::result::Result::ok)
-            {
-                let path = entry.path();
-                if !path.is_file() || path.extension().is_none_or(|e| e != "md") {
-                    continue;
-                }
-                if let Ok(content) = fs::read_to_string(path) {
-                    if content.contains(&pattern) {
-                        self.tag_files.push(path.to_path_buf());
-                    }
-                }
-            }
-            self.tag_files.sort();
-            self.tag_explorer_view = TagExplorerView::FileList;
+    /// Export current buffer to PDF via Pandoc.
+    pub fn export_to_pdf(&mut self) {
+        let buf = self.focused_buffer();
+        let Some(path) = buf.and_then(|b| b.path.as_ref()) else {
+            self.push_message("No Markdown file open".to_string());
+            return;
+        };
+        if path.extension().is_none_or(|e| e != "md") {
+            self.push_message("No Markdown file open".to_string());
+            return;
+        }
+        let path = path.clone();
+        if let Err(e) = self.save_editor() {
+            self.report_err("Save", e);
+        }
+        let output = path.with_extension("pdf");
+        match export_pdf(&path, &output) {
+            Ok(()) => self.push_message(format!("Exported to {}", output.display())),
+            Err(e) => self.push_message(format!("{e:#}")),
         }
     }
 
-    pub fn open_selected_tag_file(&mut self) -> Result<()> {
-        if let Some(path) = self.tag_files.get(self.tag_file_selected).cloned() {
-            self.exit_tag_explorer();
-            self.load_file_into_editor(path)?;
+    // HTML export (command palette)
+    pub fn enter_export_html(&mut self) {
+        self.export_scope_selected = 0;
+        self.focus = Focus::ExportScope;
+    }
+
+    pub fn exit_export_html(&mut self) {
+        self.export_summary = None;
+        self.exit_command_palette();
+    }
+
+    pub fn export_scope_move_up(&mut self) {
+        self.export_scope_selected = self.export_scope_selected.saturating_sub(1);
+    }
+
+    pub fn export_scope_move_down(&mut self) {
+        if self.export_scope_selected + 1 < ExportScope::ALL.len() {
+            self.export_scope_selected += 1;
         }
-        Ok(())
     }
 
-    // Global Task Board
-    pub fn enter_task_view(&mut self) {
-        self.task_view_active = true;
-        self.focus = Focus::TaskView;
-        self.scan_tasks();
+    /// Confirm the scope and ask for the output directory, suggesting `<vault>-html` next
+    /// to the vault.
+    pub fn confirm_export_scope(&mut self) {
+        let vault_name = self
+            .notes_dir
+            .file_name()
+            .map_or_else(|| "notes".into(), |n| n.to_string_lossy());
+        self.export_dir = TextInput::new(
+            self.notes_dir
+                .with_file_name(format!("{vault_name}-html"))
+                .display()
+                .to_string(),
+        );
+        self.focus = Focus::ExportDir;
     }
 
-    pub fn exit_task_view(&mut self) {
-        self.task_view_active = false;
-        self.focus = Focus::List;
+    pub fn export_dir_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.export_dir.handle_key(key);
     }
 
-    /// Recursively scan workspace for lines starting with `- [ ]` (unchecked tasks).
-    pub fn scan_tasks(&mut self) {
-        self.tasks.clear();
-        self.task_selected = 0;
+    pub fn export_dir_backspace(&mut self) {
+        self.export_dir.backspace();
+    }
 
-        for entry in WalkDir::new(&self.notes_dir)
-            .follow_links(true)
-            .into_iter()
-            .filter_map(std::result::Result::ok)
-        {
-            let path = entry.path();
-            if !path.is_file() || path.extension().is_none_or(|e| e != "md") {
-                continue;
+    /// Save open buffers and export the chosen scope into `export_dir`.
+    pub fn run_export_html(&mut self) {
+        let scope = ExportScope::ALL[self.export_scope_selected.min(ExportScope::ALL.len() - 1)];
+        let out_dir = expand_path(&self.export_dir);
+        if self.export_dir.trim().is_empty() {
+            self.push_message("Enter an output directory".to_string());
+            return;
+        }
+        if let Err(e) = self.save_all_buffers() {
+            self.report_err("Save", e);
+        }
+        let (files, base) = match scope {
+            ExportScope::CurrentFile => {
+                let Some(path) = self
+                    .editing_path()
+                    .filter(|p| p.extension().is_some_and(|e| e == "md"))
+                else {
+                    self.push_message("No Markdown file open".to_string());
+                    return;
+                };
+                let base = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                (vec![path], base)
             }
-            let path_buf = path.to_path_buf();
-            if let Ok(content) = fs::read_to_string(path) {
-                let mut in_code_block = false;
-                for (zero_based_line, line) in content.lines().enumerate() {
-                    let trimmed = line.trim_start();
-                    if trimmed.starts_with("```") {
-                        in_code_block = !in_code_block;
-                        continue;
-                    }
-                    if in_code_block {
-                        continue;
-                    }
-                    if trimmed.starts_with("- [ ]") {
-                        let task_content = trimmed
-                            .trim_start_matches("- [ ]")
-                            .trim()
-                            .to_string();
-                        self.tasks.push(TaskEntry {
-                            path: path_buf.clone(),
-                            line_number: zero_based_line,
-                            content: task_content,
-                        });
-                    }
-                }
+            ExportScope::CurrentDirectory | ExportScope::Vault => {
+                let dir = if scope == ExportScope::Vault {
+                    self.notes_dir.clone()
+                } else {
+                    self.current_dir.clone()
+                };
+                let walk = vault::walk(&dir, &self.ignore);
+                self.report_walk_warnings(&walk);
+                (collect_markdown(&dir, &walk, &out_dir), dir)
+            }
+        };
+        match export_html(&files, &base, &out_dir) {
+            Ok(summary) => {
+                self.export_summary = Some(summary);
+                self.export_summary_scroll = 0;
+                self.focus = Focus::ExportSummary;
             }
+            Err(e) => self.push_message(format!("HTML export failed: {e:#}")),
         }
     }
 
-    pub fn task_move_up(&mut self) {
-        if self.task_selected > 0 {
-            self.task_selected -= 1;
-        }
+    pub fn export_summary_scroll_up(&mut self) {
+        self.export_summary_scroll = self.export_summary_scroll.saturating_sub(1);
     }
 
-    pub fn task_move_down(&mut self) {
-        if self.task_selected + 1 < self.tasks.len() {
-            self.task_selected += 1;
+    pub fn export_summary_scroll_down(&mut self) {
+        let skipped = self
+            .export_summary
+            .as_ref()
+            .map_or(0, |s| s.skipped_links.len());
+        if self.export_summary_scroll + 1 < skipped {
+            self.export_summary_scroll += 1;
         }
     }
 
-    pub fn open_selected_task(&mut self) -> Result<()> {
-        if let Some(task) = self.tasks.get(self.task_selected) {
-            let path = task.path.clone();
-            let line = task.line_number;
-            self.exit_task_view();
-            self.load_file_into_editor_at_line(path, Some(line))?;
-        }
-        Ok(())
+    // Link graph export (command palette)
+    pub fn enter_export_graph(&mut self) {
+        self.graph_option_selected = 0;
+        self.focus = Focus::GraphOptions;
     }
 
-    // Templates
-    pub fn enter_template_picker(&mut self) {
-        self.template_picker_active = true;
-        self.template_picker_selected = 0;
+    pub fn exit_export_graph(&mut self) {
+        self.exit_command_palette();
     }
 
-    pub fn exit_template_picker(&mut self) {
-        self.template_picker_active = false;
+    pub fn graph_option_move_up(&mut self) {
+        self.graph_option_selected = self.graph_option_selected.saturating_sub(1);
     }
 
-    pub fn template_picker_move_up(&mut self) {
-        if self.template_picker_selected > 0 {
-            self.template_picker_selected -= 1;
+    pub fn graph_option_move_down(&mut self) {
+        if self.graph_option_selected < 2 {
+            self.graph_option_selected += 1;
         }
     }
 
-    pub fn template_picker_move_down(&mut self) {
-        let max = Template::all().len().saturating_sub(1);
-        if self.template_picker_selected < max {
-            self.template_picker_selected += 1;
+    /// Change the selected option: toggle format or orphans, or step the neighborhood size.
+    pub fn graph_option_change(&mut self, forward: bool) {
+        let options = &mut self.graph_options;
+        match self.graph_option_selected {
+            0 => {
+                options.format = match options.format {
+                    GraphFormat::Dot => GraphFormat::Json,
+                    GraphFormat::Json => GraphFormat::Dot,
+                };
+            }
+            1 => options.exclude_orphans = !options.exclude_orphans,
+            _ if forward => options.hops = (options.hops + 1).min(9),
+            _ => options.hops = options.hops.saturating_sub(1),
         }
     }
 
-    pub fn get_selected_template(&self) -> Template {
-        Template::all()
-            .get(self.template_picker_selected)
-            .copied()
-            .unwrap_or(Template::Empty)
+    /// Confirm the options and ask for the output file, suggesting `graph.dot` or
+    /// `graph.json` in the vault.
+    pub fn confirm_graph_options(&mut self) {
+        if self.graph_options.hops > 0 && self.editing_path().is_none() {
+            self.push_message("Open a note to export its neighborhood".to_string());
+            return;
+        }
+        self.graph_path = TextInput::new(
+            self.notes_dir
+                .join(format!("graph.{}", self.graph_options.format.extension()))
+                .display()
+                .to_string(),
+        );
+        self.focus = Focus::GraphPath;
     }
 
-    pub fn create_note_with_template(&mut self, template: Template) -> Result<Option<PathBuf>> {
-        let name = self.create_filename.clone();
-        let path = self.create_note_from_filename(&name, template)?;
-        self.exit_create_mode();
-        self.exit_template_picker();
-        Ok(path)
+    pub fn graph_path_key(&mut self, key: crossterm::event::KeyEvent) {
+        self.graph_path.handle_key(key);
     }
 
-    fn create_note_from_filename(
-        &mut self,
-        name: &str,
-        template: Template,
-    ) -> Result<Option<PathBuf>> {
-        let name = name.trim();
-        if name.is_empty() {
-            return Ok(None);
-        }
-        let name = if std::path::Path::new(name).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("md")) {
-            name.to_string()
-        } else {
-            format!("{name}.md")
-        };
-        let path = self.current_dir.join(&name);
-        if path.exists() {
-            self.message = Some("File already exists".to_string());
-            return Ok(None);
-        }
-        let content = template.content();
-        fs::write(&path, content)?;
-        self.message = None;
-        Ok(Some(path))
+    pub fn graph_path_backspace(&mut self) {
+        self.graph_path.backspace();
     }
 
-    pub fn insert_date_at_cursor(&mut self) {
-        let idx = self.focused_buffer_index();
-        let Some(buf) = self.buffers.get_mut(idx) else { return };
-        let date = Local::now().format(DAILY_NOTE_DATE_FORMAT).to_string();
-        let (r, c) = buf.textarea.cursor();
-        let l = buf.textarea.lines().to_vec();
-        let (date, row, col, mut lines) = (date, r, c, l);
-        let Some(line) = lines.get_mut(row) else { return };
-        let mut s = line.clone();
-        if col <= s.len() {
-            s.insert_str(col, &date);
-        } else {
-            s.push_str(&date);
+    /// Save open buffers (so the graph has their links) and start the export in the
+    /// background; `poll_graph_export` reports the result.
+    pub fn run_export_graph(&mut self) {
+        if self.graph_path.trim().is_empty() {
+            self.push_message("Enter an output file".to_string());
+            return;
         }
-        lines[row] = s;
-        let theme = self.theme.clone();
-        if let Some(buf) = self.buffers.get_mut(idx) {
-            buf.textarea = TextArea::new(lines);
-            buf.textarea.set_max_histories(50);
-            Self::apply_theme_to_textarea(&theme, &mut buf.textarea, &self.config.editor);
-            #[allow(clippy::cast_possible_truncation)]
-            let r = row as u16;
-            #[allow(clippy::cast_possible_truncation)]
-            let c = (col + date.len()).min(u16::MAX as usize) as u16;
-            buf.textarea.move_cursor(CursorMove::Jump(r, c));
+        if self.graph_export.is_some() {
+            self.push_message("A graph export is already running".to_string());
+            return;
+        }
+        if let Err(e) = self.save_all_buffers() {
+            self.report_err("Save", e);
         }
+        self.graph_export = Some(GraphExport::spawn(
+            self.links.clone(),
+            self.notes_dir.clone(),
+            self.editing_path(),
+            self.graph_options.clone(),
+            expand_path(&self.graph_path),
+        ));
+        self.push_message("Exporting link graph...".to_string());
+        self.exit_export_graph();
     }
 
-    pub fn git_push(&mut self) -> Result<()> {
-        Command::new("git")
-            .arg("push")
-            .current_dir(&self.notes_dir)
-            .status()?;
-        self.message = Some("Git push done".to_string());
-        Ok(())
+    /// Check on a running graph export. Returns true while it is still running.
+    pub fn poll_graph_export(&mut self) -> bool {
+        let Some(export) = &self.graph_export else {
+            return false;
+        };
+        let Some(result) = export.poll() else {
+            return true;
+        };
+        self.graph_export = None;
+        self.push_message(match result {
+            Ok(summary) => format!(
+                "Exported {} notes and {} links to {}",
+                summary.nodes,
+                summary.edges,
+                summary.path.display()
+            ),
+            Err(e) => format!("Graph export failed: {e:#}"),
+        });
+        false
     }
 
-    /// Toggle split view.
-    pub fn toggle_split_view(&mut self) {
-        self.editor_layout = match self.editor_layout {
-            EditorLayout::Single => {
-                if self.buffers.len() >= 2 {
-                    self.split_right_tab = Some((self.active_tab + 1) % self.buffers.len());
-                    self.split_focus_left = true;
-                    EditorLayout::SplitVertical
-                } else {
-                    EditorLayout::Single
-                }
-            }
-            EditorLayout::SplitVertical => {
-                self.split_right_tab = None;
-                EditorLayout::Single
-            }
+    /// Start a vault snapshot in the background when `[backup]` is enabled and one is due;
+    /// `poll_backup` reports the result.
+    fn start_backup_if_due(&mut self, now: Instant) {
+        let backup = &self.config.backup;
+        if !backup.enabled || self.backup.is_some() || now < self.backup_due {
+            return;
+        }
+        self.backup_due = now + backup.interval();
+        let Some(dir) = backup.path() else {
+            self.push_message("Backup skipped: no backup directory".to_string());
+            return;
         };
+        self.backup = Some(BackupJob::spawn(
+            self.notes_dir.clone(),
+            self.ignore.clone(),
+            dir,
+            backup.keep,
+            Arc::clone(&self.save_lock),
+        ));
     }
 
-    /// Export current buffer to PDF via Pandoc.
-    pub fn export_to_pdf(&mut self) {
-        let buf = self.focused_buffer();
-        let Some(path) = buf.and_then(|b| b.path.as_ref()) else {
-            self.message = Some("No Markdown file open".to_string());
-            return;
+    /// Check on a running snapshot. Returns true while it is still running.
+    pub fn poll_backup(&mut self) -> bool {
+        let Some(job) = &self.backup else {
+            return false;
         };
-        if path.extension().is_none_or(|e| e != "md") {
-            self.message = Some("No Markdown file open".to_string());
-            return;
-        }
-        let path = path.clone();
-        let _ = self.save_editor();
-        let output = path.with_extension("pdf");
-        let output_str = output.to_string_lossy();
-        let input_str = path.to_string_lossy();
-        let status = Command::new("pandoc")
-            .arg(&*input_str)
-            .arg("-o")
-            .arg(&*output_str)
-            .status();
-        match status {
-            Ok(s) if s.success() => {
-                self.message = Some(format!("Exported to {}", output.display()));
-            }
-            Ok(_) => {
-                self.message = Some("Pandoc failed".to_string());
-            }
-            Err(_) => {
-                self.message = Some("Pandoc not found - install pandoc".to_string());
-            }
-        }
+        let Some(result) = job.poll() else {
+            return true;
+        };
+        self.backup = None;
+        self.push_message(match result {
+            Ok(snapshot) if snapshot.pruned > 0 => format!(
+                "Backed up {} notes to {} (removed {} old)",
+                snapshot.notes,
+                snapshot.path.display(),
+                snapshot.pruned
+            ),
+            Ok(snapshot) => format!(
+                "Backed up {} notes to {}",
+                snapshot.notes,
+                snapshot.path.display()
+            ),
+            Err(e) => format!("Backup failed: {e:#}"),
+        });
+        false
     }
 
     /// Switch to next tab.
@@ -1708,19 +6451,313 @@ impl App {
         if self.buffers.len() <= 1 {
             return;
         }
-        let _ = self.save_editor();
-        self.buffers.remove(self.focused_buffer_index());
-        if self.active_tab >= self.buffers.len() {
-            self.active_tab = self.buffers.len() - 1;
+        if let Err(e) = self.save_editor() {
+            // Closing now would lose the unsaved text.
+            self.report_err("Save", e);
+            return;
+        }
+        self.remove_buffer(self.focused_buffer_index());
+    }
+
+    /// Close every tab except the focused one.
+    pub fn close_other_tabs(&mut self) {
+        if self.buffers.len() <= 1 {
+            return;
+        }
+        if let Err(e) = self.save_editor() {
+            // Closing now would lose the unsaved text.
+            self.report_err("Save", e);
+            return;
+        }
+        let keep = self.focused_buffer_index();
+        for idx in (0..self.buffers.len()).rev() {
+            if idx != keep {
+                self.remove_buffer(idx);
+            }
+        }
+        self.split_focus_left = true;
+    }
+
+    /// Close all tabs, leaving a single empty buffer, and return to the file list.
+    pub fn close_all_tabs(&mut self) {
+        if let Err(e) = self.save_editor() {
+            // Closing now would lose the unsaved text.
+            self.report_err("Save", e);
+            return;
+        }
+        for idx in (0..self.buffers.len()).rev() {
+            self.remove_buffer(idx);
+        }
+        self.buffers
+            .push(EditorBuffer::new(None, vec![String::new()]));
+        self.active_tab = 0;
+        self.split_focus_left = true;
+        self.apply_editor_theme_to_all();
+        self.focus = Focus::List;
+    }
+
+    /// Reopen the most recently closed tab at its previous cursor position.
+    pub fn reopen_closed_tab(&mut self) -> Result<()> {
+        let Some((path, (row, col))) = self.closed_tabs.pop() else {
+            self.push_message("No closed tabs".to_string());
+            return Ok(());
+        };
+        if !path.exists() {
+            self.push_message(format!("{} no longer exists", path.display()));
+            return Ok(());
+        }
+        self.load_file_into_editor(path)?;
+        if let Some(buf) = self.buffers.get_mut(self.active_tab) {
+            #[allow(clippy::cast_possible_truncation)]
+            buf.textarea.move_cursor(CursorMove::Jump(
+                row.min(u16::MAX as usize) as u16,
+                col.min(u16::MAX as usize) as u16,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Remove the buffer at `idx`, remembering it for reopen.
+    fn remove_buffer(&mut self, idx: usize) {
+        let Some(closed) = self.buffers.get(idx) else {
+            return;
+        };
+        let id = closed.id;
+        if let Some(path) = closed.path.clone() {
+            let cursor = closed.textarea.cursor();
+            self.closed_tabs.retain(|(p, _)| *p != path);
+            self.closed_tabs.push((path, cursor));
+            if self.closed_tabs.len() > MAX_CLOSED_TABS {
+                self.closed_tabs.remove(0);
+            }
         }
-        if self.split_right_tab.is_some_and(|i| i >= self.buffers.len()) {
-            self.split_right_tab = None;
+        self.retain_buffers(|b| b.id != id);
+    }
+
+    /// Drop buffers rejected by `keep`. The active tab and the split's right pane stay on
+    /// the same buffers when those survive. If either side of the split disappears, the
+    /// split collapses onto the remaining pane with focus on it; if both do, the tab that
+    /// moved into the active one's place becomes active.
+    fn retain_buffers(&mut self, keep: impl Fn(&EditorBuffer) -> bool) {
+        let active_id = self.buffers.get(self.active_tab).map(|b| b.id);
+        let old_active = self.active_tab;
+        self.buffers.retain(|b| keep(b));
+        let active = active_id.and_then(|id| self.buffers.iter().position(|b| b.id == id));
+        self.active_tab = match (active, self.split_right_tab()) {
+            (Some(idx), _) => idx,
+            (None, Some(right)) => right,
+            (None, None) => old_active.min(self.buffers.len().saturating_sub(1)),
+        };
+        let right = self.split_right_tab();
+        if right.is_none() || right == Some(self.active_tab) {
+            self.split_right_id = None;
+            self.split_focus_left = true;
             self.editor_layout = EditorLayout::Single;
         }
     }
 }
 
-fn load_entries(dir: &PathBuf) -> Result<Vec<NoteEntry>> {
+fn palette_label(config: &Config, item: PaletteItem) -> &str {
+    match item {
+        PaletteItem::Action(action) => action.label(),
+        PaletteItem::User(i) => config.commands.get(i).map_or("", |c| c.name.as_str()),
+    }
+}
+
+/// `~`: the character in the other case; characters without case stay as they are.
+fn toggle_case(c: char) -> String {
+    if c.is_lowercase() {
+        c.to_uppercase().collect()
+    } else if c.is_uppercase() {
+        c.to_lowercase().collect()
+    } else {
+        c.to_string()
+    }
+}
+
+/// The spaces and tabs `line` starts with.
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Delimiters paired by `editor.auto_pairs`: opening character and its closing one.
+const AUTO_PAIRS: [(char, char); 6] = [
+    ('(', ')'),
+    ('[', ']'),
+    ('`', '`'),
+    ('*', '*'),
+    ('_', '_'),
+    ('"', '"'),
+];
+
+/// Closing delimiter for `open`, if `editor.auto_pairs` pairs it.
+fn auto_pair_close(open: char) -> Option<char> {
+    AUTO_PAIRS
+        .iter()
+        .find(|&&(o, _)| o == open)
+        .map(|&(_, close)| close)
+}
+
+/// The marker that continues the list item on `line`: its indentation and bullet, the next
+/// number of an ordered item, and an unchecked box for a task. The flag is set when the item
+/// has nothing after its marker.
+fn list_continuation(line: &str) -> Option<(String, bool)> {
+    let rest = line.trim_start();
+    let indent = &line[..line.len() - rest.len()];
+    let (bullet, rest) =
+        if let Some(after) = ["- ", "* ", "+ "].iter().find_map(|b| rest.strip_prefix(b)) {
+            (rest[..2].to_string(), after)
+        } else {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let number: u64 = rest[..digits].parse().ok()?;
+            let after = &rest[digits..];
+            let delim = after.chars().next().filter(|c| matches!(c, '.' | ')'))?;
+            let after = after[1..].strip_prefix(' ')?;
+            (format!("{}{delim} ", number + 1), after)
+        };
+    let (task, rest) = match ["[ ] ", "[x] ", "[X] "]
+        .iter()
+        .find_map(|b| rest.strip_prefix(b))
+    {
+        Some(after) => ("[ ] ", after),
+        None => ("", rest),
+    };
+    Some((format!("{indent}{bullet}{task}"), rest.trim().is_empty()))
+}
+
+/// First free sibling path named `<stem> copy[.ext]`, then `<stem> copy 2[.ext]`, ...
+fn is_markdown_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(MARKDOWN_EXT))
+}
+
+/// Whether `new` is an existing entry other than `old`. On a case-insensitive filesystem a
+/// name that differs from `old` only in case exists as `old` itself, so it is only taken when
+/// the folder lists that exact name.
+fn is_taken(old: &Path, new: &Path) -> bool {
+    if new == old || !new.exists() {
+        return false;
+    }
+    let case_only = old.parent() == new.parent()
+        && old.file_name().zip(new.file_name()).is_some_and(|(a, b)| {
+            a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+        });
+    !case_only
+        || new
+            .parent()
+            .and_then(|dir| fs::read_dir(dir).ok())
+            .is_none_or(|entries| {
+                entries
+                    .flatten()
+                    .any(|e| Some(e.file_name().as_os_str()) == new.file_name())
+            })
+}
+
+fn duplicate_path(path: &Path) -> PathBuf {
+    let parent = path.parent().unwrap_or(Path::new(""));
+    // A folder's whole name is kept: `v1.2` has no extension.
+    let (stem, ext) = if path.is_dir() {
+        (path.file_name(), None)
+    } else {
+        (path.file_stem(), path.extension().and_then(|e| e.to_str()))
+    };
+    let stem = stem.and_then(|s| s.to_str()).unwrap_or("untitled");
+    let mut n = 1;
+    loop {
+        let base = if n == 1 {
+            format!("{stem} copy")
+        } else {
+            format!("{stem} copy {n}")
+        };
+        let candidate = match ext {
+            Some(ext) => parent.join(format!("{base}.{ext}")),
+            None => parent.join(base),
+        };
+        if !candidate.exists() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// Whether the note at `path` has `tag` (lowercase, no `#`) in its frontmatter or as an
+/// inline `#tag`.
+fn note_has_tag(path: &Path, tag: &str) -> bool {
+    let Ok(content) = fs::read_to_string(path) else {
+        return false;
+    };
+    if parse_tags(&content).iter().any(|t| t.to_lowercase() == tag) {
+        return true;
+    }
+    Regex::new(&format!(r"(?i)#{}\b", regex::escape(tag))).is_ok_and(|re| re.is_match(&content))
+}
+
+/// Recursively copy a directory tree.
+fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+    for entry in WalkDir::new(src)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+    {
+        let Ok(rel) = entry.path().strip_prefix(src) else {
+            continue;
+        };
+        let target = dst.join(rel);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace line `row` of `textarea` with `lines`, leaving the cursor at the end of the last.
+fn replace_line(textarea: &mut TextArea<'static>, row: usize, lines: &[String]) {
+    #[allow(clippy::cast_possible_truncation)]
+    textarea.move_cursor(CursorMove::Jump(row as u16, 0));
+    textarea.delete_line_by_end();
+    textarea.insert_str(lines.join("\n"));
+}
+
+/// Modification times of config.toml and theme.toml in `config_dir`.
+fn config_stamps(config_dir: &Path) -> [Option<SystemTime>; 2] {
+    [CONFIG_FILE_NAME, THEME_FILE_NAME].map(|name| {
+        fs::metadata(config_dir.join(name))
+            .and_then(|m| m.modified())
+            .ok()
+    })
+}
+
+/// Files whose saving changes oxid's settings: config.toml, theme.toml and `.oxidignore`.
+fn is_config_file(path: &Path) -> bool {
+    path.ends_with(CONFIG_FILE_NAME)
+        || path.ends_with(THEME_FILE_NAME)
+        || path.ends_with(IGNORE_FILE_NAME)
+}
+
+/// Returns true for files that should not be loaded into the editor (images, PDFs, archives).
+pub fn is_binary_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| BINARY_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Content sniff for files whose extension is not in [`BINARY_EXTENSIONS`]: a NUL byte in the
+/// first 8 KiB marks the file as binary, as git does.
+fn has_binary_content(bytes: &[u8]) -> bool {
+    bytes.iter().take(8192).any(|&b| b == 0)
+}
+
+/// [`has_binary_content`] on the head of the file at `path`; unreadable files are not binary.
+fn sniffs_binary(path: &Path) -> bool {
+    use std::io::Read;
+    let mut head = Vec::with_capacity(8192);
+    fs::File::open(path)
+        .and_then(|f| f.take(8192).read_to_end(&mut head))
+        .is_ok_and(|_| has_binary_content(&head))
+}
+
+fn load_entries(dir: &PathBuf, show_all_files: bool) -> Result<Vec<NoteEntry>> {
     let mut dirs = Vec::new();
     let mut files = Vec::new();
 
@@ -1748,20 +6785,23 @@ fn load_entries(dir: &PathBuf) -> Result<Vec<NoteEntry>> {
                 .unwrap_or("")
                 .to_string();
             dirs.push(NoteEntry::dir(path, format!("{display}/")));
-        } else if meta.is_file() && path.extension().is_some_and(|e| e == "md") {
+        } else if meta.is_file()
+            && (show_all_files
+                || path.extension().is_some_and(|e| e == MARKDOWN_EXT)
+                || crypt::is_encrypted(&path))
+        {
             let display = path
                 .file_name()
                 .and_then(|n| n.to_str())
                 .unwrap_or("")
                 .to_string();
-            let (content, searchable) = read_note_content(&path, &display);
-            files.push(NoteEntry {
-                path,
-                display,
-                content,
-                searchable,
-                is_directory: false,
-            });
+            let content = if is_binary_file(&path) || crypt::is_encrypted(&path) {
+                NoteContent::Skipped
+            } else {
+                NoteContent::Pending
+            };
+            let modified = meta.modified().ok();
+            files.push(NoteEntry::file(path, display, content, modified));
         }
     }
 
@@ -1773,19 +6813,37 @@ fn load_entries(dir: &PathBuf) -> Result<Vec<NoteEntry>> {
     Ok(result)
 }
 
-fn read_note_content(path: &PathBuf, display: &str) -> (String, String) {
-    let Ok(file) = fs::File::open(path) else { return (String::new(), display.to_string()) };
+/// Tabs the split's buffer picker offers out of `tabs`: every one but `active`. The selection
+/// starts on `shown`, the tab the split shows now, or on the first offered.
+fn split_picker(tabs: usize, active: usize, shown: Option<usize>) -> (Vec<usize>, usize) {
+    let candidates: Vec<usize> = (0..tabs).filter(|&i| i != active).collect();
+    let selected = shown
+        .and_then(|shown| candidates.iter().position(|&i| i == shown))
+        .unwrap_or(0);
+    (candidates, selected)
+}
 
-    let mut buf = Vec::with_capacity(MAX_CONTENT_BYTES + 1);
-    let mut take = file.take(MAX_CONTENT_BYTES as u64);
-    if take.read_to_end(&mut buf).is_err() {
-        return (String::new(), display.to_string());
+/// With `ui.display_titles`, give the notes among `entries` the first heading the link graph
+/// read from them. Notes without one keep showing their file name.
+fn set_titles(entries: &mut [NoteEntry], links: &LinkGraph, enabled: bool) {
+    if !enabled {
+        return;
     }
+    for entry in entries.iter_mut().filter(|e| !e.is_directory) {
+        entry.set_title(links.title(&entry.path).map(str::to_string));
+    }
+}
 
-    let mut content = String::from_utf8_lossy(&buf).into_owned();
-    if buf.len() >= MAX_CONTENT_BYTES {
-        content.push_str("\n\n(Content truncated - file too large)");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_picker_offers_other_tabs_and_starts_on_the_shown_one() {
+        assert_eq!(split_picker(4, 1, None), (vec![0, 2, 3], 0));
+        assert_eq!(split_picker(4, 1, Some(3)), (vec![0, 2, 3], 2));
+        // The active tab is never offered, so it cannot be the starting point either.
+        assert_eq!(split_picker(4, 1, Some(1)), (vec![0, 2, 3], 0));
+        assert_eq!(split_picker(1, 0, None), (vec![], 0));
     }
-    let searchable = format!("{display}\n{content}");
-    (content, searchable)
 }
@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Periodic snapshots of the vault's notes
+
+use crate::vault::{walk, Ignore};
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDateTime};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+/// Name of a snapshot folder: the local time it was taken.
+const SNAPSHOT_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+
+/// Suffix of a snapshot still being written; it is renamed once every note is in it.
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Result of a finished snapshot, for the footer.
+#[derive(Debug)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub notes: usize,
+    /// Old snapshots removed to stay within `keep`.
+    pub pruned: usize,
+}
+
+/// A snapshot being taken on a background thread.
+pub struct BackupJob {
+    result: Receiver<Result<Snapshot>>,
+}
+
+impl BackupJob {
+    /// Copy the notes in `vault` into a new snapshot under `dir`, then remove all but the
+    /// newest `keep` snapshots. `save_lock` is held around each copy, as the app holds it
+    /// while it writes notes, so no note is copied half-written.
+    pub fn spawn(
+        vault: PathBuf,
+        ignore: Ignore,
+        dir: PathBuf,
+        keep: usize,
+        save_lock: Arc<Mutex<()>>,
+    ) -> Self {
+        let (tx, result) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(take_snapshot(&vault, &ignore, &dir, keep, &save_lock));
+        });
+        Self { result }
+    }
+
+    /// The snapshot's result once it is done.
+    pub fn poll(&self) -> Option<Result<Snapshot>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err(anyhow::anyhow!("Backup stopped unexpectedly")))
+            }
+        }
+    }
+}
+
+fn take_snapshot(
+    vault: &Path,
+    ignore: &Ignore,
+    dir: &Path,
+    keep: usize,
+    save_lock: &Mutex<()>,
+) -> Result<Snapshot> {
+    let name = Local::now().format(SNAPSHOT_FORMAT).to_string();
+    let path = dir.join(&name);
+    let partial = dir.join(format!("{name}{PARTIAL_SUFFIX}"));
+    fs::create_dir_all(&partial).with_context(|| format!("Cannot create {}", partial.display()))?;
+    let mut notes = 0;
+    // A backup folder inside the vault must not end up in its own snapshots.
+    for note in walk(vault, ignore)
+        .markdown_files()
+        .filter(|p| !p.starts_with(dir))
+    {
+        let Ok(rel) = note.strip_prefix(vault) else {
+            continue;
+        };
+        let target = partial.join(rel);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Cannot create {}", parent.display()))?;
+        }
+        let _saving = save_lock.lock().unwrap_or_else(PoisonError::into_inner);
+        match fs::copy(note, &target) {
+            Ok(_) => notes += 1,
+            // Deleted since the walk found it.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Cannot write {}", target.display())),
+        }
+    }
+    fs::rename(&partial, &path).with_context(|| format!("Cannot rename {}", partial.display()))?;
+    let pruned = prune(dir, keep, &name)?;
+    Ok(Snapshot {
+        path,
+        notes,
+        pruned,
+    })
+}
+
+/// Remove all but the newest `keep` snapshots in `dir`, and snapshots other than `current`
+/// left unfinished (oxid exited while writing them). Returns how many snapshots went.
+fn prune(dir: &Path, keep: usize, current: &str) -> Result<usize> {
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !entry.path().is_dir() {
+            continue;
+        }
+        match name.strip_suffix(PARTIAL_SUFFIX) {
+            Some(stem) if stem != current && is_snapshot_name(stem) => {
+                fs::remove_dir_all(entry.path())?;
+            }
+            Some(_) => {}
+            None if is_snapshot_name(&name) => snapshots.push(name),
+            None => {}
+        }
+    }
+    // The names sort by time.
+    snapshots.sort_unstable();
+    let excess = snapshots.len().saturating_sub(keep.max(1));
+    for name in &snapshots[..excess] {
+        fs::remove_dir_all(dir.join(name))?;
+    }
+    Ok(excess)
+}
+
+fn is_snapshot_name(name: &str) -> bool {
+    NaiveDateTime::parse_from_str(name, SNAPSHOT_FORMAT).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_copy_notes_and_keep_the_newest() {
+        let base = std::env::temp_dir().join(format!("oxid-backup-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let vault = base.join("vault");
+        let dir = vault.join("backups");
+        fs::create_dir_all(vault.join("sub")).unwrap();
+        fs::write(vault.join("a.md"), "a").unwrap();
+        fs::write(vault.join("sub/b.md"), "b").unwrap();
+        fs::write(vault.join("image.png"), "").unwrap();
+        fs::create_dir_all(dir.join("2020-01-01_00-00-00")).unwrap();
+        fs::create_dir_all(dir.join("2020-01-02_00-00-00")).unwrap();
+        fs::create_dir_all(dir.join("2020-01-03_00-00-00.partial")).unwrap();
+        fs::create_dir_all(dir.join("not-a-snapshot")).unwrap();
+
+        let ignore = Ignore::new(&vault, &[]);
+        let snapshot = take_snapshot(&vault, &ignore, &dir, 2, &Mutex::new(())).unwrap();
+        assert_eq!(snapshot.notes, 2);
+        assert_eq!(snapshot.pruned, 1);
+        assert_eq!(
+            fs::read_to_string(snapshot.path.join("sub/b.md")).unwrap(),
+            "b"
+        );
+        assert!(!snapshot.path.join("image.png").exists());
+        let mut left: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        let name = snapshot
+            .path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert_eq!(left, ["2020-01-02_00-00-00", &name, "not-a-snapshot"]);
+        let _ = fs::remove_dir_all(&base);
+    }
+}
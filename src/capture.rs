@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Quick capture into the inbox note and tasks added from anywhere
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const CAPTURE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// The inbox line for `text`: a bullet with the current date and time.
+pub fn capture_line(text: &str) -> String {
+    format!(
+        "- {} {}",
+        Local::now().format(CAPTURE_TIME_FORMAT),
+        text.trim()
+    )
+}
+
+/// Append `line` to the note at `path`, creating it (and its folder) if missing. The file is
+/// opened in append mode and closed again, so writes by other programs in between are kept.
+pub fn append_line(path: &Path, line: &str) -> Result<()> {
+    if crate::crypt::is_encrypted(path) {
+        anyhow::bail!("Cannot append to an encrypted note; open it to capture into it");
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    // Start on a new line if the note does not end with one.
+    let mut last = [0u8; 1];
+    let needs_newline = file.seek(SeekFrom::End(-1)).is_ok()
+        && file.read_exact(&mut last).is_ok()
+        && last[0] != b'\n';
+    let mut entry = String::new();
+    if needs_newline {
+        entry.push('\n');
+    }
+    entry.push_str(line);
+    entry.push('\n');
+    file.write_all(entry.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Where `line` goes to be the last entry under `heading` (e.g. "## Tasks"): the row of
+/// `lines` to insert at and the lines to insert there. The section ends at the next heading
+/// of the same or a higher level. A note without the heading gets it at its end, after a
+/// blank line.
+pub fn under_heading(lines: &[String], heading: &str, line: &str) -> (usize, Vec<String>) {
+    let level = heading_level(heading).unwrap_or(0);
+    let heading_row = lines.iter().position(|l| l.trim() == heading.trim());
+    let Some(start) = heading_row else {
+        let end = lines.len()
+            - lines
+                .iter()
+                .rev()
+                .take_while(|l| l.trim().is_empty())
+                .count();
+        let mut insert = Vec::new();
+        // Reuse a blank line the note ends with.
+        let row = if end < lines.len() {
+            end + 1
+        } else {
+            if end > 0 {
+                insert.push(String::new());
+            }
+            end
+        };
+        insert.extend([heading.trim().to_string(), line.to_string()]);
+        return (row, insert);
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|l| heading_level(l).is_some_and(|n| level == 0 || n <= level))
+        .map_or(lines.len(), |n| start + 1 + n);
+    // After the section's last entry, leaving the blank lines before the next heading.
+    let row = (start + 1..end)
+        .rev()
+        .find(|&i| !lines[i].trim().is_empty())
+        .map_or(start + 1, |i| i + 1);
+    (row, vec![line.to_string()])
+}
+
+/// Level of a markdown heading line (`## x` is 2).
+pub fn heading_level(line: &str) -> Option<usize> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    let heading = (1..=6).contains(&hashes) && line[hashes..].starts_with(' ');
+    heading.then_some(hashes)
+}
+
+/// Add `line` under `heading` in the note at `path` (see `under_heading`), creating the
+/// note and its folder if missing.
+pub fn insert_under_heading(path: &Path, heading: &str, line: &str) -> Result<()> {
+    if crate::crypt::is_encrypted(path) {
+        anyhow::bail!("Cannot add to an encrypted note; open it to edit it");
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let (row, insert) = under_heading(&lines, heading, line);
+    lines.splice(row..row, insert);
+    let mut content = lines.join("\n");
+    content.push('\n');
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(note: &str, line: &str) -> String {
+        let mut lines: Vec<String> = note.lines().map(String::from).collect();
+        let (row, insert) = under_heading(&lines, "## Tasks", line);
+        lines.splice(row..row, insert);
+        lines.join("\n")
+    }
+
+    #[test]
+    fn tasks_go_at_the_end_of_their_section() {
+        let note = "# Day\n\n## Tasks\n- [ ] one\n\n## Notes\ntext";
+        assert_eq!(
+            insert(note, "- [ ] two"),
+            "# Day\n\n## Tasks\n- [ ] one\n- [ ] two\n\n## Notes\ntext"
+        );
+        assert_eq!(insert("## Tasks", "- [ ] a"), "## Tasks\n- [ ] a");
+        // A subheading belongs to the section.
+        assert_eq!(
+            insert("## Tasks\n### Later\n- [ ] b\n# Next", "- [ ] a"),
+            "## Tasks\n### Later\n- [ ] b\n- [ ] a\n# Next"
+        );
+    }
+
+    #[test]
+    fn a_missing_heading_is_added_at_the_end() {
+        assert_eq!(
+            insert("# Day\ntext", "- [ ] a"),
+            "# Day\ntext\n\n## Tasks\n- [ ] a"
+        );
+        assert_eq!(
+            insert("# Day\n\n\n", "- [ ] a"),
+            "# Day\n\n## Tasks\n- [ ] a\n"
+        );
+        assert_eq!(insert("", "- [ ] a"), "## Tasks\n- [ ] a");
+    }
+}
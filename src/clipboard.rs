@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - System clipboard via OSC 52 or an external command
+
+use anyhow::{bail, Context, Result};
+use crossterm::{execute, style::Print};
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Tried in order by `paste` when no `ui.paste_command` is set.
+const PASTE_COMMANDS: [&str; 3] = [
+    "wl-paste --no-newline",
+    "xclip -selection clipboard -o",
+    "xsel --clipboard --output",
+];
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// How the text reached the clipboard, for the footer message.
+pub enum CopyMethod {
+    Osc52,
+    Command(String),
+}
+
+/// Put `text` on the system clipboard. With `command` set (e.g. "wl-copy" or
+/// "xclip -selection clipboard") the text is piped to it; if that is unset or fails, an
+/// OSC 52 escape sequence asks the terminal to do it, which also works over SSH.
+pub fn copy(text: &str, command: &str) -> Result<CopyMethod> {
+    let command = command.trim();
+    if !command.is_empty() && copy_with_command(text, command).is_ok() {
+        return Ok(CopyMethod::Command(command.to_string()));
+    }
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    execute!(io::stdout(), Print(sequence)).context("Failed to write to the terminal")?;
+    Ok(CopyMethod::Osc52)
+}
+
+/// Read the system clipboard with `command`, or else the first of wl-paste, xclip and xsel
+/// that works. Terminals cannot be asked for the clipboard reliably, so there is no OSC 52
+/// fallback here; their own paste shortcut arrives as a bracketed paste instead.
+pub fn paste(command: &str) -> Result<String> {
+    let command = command.trim();
+    let candidates: Vec<&str> = if command.is_empty() {
+        PASTE_COMMANDS.to_vec()
+    } else {
+        vec![command]
+    };
+    for candidate in &candidates {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(candidate)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output();
+        if let Some(output) = output.ok().filter(|o| o.status.success()) {
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+    }
+    bail!(
+        "No clipboard program worked (tried {})",
+        candidates.join(", ")
+    )
+}
+
+/// Image bytes printed by `command`, or None when it fails or prints nothing (the
+/// clipboard holds no image).
+pub fn paste_image(command: &str) -> Option<Vec<u8>> {
+    let command = command.trim();
+    if command.is_empty() {
+        return None;
+    }
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    (output.status.success() && !output.stdout.is_empty()).then_some(output.stdout)
+}
+
+fn copy_with_command(text: &str, command: &str) -> Result<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {command}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    if !child.wait()?.success() {
+        bail!("{command} failed");
+    }
+    Ok(())
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
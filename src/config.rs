@@ -1,36 +1,50 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // oxid - A fast, keyboard-driven note manager TUI for Linux
 
+use crate::theme::ColorMode;
 use anyhow::{Context, Result};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use directories::ProjectDirs;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
-/// Theme overrides in config.toml. Hex (#RRGGBB) or named colors.
+/// Theme preset and overrides in config.toml. Hex (#RRGGBB) or named colors; unset colors
+/// come from the preset and theme.toml.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct ThemeConfig {
-    pub background: String,
-    pub foreground: String,
-    pub cursor: String,
-    pub selection: String,
-    pub statusbar_bg: String,
-    pub statusbar_fg: String,
-    pub border_color: String,
+    /// Built-in color scheme (see `theme::THEME_PRESETS`).
+    pub preset: String,
+    /// "dark", "light" or "auto" (see `theme::ThemeMode::from_config`); empty keeps the
+    /// preset's own variant.
+    pub mode: String,
+    /// For "auto": command printing "dark" or "light". Empty reads `$COLORFGBG`.
+    pub mode_command: String,
+    pub background: Option<String>,
+    pub foreground: Option<String>,
+    pub cursor: Option<String>,
+    pub selection: Option<String>,
+    pub statusbar_bg: Option<String>,
+    pub statusbar_fg: Option<String>,
+    pub border_color: Option<String>,
 }
 
 impl Default for ThemeConfig {
     fn default() -> Self {
         Self {
-            background: "black".to_string(),
-            foreground: "white".to_string(),
-            cursor: "cyan".to_string(),
-            selection: "green".to_string(),
-            statusbar_bg: "black".to_string(),
-            statusbar_fg: "white".to_string(),
-            border_color: "cyan".to_string(),
+            preset: "default".to_string(),
+            mode: String::new(),
+            mode_command: String::new(),
+            background: None,
+            foreground: None,
+            cursor: None,
+            selection: None,
+            statusbar_bg: None,
+            statusbar_fg: None,
+            border_color: None,
         }
     }
 }
@@ -45,6 +59,69 @@ pub struct UiConfig {
     pub icons: bool,
     /// Show dotfiles in file tree.
     pub show_hidden: bool,
+    /// List every file in the file tree (and telescope), not just markdown notes.
+    pub show_all_files: bool,
+    /// Start the file list as a tree of expandable folders instead of one folder at a time.
+    pub tree_view: bool,
+    /// Show notes by their first `# Heading` instead of their file name in the lists.
+    pub display_titles: bool,
+    /// Draw the content of unfocused panes dimmed (or in the theme's `inactive_text`).
+    pub dim_inactive: bool,
+    /// Terminal window title; `{file}`, `{vault}` and `{dirty}` are filled in. Empty leaves
+    /// the title alone.
+    pub window_title: String,
+    /// Program used to open binary files (images, PDFs), e.g. "xdg-open". Empty disables.
+    pub external_opener: String,
+    /// Command that reads clipboard text from stdin, e.g. "wl-copy". Empty (or a failing
+    /// command) uses the terminal's OSC 52 clipboard.
+    pub clipboard_command: String,
+    /// Command that prints the clipboard, e.g. "wl-paste". Empty tries wl-paste, xclip, xsel.
+    pub paste_command: String,
+    /// Command for *Open in External Editor*, e.g. "nvim". Empty uses `$EDITOR`.
+    pub external_editor: String,
+    /// Width of the file list column in percent (10–50).
+    pub list_width_percent: u16,
+    /// Width of the preview column in percent (10–60). The editor takes the rest.
+    pub preview_width_percent: u16,
+    /// Show the preview column.
+    pub show_preview: bool,
+    /// Scroll the preview to the editor cursor while editing.
+    pub preview_follow_cursor: bool,
+    /// Show link destinations in parentheses after link text in the preview.
+    pub preview_show_link_urls: bool,
+    /// Terminal color support: "truecolor", "256", or "16". Hex colors are reduced to the
+    /// nearest palette color in the indexed modes.
+    pub color_mode: String,
+    /// Milliseconds between idle wake-ups that check auto-save and expire footer messages.
+    pub tick_rate_ms: u64,
+    /// Filter searches once the keys typed so far are handled rather than after every key.
+    pub debounce_search: bool,
+}
+
+/// Minimum share of the main area left to the editor column.
+pub const MIN_EDITOR_PERCENT: u16 = 20;
+
+impl UiConfig {
+    /// List and preview widths clamped to sane ranges, leaving the editor at least
+    /// `MIN_EDITOR_PERCENT`.
+    pub fn pane_percents(&self) -> (u16, u16) {
+        let list = self.list_width_percent.clamp(10, 50);
+        let preview = self
+            .preview_width_percent
+            .clamp(10, 60)
+            .min(100 - MIN_EDITOR_PERCENT - list);
+        (list, preview)
+    }
+
+    /// `tick_rate_ms`, at least 10 ms.
+    pub fn tick_rate(&self) -> Duration {
+        Duration::from_millis(self.tick_rate_ms.max(10))
+    }
+
+    /// Parsed `color_mode`; unknown values use true color.
+    pub fn color_mode(&self) -> ColorMode {
+        ColorMode::parse(&self.color_mode).unwrap_or_default()
+    }
 }
 
 impl Default for UiConfig {
@@ -53,6 +130,23 @@ impl Default for UiConfig {
             border_style: "rounded".to_string(),
             icons: false,
             show_hidden: false,
+            show_all_files: false,
+            tree_view: false,
+            display_titles: false,
+            dim_inactive: false,
+            window_title: "{dirty}{file} - oxid".to_string(),
+            external_opener: String::new(),
+            external_editor: String::new(),
+            clipboard_command: String::new(),
+            paste_command: String::new(),
+            list_width_percent: 20,
+            preview_width_percent: 40,
+            show_preview: true,
+            preview_follow_cursor: true,
+            preview_show_link_urls: false,
+            color_mode: "truecolor".to_string(),
+            tick_rate_ms: 500,
+            debounce_search: false,
         }
     }
 }
@@ -68,6 +162,8 @@ pub struct EditorConfig {
     pub syntax_highlighting: bool,
     pub auto_save: bool,
     pub auto_save_interval: u64,
+    /// Save edited buffers when focus leaves the editor or switches tabs or panes.
+    pub auto_save_on_focus_change: bool,
     /// Show line numbers in gutter.
     pub line_numbers: bool,
     /// Relative / hybrid line numbers (when line_numbers is true).
@@ -78,6 +174,20 @@ pub struct EditorConfig {
     pub tab_width: u8,
     /// Enable mouse in editor.
     pub mouse_support: bool,
+    /// Folder (relative to `notes_directory`) that *Insert Image* copies images into.
+    pub assets_folder: String,
+    /// Command that prints a clipboard image as PNG. Empty skips the clipboard.
+    pub image_paste_command: String,
+    /// Start the line after a list item with its marker (Enter in Insert mode, and `o`).
+    pub continue_lists: bool,
+    /// Wrap lines wider than the editor pane instead of scrolling sideways.
+    pub soft_wrap: bool,
+    /// Typing `(`, `[`, `` ` ``, `*`, `_` or `"` in Insert mode also inserts the closing one.
+    pub auto_pairs: bool,
+    /// Home goes to the first non-blank character; Enter in Insert mode keeps the indentation.
+    pub smart_indent: bool,
+    /// Checking a `🔁 every week` task adds the next one, due a recurrence later.
+    pub recurring_tasks: bool,
 }
 
 impl Default for EditorConfig {
@@ -90,11 +200,188 @@ impl Default for EditorConfig {
             syntax_highlighting: true,
             auto_save: true,
             auto_save_interval: 30,
+            auto_save_on_focus_change: false,
             line_numbers: true,
             rel_line_numbers: false,
             tab_width: 4,
             mouse_support: true,
+            assets_folder: "assets".to_string(),
+            image_paste_command: "wl-paste --type image/png".to_string(),
+            continue_lists: false,
+            soft_wrap: true,
+            auto_pairs: true,
+            smart_indent: true,
+            recurring_tasks: true,
+        }
+    }
+}
+
+/// Commands for encrypted notes (`.md.age`, `.md.gpg`). Decrypt commands read the
+/// ciphertext on stdin and print the note; encrypt commands do the reverse.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    pub age_decrypt: String,
+    pub age_encrypt: String,
+    pub gpg_decrypt: String,
+    pub gpg_encrypt: String,
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            age_decrypt: "age -d -i ~/.config/age/key.txt".to_string(),
+            age_encrypt: "age -e -R ~/.config/age/recipients.txt".to_string(),
+            gpg_decrypt: "gpg --quiet --batch -d".to_string(),
+            gpg_encrypt: "gpg --quiet --batch -e --default-recipient-self".to_string(),
+        }
+    }
+}
+
+/// Periodic snapshots of the vault's notes, for vaults without git.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub enabled: bool,
+    /// Minutes between snapshots; the first is taken this long after startup.
+    pub interval_minutes: u64,
+    /// Folder the snapshots go in; empty uses `backups` in the XDG data directory.
+    pub directory: String,
+    /// Snapshots kept; older ones are removed after each new one.
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 60,
+            directory: String::new(),
+            keep: 10,
+        }
+    }
+}
+
+impl BackupConfig {
+    /// Time between snapshots, at least a minute.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_minutes.max(1) * 60)
+    }
+
+    /// Folder the snapshots go in, `None` when it is left empty and there is no data
+    /// directory.
+    pub fn path(&self) -> Option<PathBuf> {
+        if !self.directory.trim().is_empty() {
+            return Some(expand_path(&self.directory));
         }
+        let dirs = ProjectDirs::from("", "", "oxid")?;
+        Some(dirs.data_dir().join("backups"))
+    }
+}
+
+/// Git integration.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Read the status with the `git` binary instead of libgit2, e.g. for a repository
+    /// layout libgit2 does not handle.
+    pub use_binary: bool,
+}
+
+/// List search and telescope matching.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Match case when the query has an uppercase letter, instead of always ignoring it.
+    pub smart_case: bool,
+}
+
+/// A piece of the footer, in the order `[statusline] segments` lists them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusSegment {
+    /// Editor mode badge, `NORMAL` or `INSERT`.
+    Mode,
+    /// Focused note, `[+]` when it has unsaved edits.
+    File,
+    /// Cursor line and column, line count and soft wrap.
+    Position,
+    WordCount,
+    Git,
+    /// `Saved...` for a moment after a save.
+    Saved,
+    /// The latest footer message until it expires.
+    Message,
+    /// Keys for the focused pane, and the search or file name prompt.
+    Hints,
+    /// Fills the free width: segments after it are pushed right.
+    Spacer,
+}
+
+impl StatusSegment {
+    pub const NAMES: [&'static str; 9] = [
+        "mode",
+        "file",
+        "position",
+        "wordcount",
+        "git",
+        "saved",
+        "message",
+        "hints",
+        "spacer",
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name.trim() {
+            "mode" => Self::Mode,
+            "file" => Self::File,
+            "position" => Self::Position,
+            "wordcount" => Self::WordCount,
+            "git" => Self::Git,
+            "saved" => Self::Saved,
+            "message" => Self::Message,
+            "hints" => Self::Hints,
+            "spacer" => Self::Spacer,
+            _ => return None,
+        })
+    }
+}
+
+/// What the footer shows.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StatuslineConfig {
+    /// Segment names, left to right.
+    pub segments: Vec<String>,
+}
+
+impl Default for StatuslineConfig {
+    fn default() -> Self {
+        Self {
+            segments: [
+                "message", "hints", "git", "saved", "spacer", "mode", "position",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+impl StatuslineConfig {
+    /// The known segments, in order; unknown names are skipped.
+    pub fn resolved(&self) -> Vec<StatusSegment> {
+        self.segments
+            .iter()
+            .filter_map(|name| StatusSegment::parse(name))
+            .collect()
+    }
+
+    /// Names that are not segments, for a warning.
+    pub fn unknown(&self) -> Vec<&str> {
+        self.segments
+            .iter()
+            .filter(|name| StatusSegment::parse(name).is_none())
+            .map(String::as_str)
+            .collect()
     }
 }
 
@@ -108,7 +395,17 @@ pub struct KeysConfig {
     pub search: String,
     pub command_palette: String,
     pub daily_note: String,
+    pub quick_capture: String,
+    pub add_task: String,
     pub task_board: String,
+    pub pane_grow: String,
+    pub pane_shrink: String,
+    /// Unbound by default; e.g. "space g p".
+    pub git_push: String,
+    /// Unbound by default.
+    pub random_note: String,
+    /// Suspend oxid to the shell; `fg` brings it back.
+    pub suspend: String,
     // Generic (used in multiple contexts)
     pub escape: String,
     pub enter: String,
@@ -116,9 +413,11 @@ pub struct KeysConfig {
     pub move_up: String,
     pub move_down: String,
     pub move_left: String,
+    pub move_right: String,
     pub move_up_alt: String,
     pub move_down_alt: String,
     pub move_left_alt: String,
+    pub move_right_alt: String,
     pub delete: String,
     // List (file explorer) Normal mode
     pub list_create_note: String,
@@ -127,16 +426,58 @@ pub struct KeysConfig {
     pub list_rename: String,
     pub list_edit_config: String,
     pub list_delete: String,
+    pub list_move: String,
+    pub list_duplicate: String,
+    pub list_archive: String,
+    pub list_filter: String,
+    pub list_filter_repeat: String,
+    pub list_tree: String,
+    pub list_mark: String,
+    pub list_tag: String,
     pub list_parent: String,
     pub list_parent_alt: String,
+    pub list_root: String,
+    /// Followed by a digit, jumps to that level of the breadcrumb path (0 is the vault root).
+    pub list_jump_level: String,
     // Editor
     pub editor_back: String,
     pub editor_pdf: String,
     pub editor_backlinks: String,
     pub editor_wiki_link: String,
+    pub editor_jump_back: String,
+    pub editor_jump_forward: String,
     pub editor_insert: String,
     pub editor_append: String,
+    pub editor_open_below: String,
+    pub editor_open_above: String,
     pub editor_split_focus: String,
+    pub editor_paste: String,
+    // Telescope
+    pub telescope_open_split: String,
+    pub telescope_mode: String,
+    pub search_case: String,
+    // Task board
+    pub task_toggle: String,
+    pub task_advance: String,
+    // Replace review
+    pub replace_dry_run: String,
+    // Editor Normal-mode sequences
+    pub editor_next_tab: String,
+    pub editor_prev_tab: String,
+    pub editor_toggle_split: String,
+    pub editor_close_tab: String,
+    pub editor_close_others: String,
+    pub editor_close_all: String,
+    pub editor_reopen_tab: String,
+    pub editor_follow_link: String,
+    pub editor_spell_suggest: String,
+    pub editor_spell_add: String,
+    pub editor_external: String,
+    pub editor_toggle_task: String,
+    pub editor_display_down: String,
+    pub editor_display_up: String,
+    pub editor_row_start: String,
+    pub editor_row_end: String,
 }
 
 impl Default for KeysConfig {
@@ -147,16 +488,25 @@ impl Default for KeysConfig {
             search: "/".to_string(),
             command_palette: "ctrl-p".to_string(),
             daily_note: "alt-d".to_string(),
+            quick_capture: "alt-n".to_string(),
+            add_task: "alt-a".to_string(),
             task_board: "alt-t".to_string(),
+            pane_grow: "alt-l".to_string(),
+            pane_shrink: "alt-h".to_string(),
+            git_push: String::new(),
+            random_note: String::new(),
+            suspend: "ctrl-z".to_string(),
             escape: "esc".to_string(),
             enter: "enter".to_string(),
             backspace: "backspace".to_string(),
             move_up: "k".to_string(),
             move_down: "j".to_string(),
             move_left: "h".to_string(),
+            move_right: "l".to_string(),
             move_up_alt: "up".to_string(),
             move_down_alt: "down".to_string(),
             move_left_alt: "left".to_string(),
+            move_right_alt: "right".to_string(),
             delete: "delete".to_string(),
             list_create_note: "n".to_string(),
             list_create_dir: "shift-n".to_string(),
@@ -164,15 +514,52 @@ impl Default for KeysConfig {
             list_rename: "r".to_string(),
             list_edit_config: "c".to_string(),
             list_delete: "d".to_string(),
+            list_move: "m".to_string(),
+            list_duplicate: "shift-d".to_string(),
+            list_archive: "a".to_string(),
+            list_filter: "f".to_string(),
+            list_filter_repeat: "shift-f".to_string(),
+            list_tree: "t".to_string(),
+            list_mark: "space".to_string(),
+            list_tag: "#".to_string(),
             list_parent: "backspace".to_string(),
             list_parent_alt: "left".to_string(),
+            list_root: "~".to_string(),
+            list_jump_level: "g".to_string(),
             editor_back: "q".to_string(),
             editor_pdf: "ctrl-e".to_string(),
             editor_backlinks: "ctrl-b".to_string(),
             editor_wiki_link: "ctrl-]".to_string(),
+            editor_jump_back: "ctrl-o".to_string(),
+            editor_jump_forward: "ctrl-i".to_string(),
             editor_insert: "i".to_string(),
             editor_append: "a".to_string(),
+            editor_open_below: "o".to_string(),
+            editor_open_above: "shift-o".to_string(),
             editor_split_focus: "tab".to_string(),
+            editor_paste: "ctrl-shift-v".to_string(),
+            telescope_open_split: "ctrl-v".to_string(),
+            telescope_mode: "ctrl-g".to_string(),
+            search_case: "ctrl-t".to_string(),
+            task_toggle: "x".to_string(),
+            task_advance: "m".to_string(),
+            replace_dry_run: "d".to_string(),
+            editor_next_tab: "g t".to_string(),
+            editor_prev_tab: "g shift-t".to_string(),
+            editor_toggle_split: "g s".to_string(),
+            editor_close_tab: "g q".to_string(),
+            editor_close_others: "g o".to_string(),
+            editor_close_all: "g shift-q".to_string(),
+            editor_reopen_tab: "g u".to_string(),
+            editor_follow_link: "g d".to_string(),
+            editor_spell_suggest: "z =".to_string(),
+            editor_spell_add: "z g".to_string(),
+            editor_external: "g x".to_string(),
+            editor_toggle_task: "ctrl-space".to_string(),
+            editor_display_down: "g j".to_string(),
+            editor_display_up: "g k".to_string(),
+            editor_row_start: "g 0".to_string(),
+            editor_row_end: "g $".to_string(),
         }
     }
 }
@@ -236,20 +623,17 @@ pub fn parse_key_event(s: &str) -> Option<KeyEvent> {
 #[derive(Debug, Clone)]
 pub struct ResolvedKeys {
     pub quit: KeyEvent,
-    pub zen_mode: KeyEvent,
-    pub search: KeyEvent,
-    pub command_palette: KeyEvent,
-    pub daily_note: KeyEvent,
-    pub task_board: KeyEvent,
     pub escape: KeyEvent,
     pub enter: KeyEvent,
     pub backspace: KeyEvent,
     pub move_up: KeyEvent,
     pub move_down: KeyEvent,
     pub move_left: KeyEvent,
+    pub move_right: KeyEvent,
     pub move_up_alt: KeyEvent,
     pub move_down_alt: KeyEvent,
     pub move_left_alt: KeyEvent,
+    pub move_right_alt: KeyEvent,
     pub delete: KeyEvent,
     pub list_create_note: KeyEvent,
     pub list_create_dir: KeyEvent,
@@ -257,15 +641,35 @@ pub struct ResolvedKeys {
     pub list_rename: KeyEvent,
     pub list_edit_config: KeyEvent,
     pub list_delete: KeyEvent,
+    pub list_move: KeyEvent,
+    pub list_duplicate: KeyEvent,
+    pub list_archive: KeyEvent,
+    pub list_filter: KeyEvent,
+    pub list_filter_repeat: KeyEvent,
+    pub list_tree: KeyEvent,
+    pub list_mark: KeyEvent,
+    pub list_tag: KeyEvent,
     pub list_parent: KeyEvent,
     pub list_parent_alt: KeyEvent,
+    pub list_root: KeyEvent,
     pub editor_back: KeyEvent,
     pub editor_pdf: KeyEvent,
     pub editor_backlinks: KeyEvent,
     pub editor_wiki_link: KeyEvent,
+    pub editor_jump_back: KeyEvent,
+    pub editor_jump_forward: KeyEvent,
     pub editor_insert: KeyEvent,
     pub editor_append: KeyEvent,
+    pub editor_open_below: KeyEvent,
+    pub editor_open_above: KeyEvent,
     pub editor_split_focus: KeyEvent,
+    pub editor_paste: KeyEvent,
+    pub telescope_open_split: KeyEvent,
+    pub telescope_mode: KeyEvent,
+    pub search_case: KeyEvent,
+    pub task_toggle: KeyEvent,
+    pub task_advance: KeyEvent,
+    pub replace_dry_run: KeyEvent,
 }
 
 impl ResolvedKeys {
@@ -287,32 +691,16 @@ impl ResolvedKeys {
                 &keys.quit,
                 KeyEvent::new(KeyCode::Char('q'), KeyModifiers::empty()),
             ),
-            zen_mode: parse_or(
-                &keys.zen_mode,
-                KeyEvent::new(KeyCode::F(11), KeyModifiers::empty()),
-            ),
-            search: parse_or(
-                &keys.search,
-                KeyEvent::new(KeyCode::Char('/'), KeyModifiers::empty()),
-            ),
-            command_palette: parse_or(
-                &keys.command_palette,
-                KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL),
-            ),
-            daily_note: parse_or(
-                &keys.daily_note,
-                KeyEvent::new(KeyCode::Char('d'), KeyModifiers::ALT),
-            ),
-            task_board: parse_or(
-                &keys.task_board,
-                KeyEvent::new(KeyCode::Char('t'), KeyModifiers::ALT),
-            ),
             escape: parse_or(&keys.escape, def_esc),
             enter: parse_or(&keys.enter, def_enter),
             backspace: parse_or(&keys.backspace, def_backspace),
             move_up: parse_or(&keys.move_up, def_k),
             move_down: parse_or(&keys.move_down, def_j),
             move_left: parse_or(&keys.move_left, def_h),
+            move_right: parse_or(
+                &keys.move_right,
+                KeyEvent::new(KeyCode::Char('l'), KeyModifiers::empty()),
+            ),
             move_up_alt: parse_or(
                 &keys.move_up_alt,
                 KeyEvent::new(KeyCode::Up, KeyModifiers::empty()),
@@ -322,6 +710,10 @@ impl ResolvedKeys {
                 KeyEvent::new(KeyCode::Down, KeyModifiers::empty()),
             ),
             move_left_alt: parse_or(&keys.move_left_alt, def_left),
+            move_right_alt: parse_or(
+                &keys.move_right_alt,
+                KeyEvent::new(KeyCode::Right, KeyModifiers::empty()),
+            ),
             delete: parse_or(&keys.delete, def_del),
             list_create_note: parse_or(
                 &keys.list_create_note,
@@ -347,8 +739,44 @@ impl ResolvedKeys {
                 &keys.list_delete,
                 KeyEvent::new(KeyCode::Char('d'), KeyModifiers::empty()),
             ),
+            list_move: parse_or(
+                &keys.list_move,
+                KeyEvent::new(KeyCode::Char('m'), KeyModifiers::empty()),
+            ),
+            list_duplicate: parse_or(
+                &keys.list_duplicate,
+                KeyEvent::new(KeyCode::Char('d'), KeyModifiers::SHIFT),
+            ),
+            list_archive: parse_or(
+                &keys.list_archive,
+                KeyEvent::new(KeyCode::Char('a'), KeyModifiers::empty()),
+            ),
+            list_filter: parse_or(
+                &keys.list_filter,
+                KeyEvent::new(KeyCode::Char('f'), KeyModifiers::empty()),
+            ),
+            list_filter_repeat: parse_or(
+                &keys.list_filter_repeat,
+                KeyEvent::new(KeyCode::Char('f'), KeyModifiers::SHIFT),
+            ),
+            list_tree: parse_or(
+                &keys.list_tree,
+                KeyEvent::new(KeyCode::Char('t'), KeyModifiers::empty()),
+            ),
+            list_mark: parse_or(
+                &keys.list_mark,
+                KeyEvent::new(KeyCode::Char(' '), KeyModifiers::empty()),
+            ),
+            list_tag: parse_or(
+                &keys.list_tag,
+                KeyEvent::new(KeyCode::Char('#'), KeyModifiers::empty()),
+            ),
             list_parent: parse_or(&keys.list_parent, def_backspace),
             list_parent_alt: parse_or(&keys.list_parent_alt, def_left),
+            list_root: parse_or(
+                &keys.list_root,
+                KeyEvent::new(KeyCode::Char('~'), KeyModifiers::empty()),
+            ),
             editor_back: parse_or(
                 &keys.editor_back,
                 KeyEvent::new(KeyCode::Char('q'), KeyModifiers::empty()),
@@ -365,6 +793,14 @@ impl ResolvedKeys {
                 &keys.editor_wiki_link,
                 KeyEvent::new(KeyCode::Char(']'), KeyModifiers::CONTROL),
             ),
+            editor_jump_back: parse_or(
+                &keys.editor_jump_back,
+                KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL),
+            ),
+            editor_jump_forward: parse_or(
+                &keys.editor_jump_forward,
+                KeyEvent::new(KeyCode::Char('i'), KeyModifiers::CONTROL),
+            ),
             editor_insert: parse_or(
                 &keys.editor_insert,
                 KeyEvent::new(KeyCode::Char('i'), KeyModifiers::empty()),
@@ -373,20 +809,63 @@ impl ResolvedKeys {
                 &keys.editor_append,
                 KeyEvent::new(KeyCode::Char('a'), KeyModifiers::empty()),
             ),
+            editor_open_below: parse_or(
+                &keys.editor_open_below,
+                KeyEvent::new(KeyCode::Char('o'), KeyModifiers::empty()),
+            ),
+            editor_open_above: parse_or(
+                &keys.editor_open_above,
+                KeyEvent::new(KeyCode::Char('o'), KeyModifiers::SHIFT),
+            ),
             editor_split_focus: parse_or(
                 &keys.editor_split_focus,
                 KeyEvent::new(KeyCode::Tab, KeyModifiers::empty()),
             ),
+            editor_paste: parse_or(
+                &keys.editor_paste,
+                KeyEvent::new(
+                    KeyCode::Char('v'),
+                    KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+                ),
+            ),
+            telescope_open_split: parse_or(
+                &keys.telescope_open_split,
+                KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL),
+            ),
+            telescope_mode: parse_or(
+                &keys.telescope_mode,
+                KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL),
+            ),
+            search_case: parse_or(
+                &keys.search_case,
+                KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL),
+            ),
+            task_toggle: parse_or(
+                &keys.task_toggle,
+                KeyEvent::new(KeyCode::Char('x'), KeyModifiers::empty()),
+            ),
+            task_advance: parse_or(
+                &keys.task_advance,
+                KeyEvent::new(KeyCode::Char('m'), KeyModifiers::empty()),
+            ),
+            replace_dry_run: parse_or(
+                &keys.replace_dry_run,
+                KeyEvent::new(KeyCode::Char('d'), KeyModifiers::empty()),
+            ),
         }
     }
 }
 
-/// Formats a key config string for display (e.g. "ctrl-q" -> "Ctrl+Q").
+/// Formats a key config string for display (e.g. "ctrl-q" -> "Ctrl+Q", "space f" ->
+/// "Space → F").
 pub fn key_display_string(s: &str) -> String {
-    let s = s.trim();
-    if s.is_empty() {
-        return String::new();
-    }
+    s.split_whitespace()
+        .map(chord_display_string)
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+fn chord_display_string(s: &str) -> String {
     let parts: Vec<&str> = s.split('-').collect();
     let (mods, key_part) = if parts.len() >= 2 {
         let mod_str: Vec<String> = parts[..parts.len() - 1]
@@ -429,6 +908,18 @@ pub fn key_display_string(s: &str) -> String {
     }
 }
 
+/// A `[[commands]]` entry: a shell command run from the command palette.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserCommand {
+    /// Label shown in the palette.
+    pub name: String,
+    /// Run with `sh -c` in the notes directory.
+    pub command: String,
+    /// Ask before running.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
 /// Application logic configuration loaded from config.toml.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
@@ -437,6 +928,12 @@ pub struct Config {
     pub notes_directory: String,
     /// Folder for daily notes (journal), relative to `notes_directory`.
     pub daily_notes_folder: String,
+    /// Note that quick capture appends to, relative to `notes_directory`.
+    pub capture_note: String,
+    /// Heading that Add Task puts tasks under, added to the note if missing.
+    pub task_heading: String,
+    /// Status tags of the task board's columns, left to right; empty shows one list.
+    pub task_columns: Vec<String>,
     #[serde(default)]
     pub theme: ThemeConfig,
     #[serde(default)]
@@ -444,7 +941,33 @@ pub struct Config {
     #[serde(default)]
     pub ui: UiConfig,
     #[serde(default)]
+    pub encryption: EncryptionConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub git: GitConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub statusline: StatuslineConfig,
+    #[serde(default)]
     pub keys: KeysConfig,
+    /// User-defined palette commands.
+    #[serde(default)]
+    pub commands: Vec<UserCommand>,
+    /// Insert-mode snippets: trigger word -> body with `${1:placeholder}` tab stops.
+    #[serde(default)]
+    pub snippets: HashMap<String, String>,
+    /// Glob patterns for paths every vault scan skips, on top of `.oxidignore`.
+    pub ignore: Vec<String>,
+    /// Include the `archive` folder in vault scans instead of skipping it.
+    pub search_archive: bool,
+    /// Give new notes a timestamp ID that `[[links]]` can use instead of the name.
+    pub zettel_ids: bool,
+    /// chrono format of the IDs, e.g. "%Y%m%d%H%M" for `202403141530`.
+    pub zettel_id_format: String,
+    /// Write the ID as an `id:` frontmatter field instead of in front of the file name.
+    pub zettel_id_in_frontmatter: bool,
 }
 
 impl Default for Config {
@@ -453,10 +976,25 @@ impl Default for Config {
         Self {
             notes_directory: format!("{}/Documents/Notes", home),
             daily_notes_folder: "journal".to_string(),
+            capture_note: "inbox.md".to_string(),
+            task_heading: "## Tasks".to_string(),
+            task_columns: ["todo", "doing", "done"].map(String::from).to_vec(),
             theme: ThemeConfig::default(),
             editor: EditorConfig::default(),
             ui: UiConfig::default(),
+            encryption: EncryptionConfig::default(),
+            backup: BackupConfig::default(),
+            git: GitConfig::default(),
+            search: SearchConfig::default(),
+            statusline: StatuslineConfig::default(),
             keys: KeysConfig::default(),
+            commands: Vec::new(),
+            snippets: HashMap::new(),
+            ignore: [".git", ".trash", ".obsidian"].map(String::from).to_vec(),
+            search_archive: false,
+            zettel_ids: false,
+            zettel_id_format: "%Y%m%d%H%M".to_string(),
+            zettel_id_in_frontmatter: false,
         }
     }
 }
@@ -519,15 +1057,52 @@ notes_directory = "{}"
 # Folder for daily notes (relative to notes_directory)
 daily_notes_folder = "{}"
 
+# Note that quick capture (keys.quick_capture, or `oxid --capture "text"`) appends
+# timestamped bullets to (relative to notes_directory; created if missing)
+capture_note = "{}"
+
+# Heading that Add Task (keys.add_task) puts new tasks under, in today's daily note or in
+# the note named with "@folder/note" before the task; added to the note if missing
+task_heading = "{}"
+
+# Columns of the task board: a task with one of these #tags is shown in its column, a task
+# without one in the first. keys.task_advance moves a task on by rewriting its tag.
+# [] shows all tasks in one list
+task_columns = [{}]
+
+# Paths skipped when scanning the vault (search, tags, tasks, backlinks, replace, export).
+# A bare name matches anywhere ("*.tmp", "archive"); a pattern with "/" matches from the
+# vault root ("archive/2020"). A .oxidignore file at the vault root adds one per line.
+ignore = [{}]
+
+# Notes archived with keys.list_archive are moved to archive/ and skipped by the scans
+# above; set to true to search them anyway
+search_archive = {}
+
+# Give notes created with keys.list_create_note or by following a [[link]] a timestamp ID, as
+# "202403141530-name.md" or, with zettel_id_in_frontmatter, an "id:" frontmatter field.
+# [[202403141530]] links find the note by its ID, even after it is renamed
+zettel_ids = {}
+# chrono format of the IDs (https://docs.rs/chrono/latest/chrono/format/strftime)
+zettel_id_format = "{}"
+zettel_id_in_frontmatter = {}
+
 [theme]
-# Hex (#RRGGBB) or named colors. Override theme.toml for main editor/status bar/borders.
-background = "{}"
-foreground = "{}"
-cursor = "{}"
-selection = "{}"
-statusbar_bg = "{}"
-statusbar_fg = "{}"
-border_color = "{}"
+# Built-in color scheme: {}
+preset = "{}"
+# "dark" or "light" picks that variant of the preset (and section of theme.toml); "auto"
+# asks mode_command, or else the terminal's COLORFGBG. Empty keeps the preset's variant.
+mode = "{}"
+mode_command = "{}"
+# Hex (#RRGGBB) or named colors. Uncomment to override the preset and theme.toml for the
+# main editor/status bar/borders.
+# background = "black"
+# foreground = "white"
+# cursor = "cyan"
+# selection = "green"
+# statusbar_bg = "black"
+# statusbar_fg = "white"
+# border_color = "cyan"
 
 [editor]
 typewriter_mode = false
@@ -537,16 +1112,102 @@ show_backlinks = true
 syntax_highlighting = true
 auto_save = true
 auto_save_interval = 30
+# Also save edited buffers when switching tabs or panes, or leaving the editor
+auto_save_on_focus_change = false
 line_numbers = true
 rel_line_numbers = false
 tab_width = 4
 mouse_support = true
+# Insert Image copies images here (relative to notes_directory). The clipboard is read with
+# image_paste_command (e.g. "xclip -selection clipboard -t image/png -o"); empty disables it
+assets_folder = "assets"
+image_paste_command = "wl-paste --type image/png"
+# Enter (and o) after a list item starts the next line with "- ", "2. " or "- [ ] "
+continue_lists = false
+# Wrap long lines at the pane's width (at spaces where possible); false scrolls sideways
+soft_wrap = true
+# Typing ( [ ` * _ or " also inserts the closing character; typing it again steps over it
+auto_pairs = true
+# Home goes to the first non-blank character (again: column 0); Enter in Insert mode starts
+# the new line with the current line's indentation
+smart_indent = true
+# Checking a task with "🔁 every day/week/month/year" or "🔁 every 2 weeks" adds an
+# unchecked copy above it, its "📅 YYYY-MM-DD" due date moved on by the recurrence
+recurring_tasks = true
 
 [ui]
 # Border style: "rounded", "double", "thick", "plain"
 border_style = "{}"
 icons = {}
 show_hidden = {}
+# List non-markdown files too; binary files are opened with external_opener
+show_all_files = {}
+# Start the file list as a folder tree (keys.list_tree toggles it)
+tree_view = {}
+# Show notes by their first level-1 heading in the lists; renaming uses the file name
+display_titles = {}
+# Dim the notes list, editor pane, preview or backlinks while another one has the focus
+dim_inactive = {}
+# Terminal window title: {{file}} is the focused note (the vault with none open), {{vault}}
+# the notes folder and {{dirty}} a "*" while the note has unsaved edits. Empty: unchanged
+window_title = "{}"
+external_opener = "{}"
+# Editor for heavy edits (editor_external); empty uses $EDITOR
+external_editor = "{}"
+# Clipboard program for the Copy commands (e.g. "wl-copy", "xclip -selection clipboard");
+# empty uses the terminal (OSC 52), which also works over SSH
+clipboard_command = "{}"
+# Clipboard reader for editor_paste; empty tries wl-paste, xclip and xsel
+paste_command = "{}"
+# Column widths in percent; the editor gets the remainder
+list_width_percent = {}
+preview_width_percent = {}
+show_preview = {}
+# Keep the preview scrolled to the editor cursor
+preview_follow_cursor = {}
+# Show link URLs after link text in the preview
+preview_show_link_urls = {}
+# Terminal colors: "truecolor", "256" or "16" (hex colors are reduced to the nearest one)
+color_mode = "{}"
+# Milliseconds between idle checks for auto-save and expired messages; the screen is only
+# redrawn when something changed
+tick_rate_ms = {}
+# Filter searches once per burst of typed keys instead of after every key; helps with a
+# large vault over a slow connection
+debounce_search = {}
+
+[encryption]
+# Notes named *.md.age or *.md.gpg are decrypted into the editor with these commands and
+# encrypted again on save; plaintext is never written to disk. Decrypt commands read the
+# ciphertext on stdin, encrypt commands the note; both print the result. They cannot ask
+# for a passphrase in the terminal, so use a key file, gpg-agent or a graphical pinentry.
+age_decrypt = "{}"
+age_encrypt = "{}"
+gpg_decrypt = "{}"
+gpg_encrypt = "{}"
+
+[backup]
+# Copy the vault's notes into a timestamped folder every interval_minutes, keeping the
+# newest `keep` snapshots. Empty directory: ~/.local/share/oxid/backups
+enabled = {}
+interval_minutes = {}
+directory = "{}"
+keep = {}
+
+[git]
+# Read the vault's git status with the git binary instead of the built-in library.
+# Pushing always runs the git binary, so it uses your credential helpers and SSH agent
+use_binary = {}
+
+[search]
+# Match case when the query has an uppercase letter; keys.search_case switches between
+# this and always matching case
+smart_case = {}
+
+[statusline]
+# Footer segments, left to right, out of: {}
+# Segments after "spacer" are aligned right; "hints" are the keys for the focused pane
+segments = [{}]
 
 [keys]
 # Global
@@ -555,7 +1216,19 @@ zen_mode = "{}"
 search = "{}"
 command_palette = "{}"
 daily_note = "{}"
+# One-line note appended to capture_note without leaving what you are doing
+quick_capture = "{}"
+# Add "- [ ] task" under task_heading in today's daily note, or in "@note task"
+add_task = "{}"
 task_board = "{}"
+pane_grow = "{}"
+pane_shrink = "{}"
+# Bindings may be key sequences separated by spaces, e.g. git_push = "space g p"
+git_push = "{}"
+# Open Random Note (unbound by default), e.g. "space r"
+random_note = "{}"
+# Suspend to the shell; resume with fg
+suspend = "{}"
 # Generic
 escape = "{}"
 enter = "{}"
@@ -563,9 +1236,11 @@ backspace = "{}"
 move_up = "{}"
 move_down = "{}"
 move_left = "{}"
+move_right = "{}"
 move_up_alt = "{}"
 move_down_alt = "{}"
 move_left_alt = "{}"
+move_right_alt = "{}"
 delete = "{}"
 # List (file explorer)
 list_create_note = "{}"
@@ -574,44 +1249,168 @@ list_tag_explorer = "{}"
 list_rename = "{}"
 list_edit_config = "{}"
 list_delete = "{}"
+list_move = "{}"
+list_duplicate = "{}"
+list_archive = "{}"
+list_filter = "{}"
+# Filter again with the last filter's query
+list_filter_repeat = "{}"
+list_tree = "{}"
+list_mark = "{}"
+# Add a tag to the selected or marked notes
+list_tag = "{}"
 list_parent = "{}"
 list_parent_alt = "{}"
+list_root = "{}"
+# Followed by a digit 0-9: jump to that folder of the breadcrumb path (0 = vault root)
+list_jump_level = "{}"
 # Editor
 editor_back = "{}"
 editor_pdf = "{}"
 editor_backlinks = "{}"
 editor_wiki_link = "{}"
+# Back / forward through positions left by following links, backlinks, tasks and search
+# results. Many terminals send Ctrl+I as Tab; rebind editor_jump_forward if it does nothing
+editor_jump_back = "{}"
+editor_jump_forward = "{}"
 editor_insert = "{}"
 editor_append = "{}"
+editor_open_below = "{}"
+editor_open_above = "{}"
 editor_split_focus = "{}"
+editor_paste = "{}"
+# Telescope
+telescope_open_split = "{}"
+# Switch between searching files and the lines of the open buffers
+telescope_mode = "{}"
+# In the list search and telescope: match case exactly, or go back to the default
+search_case = "{}"
+# Task board: check or uncheck the selected task
+task_toggle = "{}"
+# Task board: move the selected task to the next column
+task_advance = "{}"
+# Replace review: only report what would change, or go back to applying
+replace_dry_run = "{}"
+# Editor Normal-mode sequences
+editor_next_tab = "{}"
+editor_prev_tab = "{}"
+editor_toggle_split = "{}"
+editor_close_tab = "{}"
+editor_close_others = "{}"
+editor_close_all = "{}"
+editor_reopen_tab = "{}"
+editor_follow_link = "{}"
+editor_spell_suggest = "{}"
+editor_spell_add = "{}"
+editor_external = "{}"
+# Check or uncheck the task on the cursor's line
+editor_toggle_task = "{}"
+# Down/up one display line: the pane's width of a long line at a time
+editor_display_down = "{}"
+editor_display_up = "{}"
+# Start / end of the display line (the row of a wrapped line the cursor is on)
+editor_row_start = "{}"
+editor_row_end = "{}"
+
+# Insert-mode snippets: type a trigger and press Tab. Tab / Shift-Tab move between the tab
+# stops ${{1:placeholder}}, ${{2}}, ... (typing replaces the placeholder); $0 is where the cursor
+# ends up. "\n" starts a new line
+# [snippets]
+# todo = "- [ ] ${{1:task}} 📅 ${{2:date}}"
+
+# Shell commands for the command palette. Each runs with `sh -c` in notes_directory, with
+# OXID_FILE (the focused note, if any) and OXID_DIR (notes_directory) set.
+# [[commands]]
+# name = "Sync vault"
+# command = "git pull --rebase && git push"
+# confirm = true
 "#,
         config.notes_directory,
         config.daily_notes_folder,
-        t.background,
-        t.foreground,
-        t.cursor,
-        t.selection,
-        t.statusbar_bg,
-        t.statusbar_fg,
-        t.border_color,
+        config.capture_note,
+        config.task_heading,
+        config
+            .task_columns
+            .iter()
+            .map(|c| format!("{c:?}"))
+            .collect::<Vec<_>>()
+            .join(", "),
+        config
+            .ignore
+            .iter()
+            .map(|p| format!("{p:?}"))
+            .collect::<Vec<_>>()
+            .join(", "),
+        config.search_archive,
+        config.zettel_ids,
+        config.zettel_id_format,
+        config.zettel_id_in_frontmatter,
+        crate::theme::THEME_PRESETS.join(", "),
+        t.preset,
+        t.mode,
+        t.mode_command,
         u.border_style,
         u.icons,
         u.show_hidden,
+        u.show_all_files,
+        u.tree_view,
+        u.display_titles,
+        u.dim_inactive,
+        u.window_title,
+        u.external_opener,
+        u.external_editor,
+        u.clipboard_command,
+        u.paste_command,
+        u.list_width_percent,
+        u.preview_width_percent,
+        u.show_preview,
+        u.preview_follow_cursor,
+        u.preview_show_link_urls,
+        u.color_mode,
+        u.tick_rate_ms,
+        u.debounce_search,
+        config.encryption.age_decrypt,
+        config.encryption.age_encrypt,
+        config.encryption.gpg_decrypt,
+        config.encryption.gpg_encrypt,
+        config.backup.enabled,
+        config.backup.interval_minutes,
+        config.backup.directory,
+        config.backup.keep,
+        config.git.use_binary,
+        config.search.smart_case,
+        StatusSegment::NAMES.join(", "),
+        config
+            .statusline
+            .segments
+            .iter()
+            .map(|s| format!("{s:?}"))
+            .collect::<Vec<_>>()
+            .join(", "),
         k.quit,
         k.zen_mode,
         k.search,
         k.command_palette,
         k.daily_note,
+        k.quick_capture,
+        k.add_task,
         k.task_board,
+        k.pane_grow,
+        k.pane_shrink,
+        k.git_push,
+        k.random_note,
+        k.suspend,
         k.escape,
         k.enter,
         k.backspace,
         k.move_up,
         k.move_down,
         k.move_left,
+        k.move_right,
         k.move_up_alt,
         k.move_down_alt,
         k.move_left_alt,
+        k.move_right_alt,
         k.delete,
         k.list_create_note,
         k.list_create_dir,
@@ -619,15 +1418,52 @@ editor_split_focus = "{}"
         k.list_rename,
         k.list_edit_config,
         k.list_delete,
+        k.list_move,
+        k.list_duplicate,
+        k.list_archive,
+        k.list_filter,
+        k.list_filter_repeat,
+        k.list_tree,
+        k.list_mark,
+        k.list_tag,
         k.list_parent,
         k.list_parent_alt,
+        k.list_root,
+        k.list_jump_level,
         k.editor_back,
         k.editor_pdf,
         k.editor_backlinks,
         k.editor_wiki_link,
+        k.editor_jump_back,
+        k.editor_jump_forward,
         k.editor_insert,
         k.editor_append,
+        k.editor_open_below,
+        k.editor_open_above,
         k.editor_split_focus,
+        k.editor_paste,
+        k.telescope_open_split,
+        k.telescope_mode,
+        k.search_case,
+        k.task_toggle,
+        k.task_advance,
+        k.replace_dry_run,
+        k.editor_next_tab,
+        k.editor_prev_tab,
+        k.editor_toggle_split,
+        k.editor_close_tab,
+        k.editor_close_others,
+        k.editor_close_all,
+        k.editor_reopen_tab,
+        k.editor_follow_link,
+        k.editor_spell_suggest,
+        k.editor_spell_add,
+        k.editor_external,
+        k.editor_toggle_task,
+        k.editor_display_down,
+        k.editor_display_up,
+        k.editor_row_start,
+        k.editor_row_end,
     )
 }
 
@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Note content for the notes list, read on demand
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::SystemTime;
+
+/// Bytes of a note read for its preview and content search.
+const MAX_CONTENT_BYTES: usize = 100_000;
+
+/// What the notes list knows about a file's content.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum NoteContent {
+    /// Not read yet.
+    #[default]
+    Pending,
+    Loaded(String),
+    Unreadable,
+    /// Directories, binary files and encrypted notes: nothing to preview or search.
+    Skipped,
+}
+
+impl NoteContent {
+    /// The loaded text, empty when there is none (yet).
+    pub fn text(&self) -> &str {
+        match self {
+            NoteContent::Loaded(text) => text,
+            _ => "",
+        }
+    }
+}
+
+/// The modification time of `path`, used to tell whether loaded content is stale.
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Read the start of the note at `path`, with a marker when it was cut off.
+pub fn read(path: &Path) -> NoteContent {
+    let Ok(file) = fs::File::open(path) else {
+        return NoteContent::Unreadable;
+    };
+    let mut buf = Vec::with_capacity(MAX_CONTENT_BYTES + 1);
+    let mut take = file.take(MAX_CONTENT_BYTES as u64);
+    if take.read_to_end(&mut buf).is_err() {
+        return NoteContent::Unreadable;
+    }
+    let mut content = String::from_utf8_lossy(&buf).into_owned();
+    if buf.len() >= MAX_CONTENT_BYTES {
+        content.push_str("\n\n(Content truncated - file too large)");
+    }
+    NoteContent::Loaded(content)
+}
+
+/// One note read by a `ContentLoader`, with the modification time from just before.
+pub struct Loaded {
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+    pub content: NoteContent,
+}
+
+/// Reads notes on a background thread so entering a folder of large notes stays instant.
+pub struct ContentLoader {
+    results: Receiver<Loaded>,
+}
+
+impl ContentLoader {
+    pub fn spawn(paths: Vec<PathBuf>) -> Self {
+        let (tx, results) = mpsc::channel();
+        thread::spawn(move || {
+            for path in paths {
+                let modified = modified(&path);
+                let content = read(&path);
+                if tx
+                    .send(Loaded {
+                        path,
+                        modified,
+                        content,
+                    })
+                    .is_err()
+                {
+                    // The loader was dropped, e.g. the folder changed.
+                    break;
+                }
+            }
+        });
+        Self { results }
+    }
+
+    /// The notes read since the last poll, and whether the loader is done.
+    pub fn poll(&self) -> (Vec<Loaded>, bool) {
+        let mut loaded = Vec::new();
+        loop {
+            match self.results.try_recv() {
+                Ok(note) => loaded.push(note),
+                Err(TryRecvError::Empty) => return (loaded, false),
+                Err(TryRecvError::Disconnected) => return (loaded, true),
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Encrypted notes (.md.age / .md.gpg) through external age or gpg commands
+
+use crate::config::EncryptionConfig;
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// How an encrypted note is encrypted, from its file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cipher {
+    Age,
+    Gpg,
+}
+
+impl Cipher {
+    /// The cipher for `foo.md.age` or `foo.md.gpg`; `None` for anything else.
+    pub fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".md.age") {
+            Some(Cipher::Age)
+        } else if name.ends_with(".md.gpg") {
+            Some(Cipher::Gpg)
+        } else {
+            None
+        }
+    }
+
+    fn decrypt_command(self, config: &EncryptionConfig) -> &str {
+        match self {
+            Cipher::Age => &config.age_decrypt,
+            Cipher::Gpg => &config.gpg_decrypt,
+        }
+    }
+
+    fn encrypt_command(self, config: &EncryptionConfig) -> &str {
+        match self {
+            Cipher::Age => &config.age_encrypt,
+            Cipher::Gpg => &config.gpg_encrypt,
+        }
+    }
+}
+
+pub fn is_encrypted(path: &Path) -> bool {
+    Cipher::of(path).is_some()
+}
+
+/// Decrypt the note at `path`. A command that exits non-zero is an error, so a broken key
+/// never turns into an empty buffer that the next save would encrypt over the real note;
+/// empty output from a successful command is an empty note.
+pub fn decrypt(path: &Path, config: &EncryptionConfig) -> Result<String> {
+    let cipher = Cipher::of(path).context("Not an encrypted note")?;
+    let ciphertext =
+        fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let plaintext = pipe(cipher.decrypt_command(config), path, ciphertext)?;
+    String::from_utf8(plaintext).context("Decrypted note is not UTF-8")
+}
+
+/// Encrypt `content` into `path`. The ciphertext goes to a temporary file next to the note
+/// that replaces it only once the command succeeded; plaintext is never written to disk.
+pub fn encrypt(path: &Path, content: &str, config: &EncryptionConfig) -> Result<()> {
+    let cipher = Cipher::of(path).context("Not an encrypted note")?;
+    let ciphertext = pipe(
+        cipher.encrypt_command(config),
+        path,
+        content.as_bytes().to_vec(),
+    )?;
+    if ciphertext.is_empty() {
+        anyhow::bail!("Encrypt command printed nothing");
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{name}.oxid-tmp"));
+    fs::write(&tmp, ciphertext)
+        .and_then(|()| fs::rename(&tmp, path))
+        .with_context(|| format!("Failed to write {}", path.display()))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+}
+
+/// Run `command` with `sh -c`, feeding `input` on stdin, and return its stdout. `OXID_FILE`
+/// is set to the note's path.
+fn pipe(command: &str, path: &Path, input: Vec<u8>) -> Result<Vec<u8>> {
+    if command.trim().is_empty() {
+        anyhow::bail!("No command configured for {}", path.display());
+    }
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("OXID_FILE", path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {command}"))?;
+    // Write from a thread so a command that prints before reading all its input cannot
+    // deadlock on full pipes.
+    let mut stdin = child.stdin.take().context("No stdin")?;
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    let written = writer.join();
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        anyhow::bail!("{command} exited with {}: {reason}", output.status);
+    }
+    match written {
+        Ok(Ok(())) => Ok(output.stdout),
+        _ => anyhow::bail!("{command} did not read its input"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A rot13 "cipher", so the tests need neither age nor gpg.
+    fn rot13() -> EncryptionConfig {
+        let tr = "tr 'A-Za-z' 'N-ZA-Mn-za-m'".to_string();
+        EncryptionConfig {
+            age_decrypt: tr.clone(),
+            age_encrypt: tr.clone(),
+            gpg_decrypt: tr.clone(),
+            gpg_encrypt: tr,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("oxid-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn recognizes_encrypted_names() {
+        assert_eq!(Cipher::of(Path::new("a/secret.md.age")), Some(Cipher::Age));
+        assert_eq!(Cipher::of(Path::new("Secret.MD.GPG")), Some(Cipher::Gpg));
+        assert_eq!(Cipher::of(Path::new("plain.md")), None);
+        assert_eq!(Cipher::of(Path::new("key.age")), None);
+    }
+
+    #[test]
+    fn round_trip_keeps_plaintext_off_disk() {
+        let dir = temp_dir("crypt-round-trip");
+        let note = dir.join("secret.md.age");
+        encrypt(&note, "Password: hunter2", &rot13()).unwrap();
+        assert_eq!(fs::read_to_string(&note).unwrap(), "Cnffjbeq: uhagre2");
+        assert_eq!(decrypt(&note, &rot13()).unwrap(), "Password: hunter2");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn empty_notes_can_be_reopened() {
+        let dir = temp_dir("crypt-empty");
+        let note = dir.join("blank.md.age");
+        // Like age, this cipher writes a header, so even empty plaintext has ciphertext.
+        let config = EncryptionConfig {
+            age_encrypt: "printf 'hdr:'; cat".to_string(),
+            age_decrypt: "tail -c +5".to_string(),
+            ..rot13()
+        };
+        encrypt(&note, "", &config).unwrap();
+        assert_eq!(fs::read_to_string(&note).unwrap(), "hdr:");
+        assert_eq!(decrypt(&note, &config).unwrap(), "");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_commands_never_touch_the_ciphertext() {
+        let dir = temp_dir("crypt-failure");
+        let note = dir.join("secret.md.gpg");
+        fs::write(&note, "ciphertext").unwrap();
+        let mut config = rot13();
+
+        // A wrong key: the command fails.
+        config.gpg_decrypt = "echo 'no secret key' >&2; exit 2".to_string();
+        let err = decrypt(&note, &config).unwrap_err().to_string();
+        assert!(err.contains("no secret key"), "{err}");
+
+        config.gpg_encrypt = "exit 1".to_string();
+        assert!(encrypt(&note, "new text", &config).is_err());
+        config.gpg_encrypt = "cat >/dev/null".to_string();
+        assert!(encrypt(&note, "new text", &config).is_err());
+
+        assert_eq!(fs::read_to_string(&note).unwrap(), "ciphertext");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,259 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Line diffs for the diff view (unsaved changes and git diff)
+
+/// Edits beyond which the middle of two texts is shown as removed and added whole.
+const MAX_EDITS: usize = 4000;
+
+/// Lines of unchanged text shown around each change.
+const CONTEXT: usize = 3;
+
+/// What a line of a unified diff is, for its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// `---`/`+++` file names and git's `diff`/`index` lines.
+    Header,
+    /// `@@ -1,4 +1,5 @@`
+    Hunk,
+    Context,
+    Added,
+    Removed,
+}
+
+/// Kind of a line of `git diff` output.
+pub fn classify(line: &str) -> LineKind {
+    if line.starts_with("+++") || line.starts_with("---") {
+        LineKind::Header
+    } else if line.starts_with("@@") {
+        LineKind::Hunk
+    } else if line.starts_with('+') {
+        LineKind::Added
+    } else if line.starts_with('-') {
+        LineKind::Removed
+    } else if line.starts_with(' ') || line.is_empty() {
+        LineKind::Context
+    } else {
+        LineKind::Header
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Insert,
+    Delete,
+}
+
+/// Unified diff from `old` to `new` without file name headers; empty when they are equal.
+pub fn unified(old: &str, new: &str) -> Vec<(LineKind, String)> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let edits = edit_script(&a, &b);
+    if edits.iter().all(|&e| e == Edit::Keep) {
+        return Vec::new();
+    }
+    // Line numbers in `a` and `b` before each edit.
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut i, mut j) = (0, 0);
+    for &edit in &edits {
+        positions.push((i, j));
+        match edit {
+            Edit::Keep => (i, j) = (i + 1, j + 1),
+            Edit::Delete => i += 1,
+            Edit::Insert => j += 1,
+        }
+    }
+    positions.push((i, j));
+
+    let mut out = Vec::new();
+    let mut start = 0;
+    while let Some(first) = edits[start..].iter().position(|&e| e != Edit::Keep) {
+        let first = start + first;
+        // A hunk runs until a stretch of more than twice the context without changes.
+        let mut end = first;
+        let mut kept = 0;
+        for (n, &edit) in edits.iter().enumerate().skip(first) {
+            if edit == Edit::Keep {
+                kept += 1;
+                if kept > 2 * CONTEXT {
+                    break;
+                }
+            } else {
+                kept = 0;
+                end = n + 1;
+            }
+        }
+        let from = first.saturating_sub(CONTEXT).max(start);
+        let to = (end + CONTEXT).min(edits.len());
+        let (a0, b0) = positions[from];
+        let (a1, b1) = positions[to];
+        out.push((
+            LineKind::Hunk,
+            format!("@@ -{} +{} @@", range(a0, a1 - a0), range(b0, b1 - b0)),
+        ));
+        for (n, &edit) in edits.iter().enumerate().take(to).skip(from) {
+            let (i, j) = positions[n];
+            out.push(match edit {
+                Edit::Keep => (LineKind::Context, format!(" {}", a[i])),
+                Edit::Delete => (LineKind::Removed, format!("-{}", a[i])),
+                Edit::Insert => (LineKind::Added, format!("+{}", b[j])),
+            });
+        }
+        start = to;
+    }
+    out
+}
+
+/// Hunk range as `diff -u` writes it: 1-based start and length, length left out when 1.
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// Shortest edit script from `a` to `b` (Myers), after taking off the lines they start and
+/// end with in common.
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (ma, mb) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let mut edits = vec![Edit::Keep; prefix];
+    edits.extend(myers(ma, mb).unwrap_or_else(|| {
+        let mut whole = vec![Edit::Delete; ma.len()];
+        whole.extend(vec![Edit::Insert; mb.len()]);
+        whole
+    }));
+    edits.extend(vec![Edit::Keep; suffix]);
+    edits
+}
+
+/// Myers' greedy diff; `None` when it takes more than `MAX_EDITS` edits.
+fn myers(a: &[&str], b: &[&str]) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (a.len() + b.len()).min(MAX_EDITS) as isize;
+    let offset = max + 1;
+    let idx = |k: isize| (k + offset) as usize;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let mut trace = Vec::new();
+    let mut found = false;
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[idx(k - 1)] < v[idx(k + 1)]) {
+                v[idx(k + 1)]
+            } else {
+                v[idx(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx(k)] = x;
+            if x >= n && y >= m {
+                found = true;
+                break 'search;
+            }
+        }
+    }
+    if !found {
+        return None;
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[idx(k - 1)] < v[idx(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[idx(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            edits.push(if x == prev_x {
+                Edit::Insert
+            } else {
+                Edit::Delete
+            });
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    edits.reverse();
+    Some(edits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(lines: &[(LineKind, String)]) -> Vec<&str> {
+        lines.iter().map(|(_, l)| l.as_str()).collect()
+    }
+
+    #[test]
+    fn unified_diff_has_hunks_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\n";
+        let diff = unified(old, new);
+        assert_eq!(
+            text(&diff),
+            [
+                "@@ -1,5 +1,5 @@",
+                " a",
+                "-b",
+                "+B",
+                " c",
+                " d",
+                " e",
+                "@@ -11,3 +11,4 @@",
+                " k",
+                " l",
+                " m",
+                "+n",
+            ]
+        );
+        assert_eq!(diff[2].0, LineKind::Removed);
+        assert_eq!(diff[3].0, LineKind::Added);
+        assert!(unified(old, old).is_empty());
+    }
+
+    #[test]
+    fn diffs_of_empty_texts() {
+        assert_eq!(text(&unified("", "x\n")), ["@@ -0,0 +1 @@", "+x"]);
+        assert_eq!(
+            text(&unified("x\ny\n", "")),
+            ["@@ -1,2 +0,0 @@", "-x", "-y"]
+        );
+    }
+
+    #[test]
+    fn classifies_git_diff_lines() {
+        let kinds: Vec<LineKind> = [
+            "diff --git a/x b/x",
+            "--- a/x",
+            "@@ -1 +1 @@",
+            "-x",
+            "+y",
+            " z",
+        ]
+        .into_iter()
+        .map(classify)
+        .collect();
+        use LineKind::*;
+        assert_eq!(kinds, [Header, Header, Hunk, Removed, Added, Context]);
+    }
+}
@@ -0,0 +1,372 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Static HTML export of notes
+
+use crate::vault::Walk;
+use anyhow::{Context, Result};
+use pulldown_cmark::escape::{escape_href, escape_html};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Minimal stylesheet so exported pages are readable without any assets.
+const STYLE: &str = "body{max-width:46em;margin:2em auto;padding:0 1em;\
+font-family:sans-serif;line-height:1.5}pre,code{background:#f4f4f4}\
+pre{padding:.5em;overflow-x:auto}blockquote{border-left:3px solid #ccc;\
+margin-left:0;padding-left:1em;color:#555}";
+
+/// What *Export to HTML* covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportScope {
+    CurrentFile,
+    CurrentDirectory,
+    Vault,
+}
+
+impl ExportScope {
+    pub const ALL: [ExportScope; 3] = [
+        ExportScope::CurrentFile,
+        ExportScope::CurrentDirectory,
+        ExportScope::Vault,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportScope::CurrentFile => "Current file",
+            ExportScope::CurrentDirectory => "Current directory",
+            ExportScope::Vault => "Whole vault",
+        }
+    }
+}
+
+/// Result of an HTML export.
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    pub out_dir: PathBuf,
+    pub exported: usize,
+    /// `file: [[link]]` for each wiki link that does not point at an exported note. These
+    /// are written as plain text.
+    pub skipped_links: Vec<String>,
+}
+
+/// Markdown files from a walk of `dir`, skipping hidden entries and anything inside
+/// `exclude` (the output directory, so re-exporting into the vault does not pick up old
+/// output).
+pub fn collect_markdown(dir: &Path, walk: &Walk, exclude: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = walk
+        .markdown_files()
+        .filter(|p| {
+            let hidden = p.strip_prefix(dir).is_ok_and(|rel| {
+                rel.components()
+                    .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+            });
+            !hidden && !p.starts_with(exclude)
+        })
+        .map(Path::to_path_buf)
+        .collect();
+    files.sort();
+    files
+}
+
+/// Render `files` (all under `base`) to standalone HTML files in `out_dir`, keeping their
+/// paths relative to `base`. `[[wiki links]]` between exported notes become relative links.
+pub fn export_html(files: &[PathBuf], base: &Path, out_dir: &Path) -> Result<ExportSummary> {
+    let exported: HashSet<PathBuf> = files
+        .iter()
+        .filter_map(|f| f.strip_prefix(base).ok().map(Path::to_path_buf))
+        .collect();
+    let mut summary = ExportSummary {
+        out_dir: out_dir.to_path_buf(),
+        ..ExportSummary::default()
+    };
+    for file in files {
+        let Ok(rel) = file.strip_prefix(base) else {
+            continue;
+        };
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let mut skipped = Vec::new();
+        let body = render_note(&content, rel, &exported, &mut skipped);
+        summary.skipped_links.extend(
+            skipped
+                .into_iter()
+                .map(|link| format!("{}: [[{link}]]", rel.display())),
+        );
+
+        let title = rel
+            .file_stem()
+            .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+        let target = out_dir.join(rel).with_extension("html");
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&target, page(&title, &body))
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        summary.exported += 1;
+    }
+    Ok(summary)
+}
+
+/// Render `note` to a PDF at `output` with pandoc.
+pub fn export_pdf(note: &Path, output: &Path) -> Result<()> {
+    let status = Command::new("pandoc")
+        .arg(note)
+        .arg("-o")
+        .arg(output)
+        .status();
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => anyhow::bail!("Pandoc failed ({status})"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            anyhow::bail!("Pandoc not found - install pandoc")
+        }
+        Err(e) => Err(e).context("Failed to run pandoc"),
+    }
+}
+
+/// HTML body for one note. Wiki links that resolve to a note in `exported` become `<a>`
+/// tags; the rest are kept as text and pushed to `skipped`.
+fn render_note(
+    content: &str,
+    rel: &Path,
+    exported: &HashSet<PathBuf>,
+    skipped: &mut Vec<String>,
+) -> String {
+    let mut opts = Options::empty();
+    opts.insert(Options::ENABLE_TASKLISTS);
+    opts.insert(Options::ENABLE_STRIKETHROUGH);
+    opts.insert(Options::ENABLE_TABLES);
+
+    // The parser splits text at `[`, so whole runs of text are joined before looking for
+    // `[[...]]`. Code blocks are left alone.
+    let mut events: Vec<Event> = Vec::new();
+    let mut text = String::new();
+    let mut in_code_block = false;
+    for event in Parser::new_ext(content, opts) {
+        match event {
+            Event::Text(t) => {
+                text.push_str(&t);
+                continue;
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                flush_text(
+                    &mut text,
+                    &mut events,
+                    rel,
+                    exported,
+                    skipped,
+                    in_code_block,
+                );
+                in_code_block = true;
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                flush_text(
+                    &mut text,
+                    &mut events,
+                    rel,
+                    exported,
+                    skipped,
+                    in_code_block,
+                );
+                in_code_block = false;
+            }
+            _ => flush_text(
+                &mut text,
+                &mut events,
+                rel,
+                exported,
+                skipped,
+                in_code_block,
+            ),
+        }
+        events.push(event);
+    }
+    flush_text(
+        &mut text,
+        &mut events,
+        rel,
+        exported,
+        skipped,
+        in_code_block,
+    );
+
+    let mut out = String::new();
+    html::push_html(&mut out, events.into_iter());
+    out
+}
+
+fn flush_text(
+    text: &mut String,
+    events: &mut Vec<Event>,
+    rel: &Path,
+    exported: &HashSet<PathBuf>,
+    skipped: &mut Vec<String>,
+    in_code_block: bool,
+) {
+    if text.is_empty() {
+        return;
+    }
+    let text = std::mem::take(text);
+    if in_code_block || !text.contains("[[") {
+        events.push(Event::Text(CowStr::from(text)));
+        return;
+    }
+    let Some(re) = wiki_link_regex() else {
+        events.push(Event::Text(CowStr::from(text)));
+        return;
+    };
+    let mut last = 0;
+    for cap in re.captures_iter(&text) {
+        let (Some(whole), Some(inner)) = (cap.get(0), cap.get(1)) else {
+            continue;
+        };
+        let (target, label) = inner
+            .as_str()
+            .split_once('|')
+            .unwrap_or((inner.as_str(), inner.as_str()));
+        let Some(linked) = resolve_link(target.trim(), rel, exported) else {
+            skipped.push(inner.as_str().to_string());
+            continue;
+        };
+        if whole.start() > last {
+            events.push(Event::Text(CowStr::from(
+                text[last..whole.start()].to_string(),
+            )));
+        }
+        let mut anchor = String::from("<a href=\"");
+        let _ = escape_href(&mut anchor, &relative_href(rel, &linked));
+        anchor.push_str("\">");
+        let _ = escape_html(&mut anchor, label.trim());
+        anchor.push_str("</a>");
+        events.push(Event::Html(CowStr::from(anchor)));
+        last = whole.end();
+    }
+    if last < text.len() {
+        events.push(Event::Text(CowStr::from(text[last..].to_string())));
+    }
+}
+
+/// `[[target|label]]`, compiled on first use.
+fn wiki_link_regex() -> Option<&'static Regex> {
+    static RE: OnceLock<Option<Regex>> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\[\[([^\]]+)\]\]").ok())
+        .as_ref()
+}
+
+/// Exported note a link target refers to: next to the linking note first, then from the
+/// export root (the same order the editor uses when following links).
+fn resolve_link(target: &str, from: &Path, exported: &HashSet<PathBuf>) -> Option<PathBuf> {
+    if target.is_empty() {
+        return None;
+    }
+    let name = if Path::new(target)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+    {
+        target.to_string()
+    } else {
+        format!("{target}.md")
+    };
+    let beside = from.parent().unwrap_or(Path::new("")).join(&name);
+    [normalize(&beside), normalize(Path::new(&name))]
+        .into_iter()
+        .find(|p| exported.contains(p))
+}
+
+/// Resolve `.` and `..` components without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Link from the page for `from` to the page for `to`, both relative to the export root.
+fn relative_href(from: &Path, to: &Path) -> String {
+    let depth = from.parent().map_or(0, |p| p.components().count());
+    let mut href = "../".repeat(depth);
+    href.push_str(&to.with_extension("html").to_string_lossy());
+    href
+}
+
+fn page(title: &str, body: &str) -> String {
+    let mut escaped_title = String::new();
+    let _ = escape_html(&mut escaped_title, title);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{escaped_title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n{body}</body>\n</html>\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_resolve_beside_the_note_then_from_the_root() {
+        let exported: HashSet<PathBuf> = ["index.md", "sub/page.md", "sub/deeper/leaf.md"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        let from = Path::new("sub/page.md");
+        let leaf = resolve_link("deeper/leaf", from, &exported);
+        assert_eq!(leaf, Some("sub/deeper/leaf.md".into()));
+        assert_eq!(
+            resolve_link("index", from, &exported),
+            Some("index.md".into())
+        );
+        assert_eq!(
+            resolve_link("../index.md", from, &exported),
+            Some("index.md".into())
+        );
+        assert_eq!(resolve_link("missing", from, &exported), None);
+        assert_eq!(resolve_link("", from, &exported), None);
+
+        let leaf = Path::new("sub/deeper/leaf.md");
+        assert_eq!(
+            resolve_link("../page", leaf, &exported),
+            Some("sub/page.md".into())
+        );
+        assert_eq!(
+            relative_href(leaf, Path::new("sub/page.md")),
+            "../../sub/page.html"
+        );
+        assert_eq!(
+            relative_href(Path::new("index.md"), leaf),
+            "sub/deeper/leaf.html"
+        );
+    }
+
+    #[test]
+    fn normalize_drops_dot_components() {
+        assert_eq!(
+            normalize(Path::new("a/./b/../c.md")),
+            PathBuf::from("a/c.md")
+        );
+        assert_eq!(normalize(Path::new("../a.md")), PathBuf::from("a.md"));
+    }
+
+    #[test]
+    fn unresolved_links_stay_text_and_are_reported() {
+        let exported: HashSet<PathBuf> = ["sub/page.md", "index.md"].map(PathBuf::from).into();
+        let mut skipped = Vec::new();
+        let note = "[[../index|Home]] and [[nowhere]]";
+        let html = render_note(note, Path::new("sub/page.md"), &exported, &mut skipped);
+        assert_eq!(
+            html,
+            "<p><a href=\"../index.html\">Home</a> and [[nowhere]]</p>\n"
+        );
+        assert_eq!(skipped, ["nowhere"]);
+    }
+}
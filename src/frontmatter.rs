@@ -1,5 +1,5 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
-// oxid - YAML frontmatter parsing for tags
+// oxid - YAML frontmatter parsing for tags, aliases and note IDs
 
 use regex::Regex;
 use std::collections::HashSet;
@@ -7,49 +7,99 @@ use std::collections::HashSet;
 /// Parse tags from YAML-like frontmatter at the top of markdown content.
 /// Looks for `tags: [a, b, c]` or `tags: a, b, c`.
 pub fn parse_tags(content: &str) -> HashSet<String> {
-    let mut tags = HashSet::new();
-
-    let Ok(frontmatter_re) = Regex::new(r"(?s)^---\s*\n(.*?)\n---") else { return tags };
-    let frontmatter = match frontmatter_re.captures(content) {
-        Some(c) => match c.get(1) {
-            Some(m) => m.as_str(),
-            None => return tags,
-        },
-        None => return tags,
+    parse_list(content, "tags", |c: char| c.is_whitespace() || c == ',')
+}
+
+/// Parse alternative note names from frontmatter: `aliases: [ProjA, Project Alpha]` or
+/// `aliases: ProjA, Project Alpha`. Unlike tags, aliases may contain spaces, so the
+/// unbracketed form is split at commas only.
+pub fn parse_aliases(content: &str) -> HashSet<String> {
+    parse_list(content, "aliases", |c: char| c == ',')
+}
+
+/// The note ID from frontmatter: `id: 202403141530`.
+pub fn parse_id(content: &str) -> Option<String> {
+    let re = Regex::new(r"(?m)^id:[ \t]*(.+?)[ \t]*$").ok()?;
+    let cap = re.captures(frontmatter(content)?)?;
+    let id = cap.get(1)?.as_str().trim_matches(|c| c == '"' || c == '\'');
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+/// `content` with `tag` added to the frontmatter `tags:` field, which (like the frontmatter
+/// itself) is added when missing.
+pub fn with_tag(content: &str, tag: &str) -> String {
+    let Some(fields) = Regex::new(r"(?s)^---\s*\n(.*?)\n---")
+        .ok()
+        .and_then(|re| re.captures(content)?.get(1))
+    else {
+        return format!("---\ntags: [{tag}]\n---\n{content}");
+    };
+    let mut offset = fields.start();
+    for line in fields.as_str().split('\n') {
+        if let Some(value) = line.strip_prefix("tags:") {
+            let value = value.trim();
+            let tags = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                Some(list) if !list.trim().is_empty() => format!("[{}, {tag}]", list.trim()),
+                None if !value.is_empty() => format!("{value}, {tag}"),
+                _ => format!("[{tag}]"),
+            };
+            let rest = &content[offset + line.len()..];
+            return format!("{}tags: {tags}{rest}", &content[..offset]);
+        }
+        offset += line.len() + 1;
+    }
+    let start = fields.start();
+    format!("{}tags: [{tag}]\n{}", &content[..start], &content[start..])
+}
+
+/// The text between the `---` lines at the top of `content`.
+fn frontmatter(content: &str) -> Option<&str> {
+    let re = Regex::new(r"(?s)^---\s*\n(.*?)\n---").ok()?;
+    Some(re.captures(content)?.get(1)?.as_str())
+}
+
+/// Values of the frontmatter field `key`, either as `[a, b]` or as a plain line split with
+/// `separator`.
+fn parse_list(content: &str, key: &str, separator: fn(char) -> bool) -> HashSet<String> {
+    let mut values = HashSet::new();
+
+    let Some(frontmatter) = frontmatter(content) else {
+        return values;
     };
 
-    if let Ok(tags_re) = Regex::new(r"tags:\s*\[([^\]]*)\]") {
-        if let Some(cap) = tags_re.captures(frontmatter) {
+    let key = regex::escape(key);
+    if let Ok(list_re) = Regex::new(&format!(r"{key}:\s*\[([^\]]*)\]")) {
+        if let Some(cap) = list_re.captures(frontmatter) {
             if let Some(m) = cap.get(1) {
-                for tag in m.as_str().split(',') {
-                    let t = tag
+                for value in m.as_str().split(',') {
+                    let v = value
                         .trim()
                         .trim_matches(|c| c == '"' || c == '\'')
                         .to_string();
-                    if !t.is_empty() {
-                        tags.insert(t);
+                    if !v.is_empty() {
+                        values.insert(v);
                     }
                 }
-                return tags;
+                return values;
             }
         }
     }
 
-    if let Ok(tags_line_re) = Regex::new(r"tags:\s*(.+)") {
-        if let Some(cap) = tags_line_re.captures(frontmatter) {
+    if let Ok(line_re) = Regex::new(&format!(r"{key}:\s*(.+)")) {
+        if let Some(cap) = line_re.captures(frontmatter) {
             if let Some(m) = cap.get(1) {
-                for tag in m.as_str().split(|c: char| c.is_whitespace() || c == ',') {
-                    let t = tag
+                for value in m.as_str().split(separator) {
+                    let v = value
                         .trim()
                         .trim_matches(|c| c == '"' || c == '\'')
                         .to_string();
-                    if !t.is_empty() {
-                        tags.insert(t);
+                    if !v.is_empty() {
+                        values.insert(v);
                     }
                 }
             }
         }
     }
 
-    tags
+    values
 }
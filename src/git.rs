@@ -1,40 +1,291 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // oxid - Git status integration for footer
 
-use std::path::Path;
+use git2::{Branch, Repository, StatusOptions};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-/// Git status: Clean or Dirty (has changes).
+/// How a file differs from the last commit, as shown next to it in the file list.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum GitStatus {
-    Clean,
-    Dirty,
-    /// No .git directory or git not available.
-    Unknown,
+pub enum FileStatus {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
 }
 
-/// Check git status for the given directory.
-/// Runs `git status -s` and returns Dirty if there is any output.
-pub fn get_git_status(dir: &Path) -> GitStatus {
-    let git_dir = dir.join(".git");
-    if !git_dir.exists() {
-        return GitStatus::Unknown;
+impl FileStatus {
+    /// Letter shown in the file list, as in `git status -s`.
+    pub fn marker(self) -> char {
+        match self {
+            Self::Modified => 'M',
+            Self::Added => 'A',
+            Self::Deleted => 'D',
+            Self::Renamed => 'R',
+            Self::Untracked => '?',
+            Self::Conflicted => 'U',
+        }
+    }
+}
+
+/// State of the vault's repository: changed files, and commits ahead of and behind the
+/// upstream branch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitStatus {
+    pub ahead: usize,
+    pub behind: usize,
+    /// Changed files by absolute path.
+    pub files: HashMap<PathBuf, FileStatus>,
+}
+
+impl GitStatus {
+    pub fn is_dirty(&self) -> bool {
+        !self.files.is_empty()
+    }
+
+    /// Status of `path`; a folder counts as modified when a file below it changed.
+    pub fn file(&self, path: &Path) -> Option<FileStatus> {
+        if let Some(&status) = self.files.get(path) {
+            return Some(status);
+        }
+        self.files
+            .keys()
+            .any(|p| p.starts_with(path))
+            .then_some(FileStatus::Modified)
+    }
+}
+
+/// Run `git` with `args` in `dir`; a failure carries git's error output.
+pub fn run(dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git").args(args).current_dir(dir).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{}", stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Git status of the repository at `dir`, or `None` when `dir` is not the root of one or
+/// it cannot be read. It is read with libgit2, or with the `git` binary when `use_binary`
+/// is set.
+pub fn get_git_status(dir: &Path, use_binary: bool) -> Option<GitStatus> {
+    if !dir.join(".git").exists() {
+        return None;
+    }
+    if use_binary {
+        return binary_status(dir);
+    }
+    library_status(dir).ok()
+}
+
+fn library_status(dir: &Path) -> Result<GitStatus, git2::Error> {
+    let repo = Repository::open(dir)?;
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    let mut status = GitStatus::default();
+    for entry in repo.statuses(Some(&mut options))?.iter() {
+        let flags = entry.status();
+        let kind = if flags.is_conflicted() {
+            FileStatus::Conflicted
+        } else if flags.is_wt_new() {
+            FileStatus::Untracked
+        } else if flags.is_index_new() {
+            FileStatus::Added
+        } else if flags.is_index_renamed() || flags.is_wt_renamed() {
+            FileStatus::Renamed
+        } else if flags.is_index_deleted() || flags.is_wt_deleted() {
+            FileStatus::Deleted
+        } else if flags.is_ignored() || flags.is_empty() {
+            continue;
+        } else {
+            FileStatus::Modified
+        };
+        // A rename is listed under the path it was renamed to.
+        let path = entry
+            .head_to_index()
+            .filter(|_| kind == FileStatus::Renamed)
+            .and_then(|delta| delta.new_file().path().map(Path::to_path_buf))
+            .or_else(|| entry.path().ok().map(PathBuf::from));
+        if let Some(path) = path {
+            status.files.insert(dir.join(path), kind);
+        }
+    }
+    // No upstream (or no commit yet) leaves both counts at 0.
+    let upstream = repo.head().ok().and_then(|head| {
+        let local = head.target()?;
+        let upstream = Branch::wrap(head).upstream().ok()?.get().target()?;
+        Some((local, upstream))
+    });
+    if let Some((local, upstream)) = upstream {
+        (status.ahead, status.behind) = repo.graph_ahead_behind(local, upstream)?;
     }
+    Ok(status)
+}
 
+fn binary_status(dir: &Path) -> Option<GitStatus> {
     let output = Command::new("git")
-        .arg("status")
-        .arg("-s")
+        .args([
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "-z",
+            "--untracked-files=all",
+        ])
         .current_dir(dir)
-        .output();
-
-    match output {
-        Ok(out) if out.status.success() => {
-            if out.stdout.is_empty() {
-                GitStatus::Clean
-            } else {
-                GitStatus::Dirty
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(parse_status(&String::from_utf8_lossy(&output.stdout), dir))
+}
+
+/// Parse `git status --porcelain=v2 --branch -z` output; paths are relative to `root`.
+fn parse_status(output: &str, root: &Path) -> GitStatus {
+    let mut status = GitStatus::default();
+    let mut fields = output.split('\0');
+    while let Some(field) = fields.next() {
+        if let Some(ab) = field.strip_prefix("# branch.ab ") {
+            let mut counts = ab
+                .split(' ')
+                .map(|n| n.get(1..).and_then(|n| n.parse().ok()).unwrap_or(0));
+            status.ahead = counts.next().unwrap_or(0);
+            status.behind = counts.next().unwrap_or(0);
+            continue;
+        }
+        // Ordinary, renamed and unmerged entries have this many fields before the path.
+        let (skip, kind) = match field.split_at_checked(2) {
+            Some(("1 ", _)) => (8, None),
+            Some(("2 ", _)) => (9, Some(FileStatus::Renamed)),
+            Some(("u ", _)) => (10, Some(FileStatus::Conflicted)),
+            Some(("? ", path)) => {
+                status.files.insert(root.join(path), FileStatus::Untracked);
+                continue;
             }
+            _ => continue,
+        };
+        let mut parts = field.splitn(skip + 1, ' ');
+        let xy = parts.nth(1).unwrap_or("..");
+        let Some(path) = parts.nth(skip - 2) else {
+            continue;
+        };
+        if kind == Some(FileStatus::Renamed) {
+            // The path it was renamed from follows as a field of its own.
+            fields.next();
+        }
+        let kind = kind.unwrap_or(match xy.as_bytes() {
+            [b'A', _] => FileStatus::Added,
+            [b'D', _] | [_, b'D'] => FileStatus::Deleted,
+            _ => FileStatus::Modified,
+        });
+        status.files.insert(root.join(path), kind);
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_branch_counts_and_file_statuses() {
+        let root = Path::new("/vault");
+        let output = [
+            "# branch.oid 1234",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "1 .M N... 100644 100644 100644 aaaa aaaa notes/a b.md",
+            "1 A. N... 000000 100644 100644 0000 bbbb new.md",
+            "1 .D N... 100644 100644 000000 cccc cccc gone.md",
+            "2 R. N... 100644 100644 100644 dddd dddd R100 moved.md",
+            "old.md",
+            "u UU N... 100644 100644 100644 100644 e e e clash.md",
+            "? inbox/idea.md",
+            "",
+        ]
+        .join("\0");
+        let status = parse_status(&output, root);
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        let file = |p: &str| status.file(&root.join(p));
+        assert_eq!(file("notes/a b.md"), Some(FileStatus::Modified));
+        assert_eq!(file("new.md"), Some(FileStatus::Added));
+        assert_eq!(file("gone.md"), Some(FileStatus::Deleted));
+        assert_eq!(file("moved.md"), Some(FileStatus::Renamed));
+        assert_eq!(file("old.md"), None);
+        assert_eq!(file("clash.md"), Some(FileStatus::Conflicted));
+        assert_eq!(file("inbox/idea.md"), Some(FileStatus::Untracked));
+        assert_eq!(file("inbox"), Some(FileStatus::Modified));
+        assert_eq!(file("other"), None);
+        assert!(status.is_dirty());
+
+        let clean = parse_status("# branch.oid 1234\0# branch.head main\0", root);
+        assert_eq!(clean, GitStatus::default());
+        assert!(!clean.is_dirty());
+    }
+
+    #[test]
+    fn library_status_matches_the_git_binary() {
+        let dir = std::env::temp_dir().join(format!("oxid-git2-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = Repository::init(&dir).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "oxid").unwrap();
+        config.set_str("user.email", "oxid@example.com").unwrap();
+        for (name, content) in [("a.md", "a"), ("b.md", "b"), ("notes/c.md", "c")] {
+            std::fs::create_dir_all(dir.join(name).parent().unwrap()).unwrap();
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+        let commit = |message: &str| {
+            let mut index = repo.index().unwrap();
+            index
+                .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+                .unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig = repo.signature().unwrap();
+            let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+            let parents: Vec<_> = parent.iter().collect();
+            repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+                .unwrap();
+        };
+        commit("first");
+        let head = repo.head().unwrap();
+        let branch = head.shorthand().unwrap().to_string();
+        repo.branch("base", &head.peel_to_commit().unwrap(), false)
+            .unwrap();
+        config
+            .set_str(&format!("branch.{branch}.remote"), ".")
+            .unwrap();
+        config
+            .set_str(&format!("branch.{branch}.merge"), "refs/heads/base")
+            .unwrap();
+        std::fs::write(dir.join("b.md"), "b2").unwrap();
+        commit("second");
+
+        std::fs::write(dir.join("a.md"), "changed").unwrap();
+        std::fs::remove_file(dir.join("b.md")).unwrap();
+        std::fs::write(dir.join("notes/new.md"), "new").unwrap();
+        let status = get_git_status(&dir, false).unwrap();
+        assert_eq!((status.ahead, status.behind), (1, 0));
+        assert_eq!(status.file(&dir.join("a.md")), Some(FileStatus::Modified));
+        assert_eq!(status.file(&dir.join("b.md")), Some(FileStatus::Deleted));
+        assert_eq!(
+            status.file(&dir.join("notes/new.md")),
+            Some(FileStatus::Untracked)
+        );
+        assert_eq!(status.file(&dir.join("notes/c.md")), None);
+        assert_eq!(status.files.len(), 3);
+        if let Some(binary) = get_git_status(&dir, true) {
+            assert_eq!(binary, status);
         }
-        _ => GitStatus::Unknown,
+        assert_eq!(get_git_status(&dir.join("notes"), false), None);
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Link graph export to Graphviz DOT or JSON
+
+use crate::frontmatter::parse_tags;
+use crate::links::LinkGraph;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+impl GraphFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "Graphviz DOT",
+            GraphFormat::Json => "JSON",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::Json => "json",
+        }
+    }
+}
+
+/// What *Export Link Graph* writes.
+#[derive(Debug, Clone)]
+pub struct GraphOptions {
+    pub format: GraphFormat,
+    /// Leave out notes without links to or from other exported notes.
+    pub exclude_orphans: bool,
+    /// Only notes within this many links of the current note; 0 exports the whole vault.
+    pub hops: usize,
+}
+
+impl Default for GraphOptions {
+    fn default() -> Self {
+        Self {
+            format: GraphFormat::Dot,
+            exclude_orphans: false,
+            hops: 0,
+        }
+    }
+}
+
+/// Result of a finished export, for the footer.
+#[derive(Debug)]
+pub struct GraphSummary {
+    pub path: PathBuf,
+    pub nodes: usize,
+    pub edges: usize,
+}
+
+/// A graph export running on a background thread.
+pub struct GraphExport {
+    result: Receiver<Result<GraphSummary>>,
+}
+
+impl GraphExport {
+    /// Export `graph` (a snapshot, so the app keeps its own) to `out`. Paths are written
+    /// relative to `root`; `center` is the note the neighborhood option counts hops from.
+    pub fn spawn(
+        graph: LinkGraph,
+        root: PathBuf,
+        center: Option<PathBuf>,
+        options: GraphOptions,
+        out: PathBuf,
+    ) -> Self {
+        let (tx, result) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(export_graph(
+                &graph,
+                &root,
+                center.as_deref(),
+                &options,
+                &out,
+            ));
+        });
+        Self { result }
+    }
+
+    /// The export's result once it is done.
+    pub fn poll(&self) -> Option<Result<GraphSummary>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err(anyhow::anyhow!("Graph export stopped unexpectedly")))
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonGraph {
+    nodes: Vec<JsonNode>,
+    edges: Vec<JsonEdge>,
+}
+
+#[derive(Serialize)]
+struct JsonNode {
+    path: String,
+    title: String,
+    tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct JsonEdge {
+    source: String,
+    target: String,
+}
+
+fn export_graph(
+    graph: &LinkGraph,
+    root: &Path,
+    center: Option<&Path>,
+    options: &GraphOptions,
+    out: &Path,
+) -> Result<GraphSummary> {
+    let mut nodes: BTreeSet<PathBuf> = graph.notes().map(Path::to_path_buf).collect();
+    let mut edges = graph.edges();
+    if options.hops > 0 {
+        let center = center.context("Open a note to export its neighborhood")?;
+        nodes = neighborhood(&edges, center, options.hops);
+        edges.retain(|(s, t)| nodes.contains(s) && nodes.contains(t));
+    }
+    if options.exclude_orphans {
+        let linked: BTreeSet<&PathBuf> = edges.iter().flat_map(|(s, t)| [s, t]).collect();
+        nodes.retain(|n| linked.contains(n));
+    }
+
+    let rel = |p: &Path| p.strip_prefix(root).unwrap_or(p).display().to_string();
+    let title = |p: &Path| {
+        p.file_stem()
+            .map_or_else(String::new, |s| s.to_string_lossy().into_owned())
+    };
+    let content = match options.format {
+        GraphFormat::Dot => {
+            let mut dot = String::from("digraph vault {\n");
+            for node in &nodes {
+                let _ = writeln!(
+                    dot,
+                    "  {} [label={}];",
+                    dot_string(&rel(node)),
+                    dot_string(&title(node))
+                );
+            }
+            for (source, target) in &edges {
+                let _ = writeln!(
+                    dot,
+                    "  {} -> {};",
+                    dot_string(&rel(source)),
+                    dot_string(&rel(target))
+                );
+            }
+            dot.push_str("}\n");
+            dot
+        }
+        GraphFormat::Json => {
+            let json = JsonGraph {
+                nodes: nodes
+                    .iter()
+                    .map(|node| {
+                        let mut tags: Vec<String> = fs::read_to_string(node)
+                            .map(|content| parse_tags(&content).into_iter().collect())
+                            .unwrap_or_default();
+                        tags.sort();
+                        JsonNode {
+                            path: rel(node),
+                            title: title(node),
+                            tags,
+                        }
+                    })
+                    .collect(),
+                edges: edges
+                    .iter()
+                    .map(|(source, target)| JsonEdge {
+                        source: rel(source),
+                        target: rel(target),
+                    })
+                    .collect(),
+            };
+            serde_json::to_string_pretty(&json)?
+        }
+    };
+    if let Some(dir) = out.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::write(out, content).with_context(|| format!("Failed to write {}", out.display()))?;
+    Ok(GraphSummary {
+        path: out.to_path_buf(),
+        nodes: nodes.len(),
+        edges: edges.len(),
+    })
+}
+
+/// Notes within `hops` links of `center`, following links in either direction.
+fn neighborhood(
+    edges: &BTreeSet<(PathBuf, PathBuf)>,
+    center: &Path,
+    hops: usize,
+) -> BTreeSet<PathBuf> {
+    let mut adjacent: HashMap<&Path, Vec<&Path>> = HashMap::new();
+    for (source, target) in edges {
+        adjacent.entry(source).or_default().push(target);
+        adjacent.entry(target).or_default().push(source);
+    }
+    let mut seen = BTreeSet::from([center.to_path_buf()]);
+    let mut queue = VecDeque::from([(center, 0)]);
+    while let Some((node, depth)) = queue.pop_front() {
+        if depth == hops {
+            continue;
+        }
+        for next in adjacent.get(node).into_iter().flatten() {
+            if seen.insert(next.to_path_buf()) {
+                queue.push_back((next, depth + 1));
+            }
+        }
+    }
+    seen
+}
+
+/// Quoted DOT identifier.
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
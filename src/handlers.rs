@@ -1,10 +1,11 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // oxid - Input handling: key comparison against config
 
+use crate::keymap::same_key;
 use crossterm::event::KeyEvent;
 
-/// Returns true if the pressed key matches any of the given keys (code + modifiers only).
+/// Returns true if the pressed key matches any of the given keys (code + modifiers only;
+/// `shift-v` also matches `V`, as terminals report it either way).
 pub fn key_matches(event: KeyEvent, keys: &[KeyEvent]) -> bool {
-    keys.iter()
-        .any(|k| event.code == k.code && event.modifiers == k.modifiers)
+    keys.iter().any(|&k| same_key(event, k))
 }
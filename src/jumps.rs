@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Jump list: positions left by cross-file navigation, walked with Ctrl+O / Ctrl+I
+
+use std::path::{Path, PathBuf};
+
+/// Maximum number of positions kept; the oldest go first.
+const MAX_JUMPS: usize = 100;
+
+/// A position in a note: its path and 0-based line.
+pub type Jump = (PathBuf, usize);
+
+/// Positions in visiting order, oldest first, with a cursor that walking back and forth
+/// moves. The cursor is at the end (one past the newest) until the user walks back.
+#[derive(Debug, Default)]
+pub struct JumpList {
+    jumps: Vec<Jump>,
+    index: usize,
+}
+
+impl JumpList {
+    /// Record the position being left for another file. Positions ahead of the cursor are
+    /// dropped, as in a browser's history.
+    pub fn record(&mut self, path: &Path, line: usize) {
+        self.jumps.truncate(self.index);
+        if self.jumps.last() != Some(&(path.to_path_buf(), line)) {
+            self.jumps.push((path.to_path_buf(), line));
+        }
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.index = self.jumps.len();
+    }
+
+    /// Ctrl+O: the previous position whose file still exists. `current` is where the cursor
+    /// is now; leaving the newest end keeps it, so Ctrl+I can come back to it.
+    pub fn back(&mut self, current: Option<Jump>) -> Option<Jump> {
+        let target = (0..self.index.min(self.jumps.len()))
+            .rev()
+            .find(|&i| self.jumps[i].0.exists())?;
+        if self.index >= self.jumps.len() {
+            if let Some(current) = current.filter(|c| self.jumps.last() != Some(c)) {
+                self.jumps.push(current);
+            }
+        }
+        self.index = target;
+        Some(self.jumps[target].clone())
+    }
+
+    /// Ctrl+I: the next position whose file still exists, after walking back.
+    pub fn forward(&mut self) -> Option<Jump> {
+        let target = (self.index + 1..self.jumps.len()).find(|&i| self.jumps[i].0.exists())?;
+        self.index = target;
+        Some(self.jumps[target].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn walks_back_and_forth_skipping_deleted_notes() {
+        let dir = std::env::temp_dir().join(format!("oxid-jumps-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let [a, b, c] = ["a.md", "b.md", "c.md"].map(|n| dir.join(n));
+        for path in [&a, &b, &c] {
+            fs::write(path, "").unwrap();
+        }
+
+        let mut jumps = JumpList::default();
+        jumps.record(&a, 3);
+        jumps.record(&b, 0);
+        assert_eq!(jumps.back(Some((c.clone(), 7))), Some((b.clone(), 0)));
+        assert_eq!(jumps.back(None), Some((a.clone(), 3)));
+        assert_eq!(jumps.back(None), None);
+        assert_eq!(jumps.forward(), Some((b.clone(), 0)));
+        assert_eq!(jumps.forward(), Some((c.clone(), 7)));
+        assert_eq!(jumps.forward(), None);
+
+        fs::remove_file(&b).unwrap();
+        assert_eq!(jumps.back(None), Some((a.clone(), 3)));
+        assert_eq!(jumps.forward(), Some((c.clone(), 7)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_the_newest_positions() {
+        let mut jumps = JumpList::default();
+        for line in 0..MAX_JUMPS + 10 {
+            jumps.record(Path::new("note.md"), line);
+        }
+        assert_eq!(jumps.jumps.len(), MAX_JUMPS);
+        assert_eq!(jumps.jumps[0].1, 10);
+    }
+}
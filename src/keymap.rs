@@ -0,0 +1,1089 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Multi-key sequences (leader keys, g/z commands) and their matcher
+
+use crate::config::{key_display_string, parse_key_event, KeysConfig};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::time::{Duration, Instant};
+
+/// How long a pending prefix waits for its next key before it is resolved.
+pub const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Whether a prefix pending since `since` has waited out `SEQUENCE_TIMEOUT` at `now`, and is
+/// to be resolved without its next key.
+pub fn sequence_expired(since: Instant, now: Instant) -> bool {
+    now.saturating_duration_since(since) >= SEQUENCE_TIMEOUT
+}
+
+/// How long a prefix must be pending before the which-key hint popup appears.
+pub const WHICH_KEY_DELAY: Duration = Duration::from_millis(300);
+
+/// One or more keystrokes, e.g. `"space g p"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySequence(pub Vec<KeyEvent>);
+
+/// Parse a whitespace-separated list of key strings into a sequence. Returns None if any
+/// key fails to parse or the string is empty.
+pub fn parse_key_sequence(s: &str) -> Option<KeySequence> {
+    let keys: Vec<KeyEvent> = s
+        .split_whitespace()
+        .map(parse_key_event)
+        .collect::<Option<_>>()?;
+    (!keys.is_empty()).then_some(KeySequence(keys))
+}
+
+/// Whether `key` types a character into a text field: anything but ctrl and alt chords and
+/// function keys.
+fn is_typed(key: KeyEvent) -> bool {
+    !key.modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        && !matches!(key.code, KeyCode::F(_))
+}
+
+/// Compare keys the way terminals report them: `T` and `shift-t` are the same key, and
+/// shift is ignored for symbols.
+pub fn same_key(a: KeyEvent, b: KeyEvent) -> bool {
+    fn normalize(k: KeyEvent) -> (KeyCode, KeyModifiers) {
+        match k.code {
+            KeyCode::Char(c) if c.is_alphabetic() => {
+                let mut mods = k.modifiers;
+                if c.is_uppercase() {
+                    mods.insert(KeyModifiers::SHIFT);
+                }
+                (KeyCode::Char(c.to_lowercase().next().unwrap_or(c)), mods)
+            }
+            KeyCode::Char(c) => (KeyCode::Char(c), k.modifiers - KeyModifiers::SHIFT),
+            code => (code, k.modifiers),
+        }
+    }
+    normalize(a) == normalize(b)
+}
+
+/// Actions that can be bound to key sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    ZenMode,
+    Search,
+    CommandPalette,
+    DailyNote,
+    QuickCapture,
+    AddTask,
+    TaskBoard,
+    PaneGrow,
+    PaneShrink,
+    GitPush,
+    RandomNote,
+    Suspend,
+    NextTab,
+    PrevTab,
+    ToggleSplit,
+    CloseTab,
+    CloseOtherTabs,
+    CloseAllTabs,
+    ReopenClosedTab,
+    FollowLink,
+    SpellSuggest,
+    SpellAddWord,
+    ExternalEditor,
+    ToggleTask,
+    DisplayLineDown,
+    DisplayLineUp,
+    DisplayLineStart,
+    DisplayLineEnd,
+    /// Go to the folder at this level of the breadcrumb path; 0 is the vault root.
+    JumpToLevel(u8),
+}
+
+/// Which-key labels for `KeyAction::JumpToLevel`.
+const LEVEL_LABELS: [&str; 10] = [
+    "vault root",
+    "level 1",
+    "level 2",
+    "level 3",
+    "level 4",
+    "level 5",
+    "level 6",
+    "level 7",
+    "level 8",
+    "level 9",
+];
+
+impl KeyAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyAction::ZenMode => "zen mode",
+            KeyAction::Search => "search",
+            KeyAction::CommandPalette => "command palette",
+            KeyAction::DailyNote => "daily note",
+            KeyAction::QuickCapture => "quick capture",
+            KeyAction::AddTask => "add task",
+            KeyAction::TaskBoard => "task board",
+            KeyAction::PaneGrow => "grow editor",
+            KeyAction::PaneShrink => "shrink editor",
+            KeyAction::GitPush => "git push",
+            KeyAction::RandomNote => "random note",
+            KeyAction::Suspend => "suspend",
+            KeyAction::NextTab => "next tab",
+            KeyAction::PrevTab => "prev tab",
+            KeyAction::ToggleSplit => "split",
+            KeyAction::CloseTab => "close tab",
+            KeyAction::CloseOtherTabs => "close other tabs",
+            KeyAction::CloseAllTabs => "close all tabs",
+            KeyAction::ReopenClosedTab => "reopen closed tab",
+            KeyAction::FollowLink => "follow link",
+            KeyAction::SpellSuggest => "spelling suggestions",
+            KeyAction::SpellAddWord => "add word to dictionary",
+            KeyAction::ExternalEditor => "external editor",
+            KeyAction::ToggleTask => "toggle task",
+            KeyAction::DisplayLineDown => "display line down",
+            KeyAction::DisplayLineUp => "display line up",
+            KeyAction::DisplayLineStart => "display line start",
+            KeyAction::DisplayLineEnd => "display line end",
+            KeyAction::JumpToLevel(level) => LEVEL_LABELS[usize::from(*level).min(9)],
+        }
+    }
+
+    /// Actions that only apply to the file list.
+    fn list_only(&self) -> bool {
+        matches!(self, KeyAction::JumpToLevel(_))
+    }
+
+    /// Actions that only apply to the editor in Normal mode.
+    fn editor_only(&self) -> bool {
+        matches!(
+            self,
+            KeyAction::NextTab
+                | KeyAction::PrevTab
+                | KeyAction::ToggleSplit
+                | KeyAction::CloseTab
+                | KeyAction::CloseOtherTabs
+                | KeyAction::CloseAllTabs
+                | KeyAction::ReopenClosedTab
+                | KeyAction::FollowLink
+                | KeyAction::SpellSuggest
+                | KeyAction::SpellAddWord
+                | KeyAction::ExternalEditor
+                | KeyAction::ToggleTask
+                | KeyAction::DisplayLineDown
+                | KeyAction::DisplayLineUp
+                | KeyAction::DisplayLineStart
+                | KeyAction::DisplayLineEnd
+        )
+    }
+}
+
+/// Where a key was pressed, which decides the bindings that apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyContext {
+    /// Editor in Normal mode: every binding except the file list's.
+    EditorNormal,
+    /// File list in Normal mode: global bindings, including multi-key ones.
+    Navigation,
+    /// Popups: only single-key global bindings.
+    Other,
+    /// Text input, including the editor in Insert mode: only single-key global bindings
+    /// that cannot be typed, that is ctrl and alt chords and function keys.
+    TextInput,
+}
+
+/// Result of matching the pending keys against the bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceMatch {
+    /// No binding starts with these keys.
+    None,
+    /// Some longer binding starts with these keys.
+    Prefix,
+    /// These keys are a complete binding and no longer one starts with them.
+    Exact(KeyAction),
+    /// These keys are a complete binding and also a prefix of a longer one; the longer
+    /// one wins if its next key arrives before `SEQUENCE_TIMEOUT`.
+    Ambiguous(KeyAction),
+}
+
+/// One binding: the parsed keys, the action, and the key strings from the config (used to
+/// display continuations).
+#[derive(Debug, Clone)]
+struct Binding {
+    seq: KeySequence,
+    action: KeyAction,
+    keys: Vec<String>,
+}
+
+/// All sequence bindings from `[keys]`.
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    pub fn from_config(keys: &KeysConfig) -> Self {
+        let entries = [
+            (&keys.zen_mode, KeyAction::ZenMode),
+            (&keys.search, KeyAction::Search),
+            (&keys.command_palette, KeyAction::CommandPalette),
+            (&keys.daily_note, KeyAction::DailyNote),
+            (&keys.quick_capture, KeyAction::QuickCapture),
+            (&keys.add_task, KeyAction::AddTask),
+            (&keys.task_board, KeyAction::TaskBoard),
+            (&keys.pane_grow, KeyAction::PaneGrow),
+            (&keys.pane_shrink, KeyAction::PaneShrink),
+            (&keys.git_push, KeyAction::GitPush),
+            (&keys.random_note, KeyAction::RandomNote),
+            (&keys.suspend, KeyAction::Suspend),
+            (&keys.editor_next_tab, KeyAction::NextTab),
+            (&keys.editor_prev_tab, KeyAction::PrevTab),
+            (&keys.editor_toggle_split, KeyAction::ToggleSplit),
+            (&keys.editor_close_tab, KeyAction::CloseTab),
+            (&keys.editor_close_others, KeyAction::CloseOtherTabs),
+            (&keys.editor_close_all, KeyAction::CloseAllTabs),
+            (&keys.editor_reopen_tab, KeyAction::ReopenClosedTab),
+            (&keys.editor_follow_link, KeyAction::FollowLink),
+            (&keys.editor_spell_suggest, KeyAction::SpellSuggest),
+            (&keys.editor_spell_add, KeyAction::SpellAddWord),
+            (&keys.editor_external, KeyAction::ExternalEditor),
+            (&keys.editor_toggle_task, KeyAction::ToggleTask),
+            (&keys.editor_display_down, KeyAction::DisplayLineDown),
+            (&keys.editor_display_up, KeyAction::DisplayLineUp),
+            (&keys.editor_row_start, KeyAction::DisplayLineStart),
+            (&keys.editor_row_end, KeyAction::DisplayLineEnd),
+        ];
+        let mut entries: Vec<(String, KeyAction)> = entries
+            .into_iter()
+            .map(|(s, action)| (s.clone(), action))
+            .collect();
+        // The level jumps share one prefix, followed by the level's digit.
+        if !keys.list_jump_level.trim().is_empty() {
+            entries.extend((0..10u8).map(|level| {
+                (
+                    format!("{} {level}", keys.list_jump_level),
+                    KeyAction::JumpToLevel(level),
+                )
+            }));
+        }
+        let bindings = entries
+            .into_iter()
+            .filter_map(|(s, action)| {
+                Some(Binding {
+                    seq: parse_key_sequence(&s)?,
+                    action,
+                    keys: s.split_whitespace().map(str::to_string).collect(),
+                })
+            })
+            .collect();
+        Self { bindings }
+    }
+
+    fn applies(seq: &KeySequence, action: KeyAction, context: KeyContext) -> bool {
+        match context {
+            KeyContext::EditorNormal => !action.list_only(),
+            KeyContext::Navigation => !action.editor_only(),
+            KeyContext::Other => !action.editor_only() && seq.0.len() == 1,
+            KeyContext::TextInput => {
+                !action.editor_only() && matches!(seq.0[..], [key] if !is_typed(key))
+            }
+        }
+    }
+
+    /// Bindings that apply in `context` and start with `pending`.
+    fn matching<'a>(
+        &'a self,
+        pending: &'a [KeyEvent],
+        context: KeyContext,
+    ) -> impl Iterator<Item = &'a Binding> {
+        self.bindings.iter().filter(move |b| {
+            Self::applies(&b.seq, b.action, context)
+                && b.seq.0.len() >= pending.len()
+                && b.seq.0.iter().zip(pending).all(|(a, k)| same_key(*a, *k))
+        })
+    }
+
+    /// Match `pending` against the bindings that apply in `context`.
+    pub fn lookup(&self, pending: &[KeyEvent], context: KeyContext) -> SequenceMatch {
+        let mut exact = None;
+        let mut longer = false;
+        for binding in self.matching(pending, context) {
+            if binding.seq.0.len() == pending.len() {
+                exact.get_or_insert(binding.action);
+            } else {
+                longer = true;
+            }
+        }
+        match (exact, longer) {
+            (Some(action), true) => SequenceMatch::Ambiguous(action),
+            (Some(action), false) => SequenceMatch::Exact(action),
+            (None, true) => SequenceMatch::Prefix,
+            (None, false) => SequenceMatch::None,
+        }
+    }
+
+    /// Remaining keys (for display) and label of every binding that continues `pending`.
+    pub fn continuations(
+        &self,
+        pending: &[KeyEvent],
+        context: KeyContext,
+    ) -> Vec<(String, &'static str)> {
+        self.matching(pending, context)
+            .filter(|b| b.seq.0.len() > pending.len())
+            .map(|b| {
+                (
+                    key_display_string(&b.keys[pending.len()..].join(" ")),
+                    b.action.label(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Where a configurable binding is active, for conflict checking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// Checked before everything else, so it shadows every other scope.
+    Global,
+    List,
+    Editor,
+    Popup,
+}
+
+impl Scope {
+    fn label(self) -> &'static str {
+        match self {
+            Scope::Global => "global",
+            Scope::List => "file list",
+            Scope::Editor => "editor",
+            Scope::Popup => "popups",
+        }
+    }
+}
+
+/// One `[keys]` entry as seen by `check_keybindings`.
+struct CheckedKey<'a> {
+    name: &'static str,
+    /// Effective key string after falling back to the default.
+    keys: &'a str,
+    sequence: KeySequence,
+    /// Entries in the same group run the same command, so sharing a key is intended.
+    group: &'static str,
+    scopes: &'static [Scope],
+}
+
+/// (name, configured string, default for single keys or None for sequences, group, scopes)
+type KeyRow<'a> = (
+    &'static str,
+    &'a str,
+    Option<&'a str>,
+    &'static str,
+    &'static [Scope],
+);
+
+/// Problems in `[keys]`, one line each: keys bound to different commands in a shared
+/// context, and key strings that do not parse (which fall back to the default, or leave a
+/// sequence binding unbound).
+pub fn check_keybindings(keys: &KeysConfig) -> Vec<String> {
+    use Scope::{Editor, Global, List, Popup};
+    let defaults = KeysConfig::default();
+    let table: &[KeyRow] = &[
+        ("quit", &keys.quit, Some(&defaults.quit), "quit", &[List]),
+        ("zen_mode", &keys.zen_mode, None, "zen_mode", &[Global]),
+        ("search", &keys.search, None, "search", &[Global]),
+        (
+            "command_palette",
+            &keys.command_palette,
+            None,
+            "command_palette",
+            &[Global],
+        ),
+        (
+            "daily_note",
+            &keys.daily_note,
+            None,
+            "daily_note",
+            &[Global],
+        ),
+        (
+            "quick_capture",
+            &keys.quick_capture,
+            None,
+            "quick_capture",
+            &[Global],
+        ),
+        ("add_task", &keys.add_task, None, "add_task", &[Global]),
+        (
+            "task_board",
+            &keys.task_board,
+            None,
+            "task_board",
+            &[Global],
+        ),
+        ("pane_grow", &keys.pane_grow, None, "pane_grow", &[Global]),
+        (
+            "pane_shrink",
+            &keys.pane_shrink,
+            None,
+            "pane_shrink",
+            &[Global],
+        ),
+        ("git_push", &keys.git_push, None, "git_push", &[Global]),
+        (
+            "random_note",
+            &keys.random_note,
+            None,
+            "random_note",
+            &[Global],
+        ),
+        ("suspend", &keys.suspend, None, "suspend", &[Global]),
+        (
+            "escape",
+            &keys.escape,
+            Some(&defaults.escape),
+            "escape",
+            &[List, Editor, Popup],
+        ),
+        (
+            "enter",
+            &keys.enter,
+            Some(&defaults.enter),
+            "enter",
+            &[List, Editor, Popup],
+        ),
+        (
+            "backspace",
+            &keys.backspace,
+            Some(&defaults.backspace),
+            "backspace",
+            &[Popup],
+        ),
+        (
+            "move_up",
+            &keys.move_up,
+            Some(&defaults.move_up),
+            "move_up",
+            &[List, Popup],
+        ),
+        (
+            "move_down",
+            &keys.move_down,
+            Some(&defaults.move_down),
+            "move_down",
+            &[List, Popup],
+        ),
+        (
+            "move_left",
+            &keys.move_left,
+            Some(&defaults.move_left),
+            "parent",
+            &[List],
+        ),
+        (
+            "move_up_alt",
+            &keys.move_up_alt,
+            Some(&defaults.move_up_alt),
+            "move_up",
+            &[List, Popup],
+        ),
+        (
+            "move_down_alt",
+            &keys.move_down_alt,
+            Some(&defaults.move_down_alt),
+            "move_down",
+            &[List, Popup],
+        ),
+        (
+            "move_left_alt",
+            &keys.move_left_alt,
+            Some(&defaults.move_left_alt),
+            "parent",
+            &[List],
+        ),
+        (
+            "move_right",
+            &keys.move_right,
+            Some(&defaults.move_right),
+            "expand",
+            &[List],
+        ),
+        (
+            "move_right_alt",
+            &keys.move_right_alt,
+            Some(&defaults.move_right_alt),
+            "expand",
+            &[List],
+        ),
+        (
+            "delete",
+            &keys.delete,
+            Some(&defaults.delete),
+            "delete",
+            &[List],
+        ),
+        (
+            "list_create_note",
+            &keys.list_create_note,
+            Some(&defaults.list_create_note),
+            "list_create_note",
+            &[List],
+        ),
+        (
+            "list_create_dir",
+            &keys.list_create_dir,
+            Some(&defaults.list_create_dir),
+            "list_create_dir",
+            &[List],
+        ),
+        (
+            "list_tag_explorer",
+            &keys.list_tag_explorer,
+            Some(&defaults.list_tag_explorer),
+            "list_tag_explorer",
+            &[List],
+        ),
+        (
+            "list_rename",
+            &keys.list_rename,
+            Some(&defaults.list_rename),
+            "list_rename",
+            &[List],
+        ),
+        (
+            "list_edit_config",
+            &keys.list_edit_config,
+            Some(&defaults.list_edit_config),
+            "list_edit_config",
+            &[List],
+        ),
+        (
+            "list_delete",
+            &keys.list_delete,
+            Some(&defaults.list_delete),
+            "delete",
+            &[List],
+        ),
+        (
+            "list_move",
+            &keys.list_move,
+            Some(&defaults.list_move),
+            "list_move",
+            &[List],
+        ),
+        (
+            "list_duplicate",
+            &keys.list_duplicate,
+            Some(&defaults.list_duplicate),
+            "list_duplicate",
+            &[List],
+        ),
+        (
+            "list_archive",
+            &keys.list_archive,
+            Some(&defaults.list_archive),
+            "list_archive",
+            &[List],
+        ),
+        (
+            "list_filter",
+            &keys.list_filter,
+            Some(&defaults.list_filter),
+            "list_filter",
+            &[List],
+        ),
+        (
+            "list_filter_repeat",
+            &keys.list_filter_repeat,
+            Some(&defaults.list_filter_repeat),
+            "list_filter_repeat",
+            &[List],
+        ),
+        (
+            "list_tree",
+            &keys.list_tree,
+            Some(&defaults.list_tree),
+            "list_tree",
+            &[List],
+        ),
+        (
+            "list_mark",
+            &keys.list_mark,
+            Some(&defaults.list_mark),
+            "list_mark",
+            &[List, Popup],
+        ),
+        (
+            "list_tag",
+            &keys.list_tag,
+            Some(&defaults.list_tag),
+            "list_tag",
+            &[List],
+        ),
+        (
+            "list_parent",
+            &keys.list_parent,
+            Some(&defaults.list_parent),
+            "parent",
+            &[List],
+        ),
+        (
+            "list_parent_alt",
+            &keys.list_parent_alt,
+            Some(&defaults.list_parent_alt),
+            "parent",
+            &[List],
+        ),
+        (
+            "list_root",
+            &keys.list_root,
+            Some(&defaults.list_root),
+            "list_root",
+            &[List],
+        ),
+        (
+            "list_jump_level",
+            &keys.list_jump_level,
+            None,
+            "list_jump_level",
+            &[List],
+        ),
+        (
+            "editor_back",
+            &keys.editor_back,
+            Some(&defaults.editor_back),
+            "editor_back",
+            &[Editor],
+        ),
+        (
+            "editor_pdf",
+            &keys.editor_pdf,
+            Some(&defaults.editor_pdf),
+            "editor_pdf",
+            &[Editor],
+        ),
+        (
+            "editor_backlinks",
+            &keys.editor_backlinks,
+            Some(&defaults.editor_backlinks),
+            "editor_backlinks",
+            &[Editor],
+        ),
+        (
+            "editor_wiki_link",
+            &keys.editor_wiki_link,
+            Some(&defaults.editor_wiki_link),
+            "enter",
+            &[Editor],
+        ),
+        (
+            "editor_jump_back",
+            &keys.editor_jump_back,
+            Some(&defaults.editor_jump_back),
+            "editor_jump_back",
+            &[Editor],
+        ),
+        (
+            "editor_jump_forward",
+            &keys.editor_jump_forward,
+            Some(&defaults.editor_jump_forward),
+            "editor_jump_forward",
+            &[Editor],
+        ),
+        (
+            "editor_insert",
+            &keys.editor_insert,
+            Some(&defaults.editor_insert),
+            "editor_insert",
+            &[Editor],
+        ),
+        (
+            "editor_append",
+            &keys.editor_append,
+            Some(&defaults.editor_append),
+            "editor_append",
+            &[Editor],
+        ),
+        (
+            "editor_open_below",
+            &keys.editor_open_below,
+            Some(&defaults.editor_open_below),
+            "editor_open_below",
+            &[Editor],
+        ),
+        (
+            "editor_open_above",
+            &keys.editor_open_above,
+            Some(&defaults.editor_open_above),
+            "editor_open_above",
+            &[Editor],
+        ),
+        (
+            "editor_split_focus",
+            &keys.editor_split_focus,
+            Some(&defaults.editor_split_focus),
+            "editor_split_focus",
+            &[Editor],
+        ),
+        (
+            "editor_paste",
+            &keys.editor_paste,
+            Some(&defaults.editor_paste),
+            "editor_paste",
+            &[Editor],
+        ),
+        (
+            "telescope_open_split",
+            &keys.telescope_open_split,
+            Some(&defaults.telescope_open_split),
+            "telescope_open_split",
+            &[Popup],
+        ),
+        (
+            "telescope_mode",
+            &keys.telescope_mode,
+            Some(&defaults.telescope_mode),
+            "telescope_mode",
+            &[Popup],
+        ),
+        (
+            "search_case",
+            &keys.search_case,
+            Some(&defaults.search_case),
+            "search_case",
+            &[List, Popup],
+        ),
+        (
+            "task_toggle",
+            &keys.task_toggle,
+            Some(&defaults.task_toggle),
+            "task_toggle",
+            &[Popup],
+        ),
+        (
+            "task_advance",
+            &keys.task_advance,
+            Some(&defaults.task_advance),
+            "task_advance",
+            &[Popup],
+        ),
+        (
+            "replace_dry_run",
+            &keys.replace_dry_run,
+            Some(&defaults.replace_dry_run),
+            "replace_dry_run",
+            &[Popup],
+        ),
+        (
+            "editor_next_tab",
+            &keys.editor_next_tab,
+            None,
+            "editor_next_tab",
+            &[Editor],
+        ),
+        (
+            "editor_prev_tab",
+            &keys.editor_prev_tab,
+            None,
+            "editor_prev_tab",
+            &[Editor],
+        ),
+        (
+            "editor_toggle_split",
+            &keys.editor_toggle_split,
+            None,
+            "editor_toggle_split",
+            &[Editor],
+        ),
+        (
+            "editor_close_tab",
+            &keys.editor_close_tab,
+            None,
+            "editor_close_tab",
+            &[Editor],
+        ),
+        (
+            "editor_close_others",
+            &keys.editor_close_others,
+            None,
+            "editor_close_others",
+            &[Editor],
+        ),
+        (
+            "editor_close_all",
+            &keys.editor_close_all,
+            None,
+            "editor_close_all",
+            &[Editor],
+        ),
+        (
+            "editor_reopen_tab",
+            &keys.editor_reopen_tab,
+            None,
+            "editor_reopen_tab",
+            &[Editor],
+        ),
+        (
+            "editor_follow_link",
+            &keys.editor_follow_link,
+            None,
+            "enter",
+            &[Editor],
+        ),
+        (
+            "editor_spell_suggest",
+            &keys.editor_spell_suggest,
+            None,
+            "editor_spell_suggest",
+            &[Editor],
+        ),
+        (
+            "editor_spell_add",
+            &keys.editor_spell_add,
+            None,
+            "editor_spell_add",
+            &[Editor],
+        ),
+        (
+            "editor_external",
+            &keys.editor_external,
+            None,
+            "editor_external",
+            &[Editor],
+        ),
+        (
+            "editor_toggle_task",
+            &keys.editor_toggle_task,
+            None,
+            "editor_toggle_task",
+            &[Editor],
+        ),
+        (
+            "editor_display_down",
+            &keys.editor_display_down,
+            None,
+            "editor_display_down",
+            &[Editor],
+        ),
+        (
+            "editor_display_up",
+            &keys.editor_display_up,
+            None,
+            "editor_display_up",
+            &[Editor],
+        ),
+        (
+            "editor_row_start",
+            &keys.editor_row_start,
+            None,
+            "editor_row_start",
+            &[Editor],
+        ),
+        (
+            "editor_row_end",
+            &keys.editor_row_end,
+            None,
+            "editor_row_end",
+            &[Editor],
+        ),
+    ];
+    let mut report = Vec::new();
+    let mut checked: Vec<CheckedKey> = Vec::new();
+    for &(name, configured, default, group, scopes) in table {
+        match default {
+            Some(default) => {
+                let keys = if parse_key_event(configured).is_some() {
+                    configured
+                } else {
+                    report.push(format!(
+                        "{name} = \"{configured}\" is not a valid key; using {}",
+                        key_display_string(default)
+                    ));
+                    default
+                };
+                if let Some(key) = parse_key_event(keys) {
+                    checked.push(CheckedKey {
+                        name,
+                        keys,
+                        sequence: KeySequence(vec![key]),
+                        group,
+                        scopes,
+                    });
+                }
+            }
+            None if configured.trim().is_empty() => {}
+            None => match parse_key_sequence(configured) {
+                Some(sequence) => checked.push(CheckedKey {
+                    name,
+                    keys: configured,
+                    sequence,
+                    group,
+                    scopes,
+                }),
+                None => report.push(format!(
+                    "{name} = \"{configured}\" is not a valid key sequence; binding ignored"
+                )),
+            },
+        }
+    }
+
+    for (i, a) in checked.iter().enumerate() {
+        for b in &checked[i + 1..] {
+            if a.group == b.group
+                || a.sequence.0.len() != b.sequence.0.len()
+                || !a
+                    .sequence
+                    .0
+                    .iter()
+                    .zip(&b.sequence.0)
+                    .all(|(x, y)| same_key(*x, *y))
+            {
+                continue;
+            }
+            let shared = if a.scopes.contains(&Scope::Global) || b.scopes.contains(&Scope::Global) {
+                Some(Scope::Global)
+            } else {
+                a.scopes.iter().copied().find(|s| b.scopes.contains(s))
+            };
+            if let Some(scope) = shared {
+                report.push(format!(
+                    "{} is bound to both {} and {} ({})",
+                    key_display_string(a.keys),
+                    a.name,
+                    b.name,
+                    scope.label()
+                ));
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(s: &str) -> Vec<KeyEvent> {
+        parse_key_sequence(s).unwrap().0
+    }
+
+    #[test]
+    fn lookup_tells_prefixes_from_complete_bindings() {
+        use KeyContext::{EditorNormal, Navigation, Other, TextInput};
+        use SequenceMatch::{Ambiguous, Exact, None, Prefix};
+        let map = Keymap::from_config(&KeysConfig {
+            search: "space".to_string(),
+            zen_mode: "space z".to_string(),
+            ..KeysConfig::default()
+        });
+        assert_eq!(map.lookup(&keys("x"), Navigation), None);
+        assert_eq!(map.lookup(&keys("space x"), Navigation), None);
+        assert_eq!(
+            map.lookup(&keys("space"), Navigation),
+            Ambiguous(KeyAction::Search)
+        );
+        assert_eq!(
+            map.lookup(&keys("space z"), Navigation),
+            Exact(KeyAction::ZenMode)
+        );
+        assert_eq!(map.lookup(&keys("g"), EditorNormal), Prefix);
+        assert_eq!(
+            map.lookup(&keys("g t"), EditorNormal),
+            Exact(KeyAction::NextTab)
+        );
+
+        // Level jumps are for the file list and tab keys for the editor only.
+        assert_eq!(
+            map.lookup(&keys("g 2"), Navigation),
+            Exact(KeyAction::JumpToLevel(2))
+        );
+        assert_eq!(map.lookup(&keys("g 2"), EditorNormal), None);
+        assert_eq!(map.lookup(&keys("g t"), Navigation), None);
+        // Popups see single keys only, so the shorter binding is no longer ambiguous, and
+        // text input sees chords only.
+        assert_eq!(map.lookup(&keys("space"), Other), Exact(KeyAction::Search));
+        assert_eq!(map.lookup(&keys("space"), TextInput), None);
+        assert_eq!(
+            map.lookup(&keys("ctrl-p"), TextInput),
+            Exact(KeyAction::CommandPalette)
+        );
+    }
+
+    #[test]
+    fn pending_sequences_expire_after_the_timeout() {
+        let since = Instant::now();
+        assert!(!sequence_expired(since, since));
+        let just_before = since + SEQUENCE_TIMEOUT - Duration::from_millis(1);
+        assert!(!sequence_expired(since, just_before));
+        assert!(sequence_expired(since, since + SEQUENCE_TIMEOUT));
+        assert!(!sequence_expired(since + SEQUENCE_TIMEOUT, since));
+    }
+
+    #[test]
+    fn check_keybindings_reports_keys_shared_in_one_context() {
+        assert!(check_keybindings(&KeysConfig::default()).is_empty());
+
+        // The same key for two commands in the file list clashes; in different contexts, or
+        // for two names of one command, it does not.
+        let report = check_keybindings(&KeysConfig {
+            list_rename: "x".to_string(),
+            list_archive: "x".to_string(),
+            editor_insert: "ctrl-r".to_string(),
+            list_edit_config: "ctrl-r".to_string(),
+            list_delete: "delete".to_string(),
+            ..KeysConfig::default()
+        });
+        assert_eq!(
+            report,
+            ["X is bound to both list_rename and list_archive (file list)"]
+        );
+
+        // A global key shadows every context.
+        let report = check_keybindings(&KeysConfig {
+            zen_mode: "ctrl-y".to_string(),
+            list_rename: "ctrl-y".to_string(),
+            ..KeysConfig::default()
+        });
+        assert_eq!(
+            report,
+            ["Ctrl+Y is bound to both zen_mode and list_rename (global)"]
+        );
+    }
+
+    #[test]
+    fn check_keybindings_covers_every_key() {
+        // Every field of KeysConfig, by name, from its derived Debug output.
+        let fields = format!("{:?}", KeysConfig::default());
+        let names: Vec<&str> = regex::Regex::new(r#"(?:\{|", ) ?(\w+): ""#)
+            .unwrap()
+            .captures_iter(&fields)
+            .map(|c| c.get(1).unwrap().as_str())
+            .collect();
+        assert!(
+            names.len() > 50 && names.contains(&"editor_row_end"),
+            "{names:?}"
+        );
+        for name in names {
+            let keys: KeysConfig = toml::from_str(&format!("{name} = \"ctrl-nope\"")).unwrap();
+            let report = check_keybindings(&keys);
+            let checked = report.iter().any(|l| l.starts_with(&format!("{name} = ")));
+            assert!(
+                checked,
+                "{name} is missing from the check_keybindings table"
+            );
+        }
+    }
+
+    #[test]
+    fn check_keybindings_allows_a_binding_that_prefixes_another() {
+        // `ctrl-x` waits for `z` until the sequence times out and then runs search (see
+        // `SequenceMatch::Ambiguous`), so neither binding hides the other.
+        let keys = KeysConfig {
+            search: "ctrl-x".to_string(),
+            zen_mode: "ctrl-x z".to_string(),
+            ..KeysConfig::default()
+        };
+        assert!(check_keybindings(&keys).is_empty());
+        let keys = KeysConfig {
+            search: "ctrl-x z".to_string(),
+            zen_mode: "ctrl-x z".to_string(),
+            ..KeysConfig::default()
+        };
+        assert_eq!(
+            check_keybindings(&keys),
+            ["Ctrl+X → Z is bound to both zen_mode and search (global)"]
+        );
+    }
+}
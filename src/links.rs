@@ -0,0 +1,587 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Wiki link graph, persisted to the XDG data directory
+
+use crate::frontmatter::{parse_aliases, parse_id};
+use crate::tasks::{self, TaskCounts};
+use crate::vault::{self, Ignore, Walk};
+use crate::zettel;
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bumped when `NoteLinks` gains data, so older links.json files are rebuilt.
+const FORMAT_VERSION: u32 = 5;
+
+/// Wiki links, aliases, title, ID and task counts of one note, and the modification time
+/// they were read at.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct NoteLinks {
+    /// Milliseconds since the Unix epoch.
+    modified: u64,
+    targets: BTreeSet<String>,
+    /// `aliases` from the frontmatter.
+    #[serde(default)]
+    aliases: BTreeSet<String>,
+    /// Text of the first `# Heading`, see `parse_title`.
+    #[serde(default)]
+    title: Option<String>,
+    /// `id` from the frontmatter.
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    tasks: TaskCounts,
+}
+
+/// On-disk form of the graph.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Stored {
+    #[serde(default)]
+    version: u32,
+    root: PathBuf,
+    notes: BTreeMap<PathBuf, NoteLinks>,
+}
+
+/// `[[wiki links]]` between the notes of a vault: the targets each note links to, and the
+/// reverse index from a note name to the notes linking to it. Built once from a vault walk,
+/// re-reading only notes changed since the last run, then kept current by `update` for the
+/// files oxid writes.
+#[derive(Debug, Clone, Default)]
+pub struct LinkGraph {
+    root: PathBuf,
+    notes: BTreeMap<PathBuf, NoteLinks>,
+    /// Lowercased link name (see `link_name`) -> notes linking to it.
+    reverse: HashMap<String, BTreeSet<PathBuf>>,
+    /// Note name (file stem) -> notes with that name.
+    names: HashMap<String, Vec<PathBuf>>,
+    /// Lowercased alias -> notes claiming it.
+    aliases: HashMap<String, Vec<PathBuf>>,
+    /// Note ID (see `note_id`) -> notes with it.
+    ids: HashMap<String, Vec<PathBuf>>,
+    /// Name after a note's ID prefix (`Idea` for `202403141530-Idea.md`) -> notes.
+    after_id: HashMap<String, Vec<PathBuf>>,
+    /// chrono format of the IDs that start note names.
+    id_format: String,
+    file: Option<PathBuf>,
+}
+
+impl LinkGraph {
+    /// Load ~/.local/share/oxid/links.json and bring it up to date with the markdown files
+    /// of `walk`. A missing or unreadable file, or one saved for another vault or by an
+    /// older version, is rebuilt from scratch. Note names starting with an ID in the chrono
+    /// `id_format` can be linked to by that ID.
+    pub fn open(root: &Path, walk: &Walk, id_format: &str) -> Self {
+        let file = links_file_path();
+        let stored = file
+            .as_ref()
+            .and_then(|f| fs::read_to_string(f).ok())
+            .and_then(|content| serde_json::from_str::<Stored>(&content).ok())
+            .filter(|s| s.version == FORMAT_VERSION && s.root == root)
+            .unwrap_or_default();
+        let mut graph = Self {
+            root: root.to_path_buf(),
+            notes: stored.notes,
+            id_format: id_format.to_string(),
+            file,
+            ..Self::default()
+        };
+        let changed = graph.sync(walk.markdown_files());
+        graph.rebuild_indexes();
+        if changed {
+            let _ = graph.save();
+        }
+        graph
+    }
+
+    /// Graph of `files` only, not persisted.
+    #[cfg(test)]
+    fn from_files<'a>(root: &Path, files: impl Iterator<Item = &'a Path>) -> Self {
+        let mut graph = Self {
+            root: root.to_path_buf(),
+            id_format: "%Y%m%d%H%M".to_string(),
+            ..Self::default()
+        };
+        graph.sync(files);
+        graph.rebuild_indexes();
+        graph
+    }
+
+    /// Notes linking to `path` by its name, ID or one of its aliases, excluding `path` itself.
+    /// Like `resolve`, names and IDs match with their case and aliases in any case.
+    pub fn backlinks(&self, path: &Path) -> Vec<PathBuf> {
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            return Vec::new();
+        };
+        let aliases = self.notes.get(path).into_iter().flat_map(|n| &n.aliases);
+        let id = self.note_id(path);
+        let after_id = zettel::split_id(name, &self.id_format).map(|(_, rest)| rest);
+        let sources: BTreeSet<&PathBuf> = std::iter::once(name)
+            .chain(id.as_deref())
+            .chain(after_id.filter(|n| !n.is_empty()))
+            .flat_map(|name| self.linking_to(name, false))
+            .chain(aliases.flat_map(|alias| self.linking_to(alias, true)))
+            .filter(|p| p.as_path() != path)
+            .collect();
+        sources.into_iter().cloned().collect()
+    }
+
+    /// Notes with a link to `name`, in any case with `ignore_case` and exactly otherwise.
+    fn linking_to<'a>(
+        &'a self,
+        name: &'a str,
+        ignore_case: bool,
+    ) -> impl Iterator<Item = &'a PathBuf> + 'a {
+        self.reverse
+            .get(&name.to_lowercase())
+            .into_iter()
+            .flatten()
+            .filter(move |source| {
+                ignore_case
+                    || self
+                        .notes
+                        .get(*source)
+                        .is_some_and(|n| n.targets.iter().any(|t| link_name(t) == name))
+            })
+    }
+
+    /// Notes whose frontmatter lists `alias` (ignoring case).
+    pub fn notes_with_alias(&self, alias: &str) -> &[PathBuf] {
+        self.aliases
+            .get(&alias.to_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Title of the note at `path`: its first level-1 heading, if it has one.
+    pub fn title(&self, path: &Path) -> Option<&str> {
+        self.notes.get(path)?.title.as_deref()
+    }
+
+    /// Checked and total tasks of the note at `path` when it was last read.
+    pub fn tasks(&self, path: &Path) -> TaskCounts {
+        self.notes.get(path).map(|n| n.tasks).unwrap_or_default()
+    }
+
+    /// ID of the note at `path`: the frontmatter `id`, else the ID its name starts with.
+    pub fn note_id(&self, path: &Path) -> Option<String> {
+        if let Some(id) = self.notes.get(path).and_then(|n| n.id.clone()) {
+            return Some(id);
+        }
+        let stem = path.file_stem()?.to_str()?;
+        zettel::split_id(stem, &self.id_format).map(|(id, _)| id.to_string())
+    }
+
+    /// Notes that `link` names by their ID, or by the name after their ID prefix
+    /// (`[[Idea]]` for `202403141530-Idea.md`).
+    pub fn id_matches(&self, link: &str) -> Vec<&Path> {
+        let name = link_name(link);
+        self.ids
+            .get(name)
+            .into_iter()
+            .chain(self.after_id.get(name))
+            .flatten()
+            .map(PathBuf::as_path)
+            .collect()
+    }
+
+    /// Every note in the graph.
+    pub fn notes(&self) -> impl Iterator<Item = &Path> {
+        self.notes.keys().map(PathBuf::as_path)
+    }
+
+    /// Note-to-note links whose target exists, without self-links or duplicates.
+    pub fn edges(&self) -> BTreeSet<(PathBuf, PathBuf)> {
+        let mut edges = BTreeSet::new();
+        for (source, links) in &self.notes {
+            for target in &links.targets {
+                if let Some(target) = self.resolve(target, source) {
+                    if target != source {
+                        edges.insert((source.clone(), target.to_path_buf()));
+                    }
+                }
+            }
+        }
+        edges
+    }
+
+    /// Note that `target`, linked from `from`, points at: the path next to the linking note
+    /// or from the vault root, else a note of that name in the same folder, else the first
+    /// one anywhere, else a note with that ID (see `id_matches`), else the first note with
+    /// that alias.
+    pub fn resolve(&self, target: &str, from: &Path) -> Option<&Path> {
+        let file = if target.ends_with(".md") {
+            target.to_string()
+        } else {
+            format!("{target}.md")
+        };
+        let dir = from.parent().unwrap_or(&self.root);
+        for candidate in [dir.join(&file), self.root.join(&file)] {
+            if let Some((path, _)) = self.notes.get_key_value(&candidate) {
+                return Some(path);
+            }
+        }
+        let named: Vec<&PathBuf> = self
+            .names
+            .get(link_name(target))
+            .into_iter()
+            .flatten()
+            .filter(|p| p.ends_with(&file))
+            .collect();
+        named
+            .iter()
+            .find(|p| p.parent() == Some(dir))
+            .or_else(|| named.first())
+            .copied()
+            .map(PathBuf::as_path)
+            .or_else(|| self.id_matches(target).first().copied())
+            .or_else(|| self.notes_with_alias(target).first().map(PathBuf::as_path))
+    }
+
+    /// Re-read `path` after oxid wrote, created, moved or deleted it. A folder updates every
+    /// note under it; a path that no longer exists, or is ignored (such as an archived note),
+    /// drops its notes from the graph.
+    pub fn update(&mut self, path: &Path, ignore: &Ignore) {
+        let mut changed = false;
+        let excluded = ignore.excludes(path);
+        let stale: Vec<PathBuf> = self
+            .notes
+            .keys()
+            .filter(|p| p.starts_with(path) && (excluded || !p.is_file()))
+            .cloned()
+            .collect();
+        for p in stale {
+            self.notes.remove(&p);
+            changed = true;
+        }
+        if excluded {
+            // Nothing to read.
+        } else if path.is_dir() {
+            let walk = vault::walk(path, ignore);
+            for file in walk.markdown_files() {
+                changed |= self.read_note(file);
+            }
+        } else if is_markdown(path) && path.is_file() {
+            changed |= self.read_note(path);
+        }
+        if changed {
+            self.rebuild_indexes();
+            let _ = self.save();
+        }
+    }
+
+    /// Read notes that are new or modified since they were last read, and forget notes that
+    /// are gone. Returns whether any links changed.
+    fn sync<'a>(&mut self, files: impl Iterator<Item = &'a Path>) -> bool {
+        let mut seen = BTreeSet::new();
+        let mut changed = false;
+        for file in files {
+            seen.insert(file.to_path_buf());
+            let modified = modified_millis(file);
+            if self.notes.get(file).is_none_or(|n| n.modified != modified) {
+                changed |= self.read_note(file);
+            }
+        }
+        let before = self.notes.len();
+        self.notes.retain(|p, _| seen.contains(p));
+        changed || self.notes.len() != before
+    }
+
+    /// Parse one note into the graph. Returns whether anything but its modification time
+    /// changed.
+    fn read_note(&mut self, path: &Path) -> bool {
+        let Ok(content) = fs::read_to_string(path) else {
+            return false;
+        };
+        let links = NoteLinks {
+            modified: modified_millis(path),
+            targets: parse_links(&content),
+            aliases: parse_aliases(&content).into_iter().collect(),
+            title: parse_title(&content),
+            id: parse_id(&content),
+            tasks: tasks::count(&content),
+        };
+        let changed = self.notes.get(path).is_none_or(|old| {
+            old.targets != links.targets
+                || old.aliases != links.aliases
+                || old.title != links.title
+                || old.id != links.id
+                || old.tasks != links.tasks
+        });
+        self.notes.insert(path.to_path_buf(), links);
+        changed
+    }
+
+    fn rebuild_indexes(&mut self) {
+        self.reverse.clear();
+        self.names.clear();
+        self.aliases.clear();
+        self.ids.clear();
+        self.after_id.clear();
+        for (source, links) in &self.notes {
+            if let Some(id) = &links.id {
+                self.ids.entry(id.clone()).or_default().push(source.clone());
+            }
+            for alias in &links.aliases {
+                self.aliases
+                    .entry(alias.to_lowercase())
+                    .or_default()
+                    .push(source.clone());
+            }
+            if let Some(name) = source.file_stem().and_then(|s| s.to_str()) {
+                self.names
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(source.clone());
+                if let Some((id, rest)) = zettel::split_id(name, &self.id_format) {
+                    if links.id.as_deref() != Some(id) {
+                        self.ids
+                            .entry(id.to_string())
+                            .or_default()
+                            .push(source.clone());
+                    }
+                    if !rest.is_empty() {
+                        self.after_id
+                            .entry(rest.to_string())
+                            .or_default()
+                            .push(source.clone());
+                    }
+                }
+            }
+            for target in &links.targets {
+                self.reverse
+                    .entry(link_name(target).to_lowercase())
+                    .or_default()
+                    .insert(source.clone());
+            }
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create data directory: {}", dir.display()))?;
+        }
+        let stored = Stored {
+            version: FORMAT_VERSION,
+            root: self.root.clone(),
+            notes: self.notes.clone(),
+        };
+        let content = serde_json::to_string(&stored)?;
+        fs::write(file, content)
+            .with_context(|| format!("Failed to write link graph: {}", file.display()))
+    }
+}
+
+/// Targets of the `[[wiki links]]` in `content`, without `|alias` or `#heading`. Links in
+/// fenced code blocks and inline code are not links.
+pub fn parse_links(content: &str) -> BTreeSet<String> {
+    let mut targets = BTreeSet::new();
+    let Ok(re) = Regex::new(r"\[\[([^\]]+)\]\]") else {
+        return targets;
+    };
+    let mut fence: Option<&str> = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            fence = Some("```");
+            continue;
+        }
+        if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+            continue;
+        }
+        // Odd segments between backticks are inline code.
+        for (i, segment) in line.split('`').enumerate() {
+            if i % 2 == 1 {
+                continue;
+            }
+            for cap in re.captures_iter(segment) {
+                let Some(inner) = cap.get(1) else { continue };
+                let target = inner.as_str().split('|').next().unwrap_or_default();
+                let target = target.split('#').next().unwrap_or_default().trim();
+                if !target.is_empty() {
+                    targets.insert(target.to_string());
+                }
+            }
+        }
+    }
+    targets
+}
+
+/// Text of the first `# Heading` in `content`, outside the frontmatter and code blocks.
+pub fn parse_title(content: &str) -> Option<String> {
+    let mut lines = content.lines().peekable();
+    if lines.peek().is_some_and(|l| l.trim_end() == "---") {
+        lines.next();
+        lines.by_ref().find(|l| l.trim_end() == "---");
+    }
+    let mut fence: Option<&str> = None;
+    for line in lines {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            fence = Some("```");
+        } else if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+        } else if let Some(title) = line.strip_prefix("# ") {
+            let title = title.trim().trim_end_matches('#').trim_end();
+            if !title.is_empty() {
+                return Some(title.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Note name a link target refers to: `folder/Note.md` -> `Note`.
+fn link_name(target: &str) -> &str {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    name.strip_suffix(".md").unwrap_or(name)
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+}
+
+fn modified_millis(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+}
+
+fn links_file_path() -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "oxid")?;
+    Some(dirs.data_dir().join("links.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(content: &str) -> Vec<String> {
+        parse_links(content).into_iter().collect()
+    }
+
+    #[test]
+    fn parses_plain_and_aliased_links() {
+        assert_eq!(
+            links("See [[Alpha]] and [[Beta|the beta note]], [[Alpha]] again."),
+            ["Alpha", "Beta"]
+        );
+        assert_eq!(links("[[folder/Gamma#Heading|g]] [[ ]]"), ["folder/Gamma"]);
+    }
+
+    #[test]
+    fn skips_links_in_code() {
+        let content = "\
+[[Real]]
+```
+[[InFence]]
+```
+~~~rust
+let x = \"[[InTilde]]\";
+~~~
+Inline `[[InCode]]` but [[After]].
+";
+        assert_eq!(links(content), ["After", "Real"]);
+    }
+
+    #[test]
+    fn titles_come_from_the_first_level_one_heading() {
+        assert_eq!(
+            parse_title("# Meeting notes\n# Other"),
+            Some("Meeting notes".into())
+        );
+        assert_eq!(
+            parse_title("---\ntitle: x\n# not a heading\n---\n## Sub\n# Top #"),
+            Some("Top".into())
+        );
+        assert_eq!(parse_title("```\n# comment\n```\nplain"), None);
+        assert_eq!(parse_title("#tag\n#  \n"), None);
+    }
+
+    #[test]
+    fn backlinks_and_updates() {
+        let root = std::env::temp_dir().join(format!("oxid-links-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub")).unwrap();
+        let target = root.join("Target.md");
+        let a = root.join("a.md");
+        let b = root.join("sub/b.md");
+        fs::write(&target, "[[Target]] links to itself").unwrap();
+        fs::write(&a, "[[Target|alias]]").unwrap();
+        fs::write(&b, "[[sub/Other]]").unwrap();
+
+        let ignore = Ignore::new(&root, &[]);
+        let walk = vault::walk(&root, &ignore);
+        let mut graph = LinkGraph::from_files(&root, walk.markdown_files());
+        assert_eq!(graph.backlinks(&target), vec![a.clone()]);
+        // The self-link and the link to a missing note are not edges.
+        assert_eq!(
+            graph.edges().into_iter().collect::<Vec<_>>(),
+            [(a.clone(), target.clone())]
+        );
+
+        fs::write(&b, "[[Target.md]]").unwrap();
+        graph.update(&b, &ignore);
+        assert_eq!(graph.backlinks(&target), [a.clone(), b.clone()]);
+
+        fs::remove_dir_all(root.join("sub")).unwrap();
+        graph.update(&root.join("sub"), &ignore);
+        assert_eq!(graph.backlinks(&target), [a]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn aliases_resolve_and_count_as_backlinks() {
+        let root = std::env::temp_dir().join(format!("oxid-aliases-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let alpha = root.join("project-alpha.md");
+        let other = root.join("other.md");
+        let linker = root.join("linker.md");
+        fs::write(&alpha, "---\naliases: [ProjA, Project Alpha]\n---\n").unwrap();
+        fs::write(&other, "---\naliases: Project Alpha\n---\n").unwrap();
+        fs::write(&linker, "[[ProjA]] and [[Project Alpha]]").unwrap();
+        // Aliases match in any case, names only in their own.
+        let shouting = root.join("shouting.md");
+        fs::write(&shouting, "[[PROJECT ALPHA]] and [[Project-Alpha]]").unwrap();
+
+        let walk = vault::walk(&root, &Ignore::new(&root, &[]));
+        let graph = LinkGraph::from_files(&root, walk.markdown_files());
+        assert_eq!(graph.notes_with_alias("proja"), vec![alpha.clone()]);
+        assert_eq!(
+            graph.notes_with_alias("Project Alpha"),
+            [other.clone(), alpha.clone()]
+        );
+        assert_eq!(graph.resolve("ProjA", &linker), Some(alpha.as_path()));
+        assert_eq!(graph.resolve("proja", &linker), Some(alpha.as_path()));
+        assert_eq!(graph.backlinks(&alpha), [linker.clone(), shouting.clone()]);
+        assert_eq!(graph.backlinks(&other), [linker, shouting.clone()]);
+
+        fs::write(&shouting, "[[Project-Alpha]]").unwrap();
+        let graph = LinkGraph::from_files(&root, walk.markdown_files());
+        assert!(graph.resolve("Project-Alpha", &shouting).is_none());
+        assert!(graph.backlinks(&alpha).iter().all(|p| *p != shouting));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
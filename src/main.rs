@@ -2,404 +2,1385 @@
 // oxid - A fast, keyboard-driven note manager TUI for Linux
 
 mod app;
+mod backup;
+mod capture;
+mod clipboard;
 mod config;
+mod content;
+mod crypt;
+mod diff;
+mod export;
 mod frontmatter;
 mod git;
+mod graph;
 mod handlers;
+mod jumps;
+mod keymap;
+mod links;
 mod markdown;
+mod random;
+mod recent;
+mod replace;
+mod scan;
 mod search;
+mod shell;
+mod snippets;
 mod spellcheck;
+mod tasks;
 mod telescope;
 mod templates;
+mod text_input;
 mod theme;
 mod ui;
+mod vault;
+mod wrap;
+mod zettel;
 
 use anyhow::Result;
-use app::{App, CommandAction, EditorLayout, EditorMode, Focus, Mode, TagExplorerView};
+use app::{App, EditorMode, Focus, Mode, PaletteItem, TagExplorerView};
 use clap::Parser;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    style::Print,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
+    },
 };
 use handlers::key_matches;
+use keymap::{sequence_expired, KeyAction, SequenceMatch, SEQUENCE_TIMEOUT, WHICH_KEY_DELAY};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
+use signal_hook::SigId;
 use std::io;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use telescope::TelescopeMode;
 use tui_textarea::Input;
 
+/// Redraw interval while a user command, graph export or note loading is running.
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// xterm window operations that save the terminal's title on a stack and restore it.
+/// Terminals without the stack ignore them.
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Signals that end oxid like a quit: the terminal closing (SIGHUP), `kill` or tmux closing
+/// the pane (SIGTERM), and an interrupt sent from outside (SIGINT; raw mode turns Ctrl+C
+/// into a key).
+const SHUTDOWN_SIGNALS: [i32; 3] = [
+    signal_hook::consts::SIGTERM,
+    signal_hook::consts::SIGHUP,
+    signal_hook::consts::SIGINT,
+];
+
+/// The flag `SHUTDOWN_SIGNALS` set. SIGINT can be let go while another program has the
+/// terminal: in cooked mode Ctrl+C interrupts the whole foreground process group, and then
+/// it is meant for that program, not for oxid.
+struct Shutdown {
+    flag: Arc<AtomicBool>,
+    /// The SIGINT registration, `None` while SIGINT is let go.
+    sigint: Option<SigId>,
+}
+
+impl Shutdown {
+    fn register() -> io::Result<Self> {
+        let flag = Arc::new(AtomicBool::new(false));
+        for signal in SHUTDOWN_SIGNALS
+            .into_iter()
+            .filter(|&s| s != signal_hook::consts::SIGINT)
+        {
+            signal_hook::flag::register(signal, Arc::clone(&flag))?;
+        }
+        let mut shutdown = Self { flag, sigint: None };
+        shutdown.watch_sigint()?;
+        Ok(shutdown)
+    }
+
+    fn watch_sigint(&mut self) -> io::Result<()> {
+        if self.sigint.is_none() {
+            let flag = Arc::clone(&self.flag);
+            self.sigint = Some(signal_hook::flag::register(
+                signal_hook::consts::SIGINT,
+                flag,
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Stop counting SIGINT as a shutdown until `watch_sigint`. signal-hook keeps its handler
+    /// installed, so oxid ignores the signal; a program it starts gets the default action back
+    /// on exec.
+    fn ignore_sigint(&mut self) {
+        if let Some(id) = self.sigint.take() {
+            signal_hook::low_level::unregister(id);
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(name = "oxid")]
 #[command(author = "Oxid Contributors")]
 #[command(version)]
 #[command(about = "A fast, keyboard-driven TUI note editor for Linux")]
-struct CliArgs {}
+struct CliArgs {
+    /// Append TEXT to the capture note as a timestamped bullet and exit without the TUI.
+    #[arg(long, value_name = "TEXT")]
+    capture: Option<String>,
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum CliCommand {
+    /// Render NOTE to PDF (with pandoc) or HTML as the TUI does and print the output path.
+    Export {
+        /// Markdown note, as a path or relative to notes_directory.
+        note: PathBuf,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Pdf)]
+        to: ExportFormat,
+        /// Output file for PDF (default: next to the note); output directory for HTML
+        /// (default: the note's directory).
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Print the vault's unchecked tasks as `path:line: task`.
+    Tasks {
+        /// Print a JSON array of `{path, line, content}` instead.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the vault's `#tags`, one per line.
+    Tags {
+        /// Print a JSON array of `{tag, path, line}`, one for each use of a tag, instead.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(serde::Serialize)]
+struct JsonTask {
+    path: String,
+    line: usize,
+    content: String,
+}
+
+#[derive(serde::Serialize)]
+struct JsonTag {
+    tag: String,
+    path: String,
+    line: usize,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ExportFormat {
+    Pdf,
+    Html,
+}
+
+/// `oxid --capture "text"`: append to the capture note from the config and exit.
+fn capture_from_cli(text: &str) -> Result<()> {
+    if text.trim().is_empty() {
+        anyhow::bail!("Nothing to capture");
+    }
+    let config = config::load_config()?;
+    let path = config::expand_path(&config.notes_directory).join(config.capture_note.trim());
+    capture::append_line(&path, &capture::capture_line(text))?;
+    println!("Captured to {}", path.display());
+    Ok(())
+}
+
+/// `oxid export NOTE`: render one note without the TUI. Wiki links to other notes are
+/// written as text in HTML, as with *Export to HTML* for the current file; they are listed
+/// on stderr.
+fn export_from_cli(note: &Path, to: ExportFormat, out: Option<&Path>) -> Result<()> {
+    let note = if note.exists() || note.is_absolute() {
+        note.to_path_buf()
+    } else {
+        let config = config::load_config()?;
+        config::expand_path(&config.notes_directory).join(note)
+    };
+    if !note.is_file() || note.extension().is_none_or(|e| e != "md") {
+        anyhow::bail!("{} is not a Markdown note", note.display());
+    }
+    let output = match to {
+        ExportFormat::Pdf => {
+            let output = out.map_or_else(|| note.with_extension("pdf"), Path::to_path_buf);
+            export::export_pdf(&note, &output)?;
+            output
+        }
+        ExportFormat::Html => {
+            let base = note.parent().unwrap_or(Path::new(""));
+            let out_dir = out.unwrap_or(base);
+            let summary = export::export_html(std::slice::from_ref(&note), base, out_dir)?;
+            for link in &summary.skipped_links {
+                eprintln!("Not exported: {link}");
+            }
+            out_dir
+                .join(note.file_name().unwrap_or_default())
+                .with_extension("html")
+        }
+    };
+    println!("{}", output.display());
+    Ok(())
+}
+
+/// Walk the configured vault for `oxid tasks` and `oxid tags`, with walk warnings on
+/// stderr. Returns the config, the vault folder and the walk.
+fn walk_vault_from_cli() -> Result<(config::Config, PathBuf, vault::Walk)> {
+    let config = config::load_config()?;
+    let notes_dir = config::expand_path(&config.notes_directory);
+    if !notes_dir.is_dir() {
+        anyhow::bail!("Notes directory {} does not exist", notes_dir.display());
+    }
+    let walk = vault::walk(&notes_dir, &scan::vault_ignore(&notes_dir, &config));
+    for warning in &walk.warnings {
+        eprintln!("{warning}");
+    }
+    Ok((config, notes_dir, walk))
+}
+
+/// What `oxid tasks` prints, with paths relative to `notes_dir` and 1-based line numbers.
+fn list_tasks(
+    walk: &vault::Walk,
+    notes_dir: &Path,
+    columns: &[String],
+    json: bool,
+) -> Result<String> {
+    let tasks = scan::tasks(walk, columns).into_iter().map(|t| JsonTask {
+        path: relative_display(&t.path, notes_dir),
+        line: t.line_number + 1,
+        content: t.content,
+    });
+    if json {
+        return Ok(serde_json::to_string_pretty(&tasks.collect::<Vec<_>>())?);
+    }
+    Ok(tasks
+        .map(|t| format!("{}:{}: {}", t.path, t.line, t.content))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// What `oxid tags` prints: the distinct tags, or with `json` every use of one.
+fn list_tags(walk: &vault::Walk, notes_dir: &Path, json: bool) -> Result<String> {
+    let tags = scan::tags(walk);
+    if json {
+        let tags: Vec<JsonTag> = tags
+            .into_iter()
+            .map(|t| JsonTag {
+                path: relative_display(&t.path, notes_dir),
+                line: t.line_number + 1,
+                tag: t.tag,
+            })
+            .collect();
+        return Ok(serde_json::to_string_pretty(&tags)?);
+    }
+    let mut names: Vec<String> = tags.into_iter().map(|t| t.tag).collect();
+    names.dedup();
+    Ok(names.join("\n"))
+}
+
+/// Print `listing` with a final newline; nothing when it is empty.
+fn print_listing(listing: &str) -> Result<()> {
+    if !listing.is_empty() {
+        println!("{listing}");
+    }
+    Ok(())
+}
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
-    let poll_timeout = Duration::from_millis(500);
+fn relative_display(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
 
+fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    shutdown: &mut Shutdown,
+) -> Result<()> {
+    // Title last sent to the terminal.
+    let mut title = None;
     loop {
-        terminal.draw(|f| ui::draw(f, app))?;
-        app.tick_save_indicator();
+        if shut_down_on_signal(app, &shutdown.flag)? {
+            break;
+        }
+        // With `ui.debounce_search`, filter once every key typed so far has been handled.
+        if !event::poll(Duration::ZERO)? {
+            app.apply_pending_search();
+        }
+        let command_running = poll_background(app);
+        // Idle ticks change nothing on screen, so only draw after something did.
+        if app.needs_redraw {
+            terminal.draw(|f| ui::draw(f, app))?;
+            app.needs_redraw = false;
+            show_window_title(terminal, app, &mut title)?;
+        }
 
-        if !event::poll(poll_timeout)? {
-            if app.check_auto_save()? {
+        // While a prefix is pending, wake up to show the which-key popup and again when the
+        // sequence times out.
+        let timeout = app.pending_since.map_or(app.config.ui.tick_rate(), |t| {
+            let elapsed = t.elapsed();
+            if elapsed < WHICH_KEY_DELAY {
+                WHICH_KEY_DELAY - elapsed
+            } else {
+                SEQUENCE_TIMEOUT.saturating_sub(elapsed)
+            }
+        });
+        // Keep the spinner and output of a running user command moving, and pick up a
+        // finished graph export, backup and notes read for search.
+        let timeout = if command_running {
+            timeout.min(COMMAND_POLL_INTERVAL)
+        } else {
+            timeout
+        };
+        if !event::poll(timeout)? {
+            if app.pending_since.is_some() {
+                app.needs_redraw = true;
+            }
+            if app
+                .pending_since
+                .is_some_and(|t| sequence_expired(t, Instant::now()))
+            {
+                if resolve_pending_keys(app)? {
+                    break;
+                }
                 continue;
             }
+            if let Err(e) = app.check_auto_save() {
+                app.report_err("Auto-save", e);
+            }
             continue;
         }
 
-        let Ok(Event::Key(key)) = event::read() else {
-            continue;
+        let key = match event::read() {
+            Ok(Event::Key(key)) => key,
+            Ok(Event::Paste(text)) => {
+                app.paste_text(&text);
+                app.needs_redraw = true;
+                continue;
+            }
+            Ok(Event::Resize(..)) => {
+                app.needs_redraw = true;
+                continue;
+            }
+            _ => continue,
         };
         if key.kind != KeyEventKind::Press {
             continue;
         }
 
-        let k = &app.resolved_keys;
+        app.needs_redraw = true;
+        let before = app.focus_target();
+        if dispatch_key(app, key)? {
+            break;
+        }
+        app.save_on_focus_change(before);
+        if let Some(path) = app.external_edit.take() {
+            restore_window_title(terminal, &mut title)?;
+            run_external_editor(terminal, app, &path, shutdown)?;
+        }
+        if std::mem::take(&mut app.suspend_requested) {
+            restore_window_title(terminal, &mut title)?;
+            suspend(terminal)?;
+        }
+    }
+    restore_window_title(terminal, &mut title)
+}
+
+/// Set the terminal's title to `app.window_title()` when it changed since `shown` was sent,
+/// saving the title from before oxid first.
+fn show_window_title(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &App,
+    shown: &mut Option<String>,
+) -> Result<()> {
+    let title = app.window_title();
+    if title == *shown {
+        return Ok(());
+    }
+    let Some(title) = title else {
+        return restore_window_title(terminal, shown);
+    };
+    if shown.is_none() {
+        execute!(terminal.backend_mut(), Print(PUSH_TITLE))?;
+    }
+    execute!(terminal.backend_mut(), SetTitle(&title))?;
+    *shown = Some(title);
+    Ok(())
+}
+
+/// Put back the title from before oxid set one. It is cleared first, for terminals that
+/// cannot restore it.
+fn restore_window_title(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    shown: &mut Option<String>,
+) -> Result<()> {
+    if shown.take().is_some() {
+        execute!(terminal.backend_mut(), SetTitle(""), Print(POP_TITLE))?;
+    }
+    Ok(())
+}
 
-        // Global
-        if key_matches(key, &[k.zen_mode]) {
-            app.toggle_zen_mode();
-            continue;
+/// After a shutdown signal, save the edited buffers so the caller can restore the terminal
+/// and exit. Returns true when a signal arrived.
+fn shut_down_on_signal(app: &mut App, shutdown: &AtomicBool) -> Result<bool> {
+    if !shutdown.load(Ordering::Relaxed) {
+        return Ok(false);
+    }
+    app.save_edited_buffers()
+        .map_err(|e| e.context("Saving before exit"))?;
+    Ok(true)
+}
+
+/// Work for every pass of the event loop, key or not: take in what background jobs
+/// produced, read the selected note for the preview, and expire timed footer items. Each
+/// asks for a redraw only if it changed something. Returns true while a user command, graph
+/// export, note loading, backup or replace scan is running.
+fn poll_background(app: &mut App) -> bool {
+    let running = app.poll_user_command()
+        | app.poll_graph_export()
+        | app.poll_note_content()
+        | app.poll_backup()
+        | app.poll_replace_scan();
+    app.load_selected_content();
+    app.tick_timers();
+    app.fill_highlights();
+    running
+}
+
+/// Suspend the TUI, run the external editor on `path`, then restore the terminal and reload
+/// the buffer. The terminal is restored however the editor exits.
+fn run_external_editor(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    path: &Path,
+    shutdown: &mut Shutdown,
+) -> Result<()> {
+    let editor = app.external_editor_command();
+    leave_tui(terminal)?;
+    // Ctrl+C in the editor must not quit oxid once the editor is done.
+    shutdown.ignore_sigint();
+    // The editor string may carry arguments ("code -w"); the path is passed as $1.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(path)
+        .status();
+    shutdown.watch_sigint()?;
+    enter_tui(terminal)?;
+
+    match status {
+        Ok(status) if !status.success() => {
+            app.push_message(format!("{editor} exited with {status}"));
         }
-        if key_matches(key, &[k.search]) {
-            app.enter_telescope();
-            continue;
+        Ok(_) => {}
+        Err(e) => app.push_message(format!("Failed to run {editor}: {e}")),
+    }
+    app.finish_external_edit(path);
+    Ok(())
+}
+
+/// Stop oxid with SIGTSTP as Ctrl+Z does in a shell; it carries on from here after `fg`.
+/// The terminal is handed back as the shell expects it and taken over again on resume.
+fn suspend(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+    leave_tui(terminal)?;
+    let stopped = signal_hook::low_level::raise(signal_hook::consts::SIGTSTP);
+    enter_tui(terminal)?;
+    Ok(stopped?)
+}
+
+/// Give the terminal back for another program: cooked mode, main screen, no mouse reports.
+fn leave_tui(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+    Ok(())
+}
+
+/// Take the terminal back after `leave_tui` and clear it so the next draw is a full one.
+fn enter_tui(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    terminal.clear()?;
+    Ok(())
+}
+
+/// Feed a key press through the sequence matcher. Keys that start or continue a binding are
+/// held in `app.pending_keys`; anything else goes to `handle_key`. Returns true to quit.
+fn dispatch_key(app: &mut App, key: KeyEvent) -> Result<bool> {
+    // Esc abandons a pending prefix without running or replaying it.
+    if key.code == KeyCode::Esc && !app.pending_keys.is_empty() {
+        app.pending_keys.clear();
+        app.pending_since = None;
+        return Ok(false);
+    }
+    // After `r` the next key is the replacement character, whatever it is bound to.
+    if app.replace_pending.is_some() && app.focus == Focus::Editor {
+        app.editor_normal_input(key);
+        return Ok(false);
+    }
+    if app.pending_keys.is_empty() && app.push_count_digit(key) {
+        return Ok(false);
+    }
+    let mut candidate = app.pending_keys.clone();
+    candidate.push(key);
+    match app.keymap.lookup(&candidate, app.key_context()) {
+        SequenceMatch::Exact(action) => {
+            app.pending_keys.clear();
+            app.pending_since = None;
+            run_key_action(app, action);
+            // A count is for the next key only, whether it used it or not.
+            app.normal_count = None;
+            Ok(false)
         }
-        if key_matches(key, &[k.command_palette]) {
-            app.enter_command_palette();
-            continue;
+        SequenceMatch::Prefix | SequenceMatch::Ambiguous(_) => {
+            app.pending_keys = candidate;
+            app.pending_since = Some(Instant::now());
+            Ok(false)
         }
-        if key_matches(key, &[k.daily_note]) {
-            let _ = app.open_daily_note();
-            continue;
+        SequenceMatch::None if app.pending_keys.is_empty() => {
+            let quit = handle_key(app, key);
+            app.normal_count = None;
+            quit
         }
-        if key_matches(key, &[k.task_board]) {
-            app.enter_task_view();
-            continue;
+        SequenceMatch::None => {
+            // The pending keys do not continue with this key: settle them, then start over.
+            if resolve_pending_keys(app)? {
+                return Ok(true);
+            }
+            dispatch_key(app, key)
         }
+    }
+}
 
-        // Focus-specific handling
-        match app.focus {
-            Focus::Search => {
-                if key_matches(key, &[k.escape]) {
-                    app.exit_telescope();
-                } else if key_matches(key, &[k.enter]) {
-                    if let Some(path) = app.get_telescope_selected_path() {
-                        let _ = app.load_file_into_editor(path);
-                        app.exit_telescope();
-                    }
-                } else if key_matches(key, &[k.backspace]) {
-                    app.telescope_backspace();
-                } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
-                    app.telescope_move_up();
-                } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
-                    app.telescope_move_down();
-                } else if let crossterm::event::KeyCode::Char(c) = key.code {
-                    app.telescope_add_char(c);
-                }
+/// Settle the pending keys after a timeout or a non-matching key: run the binding they
+/// complete (the shorter one of an ambiguous pair), or else handle them as plain keys.
+/// Returns true to quit.
+fn resolve_pending_keys(app: &mut App) -> Result<bool> {
+    let pending = std::mem::take(&mut app.pending_keys);
+    app.pending_since = None;
+    if pending.is_empty() {
+        return Ok(false);
+    }
+    if let SequenceMatch::Exact(action) | SequenceMatch::Ambiguous(action) =
+        app.keymap.lookup(&pending, app.key_context())
+    {
+        run_key_action(app, action);
+        app.normal_count = None;
+        return Ok(false);
+    }
+    for key in pending {
+        if handle_key(app, key)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn run_key_action(app: &mut App, action: KeyAction) {
+    match action {
+        KeyAction::ZenMode => app.toggle_zen_mode(),
+        KeyAction::Search => app.enter_telescope(),
+        KeyAction::CommandPalette => app.enter_command_palette(),
+        KeyAction::DailyNote => {
+            if let Err(e) = app.open_daily_note() {
+                app.report_err("Daily note", e);
             }
-            Focus::CommandPalette => {
-                if key_matches(key, &[k.escape]) {
-                    app.exit_command_palette();
-                } else if key_matches(key, &[k.enter]) {
-                    if let Some(action) = app.get_command_palette_action() {
-                        match action {
-                            CommandAction::RenameFile => {
-                                app.exit_command_palette();
-                                app.focus = Focus::List;
-                                app.enter_rename();
-                            }
-                            CommandAction::DeleteFile => {
-                                app.exit_command_palette();
-                                app.focus = Focus::List;
-                                app.enter_delete_confirm();
-                            }
-                            CommandAction::InsertDate => {
-                                app.exit_command_palette();
-                                app.focus = Focus::Editor;
-                                app.mark_editor_dirty();
-                                app.insert_date_at_cursor();
-                            }
-                            CommandAction::ToggleZenMode => {
-                                app.toggle_zen_mode();
-                                app.exit_command_palette();
-                            }
-                            CommandAction::ToggleSplitView => {
-                                app.toggle_split_view();
-                                app.exit_command_palette();
-                            }
-                            CommandAction::ExportPdf => {
-                                app.export_to_pdf();
-                                app.exit_command_palette();
-                            }
-                            CommandAction::GitPush => {
-                                let _ = app.git_push();
-                                app.exit_command_palette();
-                            }
-                        }
-                    }
-                } else if key_matches(key, &[k.backspace]) {
-                    app.command_palette_backspace();
-                } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
-                    app.command_palette_move_up();
-                } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
-                    app.command_palette_move_down();
-                } else if let crossterm::event::KeyCode::Char(c) = key.code {
-                    app.command_palette_add_char(c);
+        }
+        KeyAction::QuickCapture => app.enter_quick_capture(),
+        KeyAction::AddTask => app.enter_add_task(),
+        KeyAction::TaskBoard => app.enter_task_view(),
+        KeyAction::PaneGrow => app.resize_editor(5),
+        KeyAction::PaneShrink => app.resize_editor(-5),
+        KeyAction::RandomNote => app.enter_random_note(),
+        KeyAction::Suspend => app.suspend_requested = true,
+        KeyAction::GitPush => {
+            if let Err(e) = app.git_push() {
+                app.report_err("Git push", e);
+            }
+        }
+        KeyAction::NextTab => app.next_tab(),
+        KeyAction::PrevTab => app.prev_tab(),
+        KeyAction::ToggleSplit => app.toggle_split_view(),
+        KeyAction::CloseTab => app.close_tab(),
+        KeyAction::CloseOtherTabs => app.close_other_tabs(),
+        KeyAction::CloseAllTabs => app.close_all_tabs(),
+        KeyAction::ReopenClosedTab => {
+            if let Err(e) = app.reopen_closed_tab() {
+                app.push_message(format!("Cannot reopen tab: {e}"));
+            }
+        }
+        KeyAction::FollowLink => {
+            if let Some(link) = app.get_wiki_link_under_cursor() {
+                if let Err(e) = app.open_wiki_link(&link) {
+                    app.report_err("Open link", e);
                 }
             }
-            Focus::Rename => {
-                if key_matches(key, &[k.escape]) {
-                    app.exit_rename();
-                } else if key_matches(key, &[k.enter]) {
-                    let _ = app.rename_selected_note();
-                } else if key_matches(key, &[k.backspace]) {
-                    app.rename_backspace();
-                } else if let crossterm::event::KeyCode::Char(c) = key.code {
-                    app.rename_add_char(c);
+        }
+        KeyAction::SpellSuggest => app.enter_spell_suggest(),
+        KeyAction::SpellAddWord => app.add_word_under_cursor_to_dictionary(),
+        KeyAction::ExternalEditor => app.request_external_edit(),
+        KeyAction::ToggleTask => app.toggle_task_at_cursor(),
+        KeyAction::DisplayLineDown | KeyAction::DisplayLineUp => {
+            let count = app.take_count();
+            if let Some(buf) = app.focused_buffer_mut() {
+                for _ in 0..count {
+                    buf.move_display_line(action == KeyAction::DisplayLineDown);
                 }
             }
-            Focus::DeleteConfirm => {
-                if key_matches(key, &[k.escape]) {
-                    app.exit_delete_confirm();
-                } else if let crossterm::event::KeyCode::Char(c) = key.code {
-                    match c {
-                        'y' | 'Y' => {
-                            let _ = app.confirm_delete();
-                        }
-                        'n' | 'N' | '\n' | '\r' => {
-                            app.exit_delete_confirm();
-                        }
-                        _ => {}
+        }
+        KeyAction::DisplayLineStart | KeyAction::DisplayLineEnd => {
+            if let Some(buf) = app.focused_buffer_mut() {
+                buf.move_to_row_edge(action == KeyAction::DisplayLineEnd);
+            }
+        }
+        KeyAction::JumpToLevel(level) => app.jump_to_level(usize::from(level)),
+    }
+}
+
+/// Handle a key that is not (or no longer) part of a key sequence. Returns true to quit.
+fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
+    let k = &app.resolved_keys;
+
+    // Focus-specific handling
+    match app.focus {
+        Focus::Search => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_telescope();
+            } else if key_matches(key, &[k.enter]) {
+                if app.telescope_mode == TelescopeMode::Buffers {
+                    app.open_telescope_buffer_match();
+                } else if let Some(path) = app.get_telescope_selected_path() {
+                    app.record_jump();
+                    if let Err(e) = app.open_file(path) {
+                        app.report_err("Open", e);
+                    }
+                    app.exit_telescope();
+                }
+            } else if key_matches(key, &[k.telescope_open_split]) {
+                if let Some(path) = app.get_telescope_selected_path() {
+                    app.exit_telescope();
+                    if let Err(e) = app.open_in_split(path) {
+                        app.push_message(format!("Cannot open file: {e}"));
                     }
-                } else if key_matches(key, &[k.enter]) {
-                    app.exit_delete_confirm();
                 }
+            } else if key_matches(key, &[k.telescope_mode]) {
+                app.cycle_telescope_mode();
+            } else if key_matches(key, &[k.search_case]) {
+                app.toggle_search_case();
+            } else if key_matches(key, &[k.backspace]) {
+                app.telescope_backspace();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.telescope_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.telescope_move_down();
+            } else {
+                app.telescope_key(key);
             }
-            Focus::Backlinks => {
-                if key_matches(key, &[k.escape]) {
-                    app.focus = Focus::Editor;
-                } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
-                    app.backlinks_move_up();
-                } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
-                    app.backlinks_move_down();
-                } else if key_matches(key, &[k.enter]) {
-                    let _ = app.open_selected_backlink();
+        }
+        Focus::CommandPalette => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_command_palette();
+            } else if key_matches(key, &[k.enter]) {
+                match app.get_command_palette_action() {
+                    Some(PaletteItem::Action(action)) => {
+                        app.exit_command_palette();
+                        if app.execute_command(action) {
+                            return Ok(true);
+                        }
+                    }
+                    Some(PaletteItem::User(index)) => app.start_user_command(index),
+                    None => {}
                 }
+            } else if key_matches(key, &[k.backspace]) {
+                app.command_palette_backspace();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.command_palette_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.command_palette_move_down();
+            } else {
+                app.command_palette_key(key);
             }
-            Focus::CreatingDirectory => {
-                if key_matches(key, &[k.escape]) {
-                    app.exit_create_directory();
-                } else if key_matches(key, &[k.enter]) {
-                    let _ = app.create_directory();
-                } else if key_matches(key, &[k.backspace]) {
-                    app.directory_backspace();
-                } else if let crossterm::event::KeyCode::Char(c) = key.code {
-                    app.directory_add_char(c);
+        }
+        Focus::Rename => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_rename();
+            } else if key_matches(key, &[k.enter]) {
+                if let Err(e) = app.rename_selected_note() {
+                    app.report_err("Rename", e);
                 }
+            } else if key_matches(key, &[k.backspace]) {
+                app.rename_backspace();
+            } else {
+                app.rename_key(key);
             }
-            Focus::TaskView => {
-                if key_matches(key, &[k.escape]) {
-                    app.exit_task_view();
-                } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
-                    app.task_move_up();
-                } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
-                    app.task_move_down();
-                } else if key_matches(key, &[k.enter]) {
-                    let _ = app.open_selected_task();
+        }
+        Focus::TagPrompt => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_tag_prompt();
+            } else if key_matches(key, &[k.enter]) {
+                if let Err(e) = app.confirm_tag() {
+                    app.report_err("Tag", e);
                 }
+            } else if key_matches(key, &[k.backspace]) {
+                app.tag_backspace();
+            } else {
+                app.tag_key(key);
             }
-            Focus::TagExplorer => {
-                if key_matches(key, &[k.escape]) {
-                    app.exit_tag_explorer();
-                } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
-                    if app.tag_explorer_view == TagExplorerView::TagList {
-                        app.tag_list_move_up();
-                    } else {
-                        app.tag_file_move_up();
-                    }
-                } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
-                    if app.tag_explorer_view == TagExplorerView::TagList {
-                        app.tag_list_move_down();
-                    } else {
-                        app.tag_file_move_down();
+        }
+        Focus::DeleteConfirm => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_delete_confirm();
+            } else if let crossterm::event::KeyCode::Char(c) = key.code {
+                match c {
+                    'y' | 'Y' => {
+                        if let Err(e) = app.confirm_delete() {
+                            app.report_err("Delete", e);
+                        }
                     }
-                } else if key_matches(key, &[k.enter]) {
-                    if app.tag_explorer_view == TagExplorerView::TagList {
-                        app.load_files_for_selected_tag();
-                    } else {
-                        let _ = app.open_selected_tag_file();
+                    'n' | 'N' | '\n' | '\r' => {
+                        app.exit_delete_confirm();
                     }
-                } else if key_matches(key, &[k.backspace, k.move_left, k.move_left_alt])
-                    && app.tag_explorer_view == TagExplorerView::FileList
-                {
-                    app.tag_explorer_view = TagExplorerView::TagList;
+                    _ => {}
                 }
+            } else if key_matches(key, &[k.enter]) {
+                app.exit_delete_confirm();
             }
-            Focus::List => {
-                if app.template_picker_active {
-                    if key_matches(key, &[k.escape]) {
-                        app.exit_template_picker();
-                    } else if key_matches(key, &[k.enter]) {
-                        if let Some(path) =
-                            app.create_note_with_template(app.get_selected_template())?
-                        {
-                            let _ = app.load_file_into_editor(path);
-                        }
-                    } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
-                        app.template_picker_move_up();
-                    } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
-                        app.template_picker_move_down();
+        }
+        Focus::BufferPicker => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_buffer_picker();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.buffer_picker_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.buffer_picker_move_down();
+            } else if key_matches(key, &[k.enter]) {
+                app.confirm_buffer_picker();
+            }
+        }
+        Focus::ReplacePrompt => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_replace();
+            } else if key.code == crossterm::event::KeyCode::Tab {
+                app.replace_prompt_switch_field();
+            } else if key_matches(key, &[k.enter]) {
+                if app.replace_editing_with {
+                    if let Err(e) = app.start_replace_scan() {
+                        app.push_message(format!("Replace failed: {e}"));
                     }
                 } else {
-                    match app.mode {
-                        Mode::Normal => {
-                            if key_matches(key, &[k.quit]) {
-                                let _ = app.save_editor();
-                                break;
-                            }
-                            if key_matches(key, &[k.move_up, k.move_up_alt]) {
-                                app.move_selection_up();
-                            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
-                                app.move_selection_down();
-                            } else if key_matches(key, &[k.search]) {
-                                app.enter_search_mode();
-                            } else if key_matches(key, &[k.list_create_note]) {
-                                app.enter_create_mode();
-                            } else if key_matches(
-                                key,
-                                &[
-                                    k.list_create_dir,
-                                    crossterm::event::KeyEvent::new(
-                                        KeyCode::Char('N'),
-                                        KeyModifiers::empty(),
-                                    ),
-                                ],
-                            ) {
-                                app.enter_create_directory();
-                            } else if key_matches(
-                                key,
-                                &[
-                                    k.list_tag_explorer,
-                                    crossterm::event::KeyEvent::new(
-                                        KeyCode::Char('T'),
-                                        KeyModifiers::empty(),
-                                    ),
-                                ],
-                            ) {
-                                app.enter_tag_explorer();
-                            } else if key_matches(key, &[k.list_rename]) {
-                                app.enter_rename();
-                            } else if key_matches(key, &[k.list_edit_config]) {
-                                if let Ok(config_path) = config::config_file_path() {
-                                    let _ = app.load_file_into_editor(config_path);
-                                }
-                            } else if key_matches(key, &[k.list_delete, k.delete]) {
-                                app.enter_delete_confirm();
-                            } else if key_matches(
-                                key,
-                                &[
-                                    k.list_parent,
-                                    k.list_parent_alt,
-                                    k.move_left,
-                                    k.move_left_alt,
-                                ],
-                            ) {
-                                app.go_to_parent_dir();
-                            } else if key_matches(key, &[k.enter])
-                                && !app.enter_selected_directory()
-                            {
-                                if let Some(path) = app.get_selected_path() {
-                                    let _ = app.load_file_into_editor(path);
-                                }
-                            }
-                        }
-                        Mode::Search => {
-                            if key_matches(key, &[k.escape]) {
-                                app.exit_search_mode();
-                            } else if key_matches(key, &[k.enter]) {
-                                if app.enter_selected_directory() {
-                                    app.exit_search_mode();
-                                } else if let Some(path) = app.get_selected_path() {
-                                    let _ = app.load_file_into_editor(path);
-                                    app.exit_search_mode();
-                                }
-                            } else if key_matches(key, &[k.backspace]) {
-                                app.search_backspace();
-                            } else if let crossterm::event::KeyCode::Char(c) = key.code {
-                                app.search_add_char(c);
-                            }
-                        }
-                        Mode::Create => {
-                            if key_matches(key, &[k.escape]) {
-                                app.exit_create_mode();
-                            } else if key_matches(key, &[k.enter]) {
-                                app.enter_template_picker();
-                            } else if key_matches(key, &[k.backspace]) {
-                                app.create_backspace();
-                            } else if let crossterm::event::KeyCode::Char(c) = key.code {
-                                app.create_add_char(c);
-                            }
-                        }
-                    }
+                    app.replace_prompt_switch_field();
                 }
+            } else if key_matches(key, &[k.backspace]) {
+                app.replace_prompt_backspace();
+            } else {
+                app.replace_prompt_key(key);
             }
-            Focus::Editor => {
-                if key_matches(key, &[k.editor_pdf]) {
-                                app.export_to_pdf();
-                    continue;
-                }
-                if key_matches(key, &[k.editor_backlinks]) && app.config.editor.show_backlinks {
-                    app.focus = Focus::Backlinks;
-                    continue;
-                }
-                if app.editor_layout == EditorLayout::SplitVertical
-                    && app.split_right_tab.is_some()
-                    && key_matches(key, &[k.editor_split_focus])
-                {
-                    app.split_focus_left = !app.split_focus_left;
-                    continue;
+        }
+        Focus::ReplaceReview => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_replace();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.replace_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.replace_move_down();
+            } else if key_matches(key, &[k.list_mark]) {
+                app.replace_toggle_selected();
+            } else if key_matches(key, &[k.replace_dry_run]) {
+                app.replace_toggle_dry_run();
+            } else if key_matches(key, &[k.enter]) {
+                app.confirm_replace();
+            }
+        }
+        Focus::SpellSuggest => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_spell_suggest();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.spell_suggest_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.spell_suggest_move_down();
+            } else if key_matches(key, &[k.enter]) {
+                app.confirm_spell_suggestion();
+            }
+        }
+        Focus::ConfigError => {
+            if key_matches(key, &[k.list_edit_config]) {
+                app.open_config_error_file();
+            } else if key_matches(key, &[k.escape, k.enter]) {
+                app.exit_config_error();
+            }
+        }
+        Focus::UserCommandConfirm => {
+            if let crossterm::event::KeyCode::Char('y' | 'Y') = key.code {
+                app.confirm_user_command();
+            } else if key_matches(key, &[k.escape, k.enter])
+                || matches!(key.code, crossterm::event::KeyCode::Char('n' | 'N'))
+            {
+                app.exit_user_command_confirm();
+            }
+        }
+        Focus::CommandOutput => {
+            if key_matches(key, &[k.escape]) {
+                app.cancel_or_close_user_command();
+            } else if key_matches(key, &[k.enter]) {
+                app.close_user_command();
+            }
+        }
+        Focus::ImagePathPrompt => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_image_path_prompt();
+            } else if key_matches(key, &[k.enter]) {
+                app.confirm_image_path();
+            } else if key_matches(key, &[k.backspace]) {
+                app.image_path_backspace();
+            } else {
+                app.image_path_key(key);
+            }
+        }
+        Focus::GitRemotePrompt => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_git_remote_prompt();
+            } else if key_matches(key, &[k.enter]) {
+                app.confirm_git_remote();
+            } else if key_matches(key, &[k.backspace]) {
+                app.git_remote_backspace();
+            } else {
+                app.git_remote_key(key);
+            }
+        }
+        Focus::ExportScope => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_export_html();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.export_scope_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.export_scope_move_down();
+            } else if key_matches(key, &[k.enter]) {
+                app.confirm_export_scope();
+            }
+        }
+        Focus::ExportDir => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_export_html();
+            } else if key_matches(key, &[k.enter]) {
+                app.run_export_html();
+            } else if key_matches(key, &[k.backspace]) {
+                app.export_dir_backspace();
+            } else {
+                app.export_dir_key(key);
+            }
+        }
+        Focus::ExportSummary => {
+            if key_matches(key, &[k.escape, k.enter]) {
+                app.exit_export_html();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.export_summary_scroll_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.export_summary_scroll_down();
+            }
+        }
+        Focus::GraphOptions => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_export_graph();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.graph_option_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.graph_option_move_down();
+            } else if key_matches(key, &[k.move_left, k.move_left_alt]) {
+                app.graph_option_change(false);
+            } else if key_matches(key, &[k.list_mark])
+                || matches!(
+                    key.code,
+                    crossterm::event::KeyCode::Right | crossterm::event::KeyCode::Char('l')
+                )
+            {
+                app.graph_option_change(true);
+            } else if key_matches(key, &[k.enter]) {
+                app.confirm_graph_options();
+            }
+        }
+        Focus::GraphPath => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_export_graph();
+            } else if key_matches(key, &[k.enter]) {
+                app.run_export_graph();
+            } else if key_matches(key, &[k.backspace]) {
+                app.graph_path_backspace();
+            } else {
+                app.graph_path_key(key);
+            }
+        }
+        Focus::RandomNote => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_random_note();
+            } else if key_matches(key, &[k.enter]) {
+                app.open_random_note();
+            } else if key.code == KeyCode::Tab {
+                app.toggle_random_scope();
+            } else if key_matches(key, &[k.backspace]) {
+                app.random_tag_backspace();
+            } else {
+                app.random_tag_key(key);
+            }
+        }
+        Focus::QuickCapture => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_quick_capture();
+            } else if key_matches(key, &[k.enter]) {
+                app.submit_capture();
+            } else if key_matches(key, &[k.backspace]) {
+                app.capture_backspace();
+            } else {
+                app.capture_key(key);
+            }
+        }
+        Focus::AddTask => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_add_task();
+            } else if key_matches(key, &[k.enter]) {
+                app.submit_task();
+            } else if key_matches(key, &[k.backspace]) {
+                app.task_backspace();
+            } else {
+                app.task_key(key);
+            }
+        }
+        Focus::AliasPicker => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_alias_picker();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.alias_picker_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.alias_picker_move_down();
+            } else if key_matches(key, &[k.enter]) {
+                if let Err(e) = app.open_selected_alias() {
+                    app.report_err("Open", e);
                 }
-
-                if app.editor_mode == EditorMode::Normal
-                    && (key_matches(key, &[k.enter]) || key_matches(key, &[k.editor_wiki_link]))
-                {
-                    if let Some(link) = app.get_wiki_link_under_cursor() {
-                        let _ = app.open_wiki_link(&link);
-                        continue;
-                    }
+            }
+        }
+        Focus::Messages => {
+            if key_matches(key, &[k.escape, k.enter]) {
+                app.exit_messages();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.messages_scroll_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.messages_scroll_down();
+            }
+        }
+        Focus::DiffView => {
+            if key_matches(key, &[k.escape, k.enter]) {
+                app.exit_diff_view();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.diff_scroll_by(-1);
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.diff_scroll_by(1);
+            } else if key.code == KeyCode::PageUp {
+                app.diff_scroll_by(-20);
+            } else if key.code == KeyCode::PageDown {
+                app.diff_scroll_by(20);
+            }
+        }
+        Focus::KeybindingReport => {
+            if key_matches(key, &[k.escape, k.enter]) {
+                app.exit_keybinding_report();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.keybinding_report_scroll_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.keybinding_report_scroll_down();
+            }
+        }
+        Focus::RecentFiles => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_recent_files();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.recent_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.recent_move_down();
+            } else if key_matches(key, &[k.enter]) {
+                if let Err(e) = app.open_selected_recent() {
+                    app.push_message(format!("Cannot open file: {e}"));
                 }
-
-                match app.editor_mode {
-                    EditorMode::Normal => {
-                        app.editor_normal_input(key);
-                    }
-                    EditorMode::Insert => {
-                        if key_matches(key, &[k.escape]) {
-                            app.editor_mode = EditorMode::Normal;
-                        } else {
-                            app.mark_editor_dirty();
-                            if let Some(buf) = app.focused_buffer_mut() {
-                                let input: Input = key.into();
-                                buf.textarea.input_without_shortcuts(input);
-                            }
+            }
+        }
+        Focus::DuplicateConfirm => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_duplicate_confirm();
+            } else if let crossterm::event::KeyCode::Char(c) = key.code {
+                match c {
+                    'y' | 'Y' => {
+                        if let Err(e) = app.confirm_duplicate() {
+                            app.push_message(format!("Duplicate failed: {e}"));
                         }
                     }
+                    _ => app.exit_duplicate_confirm(),
                 }
+            } else if key_matches(key, &[k.enter]) {
+                app.exit_duplicate_confirm();
             }
         }
+        Focus::MovePicker => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_move_picker();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.move_picker_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.move_picker_move_down();
+            } else if key_matches(key, &[k.enter]) {
+                if let Err(e) = app.confirm_move() {
+                    app.push_message(format!("Move failed: {e}"));
+                }
+            }
+        }
+        Focus::Backlinks => {
+            if key_matches(key, &[k.escape]) {
+                app.focus = Focus::Editor;
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.backlinks_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.backlinks_move_down();
+            } else if key_matches(key, &[k.enter]) {
+                if let Err(e) = app.open_selected_backlink() {
+                    app.report_err("Open backlink", e);
+                }
+            }
+        }
+        Focus::CreatingDirectory => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_create_directory();
+            } else if key_matches(key, &[k.enter]) {
+                if let Err(e) = app.create_directory() {
+                    app.report_err("Create directory", e);
+                }
+            } else if key_matches(key, &[k.backspace]) {
+                app.directory_backspace();
+            } else {
+                app.directory_key(key);
+            }
+        }
+        Focus::TaskView => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_task_view();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                app.task_move_up();
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                app.task_move_down();
+            } else if key_matches(key, &[k.enter]) {
+                if let Err(e) = app.open_selected_task() {
+                    app.report_err("Open task", e);
+                }
+            } else if key_matches(key, &[k.move_left, k.move_left_alt]) {
+                app.task_move_column(-1);
+            } else if key_matches(key, &[k.move_right, k.move_right_alt]) {
+                app.task_move_column(1);
+            } else if key_matches(key, &[k.task_toggle]) {
+                if let Err(e) = app.toggle_selected_task() {
+                    app.report_err("Toggle task", e);
+                }
+            } else if key_matches(key, &[k.task_advance]) {
+                if let Err(e) = app.advance_selected_task() {
+                    app.report_err("Move task", e);
+                }
+            }
+        }
+        Focus::TagExplorer => {
+            if key_matches(key, &[k.escape]) {
+                app.exit_tag_explorer();
+            } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                if app.tag_explorer_view == TagExplorerView::TagList {
+                    app.tag_list_move_up();
+                } else {
+                    app.tag_file_move_up();
+                }
+            } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                if app.tag_explorer_view == TagExplorerView::TagList {
+                    app.tag_list_move_down();
+                } else {
+                    app.tag_file_move_down();
+                }
+            } else if key_matches(key, &[k.enter]) {
+                if app.tag_explorer_view == TagExplorerView::TagList {
+                    app.load_files_for_selected_tag();
+                } else {
+                    if let Err(e) = app.open_selected_tag_file() {
+                        app.report_err("Open", e);
+                    }
+                }
+            } else if key_matches(key, &[k.backspace, k.move_left, k.move_left_alt])
+                && app.tag_explorer_view == TagExplorerView::FileList
+            {
+                app.tag_explorer_view = TagExplorerView::TagList;
+            }
+        }
+        Focus::List => {
+            if app.template_picker_active {
+                if key_matches(key, &[k.escape]) {
+                    app.exit_template_picker();
+                } else if key_matches(key, &[k.enter]) {
+                    match app.create_note_with_template(app.get_selected_template()) {
+                        Ok(Some(path)) => {
+                            if let Err(e) = app.load_file_into_editor(path) {
+                                app.report_err("Open", e);
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => app.report_err("Create note", e),
+                    }
+                } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                    app.template_picker_move_up();
+                } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                    app.template_picker_move_down();
+                }
+            } else {
+                match app.mode {
+                    Mode::Normal => {
+                        if key_matches(key, &[k.quit]) {
+                            return Ok(app.save_before_quit());
+                        }
+                        if key_matches(key, &[k.escape]) {
+                            app.clear_marks();
+                        } else if key_matches(key, &[k.list_mark]) {
+                            app.toggle_mark();
+                        } else if key_matches(key, &[k.move_up, k.move_up_alt]) {
+                            app.move_selection_up();
+                        } else if key_matches(key, &[k.move_down, k.move_down_alt]) {
+                            app.move_selection_down();
+                        } else if key_matches(key, &[k.list_create_note]) {
+                            app.enter_create_mode();
+                        } else if key_matches(
+                            key,
+                            &[
+                                k.list_create_dir,
+                                crossterm::event::KeyEvent::new(
+                                    KeyCode::Char('N'),
+                                    KeyModifiers::empty(),
+                                ),
+                            ],
+                        ) {
+                            app.enter_create_directory();
+                        } else if key_matches(
+                            key,
+                            &[
+                                k.list_tag_explorer,
+                                crossterm::event::KeyEvent::new(
+                                    KeyCode::Char('T'),
+                                    KeyModifiers::empty(),
+                                ),
+                            ],
+                        ) {
+                            app.enter_tag_explorer();
+                        } else if key_matches(key, &[k.list_rename]) {
+                            app.enter_rename();
+                        } else if key_matches(key, &[k.list_edit_config]) {
+                            if let Ok(config_path) = config::config_file_path() {
+                                if let Err(e) = app.load_file_into_editor(config_path) {
+                                    app.report_err("Open", e);
+                                }
+                            }
+                        } else if key_matches(key, &[k.list_delete, k.delete]) {
+                            app.enter_delete_confirm();
+                        } else if key_matches(key, &[k.list_move]) {
+                            app.enter_move_picker();
+                        } else if key_matches(key, &[k.list_tag]) {
+                            app.enter_tag_prompt();
+                        } else if key_matches(key, &[k.list_duplicate]) {
+                            if let Err(e) = app.duplicate_selected() {
+                                app.push_message(format!("Duplicate failed: {e}"));
+                            }
+                        } else if key_matches(key, &[k.list_archive]) {
+                            if let Err(e) = app.toggle_archive() {
+                                app.push_message(format!("Archive failed: {e}"));
+                            }
+                        } else if key_matches(key, &[k.list_filter]) {
+                            app.enter_search_mode();
+                        } else if key_matches(key, &[k.list_filter_repeat]) {
+                            app.repeat_last_search();
+                        } else if key_matches(key, &[k.list_root]) {
+                            app.jump_to_level(0);
+                        } else if key_matches(key, &[k.list_tree]) {
+                            app.toggle_tree_view();
+                        } else if key_matches(key, &[k.move_right, k.move_right_alt]) {
+                            app.expand_selected();
+                        } else if key_matches(
+                            key,
+                            &[
+                                k.list_parent,
+                                k.list_parent_alt,
+                                k.move_left,
+                                k.move_left_alt,
+                            ],
+                        ) {
+                            app.collapse_selected();
+                        } else if key_matches(key, &[k.enter]) && !app.enter_selected_directory() {
+                            if let Some(path) = app.get_selected_path() {
+                                if let Err(e) = app.open_file(path) {
+                                    app.report_err("Open", e);
+                                }
+                            }
+                        }
+                    }
+                    Mode::Search => {
+                        if key_matches(key, &[k.escape]) {
+                            app.cancel_search_mode();
+                        } else if key_matches(key, &[k.enter]) {
+                            if app.enter_selected_directory() {
+                                app.exit_search_mode();
+                            } else if let Some(path) = app.get_selected_path() {
+                                if let Err(e) = app.open_file(path) {
+                                    app.report_err("Open", e);
+                                }
+                                app.exit_search_mode();
+                            }
+                        } else if key_matches(key, &[k.search_case]) {
+                            app.toggle_search_case();
+                        } else if key_matches(key, &[k.backspace]) {
+                            app.search_backspace();
+                        } else {
+                            app.search_key(key);
+                        }
+                    }
+                    Mode::Create => {
+                        if key_matches(key, &[k.escape]) {
+                            app.exit_create_mode();
+                        } else if key_matches(key, &[k.enter]) {
+                            app.enter_template_picker();
+                        } else if key_matches(key, &[k.backspace]) {
+                            app.create_backspace();
+                        } else {
+                            app.create_key(key);
+                        }
+                    }
+                }
+            }
+        }
+        Focus::Editor => {
+            if key_matches(key, &[k.editor_paste]) {
+                app.paste_from_clipboard();
+                return Ok(false);
+            }
+            if key_matches(key, &[k.editor_pdf]) {
+                app.export_to_pdf();
+                return Ok(false);
+            }
+            if key_matches(key, &[k.editor_backlinks]) && app.config.editor.show_backlinks {
+                app.focus = Focus::Backlinks;
+                return Ok(false);
+            }
+            if app.editor_mode == EditorMode::Insert
+                && ((key.code == KeyCode::Tab && key.modifiers.is_empty())
+                    || key.code == KeyCode::BackTab)
+                && app.snippet_takes_tab(key.code == KeyCode::BackTab)
+            {
+                app.snippet_tab(key.code == KeyCode::BackTab);
+                return Ok(false);
+            }
+            if app.editor_layout.is_split()
+                && app.split_right_tab().is_some()
+                && key_matches(key, &[k.editor_split_focus])
+            {
+                app.split_focus_left = !app.split_focus_left;
+                return Ok(false);
+            }
+
+            if app.editor_mode == EditorMode::Normal {
+                if key_matches(key, &[k.editor_jump_back]) {
+                    app.walk_jump_list(true);
+                    return Ok(false);
+                }
+                if key_matches(key, &[k.editor_jump_forward]) {
+                    app.walk_jump_list(false);
+                    return Ok(false);
+                }
+            }
+            if app.editor_mode == EditorMode::Normal
+                && (key_matches(key, &[k.enter]) || key_matches(key, &[k.editor_wiki_link]))
+            {
+                if let Some(link) = app.get_wiki_link_under_cursor() {
+                    if let Err(e) = app.open_wiki_link(&link) {
+                        app.report_err("Open link", e);
+                    }
+                    return Ok(false);
+                }
+            }
+
+            match app.editor_mode {
+                EditorMode::Normal => {
+                    app.editor_normal_input(key);
+                }
+                EditorMode::Insert => {
+                    let plain = key.modifiers.is_empty();
+                    if key_matches(key, &[k.escape]) {
+                        app.editor_mode = EditorMode::Normal;
+                    } else if key.code == KeyCode::Home && plain && app.config.editor.smart_indent {
+                        if let Some(buf) = app.focused_buffer_mut() {
+                            buf.smart_home();
+                        }
+                    } else if !((key.code == KeyCode::Enter
+                        && plain
+                        && (app.insert_newline_continuing_list()
+                            || app.insert_newline_keeping_indent()))
+                        || app.insert_auto_pair(key))
+                    {
+                        app.mark_editor_dirty();
+                        if let Some(buf) = app.focused_buffer_mut() {
+                            let input: Input = key.into();
+                            buf.textarea.input_without_shortcuts(input);
+                        }
+                    }
+                }
+            }
+            app.check_snippet();
+        }
     }
-    Ok(())
+    Ok(false)
 }
 
 fn main() -> Result<()> {
-    let _args = CliArgs::parse();
+    let args = CliArgs::parse();
+    if let Some(text) = args.capture {
+        return capture_from_cli(&text);
+    }
+    match args.command {
+        Some(CliCommand::Export { note, to, out }) => {
+            return export_from_cli(&note, to, out.as_deref());
+        }
+        Some(CliCommand::Tasks { json }) => {
+            let (config, notes_dir, walk) = walk_vault_from_cli()?;
+            let listing = list_tasks(&walk, &notes_dir, &config.task_columns, json)?;
+            return print_listing(&listing);
+        }
+        Some(CliCommand::Tags { json }) => {
+            let (_, notes_dir, walk) = walk_vault_from_cli()?;
+            return print_listing(&list_tags(&walk, &notes_dir, json)?);
+        }
+        None => {}
+    }
 
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     enable_raw_mode()?;
 
     std::panic::set_hook(Box::new(|_| {
@@ -407,23 +1388,2257 @@ fn main() -> Result<()> {
         let _ = execute!(
             io::stdout(),
             LeaveAlternateScreen,
-            DisableMouseCapture
+            DisableMouseCapture,
+            DisableBracketedPaste
         );
     }));
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
+    let mut shutdown = Shutdown::register()?;
+
     let mut app = App::new()?;
-    let result = run_app(&mut terminal, &mut app);
+    let result = run_app(&mut terminal, &mut app, &mut shutdown);
 
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+
+    /// App::new reads config and state from the XDG directories, which are process-wide, so
+    /// tests that build an App take turns.
+    static XDG_DIRS: Mutex<()> = Mutex::new(());
+
+    /// An App on a fresh vault under the temp dir holding `notes` (name, content). Returns the
+    /// lock guard, the vault's base directory (config, data and `notes/`) and the app.
+    fn test_app(name: &str, notes: &[(&str, &str)]) -> (MutexGuard<'static, ()>, PathBuf, App) {
+        let guard = XDG_DIRS.lock().unwrap_or_else(PoisonError::into_inner);
+        let base = std::env::temp_dir().join(format!("oxid-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let notes_dir = base.join("notes");
+        fs::create_dir_all(base.join("config/oxid")).unwrap();
+        fs::create_dir_all(&notes_dir).unwrap();
+        fs::write(
+            base.join("config/oxid/config.toml"),
+            format!("notes_directory = {:?}\n", notes_dir.display().to_string()),
+        )
+        .unwrap();
+        for (note, content) in notes {
+            let path = notes_dir.join(note);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        std::env::set_var("XDG_CONFIG_HOME", base.join("config"));
+        std::env::set_var("XDG_DATA_HOME", base.join("data"));
+        let app = App::new().unwrap();
+        (guard, base, app)
+    }
+
+    fn selected_name(app: &App) -> &str {
+        app.filtered_notes
+            .get(app.selected)
+            .map_or("", |e| e.display.as_str())
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        dispatch_key(app, KeyEvent::new(code, KeyModifiers::NONE)).unwrap();
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    /// The newest logged message; the footer shows it.
+    fn last_message(app: &App) -> &str {
+        app.messages.back().map_or("", |(_, m)| m.as_str())
+    }
+
+    #[test]
+    fn failed_writes_are_reported() {
+        // Failures that permissions cannot cause for root: a folder that is a regular file,
+        // and a note that was replaced by a folder.
+        let notes = [("existing.md", "text\n"), ("plain", "not a folder\n")];
+        let (_guard, base, mut app) = test_app("failed-writes", &notes);
+        let notes = base.join("notes");
+        let existing = notes.join("existing.md");
+
+        // Create: n, a name, Enter, Enter for the blank template.
+        type_text(&mut app, "n");
+        type_text(&mut app, "plain/new");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        assert!(
+            last_message(&app).starts_with("Create note failed"),
+            "{}",
+            last_message(&app)
+        );
+        assert_eq!(app.focus, Focus::List);
+
+        // Edit the note, then replace it on disk by a folder the list does not know about.
+        app.load_file_into_editor(existing.clone()).unwrap();
+        type_text(&mut app, "ix");
+        press(&mut app, KeyCode::Esc);
+        fs::remove_file(&existing).unwrap();
+        fs::create_dir(&existing).unwrap();
+
+        // Delete: the entry stays, and so does its buffer.
+        app.focus = Focus::List;
+        app.clear_message();
+        let index = app.filtered_notes.iter().position(|e| e.path == existing);
+        app.selected = index.unwrap();
+        type_text(&mut app, "dy");
+        assert!(
+            last_message(&app).starts_with("Delete failed"),
+            "{}",
+            last_message(&app)
+        );
+        assert!(existing.exists());
+        assert!(app
+            .buffers
+            .iter()
+            .any(|b| b.path.as_ref() == Some(&existing)));
+
+        // Save: leaving the editor saves, which cannot write over a folder.
+        app.focus = Focus::Editor;
+        type_text(&mut app, "q");
+        assert!(
+            last_message(&app).starts_with("Save failed"),
+            "{}",
+            last_message(&app)
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn unreadable_files_do_not_open_as_empty_buffers() {
+        let notes = [("folder.md/inner.md", "text\n")];
+        let (_guard, base, mut app) = test_app("unreadable", &notes);
+        // A folder named like a note cannot be read, even by root.
+        let path = base.join("notes/folder.md");
+        let open = app.buffers.len();
+        app.load_file_into_editor(path.clone()).unwrap();
+        let message = last_message(&app);
+        assert!(message.starts_with("Open failed: Cannot read"), "{message}");
+        assert_eq!(app.buffers.len(), open);
+        assert!(app.buffers.iter().all(|b| b.path.as_ref() != Some(&path)));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn unlisted_binary_files_are_left_untouched() {
+        let (_guard, base, mut app) = test_app("unlisted-binary", &[]);
+        let nul = base.join("notes/data.sqlite");
+        let latin1 = base.join("notes/legacy.txt");
+        fs::write(&nul, b"SQLite format 3\0\x10\x00").unwrap();
+        fs::write(&latin1, b"caf\xe9\n").unwrap();
+        let open = app.buffers.len();
+
+        app.open_file(nul.clone()).unwrap();
+        assert_eq!(last_message(&app), "Cannot edit binary file: data.sqlite");
+        app.load_file_into_editor(nul.clone()).unwrap();
+        assert!(last_message(&app).starts_with("Cannot edit binary file"));
+        app.load_file_into_editor(latin1.clone()).unwrap();
+        let message = last_message(&app);
+        assert!(
+            message.starts_with("Open failed:") && message.contains("not UTF-8"),
+            "{message}"
+        );
+        assert_eq!(app.buffers.len(), open);
+
+        // Leaving the editor saves every buffer; none of them may write over the files.
+        app.focus = Focus::Editor;
+        type_text(&mut app, "q");
+        assert_eq!(fs::read(&nul).unwrap(), b"SQLite format 3\0\x10\x00");
+        assert_eq!(fs::read(&latin1).unwrap(), b"caf\xe9\n");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn deleting_the_last_entry_selects_the_new_last() {
+        let notes = [("a.md", ""), ("b.md", ""), ("c.md", "")];
+        let (_guard, base, mut app) = test_app("delete-last", &notes);
+        app.selected = 2;
+        type_text(&mut app, "dy");
+        assert!(!base.join("notes/c.md").exists());
+        assert_eq!(selected_name(&app), "b.md");
+
+        // A refresh keeps the selected entry even when entries before it go away.
+        fs::remove_file(base.join("notes/a.md")).unwrap();
+        app.refresh_notes().unwrap();
+        assert_eq!(selected_name(&app), "b.md");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn renaming_the_selected_entry_keeps_it_selected() {
+        let notes = [("a.md", ""), ("b.md", ""), ("c.md", "")];
+        let (_guard, base, mut app) = test_app("rename-selected", &notes);
+        app.selected = 0;
+        type_text(&mut app, "r");
+        for _ in 0.."a.md".len() {
+            press(&mut app, KeyCode::Backspace);
+        }
+        type_text(&mut app, "z.md");
+        press(&mut app, KeyCode::Enter);
+        assert!(base.join("notes/z.md").exists(), "{}", last_message(&app));
+        assert_eq!(selected_name(&app), "z.md");
+        assert_eq!(app.selected, 2);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn display_titles_show_first_headings_and_search_both_names() {
+        let notes = [
+            ("2024-05-01.md", "---\ntags: [work]\n---\n# Weekly sync\n"),
+            ("plain.md", "no heading\n[[2024-05-01]]\n"),
+        ];
+        let (_guard, base, mut app) = test_app("display-titles", &notes);
+        let labels = |app: &App| -> Vec<String> {
+            app.filtered_notes
+                .iter()
+                .map(|e| e.label().to_string())
+                .collect()
+        };
+        assert_eq!(labels(&app), ["2024-05-01.md", "plain.md"]);
+
+        app.config.ui.display_titles = true;
+        app.refresh_notes().unwrap();
+        assert_eq!(labels(&app), ["Weekly sync", "plain.md"]);
+        assert_eq!(
+            app.note_title(&base.join("notes/2024-05-01.md")),
+            Some("Weekly sync")
+        );
+        for query in ["weekly", "2024"] {
+            type_text(&mut app, "f");
+            type_text(&mut app, query);
+            assert_eq!(labels(&app), ["Weekly sync"], "{query}");
+            assert!(!app.list_highlights.indices(0).is_empty(), "{query}");
+            press(&mut app, KeyCode::Esc);
+        }
+
+        // Renaming starts from the file name, not the title.
+        app.selected = 0;
+        type_text(&mut app, "r");
+        assert_eq!(app.rename_input, "2024-05-01");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn git_init_makes_a_repository_and_sets_the_remote() {
+        let (_guard, base, mut app) = test_app("git-init", &[("a.md", "hello\n")]);
+        let notes = base.join("notes");
+        assert_eq!(app.git, None);
+        app.execute_command(app::CommandAction::GitInit);
+        assert!(notes.join(".git").is_dir(), "{}", last_message(&app));
+        let gitignore = fs::read_to_string(notes.join(".gitignore")).unwrap();
+        assert!(gitignore.contains(".trash/"));
+        assert_eq!(app.focus, Focus::GitRemotePrompt);
+        app.git_remote_input.set("https://example.com/notes.git");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(
+            last_message(&app),
+            "Remote origin set to https://example.com/notes.git"
+        );
+        let url = git::run(&notes, &["remote", "get-url", "origin"]).unwrap();
+        assert_eq!(url.trim(), "https://example.com/notes.git");
+        app.tick_timers();
+        assert!(app.git.is_some());
+
+        app.execute_command(app::CommandAction::GitInit);
+        assert_eq!(last_message(&app), "The vault is already a git repository");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn diff_view_shows_unsaved_changes() {
+        let (_guard, base, mut app) = test_app("diff-view", &[("a.md", "one\ntwo\nthree\n")]);
+        app.load_file_into_editor(base.join("notes/a.md")).unwrap();
+        app.execute_command(app::CommandAction::DiffBuffer);
+        assert_eq!(last_message(&app), "No unsaved changes in a.md");
+        assert_eq!(app.focus, Focus::Editor);
+
+        type_text(&mut app, "ji2");
+        press(&mut app, KeyCode::Esc);
+        app.execute_command(app::CommandAction::DiffBuffer);
+        assert_eq!(app.focus, Focus::DiffView);
+        let lines: Vec<&str> = app.diff_lines.iter().map(|(_, l)| l.as_str()).collect();
+        assert_eq!(
+            lines,
+            [
+                "--- a.md (on disk)",
+                "+++ a.md (buffer)",
+                "@@ -1,3 +1,3 @@",
+                " one",
+                "-two",
+                "+2two",
+                " three"
+            ]
+        );
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.focus, Focus::Editor);
+
+        app.execute_command(app::CommandAction::GitDiffFile);
+        assert!(last_message(&app).contains("not a git repository"));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn auto_save_writes_edited_buffers_and_waits_to_apply_config() {
+        let notes = [("a.md", "a"), ("b.md", "b")];
+        let (_guard, base, mut app) = test_app("auto-save", &notes);
+        let notes = base.join("notes");
+        app.config.editor.auto_save_interval = 0;
+        app.load_file_into_editor(notes.join("a.md")).unwrap();
+        app.load_file_into_editor(notes.join("b.md")).unwrap();
+        type_text(&mut app, "ix");
+        press(&mut app, KeyCode::Esc);
+        // a.md is not edited in oxid, so auto-save leaves what another program wrote.
+        fs::write(notes.join("a.md"), "external").unwrap();
+        assert!(app.check_auto_save().unwrap());
+        assert_eq!(fs::read_to_string(notes.join("b.md")).unwrap(), "xb");
+        assert_eq!(fs::read_to_string(notes.join("a.md")).unwrap(), "external");
+
+        // Switching tabs saves with auto_save_on_focus_change.
+        app.config.editor.auto_save_on_focus_change = true;
+        type_text(&mut app, "iy");
+        press(&mut app, KeyCode::Esc);
+        let before = app.focus_target();
+        app.next_tab();
+        app.save_on_focus_change(before);
+        assert_eq!(fs::read_to_string(notes.join("b.md")).unwrap(), "xyb");
+
+        // A config file saved while it is edited applies once focus leaves it.
+        let config = base.join("config/oxid/config.toml");
+        let mut text = fs::read_to_string(&config).unwrap();
+        text.push_str("[ui]\ntick_rate_ms = 123\n");
+        fs::write(&config, &text).unwrap();
+        app.load_file_into_editor(config).unwrap();
+        app.focus = Focus::Editor;
+        type_text(&mut app, "i");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Esc);
+        app.config.editor.auto_save_on_focus_change = false;
+        assert!(app.check_auto_save().unwrap());
+        app.tick_timers();
+        assert_eq!(app.config.ui.tick_rate_ms, 500);
+        app.focus = Focus::List;
+        app.tick_timers();
+        assert_eq!(app.config.ui.tick_rate_ms, 123);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn config_changed_outside_oxid_applies_without_dropping_pending_keys() {
+        let notes = [("a.md", "a"), ("b.md", "b")];
+        let (_guard, base, mut app) = test_app("config-hot-reload", &notes);
+        let config_dir = base.join("config/oxid");
+        app.load_file_into_editor(base.join("notes/a.md")).unwrap();
+        app.load_file_into_editor(base.join("notes/b.md")).unwrap();
+        let active = app.active_tab;
+        type_text(&mut app, "g");
+        assert_eq!(app.pending_keys.len(), 1);
+
+        let mut config = fs::read_to_string(config_dir.join("config.toml")).unwrap();
+        config.push_str("[keys]\neditor_next_tab = \"g n\"\n");
+        fs::write(config_dir.join("config.toml"), config).unwrap();
+        fs::write(config_dir.join("theme.toml"), "editor_fg = \"#112233\"\n").unwrap();
+        app.tick_timers();
+        assert_eq!(last_message(&app), "Config reloaded");
+        let fg = ratatui::style::Color::Rgb(0x11, 0x22, 0x33);
+        assert_eq!(app.theme.editor_fg_style.fg, Some(fg));
+        assert_eq!(app.pending_keys.len(), 1);
+        type_text(&mut app, "n");
+        assert_ne!(app.active_tab, active);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn light_dark_toggle_restyles_open_buffers_and_cycles_within_the_mode() {
+        let (_guard, base, mut app) = test_app("light-dark", &[("a.md", "a")]);
+        app.load_file_into_editor(base.join("notes/a.md")).unwrap();
+        assert_eq!(app.theme_mode, theme::ThemeMode::Dark);
+        app.execute_command(app::CommandAction::ToggleLightDark);
+        assert_eq!(app.theme_mode, theme::ThemeMode::Light);
+        assert!(last_message(&app).starts_with("Theme: light"));
+        let bg = Some(ratatui::style::Color::White);
+        assert_eq!(app.theme.editor_bg_style.bg, bg);
+        assert!(app.buffers.iter().all(|b| b.textarea.style().bg == bg));
+
+        app.execute_command(app::CommandAction::CycleTheme);
+        assert_eq!(app.theme_preset, "gruvbox-light");
+        app.execute_command(app::CommandAction::ToggleLightDark);
+        assert_eq!(app.theme_preset, "gruvbox-dark");
+        assert_eq!(app.theme_mode, theme::ThemeMode::Dark);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn broken_config_files_stay_open_with_the_line_at_fault() {
+        let (_guard, base, mut app) = test_app("broken-config", &[("a.md", "a")]);
+        let config_dir = base.join("config/oxid");
+        app.config.editor.auto_save_interval = 0;
+        app.enter_task_view();
+        app.execute_command(app::CommandAction::OpenTheme);
+        assert_eq!(app.focus, Focus::Editor);
+        assert_eq!(app.editing_path(), Some(config_dir.join("theme.toml")));
+
+        app.execute_command(app::CommandAction::OpenConfig);
+        assert_eq!(app.editing_path(), Some(config_dir.join("config.toml")));
+        type_text(&mut app, "ggO");
+        type_text(&mut app, "oops = ");
+        press(&mut app, KeyCode::Esc);
+        assert!(app.check_auto_save().unwrap());
+        app.focus = Focus::List;
+        app.tick_timers();
+        assert_eq!(
+            last_message(&app),
+            "config.toml line 1: invalid string, expected `\"`, `'`; fix it and save to reload"
+        );
+        assert_eq!(app.config_errors.len(), 1);
+        let shown = app.messages.len();
+        app.tick_timers();
+        app.tick_timers();
+        assert_eq!(app.messages.len(), shown);
+        assert!(app
+            .buffers
+            .iter()
+            .any(|b| b.path == Some(config_dir.join("config.toml"))));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn shutdown_signal_saves_edited_buffers() {
+        let (_guard, base, mut app) = test_app("shutdown-signal", &[("a.md", "a")]);
+        let note = base.join("notes/a.md");
+        app.load_file_into_editor(note.clone()).unwrap();
+        type_text(&mut app, "ix");
+        press(&mut app, KeyCode::Esc);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        assert!(!shut_down_on_signal(&mut app, &shutdown).unwrap());
+        assert_eq!(fs::read_to_string(&note).unwrap(), "a");
+
+        signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&shutdown)).unwrap();
+        signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+        assert!(shut_down_on_signal(&mut app, &shutdown).unwrap());
+        assert_eq!(fs::read_to_string(&note).unwrap(), "xa");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn sigint_is_no_shutdown_while_it_is_let_go() {
+        // Built by hand: the test above raises SIGHUP, which `Shutdown::register` would watch.
+        let mut shutdown = Shutdown {
+            flag: Arc::new(AtomicBool::new(false)),
+            sigint: None,
+        };
+        shutdown.watch_sigint().unwrap();
+        shutdown.ignore_sigint();
+        signal_hook::low_level::raise(signal_hook::consts::SIGINT).unwrap();
+        assert!(!shutdown.flag.load(Ordering::Relaxed));
+
+        shutdown.watch_sigint().unwrap();
+        signal_hook::low_level::raise(signal_hook::consts::SIGINT).unwrap();
+        assert!(shutdown.flag.load(Ordering::Relaxed));
+        shutdown.ignore_sigint();
+    }
+
+    #[test]
+    fn ctrl_z_asks_to_suspend_from_the_editor_and_popups() {
+        let (_guard, base, mut app) = test_app("suspend-key", &[("a.md", "a")]);
+        let ctrl_z = |app: &mut App| {
+            let key = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+            dispatch_key(app, key).unwrap();
+        };
+        app.load_file_into_editor(base.join("notes/a.md")).unwrap();
+        type_text(&mut app, "ix");
+        ctrl_z(&mut app);
+        assert!(std::mem::take(&mut app.suspend_requested));
+        assert_eq!(app.focused_buffer().unwrap().textarea.lines(), ["xa"]);
+        assert_eq!(app.editor_mode, EditorMode::Insert);
+
+        app.enter_quick_capture();
+        type_text(&mut app, "idea");
+        ctrl_z(&mut app);
+        assert!(std::mem::take(&mut app.suspend_requested));
+        assert_eq!(app.focus, Focus::QuickCapture);
+        assert_eq!(app.capture_input, "idea");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn add_task_puts_tasks_under_the_heading_of_their_note() {
+        let roadmap = "# Roadmap\n\n## Tasks\n- [ ] plan\n\n## Done\n";
+        let notes = [
+            ("projects/roadmap.md", roadmap),
+            ("open.md", "# Open\ntext"),
+        ];
+        let (_guard, base, mut app) = test_app("add-task", &notes);
+        let notes = base.join("notes");
+        let add = |app: &mut App, task: &str| {
+            app.enter_add_task();
+            app.task_input.set(task);
+            press(app, KeyCode::Enter);
+        };
+        app.enter_task_view();
+        add(&mut app, "buy milk");
+        assert_eq!(app.focus, Focus::TaskView);
+        let date = chrono::Local::now().format("%Y-%m-%d");
+        let daily = fs::read_to_string(notes.join(format!("journal/{date}.md"))).unwrap();
+        assert!(daily.ends_with("\n\n## Tasks\n- [ ] buy milk\n"), "{daily}");
+        assert!(app.tasks.iter().any(|t| t.content == "buy milk"));
+
+        add(&mut app, "@projects/roadmap ship it");
+        assert_eq!(
+            fs::read_to_string(notes.join("projects/roadmap.md")).unwrap(),
+            "# Roadmap\n\n## Tasks\n- [ ] plan\n- [ ] ship it\n\n## Done\n"
+        );
+        app.exit_task_view();
+
+        app.load_file_into_editor(notes.join("open.md")).unwrap();
+        add(&mut app, "@open reply");
+        assert_eq!(app.focus, Focus::Editor);
+        let buf = app.focused_buffer().unwrap();
+        assert_eq!(
+            buf.textarea.lines(),
+            ["# Open", "text", "", "## Tasks", "- [ ] reply"]
+        );
+        assert_eq!(buf.textarea.cursor(), (0, 0));
+        assert_eq!(
+            fs::read_to_string(notes.join("open.md")).unwrap(),
+            "# Open\ntext"
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn popups_opened_over_each_other_close_or_layer() {
+        let (_guard, base, mut app) = test_app("popup-focus", &[]);
+        fn key(app: &mut App, c: char, modifiers: KeyModifiers) {
+            dispatch_key(app, KeyEvent::new(KeyCode::Char(c), modifiers)).unwrap();
+        }
+        let popups = [
+            (Focus::Search, App::enter_telescope as fn(&mut App)),
+            (Focus::CommandPalette, |app| {
+                key(app, 'p', KeyModifiers::CONTROL)
+            }),
+            (Focus::TagExplorer, App::enter_tag_explorer),
+            (Focus::TaskView, |app| key(app, 't', KeyModifiers::ALT)),
+            (Focus::QuickCapture, |app| key(app, 'n', KeyModifiers::ALT)),
+            (Focus::AddTask, |app| key(app, 'a', KeyModifiers::ALT)),
+        ];
+        let is_prompt = |focus| matches!(focus, Focus::QuickCapture | Focus::AddTask);
+        // Where Esc goes from a popup that is not a prompt; a scratch buffer is always open.
+        let closes_to = |focus| match focus {
+            Focus::Search | Focus::CommandPalette => Focus::Editor,
+            _ => Focus::List,
+        };
+        for (first, open_first) in popups {
+            for (second, open_second) in popups {
+                if first == second {
+                    continue;
+                }
+                let pair = format!("{second:?} over {first:?}");
+                let start = app.focus;
+                open_first(&mut app);
+                assert_eq!(app.focus, first, "{pair}");
+                open_second(&mut app);
+                assert_eq!(app.focus, second, "{pair}");
+                // Prompts are drawn over the view they came from and go back to it; anything
+                // else replaces the open popup.
+                let beneath = if is_prompt(first) { start } else { first };
+                let expected = if is_prompt(second) { beneath } else { second };
+                assert_eq!(app.underlying_focus(), expected, "{pair}");
+                press(&mut app, KeyCode::Esc);
+                let back = if is_prompt(second) {
+                    beneath
+                } else {
+                    closes_to(second)
+                };
+                assert_eq!(app.focus, back, "{pair}");
+                assert_eq!(app.underlying_focus(), back, "{pair}");
+                if back == first {
+                    press(&mut app, KeyCode::Esc);
+                    assert_eq!(app.focus, closes_to(first), "{pair}");
+                }
+            }
+        }
+
+        // Leaving telescope opened over the tag explorer shows the file list again.
+        app.enter_tag_explorer();
+        app.enter_telescope();
+        press(&mut app, KeyCode::Esc);
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 24)).unwrap();
+        terminal.draw(|f| ui::draw(f, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = buffer.content().iter().map(|c| c.symbol()).collect();
+        assert!(!screen.contains("Tag Explorer"), "{screen}");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    /// Whether the first cell of `text` on screen is drawn dimmed.
+    fn dimmed(app: &App, text: &str) -> bool {
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 24)).unwrap();
+        terminal.draw(|f| ui::draw(f, app)).unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        let (x, y) = (0..buffer.area.height)
+            .find_map(|y| {
+                let row: String = (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect();
+                row.find(text).map(|x| (x as u16, y))
+            })
+            .unwrap_or_else(|| panic!("{text} not on screen"));
+        buffer[(x, y)]
+            .modifier
+            .contains(ratatui::style::Modifier::DIM)
+    }
+
+    #[test]
+    fn inactive_panes_dim_while_the_focused_split_pane_does_not() {
+        let notes = [("left.md", "LLLL"), ("right.md", "RRRR"), ("listed.md", "")];
+        let (_guard, base, mut app) = test_app("dim-inactive", &notes);
+        let notes = base.join("notes");
+        app.config.ui.dim_inactive = true;
+        app.show_preview = false;
+        app.load_file_into_editor(notes.join("left.md")).unwrap();
+        app.open_in_split(notes.join("right.md")).unwrap();
+        app.focus = Focus::Editor;
+        assert!(dimmed(&app, "LLLL"));
+        assert!(!dimmed(&app, "RRRR"));
+        assert!(dimmed(&app, "listed.md"));
+
+        app.execute_command(app::CommandAction::SwitchSplitFocus);
+        assert!(!dimmed(&app, "LLLL"));
+        assert!(dimmed(&app, "RRRR"));
+
+        app.focus = Focus::List;
+        assert!(dimmed(&app, "LLLL"));
+        assert!(!dimmed(&app, "listed.md"));
+        app.config.ui.dim_inactive = false;
+        assert!(!dimmed(&app, "RRRR"));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn focus_mode_dims_all_but_the_paragraph_with_the_cursor() {
+        let note = "# Heading\nfirst one\nfirst two\n\nsecond one\n";
+        let (_guard, base, mut app) = test_app("focus-mode", &[("a.md", note)]);
+        app.show_preview = false;
+        app.load_file_into_editor(base.join("notes/a.md")).unwrap();
+        app.focus = Focus::Editor;
+        type_text(&mut app, "jj");
+        assert!(!dimmed(&app, "Heading"));
+        app.execute_command(app::CommandAction::ToggleFocusMode);
+        assert!(app.focus_mode);
+        assert!(dimmed(&app, "Heading"));
+        assert!(!dimmed(&app, "first one"));
+        assert!(!dimmed(&app, "first two"));
+        assert!(dimmed(&app, "second one"));
+
+        // A heading is a paragraph of its own; typing keeps the focus where the cursor is.
+        type_text(&mut app, "kk");
+        assert!(!dimmed(&app, "Heading"));
+        assert!(dimmed(&app, "first one"));
+        type_text(&mut app, "jjjjix");
+        assert!(dimmed(&app, "first two"));
+        assert!(!dimmed(&app, "xsecond one"));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn global_keys_are_typed_as_text_in_inputs() {
+        let (_guard, base, mut app) = test_app("typed-globals", &[("a.md", "x")]);
+        let notes = base.join("notes");
+        // From the file list `/` opens telescope and `q` quits.
+        type_text(&mut app, "nfoo/bar");
+        assert_eq!(app.create_filename, "foo/bar");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        assert!(notes.join("foo/bar.md").is_file(), "{}", last_message(&app));
+
+        app.focus = Focus::List;
+        app.selected = 0;
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(selected_name(&app), "bar.md");
+        type_text(&mut app, "r");
+        app.rename_input.clear();
+        type_text(&mut app, "q-notes.md");
+        assert_eq!(app.focus, Focus::Rename);
+        press(&mut app, KeyCode::Enter);
+        assert!(
+            notes.join("foo/q-notes.md").is_file(),
+            "{}",
+            last_message(&app)
+        );
+
+        type_text(&mut app, "/");
+        type_text(&mut app, "foo/q");
+        assert_eq!(
+            (app.focus, app.telescope_query.as_str()),
+            (Focus::Search, "foo/q")
+        );
+        press(&mut app, KeyCode::Esc);
+        type_text(&mut app, "ia/b");
+        assert_eq!(app.focus, Focus::Editor);
+        assert!(app.focused_buffer().unwrap().textarea.lines()[0].starts_with("a/b"));
+
+        // Chords still work while typing.
+        let key = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL);
+        dispatch_key(&mut app, key).unwrap();
+        assert_eq!(app.focus, Focus::CommandPalette);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn prompts_edit_at_the_cursor_and_ignore_chords() {
+        let (_guard, base, mut app) = test_app("prompt-cursor", &[("draft.md", "x")]);
+        let notes = base.join("notes");
+        app.focus = Focus::List;
+        app.selected = 0;
+        type_text(&mut app, "r");
+        // Fix the first letter and add one in the middle without retyping the name.
+        press(&mut app, KeyCode::Home);
+        press(&mut app, KeyCode::Delete);
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Right);
+        }
+        type_text(&mut app, "o");
+        for modifiers in [KeyModifiers::CONTROL, KeyModifiers::ALT] {
+            dispatch_key(&mut app, KeyEvent::new(KeyCode::Char('h'), modifiers)).unwrap();
+        }
+        assert_eq!(app.rename_input, "rafot");
+        press(&mut app, KeyCode::Enter);
+        assert!(notes.join("rafot.md").is_file(), "{}", last_message(&app));
+
+        app.enter_telescope();
+        type_text(&mut app, "rft");
+        press(&mut app, KeyCode::Left);
+        press(&mut app, KeyCode::Left);
+        type_text(&mut app, "a");
+        assert_eq!(app.telescope_query, "raft");
+        app.apply_pending_search();
+        assert_eq!(
+            app.get_telescope_selected_path(),
+            Some(notes.join("rafot.md"))
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn renaming_starts_from_the_stem_and_follows_open_notes() {
+        let notes = [("note.md", "n"), ("other.md", "o"), ("dir/child.md", "c")];
+        let (_guard, base, mut app) = test_app("rename-stem", &notes);
+        let notes = base.join("notes");
+        let names = |dir: &Path| {
+            let mut names: Vec<String> = fs::read_dir(dir)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+        app.focus = Focus::List;
+        assert!(app.select_path(&notes.join("note.md")));
+        type_text(&mut app, "r");
+        assert_eq!(app.rename_input, "note");
+        type_text(&mut app, "s");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(last_message(&app), "Renamed");
+
+        // Only the case changes; the note is not in its own way.
+        type_text(&mut app, "r");
+        press(&mut app, KeyCode::Home);
+        press(&mut app, KeyCode::Delete);
+        type_text(&mut app, "N");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(names(&notes), ["Notes.md", "dir", "other.md"]);
+        type_text(&mut app, "r");
+        app.rename_input.set("other");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(last_message(&app), "File already exists");
+        press(&mut app, KeyCode::Esc);
+
+        // Notes open from a renamed folder are saved under its new name.
+        app.load_file_into_editor(notes.join("dir/child.md"))
+            .unwrap();
+        type_text(&mut app, "ax");
+        press(&mut app, KeyCode::Esc);
+        app.focus = Focus::List;
+        assert!(app.select_path(&notes.join("dir")));
+        type_text(&mut app, "r");
+        assert_eq!(app.rename_input, "dir");
+        type_text(&mut app, "s");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.editing_path(), Some(notes.join("dirs/child.md")));
+        app.save_editor().unwrap();
+        assert_eq!(names(&notes), ["Notes.md", "dirs", "other.md"]);
+        assert_eq!(
+            fs::read_to_string(notes.join("dirs/child.md")).unwrap(),
+            "cx"
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn renaming_a_folder_moves_buffers_and_the_listed_folder_below_it() {
+        let notes = [("top.md", "t"), ("a/b/c/deep.md", "d"), ("a/b/mid.md", "m")];
+        let (_guard, base, mut app) = test_app("rename-folder", &notes);
+        let notes = base.join("notes");
+        app.load_file_into_editor(notes.join("top.md")).unwrap();
+        app.open_in_split(notes.join("a/b/c/deep.md")).unwrap();
+        type_text(&mut app, "ax");
+        press(&mut app, KeyCode::Esc);
+        assert!(app.editor_dirty);
+
+        app.focus = Focus::List;
+        assert!(app.select_path(&notes.join("a")));
+        type_text(&mut app, "r");
+        app.rename_input.set("z");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(last_message(&app), "Renamed");
+        let right = app.split_right_tab().expect("split keeps its buffer");
+        assert_eq!(app.buffers[right].path, Some(notes.join("z/b/c/deep.md")));
+        app.save_all_buffers().unwrap();
+        assert_eq!(
+            fs::read_to_string(notes.join("z/b/c/deep.md")).unwrap(),
+            "dx"
+        );
+        assert!(!notes.join("a").exists());
+
+        // Listing a folder below the renamed one keeps listing it under its new path.
+        app.focus = Focus::List;
+        assert!(app.select_path(&notes.join("z")));
+        app.current_dir = notes.join("z/b");
+        type_text(&mut app, "r");
+        app.rename_input.set("y");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(last_message(&app), "Renamed");
+        assert_eq!(app.current_dir, notes.join("y/b"));
+        assert!(app.select_path(&notes.join("y/b/mid.md")));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn moving_never_overwrites_and_the_picker_scrolls_to_its_selection() {
+        let mut notes = vec![
+            ("a.md".to_string(), "root"),
+            ("sub/a.md".to_string(), "sub"),
+        ];
+        notes.extend((0..20).map(|i| (format!("dir{i:02}/n.md"), "")));
+        let notes: Vec<(&str, &str)> = notes.iter().map(|(p, c)| (p.as_str(), *c)).collect();
+        let (_guard, base, mut app) = test_app("move", &notes);
+        let notes = base.join("notes");
+        app.focus = Focus::List;
+
+        // The last folder is far below the popup's last row, yet drawn once selected.
+        assert!(app.select_path(&notes.join("a.md")));
+        type_text(&mut app, "m");
+        assert_eq!(app.focus, Focus::MovePicker);
+        while app.move_targets.get(app.move_target_selected) != Some(&notes.join("sub")) {
+            press(&mut app, KeyCode::Char('j'));
+        }
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 24)).unwrap();
+        terminal.draw(|f| ui::draw(f, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("/sub "), "{screen}");
+
+        // A name taken in the target aborts the move.
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(last_message(&app), "Cannot move: sub/a.md already exists");
+        assert_eq!(app.focus, Focus::List);
+        assert_eq!(fs::read_to_string(notes.join("a.md")).unwrap(), "root");
+        assert_eq!(fs::read_to_string(notes.join("sub/a.md")).unwrap(), "sub");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn marks_survive_filtering_and_are_cleared_by_navigation_and_esc() {
+        let notes = [("alpha.md", "a"), ("beta.md", "b"), ("sub/gamma.md", "g")];
+        let (_guard, base, mut app) = test_app("marks", &notes);
+        let notes = base.join("notes");
+        app.focus = Focus::List;
+        let mark = |app: &mut App, path: PathBuf| {
+            assert!(app.select_path(&path));
+            press(app, KeyCode::Char(' '));
+        };
+        mark(&mut app, notes.join("alpha.md"));
+        mark(&mut app, notes.join("beta.md"));
+        assert_eq!(app.marked.len(), 2);
+
+        // A filter that hides a marked entry keeps its mark, and so does leaving the filter.
+        type_text(&mut app, "falpha");
+        assert_eq!(app.filtered_notes.len(), 1);
+        assert_eq!(app.marked.len(), 2);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.marked.len(), 2);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.marked.is_empty());
+
+        mark(&mut app, notes.join("alpha.md"));
+        assert!(app.select_path(&notes.join("sub")));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.current_dir, notes.join("sub"));
+        assert!(app.marked.is_empty());
+        mark(&mut app, notes.join("sub/gamma.md"));
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.current_dir, notes);
+        assert!(app.marked.is_empty());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn tagging_marked_notes_adds_the_tag_to_each_frontmatter() {
+        let notes = [
+            ("plain.md", "# Plain"),
+            ("listed.md", "---\ntags: [old]\ntitle: L\n---\nbody\n"),
+            ("untagged.md", "---\ntitle: U\n---\nbody\n"),
+            ("inline.md", "already #Idea here\n"),
+        ];
+        let (_guard, base, mut app) = test_app("batch-tag", &notes);
+        let notes = base.join("notes");
+        app.load_file_into_editor(notes.join("plain.md")).unwrap();
+        app.focus = Focus::List;
+        for name in ["plain.md", "listed.md", "untagged.md", "inline.md"] {
+            assert!(app.select_path(&notes.join(name)));
+            press(&mut app, KeyCode::Char(' '));
+        }
+        press(&mut app, KeyCode::Char('#'));
+        assert_eq!((app.focus, app.tag_pending.len()), (Focus::TagPrompt, 4));
+        type_text(&mut app, "#idea");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(last_message(&app), "Tagged 3 notes #idea (1 already were)");
+        assert!(app.marked.is_empty());
+
+        let read = |name: &str| fs::read_to_string(notes.join(name)).unwrap();
+        assert_eq!(read("plain.md"), "---\ntags: [idea]\n---\n# Plain");
+        assert_eq!(
+            read("listed.md"),
+            "---\ntags: [old, idea]\ntitle: L\n---\nbody\n"
+        );
+        assert_eq!(
+            read("untagged.md"),
+            "---\ntags: [idea]\ntitle: U\n---\nbody\n"
+        );
+        assert_eq!(read("inline.md"), "already #Idea here\n");
+        let open = app
+            .buffers
+            .iter()
+            .find(|b| b.path == Some(notes.join("plain.md")));
+        assert_eq!(open.unwrap().textarea.lines()[1], "tags: [idea]");
+
+        // Without marks only the selected note is tagged.
+        assert!(app.select_path(&notes.join("plain.md")));
+        press(&mut app, KeyCode::Char('#'));
+        type_text(&mut app, "more");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(last_message(&app), "Tagged #more");
+        assert_eq!(read("plain.md"), "---\ntags: [idea, more]\n---\n# Plain");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn close_all_saves_first_and_every_tab_can_be_reopened() {
+        let (_guard, base, mut app) = test_app("close-all", &[("a.md", "a"), ("b.md", "b")]);
+        let notes = base.join("notes");
+        app.load_file_into_editor(notes.join("a.md")).unwrap();
+        app.load_file_into_editor(notes.join("b.md")).unwrap();
+        type_text(&mut app, "ix");
+        press(&mut app, KeyCode::Esc);
+
+        // A failed save keeps every tab, so the edit is not lost.
+        fs::remove_file(notes.join("b.md")).unwrap();
+        fs::create_dir(notes.join("b.md")).unwrap();
+        type_text(&mut app, "gQ");
+        assert!(
+            last_message(&app).starts_with("Save failed"),
+            "{}",
+            last_message(&app)
+        );
+        assert_eq!(app.editing_path(), Some(notes.join("b.md")));
+        fs::remove_dir(notes.join("b.md")).unwrap();
+
+        type_text(&mut app, "gQ");
+        assert_eq!(fs::read_to_string(notes.join("b.md")).unwrap(), "xb");
+        assert_eq!(app.focus, Focus::List);
+        assert_eq!(app.buffers.len(), 1);
+        assert_eq!(app.buffers[0].path, None);
+        for name in ["a.md", "b.md"] {
+            app.reopen_closed_tab().unwrap();
+            assert_eq!(app.editing_path(), Some(notes.join(name)));
+        }
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn closing_or_deleting_tabs_keeps_the_split_on_its_buffers() {
+        let notes = [("a.md", "a"), ("b.md", "b"), ("c.md", "c"), ("d.md", "d")];
+        let (_guard, base, mut app) = test_app("split-close", &notes);
+        let notes = base.join("notes");
+        // Tabs a, b and c with c on the left, and d opened on the right (and focused).
+        let open = |app: &mut App| {
+            app.close_all_tabs();
+            for name in ["a.md", "b.md", "c.md"] {
+                app.load_file_into_editor(notes.join(name)).unwrap();
+            }
+            app.open_in_split(notes.join("d.md")).unwrap();
+            assert!(app.editor_layout.is_split());
+        };
+        let names = |app: &App| -> Vec<String> {
+            app.buffers
+                .iter()
+                .filter_map(|b| Some(b.path.as_ref()?.file_name()?.to_string_lossy().into_owned()))
+                .collect()
+        };
+        let index = |app: &App, name: &str| {
+            app.buffers
+                .iter()
+                .position(|b| b.path == Some(notes.join(name)))
+                .unwrap()
+        };
+
+        // Closing the left tab collapses the split onto the right one.
+        open(&mut app);
+        app.split_focus_left = true;
+        app.close_tab();
+        assert_eq!(names(&app), ["a.md", "b.md", "d.md"]);
+        assert_eq!(app.focused_buffer_index(), index(&app, "d.md"));
+        assert_eq!(app.split_right_id, None);
+        assert!(!app.editor_layout.is_split());
+
+        // Closing the right tab collapses it onto the left one.
+        open(&mut app);
+        assert!(!app.split_focus_left);
+        app.close_tab();
+        assert_eq!(names(&app), ["a.md", "b.md", "c.md"]);
+        assert_eq!(app.focused_buffer_index(), index(&app, "c.md"));
+        assert_eq!(app.split_right_id, None);
+        assert!(!app.editor_layout.is_split());
+
+        // Deleting a note before both panes moves their indices but not their buffers.
+        open(&mut app);
+        let right_id = app.buffers[index(&app, "d.md")].id;
+        app.focus = Focus::List;
+        assert!(app.select_path(&notes.join("a.md")));
+        app.enter_delete_confirm();
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(names(&app), ["b.md", "c.md", "d.md"]);
+        assert_eq!(app.active_tab, index(&app, "c.md"));
+        assert_eq!(app.split_right_id, Some(right_id));
+        assert_eq!(app.split_right_tab(), Some(index(&app, "d.md")));
+        assert_eq!(app.focused_buffer_index(), index(&app, "d.md"));
+
+        // Deleting the note shown on the right collapses the split onto the left one.
+        assert!(app.select_path(&notes.join("d.md")));
+        app.enter_delete_confirm();
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(names(&app), ["b.md", "c.md"]);
+        assert_eq!(app.focused_buffer_index(), index(&app, "c.md"));
+        assert_eq!(app.split_right_id, None);
+        assert!(app.split_focus_left);
+        assert!(!app.editor_layout.is_split());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn a_link_to_an_alias_of_several_notes_asks_which_one_to_open() {
+        let notes = [
+            ("one.md", "---\naliases: [Shared]\n---\n"),
+            ("two.md", "---\naliases: [shared, Other]\n---\n"),
+            ("linker.md", "see [[SHARED]]"),
+        ];
+        let (_guard, base, mut app) = test_app("alias-picker", &notes);
+        let notes = base.join("notes");
+        app.load_file_into_editor(notes.join("linker.md")).unwrap();
+        assert_eq!(
+            app.links.backlinks(&notes.join("two.md")),
+            [notes.join("linker.md")]
+        );
+
+        app.open_wiki_link("SHARED").unwrap();
+        assert_eq!(app.focus, Focus::AliasPicker);
+        assert_eq!(
+            app.alias_candidates,
+            [notes.join("one.md"), notes.join("two.md")]
+        );
+        press(&mut app, KeyCode::Esc);
+        assert!(app.alias_candidates.is_empty());
+        assert_eq!(app.editing_path(), Some(notes.join("linker.md")));
+
+        app.open_wiki_link("SHARED").unwrap();
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.focus, Focus::Editor);
+        assert_eq!(app.editing_path(), Some(notes.join("two.md")));
+        // A single note with the alias opens directly, whatever the case.
+        app.load_file_into_editor(notes.join("linker.md")).unwrap();
+        app.open_wiki_link("other").unwrap();
+        assert_eq!(app.editing_path(), Some(notes.join("two.md")));
+        assert!(!notes.join("other.md").exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn duplicates_keep_dots_in_folder_names() {
+        let notes = [("v1.2/a.md", "a"), ("draft.v2.md", "d")];
+        let (_guard, base, mut app) = test_app("duplicate-dots", &notes);
+        let notes = base.join("notes");
+        app.focus = Focus::List;
+        for copy in ["v1.2 copy", "v1.2 copy 2"] {
+            assert!(app.select_path(&notes.join("v1.2")));
+            press(&mut app, KeyCode::Char('D'));
+            assert_eq!(app.focus, Focus::DuplicateConfirm);
+            press(&mut app, KeyCode::Char('y'));
+            assert_eq!(last_message(&app), format!("Duplicated as {copy}"));
+            assert!(notes.join(copy).join("a.md").is_file());
+        }
+
+        assert!(app.select_path(&notes.join("draft.v2.md")));
+        press(&mut app, KeyCode::Char('D'));
+        assert_eq!(last_message(&app), "Duplicated as draft.v2 copy.md");
+        assert!(notes.join("draft.v2 copy.md").is_file());
+
+        // Remapped, the duplicate binding no longer answers to D.
+        app.config.keys.list_duplicate = "ctrl-d".to_string();
+        app.resolved_keys = config::ResolvedKeys::from_config(&app.config.keys);
+        press(&mut app, KeyCode::Char('D'));
+        assert_eq!(app.focus, Focus::List);
+        assert!(!notes.join("draft.v2 copy 2.md").exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn deleting_a_note_open_in_the_split_keeps_the_other_pane() {
+        let notes = [("left.md", "l"), ("right.md", "r"), ("other.md", "o")];
+        let (_guard, base, mut app) = test_app("delete-split", &notes);
+        let notes = base.join("notes");
+        let open_split = |app: &mut App| {
+            app.load_file_into_editor(notes.join("other.md")).unwrap();
+            app.load_file_into_editor(notes.join("left.md")).unwrap();
+            app.open_in_split(notes.join("right.md")).unwrap();
+        };
+        let delete = |app: &mut App, names: &[&str]| {
+            app.focus = Focus::List;
+            for name in names {
+                assert!(app.select_path(&notes.join(name)));
+                app.toggle_mark();
+            }
+            app.enter_delete_confirm();
+            press(app, KeyCode::Char('y'));
+            assert!(last_message(app).starts_with("Deleted"));
+            assert!(!app.editor_layout.is_split());
+            assert!(app.split_focus_left);
+            app.focused_buffer().and_then(|b| b.path.clone())
+        };
+
+        // The right pane is focused when it is opened; deleting its note leaves the left.
+        open_split(&mut app);
+        assert!(!app.split_focus_left);
+        assert_eq!(delete(&mut app, &["right.md"]), Some(notes.join("left.md")));
+
+        fs::write(notes.join("right.md"), "r").unwrap();
+        app.refresh_notes().unwrap();
+        open_split(&mut app);
+        app.split_focus_left = true;
+        assert_eq!(delete(&mut app, &["left.md"]), Some(notes.join("right.md")));
+        assert_eq!(
+            app.buffers[app.active_tab].path,
+            Some(notes.join("right.md"))
+        );
+
+        fs::write(notes.join("left.md"), "l").unwrap();
+        app.refresh_notes().unwrap();
+        open_split(&mut app);
+        assert_eq!(
+            delete(&mut app, &["left.md", "right.md"]),
+            Some(notes.join("other.md"))
+        );
+        let open: Vec<_> = app.buffers.iter().filter_map(|b| b.path.clone()).collect();
+        assert_eq!(open, [notes.join("other.md")]);
+        // Deleted notes are not offered for reopening.
+        app.close_tab();
+        assert_eq!(app.closed_tabs.len(), 1);
+        delete(&mut app, &["other.md"]);
+        app.reopen_closed_tab().unwrap();
+        assert_eq!(last_message(&app), "No closed tabs");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn checking_a_recurring_task_adds_the_next_one() {
+        let chores = "# Chores\n- [ ] water plants 🔁 every week 📅 2024-03-14\n- [ ] call mum\n";
+        let notes = [
+            ("chores.md", chores),
+            ("open.md", "- [ ] pay rent 🔁 every month\n"),
+        ];
+        let (_guard, base, mut app) = test_app("recurring-tasks", &notes);
+        let notes = base.join("notes");
+        app.enter_task_view();
+        let plants = app
+            .tasks
+            .iter()
+            .position(|t| t.content.starts_with("water"))
+            .unwrap();
+        app.task_selected = plants;
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(
+            fs::read_to_string(notes.join("chores.md")).unwrap(),
+            "# Chores\n- [ ] water plants 🔁 every week 📅 2024-03-21\n\
+             - [x] water plants 🔁 every week 📅 2024-03-14\n- [ ] call mum\n"
+        );
+        let plants = app
+            .tasks
+            .iter()
+            .find(|t| t.content.starts_with("water"))
+            .unwrap();
+        assert!(plants.content.ends_with("2024-03-21"));
+        app.exit_task_view();
+
+        app.load_file_into_editor(notes.join("open.md")).unwrap();
+        app.toggle_task_at_cursor();
+        let next = tasks::today()
+            .checked_add_months(chrono::Months::new(1))
+            .unwrap();
+        let buf = app.focused_buffer().unwrap();
+        assert_eq!(
+            buf.textarea.lines(),
+            [
+                format!(
+                    "- [ ] pay rent 🔁 every month 📅 {}",
+                    next.format("%Y-%m-%d")
+                ),
+                "- [x] pay rent 🔁 every month".to_string(),
+            ]
+        );
+        assert_eq!(buf.textarea.cursor().0, 1);
+        app.toggle_task_at_cursor();
+        assert_eq!(
+            app.focused_buffer().unwrap().textarea.lines()[1],
+            "- [ ] pay rent 🔁 every month"
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn task_board_columns_follow_status_tags() {
+        let work = "- [ ] write #doing\n- [ ] plan\n- [ ] ship #done\n";
+        let notes = [
+            ("work.md", work),
+            ("home.md", "# Home\n- [ ] paint #todo\n"),
+        ];
+        let (_guard, base, mut app) = test_app("task-columns", &notes);
+        let notes = base.join("notes");
+        app.load_file_into_editor(notes.join("home.md")).unwrap();
+        app.enter_task_view();
+        let column = |app: &App, column: usize| -> Vec<String> {
+            let mut tasks: Vec<String> = app.tasks[app.task_column_range(column)]
+                .iter()
+                .map(|t| t.content.clone())
+                .collect();
+            tasks.sort();
+            tasks
+        };
+        assert_eq!(column(&app, 0), ["paint #todo", "plan"]);
+        assert_eq!(column(&app, 1), ["write #doing"]);
+        assert_eq!(column(&app, 2), ["ship #done"]);
+
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.selected_task().unwrap().content, "write #doing");
+        press(&mut app, KeyCode::Char('m'));
+        assert_eq!(
+            fs::read_to_string(notes.join("work.md")).unwrap(),
+            "- [ ] write #done\n- [ ] plan\n- [ ] ship #done\n"
+        );
+        assert_eq!(column(&app, 2), ["ship #done", "write #done"]);
+        assert_eq!(app.task_column, 1);
+        assert!(app.selected_task().is_none());
+
+        press(&mut app, KeyCode::Char('h'));
+        let paint = app
+            .tasks
+            .iter()
+            .position(|t| t.content == "paint #todo")
+            .unwrap();
+        app.task_selected = paint;
+        press(&mut app, KeyCode::Char('m'));
+        let buf = app.focused_buffer().unwrap();
+        assert_eq!(buf.textarea.lines(), ["# Home", "- [ ] paint #doing"]);
+        assert!(!buf.dirty);
+        assert_eq!(
+            fs::read_to_string(notes.join("home.md")).unwrap(),
+            "# Home\n- [ ] paint #doing"
+        );
+        assert_eq!(column(&app, 1), ["paint #doing"]);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn task_counts_follow_saved_notes() {
+        let plan = "- [x] draft\n- [ ] review 📅 2000-01-01\n```\n- [ ] code\n```\n";
+        let (_guard, base, mut app) =
+            test_app("task-counts", &[("plan.md", plan), ("idea.md", "x")]);
+        let plan = base.join("notes/plan.md");
+        let counts = app.task_counts(&plan);
+        assert_eq!(counts.badge().as_deref(), Some("☑ 1/2"));
+        assert!(counts.overdue(tasks::today()));
+        assert_eq!(app.task_counts(&base.join("notes/idea.md")).badge(), None);
+
+        app.load_file_into_editor(plan.clone()).unwrap();
+        app.focused_buffer_mut()
+            .unwrap()
+            .textarea
+            .move_cursor(tui_textarea::CursorMove::Down);
+        app.toggle_task_at_cursor();
+        assert_eq!(app.task_counts(&plan).badge().as_deref(), Some("☑ 1/2"));
+        app.save_editor().unwrap();
+        let counts = app.task_counts(&plan);
+        assert_eq!(counts.badge().as_deref(), Some("☑ 2/2"));
+        assert!(!counts.overdue(tasks::today()));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn telescope_searches_the_open_buffers_unsaved_text() {
+        let notes = [
+            ("a.md", "alpha\nnothing here"),
+            ("b.md", "beta\n  see the Zebra\n"),
+        ];
+        let (_guard, base, mut app) = test_app("buffer-search", &notes);
+        let notes = base.join("notes");
+        app.load_file_into_editor(notes.join("a.md")).unwrap();
+        app.load_file_into_editor(notes.join("b.md")).unwrap();
+        let tab = |app: &App, name: &str| {
+            app.buffers
+                .iter()
+                .position(|b| b.display_name() == name)
+                .unwrap()
+        };
+        app.active_tab = tab(&app, "a.md");
+        // Unsaved: only the textarea has it.
+        app.focused_buffer_mut()
+            .unwrap()
+            .textarea
+            .insert_str("zebra crossing ");
+
+        app.enter_telescope();
+        dispatch_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL),
+        )
+        .unwrap();
+        assert_eq!(app.telescope_mode, TelescopeMode::Buffers);
+        type_text(&mut app, "zebr");
+        let found: Vec<(String, usize, usize)> = app
+            .telescope_buffer_matches
+            .iter()
+            .map(|m| (m.tab.clone(), m.row, m.start))
+            .collect();
+        assert_eq!(
+            found,
+            [("a.md".to_string(), 0, 0), ("b.md".to_string(), 1, 10)]
+        );
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.focus, Focus::Editor);
+        assert_eq!(app.active_tab, tab(&app, "b.md"));
+        assert_eq!(app.focused_buffer().unwrap().textarea.cursor(), (1, 10));
+
+        app.enter_telescope();
+        assert_eq!(app.telescope_mode, TelescopeMode::Files);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn regex_queries_keep_the_results_while_the_pattern_is_invalid() {
+        let notes = [
+            ("a.md", "## Decision: go"),
+            ("b.md", "TODO(ann)"),
+            ("c.md", "done"),
+        ];
+        let (_guard, base, mut app) = test_app("regex-search", &notes);
+        type_text(&mut app, "f");
+        type_text(&mut app, "re:TODO");
+        while app.poll_note_content() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(selected_name(&app), "b.md");
+        assert_eq!(app.filtered_notes.len(), 1);
+        type_text(&mut app, "(");
+        assert_eq!(app.search_error.as_deref(), Some("error: unclosed group"));
+        assert_eq!(app.filtered_notes.len(), 1);
+        press(&mut app, KeyCode::Backspace);
+        type_text(&mut app, r"\(\w+\)");
+        assert_eq!(app.search_error, None);
+        assert_eq!(selected_name(&app), "b.md");
+        assert_eq!(
+            app.list_highlights.content(0),
+            Some(&(0, "TODO(ann)".to_string()))
+        );
+
+        press(&mut app, KeyCode::Esc);
+        app.enter_telescope();
+        type_text(&mut app, "re:(?m)^## Dec");
+        let found: Vec<&str> = app
+            .telescope_filtered
+            .iter()
+            .map(|n| n.display.as_str())
+            .collect();
+        assert_eq!(found, ["a.md"]);
+        type_text(&mut app, "[");
+        assert!(app.telescope_error.is_some());
+        assert_eq!(app.telescope_filtered.len(), 1);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn the_case_toggle_matches_case_in_both_searches() {
+        let notes = [("api.md", "call the API"), ("notes.md", "an api call")];
+        let (_guard, base, mut app) = test_app("search-case", &notes);
+        let ctrl_t = KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL);
+        type_text(&mut app, "f");
+        type_text(&mut app, ">api");
+        while app.poll_note_content() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(app.filtered_notes.len(), 2);
+        dispatch_key(&mut app, ctrl_t).unwrap();
+        assert_eq!(app.search_case, search::CaseMode::Sensitive);
+        assert_eq!(app.search_query, ">api");
+        assert_eq!(selected_name(&app), "notes.md");
+        assert_eq!(app.filtered_notes.len(), 1);
+
+        press(&mut app, KeyCode::Esc);
+        app.enter_telescope();
+        type_text(&mut app, "API");
+        let found: Vec<&str> = app
+            .telescope_filtered
+            .iter()
+            .map(|n| n.display.as_str())
+            .collect();
+        assert_eq!(found, ["api.md"]);
+        dispatch_key(&mut app, ctrl_t).unwrap();
+        assert_eq!(app.search_case, search::CaseMode::Ignore);
+        assert_eq!(app.telescope_filtered.len(), 2);
+
+        app.config.search.smart_case = true;
+        dispatch_key(&mut app, ctrl_t).unwrap();
+        dispatch_key(&mut app, ctrl_t).unwrap();
+        assert_eq!(app.search_case, search::CaseMode::Smart);
+        assert_eq!(app.telescope_filtered.len(), 1);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn typing_keeps_the_selection_while_it_still_matches() {
+        let notes = [("alpha.md", "x"), ("alpine.md", "x"), ("beta.md", "x")];
+        let (_guard, base, mut app) = test_app("keep-selection", &notes);
+        let notes = base.join("notes");
+        type_text(&mut app, "f");
+        type_text(&mut app, "al");
+        assert!(app.select_path(&notes.join("alpha.md")));
+        type_text(&mut app, "p");
+        assert_eq!(selected_name(&app), "alpha.md");
+        // No longer a match: the best one is selected.
+        type_text(&mut app, "i");
+        assert_eq!(selected_name(&app), "alpine.md");
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(selected_name(&app), "alpine.md");
+        press(&mut app, KeyCode::Esc);
+
+        app.enter_telescope();
+        type_text(&mut app, "al");
+        let telescope_name = |app: &App| {
+            app.telescope_filtered[app.telescope_selected]
+                .display
+                .clone()
+        };
+        if telescope_name(&app) != "alpha.md" {
+            press(&mut app, KeyCode::Down);
+        }
+        assert_eq!(telescope_name(&app), "alpha.md");
+        type_text(&mut app, "ph");
+        assert_eq!(telescope_name(&app), "alpha.md");
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(telescope_name(&app), "alpha.md");
+        type_text(&mut app, "i");
+        assert_eq!(telescope_name(&app), "alpine.md");
+        press(&mut app, KeyCode::Esc);
+
+        app.enter_command_palette();
+        type_text(&mut app, "togg");
+        press(&mut app, KeyCode::Down);
+        let item = app.command_palette_filtered[app.command_palette_selected];
+        assert_eq!(app.command_palette_selected, 1);
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(
+            app.command_palette_filtered[app.command_palette_selected],
+            item
+        );
+        press(&mut app, KeyCode::Esc);
+        app.enter_command_palette();
+        assert_eq!(app.command_palette_selected, 0);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn leaving_the_list_search_restores_or_keeps_the_selection() {
+        let notes = [("apple.md", "x"), ("banana.md", "x"), ("cherry.md", "x")];
+        let (_guard, base, mut app) = test_app("search-exit", &notes);
+        let notes = base.join("notes");
+        assert!(app.select_path(&notes.join("banana.md")));
+
+        // Esc goes back to the entry selected before the search.
+        type_text(&mut app, "f");
+        type_text(&mut app, "cher");
+        assert_eq!(selected_name(&app), "cherry.md");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.filtered_notes.len(), 3);
+        assert_eq!(selected_name(&app), "banana.md");
+        assert_eq!(app.last_search, "cher");
+
+        // The repeat key searches with the last query; Enter keeps the opened note selected.
+        type_text(&mut app, "F");
+        assert_eq!(app.mode, Mode::Search);
+        assert_eq!(app.search_query, "cher");
+        assert_eq!(app.filtered_notes.len(), 1);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.focused_buffer().unwrap().display_name(), "cherry.md");
+        assert_eq!(app.filtered_notes.len(), 3);
+        assert_eq!(selected_name(&app), "cherry.md");
+
+        // A debounced search is cancelled before it is applied.
+        app.focus = Focus::List;
+        app.config.ui.debounce_search = true;
+        type_text(&mut app, "f");
+        type_text(&mut app, "app");
+        app.apply_pending_search();
+        assert_eq!(selected_name(&app), "apple.md");
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(selected_name(&app), "cherry.md");
+        assert_eq!(app.last_search, "ap");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn typing_a_search_narrows_and_can_wait_for_the_keys() {
+        let notes: Vec<(String, String)> = (0..120)
+            .map(|i| (format!("note-{i:03}.md"), format!("body {i}\n")))
+            .collect();
+        let notes: Vec<(&str, &str)> = notes
+            .iter()
+            .map(|(n, c)| (n.as_str(), c.as_str()))
+            .collect();
+        let (_guard, base, mut app) = test_app("search-narrowing", &notes);
+        let names = |notes: &[app::NoteEntry]| -> Vec<String> {
+            let mut names: Vec<String> = notes.iter().map(|n| n.display.clone()).collect();
+            names.sort();
+            names
+        };
+        type_text(&mut app, "f");
+        type_text(&mut app, "note");
+        assert_eq!(app.filtered_notes.len(), 120);
+        // Only rows near the view get highlights.
+        assert!(!app.list_highlights.indices(0).is_empty());
+        assert!(app.list_highlights.indices(100).is_empty());
+        app.selected = 100;
+        app.fill_highlights();
+        assert!(!app.list_highlights.indices(100).is_empty());
+
+        // Narrowed results are the ones filtering every note gives.
+        type_text(&mut app, "-11");
+        let mut matcher = nucleo_matcher::Matcher::new(nucleo_matcher::Config::DEFAULT);
+        let (mode, case) = (search::SearchMode::Fuzzy, search::CaseMode::Ignore);
+        let full = search::filter_notes(&app.all_notes, "note-11", mode, case, &mut matcher);
+        assert_eq!(names(&app.filtered_notes), names(&full));
+        assert!(app.filtered_notes.len() < 120);
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Backspace);
+        }
+        assert_eq!(app.filtered_notes.len(), 120);
+
+        app.config.ui.debounce_search = true;
+        type_text(&mut app, "-012");
+        assert_eq!(app.filtered_notes.len(), 120);
+        app.apply_pending_search();
+        assert_eq!(selected_name(&app), "note-012.md");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn zettel_ids_name_new_notes_and_links_find_them_after_a_rename() {
+        let (_guard, base, mut app) = test_app("zettel-ids", &[("index.md", "")]);
+        let notes = base.join("notes");
+        app.config.zettel_ids = true;
+        type_text(&mut app, "n");
+        type_text(&mut app, "Idea");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        let name = selected_name(&app).to_string();
+        let (id, rest) = name.split_once('-').unwrap();
+        assert_eq!((id.len(), rest), (12, "Idea.md"), "{}", last_message(&app));
+        let id = id.to_string();
+
+        // Renaming the part after the ID keeps [[ID]] links (and backlinks) working.
+        app.close_all_tabs();
+        let renamed = notes.join(format!("{id}-Better idea.md"));
+        fs::rename(notes.join(&name), &renamed).unwrap();
+        fs::write(notes.join("index.md"), format!("[[{id}]]")).unwrap();
+        for path in [notes.join(&name), renamed.clone(), notes.join("index.md")] {
+            app.links.update(&path, &app.ignore);
+        }
+        assert_eq!(app.links.backlinks(&renamed), [notes.join("index.md")]);
+        assert_eq!(app.links.note_id(&renamed), Some(id.clone()));
+        let count = || fs::read_dir(&notes).unwrap().count();
+        let before = count();
+        for link in [id.as_str(), "Better idea"] {
+            app.open_wiki_link(link).unwrap();
+            assert_eq!(
+                app.focused_buffer().unwrap().path.as_ref(),
+                Some(&renamed),
+                "{link}"
+            );
+        }
+        assert_eq!(count(), before);
+
+        // In the frontmatter, the ID leaves the name alone.
+        app.config.zettel_id_in_frontmatter = true;
+        app.open_wiki_link("Fresh").unwrap();
+        let fresh = fs::read_to_string(notes.join("Fresh.md")).unwrap();
+        let fresh_id = fresh
+            .strip_prefix("---\nid: ")
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap();
+        assert_eq!(
+            app.links.note_id(&notes.join("Fresh.md")).as_deref(),
+            Some(fresh_id)
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn idle_loop_passes_do_not_redraw() {
+        let (_guard, base, mut app) = test_app("idle", &[("a.md", "# A\n"), ("b.md", "b")]);
+        while poll_background(&mut app) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        app.needs_redraw = false;
+        for _ in 0..5 {
+            poll_background(&mut app);
+        }
+        assert!(!app.needs_redraw);
+
+        // A new footer message does.
+        app.push_message("hello".to_string());
+        assert!(app.needs_redraw);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn tree_view_expands_and_collapses_in_place() {
+        let (_guard, base, mut app) = test_app("tree-view", &[("top.md", "")]);
+        let notes = base.join("notes");
+        fs::create_dir_all(notes.join("projects/2024")).unwrap();
+        fs::write(notes.join("projects/a.md"), "").unwrap();
+        fs::write(notes.join("projects/2024/deep.md"), "zebra\n").unwrap();
+        type_text(&mut app, "t");
+        assert!(app.tree_view);
+        app.refresh_notes().unwrap();
+        app.selected = 0;
+        assert_eq!(selected_name(&app), "projects/");
+
+        // Enter expands the folder in place; its entries follow it, indented.
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.current_dir, notes);
+        let names: Vec<(&str, usize)> = app
+            .filtered_notes
+            .iter()
+            .map(|e| (e.display.as_str(), e.depth))
+            .collect();
+        assert_eq!(
+            names,
+            [("projects/", 0), ("2024/", 1), ("a.md", 1), ("top.md", 0)]
+        );
+
+        // Right expands a subfolder; Left on a file inside collapses the folder it is in.
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(selected_name(&app), "deep.md");
+        press(&mut app, KeyCode::Left);
+        assert_eq!(selected_name(&app), "2024/");
+        assert!(!app.expanded.contains(&notes.join("projects/2024")));
+        press(&mut app, KeyCode::Left);
+        assert_eq!(selected_name(&app), "projects/");
+        press(&mut app, KeyCode::Left);
+        assert_eq!(app.filtered_notes.len(), 2);
+
+        // The filter searches the whole tree, and opening a result reveals it.
+        type_text(&mut app, "f");
+        type_text(&mut app, "deep");
+        while app.poll_note_content() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(selected_name(&app), "projects/2024/deep.md");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(selected_name(&app), "deep.md");
+        assert_eq!(app.filtered_notes[app.selected].depth, 2);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn j_k_keep_the_goal_column_and_gj_gk_step_through_wrapped_lines() {
+        let long = "x".repeat(300);
+        let content = format!("{}\nab\n{long}\nlast line here\n", "s".repeat(40));
+        let (_guard, base, mut app) = test_app("display-lines", &[("wide.md", &content)]);
+        app.load_file_into_editor(base.join("notes/wide.md"))
+            .unwrap();
+        let cursor = |app: &App| app.focused_buffer().unwrap().textarea.cursor();
+        // As drawn in an 80-column pane.
+        app.focused_buffer().unwrap().text_width.set(80);
+
+        // j over a short line comes back to the column it started in.
+        type_text(&mut app, &"l".repeat(30));
+        assert_eq!(cursor(&app), (0, 30));
+        type_text(&mut app, "j");
+        assert_eq!(cursor(&app), (1, 2));
+        type_text(&mut app, "j");
+        assert_eq!(cursor(&app), (2, 30));
+        type_text(&mut app, "kk");
+        assert_eq!(cursor(&app), (0, 30));
+
+        // gj walks the 300-character line 80 columns at a time, then moves on.
+        type_text(&mut app, "jj");
+        for col in [110, 190, 270] {
+            type_text(&mut app, "gj");
+            assert_eq!(cursor(&app), (2, col));
+        }
+        type_text(&mut app, "gj");
+        assert_eq!(cursor(&app), (3, 14));
+        type_text(&mut app, "gk");
+        assert_eq!(cursor(&app), (2, 270));
+        for col in [190, 110, 30] {
+            type_text(&mut app, "gk");
+            assert_eq!(cursor(&app), (2, col));
+        }
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn o_and_shift_o_open_lines_as_one_undo_step() {
+        let note = ("list.md", "title\n- [x] done\n9. nine\n");
+        let (_guard, base, mut app) = test_app("open-line", &[note]);
+        app.load_file_into_editor(base.join("notes/list.md"))
+            .unwrap();
+        let lines = |app: &App| app.focused_buffer().unwrap().textarea.lines().to_vec();
+
+        type_text(&mut app, "Oabove");
+        assert_eq!(app.editor_mode, EditorMode::Insert);
+        assert!(app.editor_dirty);
+        press(&mut app, KeyCode::Esc);
+        type_text(&mut app, "jo");
+        assert_eq!(lines(&app), ["above", "title", "", "- [x] done", "9. nine"]);
+        press(&mut app, KeyCode::Esc);
+        type_text(&mut app, "u");
+        assert_eq!(lines(&app), ["above", "title", "- [x] done", "9. nine"]);
+
+        // With continue_lists, o and Enter carry the marker over; Enter on a bare marker ends
+        // the list.
+        app.config.editor.continue_lists = true;
+        type_text(&mut app, "jo");
+        press(&mut app, KeyCode::Esc);
+        type_text(&mut app, "jjoten");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(
+            lines(&app),
+            [
+                "above",
+                "title",
+                "- [x] done",
+                "- [ ] ",
+                "9. nine",
+                "10. ten",
+                ""
+            ]
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn auto_pairs_close_skip_and_delete_delimiters() {
+        let (_guard, base, mut app) = test_app("auto-pairs", &[("pairs.md", "word\n")]);
+        app.load_file_into_editor(base.join("notes/pairs.md"))
+            .unwrap();
+        let lines = |app: &App| app.focused_buffer().unwrap().textarea.lines().to_vec();
+        let cursor = |app: &App| app.focused_buffer().unwrap().textarea.cursor();
+
+        // [[ gives [[]]; typing the closing brackets steps over them.
+        type_text(&mut app, "O[[Page");
+        assert_eq!(lines(&app)[0], "[[Page]]");
+        type_text(&mut app, "]] (x) **b**");
+        assert_eq!(lines(&app)[0], "[[Page]] (x) **b**");
+        assert_eq!(cursor(&app), (0, 18));
+
+        // Backspace in an empty pair deletes both halves.
+        type_text(&mut app, " `");
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(lines(&app)[0], "[[Page]] (x) **b** ");
+
+        // No pair before a letter, nor a symmetric one after it.
+        press(&mut app, KeyCode::Esc);
+        type_text(&mut app, "j0i(");
+        type_text(&mut app, "snake_\"");
+        assert_eq!(lines(&app)[1], "(snake_\"word");
+
+        app.config.editor.auto_pairs = false;
+        type_text(&mut app, "[");
+        assert_eq!(lines(&app)[1], "(snake_\"[word");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn smart_indent_keeps_tabs_and_spaces_and_home_toggles() {
+        let note = ("indent.md", "- top\n    - spaces\n\t\tcode\n");
+        let (_guard, base, mut app) = test_app("smart-indent", &[note]);
+        app.load_file_into_editor(base.join("notes/indent.md"))
+            .unwrap();
+        let lines = |app: &App| app.focused_buffer().unwrap().textarea.lines().to_vec();
+        let cursor = |app: &App| app.focused_buffer().unwrap().textarea.cursor();
+
+        // Enter copies the indentation as it is: spaces stay spaces, tabs stay tabs.
+        type_text(&mut app, "j");
+        press(&mut app, KeyCode::End);
+        type_text(&mut app, "i");
+        press(&mut app, KeyCode::Enter);
+        type_text(&mut app, "next");
+        press(&mut app, KeyCode::Esc);
+        type_text(&mut app, "j");
+        press(&mut app, KeyCode::End);
+        type_text(&mut app, "i");
+        press(&mut app, KeyCode::Enter);
+        type_text(&mut app, "more");
+        assert_eq!(
+            lines(&app),
+            ["- top", "    - spaces", "    next", "\t\tcode", "\t\tmore"]
+        );
+
+        // Home goes to the first non-blank character, then to column 0 and back.
+        press(&mut app, KeyCode::Home);
+        assert_eq!(cursor(&app), (4, 2));
+        press(&mut app, KeyCode::Home);
+        assert_eq!(cursor(&app), (4, 0));
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Home);
+        assert_eq!(cursor(&app), (4, 2));
+
+        // Without smart_indent, Home is column 0 and Enter starts unindented.
+        app.config.editor.smart_indent = false;
+        type_text(&mut app, "k");
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Home);
+        assert_eq!(cursor(&app), (3, 0));
+        press(&mut app, KeyCode::End);
+        type_text(&mut app, "i");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(lines(&app)[4], "");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn snippets_expand_on_tab_and_walk_their_tab_stops() {
+        let (_guard, base, mut app) = test_app("snippets", &[("snip.md", "")]);
+        app.load_file_into_editor(base.join("notes/snip.md"))
+            .unwrap();
+        let lines = |app: &App| app.focused_buffer().unwrap().textarea.lines().to_vec();
+        let cursor = |app: &App| app.focused_buffer().unwrap().textarea.cursor();
+        let snippets = &mut app.config.snippets;
+        snippets.insert("todo".into(), "- [ ] ${1:task} 📅 ${2:date}".into());
+        snippets.insert("code".into(), "```${1:lang}\n$0\n```".into());
+
+        // Tab expands the trigger and selects the first placeholder; typing replaces it.
+        type_text(&mut app, "ia todo");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(lines(&app), ["a - [ ] task 📅 date"]);
+        type_text(&mut app, "milk");
+        press(&mut app, KeyCode::Tab);
+        type_text(&mut app, "friday");
+        assert_eq!(lines(&app), ["a - [ ] milk 📅 friday"]);
+
+        // Shift-Tab goes back and selects what was typed there.
+        press(&mut app, KeyCode::BackTab);
+        type_text(&mut app, "eggs and bread");
+        assert_eq!(lines(&app), ["a - [ ] eggs and bread 📅 friday"]);
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(cursor(&app), (0, 31));
+
+        // Multi-line bodies become lines of their own, and one u undoes the expansion.
+        press(&mut app, KeyCode::Enter);
+        type_text(&mut app, "code");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(lines(&app)[1..], ["```lang", "", "```"]);
+        press(&mut app, KeyCode::Esc);
+        type_text(&mut app, "u");
+        assert_eq!(lines(&app)[1..], ["code"]);
+        press(&mut app, KeyCode::End);
+        type_text(&mut app, "i");
+        press(&mut app, KeyCode::Tab);
+        type_text(&mut app, "rust");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(lines(&app)[1..], ["```rust", "", "```"]);
+        assert_eq!(cursor(&app), (2, 0));
+
+        // Esc ends the session: the placeholder is no longer selected, and Tab is a Tab.
+        type_text(&mut app, "todo");
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Esc);
+        type_text(&mut app, "iX");
+        press(&mut app, KeyCode::Tab);
+        assert!(
+            lines(&app)[2].starts_with("- [ ] taskX"),
+            "{:?}",
+            lines(&app)
+        );
+        assert_ne!(lines(&app)[2], "- [ ] taskX 📅 date");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn x_r_and_tilde_edit_single_characters() {
+        let (_guard, base, mut app) = test_app("char-edits", &[("chars.md", "äbc\n\nßq\n")]);
+        app.load_file_into_editor(base.join("notes/chars.md"))
+            .unwrap();
+        let lines = |app: &App| app.focused_buffer().unwrap().textarea.lines().to_vec();
+        let cursor = |app: &App| app.focused_buffer().unwrap().textarea.cursor();
+
+        // ~ toggles and advances, staying on the last character; r replaces in place.
+        type_text(&mut app, "~~~");
+        assert_eq!(lines(&app)[0], "ÄBC");
+        assert_eq!(cursor(&app), (0, 2));
+        type_text(&mut app, "rj");
+        assert_eq!(lines(&app)[0], "ÄBj");
+        assert_eq!(cursor(&app), (0, 2));
+        assert!(app.editor_dirty);
+
+        // u undoes one replacement at a time.
+        type_text(&mut app, "u");
+        assert_eq!(lines(&app)[0], "ÄBC");
+        type_text(&mut app, "u");
+        assert_eq!(lines(&app)[0], "ÄBc");
+
+        // x deletes and backs off the end; on an empty line it does nothing, and Esc
+        // cancels a pending r.
+        type_text(&mut app, "x");
+        assert_eq!(lines(&app)[0], "ÄB");
+        assert_eq!(cursor(&app), (0, 1));
+        type_text(&mut app, "jx");
+        assert_eq!(lines(&app), ["ÄB", "", "ßq"]);
+        type_text(&mut app, "r");
+        press(&mut app, KeyCode::Esc);
+        type_text(&mut app, "j");
+        press(&mut app, KeyCode::Home);
+        type_text(&mut app, "~");
+        assert_eq!(lines(&app)[2], "SSq");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn counts_repeat_motions_and_edits() {
+        let lines: Vec<String> = (1..=20)
+            .map(|n| format!("line {n} one two three"))
+            .collect();
+        let (_guard, base, mut app) = test_app("counts", &[("count.md", &lines.join("\n"))]);
+        app.load_file_into_editor(base.join("notes/count.md"))
+            .unwrap();
+        let cursor = |app: &App| app.focused_buffer().unwrap().textarea.cursor();
+        let last_line = |app: &App| app.focused_buffer().unwrap().textarea.lines()[19].clone();
+
+        type_text(&mut app, "1");
+        type_text(&mut app, "0");
+        assert_eq!(app.normal_count, Some(10));
+        type_text(&mut app, "j");
+        assert_eq!(app.normal_count, None);
+        assert_eq!(cursor(&app).0, 10);
+        type_text(&mut app, "3k3w");
+        assert_eq!(cursor(&app), (7, 11));
+        // 0 on its own goes to the start of the line; counts past the end clamp.
+        type_text(&mut app, "0");
+        assert_eq!(cursor(&app), (7, 0));
+        type_text(&mut app, "500j");
+        assert_eq!(cursor(&app).0, 19);
+
+        // Edits take counts too, and Esc drops a pending one.
+        type_text(&mut app, "5x");
+        assert_eq!(last_line(&app), "20 one two three");
+        type_text(&mut app, "3rx");
+        assert_eq!(last_line(&app), "xxxone two three");
+        assert_eq!(cursor(&app), (19, 2));
+        type_text(&mut app, "2~");
+        assert_eq!(last_line(&app), "xxXOne two three");
+        type_text(&mut app, "9");
+        press(&mut app, KeyCode::Esc);
+        type_text(&mut app, "l");
+        assert_eq!(cursor(&app), (19, 5));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn ctrl_o_and_ctrl_i_walk_the_jump_list() {
+        let notes = [("a.md", "one\ntwo\nsee [[b]]\n"), ("b.md", "bee\n")];
+        let (_guard, base, mut app) = test_app("jump-list", &notes);
+        let notes = base.join("notes");
+        let ctrl = |app: &mut App, c| {
+            dispatch_key(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)).unwrap();
+        };
+        app.load_file_into_editor(notes.join("a.md")).unwrap();
+        type_text(&mut app, "jj");
+        app.open_wiki_link("b").unwrap();
+        assert_eq!(app.editing_path(), Some(notes.join("b.md")));
+
+        ctrl(&mut app, 'o');
+        assert_eq!(app.editing_path(), Some(notes.join("a.md")));
+        assert_eq!(app.focused_buffer().unwrap().textarea.cursor(), (2, 0));
+        ctrl(&mut app, 'i');
+        assert_eq!(app.editing_path(), Some(notes.join("b.md")));
+        ctrl(&mut app, 'i');
+        assert_eq!(last_message(&app), "Already at the newest jump");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn soft_wrap_shows_long_lines_on_several_rows() {
+        let words = "lorem ipsum dolor sit amet ".repeat(12);
+        let (_guard, base, mut app) = test_app("soft-wrap", &[("wide.md", &words)]);
+        app.load_file_into_editor(base.join("notes/wide.md"))
+            .unwrap();
+        let screen = |app: &App| {
+            let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 24)).unwrap();
+            terminal.draw(|f| ui::draw(f, app)).unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            (0..buffer.area.height)
+                .map(|y| {
+                    (0..buffer.area.width)
+                        .map(|x| buffer[(x, y)].symbol())
+                        .collect()
+                })
+                .collect::<Vec<String>>()
+        };
+
+        // The editor is the middle pane, between the notes list and the preview.
+        let editor_rows = |rows: &[String]| {
+            rows.iter()
+                .filter(|r| {
+                    r.chars()
+                        .skip(20)
+                        .take(38)
+                        .collect::<String>()
+                        .contains("dolor")
+                })
+                .count()
+        };
+        let rows = screen(&app);
+        assert!(editor_rows(&rows) > 1, "{rows:#?}");
+        assert!(rows.iter().any(|r| r.contains(" wrap ")));
+        let width = usize::from(app.focused_buffer().unwrap().text_width.get());
+        // gj follows the rows the pane shows (a row's last space may hang past the edge); g$
+        // ends on the row's last character.
+        let starts = app.focused_buffer().unwrap().row_starts(0);
+        assert!(starts.len() > 2 && starts.windows(2).all(|w| w[1] - w[0] <= width + 1));
+        type_text(&mut app, "gj");
+        assert_eq!(
+            app.focused_buffer().unwrap().textarea.cursor(),
+            (0, starts[1])
+        );
+        type_text(&mut app, "g$");
+        assert_eq!(
+            app.focused_buffer().unwrap().textarea.cursor(),
+            (0, starts[2] - 1)
+        );
+
+        app.toggle_soft_wrap();
+        let rows = screen(&app);
+        assert_eq!(editor_rows(&rows), 1, "{rows:#?}");
+        assert!(rows.iter().any(|r| r.contains(" nowrap ")));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn statusline_segments_follow_the_configured_order() {
+        let (_guard, base, mut app) = test_app("statusline", &[("sub/idea.md", "one two three\n")]);
+        app.load_file_into_editor(base.join("notes/sub/idea.md"))
+            .unwrap();
+        let footer = |app: &App| {
+            let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 24)).unwrap();
+            terminal.draw(|f| ui::draw(f, app)).unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, 22)].symbol())
+                .collect::<String>()
+        };
+        assert!(
+            footer(&app).ends_with(" NORMAL  1:1/1 wrap │"),
+            "{}",
+            footer(&app)
+        );
+
+        let config_file = base.join("config/oxid/config.toml");
+        let mut config = fs::read_to_string(&config_file).unwrap();
+        config.push_str("[statusline]\n");
+        config.push_str(r#"segments = ["spacer", "file", "clock", "wordcount", "spacer", "mode"]"#);
+        fs::write(&config_file, config).unwrap();
+        app.reload_config().unwrap();
+        assert!(last_message(&app).starts_with("Unknown statusline segment \"clock\""));
+        type_text(&mut app, "x");
+        let row = footer(&app);
+        let file = row
+            .find("sub/idea.md [+] | 3 words")
+            .unwrap_or_else(|| panic!("{row}"));
+        assert!(
+            file > 20
+                && row
+                    .trim_start_matches(['│', ' '])
+                    .starts_with("sub/idea.md")
+        );
+        assert!(row.ends_with(" NORMAL  │"), "{row}");
+        assert!(!row.contains("insert"));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn window_title_names_the_focused_note_and_its_unsaved_edits() {
+        let (_guard, base, mut app) = test_app("window-title", &[("a.md", "a\n"), ("b.md", "b\n")]);
+        assert_eq!(app.window_title().as_deref(), Some("notes - oxid"));
+        app.load_file_into_editor(base.join("notes/a.md")).unwrap();
+        app.load_file_into_editor(base.join("notes/b.md")).unwrap();
+        type_text(&mut app, "x");
+        assert_eq!(app.window_title().as_deref(), Some("*b.md - oxid"));
+
+        app.config.ui.window_title = "{file} in {vault}{dirty}".to_string();
+        app.active_tab = app
+            .buffers
+            .iter()
+            .position(|b| b.display_name() == "a.md")
+            .unwrap();
+        assert_eq!(app.window_title().as_deref(), Some("a.md in notes"));
+        app.config.ui.window_title = " ".to_string();
+        assert_eq!(app.window_title(), None);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn cli_export_writes_html_next_to_the_note_or_into_out() {
+        let (_guard, base, _app) = test_app("cli-export", &[("sub/a.md", "# A\n\n[[b]]\n")]);
+        let out = base.join("site");
+        export_from_cli(Path::new("sub/a.md"), ExportFormat::Html, Some(&out)).unwrap();
+        assert!(fs::read_to_string(out.join("a.html"))
+            .unwrap()
+            .contains("<h1>A</h1>"));
+        let note = base.join("notes/sub/a.md");
+        export_from_cli(&note, ExportFormat::Html, None).unwrap();
+        assert!(base.join("notes/sub/a.html").is_file());
+        let missing = export_from_cli(Path::new("b.md"), ExportFormat::Pdf, None).unwrap_err();
+        assert!(missing.to_string().ends_with("b.md is not a Markdown note"));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn cli_lists_open_tasks_and_tags_of_the_vault() {
+        let notes = [
+            ("a.md", "# A #rust\n- [ ] ship it #doing\n- [x] done\n"),
+            (
+                "sub/b.md",
+                "- [ ] write #rust docs\n```\n- [ ] not a task #code\n```\n",
+            ),
+        ];
+        let (_guard, base, _app) = test_app("cli-listing", &notes);
+        let (config, notes_dir, walk) = walk_vault_from_cli().unwrap();
+        let columns = &config.task_columns;
+        assert_eq!(
+            list_tasks(&walk, &notes_dir, columns, false).unwrap(),
+            "sub/b.md:1: write #rust docs\na.md:2: ship it #doing"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&list_tasks(&walk, &notes_dir, columns, true).unwrap()).unwrap();
+        let task = serde_json::json!({"path": "a.md", "line": 2, "content": "ship it #doing"});
+        assert_eq!(json[1], task);
+
+        assert_eq!(
+            list_tags(&walk, &notes_dir, false).unwrap(),
+            "code\ndoing\nrust"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&list_tags(&walk, &notes_dir, true).unwrap()).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 4);
+        assert_eq!(
+            json[2],
+            serde_json::json!({"tag": "rust", "path": "a.md", "line": 1})
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn vault_replace_scans_in_the_background_and_can_be_undone_in_open_buffers() {
+        let notes = [("a.md", "old text\nmore old\n"), ("b.md", "nothing\n")];
+        let (_guard, base, mut app) = test_app("replace-undo", &notes);
+        app.load_file_into_editor(base.join("notes/a.md")).unwrap();
+        app.enter_replace_prompt();
+        app.replace_pattern.set("old");
+        app.replace_with.set("new");
+        app.start_replace_scan().unwrap();
+        assert_eq!(app.focus, Focus::ReplacePrompt);
+        let started = Instant::now();
+        while app.poll_replace_scan() {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "scan never finished"
+            );
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(app.focus, Focus::ReplaceReview);
+        assert_eq!(app.replace_files.len(), 1);
+        // keys.replace_dry_run switches the dry run on and off again.
+        press(&mut app, KeyCode::Char('d'));
+        assert!(app.replace_dry_run);
+        press(&mut app, KeyCode::Char('d'));
+        assert!(!app.replace_dry_run);
+
+        app.confirm_replace();
+        assert_eq!(last_message(&app), "Replaced 2 lines in 1 files");
+        let text = |app: &App| app.focused_buffer().unwrap().textarea.lines().join("\n");
+        assert_eq!(text(&app), "new text\nmore new");
+        app.focus = Focus::Editor;
+        type_text(&mut app, "u");
+        assert_eq!(text(&app), "old text\nmore old");
+        let _ = fs::remove_dir_all(&base);
+    }
+}
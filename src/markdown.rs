@@ -3,88 +3,431 @@
 
 use crate::theme::ResolvedTheme;
 use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
-use ratatui::style::Modifier;
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
 
-/// Render markdown content to ratatui Lines with theme styling.
-pub fn render_markdown(content: &str, theme: &ResolvedTheme) -> Vec<Line<'static>> {
-    let mut lines = Vec::new();
-    let mut current_line = Vec::new();
+/// Last rendered preview, reused while the source content and the notes it embeds are
+/// unchanged.
+#[derive(Default)]
+pub struct RenderCache {
+    /// Content and `show_link_urls` the cached render is for; `None` once invalidated.
+    source: Option<(String, bool)>,
+    /// Name and stamp of each note the cached render embeds.
+    embeds: Vec<(String, EmbedStamp)>,
+    lines: Vec<Line<'static>>,
+    source_map: Vec<usize>,
+}
+
+impl RenderCache {
+    /// Rendered lines and source map for `content`, re-rendering only when it or a note it
+    /// embeds changed. `embed` tells where the note an `![[embed]]` names is; embedded files
+    /// are only read when re-rendering, and are checked by modification time otherwise.
+    pub fn render(
+        &mut self,
+        content: &str,
+        theme: &ResolvedTheme,
+        show_link_urls: bool,
+        embed: &dyn Fn(&str) -> Option<EmbedSource>,
+    ) -> (&[Line<'static>], &[usize]) {
+        // Compared rather than hashed, so an unchanged frame costs a memcmp.
+        let fresh = self
+            .source
+            .as_ref()
+            .is_some_and(|(cached, urls)| cached == content && *urls == show_link_urls)
+            && self
+                .embeds
+                .iter()
+                .all(|(name, stamp)| EmbedStamp::of(embed(name).as_ref()) == *stamp);
+        if !fresh {
+            let mut embeds = Vec::new();
+            let note = Obsidian::prepare(content, &mut |name| {
+                let source = embed(name);
+                embeds.push((name.to_string(), EmbedStamp::of(source.as_ref())));
+                match source? {
+                    EmbedSource::Buffer(text) => Some(text),
+                    EmbedSource::File(path) => fs::read_to_string(path).ok(),
+                }
+            });
+            let (lines, source_map) = note.render(theme, show_link_urls);
+            self.lines = lines;
+            self.source_map = source_map;
+            self.embeds = embeds;
+            self.source = Some((content.to_string(), show_link_urls));
+        }
+        (&self.lines, &self.source_map)
+    }
+
+    /// Drop the cached render (e.g. after the theme changed).
+    pub fn invalidate(&mut self) {
+        self.source = None;
+    }
+}
+
+/// Where the note an `![[embed]]` names is read from.
+pub enum EmbedSource {
+    /// The text of the note's open buffer.
+    Buffer(String),
+    /// The note's file.
+    File(PathBuf),
+}
+
+/// What an embedded note is compared by to tell whether a cached render is stale.
+#[derive(PartialEq)]
+enum EmbedStamp {
+    Missing,
+    Buffer(String),
+    File(Option<SystemTime>),
+}
+
+impl EmbedStamp {
+    fn of(source: Option<&EmbedSource>) -> Self {
+        match source {
+            None => Self::Missing,
+            Some(EmbedSource::Buffer(text)) => Self::Buffer(text.clone()),
+            Some(EmbedSource::File(path)) => {
+                Self::File(fs::metadata(path).and_then(|m| m.modified()).ok())
+            }
+        }
+    }
+}
+
+/// A note with the Obsidian syntax the preview understands taken out before it is parsed as
+/// markdown: `%%comments%%` are removed, `> [!type] Title` callout lines become a bold
+/// title (the type is shown as a badge in front of it), and lines that are only an
+/// `![[embed]]` are emptied, the embedded note being rendered there afterwards. Line numbers
+/// stay as they are in the note.
+struct Obsidian {
+    text: String,
+    /// Callout type by source line, e.g. "NOTE".
+    callouts: HashMap<usize, String>,
+    /// Source line, link target and content of each embedded note.
+    embeds: Vec<(usize, String, String)>,
+}
+
+impl Obsidian {
+    fn prepare(content: &str, embed: &mut dyn FnMut(&str) -> Option<String>) -> Self {
+        let mut note = Self {
+            text: String::with_capacity(content.len()),
+            callouts: HashMap::new(),
+            embeds: Vec::new(),
+        };
+        let mut fence: Option<&str> = None;
+        let mut in_comment = false;
+        for (row, line) in content.split('\n').enumerate() {
+            if row > 0 {
+                note.text.push('\n');
+            }
+            let trimmed = line.trim_start();
+            if !in_comment {
+                if let Some(open) = fence {
+                    if trimmed.starts_with(open) {
+                        fence = None;
+                    }
+                    note.text.push_str(line);
+                    continue;
+                }
+                if let Some(open) = ["```", "~~~"].into_iter().find(|f| trimmed.starts_with(f)) {
+                    fence = Some(open);
+                    note.text.push_str(line);
+                    continue;
+                }
+            }
+            let line = strip_comments(line, &mut in_comment);
+            if let Some((target, content)) =
+                embed_target(&line).and_then(|target| Some((target, embedded(target, embed)?)))
+            {
+                note.embeds.push((row, target.to_string(), content));
+            } else if let Some((prefix, kind, title)) = callout(&line) {
+                note.callouts.insert(row, kind.to_uppercase());
+                note.text.push_str(&format!("{prefix} **{title}**"));
+            } else {
+                note.text.push_str(&line);
+            }
+        }
+        note
+    }
+
+    fn render(
+        &self,
+        theme: &ResolvedTheme,
+        show_link_urls: bool,
+    ) -> (Vec<Line<'static>>, Vec<usize>) {
+        let (mut lines, mut source_map) =
+            render_markdown(&self.text, theme, show_link_urls, self.callouts.clone());
+        for (row, target, content) in self.embeds.iter().rev() {
+            let at = source_map.partition_point(|&s| s < *row);
+            // Embeds inside an embedded note are left as they are, so embeds cannot loop.
+            let (embedded, _) = Self::prepare(content, &mut |_| None).render(theme, show_link_urls);
+            let bar = || Span::styled("▎ ", theme.md_blockquote_style);
+            let title = Line::from(vec![
+                bar(),
+                Span::styled(
+                    format!("↳ {target}"),
+                    theme.md_blockquote_style.add_modifier(Modifier::BOLD),
+                ),
+            ]);
+            let inlined = std::iter::once(title).chain(embedded.into_iter().map(|line| {
+                let mut spans = vec![bar()];
+                spans.extend(line.spans);
+                Line::from(spans)
+            }));
+            let inlined: Vec<Line<'static>> = inlined.collect();
+            source_map.splice(at..at, std::iter::repeat_n(*row, inlined.len()));
+            lines.splice(at..at, inlined);
+        }
+        (lines, source_map)
+    }
+}
+
+/// `line` without its `%%comments%%`; `in_comment` carries a comment over to the next line.
+fn strip_comments(line: &str, in_comment: &mut bool) -> String {
+    let mut out = String::new();
+    for (i, part) in line.split("%%").enumerate() {
+        if i > 0 {
+            *in_comment = !*in_comment;
+        }
+        if !*in_comment {
+            out.push_str(part);
+        }
+    }
+    out
+}
+
+/// Target of a line that holds nothing but an embed, e.g. `note#Heading` for
+/// `![[note#Heading|shown]]`.
+fn embed_target(line: &str) -> Option<&str> {
+    let inner = line.trim().strip_prefix("![[")?.strip_suffix("]]")?;
+    if inner.contains("]]") {
+        return None;
+    }
+    let target = inner.split('|').next().unwrap_or(inner).trim();
+    (!target.is_empty()).then_some(target)
+}
+
+/// Content `target` embeds: the whole note, or the section under `#heading`.
+fn embedded(target: &str, embed: &mut dyn FnMut(&str) -> Option<String>) -> Option<String> {
+    let (name, heading) = match target.split_once('#') {
+        Some((name, heading)) => (name.trim(), Some(heading.trim())),
+        None => (target, None),
+    };
+    if name.is_empty() {
+        return None;
+    }
+    let content = embed(name)?;
+    match heading {
+        Some(heading) => section(&content, heading),
+        None => Some(content),
+    }
+}
+
+/// The heading named `heading` and what follows it, up to the next heading of the same or a
+/// higher level.
+fn section(content: &str, heading: &str) -> Option<String> {
+    let level = |line: &str| {
+        let hashes = line.len() - line.trim_start_matches('#').len();
+        (1..=6)
+            .contains(&hashes)
+            .then_some(hashes)
+            .filter(|&n| line[n..].starts_with(' '))
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines
+        .iter()
+        .position(|l| level(l).is_some_and(|n| l[n..].trim().eq_ignore_ascii_case(heading)))?;
+    let depth = level(lines[start])?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|l| level(l).is_some_and(|n| n <= depth))
+        .map_or(lines.len(), |n| start + 1 + n);
+    Some(lines[start..end].join("\n"))
+}
+
+/// Quote markers, type and title of an Obsidian callout's first line, e.g. (">", "note",
+/// "Title") for `> [!note]- Title`. Without a title the type is used, capitalized.
+fn callout(line: &str) -> Option<(&str, &str, String)> {
+    let rest = line.trim_start_matches(|c: char| c == '>' || c.is_whitespace());
+    let prefix = line[..line.len() - rest.len()].trim_end();
+    if !prefix.contains('>') {
+        return None;
+    }
+    let (kind, title) = rest.strip_prefix("[!")?.split_once(']')?;
+    if kind.is_empty()
+        || !kind
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+    let title = title.trim_start_matches(['+', '-']).trim();
+    let title = if title.is_empty() {
+        let mut chars = kind.chars();
+        chars
+            .next()
+            .map_or(String::new(), |c| c.to_uppercase().chain(chars).collect())
+    } else {
+        title.to_string()
+    };
+    Some((prefix, kind, title))
+}
+
+/// Render markdown content to ratatui Lines with theme styling, putting a badge in front
+/// of the callout titles in `callouts` (see `Obsidian`). Also returns, for each rendered
+/// line, the (0-based) source line it started on; the mapping is approximate, as a rendered
+/// line maps to the source line of its first span.
+fn render_markdown(
+    content: &str,
+    theme: &ResolvedTheme,
+    show_link_urls: bool,
+    mut callouts: HashMap<usize, String>,
+) -> (Vec<Line<'static>>, Vec<usize>) {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let source_line = |offset: usize| {
+        line_starts
+            .partition_point(|&s| s <= offset)
+            .saturating_sub(1)
+    };
+
+    let mut out = LineBuilder::new(theme.md_blockquote_style);
     let mut block_stack: Vec<BlockStyle> = vec![BlockStyle::Paragraph];
-    let mut list_item_counter: Option<u64> = None;
-    let mut list_item_prefix = "• ".to_string();
+    // One entry per open list: the next item number, or None for bullet lists.
+    let mut list_counters: Vec<Option<u64>> = Vec::new();
+    // Marker for the current list item, emitted before its first span.
+    let mut pending_prefix: Option<String> = None;
     let mut task_list_checked: Option<bool> = None;
+    // Footnote numbers by label, in the order they are first referenced, and the rendered
+    // definitions. While a definition is rendered, `out` is its builder and `body` the
+    // note's; definitions inside definitions are flattened into the outer one.
+    let mut footnotes: HashMap<String, usize> = HashMap::new();
+    let mut definitions: Vec<(String, LineBuilder)> = Vec::new();
+    let mut body: Option<(String, LineBuilder)> = None;
+    let mut definition_depth = 0;
 
     let mut opts = Options::empty();
     opts.insert(Options::ENABLE_TASKLISTS);
-    let parser = Parser::new_ext(content, opts);
+    opts.insert(Options::ENABLE_STRIKETHROUGH);
+    opts.insert(Options::ENABLE_FOOTNOTES);
+    let parser = Parser::new_ext(content, opts).into_offset_iter();
 
-    for event in parser {
+    for (event, range) in parser {
+        if out.current.is_empty() {
+            out.source = source_line(range.start);
+        }
         match event {
             Event::Start(tag) => match tag {
-                Tag::Heading(_, _, _) => {
-                    flush_line(&mut current_line, &mut lines);
-                    block_stack.push(BlockStyle::Heading);
+                Tag::Heading(level, _, _) => {
+                    out.flush();
+                    block_stack.push(BlockStyle::Heading(level as usize));
                 }
                 Tag::CodeBlock(CodeBlockKind::Fenced(_) | CodeBlockKind::Indented) => {
-                    flush_line(&mut current_line, &mut lines);
+                    out.flush();
                     block_stack.push(BlockStyle::CodeBlock);
                 }
+                Tag::BlockQuote => {
+                    out.flush();
+                    out.quote_depth += 1;
+                    block_stack.push(BlockStyle::BlockQuote);
+                }
                 Tag::List(numbering) => {
-                    flush_line(&mut current_line, &mut lines);
-                    list_item_counter = numbering;
+                    out.flush();
+                    list_counters.push(numbering);
                     block_stack.push(BlockStyle::List);
                 }
                 Tag::Item => {
-                    if !current_line.is_empty() {
-                        flush_line(&mut current_line, &mut lines);
+                    if !out.current.is_empty() {
+                        out.flush();
                     }
                     task_list_checked = None;
-                    list_item_prefix = match list_item_counter {
-                        Some(n) => {
-                            let prefix = format!("{}. ", n);
-                            list_item_counter = Some(n + 1);
-                            prefix
+                    let depth = list_counters.len().max(1);
+                    let marker = match list_counters.last_mut() {
+                        Some(Some(n)) => {
+                            let marker = format!("{}. ", n);
+                            *n += 1;
+                            marker
                         }
-                        None => "• ".to_string(),
+                        _ => format!("{} ", BULLETS[(depth - 1) % BULLETS.len()]),
                     };
+                    pending_prefix = Some(format!("{}{}", "  ".repeat(depth - 1), marker));
                     block_stack.push(BlockStyle::ListItem);
                 }
                 Tag::Paragraph => {
                     if !matches!(block_stack.last(), Some(BlockStyle::ListItem)) {
-                        flush_line(&mut current_line, &mut lines);
+                        out.flush();
                     }
                     block_stack.push(BlockStyle::Paragraph);
                 }
                 Tag::Strong | Tag::Emphasis => {
                     block_stack.push(BlockStyle::Bold);
                 }
+                Tag::Strikethrough => {
+                    block_stack.push(BlockStyle::Strikethrough);
+                }
+                Tag::Link(_, _, _) => {
+                    block_stack.push(BlockStyle::Link);
+                }
+                Tag::FootnoteDefinition(label) => {
+                    out.flush();
+                    if definition_depth == 0 {
+                        let definition = LineBuilder::new(theme.md_blockquote_style);
+                        body = Some((label.to_string(), std::mem::replace(&mut out, definition)));
+                    }
+                    definition_depth += 1;
+                }
                 _ => {}
             },
             Event::End(tag) => match tag {
-                Tag::Heading(_, _, _) | Tag::CodeBlock(_) | Tag::List(_) | Tag::Paragraph => {
-                    flush_line(&mut current_line, &mut lines);
+                Tag::Heading(_, _, _) | Tag::CodeBlock(_) | Tag::Paragraph => {
+                    out.flush();
+                    let _ = block_stack.pop();
+                }
+                Tag::List(_) => {
+                    out.flush();
+                    let _ = list_counters.pop();
+                    let _ = block_stack.pop();
+                }
+                Tag::BlockQuote => {
+                    out.flush();
+                    out.quote_depth = out.quote_depth.saturating_sub(1);
                     let _ = block_stack.pop();
                 }
                 Tag::Item => {
-                    flush_line(&mut current_line, &mut lines);
+                    out.flush();
                     task_list_checked = None;
                     let _ = block_stack.pop();
                 }
-                Tag::Strong | Tag::Emphasis => {
+                Tag::Strong | Tag::Emphasis | Tag::Strikethrough => {
                     let _ = block_stack.pop();
                 }
+                Tag::FootnoteDefinition(_) => {
+                    out.flush();
+                    definition_depth -= 1;
+                    if definition_depth == 0 {
+                        if let Some((label, note)) = body.take() {
+                            definitions.push((label, std::mem::replace(&mut out, note)));
+                        }
+                    }
+                }
+                Tag::Link(_, dest, _) => {
+                    let _ = block_stack.pop();
+                    if show_link_urls && !dest.is_empty() {
+                        out.current.push(Span::styled(
+                            format!(" ({})", dest),
+                            theme.preview_text_style.add_modifier(Modifier::DIM),
+                        ));
+                    }
+                }
                 _ => {}
             },
             Event::TaskListMarker(checked) => {
                 task_list_checked = Some(checked);
-                if matches!(block_stack.last(), Some(BlockStyle::ListItem))
-                    && current_line.is_empty()
-                {
-                    current_line.push(Span::styled(
-                        list_item_prefix.clone(),
-                        theme.md_list_marker_style,
-                    ));
+                if let Some(prefix) = pending_prefix.take() {
+                    out.current
+                        .push(Span::styled(prefix, theme.md_list_marker_style));
                 }
                 let marker = if checked { "[x] " } else { "[ ] " };
                 let style = if checked {
@@ -94,9 +437,20 @@ pub fn render_markdown(content: &str, theme: &ResolvedTheme) -> Vec<Line<'static
                 } else {
                     theme.editor_checkbox_style.patch(theme.preview_text_style)
                 };
-                current_line.push(Span::styled(marker.to_string(), style));
+                out.current.push(Span::styled(marker.to_string(), style));
             }
             Event::Text(text) => {
+                if out.current.is_empty() {
+                    if let Some(kind) = callouts.remove(&out.source) {
+                        out.current.push(Span::styled(
+                            format!(" {kind} "),
+                            theme
+                                .md_blockquote_style
+                                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+                        ));
+                        out.current.push(Span::raw(" "));
+                    }
+                }
                 let base_style = block_style(&block_stack, theme);
                 let style = if let Some(checked) = task_list_checked {
                     if checked {
@@ -107,74 +461,352 @@ pub fn render_markdown(content: &str, theme: &ResolvedTheme) -> Vec<Line<'static
                 } else {
                     base_style
                 };
-                let prefix = if matches!(block_stack.last(), Some(BlockStyle::ListItem))
-                    && current_line.is_empty()
-                {
-                    list_item_prefix.clone()
-                } else {
-                    String::new()
-                };
-                if !prefix.is_empty() {
-                    current_line.push(Span::styled(prefix, theme.md_list_marker_style));
+                if let Some(prefix) = pending_prefix.take() {
+                    out.current
+                        .push(Span::styled(prefix, theme.md_list_marker_style));
                 }
-                current_line.push(Span::styled(text.to_string(), style));
+                out.current.push(Span::styled(text.to_string(), style));
                 task_list_checked = None;
             }
+            Event::FootnoteReference(label) => {
+                if let Some(prefix) = pending_prefix.take() {
+                    out.current
+                        .push(Span::styled(prefix, theme.md_list_marker_style));
+                }
+                let next = footnotes.len() + 1;
+                let number = *footnotes.entry(label.to_string()).or_insert(next);
+                out.current.push(Span::styled(
+                    format!("[{number}]"),
+                    theme.md_list_marker_style,
+                ));
+            }
             Event::Code(text) => {
+                if let Some(prefix) = pending_prefix.take() {
+                    out.current
+                        .push(Span::styled(prefix, theme.md_list_marker_style));
+                }
                 let style = theme.preview_text_style.patch(theme.md_code_bg_style);
-                current_line.push(Span::styled(text.to_string(), style));
+                out.current.push(Span::styled(text.to_string(), style));
             }
             Event::SoftBreak | Event::HardBreak => {
-                flush_line(&mut current_line, &mut lines);
+                out.flush();
             }
             Event::Rule => {
-                flush_line(&mut current_line, &mut lines);
-                lines.push(Line::from(Span::styled(
-                    "─".repeat(20),
-                    theme.preview_text_style,
-                )));
+                out.flush();
+                out.source = source_line(range.start);
+                out.current
+                    .push(Span::styled("─".repeat(20), theme.preview_text_style));
+                out.flush();
             }
             _ => {}
         }
     }
 
-    flush_line(&mut current_line, &mut lines);
+    out.flush();
+    append_footnotes(&mut out, definitions, &mut footnotes, theme);
+
+    if out.lines.is_empty() {
+        out.lines
+            .push(Line::from(Span::styled("", theme.preview_text_style)));
+        out.source_map.push(0);
+    }
+
+    (out.lines, out.source_map)
+}
 
-    if lines.is_empty() {
-        lines.push(Line::from(Span::styled("", theme.preview_text_style)));
+/// Add the footnote definitions below a separator, numbered as they were referenced;
+/// definitions nothing refers to come last. Their lines map to the source line of the
+/// definition, but never to one before the note's last line so the map stays sorted.
+fn append_footnotes(
+    out: &mut LineBuilder,
+    definitions: Vec<(String, LineBuilder)>,
+    footnotes: &mut HashMap<String, usize>,
+    theme: &ResolvedTheme,
+) {
+    if definitions.is_empty() {
+        return;
+    }
+    let mut numbered: Vec<(usize, LineBuilder)> = definitions
+        .into_iter()
+        .map(|(label, definition)| {
+            let next = footnotes.len() + 1;
+            (*footnotes.entry(label).or_insert(next), definition)
+        })
+        .collect();
+    numbered.sort_by_key(|(number, _)| *number);
+    let mut last = out.source_map.last().copied().unwrap_or(0);
+    out.lines.push(Line::from(Span::styled(
+        "─".repeat(20),
+        theme.preview_text_style,
+    )));
+    out.source_map.push(last);
+    for (number, definition) in numbered {
+        let marker = Span::styled(format!("[{number}] "), theme.md_list_marker_style);
+        let mut lines = definition.lines.into_iter().zip(definition.source_map);
+        let (first, source) = lines
+            .next()
+            .unwrap_or_else(|| (Line::default(), definition.source));
+        let mut spans = vec![marker];
+        spans.extend(first.spans);
+        last = last.max(source);
+        out.lines.push(Line::from(spans));
+        out.source_map.push(last);
+        for (line, source) in lines {
+            last = last.max(source);
+            out.lines.push(line);
+            out.source_map.push(last);
+        }
     }
+}
 
-    lines
+/// Accumulates spans into lines, recording each line's source line and prefixing
+/// blockquote bars.
+struct LineBuilder {
+    lines: Vec<Line<'static>>,
+    source_map: Vec<usize>,
+    current: Vec<Span<'static>>,
+    /// Source line of the first span in `current`.
+    source: usize,
+    quote_depth: usize,
+    quote_style: Style,
 }
 
+impl LineBuilder {
+    fn new(quote_style: Style) -> Self {
+        Self {
+            lines: Vec::new(),
+            source_map: Vec::new(),
+            current: Vec::new(),
+            source: 0,
+            quote_depth: 0,
+            quote_style,
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+        let mut spans = std::mem::take(&mut self.current);
+        if self.quote_depth > 0 {
+            spans.insert(
+                0,
+                Span::styled("▎ ".repeat(self.quote_depth), self.quote_style),
+            );
+        }
+        self.lines.push(Line::from(spans));
+        self.source_map.push(self.source);
+    }
+}
+
+/// Bullet markers by nesting depth, cycling for deeper lists.
+const BULLETS: [char; 3] = ['•', '◦', '▪'];
+
 #[derive(Clone, Copy, PartialEq)]
 enum BlockStyle {
     Paragraph,
-    Heading,
+    /// Heading level, 1–6.
+    Heading(usize),
     CodeBlock,
+    BlockQuote,
     List,
     ListItem,
     Bold,
+    Strikethrough,
+    Link,
 }
 
-fn block_style(stack: &[BlockStyle], theme: &ResolvedTheme) -> ratatui::style::Style {
+fn block_style(stack: &[BlockStyle], theme: &ResolvedTheme) -> Style {
+    let mut style = theme.preview_text_style;
     for s in stack.iter().rev() {
         match s {
-            BlockStyle::Heading => return theme.md_header_fg_style,
+            BlockStyle::Heading(level) => {
+                style = theme.md_heading_styles[(*level).clamp(1, 6) - 1];
+                break;
+            }
             BlockStyle::CodeBlock => {
-                return theme.preview_text_style.patch(theme.md_code_bg_style);
+                style = theme.preview_text_style.patch(theme.md_code_bg_style);
+                break;
             }
             BlockStyle::Bold => {
-                return theme.preview_text_style.add_modifier(Modifier::BOLD);
+                style = theme.preview_text_style.add_modifier(Modifier::BOLD);
+                break;
             }
             _ => {}
         }
     }
-    theme.preview_text_style
+    // Inline decorations and quoting combine with whatever base style applies.
+    for s in stack {
+        match s {
+            BlockStyle::BlockQuote => style = style.patch(theme.md_blockquote_style),
+            BlockStyle::Strikethrough => style = style.add_modifier(Modifier::CROSSED_OUT),
+            BlockStyle::Link => style = style.add_modifier(Modifier::UNDERLINED),
+            _ => {}
+        }
+    }
+    style
 }
 
-fn flush_line(spans: &mut Vec<Span<'static>>, lines: &mut Vec<Line<'static>>) {
-    if !spans.is_empty() {
-        lines.push(Line::from(std::mem::take(spans)));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::{ColorMode, Theme};
+
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn renders_obsidian_embeds_callouts_and_comments() {
+        let theme = ResolvedTheme::resolve(&Theme::default(), None, ColorMode::TrueColor).unwrap();
+        let note = "# Top %%hidden%%\n\n> [!tip]- \n> Body\n\n![[other#Part|shown]]\n\n%%\ngone\n%%\nend\n```\n%%kept%%\n```";
+        let mut embed = |name: &str| {
+            (name == "other").then(|| "intro\n## Part\npart ![[other]]\n## Next\nno".to_string())
+        };
+        let (lines, source_map) = Obsidian::prepare(note, &mut embed).render(&theme, false);
+        assert_eq!(
+            text(&lines),
+            [
+                "Top",
+                "▎  TIP  Tip",
+                "▎ Body",
+                "▎ ↳ other#Part",
+                "▎ Part",
+                "▎ part ![[other]]",
+                "end",
+                "%%kept%%\n",
+            ]
+        );
+        assert_eq!(source_map, [0, 2, 3, 5, 5, 5, 10, 12]);
+
+        let (lines, _) = Obsidian::prepare("![[missing]]", &mut embed).render(&theme, false);
+        assert_eq!(text(&lines), ["![[missing]]"]);
+    }
+
+    #[test]
+    fn cached_render_reads_embedded_files_only_when_their_mtime_changes() {
+        let theme = ResolvedTheme::resolve(&Theme::default(), None, ColorMode::TrueColor).unwrap();
+        let dir = std::env::temp_dir().join(format!("oxid-embed-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("other.md");
+        fs::write(&path, "first").unwrap();
+        let embed = |name: &str| (name == "other").then(|| EmbedSource::File(path.clone()));
+        let mut cache = RenderCache::default();
+        let render =
+            |cache: &mut RenderCache| text(cache.render("![[other]]", &theme, false, &embed).0);
+        assert_eq!(render(&mut cache), ["▎ ↳ other", "▎ first", ""]);
+
+        // Same modification time: the cached render is kept and the file is not read.
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        fs::write(&path, "second").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(render(&mut cache), ["▎ ↳ other", "▎ first", ""]);
+
+        let later = modified + std::time::Duration::from_secs(1);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(render(&mut cache), ["▎ ↳ other", "▎ second", ""]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// `cargo test --release -- --ignored --nocapture render_cache_benchmark`
+    #[test]
+    #[ignore]
+    fn render_cache_benchmark() {
+        use std::time::Instant;
+        let theme = ResolvedTheme::resolve(&Theme::default(), None, ColorMode::TrueColor).unwrap();
+        let note: String = (0..5_000)
+            .map(|i| match i % 5 {
+                0 => format!("## Section {i}\n"),
+                1 => format!("- item **{i}** with [a link](https://example.com/{i})\n"),
+                _ => format!("Some *text* on line {i}, with `code` and [[note-{i}]].\n"),
+            })
+            .collect();
+        let embed = |_: &str| None;
+        let time = |label: &str, run: &mut dyn FnMut()| {
+            let start = Instant::now();
+            for _ in 0..10 {
+                run();
+            }
+            println!("{label}: {:?} per frame", start.elapsed() / 10);
+        };
+
+        time("uncached render of a 5k-line note", &mut || {
+            let mut cache = RenderCache::default();
+            cache.render(&note, &theme, false, &embed);
+        });
+        let mut cache = RenderCache::default();
+        cache.render(&note, &theme, false, &embed);
+        time("cached render of a 5k-line note", &mut || {
+            cache.render(&note, &theme, false, &embed);
+        });
+    }
+
+    #[test]
+    fn nested_lists_mix_bullets_and_numbers() {
+        let theme = ResolvedTheme::resolve(&Theme::default(), None, ColorMode::TrueColor).unwrap();
+        let note = "\
+1. first
+   - bullet
+     1. inner one
+     2. inner two
+   - bullet two
+2. second
+3. third
+   1. sub
+
+- loose
+  1. ordered under a bullet
+     - deepest
+  2. resumes";
+        let (lines, source_map) = render_markdown(note, &theme, false, HashMap::new());
+        assert_eq!(
+            text(&lines),
+            [
+                "1. first",
+                "  ◦ bullet",
+                "    1. inner one",
+                "    2. inner two",
+                "  ◦ bullet two",
+                "2. second",
+                "3. third",
+                "  1. sub",
+                "• loose",
+                "  1. ordered under a bullet",
+                "    ▪ deepest",
+                "  2. resumes",
+            ]
+        );
+        assert_eq!(source_map, [0, 1, 2, 3, 4, 5, 6, 7, 9, 10, 11, 12]);
+    }
+
+    #[test]
+    fn footnotes_are_numbered_and_listed_at_the_end() {
+        let theme = ResolvedTheme::resolve(&Theme::default(), None, ColorMode::TrueColor).unwrap();
+        let note = "[^a]: Unused.\n\nFirst[^b] and second[^c].\n\n[^b]: Bee, see[^c].\n\nEnd";
+        let (lines, source_map) = render_markdown(note, &theme, false, HashMap::new());
+        assert_eq!(
+            text(&lines),
+            [
+                "First[1] and second[2].",
+                "End",
+                "────────────────────",
+                "[1] Bee, see[2].",
+                "[3] Unused.",
+            ]
+        );
+        assert_eq!(source_map, [2, 6, 6, 6, 6]);
     }
 }
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Recently opened files (MRU), persisted to the XDG data directory
+
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of paths kept in the MRU list.
+const MAX_RECENT: usize = 100;
+
+/// Most-recently-used list of opened notes, newest first.
+#[derive(Debug, Default)]
+pub struct RecentFiles {
+    pub paths: Vec<PathBuf>,
+    file: Option<PathBuf>,
+}
+
+impl RecentFiles {
+    /// Load ~/.local/share/oxid/recent.json, dropping paths that no longer exist.
+    /// A missing or unreadable file yields an empty list.
+    pub fn load() -> Self {
+        Self::load_from(recent_file_path())
+    }
+
+    fn load_from(file: Option<PathBuf>) -> Self {
+        let paths = file
+            .as_ref()
+            .and_then(|f| fs::read_to_string(f).ok())
+            .and_then(|content| serde_json::from_str::<Vec<PathBuf>>(&content).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| p.exists())
+            .take(MAX_RECENT)
+            .collect();
+        Self { paths, file }
+    }
+
+    /// Move `path` to the front of the list and persist it.
+    pub fn record(&mut self, path: &Path) -> Result<()> {
+        self.paths.retain(|p| p != path);
+        self.paths.insert(0, path.to_path_buf());
+        self.paths.truncate(MAX_RECENT);
+        self.save()
+    }
+
+    /// Position of `path` in the list (0 = most recent), if present.
+    pub fn rank(&self, path: &Path) -> Option<usize> {
+        self.paths.iter().position(|p| p == path)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create data directory: {}", dir.display()))?;
+        }
+        let content = serde_json::to_string_pretty(&self.paths)?;
+        fs::write(file, content)
+            .with_context(|| format!("Failed to write recent files: {}", file.display()))
+    }
+}
+
+fn recent_file_path() -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "oxid")?;
+    Some(dirs.data_dir().join("recent.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newest_first_without_duplicates_and_capped() {
+        let dir = std::env::temp_dir().join(format!("oxid-recent-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let file = dir.join("data/recent.json");
+        let mut recent = RecentFiles {
+            paths: Vec::new(),
+            file: Some(file.clone()),
+        };
+        let note = |i: usize| dir.join(format!("{i}.md"));
+        for i in 0..MAX_RECENT + 5 {
+            recent.record(&note(i)).unwrap();
+        }
+        assert_eq!(recent.paths.len(), MAX_RECENT);
+        assert_eq!(recent.rank(&note(MAX_RECENT + 4)), Some(0));
+        assert_eq!(recent.rank(&note(4)), None);
+
+        // Opening a listed note again moves it to the front instead of adding it twice.
+        recent.record(&note(10)).unwrap();
+        assert_eq!(recent.paths.len(), MAX_RECENT);
+        assert_eq!(recent.rank(&note(10)), Some(0));
+        assert_eq!(recent.rank(&note(MAX_RECENT + 4)), Some(1));
+
+        // Loading drops the notes that are gone and keeps the order of the rest.
+        for i in [10, 50] {
+            fs::write(note(i), "").unwrap();
+        }
+        let loaded = RecentFiles::load_from(Some(file));
+        assert_eq!(loaded.paths, [note(10), note(50)]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
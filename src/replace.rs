@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Vault-wide find and replace
+
+use crate::vault::{self, Ignore, Walk};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// One matching line and its proposed rewrite.
+#[derive(Debug, Clone)]
+pub struct ReplaceLine {
+    /// 0-based line number.
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+    pub enabled: bool,
+}
+
+/// All matching lines in one file.
+#[derive(Debug, Clone)]
+pub struct ReplaceFile {
+    pub path: PathBuf,
+    pub lines: Vec<ReplaceLine>,
+    pub enabled: bool,
+}
+
+impl ReplaceFile {
+    /// Lines that will be rewritten (none when the whole file is toggled off).
+    pub fn enabled_lines(&self) -> impl Iterator<Item = &ReplaceLine> {
+        self.lines.iter().filter(move |l| self.enabled && l.enabled)
+    }
+}
+
+/// Find every line containing `pattern` (literal, case-sensitive) in the markdown files
+/// of a vault walk. Unreadable files are skipped.
+pub fn scan_vault(walk: &Walk, pattern: &str, replacement: &str) -> Vec<ReplaceFile> {
+    let mut files = Vec::new();
+    if pattern.is_empty() {
+        return files;
+    }
+    for path in walk.markdown_files() {
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        let lines: Vec<ReplaceLine> = content
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| l.contains(pattern))
+            .map(|(i, l)| ReplaceLine {
+                line: i,
+                before: l.to_string(),
+                after: l.replace(pattern, replacement),
+                enabled: true,
+            })
+            .collect();
+        if !lines.is_empty() {
+            files.push(ReplaceFile {
+                path: path.to_path_buf(),
+                lines,
+                enabled: true,
+            });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+/// A vault scan for the replace review, run on a background thread.
+pub struct ReplaceScan {
+    /// Matching files and the walk's warnings.
+    result: Receiver<Result<(Vec<ReplaceFile>, Vec<String>)>>,
+}
+
+impl ReplaceScan {
+    /// Walk `root` and collect the lines containing `pattern`, as `scan_vault` does.
+    pub fn spawn(root: PathBuf, ignore: Ignore, pattern: String, replacement: String) -> Self {
+        let (tx, result) = mpsc::channel();
+        thread::spawn(move || {
+            let walk = vault::walk(&root, &ignore);
+            let files = scan_vault(&walk, &pattern, &replacement);
+            let _ = tx.send(Ok((files, walk.warnings)));
+        });
+        Self { result }
+    }
+
+    /// The scan's result once it is done.
+    pub fn poll(&self) -> Option<Result<(Vec<ReplaceFile>, Vec<String>)>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err(anyhow::anyhow!("Replace scan stopped unexpectedly")))
+            }
+        }
+    }
+}
+
+/// Rewrite the enabled lines of `file` on disk, keeping line endings. Fails without writing
+/// if any of those lines changed since the scan. The new content is written to a temporary
+/// file next to the original and renamed over it, so a failed write never leaves the note
+/// half-written. Returns the number of lines rewritten.
+pub fn apply_file(file: &ReplaceFile) -> Result<usize> {
+    let content = fs::read_to_string(&file.path)
+        .with_context(|| format!("Failed to read {}", file.path.display()))?;
+    let mut pieces: Vec<String> = content.split_inclusive('\n').map(str::to_string).collect();
+    let mut count = 0;
+    for change in file.enabled_lines() {
+        let Some(piece) = pieces.get_mut(change.line) else {
+            bail!("{} changed since the scan", file.path.display());
+        };
+        let body = piece.trim_end_matches('\n').trim_end_matches('\r');
+        if body != change.before {
+            bail!("{} changed since the scan", file.path.display());
+        }
+        let ending = piece[body.len()..].to_string();
+        *piece = format!("{}{ending}", change.after);
+        count += 1;
+    }
+    if count == 0 {
+        return Ok(0);
+    }
+
+    let name = file
+        .path
+        .file_name()
+        .context("Replace target has no file name")?
+        .to_string_lossy();
+    let tmp = file.path.with_file_name(format!(".{name}.oxid-tmp"));
+    // The rename swaps in a new inode, so it takes the note's mode along.
+    let permissions = fs::metadata(&file.path)
+        .with_context(|| format!("Failed to read {}", file.path.display()))?
+        .permissions();
+    fs::write(&tmp, pieces.concat())
+        .and_then(|()| fs::set_permissions(&tmp, permissions))
+        .with_context(|| format!("Failed to write {}", tmp.display()))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })?;
+    if let Err(e) = fs::rename(&tmp, &file.path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e).with_context(|| format!("Failed to replace {}", file.path.display()));
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn apply_keeps_the_file_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("oxid-replace-mode-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("script.md");
+        fs::write(&path, "old\r\nkeep\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+        let file = ReplaceFile {
+            path: path.clone(),
+            lines: vec![ReplaceLine {
+                line: 0,
+                before: "old".to_string(),
+                after: "new".to_string(),
+                enabled: true,
+            }],
+            enabled: true,
+        };
+
+        assert_eq!(apply_file(&file).unwrap(), 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new\r\nkeep\n");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Vault-wide task and tag scans, for the TUI and the command line
+
+use crate::config::Config;
+use crate::tasks;
+use crate::vault::{Ignore, Walk, ARCHIVE_FOLDER};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Unchecked task from a markdown file (`- [ ] ...`).
+#[derive(Clone, Debug)]
+pub struct TaskEntry {
+    pub path: PathBuf,
+    pub line_number: usize,
+    /// Task board column, from its status tag.
+    pub column: usize,
+    pub content: String,
+}
+
+/// A `#tag` in a note; `line_number` is 0-based.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TagEntry {
+    pub tag: String,
+    pub path: PathBuf,
+    pub line_number: usize,
+}
+
+/// Ignore rules for vault scans: the config's `ignore` list, `.oxidignore`, and the archive
+/// folder unless `search_archive` is set.
+pub fn vault_ignore(notes_dir: &Path, config: &Config) -> Ignore {
+    let mut patterns = config.ignore.clone();
+    if !config.search_archive {
+        patterns.push(format!("/{ARCHIVE_FOLDER}"));
+    }
+    Ignore::new(notes_dir, &patterns)
+}
+
+/// Lines starting with `- [ ]` (unchecked tasks) outside code blocks in the notes of
+/// `walk`, sorted by board column so each column's tasks are next to each other.
+pub fn tasks(walk: &Walk, columns: &[String]) -> Vec<TaskEntry> {
+    let mut found = Vec::new();
+    for path in walk.markdown_files() {
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        let mut in_code_block = false;
+        for (line_number, line) in content.lines().enumerate() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") {
+                in_code_block = !in_code_block;
+                continue;
+            }
+            if in_code_block {
+                continue;
+            }
+            if let Some(task) = trimmed.strip_prefix("- [ ]") {
+                found.push(TaskEntry {
+                    path: path.to_path_buf(),
+                    line_number,
+                    column: tasks::column(line, columns),
+                    content: task.trim().to_string(),
+                });
+            }
+        }
+    }
+    found.sort_by_key(|t| t.column);
+    found
+}
+
+/// Every `#tag` in the notes of `walk`, sorted by tag, then note and line.
+pub fn tags(walk: &Walk) -> Vec<TagEntry> {
+    let mut found = Vec::new();
+    let Ok(re) = Regex::new(r"#(\w+)") else {
+        return found;
+    };
+    for path in walk.markdown_files() {
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        for (line_number, line) in content.lines().enumerate() {
+            for cap in re.captures_iter(line) {
+                found.push(TagEntry {
+                    tag: cap[1].to_string(),
+                    path: path.to_path_buf(),
+                    line_number,
+                });
+            }
+        }
+    }
+    found.sort();
+    found
+}
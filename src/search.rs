@@ -4,15 +4,148 @@
 use crate::app::NoteEntry;
 use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
 use nucleo_matcher::{Matcher, Utf32Str};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
 
-/// Filter notes using fuzzy matching against both filename and content.
-/// Filename matches rank higher than content matches (filename is first in searchable string).
-pub fn filter_notes(notes: &[NoteEntry], query: &str, matcher: &mut Matcher) -> Vec<NoteEntry> {
+/// Prefix that makes a list or telescope query a regular expression.
+pub const REGEX_PREFIX: &str = "re:";
+
+/// Rows to work out highlights for when a list has no height yet (before the first draw).
+const DEFAULT_VISIBLE_ROWS: usize = 50;
+
+/// What a list search matches, chosen by the first character of the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Fuzzy over the name and content.
+    Fuzzy,
+    /// `>query`: fuzzy over the content only.
+    Content,
+    /// `=query`: names containing the query, ignoring case.
+    Name,
+    /// `re:pattern`: a regular expression over the name and content.
+    Regex,
+}
+
+impl SearchMode {
+    /// The mode `query` asks for, and the query without its prefix.
+    pub fn parse(query: &str) -> (Self, &str) {
+        if let Some(rest) = query.strip_prefix('>') {
+            (Self::Content, rest)
+        } else if let Some(rest) = query.strip_prefix('=') {
+            (Self::Name, rest)
+        } else if let Some(rest) = query.strip_prefix(REGEX_PREFIX) {
+            (Self::Regex, rest)
+        } else {
+            (Self::Fuzzy, query)
+        }
+    }
+
+    /// Footer title while searching in the mode.
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Fuzzy => " Search ",
+            Self::Content => " Search content ",
+            Self::Name => " Search names ",
+            Self::Regex => " Search regex ",
+        }
+    }
+}
+
+/// How searches treat letter case; `keys.search_case` switches between matching it exactly
+/// and the `search.smart_case` default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    #[default]
+    Ignore,
+    /// Match case when the query has an uppercase letter.
+    Smart,
+    Sensitive,
+}
+
+impl CaseMode {
+    /// The mode searches start in.
+    pub fn new(smart_case: bool) -> Self {
+        if smart_case {
+            Self::Smart
+        } else {
+            Self::Ignore
+        }
+    }
+
+    /// Whether `query` is matched with its case.
+    pub fn respects(self, query: &str) -> bool {
+        match self {
+            Self::Ignore => false,
+            Self::Smart => query.chars().any(char::is_uppercase),
+            Self::Sensitive => true,
+        }
+    }
+
+    /// Fuzzy pattern for `query` in this mode.
+    pub fn pattern(self, query: &str) -> Pattern {
+        let matching = match self {
+            Self::Ignore => CaseMatching::Ignore,
+            Self::Smart => CaseMatching::Smart,
+            Self::Sensitive => CaseMatching::Respect,
+        };
+        Pattern::parse(query, matching, Normalization::Smart)
+    }
+
+    /// Shown next to the search query.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ignore => "ignore case",
+            Self::Smart => "smart case",
+            Self::Sensitive => "match case",
+        }
+    }
+}
+
+/// Filter notes by `query` (without its mode prefix) in `mode`. The fuzzy search matches the
+/// filename and content, filename matches ranking higher (filename is first in the
+/// searchable string); the content search ranks by score too, the name and regex searches
+/// keep the list order. An invalid regex matches nothing.
+pub fn filter_notes(
+    notes: &[NoteEntry],
+    query: &str,
+    mode: SearchMode,
+    case: CaseMode,
+    matcher: &mut Matcher,
+) -> Vec<NoteEntry> {
     if query.is_empty() {
         return notes.to_vec();
     }
+    if mode == SearchMode::Name {
+        return notes
+            .iter()
+            .filter(|n| find_text(&n.search_label(), query, case).is_some())
+            .cloned()
+            .collect();
+    }
+    if mode == SearchMode::Regex {
+        let Ok(re) = build_regex(query, case) else {
+            return Vec::new();
+        };
+        return notes
+            .iter()
+            .filter(|n| re.is_match(n.as_ref()))
+            .cloned()
+            .collect();
+    }
 
-    let pattern = Pattern::parse(query, CaseMatching::Ignore, Normalization::Smart);
+    let pattern = case.pattern(query);
+    if mode == SearchMode::Content {
+        let mut buf = Vec::new();
+        let mut results: Vec<(&NoteEntry, u32)> = notes
+            .iter()
+            .filter_map(|n| {
+                let score = pattern.score(Utf32Str::new(n.content.text(), &mut buf), matcher)?;
+                Some((n, score))
+            })
+            .collect();
+        results.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        return results.into_iter().map(|(n, _)| n.clone()).collect();
+    }
     let results = pattern.match_list(notes, matcher);
 
     results
@@ -21,20 +154,509 @@ pub fn filter_notes(notes: &[NoteEntry], query: &str, matcher: &mut Matcher) ->
         .collect()
 }
 
+/// Character index of the first place `needle` occurs in `haystack`, ignoring case unless
+/// `case` respects it.
+pub fn find_text(haystack: &str, needle: &str, case: CaseMode) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    let respect = case.respects(needle);
+    let (hay, needle) = (fold_case(haystack, respect), fold_case(needle, respect));
+    hay.windows(needle.len())
+        .position(|w| w == needle.as_slice())
+}
+
+/// Character columns of every place `needle` occurs in `haystack`, left to right and not
+/// overlapping, ignoring case unless `case` respects it.
+pub fn find_all_text(haystack: &str, needle: &str, case: CaseMode) -> Vec<Range<usize>> {
+    let respect = case.respects(needle);
+    let (hay, needle) = (fold_case(haystack, respect), fold_case(needle, respect));
+    let mut found = Vec::new();
+    if needle.is_empty() {
+        return found;
+    }
+    let mut start = 0;
+    while let Some(at) = hay.get(start..).and_then(|rest| {
+        rest.windows(needle.len())
+            .position(|w| w == needle.as_slice())
+    }) {
+        found.push(start + at..start + at + needle.len());
+        start += at + needle.len();
+    }
+    found
+}
+
+/// The chars of `s`, lowercased unless `respect`. Lowercasing keeps one char per char (the
+/// first of the lowercase form), so indices into the folded text are indices into `s` even
+/// where the full lowercase form is longer, as with `İ`.
+fn fold_case(s: &str, respect: bool) -> Vec<char> {
+    s.chars()
+        .map(|c| {
+            if respect {
+                c
+            } else {
+                c.to_lowercase().next().unwrap_or(c)
+            }
+        })
+        .collect()
+}
+
+fn build_regex(pattern: &str, case: CaseMode) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(!case.respects(pattern))
+        .build()
+}
+
+/// The pattern of a `re:` query: `None` for other queries, else the compiled regex or the
+/// compile error, on one line.
+pub fn regex_query(query: &str, case: CaseMode) -> Option<Result<Regex, String>> {
+    let pattern = query.strip_prefix(REGEX_PREFIX)?;
+    Some(build_regex(pattern, case).map_err(|e| {
+        // The message draws a caret under the pattern over several lines; the last one
+        // says what is wrong.
+        let message = e.to_string();
+        message
+            .lines()
+            .last()
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    }))
+}
+
+/// Character columns where the first match of `re` in `text` starts and ends.
+pub fn regex_match_range(text: &str, re: &Regex) -> Option<Range<usize>> {
+    let found = re.find(text)?;
+    let start = text[..found.start()].chars().count();
+    Some(start..start + found.as_str().chars().count())
+}
+
+/// Characters of the first match of `re` in `text`, for highlighting.
+pub fn regex_match_indices(text: &str, re: &Regex) -> Vec<u32> {
+    regex_match_range(text, re).map_or_else(Vec::new, |range| {
+        #[allow(clippy::cast_possible_truncation)]
+        range.map(|i| i as u32).collect()
+    })
+}
+
+/// Characters of `display` to highlight for a list query (with its prefix) in its mode: the
+/// fuzzy matches, the substring for a name search, and none for a content search. Regex
+/// queries are highlighted with `regex_match_indices`, with the compiled pattern.
+pub fn list_match_indices(
+    display: &str,
+    query: &str,
+    case: CaseMode,
+    matcher: &mut Matcher,
+) -> Vec<u32> {
+    match SearchMode::parse(query) {
+        (SearchMode::Fuzzy, query) => get_match_indices(display, query, case, matcher),
+        (SearchMode::Name, query) if !query.is_empty() => find_text(display, query, case)
+            .map_or_else(Vec::new, |start| {
+                #[allow(clippy::cast_possible_truncation)]
+                (start..start + query.chars().count())
+                    .map(|i| i as u32)
+                    .collect()
+            }),
+        (SearchMode::Name | SearchMode::Content | SearchMode::Regex, _) => Vec::new(),
+    }
+}
+
+/// The first line of `content` that matches `query` on its own, with its index. A line
+/// containing the query as typed (ignoring case unless `case` respects it) comes first, as
+/// the preview highlights those; otherwise the first line the fuzzy pattern matches. `None`
+/// when the fuzzy match only works across lines.
+pub fn first_matching_line<'a>(
+    content: &'a str,
+    query: &str,
+    case: CaseMode,
+    matcher: &mut Matcher,
+) -> Option<(usize, &'a str)> {
+    if query.trim().is_empty() {
+        return None;
+    }
+    let contains = |line: &str| {
+        if case.respects(query) {
+            line.contains(query)
+        } else {
+            line.to_lowercase().contains(&query.to_lowercase())
+        }
+    };
+    if let Some(found) = content.lines().enumerate().find(|(_, line)| contains(line)) {
+        return Some(found);
+    }
+    let pattern = case.pattern(query);
+    let mut buf = Vec::new();
+    content.lines().enumerate().find(|(_, line)| {
+        pattern
+            .score(Utf32Str::new(line, &mut buf), matcher)
+            .is_some()
+    })
+}
+
+/// `text` as a nucleo haystack with one element per char, so that match indices count chars
+/// like the rest of the highlighting. `Utf32Str::new` folds each grapheme cluster into one
+/// element instead, and falls back to bytes when every cluster starts with an ASCII char,
+/// so an index after a combining accent or an emoji sequence would point past its char.
+fn char_haystack<'a>(text: &'a str, buf: &'a mut Vec<char>) -> Utf32Str<'a> {
+    if text.is_ascii() {
+        return Utf32Str::Ascii(text.as_bytes());
+    }
+    buf.clear();
+    buf.extend(text.chars());
+    Utf32Str::Unicode(buf)
+}
+
 /// Get match indices for highlighting in the display (filename) string.
 /// Returns character indices that match the query. Empty vec if no match or no query.
-pub fn get_match_indices(display: &str, query: &str, matcher: &mut Matcher) -> Vec<u32> {
+pub fn get_match_indices(
+    display: &str,
+    query: &str,
+    case: CaseMode,
+    matcher: &mut Matcher,
+) -> Vec<u32> {
     if query.is_empty() || display.is_empty() {
         return Vec::new();
     }
 
-    let pattern = Pattern::parse(query, CaseMatching::Ignore, Normalization::Smart);
+    let pattern = case.pattern(query);
     let mut buf = Vec::new();
-    let haystack = Utf32Str::new(display, &mut buf);
     let mut indices = Vec::new();
-    if pattern.indices(haystack, matcher, &mut indices).is_some() {
+    if pattern
+        .indices(char_haystack(display, &mut buf), matcher, &mut indices)
+        .is_some()
+    {
         indices.sort_unstable();
         indices.dedup();
     }
     indices
 }
+
+/// Whether every note matching `query` also matches `previous`, so that `query` only needs
+/// to filter the results of `previous`: it adds to the end of `previous`, and the last word
+/// of `previous` has no syntax that more characters would loosen (`!word`) or change
+/// (`word$`, a trailing `\`).
+pub fn narrows(previous: &str, query: &str) -> bool {
+    if previous.trim().is_empty() || query.len() <= previous.len() || !query.starts_with(previous) {
+        return false;
+    }
+    if previous.ends_with(char::is_whitespace) {
+        return true;
+    }
+    let last = previous.split_whitespace().last().unwrap_or_default();
+    !last.starts_with('!') && !last.ends_with('$') && !last.ends_with('\\')
+}
+
+/// Rows a list may show on its next draw: a screen (`height` rows) around both where it was
+/// scrolled to and the selection, as moving the selection scrolls the list.
+pub fn visible_rows(offset: usize, selected: usize, height: u16) -> Range<usize> {
+    let height = match usize::from(height) {
+        0 => DEFAULT_VISIBLE_ROWS,
+        h => h,
+    };
+    offset.min(selected).saturating_sub(height)..offset.max(selected) + height + 1
+}
+
+/// Highlights of one list row: the matched characters of its name and, in the file list, the
+/// first line of the note that matches (index, trimmed text).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowHighlight {
+    pub indices: Vec<u32>,
+    pub content: Option<(usize, String)>,
+}
+
+/// Highlights of the rows of a filtered list. Working them out is most of the cost of
+/// filtering a big vault, so they are only filled in for rows about to be shown.
+#[derive(Debug, Clone, Default)]
+pub struct RowHighlights {
+    rows: Vec<Option<RowHighlight>>,
+}
+
+impl RowHighlights {
+    /// Forget every row's highlights, for a list of `len` rows.
+    pub fn reset(&mut self, len: usize) {
+        self.rows = vec![None; len];
+    }
+
+    /// Work out the highlights of the rows in `range` that have none yet with `highlight`.
+    pub fn fill(&mut self, range: Range<usize>, mut highlight: impl FnMut(usize) -> RowHighlight) {
+        let end = range.end.min(self.rows.len());
+        for i in range.start.min(end)..end {
+            if self.rows[i].is_none() {
+                self.rows[i] = Some(highlight(i));
+            }
+        }
+    }
+
+    /// Forget row `i`'s highlights, as its note changed.
+    pub fn forget(&mut self, i: usize) {
+        if let Some(row) = self.rows.get_mut(i) {
+            *row = None;
+        }
+    }
+
+    /// Matched characters of row `i`'s name.
+    pub fn indices(&self, i: usize) -> &[u32] {
+        self.rows
+            .get(i)
+            .and_then(Option::as_ref)
+            .map_or(&[], |row| row.indices.as_slice())
+    }
+
+    /// First matching line of row `i`'s note.
+    pub fn content(&self, i: usize) -> Option<&(usize, String)> {
+        self.rows.get(i)?.as_ref()?.content.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    fn note(display: &str, content: &str) -> NoteEntry {
+        let searchable = format!("{display}\n{content}");
+        NoteEntry::new(
+            PathBuf::from(display),
+            display.to_string(),
+            content.to_string(),
+            searchable,
+        )
+    }
+
+    #[test]
+    fn query_prefixes_pick_what_is_matched() {
+        let notes = [
+            note("plan.md", "budget"),
+            note("budget.md", "numbers"),
+            note("x.md", "Plan B"),
+        ];
+        let mut matcher = Matcher::new(nucleo_matcher::Config::DEFAULT);
+        let mut names = |query: &str| -> Vec<String> {
+            let (mode, query) = SearchMode::parse(query);
+            filter_notes(&notes, query, mode, CaseMode::Ignore, &mut matcher)
+                .into_iter()
+                .map(|n| n.display)
+                .collect()
+        };
+        assert_eq!(names(">budget"), ["plan.md"]);
+        assert_eq!(names("=BUDGET"), ["budget.md"]);
+        assert_eq!(names("=pla"), ["plan.md"]);
+        assert_eq!(names("=").len(), 3);
+        assert_eq!(names("budget").len(), 2);
+
+        let ignore = CaseMode::Ignore;
+        assert_eq!(
+            list_match_indices("my plan.md", "=PLAN", ignore, &mut matcher),
+            [3, 4, 5, 6]
+        );
+        assert!(list_match_indices("plan.md", ">plan", ignore, &mut matcher).is_empty());
+        assert_eq!(find_text("ÄÖ big", "öB", ignore), None);
+        assert_eq!(find_text("ÄÖ big", "ö b", ignore), Some(1));
+    }
+
+    #[test]
+    fn case_modes_decide_when_case_counts() {
+        let notes = [
+            note("api.md", "call the API"),
+            note("notes.md", "an api call"),
+        ];
+        let mut matcher = Matcher::new(nucleo_matcher::Config::DEFAULT);
+        let mut names = |query: &str, case: CaseMode| -> Vec<String> {
+            let (mode, query) = SearchMode::parse(query);
+            let mut names: Vec<String> = filter_notes(&notes, query, mode, case, &mut matcher)
+                .into_iter()
+                .map(|n| n.display)
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(">API", CaseMode::Ignore), ["api.md", "notes.md"]);
+        assert_eq!(names(">API", CaseMode::Smart), ["api.md"]);
+        assert_eq!(names(">api", CaseMode::Smart), ["api.md", "notes.md"]);
+        assert_eq!(names(">api", CaseMode::Sensitive), ["notes.md"]);
+        assert_eq!(names("=API", CaseMode::Sensitive), Vec::<String>::new());
+        assert_eq!(names("re:API", CaseMode::Ignore), ["api.md", "notes.md"]);
+        assert_eq!(names("re:API", CaseMode::Smart), ["api.md"]);
+
+        assert_eq!(
+            find_text("an api, the API", "API", CaseMode::Smart),
+            Some(12)
+        );
+        assert_eq!(
+            find_text("an api, the API", "API", CaseMode::Ignore),
+            Some(3)
+        );
+        let line = first_matching_line("an api\nthe API", "API", CaseMode::Sensitive, &mut matcher);
+        assert_eq!(line, Some((1, "the API")));
+    }
+
+    #[test]
+    fn match_indices_count_chars_past_combining_accents_and_emoji() {
+        let mut matcher = Matcher::new(nucleo_matcher::Config::DEFAULT);
+        let mut indices = |display: &str| {
+            let found = get_match_indices(display, "notes", CaseMode::Ignore, &mut matcher);
+            let chars: Vec<char> = display.chars().collect();
+            found.iter().map(|&i| chars[i as usize]).collect::<String>()
+        };
+        // Every grapheme starts with ASCII here, where nucleo would count bytes.
+        assert_eq!(indices("cafe\u{301}-notes.md"), "notes");
+        assert_eq!(indices("cafe\u{301} 🚀 notes.md"), "notes");
+        assert_eq!(indices("👩\u{200d}💻 notes.md"), "notes");
+        let found = get_match_indices("e\u{301}-notes.md", "notes", CaseMode::Ignore, &mut matcher);
+        assert_eq!(found, [3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn notes_match_by_name_until_their_content_is_loaded() {
+        use crate::content::NoteContent;
+        let file =
+            |content| NoteEntry::file(PathBuf::from("plan.md"), "plan.md".into(), content, None);
+        let mut matcher = Matcher::new(nucleo_matcher::Config::DEFAULT);
+        let mut names = |notes: &[NoteEntry], query: &str| -> Vec<String> {
+            let (mode, query) = SearchMode::parse(query);
+            filter_notes(notes, query, mode, CaseMode::Ignore, &mut matcher)
+                .into_iter()
+                .map(|n| n.display)
+                .collect()
+        };
+
+        let pending = [file(NoteContent::Pending)];
+        assert_eq!(names(&pending, "plan"), ["plan.md"]);
+        assert!(names(&pending, "budget").is_empty());
+        assert!(names(&pending, ">budget").is_empty());
+        assert!(names(&pending, "re:budget").is_empty());
+
+        // Once read, the same query finds the note by its content too.
+        let loaded = [file(NoteContent::Loaded("Q3 budget".to_string()))];
+        assert_eq!(names(&loaded, "plan"), ["plan.md"]);
+        assert_eq!(names(&loaded, "budget"), ["plan.md"]);
+        assert_eq!(names(&loaded, ">budget"), ["plan.md"]);
+        assert_eq!(names(&loaded, "re:budget"), ["plan.md"]);
+    }
+
+    #[test]
+    fn regex_queries_match_and_report_errors() {
+        let notes = [
+            note("a.md", "## Decision: go"),
+            note("b.md", "# Decision"),
+            note("c.md", "TODO(ann)"),
+        ];
+        let mut matcher = Matcher::new(nucleo_matcher::Config::DEFAULT);
+        let mut names = |query: &str| -> Vec<String> {
+            let (mode, query) = SearchMode::parse(query);
+            filter_notes(&notes, query, mode, CaseMode::Ignore, &mut matcher)
+                .into_iter()
+                .map(|n| n.display)
+                .collect()
+        };
+        assert_eq!(names("re:(?m)^## Decision"), ["a.md"]);
+        assert_eq!(names(r"re:TODO\(\w+\)"), ["c.md"]);
+        assert!(names("re:TODO(").is_empty());
+
+        let query = |query: &str| regex_query(query, CaseMode::Ignore);
+        let re = query("re:ö+b").unwrap().unwrap();
+        assert_eq!(regex_match_indices("äöö big öb", &re), [8, 9]);
+        assert_eq!(regex_match_range("äöö big öb", &re), Some(8..10));
+        assert_eq!(
+            query("re:TODO(").unwrap().unwrap_err(),
+            "error: unclosed group"
+        );
+        assert!(query("TODO").is_none());
+    }
+
+    #[test]
+    fn narrowing_only_when_more_text_cannot_widen_the_match() {
+        assert!(narrows("pro", "proj"));
+        assert!(narrows("proj ", "proj !old"));
+        assert!(narrows("^pro", "^proj"));
+        assert!(!narrows("", "p"));
+        assert!(!narrows("proj", "proj"));
+        assert!(!narrows("proj", "pro"));
+        assert!(!narrows("pro", "plan"));
+        assert!(!narrows("!old", "!olde"));
+        assert!(!narrows("md$", "md$x"));
+    }
+
+    #[test]
+    fn visible_rows_cover_the_old_view_and_the_selection() {
+        assert_eq!(visible_rows(0, 0, 10), 0..11);
+        assert_eq!(visible_rows(100, 150, 10), 90..161);
+        assert_eq!(visible_rows(0, 0, 0), 0..51);
+    }
+
+    /// Synthetic vault of `n` notes named like "project-00042-alpha.md".
+    fn synthetic_notes(n: usize) -> Vec<NoteEntry> {
+        let words = [
+            "alpha", "beta", "gamma", "delta", "meeting", "journal", "recipe",
+        ];
+        (0..n)
+            .map(|i| {
+                let display = format!("project-{i:05}-{}.md", words[i % words.len()]);
+                let content = format!("# Note {i}\n\nSome text about {}.\n", words[i % 3]);
+                let searchable = format!("{display}\n{content}");
+                NoteEntry::new(PathBuf::from(&display), display, content, searchable)
+            })
+            .collect()
+    }
+
+    /// `cargo test --release -- --ignored --nocapture filter_benchmark`
+    #[test]
+    #[ignore]
+    fn filter_benchmark() {
+        use CaseMode::Ignore;
+        use SearchMode::Fuzzy;
+        let notes = synthetic_notes(10_000);
+        let queries = [
+            "p",
+            "pr",
+            "pro",
+            "proj",
+            "proje",
+            "project",
+            "project-0",
+            "project-00",
+        ];
+        let time = |label: &str, mut run: Box<dyn FnMut(&mut Matcher) + '_>| {
+            let mut matcher = Matcher::new(nucleo_matcher::Config::DEFAULT);
+            let start = Instant::now();
+            for _ in 0..10 {
+                run(&mut matcher);
+            }
+            println!("{label}: {:?} per query sequence", start.elapsed() / 10);
+        };
+
+        time(
+            "full filter, every row highlighted",
+            Box::new(|matcher| {
+                for query in queries {
+                    let results = filter_notes(&notes, query, Fuzzy, Ignore, matcher);
+                    for note in &results {
+                        get_match_indices(&note.display, query, Ignore, matcher);
+                        first_matching_line(note.content.text(), query, Ignore, matcher);
+                    }
+                }
+            }),
+        );
+        time(
+            "narrowed filter, visible rows highlighted",
+            Box::new(|matcher| {
+                let mut results = notes.clone();
+                let mut previous = "";
+                for query in queries {
+                    let source = if narrows(previous, query) {
+                        &results
+                    } else {
+                        &notes
+                    };
+                    results = filter_notes(source, query, Fuzzy, Ignore, matcher);
+                    for note in results.iter().take(visible_rows(0, 0, 40).end) {
+                        get_match_indices(&note.display, query, Ignore, matcher);
+                        first_matching_line(note.content.text(), query, Ignore, matcher);
+                    }
+                    previous = query;
+                }
+            }),
+        );
+    }
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - User-defined shell commands
+
+use crate::config::UserCommand;
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Instant;
+
+/// Output lines kept for the popup; older lines are dropped.
+const MAX_OUTPUT_LINES: usize = 200;
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// A user command started from the palette. stdin is closed and stdout/stderr are captured,
+/// so the command never draws over the TUI.
+pub struct RunningCommand {
+    pub name: String,
+    child: Child,
+    lines: Receiver<String>,
+    pub output: Vec<String>,
+    started: Instant,
+    /// Set once the command has exited (or failed to be waited on).
+    pub finished: Option<String>,
+}
+
+impl RunningCommand {
+    /// Start `cmd` with `sh -c` in `dir`, exporting `OXID_DIR` and, when set, `OXID_FILE`.
+    pub fn spawn(cmd: &UserCommand, dir: &Path, file: Option<&Path>) -> Result<Self> {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(&cmd.command)
+            .current_dir(dir)
+            .env("OXID_DIR", dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // Own process group, so cancelling also stops whatever the shell started.
+            .process_group(0);
+        if let Some(file) = file {
+            command.env("OXID_FILE", file);
+        }
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("Failed to run {}", cmd.name))?;
+
+        let (tx, lines) = mpsc::channel();
+        if let Some(stdout) = child.stdout.take() {
+            forward_lines(stdout, tx.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward_lines(stderr, tx);
+        }
+        Ok(Self {
+            name: cmd.name.clone(),
+            child,
+            lines,
+            output: Vec::new(),
+            started: Instant::now(),
+            finished: None,
+        })
+    }
+
+    /// Collect new output and check whether the command has exited.
+    pub fn poll(&mut self) {
+        if self.finished.is_none() {
+            match self.child.try_wait() {
+                Ok(Some(status)) if status.success() => self.finished = Some("done".to_string()),
+                Ok(Some(status)) => match status.code() {
+                    Some(code) => self.finished = Some(format!("exit code {code}")),
+                    None => self.finished = Some("cancelled".to_string()),
+                },
+                Ok(None) => {}
+                Err(e) => self.finished = Some(format!("failed: {e}")),
+            }
+        }
+        self.output.extend(self.lines.try_iter());
+        if self.output.len() > MAX_OUTPUT_LINES {
+            self.output.drain(..self.output.len() - MAX_OUTPUT_LINES);
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.finished.is_none()
+    }
+
+    /// Stop the command and everything it started.
+    pub fn cancel(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        let group = format!("-{}", self.child.id());
+        let signalled = Command::new("kill")
+            .args(["-TERM", "--", &group])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success());
+        if !signalled {
+            let _ = self.child.kill();
+        }
+    }
+
+    /// Spinner frame for the elapsed time.
+    pub fn spinner(&self) -> char {
+        let frame = self.started.elapsed().as_millis() / 100;
+        SPINNER[frame as usize % SPINNER.len()]
+    }
+}
+
+/// Send each line read from `source` to `tx` on a background thread.
+fn forward_lines(source: impl Read + Send + 'static, tx: Sender<String>) {
+    thread::spawn(move || {
+        for line in BufReader::new(source).lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
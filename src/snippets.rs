@@ -0,0 +1,200 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Snippets: trigger words that expand into text with tab stops
+
+/// Start and end (line, character) of a tab stop.
+pub type Range = ((usize, usize), (usize, usize));
+
+/// A snippet body with its tab stops taken out: `${1:task}` leaves `task` in the text.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Expansion {
+    pub text: String,
+    /// Tab stops in the order Tab visits them, relative to the start of `text`. The last one
+    /// is where the cursor ends up: `$0`, or else the end of the text.
+    pub stops: Vec<Range>,
+}
+
+/// Parse a snippet body. `${n:placeholder}`, `${n}` and `$n` mark tab stop `n`; stops are
+/// visited in increasing order and `$0` last. Placeholders stay on one line, and a number
+/// used twice only keeps its first stop. Any other `$` is plain text.
+pub fn expand(body: &str) -> Expansion {
+    let mut text = String::new();
+    let (mut row, mut col) = (0, 0);
+    let mut stops: Vec<(usize, Range)> = Vec::new();
+    let mut rest = body;
+    while let Some(c) = rest.chars().next() {
+        if let Some((n, placeholder, after)) = tab_stop(rest) {
+            let start = (row, col);
+            text.push_str(placeholder);
+            col += placeholder.chars().count();
+            if !stops.iter().any(|&(m, _)| m == n) {
+                stops.push((n, (start, (row, col))));
+            }
+            rest = after;
+            continue;
+        }
+        text.push(c);
+        if c == '\n' {
+            (row, col) = (row + 1, 0);
+        } else {
+            col += 1;
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    stops.sort_by_key(|&(n, _)| if n == 0 { usize::MAX } else { n });
+    if stops.last().is_none_or(|&(n, _)| n != 0) {
+        stops.push((0, ((row, col), (row, col))));
+    }
+    Expansion {
+        text,
+        stops: stops.into_iter().map(|(_, range)| range).collect(),
+    }
+}
+
+/// The tab stop `s` starts with: its number, placeholder, and the text after it.
+fn tab_stop(s: &str) -> Option<(usize, &str, &str)> {
+    let s = s.strip_prefix('$')?;
+    let (braced, s) = match s.strip_prefix('{') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let digits = s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let n = s[..digits].parse().ok()?;
+    let s = &s[digits..];
+    if !braced {
+        return Some((n, "", s));
+    }
+    if let Some(after) = s.strip_prefix('}') {
+        return Some((n, "", after));
+    }
+    let s = s.strip_prefix(':')?;
+    let end = s.find('}').filter(|&end| !s[..end].contains('\n'))?;
+    Some((n, &s[..end], &s[end + 1..]))
+}
+
+/// A position in the buffer that stays put while text is typed on one side of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    /// Line and character from the start of the buffer.
+    Start(usize, usize),
+    /// Lines after the position's line, and characters after it on that line.
+    End(usize, usize),
+}
+
+impl Anchor {
+    fn new((row, col): (usize, usize), lines: &[String], from_end: bool) -> Self {
+        if !from_end {
+            return Self::Start(row, col);
+        }
+        let len = lines.get(row).map_or(0, |l| l.chars().count());
+        Self::End(lines.len().saturating_sub(row + 1), len.saturating_sub(col))
+    }
+
+    fn resolve(self, lines: &[String]) -> (usize, usize) {
+        match self {
+            Self::Start(row, col) => (row, col),
+            Self::End(rows_after, chars_after) => {
+                let row = lines.len().saturating_sub(rows_after + 1);
+                let len = lines.get(row).map_or(0, |l| l.chars().count());
+                (row, len.saturating_sub(chars_after))
+            }
+        }
+    }
+}
+
+/// An expanded snippet whose tab stops Tab and Shift-Tab visit. Text is typed only inside
+/// the current stop, so stops before it are kept from the start of the buffer and stops
+/// after it from the end.
+#[derive(Debug, Clone)]
+pub struct SnippetSession {
+    /// Buffer the snippet was expanded in.
+    pub buffer_id: u64,
+    stops: Vec<(Anchor, Anchor)>,
+    current: usize,
+}
+
+impl SnippetSession {
+    /// A session for `stops` (buffer positions, in Tab order), before any stop is entered.
+    pub fn new(buffer_id: u64, stops: &[Range]) -> Self {
+        Self {
+            buffer_id,
+            stops: stops
+                .iter()
+                .map(|&(start, end)| (Anchor::Start(start.0, start.1), Anchor::Start(end.0, end.1)))
+                .collect(),
+            current: 0,
+        }
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn stop_count(&self) -> usize {
+        self.stops.len()
+    }
+
+    /// Where the current stop is now in `lines`.
+    pub fn current_range(&self, lines: &[String]) -> Range {
+        let (start, end) = self.stops[self.current];
+        (start.resolve(lines), end.resolve(lines))
+    }
+
+    /// Make stop `index` current and return where it is in `lines`.
+    pub fn enter(&mut self, index: usize, lines: &[String]) -> Range {
+        let ranges: Vec<Range> = self
+            .stops
+            .iter()
+            .map(|&(start, end)| (start.resolve(lines), end.resolve(lines)))
+            .collect();
+        let pivot = ranges[index].0;
+        // Typing at the current stop moves everything after it, including stops that start
+        // at the same place but come later in Tab order.
+        let after = |j: usize, pos: (usize, usize)| pos > pivot || (pos == pivot && j > index);
+        self.stops = ranges
+            .iter()
+            .enumerate()
+            .map(|(j, &(start, end))| {
+                let end_after = j == index || after(j, end);
+                let start_after = j != index && after(j, start);
+                (
+                    Anchor::new(start, lines, start_after),
+                    Anchor::new(end, lines, end_after),
+                )
+            })
+            .collect();
+        self.current = index;
+        ranges[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_tab_stops_in_order() {
+        let expansion = expand("- [ ] ${1:task} 📅 ${2:date}");
+        assert_eq!(expansion.text, "- [ ] task 📅 date");
+        assert_eq!(
+            expansion.stops,
+            [((0, 6), (0, 10)), ((0, 13), (0, 17)), ((0, 17), (0, 17))]
+        );
+
+        let expansion = expand("$2 ${1}\n$0 end $x ${3:a\nb}");
+        assert_eq!(expansion.text, " \n end $x ${3:a\nb}");
+        assert_eq!(
+            expansion.stops,
+            [((0, 1), (0, 1)), ((0, 0), (0, 0)), ((1, 0), (1, 0))]
+        );
+    }
+
+    #[test]
+    fn stops_follow_text_typed_before_them() {
+        let mut lines = vec!["x (a) b".to_string(), "end".to_string()];
+        let mut session = SnippetSession::new(0, &[((0, 3), (0, 4)), ((0, 7), (0, 7))]);
+        assert_eq!(session.enter(0, &lines), ((0, 3), (0, 4)));
+        lines = ["x (long", "word) b", "end"].map(String::from).to_vec();
+        assert_eq!(session.current_range(&lines), ((0, 3), (1, 4)));
+        assert_eq!(session.enter(1, &lines), ((1, 7), (1, 7)));
+    }
+}
@@ -1,73 +1,98 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // oxid - Spellcheck support
 
+use anyhow::{Context, Result};
 use regex::Regex;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Personal dictionary in the config directory, one word per line.
+const PERSONAL_DICT_FILE: &str = "dictionary.txt";
+
+/// Maximum edit distance for a dictionary word to be offered as a suggestion.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
 
 /// Spellchecker using word lists from system or config.
-#[allow(dead_code)]
 pub struct Spellchecker {
     dict: HashSet<String>,
+    /// Personal dictionary file that `add_word` appends to.
+    personal_path: Option<PathBuf>,
+    /// Configured languages for which no dictionary file was found.
+    pub missing_languages: Vec<String>,
 }
 
 #[allow(dead_code)]
 impl Spellchecker {
-    /// Create spellchecker with given languages. Tries common system paths.
+    /// Create spellchecker with given languages. Tries common system paths, then merges in
+    /// the personal dictionary (`~/.config/oxid/dictionary.txt`).
     pub fn new(languages: &[String]) -> Self {
         let mut dict = HashSet::new();
+        let mut missing_languages = Vec::new();
         for lang in languages {
-            let words = Self::load_dict(lang);
-            for w in words {
-                dict.insert(w.to_lowercase());
+            match Self::load_dict(lang) {
+                Some(words) => dict.extend(words.iter().map(|w| w.to_lowercase())),
+                None => missing_languages.push(lang.clone()),
             }
         }
-        Self { dict }
+        let personal_path = crate::config::ensure_config_dir()
+            .ok()
+            .map(|dir| dir.join(PERSONAL_DICT_FILE));
+        if let Some(content) = personal_path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+        {
+            dict.extend(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|w| !w.is_empty())
+                    .map(str::to_lowercase),
+            );
+        }
+        Self {
+            dict,
+            personal_path,
+            missing_languages,
+        }
     }
 
-    fn load_dict(lang: &str) -> Vec<String> {
-        let paths = [
+    /// Load the word list for `lang`, or None when no dictionary file exists. Hunspell
+    /// `.dic` files are expanded with the affix rules from the matching `.aff`.
+    fn load_dict(lang: &str) -> Option<Vec<String>> {
+        let plain = [
             format!("/usr/share/dict/{lang}-words"),
             format!("/usr/share/dict/{lang}"),
+        ];
+        let hunspell = [
             format!("/usr/share/hunspell/{lang}.dic"),
             format!("/usr/share/myspell/dicts/{lang}.dic"),
         ];
 
-        for path in &paths {
+        for path in &plain {
             if let Ok(content) = fs::read_to_string(Path::new(path)) {
-                return content
-                    .lines()
-                    .filter_map(|l| {
-                        let word = l.split('/').next()?.trim().to_string();
-                        if word.chars().all(char::is_alphabetic) && word.len() > 1 {
-                            Some(word)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
+                return Some(plain_words(&content));
+            }
+        }
+        for path in &hunspell {
+            let path = Path::new(path);
+            if let Ok(dic) = fs::read(path) {
+                let affixes = fs::read(path.with_extension("aff"))
+                    .map(|aff| Affixes::parse(&String::from_utf8_lossy(&aff)))
+                    .unwrap_or_default();
+                return Some(affixes.expand_dic(&String::from_utf8_lossy(&dic)));
             }
         }
 
         // Fallback: /usr/share/dict/words (common on Linux)
         if lang == "en" {
             if let Ok(content) = fs::read_to_string("/usr/share/dict/words") {
-                return content
-                    .lines()
-                    .filter_map(|l| {
-                        let word = l.trim().to_string();
-                        if word.chars().all(char::is_alphabetic) && word.len() > 1 {
-                            Some(word)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
+                return Some(plain_words(&content));
             }
         }
 
-        Vec::new()
+        None
     }
 
     /// Check if word is correctly spelled.
@@ -78,6 +103,44 @@ impl Spellchecker {
         self.dict.contains(&word.to_lowercase())
     }
 
+    /// Up to `limit` dictionary words close to `word`, nearest first, cased like `word`
+    /// (`Teh` suggests `The`, `TEH` suggests `THE`).
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<String> {
+        let target: Vec<char> = word.to_lowercase().chars().collect();
+        let mut scored: Vec<(usize, &String)> = self
+            .dict
+            .iter()
+            .filter(|w| w.chars().count().abs_diff(target.len()) <= MAX_SUGGESTION_DISTANCE)
+            .filter_map(|w| {
+                let candidate: Vec<char> = w.chars().collect();
+                let d = edit_distance(&target, &candidate);
+                (d <= MAX_SUGGESTION_DISTANCE).then_some((d, w))
+            })
+            .collect();
+        scored.sort();
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, w)| match_case(word, w))
+            .collect()
+    }
+
+    /// Add `word` to the personal dictionary file and the loaded dictionary.
+    pub fn add_word(&mut self, word: &str) -> Result<()> {
+        let path = self
+            .personal_path
+            .as_ref()
+            .context("No config directory for the personal dictionary")?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        writeln!(file, "{word}").with_context(|| format!("Failed to write {}", path.display()))?;
+        self.dict.insert(word.to_lowercase());
+        Ok(())
+    }
+
     /// Extract misspelled words from text. Returns set of (start_byte, end_byte) for each misspelled word.
     pub fn find_misspelled_ranges(&self, text: &str) -> Vec<(usize, usize)> {
         let re = Regex::new(r"\b[a-zA-Z][a-zA-Z']*\b")
@@ -92,3 +155,177 @@ impl Spellchecker {
         ranges
     }
 }
+
+/// Words from a plain word list, one per line.
+fn plain_words(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|w| is_dict_word(w))
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_dict_word(word: &str) -> bool {
+    word.chars().all(char::is_alphabetic) && word.len() > 1
+}
+
+/// How affix flags are written in `.dic` entries (hunspell `FLAG` directive).
+#[derive(Default, Clone, Copy)]
+enum FlagMode {
+    /// One character per flag (default).
+    #[default]
+    Char,
+    /// Two characters per flag (`FLAG long`).
+    Long,
+    /// Comma-separated numbers (`FLAG num`).
+    Num,
+}
+
+/// One `PFX`/`SFX` rule: strip `strip`, add `add`, if the word matches `condition`.
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Option<Regex>,
+}
+
+/// Prefix and suffix rules from a hunspell `.aff` file. Only single-level expansion is
+/// done (no cross products or continuation flags), which covers the common plural,
+/// past-tense and -ing forms.
+#[derive(Default)]
+struct Affixes {
+    flag_mode: FlagMode,
+    prefixes: HashMap<String, Vec<AffixRule>>,
+    suffixes: HashMap<String, Vec<AffixRule>>,
+}
+
+impl Affixes {
+    fn parse(content: &str) -> Self {
+        let mut affixes = Self::default();
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", "long", ..] => affixes.flag_mode = FlagMode::Long,
+                ["FLAG", "num", ..] => affixes.flag_mode = FlagMode::Num,
+                // Header line: SFX <flag> <cross product Y/N> <count>
+                ["PFX" | "SFX", _, "Y" | "N", count] if count.parse::<usize>().is_ok() => {}
+                [kind @ ("PFX" | "SFX"), flag, strip, add, rest @ ..] => {
+                    let prefix = *kind == "PFX";
+                    let empty = |s: &str| {
+                        if s == "0" {
+                            String::new()
+                        } else {
+                            s.to_string()
+                        }
+                    };
+                    let add = add.split('/').next().unwrap_or_default();
+                    let condition = rest.first().filter(|c| **c != ".").and_then(|c| {
+                        let pattern = if prefix {
+                            format!("^(?:{c})")
+                        } else {
+                            format!("(?:{c})$")
+                        };
+                        Regex::new(&pattern).ok()
+                    });
+                    let rule = AffixRule {
+                        strip: empty(strip),
+                        add: empty(add),
+                        condition,
+                    };
+                    let rules = if prefix {
+                        &mut affixes.prefixes
+                    } else {
+                        &mut affixes.suffixes
+                    };
+                    rules.entry((*flag).to_string()).or_default().push(rule);
+                }
+                _ => {}
+            }
+        }
+        affixes
+    }
+
+    fn split_flags(&self, flags: &str) -> Vec<String> {
+        match self.flag_mode {
+            FlagMode::Char => flags.chars().map(String::from).collect(),
+            FlagMode::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars.chunks(2).map(|c| c.iter().collect()).collect()
+            }
+            FlagMode::Num => flags.split(',').map(|f| f.trim().to_string()).collect(),
+        }
+    }
+
+    /// Base words from a `.dic` file plus every form produced by their affix flags.
+    fn expand_dic(&self, content: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        // The first line of a .dic file is the entry count.
+        for line in content.lines().skip(1) {
+            let entry = line.split_whitespace().next().unwrap_or_default();
+            let (word, flags) = entry.split_once('/').unwrap_or((entry, ""));
+            if !is_dict_word(word) {
+                continue;
+            }
+            for flag in self.split_flags(flags) {
+                for rule in self.suffixes.get(&flag).into_iter().flatten() {
+                    if rule.condition.as_ref().is_some_and(|c| !c.is_match(word)) {
+                        continue;
+                    }
+                    if let Some(stem) = word.strip_suffix(rule.strip.as_str()) {
+                        words.push(format!("{stem}{}", rule.add));
+                    }
+                }
+                for rule in self.prefixes.get(&flag).into_iter().flatten() {
+                    if rule.condition.as_ref().is_some_and(|c| !c.is_match(word)) {
+                        continue;
+                    }
+                    if let Some(stem) = word.strip_prefix(rule.strip.as_str()) {
+                        words.push(format!("{}{stem}", rule.add));
+                    }
+                }
+            }
+            words.push(word.to_string());
+        }
+        words
+    }
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions, and adjacent
+/// transpositions each cost 1.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Apply the capitalization of `original` (all caps or leading capital) to `word`.
+fn match_case(original: &str, word: &str) -> String {
+    let first_upper = original.chars().next().is_some_and(char::is_uppercase);
+    if first_upper && original.chars().count() > 1 && original.chars().all(|c| !c.is_lowercase()) {
+        return word.to_uppercase();
+    }
+    if first_upper {
+        let mut w = word.chars();
+        return w
+            .next()
+            .map(|c| c.to_uppercase().chain(w).collect())
+            .unwrap_or_default();
+    }
+    word.to_string()
+}
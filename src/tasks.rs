@@ -0,0 +1,348 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Task lines: checkboxes, due dates and recurrence
+
+use chrono::{Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Marks a task's due date: `📅 2024-03-14`.
+const DUE_MARKER: &str = "📅";
+
+/// Marks how a task recurs: `🔁 every week`.
+const RECURRENCE_MARKER: &str = "🔁";
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Length of one step of a recurrence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// How often a task comes back, e.g. every 2 weeks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recurrence {
+    pub every: u32,
+    pub unit: Unit,
+}
+
+impl Recurrence {
+    /// The date one recurrence after `date`. Months and years keep the day of the month,
+    /// or use the last day of a shorter month (Jan 31 + 1 month is Feb 28 or 29).
+    pub fn next(self, date: NaiveDate) -> NaiveDate {
+        let n = u64::from(self.every);
+        let next = match self.unit {
+            Unit::Day => date.checked_add_days(Days::new(n)),
+            Unit::Week => date.checked_add_days(Days::new(n * 7)),
+            Unit::Month => date.checked_add_months(Months::new(self.every)),
+            Unit::Year => date.checked_add_months(Months::new(self.every.saturating_mul(12))),
+        };
+        next.unwrap_or(date)
+    }
+}
+
+/// Due date of a task line, from `📅 YYYY-MM-DD`.
+pub fn due_date(line: &str) -> Option<NaiveDate> {
+    let (_, after) = line.split_once(DUE_MARKER)?;
+    let date = after.trim_start().get(..10)?;
+    NaiveDate::parse_from_str(date, DATE_FORMAT).ok()
+}
+
+/// Recurrence of a task line, from `🔁 every day`, `every week`, `every month`,
+/// `every year` or `every 2 weeks` (also `daily`, `weekly`, `monthly`, `yearly`).
+pub fn recurrence(line: &str) -> Option<Recurrence> {
+    let (_, after) = line.split_once(RECURRENCE_MARKER)?;
+    let lower = after.to_lowercase();
+    let mut words = lower.split_whitespace();
+    let first = words.next()?;
+    let single = |unit| Some(Recurrence { every: 1, unit });
+    match first {
+        "daily" => return single(Unit::Day),
+        "weekly" => return single(Unit::Week),
+        "monthly" => return single(Unit::Month),
+        "yearly" => return single(Unit::Year),
+        "every" => {}
+        _ => return None,
+    }
+    let mut word = words.next()?;
+    let every = match word.parse::<u32>() {
+        Ok(n) if n > 0 => {
+            word = words.next()?;
+            n
+        }
+        Ok(_) => return None,
+        Err(_) => 1,
+    };
+    let unit = match word.trim_end_matches([',', '.']) {
+        "day" | "days" => Unit::Day,
+        "week" | "weeks" => Unit::Week,
+        "month" | "months" => Unit::Month,
+        "year" | "years" => Unit::Year,
+        _ => return None,
+    };
+    Some(Recurrence { every, unit })
+}
+
+/// Length of the `- [ ]` / `* [x]` prefix of a task line (with its indentation), and
+/// whether it is checked.
+fn checkbox(line: &str) -> Option<(usize, bool)> {
+    let rest = line.trim_start();
+    let indent = line.len() - rest.len();
+    let rest = rest.strip_prefix(['-', '*'])?;
+    let spaces = rest.len() - rest.trim_start().len();
+    if spaces == 0 {
+        return None;
+    }
+    let rest = &rest[spaces..];
+    let (len, checked) = if rest.starts_with("[ ]") {
+        (3, false)
+    } else if rest.starts_with("[x]") || rest.starts_with("[X]") {
+        (3, true)
+    } else if rest.starts_with("[]") {
+        (2, false)
+    } else {
+        return None;
+    };
+    Some((indent + 1 + spaces + len, checked))
+}
+
+/// Lines that replace a task line when it is checked or unchecked, or `None` when `line` is
+/// not a task. Checking a task that recurs (with `recurring` on) also adds an unchecked
+/// copy above it, due one recurrence after its due date, or after `today` if it had none.
+pub fn toggle(line: &str, today: NaiveDate, recurring: bool) -> Option<Vec<String>> {
+    let (prefix_len, checked) = checkbox(line)?;
+    let prefix = &line[..prefix_len];
+    let box_start = prefix.rfind('[')?;
+    let rest = &line[prefix_len..];
+    let mark = if checked { "[ ]" } else { "[x]" };
+    let toggled = format!("{}{mark}{rest}", &prefix[..box_start]);
+    let Some(every) = recurrence(line).filter(|_| recurring && !checked) else {
+        return Some(vec![toggled]);
+    };
+    let unchecked = format!("{}[ ]{rest}", &prefix[..box_start]);
+    let next = every
+        .next(due_date(line).unwrap_or(today))
+        .format(DATE_FORMAT);
+    let fresh = match due_date(line) {
+        Some(due) => unchecked.replacen(&due.format(DATE_FORMAT).to_string(), &next.to_string(), 1),
+        None => format!("{} {DUE_MARKER} {next}", unchecked.trim_end()),
+    };
+    Some(vec![fresh, toggled])
+}
+
+/// `#tags` in `line`: where each starts (at its `#`) and its name.
+fn tags(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.char_indices().filter_map(move |(i, c)| {
+        let starts_word = line[..i]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        if c != '#' || !starts_word {
+            return None;
+        }
+        let rest = &line[i + 1..];
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '/')))
+            .unwrap_or(rest.len());
+        (len > 0).then(|| (i, &rest[..len]))
+    })
+}
+
+/// Where the status tag of a task line is (start of its `#tag` and its name), and the
+/// index of its column in `columns` (tag names, with or without `#`).
+fn status(line: &str, columns: &[String]) -> Option<(usize, usize, usize)> {
+    tags(line).find_map(|(start, name)| {
+        let column = columns
+            .iter()
+            .position(|c| c.trim_start_matches('#').eq_ignore_ascii_case(name))?;
+        Some((start, start + 1 + name.len(), column))
+    })
+}
+
+/// Board column of a task line: the first of `columns` it has as a `#tag`, else the first.
+pub fn column(line: &str, columns: &[String]) -> usize {
+    status(line, columns).map_or(0, |(_, _, column)| column)
+}
+
+/// `line` moved to column `to` of `columns`: its status tag replaced, or added at the end
+/// when it has none.
+pub fn set_column(line: &str, columns: &[String], to: usize) -> String {
+    let tag = format!("#{}", columns[to].trim_start_matches('#'));
+    match status(line, columns) {
+        Some((start, end, _)) => format!("{}{tag}{}", &line[..start], &line[end..]),
+        None => format!("{} {tag}", line.trim_end()),
+    }
+}
+
+/// Tasks of one note, for the `☑ 3/7` badge.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskCounts {
+    pub done: usize,
+    pub total: usize,
+    /// Earliest due date of the unchecked tasks.
+    pub next_due: Option<NaiveDate>,
+}
+
+impl TaskCounts {
+    /// Whether an unchecked task was due before `today`.
+    pub fn overdue(&self, today: NaiveDate) -> bool {
+        self.next_due.is_some_and(|due| due < today)
+    }
+
+    /// `☑ 3/7`: checked tasks out of all, or `None` for a note without tasks.
+    pub fn badge(&self) -> Option<String> {
+        (self.total > 0).then(|| format!("☑ {}/{}", self.done, self.total))
+    }
+}
+
+/// Tasks of a note's `content`, outside fenced code blocks.
+pub fn count(content: &str) -> TaskCounts {
+    let mut counts = TaskCounts::default();
+    let mut in_code_block = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        let Some((_, checked)) = checkbox(line).filter(|_| !in_code_block) else {
+            continue;
+        };
+        counts.total += 1;
+        if checked {
+            counts.done += 1;
+        } else if let Some(due) = due_date(line) {
+            counts.next_due = Some(counts.next_due.map_or(due, |d| d.min(due)));
+        }
+    }
+    counts
+}
+
+/// Today in the local time zone, for `toggle`.
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, DATE_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn parses_recurrence_phrases() {
+        let every = |every, unit| Some(Recurrence { every, unit });
+        assert_eq!(recurrence("- [ ] a 🔁 every day"), every(1, Unit::Day));
+        assert_eq!(
+            recurrence("- [ ] a 🔁 every week 📅 2024-01-01"),
+            every(1, Unit::Week)
+        );
+        assert_eq!(recurrence("- [ ] a 🔁 every month"), every(1, Unit::Month));
+        assert_eq!(recurrence("- [ ] a 🔁 every year"), every(1, Unit::Year));
+        assert_eq!(recurrence("- [ ] a 🔁 every 2 weeks"), every(2, Unit::Week));
+        assert_eq!(recurrence("- [ ] a 🔁 Every 3 Days"), every(3, Unit::Day));
+        assert_eq!(recurrence("- [ ] a 🔁 weekly"), every(1, Unit::Week));
+        assert_eq!(recurrence("- [ ] a 🔁 every 0 days"), None);
+        assert_eq!(recurrence("- [ ] a 🔁 every fortnight"), None);
+        assert_eq!(recurrence("- [ ] a every week"), None);
+    }
+
+    #[test]
+    fn parses_due_dates() {
+        assert_eq!(
+            due_date("- [ ] a 📅 2024-03-14 🔁 every day"),
+            Some(date("2024-03-14"))
+        );
+        assert_eq!(due_date("- [ ] a 📅 friday"), None);
+        assert_eq!(due_date("- [ ] a"), None);
+    }
+
+    #[test]
+    fn months_roll_over_to_the_last_day() {
+        let month = Recurrence {
+            every: 1,
+            unit: Unit::Month,
+        };
+        assert_eq!(month.next(date("2024-01-31")), date("2024-02-29"));
+        assert_eq!(month.next(date("2023-01-31")), date("2023-02-28"));
+        assert_eq!(month.next(date("2024-12-15")), date("2025-01-15"));
+        let year = Recurrence {
+            every: 1,
+            unit: Unit::Year,
+        };
+        assert_eq!(year.next(date("2024-02-29")), date("2025-02-28"));
+        let weeks = Recurrence {
+            every: 2,
+            unit: Unit::Week,
+        };
+        assert_eq!(weeks.next(date("2024-12-25")), date("2025-01-08"));
+    }
+
+    #[test]
+    fn toggling_a_recurring_task_adds_the_next_one() {
+        let today = date("2024-05-01");
+        assert_eq!(
+            toggle(
+                "  - [ ] water plants 🔁 every week 📅 2024-03-14",
+                today,
+                true
+            )
+            .unwrap(),
+            [
+                "  - [ ] water plants 🔁 every week 📅 2024-03-21",
+                "  - [x] water plants 🔁 every week 📅 2024-03-14",
+            ]
+        );
+        assert_eq!(
+            toggle("* [ ] pay rent 🔁 every month", today, true).unwrap(),
+            [
+                "* [ ] pay rent 🔁 every month 📅 2024-06-01",
+                "* [x] pay rent 🔁 every month"
+            ]
+        );
+        assert_eq!(
+            toggle("- [ ] a 🔁 every day", today, false).unwrap(),
+            ["- [x] a 🔁 every day"]
+        );
+        assert_eq!(
+            toggle("- [X] done 🔁 every day", today, true).unwrap(),
+            ["- [ ] done 🔁 every day"]
+        );
+        assert_eq!(toggle("- [] empty", today, true).unwrap(), ["- [x] empty"]);
+        assert_eq!(toggle("- not a task", today, true), None);
+        assert_eq!(toggle("-[ ] no space", today, true), None);
+    }
+
+    #[test]
+    fn counts_tasks_and_the_next_due_date() {
+        let content = "- [x] a 📅 2024-01-01\n- [ ] b 📅 2024-03-14\n  * [ ] c 📅 2024-02-01\n\
+                       ```\n- [ ] in code\n```\n- [ ] d\n";
+        let counts = count(content);
+        assert_eq!((counts.done, counts.total), (1, 4));
+        assert_eq!(counts.next_due, Some(date("2024-02-01")));
+        assert_eq!(counts.badge().as_deref(), Some("☑ 1/4"));
+        assert!(counts.overdue(date("2024-02-02")));
+        assert!(!counts.overdue(date("2024-02-01")));
+        assert_eq!(count("no tasks").badge(), None);
+    }
+
+    #[test]
+    fn status_tags_pick_and_move_columns() {
+        let columns = ["todo", "#doing", "done"].map(String::from);
+        assert_eq!(column("- [ ] plan #doing", &columns), 1);
+        assert_eq!(column("- [ ] plan #Done #todo", &columns), 2);
+        assert_eq!(column("- [ ] plan #doings issue#done", &columns), 0);
+        assert_eq!(column("- [ ] plan", &columns), 0);
+        assert_eq!(
+            set_column("- [ ] plan #todo 📅 2024-03-14", &columns, 1),
+            "- [ ] plan #doing 📅 2024-03-14"
+        );
+        assert_eq!(
+            set_column("- [ ] plan #work ", &columns, 2),
+            "- [ ] plan #work #done"
+        );
+    }
+}
@@ -1,39 +1,78 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
-// oxid - Telescope-style fuzzy file search (Space+f)
+// oxid - Telescope-style fuzzy file search (Space+f) and open-buffer search
 
-use crate::app::NoteEntry;
-use crate::frontmatter::parse_tags;
-use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
-use nucleo_matcher::{Matcher, Utf32Str};
+use crate::app::{is_binary_file, NoteEntry};
+use crate::crypt;
+use crate::frontmatter::{parse_aliases, parse_tags};
+use crate::recent::RecentFiles;
+use crate::search::{find_text, get_match_indices, regex_match_range, regex_query, CaseMode};
+use crate::vault::Walk;
+use nucleo_matcher::Matcher;
 use std::fs;
 use std::io::Read;
 use std::path::Path;
-use walkdir::WalkDir;
 
 const MAX_CONTENT_BYTES: usize = 50_000;
 
-/// Recursively find all .md files under a directory.
-pub fn find_md_files_recursive(dir: &Path) -> Vec<NoteEntry> {
+/// Lines shown at most by a buffer search.
+const MAX_BUFFER_MATCHES: usize = 1000;
+
+/// What telescope searches; `keys.telescope_mode` cycles through them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TelescopeMode {
+    /// Note names and content, on disk.
+    #[default]
+    Files,
+    /// Lines of the open buffers, including unsaved edits.
+    Buffers,
+}
+
+impl TelescopeMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Files => Self::Buffers,
+            Self::Buffers => Self::Files,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Files => "Open File",
+            Self::Buffers => "Search Open Buffers",
+        }
+    }
+}
+
+/// A line of an open buffer that contains the buffer search query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferMatch {
+    pub buffer_id: u64,
+    /// Tab name of the buffer.
+    pub tab: String,
+    pub row: usize,
+    /// Character columns where the query starts and ends in `text`.
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// All .md files found by a walk of `dir` (every file when `include_all` is set).
+pub fn find_md_files_recursive(dir: &Path, walk: &Walk, include_all: bool) -> Vec<NoteEntry> {
     let mut notes = Vec::new();
-    for entry in WalkDir::new(dir)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(std::result::Result::ok)
-    {
-        let path = entry.path();
-        if path.is_file() {
-            if let Some(ext) = path.extension() {
-                if ext == "md" {
-                    let display = path.strip_prefix(dir).unwrap_or(path).display().to_string();
-                    let (content, searchable) = read_note_content(path, &display);
-                    notes.push(NoteEntry::new(
-                        path.to_path_buf(),
-                        display,
-                        content,
-                        searchable,
-                    ));
-                }
-            }
+    for path in walk.files() {
+        if include_all || path.extension().is_some_and(|ext| ext == "md") {
+            let display = path.strip_prefix(dir).unwrap_or(path).display().to_string();
+            let (content, searchable) = if is_binary_file(path) || crypt::is_encrypted(path) {
+                (String::new(), display.clone())
+            } else {
+                read_note_content(path, &display)
+            };
+            notes.push(NoteEntry::new(
+                path.to_path_buf(),
+                display,
+                content,
+                searchable,
+            ));
         }
     }
     notes.sort_by_key(|a| a.display.to_lowercase());
@@ -50,56 +89,155 @@ fn read_note_content(path: &Path, display: &str) -> (String, String) {
     let content = String::from_utf8_lossy(&buf).into_owned();
     let tags = parse_tags(&content);
     let tag_str: String = tags.into_iter().collect::<Vec<_>>().join(" ");
-    let searchable = format!("{display}\n{content}\n{tag_str}");
+    let alias_str = parse_aliases(&content)
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let searchable = format!("{display}\n{content}\n{tag_str}\n{alias_str}");
     (content, searchable)
 }
 
-/// Filter notes: if query starts with #, filter by tag; else fuzzy match.
+/// Filter notes: if query starts with #, filter by tag; with `re:`, by a regular expression
+/// (nothing when it does not compile); else fuzzy match. Among equal scores, recently
+/// opened notes come first.
 pub fn filter_telescope_notes(
     notes: &[NoteEntry],
     query: &str,
+    case: CaseMode,
     matcher: &mut Matcher,
+    recent: &RecentFiles,
 ) -> Vec<NoteEntry> {
+    let recency = |n: &NoteEntry| recent.rank(&n.path).unwrap_or(usize::MAX);
     let query = query.trim();
-    if query.is_empty() {
-        return notes.to_vec();
+    let tag_query = query
+        .strip_prefix('#')
+        .map(|rest| rest.trim().to_lowercase());
+    if query.is_empty() || tag_query.as_ref().is_some_and(String::is_empty) {
+        let mut all = notes.to_vec();
+        all.sort_by_key(|n| recency(n));
+        return all;
     }
 
-    if let Some(rest) = query.strip_prefix('#') {
-        let tag = rest.trim().to_lowercase();
-        if tag.is_empty() {
-            return notes.to_vec();
-        }
-        return notes
+    if let Some(regex) = regex_query(query, case) {
+        let Ok(re) = regex else { return Vec::new() };
+        let mut found: Vec<NoteEntry> = notes
+            .iter()
+            .filter(|n| re.is_match(n.as_ref()))
+            .cloned()
+            .collect();
+        found.sort_by_key(|n| recency(n));
+        return found;
+    }
+
+    if let Some(tag) = tag_query {
+        let mut tagged: Vec<NoteEntry> = notes
             .iter()
             .filter(|n| {
-                let tags = parse_tags(&n.content);
+                let tags = parse_tags(n.content.text());
                 tags.iter().any(|t| t.to_lowercase() == tag)
             })
             .cloned()
             .collect();
+        tagged.sort_by_key(|n| recency(n));
+        return tagged;
     }
 
-    let pattern = Pattern::parse(query, CaseMatching::Ignore, Normalization::Smart);
-    let results = pattern.match_list(notes, matcher);
+    let mut results = case.pattern(query).match_list(notes, matcher);
+    results.sort_by(|(a, score_a), (b, score_b)| {
+        score_b
+            .cmp(score_a)
+            .then_with(|| recency(a).cmp(&recency(b)))
+    });
     results
         .into_iter()
         .map(|(entry, _)| entry.clone())
         .collect()
 }
 
-/// Get match indices for telescope list highlighting.
-pub fn get_telescope_match_indices(display: &str, query: &str, matcher: &mut Matcher) -> Vec<u32> {
-    if query.is_empty() || query.starts_with('#') || display.is_empty() {
+/// Lines of `buffers` (ID, tab name and lines) containing `query`, ignoring case unless
+/// `case` respects it, or matching it as a regular expression after `re:`, in tab and line
+/// order. Each line counts once, at its first match.
+pub fn search_buffers<'a>(
+    buffers: impl IntoIterator<Item = (u64, String, &'a [String])>,
+    query: &str,
+    case: CaseMode,
+) -> Vec<BufferMatch> {
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+    let regex = match regex_query(query, case) {
+        Some(Ok(re)) => Some(re),
+        Some(Err(_)) => return matches,
+        None => None,
+    };
+    let len = query.chars().count();
+    for (buffer_id, tab, lines) in buffers {
+        for (row, line) in lines.iter().enumerate() {
+            let found = match &regex {
+                Some(re) => regex_match_range(line, re),
+                None => find_text(line, query, case).map(|start| start..start + len),
+            };
+            let Some(found) = found else { continue };
+            matches.push(BufferMatch {
+                buffer_id,
+                tab: tab.clone(),
+                row,
+                start: found.start,
+                end: found.end,
+                text: line.clone(),
+            });
+            if matches.len() == MAX_BUFFER_MATCHES {
+                return matches;
+            }
+        }
+    }
+    matches
+}
+
+/// Get match indices for telescope list highlighting; tag queries highlight nothing.
+pub fn get_telescope_match_indices(
+    display: &str,
+    query: &str,
+    case: CaseMode,
+    matcher: &mut Matcher,
+) -> Vec<u32> {
+    if query.starts_with('#') {
         return Vec::new();
     }
-    let pattern = Pattern::parse(query, CaseMatching::Ignore, Normalization::Smart);
-    let mut buf = Vec::new();
-    let haystack = Utf32Str::new(display, &mut buf);
-    let mut indices = Vec::new();
-    if pattern.indices(haystack, matcher, &mut indices).is_some() {
-        indices.sort_unstable();
-        indices.dedup();
+    get_match_indices(display, query, case, matcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_search_finds_lines_ignoring_case() {
+        let a = ["Intro".to_string(), "The Big Idea, big".to_string()];
+        let b = ["ÄÖ big".to_string(), "nothing".to_string()];
+        let matches = search_buffers(
+            [
+                (1, "a.md".to_string(), &a[..]),
+                (2, "b.md".to_string(), &b[..]),
+            ],
+            "BIG",
+            CaseMode::Ignore,
+        );
+        let found: Vec<(u64, usize, usize, usize)> = matches
+            .iter()
+            .map(|m| (m.buffer_id, m.row, m.start, m.end))
+            .collect();
+        assert_eq!(found, [(1, 1, 4, 7), (2, 0, 3, 6)]);
+        assert_eq!(matches[1].tab, "b.md");
+        assert!(search_buffers([(1, "a.md".to_string(), &a[..])], "", CaseMode::Ignore).is_empty());
+
+        let search_b = |query, case| search_buffers([(2, "b.md".to_string(), &b[..])], query, case);
+        let matches = search_b(r"re:\bb\w+", CaseMode::Ignore);
+        assert_eq!((matches[0].start, matches[0].end), (3, 6));
+        assert!(search_b("re:(", CaseMode::Ignore).is_empty());
+        assert!(search_b("BIG", CaseMode::Sensitive).is_empty());
+        assert!(search_b("BIG", CaseMode::Smart).is_empty());
+        assert_eq!(search_b("big", CaseMode::Smart).len(), 1);
     }
-    indices
 }
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - One-line text field for prompts, with a cursor that moves
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use std::ops::Deref;
+
+/// What a key did to a `TextInput`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputChange {
+    /// Not an editing key; the caller may use it.
+    Ignored,
+    /// An editing key that left the text as it was: the cursor moved, or there was nothing
+    /// to delete.
+    Moved,
+    /// The text changed.
+    Edited,
+}
+
+/// Text typed into a prompt and the cursor in it, as a character index. Reads as a `str`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+}
+
+impl TextInput {
+    /// A field holding `text`, with the cursor at its end.
+    pub fn new(text: impl Into<String>) -> Self {
+        let mut input = Self::default();
+        input.set(text);
+        input
+    }
+
+    /// Replace the text and put the cursor at its end.
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.chars().count();
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset of character `index`, or the end of the text.
+    fn byte_offset(&self, index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(index)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let at = self.byte_offset(self.cursor);
+        self.text.insert(at, c);
+        self.cursor += 1;
+    }
+
+    pub fn insert_str(&mut self, s: &str) {
+        let at = self.byte_offset(self.cursor);
+        self.text.insert_str(at, s);
+        self.cursor += s.chars().count();
+    }
+
+    /// Remove the character before the cursor; false at the start.
+    pub fn backspace(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.cursor -= 1;
+        self.text.remove(self.byte_offset(self.cursor));
+        true
+    }
+
+    /// Remove the character under the cursor; false at the end.
+    pub fn delete(&mut self) -> bool {
+        let at = self.byte_offset(self.cursor);
+        if at == self.text.len() {
+            return false;
+        }
+        self.text.remove(at);
+        true
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.text.chars().count());
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.text.chars().count();
+    }
+
+    /// Edit with `key`: a character is typed at the cursor, Backspace and Delete remove one,
+    /// and Left, Right, Home and End move the cursor. Characters with ctrl or alt are
+    /// shortcuts, not text, and are ignored, as is any other key.
+    pub fn handle_key(&mut self, key: KeyEvent) -> InputChange {
+        let edited = |changed| {
+            if changed {
+                InputChange::Edited
+            } else {
+                InputChange::Moved
+            }
+        };
+        let chord = key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char(_) if chord => InputChange::Ignored,
+            KeyCode::Char(c) => {
+                self.insert(c);
+                InputChange::Edited
+            }
+            KeyCode::Backspace => edited(self.backspace()),
+            KeyCode::Delete => edited(self.delete()),
+            KeyCode::Left => {
+                self.move_left();
+                InputChange::Moved
+            }
+            KeyCode::Right => {
+                self.move_right();
+                InputChange::Moved
+            }
+            KeyCode::Home => {
+                self.move_home();
+                InputChange::Moved
+            }
+            KeyCode::End => {
+                self.move_end();
+                InputChange::Moved
+            }
+            _ => InputChange::Ignored,
+        }
+    }
+
+    /// The text before the cursor, the character under it (if any), and the text after.
+    pub fn split_at_cursor(&self) -> (&str, Option<char>, &str) {
+        let at = self.byte_offset(self.cursor);
+        let (before, rest) = self.text.split_at(at);
+        let mut chars = rest.chars();
+        let under = chars.next();
+        (before, under, chars.as_str())
+    }
+}
+
+impl Deref for TextInput {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for TextInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl PartialEq<&str> for TextInput {
+    fn eq(&self, other: &&str) -> bool {
+        self.text == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn edits_at_the_cursor() {
+        let mut input = TextInput::new("nöte.md");
+        for _ in 0..3 {
+            input.handle_key(key(KeyCode::Left, KeyModifiers::NONE));
+        }
+        assert_eq!(input.split_at_cursor(), ("nöte", Some('.'), "md"));
+        let backspace = key(KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(input.handle_key(backspace), InputChange::Edited);
+        input.handle_key(key(KeyCode::Char('E'), KeyModifiers::SHIFT));
+        assert_eq!(input, "nötE.md");
+        assert_eq!(input.split_at_cursor(), ("nötE", Some('.'), "md"));
+
+        input.handle_key(key(KeyCode::Home, KeyModifiers::NONE));
+        assert_eq!(input.handle_key(backspace), InputChange::Moved);
+        input.handle_key(key(KeyCode::Delete, KeyModifiers::NONE));
+        input.insert_str("my ");
+        assert_eq!(input.split_at_cursor(), ("my ", Some('ö'), "tE.md"));
+        input.handle_key(key(KeyCode::End, KeyModifiers::NONE));
+        input.handle_key(key(KeyCode::Right, KeyModifiers::NONE));
+        assert_eq!(input.split_at_cursor(), ("my ötE.md", None, ""));
+    }
+
+    #[test]
+    fn chords_are_not_typed() {
+        let mut input = TextInput::default();
+        for modifiers in [KeyModifiers::CONTROL, KeyModifiers::ALT] {
+            let change = input.handle_key(key(KeyCode::Char('h'), modifiers));
+            assert_eq!(change, InputChange::Ignored);
+        }
+        assert_eq!(
+            input.handle_key(key(KeyCode::Up, KeyModifiers::NONE)),
+            InputChange::Ignored
+        );
+        assert!(input.is_empty());
+    }
+}
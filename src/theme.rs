@@ -3,7 +3,7 @@
 
 use anyhow::{Context, Result};
 use ratatui::style::{Color, Modifier, Style};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 use std::str::FromStr;
@@ -14,7 +14,7 @@ fn def(s: &str) -> ColorDef {
 
 /// Visual theme configuration loaded from theme.toml.
 /// Every visible color in the TUI is configurable.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct Theme {
     pub app_background: ColorDef,
@@ -52,10 +52,19 @@ pub struct Theme {
     pub editor_line_number: ColorDef,
     #[serde(rename = "md_header_fg")]
     pub md_header_fg: ColorDef,
+    /// Per-level heading colors; unset levels fall back to `md_header_fg`.
+    pub md_h1: Option<ColorDef>,
+    pub md_h2: Option<ColorDef>,
+    pub md_h3: Option<ColorDef>,
+    pub md_h4: Option<ColorDef>,
+    pub md_h5: Option<ColorDef>,
+    pub md_h6: Option<ColorDef>,
     #[serde(rename = "md_code_bg")]
     pub md_code_bg: ColorDef,
     #[serde(rename = "md_list_marker")]
     pub md_list_marker: ColorDef,
+    #[serde(rename = "md_blockquote")]
+    pub md_blockquote: ColorDef,
     #[serde(rename = "editor_header")]
     pub editor_header: ColorDef,
     #[serde(rename = "editor_list")]
@@ -74,6 +83,18 @@ pub struct Theme {
     pub statusbar_bg: ColorDef,
     #[serde(rename = "statusbar_fg")]
     pub statusbar_fg: ColorDef,
+    /// Added and removed lines in the diff view.
+    pub diff_add: ColorDef,
+    pub diff_remove: ColorDef,
+    /// Footer mode badges; unset they fall back to `highlight` (normal) and `header` (insert).
+    pub mode_normal: Option<ColorDef>,
+    pub mode_insert: Option<ColorDef>,
+    /// `☑ 3/7` task badges in the notes list and preview title, and the badge of a note
+    /// with overdue tasks; unset they fall back to `editor_checkbox` and `diff_remove`.
+    pub task_badge: Option<ColorDef>,
+    pub task_overdue: Option<ColorDef>,
+    /// Text of unfocused panes with `ui.dim_inactive`; unset dims their own colors.
+    pub inactive_text: Option<ColorDef>,
 }
 
 impl Default for Theme {
@@ -99,8 +120,15 @@ impl Default for Theme {
             editor_cursor: def("cyan"),
             editor_line_number: def("dark_gray"),
             md_header_fg: def("yellow"),
+            md_h1: None,
+            md_h2: None,
+            md_h3: None,
+            md_h4: None,
+            md_h5: None,
+            md_h6: None,
             md_code_bg: def("dark_gray"),
             md_list_marker: def("cyan"),
+            md_blockquote: def("dark_gray"),
             editor_header: def("blue"),
             editor_list: def("yellow"),
             editor_checkbox: def("yellow"),
@@ -110,12 +138,608 @@ impl Default for Theme {
             editor_code_keyword: def("magenta"),
             statusbar_bg: def("black"),
             statusbar_fg: def("white"),
+            diff_add: def("green"),
+            diff_remove: def("red"),
+            mode_normal: None,
+            mode_insert: None,
+            task_badge: None,
+            task_overdue: None,
+            inactive_text: None,
+        }
+    }
+}
+
+/// Names accepted by `preset` under `[theme]` in config.toml, in `CycleTheme` order. Each
+/// scheme comes as a dark variant followed by its light one.
+pub const THEME_PRESETS: &[&str] = &[
+    "default",
+    "default-light",
+    "gruvbox-dark",
+    "gruvbox-light",
+    "catppuccin-mocha",
+    "catppuccin-latte",
+    "nord",
+    "nord-light",
+    "solarized-dark",
+    "solarized-light",
+];
+
+/// Which variant of a preset is used, and which section of theme.toml applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeMode {
+    Dark,
+    Light,
+}
+
+impl ThemeMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Dark => Self::Light,
+            Self::Light => Self::Dark,
+        }
+    }
+
+    /// The variant `preset` is; unknown names count as the default (dark) preset.
+    pub fn of_preset(preset: &str) -> Self {
+        match THEME_PRESETS.iter().position(|p| *p == preset.trim()) {
+            Some(i) if i % 2 == 1 => Self::Light,
+            _ => Self::Dark,
+        }
+    }
+
+    /// Mode for `theme.mode` in config.toml: "dark" or "light", or for "auto" the first
+    /// word `mode_command` prints, else the terminal background named by `$COLORFGBG`. Any
+    /// other value, and an auto mode that finds nothing, keeps the variant of `preset`.
+    pub fn from_config(config: &crate::config::ThemeConfig) -> Self {
+        let mode = match config.mode.trim() {
+            "dark" => Some(Self::Dark),
+            "light" => Some(Self::Light),
+            "auto" => mode_command_output(&config.mode_command)
+                .as_deref()
+                .and_then(Self::parse_word)
+                .or_else(|| {
+                    let colorfgbg = std::env::var("COLORFGBG").ok()?;
+                    Self::from_colorfgbg(&colorfgbg)
+                }),
+            _ => None,
+        };
+        mode.unwrap_or_else(|| Self::of_preset(&config.preset))
+    }
+
+    /// "dark" or "light" as the first word of `text`, in any case.
+    fn parse_word(text: &str) -> Option<Self> {
+        match text.split_whitespace().next()?.to_lowercase().as_str() {
+            "dark" => Some(Self::Dark),
+            "light" => Some(Self::Light),
+            _ => None,
+        }
+    }
+
+    /// Mode for a `$COLORFGBG` value such as "15;0" or "0;default;15": the last field is the
+    /// background's palette index, where 7 and 9 to 15 are light colors.
+    fn from_colorfgbg(value: &str) -> Option<Self> {
+        let bg: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+        Some(if bg == 7 || (9..=15).contains(&bg) {
+            Self::Light
+        } else {
+            Self::Dark
+        })
+    }
+}
+
+/// What `command` prints, run with `sh -c`; `None` when it is empty or fails.
+fn mode_command_output(command: &str) -> Option<String> {
+    if command.trim().is_empty() {
+        return None;
+    }
+    let output = std::process::Command::new("sh")
+        .args(["-c", command])
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The `mode` variant of `preset`'s scheme; unknown names use the default scheme.
+pub fn preset_variant(preset: &str, mode: ThemeMode) -> &'static str {
+    let scheme = THEME_PRESETS
+        .iter()
+        .position(|p| *p == preset.trim())
+        .map_or(0, |i| i / 2);
+    THEME_PRESETS[scheme * 2 + usize::from(mode == ThemeMode::Light)]
+}
+
+impl Theme {
+    /// Built-in color scheme by name (see `THEME_PRESETS`). An empty name is the default.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.trim() {
+            "" | "default" => Some(Self::default()),
+            "default-light" => Some(Self::default_light()),
+            "gruvbox-dark" => Some(Self::gruvbox_dark()),
+            "gruvbox-light" => Some(Self::gruvbox_light()),
+            "catppuccin-mocha" => Some(Self::catppuccin_mocha()),
+            "catppuccin-latte" => Some(Self::catppuccin_latte()),
+            "nord" => Some(Self::nord()),
+            "nord-light" => Some(Self::nord_light()),
+            "solarized-dark" => Some(Self::solarized_dark()),
+            "solarized-light" => Some(Self::solarized_light()),
+            _ => None,
+        }
+    }
+
+    /// Gruvbox (dark).
+    fn gruvbox_dark() -> Self {
+        Self {
+            app_background: def("#282828"),
+            border: def("#83a598"),
+            header: def("#fabd2f"),
+            highlight: def("#d3869b"),
+            text: def("#ebdbb2"),
+            list_border_active: def("#83a598"),
+            list_border_inactive: def("#504945"),
+            list_text_selected_fg: def("#b8bb26"),
+            list_text_selected_bg: def("#3c3836"),
+            list_text_normal: def("#ebdbb2"),
+            preview_border_active: def("#8ec07c"),
+            preview_border_inactive: def("#504945"),
+            preview_text: def("#ebdbb2"),
+            search_match: def("#fb4934"),
+            help_text: def("#a89984"),
+            editor_bg: def("#282828"),
+            editor_fg: def("#ebdbb2"),
+            editor_cursor: def("#fe8019"),
+            editor_line_number: def("#665c54"),
+            md_header_fg: def("#fabd2f"),
+            md_h1: Some(def("#fb4934")),
+            md_h2: Some(def("#fe8019")),
+            md_h3: Some(def("#fabd2f")),
+            md_h4: Some(def("#b8bb26")),
+            md_h5: Some(def("#8ec07c")),
+            md_h6: Some(def("#83a598")),
+            md_code_bg: def("#3c3836"),
+            md_list_marker: def("#8ec07c"),
+            md_blockquote: def("#928374"),
+            editor_header: def("#fabd2f"),
+            editor_list: def("#8ec07c"),
+            editor_checkbox: def("#fe8019"),
+            editor_checkbox_checked: def("#b8bb26"),
+            list_directory: def("#83a598"),
+            editor_code_block: def("#8ec07c"),
+            editor_code_keyword: def("#d3869b"),
+            statusbar_bg: def("#3c3836"),
+            statusbar_fg: def("#ebdbb2"),
+            diff_add: def("#b8bb26"),
+            diff_remove: def("#fb4934"),
+            mode_normal: None,
+            mode_insert: None,
+            task_badge: None,
+            task_overdue: None,
+            inactive_text: None,
+        }
+    }
+
+    /// Catppuccin Mocha.
+    fn catppuccin_mocha() -> Self {
+        Self {
+            app_background: def("#1e1e2e"),
+            border: def("#89b4fa"),
+            header: def("#f9e2af"),
+            highlight: def("#cba6f7"),
+            text: def("#cdd6f4"),
+            list_border_active: def("#89b4fa"),
+            list_border_inactive: def("#45475a"),
+            list_text_selected_fg: def("#a6e3a1"),
+            list_text_selected_bg: def("#313244"),
+            list_text_normal: def("#cdd6f4"),
+            preview_border_active: def("#b4befe"),
+            preview_border_inactive: def("#45475a"),
+            preview_text: def("#cdd6f4"),
+            search_match: def("#f38ba8"),
+            help_text: def("#a6adc8"),
+            editor_bg: def("#1e1e2e"),
+            editor_fg: def("#cdd6f4"),
+            editor_cursor: def("#f5e0dc"),
+            editor_line_number: def("#6c7086"),
+            md_header_fg: def("#f9e2af"),
+            md_h1: Some(def("#f38ba8")),
+            md_h2: Some(def("#fab387")),
+            md_h3: Some(def("#f9e2af")),
+            md_h4: Some(def("#a6e3a1")),
+            md_h5: Some(def("#74c7ec")),
+            md_h6: Some(def("#b4befe")),
+            md_code_bg: def("#313244"),
+            md_list_marker: def("#94e2d5"),
+            md_blockquote: def("#7f849c"),
+            editor_header: def("#89b4fa"),
+            editor_list: def("#f9e2af"),
+            editor_checkbox: def("#fab387"),
+            editor_checkbox_checked: def("#a6e3a1"),
+            list_directory: def("#89b4fa"),
+            editor_code_block: def("#94e2d5"),
+            editor_code_keyword: def("#cba6f7"),
+            statusbar_bg: def("#181825"),
+            statusbar_fg: def("#cdd6f4"),
+            diff_add: def("#a6e3a1"),
+            diff_remove: def("#f38ba8"),
+            mode_normal: None,
+            mode_insert: None,
+            task_badge: None,
+            task_overdue: None,
+            inactive_text: None,
+        }
+    }
+
+    /// Nord.
+    fn nord() -> Self {
+        Self {
+            app_background: def("#2e3440"),
+            border: def("#88c0d0"),
+            header: def("#ebcb8b"),
+            highlight: def("#b48ead"),
+            text: def("#d8dee9"),
+            list_border_active: def("#88c0d0"),
+            list_border_inactive: def("#4c566a"),
+            list_text_selected_fg: def("#a3be8c"),
+            list_text_selected_bg: def("#3b4252"),
+            list_text_normal: def("#d8dee9"),
+            preview_border_active: def("#81a1c1"),
+            preview_border_inactive: def("#4c566a"),
+            preview_text: def("#d8dee9"),
+            search_match: def("#bf616a"),
+            help_text: def("#e5e9f0"),
+            editor_bg: def("#2e3440"),
+            editor_fg: def("#d8dee9"),
+            editor_cursor: def("#88c0d0"),
+            editor_line_number: def("#4c566a"),
+            md_header_fg: def("#88c0d0"),
+            md_h1: Some(def("#8fbcbb")),
+            md_h2: Some(def("#88c0d0")),
+            md_h3: Some(def("#81a1c1")),
+            md_h4: Some(def("#5e81ac")),
+            md_h5: Some(def("#b48ead")),
+            md_h6: Some(def("#d08770")),
+            md_code_bg: def("#3b4252"),
+            md_list_marker: def("#8fbcbb"),
+            md_blockquote: def("#616e88"),
+            editor_header: def("#81a1c1"),
+            editor_list: def("#ebcb8b"),
+            editor_checkbox: def("#d08770"),
+            editor_checkbox_checked: def("#a3be8c"),
+            list_directory: def("#81a1c1"),
+            editor_code_block: def("#8fbcbb"),
+            editor_code_keyword: def("#b48ead"),
+            statusbar_bg: def("#3b4252"),
+            statusbar_fg: def("#eceff4"),
+            diff_add: def("#a3be8c"),
+            diff_remove: def("#bf616a"),
+            mode_normal: None,
+            mode_insert: None,
+            task_badge: None,
+            task_overdue: None,
+            inactive_text: None,
+        }
+    }
+
+    /// Solarized (light).
+    fn solarized_light() -> Self {
+        Self {
+            app_background: def("#fdf6e3"),
+            border: def("#268bd2"),
+            header: def("#b58900"),
+            highlight: def("#d33682"),
+            text: def("#657b83"),
+            list_border_active: def("#268bd2"),
+            list_border_inactive: def("#93a1a1"),
+            list_text_selected_fg: def("#859900"),
+            list_text_selected_bg: def("#eee8d5"),
+            list_text_normal: def("#657b83"),
+            preview_border_active: def("#2aa198"),
+            preview_border_inactive: def("#93a1a1"),
+            preview_text: def("#586e75"),
+            search_match: def("#dc322f"),
+            help_text: def("#586e75"),
+            editor_bg: def("#fdf6e3"),
+            editor_fg: def("#586e75"),
+            editor_cursor: def("#cb4b16"),
+            editor_line_number: def("#93a1a1"),
+            md_header_fg: def("#b58900"),
+            md_h1: Some(def("#cb4b16")),
+            md_h2: Some(def("#b58900")),
+            md_h3: Some(def("#859900")),
+            md_h4: Some(def("#2aa198")),
+            md_h5: Some(def("#268bd2")),
+            md_h6: Some(def("#6c71c4")),
+            md_code_bg: def("#eee8d5"),
+            md_list_marker: def("#2aa198"),
+            md_blockquote: def("#93a1a1"),
+            editor_header: def("#268bd2"),
+            editor_list: def("#b58900"),
+            editor_checkbox: def("#cb4b16"),
+            editor_checkbox_checked: def("#859900"),
+            list_directory: def("#268bd2"),
+            editor_code_block: def("#2aa198"),
+            editor_code_keyword: def("#6c71c4"),
+            statusbar_bg: def("#eee8d5"),
+            statusbar_fg: def("#586e75"),
+            diff_add: def("#859900"),
+            diff_remove: def("#dc322f"),
+            mode_normal: None,
+            mode_insert: None,
+            task_badge: None,
+            task_overdue: None,
+            inactive_text: None,
+        }
+    }
+
+    /// Default colors for light terminals.
+    fn default_light() -> Self {
+        Self {
+            app_background: def("white"),
+            border: def("blue"),
+            header: def("magenta"),
+            highlight: def("blue"),
+            text: def("black"),
+            list_border_active: def("blue"),
+            list_border_inactive: def("gray"),
+            list_text_selected_fg: def("white"),
+            list_text_selected_bg: def("blue"),
+            list_text_normal: def("black"),
+            preview_border_active: def("cyan"),
+            preview_border_inactive: def("gray"),
+            preview_text: def("black"),
+            search_match: def("red"),
+            help_text: def("dark_gray"),
+            editor_bg: def("white"),
+            editor_fg: def("black"),
+            editor_cursor: def("blue"),
+            editor_line_number: def("gray"),
+            md_header_fg: def("magenta"),
+            md_h1: None,
+            md_h2: None,
+            md_h3: None,
+            md_h4: None,
+            md_h5: None,
+            md_h6: None,
+            md_code_bg: def("gray"),
+            md_list_marker: def("blue"),
+            md_blockquote: def("dark_gray"),
+            editor_header: def("blue"),
+            editor_list: def("magenta"),
+            editor_checkbox: def("magenta"),
+            editor_checkbox_checked: def("green"),
+            list_directory: def("blue"),
+            editor_code_block: def("cyan"),
+            editor_code_keyword: def("magenta"),
+            statusbar_bg: def("gray"),
+            statusbar_fg: def("black"),
+            diff_add: def("green"),
+            diff_remove: def("red"),
+            mode_normal: None,
+            mode_insert: None,
+            task_badge: None,
+            task_overdue: None,
+            inactive_text: None,
+        }
+    }
+
+    /// Gruvbox (light).
+    fn gruvbox_light() -> Self {
+        Self {
+            app_background: def("#fbf1c7"),
+            border: def("#076678"),
+            header: def("#b57614"),
+            highlight: def("#8f3f71"),
+            text: def("#3c3836"),
+            list_border_active: def("#076678"),
+            list_border_inactive: def("#d5c4a1"),
+            list_text_selected_fg: def("#79740e"),
+            list_text_selected_bg: def("#ebdbb2"),
+            list_text_normal: def("#3c3836"),
+            preview_border_active: def("#427b58"),
+            preview_border_inactive: def("#d5c4a1"),
+            preview_text: def("#3c3836"),
+            search_match: def("#9d0006"),
+            help_text: def("#7c6f64"),
+            editor_bg: def("#fbf1c7"),
+            editor_fg: def("#3c3836"),
+            editor_cursor: def("#af3a03"),
+            editor_line_number: def("#bdae93"),
+            md_header_fg: def("#b57614"),
+            md_h1: Some(def("#9d0006")),
+            md_h2: Some(def("#af3a03")),
+            md_h3: Some(def("#b57614")),
+            md_h4: Some(def("#79740e")),
+            md_h5: Some(def("#427b58")),
+            md_h6: Some(def("#076678")),
+            md_code_bg: def("#ebdbb2"),
+            md_list_marker: def("#427b58"),
+            md_blockquote: def("#928374"),
+            editor_header: def("#b57614"),
+            editor_list: def("#427b58"),
+            editor_checkbox: def("#af3a03"),
+            editor_checkbox_checked: def("#79740e"),
+            list_directory: def("#076678"),
+            editor_code_block: def("#427b58"),
+            editor_code_keyword: def("#8f3f71"),
+            statusbar_bg: def("#ebdbb2"),
+            statusbar_fg: def("#3c3836"),
+            diff_add: def("#79740e"),
+            diff_remove: def("#9d0006"),
+            mode_normal: None,
+            mode_insert: None,
+            task_badge: None,
+            task_overdue: None,
+            inactive_text: None,
+        }
+    }
+
+    /// Catppuccin Latte.
+    fn catppuccin_latte() -> Self {
+        Self {
+            app_background: def("#eff1f5"),
+            border: def("#1e66f5"),
+            header: def("#df8e1d"),
+            highlight: def("#8839ef"),
+            text: def("#4c4f69"),
+            list_border_active: def("#1e66f5"),
+            list_border_inactive: def("#bcc0cc"),
+            list_text_selected_fg: def("#40a02b"),
+            list_text_selected_bg: def("#ccd0da"),
+            list_text_normal: def("#4c4f69"),
+            preview_border_active: def("#7287fd"),
+            preview_border_inactive: def("#bcc0cc"),
+            preview_text: def("#4c4f69"),
+            search_match: def("#d20f39"),
+            help_text: def("#6c6f85"),
+            editor_bg: def("#eff1f5"),
+            editor_fg: def("#4c4f69"),
+            editor_cursor: def("#dc8a78"),
+            editor_line_number: def("#9ca0b0"),
+            md_header_fg: def("#df8e1d"),
+            md_h1: Some(def("#d20f39")),
+            md_h2: Some(def("#fe640b")),
+            md_h3: Some(def("#df8e1d")),
+            md_h4: Some(def("#40a02b")),
+            md_h5: Some(def("#209fb5")),
+            md_h6: Some(def("#7287fd")),
+            md_code_bg: def("#ccd0da"),
+            md_list_marker: def("#179299"),
+            md_blockquote: def("#8c8fa1"),
+            editor_header: def("#1e66f5"),
+            editor_list: def("#df8e1d"),
+            editor_checkbox: def("#fe640b"),
+            editor_checkbox_checked: def("#40a02b"),
+            list_directory: def("#1e66f5"),
+            editor_code_block: def("#179299"),
+            editor_code_keyword: def("#8839ef"),
+            statusbar_bg: def("#e6e9ef"),
+            statusbar_fg: def("#4c4f69"),
+            diff_add: def("#40a02b"),
+            diff_remove: def("#d20f39"),
+            mode_normal: None,
+            mode_insert: None,
+            task_badge: None,
+            task_overdue: None,
+            inactive_text: None,
+        }
+    }
+
+    /// Nord on its Snow Storm colors.
+    fn nord_light() -> Self {
+        Self {
+            app_background: def("#eceff4"),
+            border: def("#5e81ac"),
+            header: def("#d08770"),
+            highlight: def("#b48ead"),
+            text: def("#2e3440"),
+            list_border_active: def("#5e81ac"),
+            list_border_inactive: def("#d8dee9"),
+            list_text_selected_fg: def("#2e3440"),
+            list_text_selected_bg: def("#d8dee9"),
+            list_text_normal: def("#2e3440"),
+            preview_border_active: def("#81a1c1"),
+            preview_border_inactive: def("#d8dee9"),
+            preview_text: def("#2e3440"),
+            search_match: def("#bf616a"),
+            help_text: def("#4c566a"),
+            editor_bg: def("#eceff4"),
+            editor_fg: def("#2e3440"),
+            editor_cursor: def("#5e81ac"),
+            editor_line_number: def("#7b88a1"),
+            md_header_fg: def("#5e81ac"),
+            md_h1: Some(def("#5e81ac")),
+            md_h2: Some(def("#81a1c1")),
+            md_h3: Some(def("#b48ead")),
+            md_h4: Some(def("#d08770")),
+            md_h5: Some(def("#bf616a")),
+            md_h6: Some(def("#4c566a")),
+            md_code_bg: def("#e5e9f0"),
+            md_list_marker: def("#5e81ac"),
+            md_blockquote: def("#4c566a"),
+            editor_header: def("#5e81ac"),
+            editor_list: def("#d08770"),
+            editor_checkbox: def("#d08770"),
+            editor_checkbox_checked: def("#a3be8c"),
+            list_directory: def("#5e81ac"),
+            editor_code_block: def("#5e81ac"),
+            editor_code_keyword: def("#b48ead"),
+            statusbar_bg: def("#e5e9f0"),
+            statusbar_fg: def("#2e3440"),
+            diff_add: def("#a3be8c"),
+            diff_remove: def("#bf616a"),
+            mode_normal: None,
+            mode_insert: None,
+            task_badge: None,
+            task_overdue: None,
+            inactive_text: None,
+        }
+    }
+
+    /// Solarized (dark).
+    fn solarized_dark() -> Self {
+        Self {
+            app_background: def("#002b36"),
+            border: def("#268bd2"),
+            header: def("#b58900"),
+            highlight: def("#d33682"),
+            text: def("#839496"),
+            list_border_active: def("#268bd2"),
+            list_border_inactive: def("#586e75"),
+            list_text_selected_fg: def("#859900"),
+            list_text_selected_bg: def("#073642"),
+            list_text_normal: def("#839496"),
+            preview_border_active: def("#2aa198"),
+            preview_border_inactive: def("#586e75"),
+            preview_text: def("#93a1a1"),
+            search_match: def("#dc322f"),
+            help_text: def("#93a1a1"),
+            editor_bg: def("#002b36"),
+            editor_fg: def("#93a1a1"),
+            editor_cursor: def("#cb4b16"),
+            editor_line_number: def("#586e75"),
+            md_header_fg: def("#b58900"),
+            md_h1: Some(def("#cb4b16")),
+            md_h2: Some(def("#b58900")),
+            md_h3: Some(def("#859900")),
+            md_h4: Some(def("#2aa198")),
+            md_h5: Some(def("#268bd2")),
+            md_h6: Some(def("#6c71c4")),
+            md_code_bg: def("#073642"),
+            md_list_marker: def("#2aa198"),
+            md_blockquote: def("#586e75"),
+            editor_header: def("#268bd2"),
+            editor_list: def("#b58900"),
+            editor_checkbox: def("#cb4b16"),
+            editor_checkbox_checked: def("#859900"),
+            list_directory: def("#268bd2"),
+            editor_code_block: def("#2aa198"),
+            editor_code_keyword: def("#6c71c4"),
+            statusbar_bg: def("#073642"),
+            statusbar_fg: def("#93a1a1"),
+            diff_add: def("#859900"),
+            diff_remove: def("#dc322f"),
+            mode_normal: None,
+            mode_insert: None,
+            task_badge: None,
+            task_overdue: None,
+            inactive_text: None,
         }
     }
 }
 
 /// Color definition supporting hex (#RRGGBB, #RGB) or named colors.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(transparent)]
 pub struct ColorDef(String);
 
@@ -130,63 +754,176 @@ pub fn parse_color_str(s: &str) -> Result<Color> {
         "orange2" | "dark_orange" => "dark_gray",
         _ => s,
     };
-    Color::from_str(normalized).with_context(|| format!("Invalid color: {s}"))
+    Color::from_str(normalized).map_err(|_| {
+        if matches!(s.len(), 3 | 6) && s.chars().all(|c| c.is_ascii_hexdigit()) {
+            anyhow::anyhow!("Invalid color {s:?}: hex colors need a leading '#' (\"#{s}\")")
+        } else {
+            anyhow::anyhow!("Invalid color {s:?}: expected #RRGGBB, #RGB or a color name")
+        }
+    })
 }
 
 fn parse_hex_color(s: &str) -> Result<Color> {
-    let s = s.trim_start_matches('#').trim();
-    let (r, g, b) = if s.len() == 6 {
-        let r = u8::from_str_radix(&s[0..2], 16)
-            .with_context(|| format!("Invalid hex color: {s}"))?;
-        let g = u8::from_str_radix(&s[2..4], 16)
-            .with_context(|| format!("Invalid hex color: {s}"))?;
-        let b = u8::from_str_radix(&s[4..6], 16)
-            .with_context(|| format!("Invalid hex color: {s}"))?;
-        (r, g, b)
-    } else if s.len() == 3 {
-        let r = u8::from_str_radix(&s[0..1].repeat(2), 16)
-            .with_context(|| format!("Invalid hex color: {s}"))?;
-        let g = u8::from_str_radix(&s[1..2].repeat(2), 16)
-            .with_context(|| format!("Invalid hex color: {s}"))?;
-        let b = u8::from_str_radix(&s[2..3].repeat(2), 16)
-            .with_context(|| format!("Invalid hex color: {s}"))?;
-        (r, g, b)
-    } else {
-        anyhow::bail!("Hex color must be #RRGGBB or #RGB, got: {s}");
+    let digits = s.trim().trim_start_matches('#');
+    if let Some(bad) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        anyhow::bail!("Invalid hex color {s:?}: {bad:?} is not a hex digit");
+    }
+    let expanded: String = match digits.len() {
+        6 => digits.to_string(),
+        3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        n => {
+            anyhow::bail!("Invalid hex color {s:?}: expected 3 or 6 hex digits after '#', got {n}")
+        }
     };
-    Ok(Color::Rgb(r, g, b))
+    let channel = |i: usize| {
+        u8::from_str_radix(&expanded[i..i + 2], 16)
+            .with_context(|| format!("Invalid hex color {s:?}"))
+    };
+    Ok(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
 }
 
 impl ColorDef {
     pub fn to_ratatui_color(&self) -> Result<Color> {
-        let s = self.0.trim();
-        if s.starts_with('#') {
-            return parse_hex_color(s);
+        parse_color_str(&self.0)
+    }
+}
+
+/// How many colors the terminal can show (`ui.color_mode`). Hex colors are reduced to the
+/// nearest palette entry in the indexed modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    #[default]
+    TrueColor,
+    /// xterm 256-color palette.
+    Indexed256,
+    /// The 16 ANSI colors.
+    Ansi16,
+}
+
+impl ColorMode {
+    /// Parse `ui.color_mode` ("truecolor", "256", "16"); None for unknown values.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "truecolor" | "24bit" => Some(Self::TrueColor),
+            "256" => Some(Self::Indexed256),
+            "16" => Some(Self::Ansi16),
+            _ => None,
+        }
+    }
+
+    /// Map `color` into this mode's palette. Named colors are already in every palette.
+    pub fn quantize(self, color: Color) -> Color {
+        match (self, color) {
+            (Self::Indexed256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256((r, g, b))),
+            (Self::Ansi16, Color::Rgb(r, g, b)) => ANSI_16[nearest_16((r, g, b))].1,
+            (Self::Ansi16, Color::Indexed(i)) if i >= 16 => ANSI_16[nearest_16(indexed_rgb(i))].1,
+            _ => color,
+        }
+    }
+}
+
+type Rgb = (u8, u8, u8);
+
+/// The 16 ANSI colors with xterm's default RGB values.
+const ANSI_16: [(Rgb, Color); 16] = [
+    ((0, 0, 0), Color::Black),
+    ((205, 0, 0), Color::Red),
+    ((0, 205, 0), Color::Green),
+    ((205, 205, 0), Color::Yellow),
+    ((0, 0, 238), Color::Blue),
+    ((205, 0, 205), Color::Magenta),
+    ((0, 205, 205), Color::Cyan),
+    ((229, 229, 229), Color::Gray),
+    ((127, 127, 127), Color::DarkGray),
+    ((255, 0, 0), Color::LightRed),
+    ((0, 255, 0), Color::LightGreen),
+    ((255, 255, 0), Color::LightYellow),
+    ((92, 92, 255), Color::LightBlue),
+    ((255, 0, 255), Color::LightMagenta),
+    ((0, 255, 255), Color::LightCyan),
+    ((255, 255, 255), Color::White),
+];
+
+/// Channel levels of the 6x6x6 color cube (indices 16–231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Perceptual distance between two colors: squared Euclidean distance with the channels
+/// weighted by how sensitive the eye is to them (the "redmean" approximation).
+fn color_distance(a: Rgb, b: Rgb) -> u32 {
+    let mean_r = (u32::from(a.0) + u32::from(b.0)) / 2;
+    let dr = i32::from(a.0) - i32::from(b.0);
+    let dg = i32::from(a.1) - i32::from(b.1);
+    let db = i32::from(a.2) - i32::from(b.2);
+    let (dr2, dg2, db2) = ((dr * dr) as u32, (dg * dg) as u32, (db * db) as u32);
+    (((512 + mean_r) * dr2) >> 8) + 4 * dg2 + (((767 - mean_r) * db2) >> 8)
+}
+
+/// RGB value of an xterm 256-color palette index.
+fn indexed_rgb(i: u8) -> Rgb {
+    match i {
+        0..=15 => ANSI_16[usize::from(i)].0,
+        16..=231 => {
+            let i = i - 16;
+            (
+                CUBE_LEVELS[usize::from(i / 36)],
+                CUBE_LEVELS[usize::from(i / 6 % 6)],
+                CUBE_LEVELS[usize::from(i % 6)],
+            )
+        }
+        _ => {
+            let v = 8 + 10 * (i - 232);
+            (v, v, v)
         }
-        let normalized = match s.to_lowercase().as_str() {
-            "orange1" | "orange" => "yellow",
-            "orange2" | "dark_orange" => "dark_gray",
-            _ => s,
-        };
-        Color::from_str(normalized).with_context(|| format!("Invalid color: {}", self.0))
     }
 }
 
-/// Load theme from ~/.config/oxid/theme.toml.
-pub fn load_theme(config_dir: &Path) -> Result<Theme> {
+/// Nearest entry of the 256-color palette, searching the color cube and the gray ramp (the
+/// first 16 entries are skipped, as terminals often remap them).
+fn nearest_256(rgb: Rgb) -> u8 {
+    (16..=255)
+        .min_by_key(|&i| color_distance(rgb, indexed_rgb(i)))
+        .unwrap_or(16)
+}
+
+/// Index into `ANSI_16` of the nearest ANSI color.
+fn nearest_16(rgb: Rgb) -> usize {
+    (0..ANSI_16.len())
+        .min_by_key(|&i| color_distance(rgb, ANSI_16[i].0))
+        .unwrap_or(0)
+}
+
+/// Load theme from ~/.config/oxid/theme.toml on top of the `mode` variant of the named
+/// preset (unknown names use the default preset). Keys set in theme.toml override the
+/// preset's colors, and keys in its `[dark]` or `[light]` section override those in `mode`.
+pub fn load_theme(config_dir: &Path, preset: &str, mode: ThemeMode) -> Result<Theme> {
     let theme_path = config_dir.join("theme.toml");
+    let base = Theme::preset(preset_variant(preset, mode)).unwrap_or_default();
 
     let theme = if theme_path.exists() {
         let content = fs::read_to_string(&theme_path)
             .with_context(|| format!("Failed to read theme: {}", theme_path.display()))?;
-        toml::from_str(&content)
+        let mut overrides: toml::Table = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse theme: {}", theme_path.display()))?;
+        let mut merged = toml::Table::try_from(&base).context("Failed to encode theme preset")?;
+        let [dark, light] = [ThemeMode::Dark, ThemeMode::Light].map(|m| overrides.remove(m.name()));
+        merged.extend(overrides);
+        match if mode == ThemeMode::Light {
+            light
+        } else {
+            dark
+        } {
+            Some(toml::Value::Table(section)) => merged.extend(section),
+            Some(_) => anyhow::bail!("theme.toml `{}` must be a table", mode.name()),
+            None => {}
+        }
+        merged
+            .try_into()
             .with_context(|| format!("Failed to parse theme: {}", theme_path.display()))?
     } else {
-        let default = Theme::default();
-        let content = generate_default_theme(&default);
+        let content = generate_default_theme(&Theme::default());
         fs::write(&theme_path, content)
             .with_context(|| format!("Failed to write default theme: {}", theme_path.display()))?;
-        default
+        base
     };
 
     Ok(theme)
@@ -199,53 +936,83 @@ fn generate_default_theme(theme: &Theme) -> String {
     format!(
         r#"# Oxid Theme Configuration
 # Every visible color is configurable. Hex (#RRGGBB) or named colors.
+# Colors start from the preset chosen with `preset` under [theme] in config.toml. The
+# values below are the default preset's; uncomment a key to override the preset.
 
-app_background = {}
-border = {}
-header = {}
-highlight = {}
-text = {}
+# app_background = {}
+# border = {}
+# header = {}
+# highlight = {}
+# text = {}
 
 # Notes list
-list_border_active = {}
-list_border_inactive = {}
-list_text_selected_fg = {}
-list_text_selected_bg = {}
-list_text_normal = {}
+# list_border_active = {}
+# list_border_inactive = {}
+# list_text_selected_fg = {}
+# list_text_selected_bg = {}
+# list_text_normal = {}
 
 # Preview pane
-preview_border_active = {}
-preview_border_inactive = {}
-preview_text = {}
+# preview_border_active = {}
+# preview_border_inactive = {}
+# preview_text = {}
 
 # Search highlighting (list + preview)
-search_match = {}
+# search_match = {}
 
-# Footer / help
-help_text = {}
+# Prompts and help text in popups (footer text is statusbar_fg)
+# help_text = {}
 
 # Editor pane
-editor_bg = {}
-editor_fg = {}
-editor_cursor = {}
-editor_line_number = {}
+# editor_bg = {}
+# editor_fg = {}
+# editor_cursor = {}
+# editor_line_number = {}
 
-# Markdown preview (headers, code blocks, list markers)
-md_header_fg = {}
-md_code_bg = {}
-md_list_marker = {}
+# Markdown preview (headers, code blocks, list markers, blockquotes)
+# md_header_fg = {}
+# Heading colors by level (fall back to md_header_fg when unset)
+# md_h1 = {}
+# md_h2 = {}
+# md_h3 = {}
+# md_h4 = {}
+# md_h5 = {}
+# md_h6 = {}
+# md_code_bg = {}
+# md_list_marker = {}
+# md_blockquote = {}
 
 # Editor syntax highlighting
-editor_header = {}
-editor_list = {}
-editor_checkbox = {}
-editor_checkbox_checked = {}
-list_directory = {}
-editor_code_block = {}
-editor_code_keyword = {}
+# editor_header = {}
+# editor_list = {}
+# editor_checkbox = {}
+# editor_checkbox_checked = {}
+# list_directory = {}
+# editor_code_block = {}
+# editor_code_keyword = {}
 # Status bar (footer)
-statusbar_bg = {}
-statusbar_fg = {}
+# statusbar_bg = {}
+# statusbar_fg = {}
+# Diff view (added and removed lines)
+# diff_add = {}
+# diff_remove = {}
+# Editor mode badge in the footer (fall back to highlight and header when unset)
+# mode_normal = {}
+# mode_insert = {}
+# Task badges (☑ 3/7) in the notes list and preview title, and for notes with overdue tasks
+# (fall back to editor_checkbox and diff_remove when unset)
+# task_badge = {}
+# task_overdue = {}
+
+# Text of unfocused panes when ui.dim_inactive is on (unset dims each pane's own colors)
+# inactive_text = {}
+
+# Keys under [dark] or [light] apply only in that mode (theme.mode in config.toml, or
+# Toggle Light/Dark in the command palette) and override the keys above. Keep these
+# sections at the end of the file.
+# [light]
+# editor_bg = "white"
+# editor_fg = "black"
         "#,
         cv(&theme.app_background),
         cv(&theme.border),
@@ -267,8 +1034,15 @@ statusbar_fg = {}
         cv(&theme.editor_cursor),
         cv(&theme.editor_line_number),
         cv(&theme.md_header_fg),
+        cv(theme.md_h1.as_ref().unwrap_or(&theme.md_header_fg)),
+        cv(theme.md_h2.as_ref().unwrap_or(&theme.md_header_fg)),
+        cv(theme.md_h3.as_ref().unwrap_or(&theme.md_header_fg)),
+        cv(theme.md_h4.as_ref().unwrap_or(&theme.md_header_fg)),
+        cv(theme.md_h5.as_ref().unwrap_or(&theme.md_header_fg)),
+        cv(theme.md_h6.as_ref().unwrap_or(&theme.md_header_fg)),
         cv(&theme.md_code_bg),
         cv(&theme.md_list_marker),
+        cv(&theme.md_blockquote),
         cv(&theme.editor_header),
         cv(&theme.editor_list),
         cv(&theme.editor_checkbox),
@@ -278,6 +1052,16 @@ statusbar_fg = {}
         cv(&theme.editor_code_keyword),
         cv(&theme.statusbar_bg),
         cv(&theme.statusbar_fg),
+        cv(&theme.diff_add),
+        cv(&theme.diff_remove),
+        cv(theme.mode_normal.as_ref().unwrap_or(&theme.highlight)),
+        cv(theme.mode_insert.as_ref().unwrap_or(&theme.header)),
+        cv(theme.task_badge.as_ref().unwrap_or(&theme.editor_checkbox)),
+        cv(theme.task_overdue.as_ref().unwrap_or(&theme.diff_remove)),
+        cv(theme
+            .inactive_text
+            .as_ref()
+            .unwrap_or(&theme.editor_line_number)),
     )
 }
 
@@ -301,10 +1085,14 @@ pub struct ResolvedTheme {
     pub editor_bg_style: Style,
     pub editor_fg_style: Style,
     pub editor_cursor_style: Style,
+    /// Selected text in the editor.
+    pub editor_selection_style: Style,
     pub editor_line_number_style: Style,
-    pub md_header_fg_style: Style,
+    /// Heading styles for levels 1–6 (index 0 is `#`).
+    pub md_heading_styles: [Style; 6],
     pub md_code_bg_style: Style,
     pub md_list_marker_style: Style,
+    pub md_blockquote_style: Style,
     pub editor_header_style: Style,
     pub editor_list_style: Style,
     pub editor_checkbox_style: Style,
@@ -315,97 +1103,476 @@ pub struct ResolvedTheme {
     pub editor_code_keyword_style: Style,
     pub statusbar_bg_style: Style,
     pub statusbar_fg_style: Style,
+    /// Footer badges for the editor modes.
+    pub diff_add_style: Style,
+    pub diff_remove_style: Style,
+    pub mode_normal_style: Style,
+    pub mode_insert_style: Style,
+    pub task_badge_style: Style,
+    pub task_overdue_style: Style,
+    /// Patched over the content of unfocused panes with `ui.dim_inactive`.
+    pub inactive_style: Style,
 }
 
 impl ResolvedTheme {
-    /// Build resolved theme from theme.toml, optionally overriding with config.toml [theme].
+    /// Build resolved theme from theme.toml, optionally overriding with config.toml [theme]
+    /// (which wins for every color it sets). Hex colors are reduced to `color_mode`.
     pub fn resolve(
         theme: &Theme,
         config_theme: Option<&crate::config::ThemeConfig>,
+        color_mode: ColorMode,
     ) -> Result<Self> {
-        let bg = config_theme
-            .map(|c| parse_color_str(&c.background))
-            .transpose()?
-            .unwrap_or_else(|| theme.editor_bg.to_ratatui_color().unwrap());
-        let fg = config_theme
-            .map(|c| parse_color_str(&c.foreground))
-            .transpose()?
-            .unwrap_or_else(|| theme.editor_fg.to_ratatui_color().unwrap());
-        let cursor = config_theme
-            .map(|c| parse_color_str(&c.cursor))
-            .transpose()?
-            .unwrap_or_else(|| theme.editor_cursor.to_ratatui_color().unwrap());
-        let sel_fg = config_theme
-            .map(|c| parse_color_str(&c.selection))
-            .transpose()?
-            .unwrap_or_else(|| theme.list_text_selected_fg.to_ratatui_color().unwrap());
-        let sel_bg = theme.list_text_selected_bg.to_ratatui_color()?;
-        let statusbar_bg = config_theme
-            .map(|c| parse_color_str(&c.statusbar_bg))
-            .transpose()?
-            .unwrap_or_else(|| theme.statusbar_bg.to_ratatui_color().unwrap());
-        let statusbar_fg = config_theme
-            .map(|c| parse_color_str(&c.statusbar_fg))
-            .transpose()?
-            .unwrap_or_else(|| theme.statusbar_fg.to_ratatui_color().unwrap());
-        let border = config_theme
-            .map(|c| parse_color_str(&c.border_color))
-            .transpose()?
-            .unwrap_or_else(|| theme.border.to_ratatui_color().unwrap());
+        let color = |key: &str, def: &ColorDef| -> Result<Color> {
+            let c = def
+                .to_ratatui_color()
+                .with_context(|| format!("theme.toml `{key}`"))?;
+            Ok(color_mode.quantize(c))
+        };
+        // config.toml [theme] wins over theme.toml (and the preset) for the colors it sets:
+        //   background   -> app background, editor background
+        //   foreground   -> editor text, general UI text
+        //   cursor       -> editor cursor
+        //   selection    -> selected list row and editor selection background
+        //   statusbar_bg -> footer background
+        //   statusbar_fg -> footer text
+        //   border_color -> header, tab bar and footer borders
+        let over = |key: &str, field: fn(&crate::config::ThemeConfig) -> &Option<String>| {
+            config_theme
+                .and_then(|c| field(c).as_deref())
+                .map(|s| {
+                    parse_color_str(s)
+                        .map(|c| color_mode.quantize(c))
+                        .with_context(|| format!("config.toml [theme] `{key}`"))
+                })
+                .transpose()
+        };
+        let background = over("background", |c| &c.background)?;
+        let foreground = over("foreground", |c| &c.foreground)?;
+        let selection = over("selection", |c| &c.selection)?;
+        let or_theme = |o: Option<Color>, key: &str, fallback: &ColorDef| match o {
+            Some(c) => Ok(c),
+            None => color(key, fallback),
+        };
+
+        let app_bg = or_theme(background, "app_background", &theme.app_background)?;
+        let bg = or_theme(background, "editor_bg", &theme.editor_bg)?;
+        let fg = or_theme(foreground, "editor_fg", &theme.editor_fg)?;
+        let text = or_theme(foreground, "text", &theme.text)?;
+        let cursor = or_theme(
+            over("cursor", |c| &c.cursor)?,
+            "editor_cursor",
+            &theme.editor_cursor,
+        )?;
+        // A selection color from config.toml is a background; the selected text uses the editor
+        // background so it stays readable whatever the selection color is.
+        let (sel_fg, sel_bg) = match selection {
+            Some(sel) => (bg, sel),
+            None => (
+                color("list_text_selected_fg", &theme.list_text_selected_fg)?,
+                color("list_text_selected_bg", &theme.list_text_selected_bg)?,
+            ),
+        };
+        let editor_selection_style = match selection {
+            Some(sel) => Style::default().fg(bg).bg(sel),
+            None => Style::default().add_modifier(Modifier::REVERSED),
+        };
+        let statusbar_bg = or_theme(
+            over("statusbar_bg", |c| &c.statusbar_bg)?,
+            "statusbar_bg",
+            &theme.statusbar_bg,
+        )?;
+        let statusbar_fg = or_theme(
+            over("statusbar_fg", |c| &c.statusbar_fg)?,
+            "statusbar_fg",
+            &theme.statusbar_fg,
+        )?;
+        let border = or_theme(
+            over("border_color", |c| &c.border_color)?,
+            "border",
+            &theme.border,
+        )?;
+
+        let heading_color = |key: &str, c: &Option<ColorDef>| -> Result<Style> {
+            let color = match c {
+                Some(c) => color(key, c)?,
+                None => color("md_header_fg", &theme.md_header_fg)?,
+            };
+            Ok(Style::default().fg(color).add_modifier(Modifier::BOLD))
+        };
+        let md_heading_styles = [
+            heading_color("md_h1", &theme.md_h1)?,
+            heading_color("md_h2", &theme.md_h2)?,
+            heading_color("md_h3", &theme.md_h3)?,
+            heading_color("md_h4", &theme.md_h4)?,
+            heading_color("md_h5", &theme.md_h5)?,
+            heading_color("md_h6", &theme.md_h6)?,
+        ];
+
+        let badge =
+            |key: &str, c: &Option<ColorDef>, fallback: (&str, &ColorDef)| -> Result<Style> {
+                let color = match c {
+                    Some(c) => color(key, c)?,
+                    None => color(fallback.0, fallback.1)?,
+                };
+                Ok(Style::default()
+                    .fg(statusbar_bg)
+                    .bg(color)
+                    .add_modifier(Modifier::BOLD))
+            };
+        let mode_normal_style = badge(
+            "mode_normal",
+            &theme.mode_normal,
+            ("highlight", &theme.highlight),
+        )?;
+        let mode_insert_style =
+            badge("mode_insert", &theme.mode_insert, ("header", &theme.header))?;
 
         Ok(Self {
-            app_background_style: Style::default().bg(bg),
+            app_background_style: Style::default().bg(app_bg),
             border_style: Style::default().fg(border),
             header_style: Style::default()
-                .fg(theme.header.to_ratatui_color()?)
+                .fg(color("header", &theme.header)?)
                 .add_modifier(Modifier::BOLD),
-            highlight_style: Style::default().fg(theme.highlight.to_ratatui_color()?),
-            text_style: Style::default().fg(theme.text.to_ratatui_color()?),
+            highlight_style: Style::default().fg(color("highlight", &theme.highlight)?),
+            text_style: Style::default().fg(text),
             list_border_active_style: Style::default()
-                .fg(theme.list_border_active.to_ratatui_color()?),
+                .fg(color("list_border_active", &theme.list_border_active)?),
             list_border_inactive_style: Style::default()
-                .fg(theme.list_border_inactive.to_ratatui_color()?),
+                .fg(color("list_border_inactive", &theme.list_border_inactive)?),
             list_text_selected_style: Style::default()
                 .fg(sel_fg)
                 .bg(sel_bg)
                 .add_modifier(Modifier::BOLD),
             list_text_normal_style: Style::default()
-                .fg(theme.list_text_normal.to_ratatui_color()?),
-            preview_border_active_style: Style::default()
-                .fg(theme.preview_border_active.to_ratatui_color()?),
-            preview_border_inactive_style: Style::default()
-                .fg(theme.preview_border_inactive.to_ratatui_color()?),
-            preview_text_style: Style::default().fg(theme.preview_text.to_ratatui_color()?),
+                .fg(color("list_text_normal", &theme.list_text_normal)?),
+            preview_border_active_style: Style::default().fg(color(
+                "preview_border_active",
+                &theme.preview_border_active,
+            )?),
+            preview_border_inactive_style: Style::default().fg(color(
+                "preview_border_inactive",
+                &theme.preview_border_inactive,
+            )?),
+            preview_text_style: Style::default().fg(color("preview_text", &theme.preview_text)?),
             search_match_style: Style::default()
-                .fg(theme.search_match.to_ratatui_color()?)
+                .fg(color("search_match", &theme.search_match)?)
                 .add_modifier(Modifier::BOLD),
-            help_text_style: Style::default().fg(theme.help_text.to_ratatui_color()?),
+            help_text_style: Style::default().fg(color("help_text", &theme.help_text)?),
             editor_bg_style: Style::default().bg(bg),
             editor_fg_style: Style::default().fg(fg),
             editor_cursor_style: Style::default().fg(cursor).add_modifier(Modifier::REVERSED),
+            editor_selection_style,
             editor_line_number_style: Style::default()
-                .fg(theme.editor_line_number.to_ratatui_color()?),
-            md_header_fg_style: Style::default()
-                .fg(theme.md_header_fg.to_ratatui_color()?)
-                .add_modifier(Modifier::BOLD),
-            md_code_bg_style: Style::default().bg(theme.md_code_bg.to_ratatui_color()?),
-            md_list_marker_style: Style::default().fg(theme.md_list_marker.to_ratatui_color()?),
-            editor_header_style: Style::default().fg(theme.editor_header.to_ratatui_color()?),
-            editor_list_style: Style::default().fg(theme.editor_list.to_ratatui_color()?),
+                .fg(color("editor_line_number", &theme.editor_line_number)?),
+            md_heading_styles,
+            md_code_bg_style: Style::default().bg(color("md_code_bg", &theme.md_code_bg)?),
+            md_list_marker_style: Style::default()
+                .fg(color("md_list_marker", &theme.md_list_marker)?),
+            md_blockquote_style: Style::default().fg(color("md_blockquote", &theme.md_blockquote)?),
+            editor_header_style: Style::default().fg(color("editor_header", &theme.editor_header)?),
+            editor_list_style: Style::default().fg(color("editor_list", &theme.editor_list)?),
             editor_checkbox_style: Style::default()
-                .fg(theme.editor_checkbox.to_ratatui_color()?)
+                .fg(color("editor_checkbox", &theme.editor_checkbox)?)
                 .add_modifier(Modifier::BOLD),
             editor_checkbox_checked_style: Style::default()
-                .fg(theme.editor_checkbox_checked.to_ratatui_color()?)
+                .fg(color(
+                    "editor_checkbox_checked",
+                    &theme.editor_checkbox_checked,
+                )?)
                 .add_modifier(Modifier::CROSSED_OUT),
-            list_directory_style: Style::default().fg(theme.list_directory.to_ratatui_color()?),
+            list_directory_style: Style::default()
+                .fg(color("list_directory", &theme.list_directory)?),
             editor_code_block_style: Style::default()
-                .fg(theme.editor_code_block.to_ratatui_color()?),
+                .fg(color("editor_code_block", &theme.editor_code_block)?),
             editor_code_keyword_style: Style::default()
-                .fg(theme.editor_code_keyword.to_ratatui_color()?)
+                .fg(color("editor_code_keyword", &theme.editor_code_keyword)?)
                 .add_modifier(Modifier::BOLD),
             statusbar_bg_style: Style::default().bg(statusbar_bg).fg(statusbar_fg),
             statusbar_fg_style: Style::default().fg(statusbar_fg),
+            diff_add_style: Style::default().fg(color("diff_add", &theme.diff_add)?),
+            diff_remove_style: Style::default().fg(color("diff_remove", &theme.diff_remove)?),
+            mode_normal_style,
+            mode_insert_style,
+            task_badge_style: Style::default().fg(match &theme.task_badge {
+                Some(c) => color("task_badge", c)?,
+                None => color("editor_checkbox", &theme.editor_checkbox)?,
+            }),
+            task_overdue_style: Style::default()
+                .fg(match &theme.task_overdue {
+                    Some(c) => color("task_overdue", c)?,
+                    None => color("diff_remove", &theme.diff_remove)?,
+                })
+                .add_modifier(Modifier::BOLD),
+            inactive_style: match &theme.inactive_text {
+                Some(c) => Style::default().fg(color("inactive_text", c)?),
+                None => Style::default().add_modifier(Modifier::DIM),
+            },
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ThemeConfig;
+
+    fn theme_toml() -> Theme {
+        Theme {
+            app_background: def("#101010"),
+            editor_bg: def("#111111"),
+            editor_fg: def("#eeeeee"),
+            text: def("#dddddd"),
+            editor_cursor: def("#00ff00"),
+            list_text_selected_fg: def("#ff0000"),
+            list_text_selected_bg: def("#220000"),
+            statusbar_bg: def("#333333"),
+            statusbar_fg: def("#cccccc"),
+            mode_normal: None,
+            mode_insert: None,
+            border: def("#0000ff"),
+            ..Theme::default()
+        }
+    }
+
+    #[test]
+    fn theme_toml_colors_apply_without_overrides() {
+        let resolved = ResolvedTheme::resolve(
+            &theme_toml(),
+            Some(&ThemeConfig::default()),
+            ColorMode::TrueColor,
+        )
+        .unwrap();
+        assert_eq!(
+            resolved.app_background_style.bg,
+            Some(Color::Rgb(0x10, 0x10, 0x10))
+        );
+        assert_eq!(
+            resolved.editor_bg_style.bg,
+            Some(Color::Rgb(0x11, 0x11, 0x11))
+        );
+        assert_eq!(
+            resolved.editor_fg_style.fg,
+            Some(Color::Rgb(0xee, 0xee, 0xee))
+        );
+        assert_eq!(resolved.text_style.fg, Some(Color::Rgb(0xdd, 0xdd, 0xdd)));
+        assert_eq!(
+            resolved.editor_cursor_style.fg,
+            Some(Color::Rgb(0, 0xff, 0))
+        );
+        assert_eq!(
+            resolved.list_text_selected_style.fg,
+            Some(Color::Rgb(0xff, 0, 0))
+        );
+        assert_eq!(
+            resolved.list_text_selected_style.bg,
+            Some(Color::Rgb(0x22, 0, 0))
+        );
+        assert_eq!(
+            resolved.statusbar_bg_style.bg,
+            Some(Color::Rgb(0x33, 0x33, 0x33))
+        );
+        assert_eq!(
+            resolved.statusbar_fg_style.fg,
+            Some(Color::Rgb(0xcc, 0xcc, 0xcc))
+        );
+        assert_eq!(resolved.border_style.fg, Some(Color::Rgb(0, 0, 0xff)));
+    }
+
+    #[test]
+    fn config_theme_overrides_win_over_theme_toml() {
+        let config = ThemeConfig {
+            background: Some("#000001".to_string()),
+            foreground: Some("#000002".to_string()),
+            cursor: Some("#000003".to_string()),
+            selection: Some("#000004".to_string()),
+            statusbar_bg: Some("#000005".to_string()),
+            statusbar_fg: Some("#000006".to_string()),
+            border_color: Some("#000007".to_string()),
+            ..ThemeConfig::default()
+        };
+        let resolved =
+            ResolvedTheme::resolve(&theme_toml(), Some(&config), ColorMode::TrueColor).unwrap();
+        assert_eq!(resolved.app_background_style.bg, Some(Color::Rgb(0, 0, 1)));
+        assert_eq!(resolved.editor_bg_style.bg, Some(Color::Rgb(0, 0, 1)));
+        assert_eq!(resolved.editor_fg_style.fg, Some(Color::Rgb(0, 0, 2)));
+        assert_eq!(resolved.text_style.fg, Some(Color::Rgb(0, 0, 2)));
+        assert_eq!(resolved.editor_cursor_style.fg, Some(Color::Rgb(0, 0, 3)));
+        assert_eq!(
+            resolved.list_text_selected_style.bg,
+            Some(Color::Rgb(0, 0, 4))
+        );
+        assert_eq!(
+            resolved.list_text_selected_style.fg,
+            Some(Color::Rgb(0, 0, 1))
+        );
+        assert_eq!(
+            resolved.editor_selection_style.bg,
+            Some(Color::Rgb(0, 0, 4))
+        );
+        assert_eq!(resolved.statusbar_bg_style.bg, Some(Color::Rgb(0, 0, 5)));
+        assert_eq!(resolved.statusbar_fg_style.fg, Some(Color::Rgb(0, 0, 6)));
+        assert_eq!(resolved.border_style.fg, Some(Color::Rgb(0, 0, 7)));
+    }
+
+    #[test]
+    fn invalid_override_color_is_an_error() {
+        let config = ThemeConfig {
+            cursor: Some("not-a-color".to_string()),
+            ..ThemeConfig::default()
+        };
+        assert!(
+            ResolvedTheme::resolve(&theme_toml(), Some(&config), ColorMode::TrueColor).is_err()
+        );
+    }
+
+    #[test]
+    fn color_distance_is_zero_for_equal_colors_and_symmetric() {
+        assert_eq!(color_distance((12, 34, 56), (12, 34, 56)), 0);
+        assert_eq!(
+            color_distance((255, 0, 0), (0, 0, 255)),
+            color_distance((0, 0, 255), (255, 0, 0))
+        );
+        // Green differences weigh more than blue ones.
+        assert!(color_distance((0, 0, 0), (0, 50, 0)) > color_distance((0, 0, 0), (0, 0, 50)));
+    }
+
+    #[test]
+    fn indexed_palette_rgb_values() {
+        assert_eq!(indexed_rgb(16), (0, 0, 0));
+        assert_eq!(indexed_rgb(196), (255, 0, 0));
+        assert_eq!(indexed_rgb(231), (255, 255, 255));
+        assert_eq!(indexed_rgb(232), (8, 8, 8));
+        assert_eq!(indexed_rgb(255), (238, 238, 238));
+    }
+
+    #[test]
+    fn quantize_to_256_colors() {
+        let q = |r, g, b| ColorMode::Indexed256.quantize(Color::Rgb(r, g, b));
+        assert_eq!(q(255, 0, 0), Color::Indexed(196));
+        assert_eq!(q(0x87, 0xaf, 0xd7), Color::Indexed(110));
+        // Dark grays land on the gray ramp rather than the cube.
+        assert_eq!(q(0x28, 0x28, 0x28), Color::Indexed(235));
+        // Named and indexed colors pass through.
+        assert_eq!(ColorMode::Indexed256.quantize(Color::Cyan), Color::Cyan);
+        assert_eq!(
+            ColorMode::Indexed256.quantize(Color::Indexed(42)),
+            Color::Indexed(42)
+        );
+    }
+
+    #[test]
+    fn quantize_to_16_colors() {
+        let q = |r, g, b| ColorMode::Ansi16.quantize(Color::Rgb(r, g, b));
+        assert_eq!(q(255, 0, 0), Color::LightRed);
+        assert_eq!(q(0x80, 0, 0), Color::Red);
+        assert_eq!(q(0x28, 0x28, 0x28), Color::Black);
+        assert_eq!(q(0xfd, 0xf6, 0xe3), Color::White);
+        assert_eq!(
+            ColorMode::Ansi16.quantize(Color::Indexed(196)),
+            Color::LightRed
+        );
+        assert_eq!(
+            ColorMode::TrueColor.quantize(Color::Rgb(1, 2, 3)),
+            Color::Rgb(1, 2, 3)
+        );
+    }
+
+    #[test]
+    fn color_mode_parse() {
+        assert_eq!(ColorMode::parse("256"), Some(ColorMode::Indexed256));
+        assert_eq!(ColorMode::parse("16"), Some(ColorMode::Ansi16));
+        assert_eq!(ColorMode::parse("TrueColor"), Some(ColorMode::TrueColor));
+        assert_eq!(ColorMode::parse("88"), None);
+    }
+
+    #[test]
+    fn every_preset_has_both_variants_and_resolves() {
+        assert_eq!(THEME_PRESETS.len() % 2, 0);
+        for (i, name) in THEME_PRESETS.iter().enumerate() {
+            let theme = Theme::preset(name).unwrap();
+            ResolvedTheme::resolve(&theme, None, ColorMode::TrueColor).unwrap();
+            let mode = if i % 2 == 0 {
+                ThemeMode::Dark
+            } else {
+                ThemeMode::Light
+            };
+            assert_eq!(ThemeMode::of_preset(name), mode);
+            assert_eq!(preset_variant(name, mode), *name);
+        }
+        assert_eq!(
+            preset_variant("solarized-light", ThemeMode::Dark),
+            "solarized-dark"
+        );
+        assert_eq!(preset_variant("nord", ThemeMode::Light), "nord-light");
+        assert_eq!(preset_variant("unknown", ThemeMode::Light), "default-light");
+    }
+
+    #[test]
+    fn theme_mode_from_config_and_terminal() {
+        let config = |mode: &str, command: &str| ThemeConfig {
+            preset: "solarized-light".to_string(),
+            mode: mode.to_string(),
+            mode_command: command.to_string(),
+            ..ThemeConfig::default()
+        };
+        assert_eq!(ThemeMode::from_config(&config("", "")), ThemeMode::Light);
+        assert_eq!(ThemeMode::from_config(&config("dark", "")), ThemeMode::Dark);
+        assert_eq!(
+            ThemeMode::from_config(&config("auto", "echo Dark")),
+            ThemeMode::Dark
+        );
+        // A command that fails or prints something else leaves it to $COLORFGBG or the preset.
+        let fallback = ThemeMode::from_config(&config("auto", "exit 1"));
+        assert_eq!(
+            fallback,
+            ThemeMode::from_config(&config("auto", "echo dim"))
+        );
+
+        assert_eq!(ThemeMode::from_colorfgbg("15;0"), Some(ThemeMode::Dark));
+        assert_eq!(
+            ThemeMode::from_colorfgbg("0;default;15"),
+            Some(ThemeMode::Light)
+        );
+        assert_eq!(ThemeMode::from_colorfgbg("0;7"), Some(ThemeMode::Light));
+        assert_eq!(ThemeMode::from_colorfgbg("default"), None);
+    }
+
+    #[test]
+    fn theme_toml_sections_apply_in_their_mode() {
+        let dir = std::env::temp_dir().join(format!("oxid-theme-mode-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("theme.toml"),
+            "border = \"#010101\"\neditor_fg = \"#020202\"\n\n[light]\neditor_fg = \"#030303\"\n",
+        )
+        .unwrap();
+        let dark = load_theme(&dir, "gruvbox-dark", ThemeMode::Dark).unwrap();
+        assert_eq!(dark.editor_fg.0, "#020202");
+        assert_eq!(dark.app_background.0, "#282828");
+        let light = load_theme(&dir, "gruvbox-dark", ThemeMode::Light).unwrap();
+        assert_eq!(light.editor_fg.0, "#030303");
+        assert_eq!(light.border.0, "#010101");
+        assert_eq!(light.app_background.0, "#fbf1c7");
+
+        fs::write(dir.join("theme.toml"), "light = \"white\"\n").unwrap();
+        assert!(load_theme(&dir, "default", ThemeMode::Light).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn malformed_hex_errors_name_the_problem_and_key() {
+        let err = |s: &str| format!("{:#}", parse_color_str(s).unwrap_err());
+        assert!(err("ff0000").contains("leading '#'"));
+        assert!(err("#ff00").contains("got 4"));
+        assert!(err("#gg0000").contains("not a hex digit"));
+
+        let theme = Theme {
+            border: def("#12345"),
+            ..Theme::default()
+        };
+        let Err(e) = ResolvedTheme::resolve(&theme, None, ColorMode::TrueColor) else {
+            panic!("malformed border color resolved");
+        };
+        assert!(format!("{e:#}").contains("`border`"));
+    }
+}
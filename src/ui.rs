@@ -1,15 +1,30 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // oxid - A fast, keyboard-driven note manager TUI for Linux
 
-use crate::app::{App, EditorLayout, Focus, Mode};
+use crate::app::{App, EditorBuffer, EditorLayout, EditorMode, Focus, Mode, PaletteItem};
+use crate::config::StatusSegment;
+use crate::diff::LineKind as DiffLineKind;
+use crate::export::ExportScope;
 use crate::git::GitStatus;
-use crate::markdown::render_markdown;
+use crate::scan::TaskEntry;
+use crate::search::{find_all_text, CaseMode, SearchMode, REGEX_PREFIX};
+use crate::tasks;
+use crate::telescope::TelescopeMode;
 use crate::templates::Template;
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use crate::text_input::TextInput;
+use crate::wrap;
+use chrono::NaiveDate;
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
 use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Wrap};
+use ratatui::widgets::{
+    Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap,
+};
 use ratatui::Frame;
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
+use unicode_width::UnicodeWidthChar;
 
 fn border_type_from_config(border_style: &str) -> BorderType {
     match border_style.trim().to_lowercase().as_str() {
@@ -23,67 +38,117 @@ fn border_type_from_config(border_style: &str) -> BorderType {
 /// Build a Line with search match highlighting. Indices are character positions.
 fn build_highlighted_line(
     text: &str,
-    match_indices: Vec<u32>,
+    match_indices: &[u32],
     base_style: Style,
     match_style: Style,
 ) -> Line<'static> {
+    Line::from(highlighted_spans(
+        text,
+        match_indices,
+        base_style,
+        match_style,
+    ))
+}
+
+/// Spans for `text` with the characters at `match_indices` in `match_style`. Consecutive
+/// characters with the same style share one span. Indices count chars; a zero-width char
+/// such as a combining accent takes the style of the char it combines with, so a cell is
+/// never split between two spans.
+fn highlighted_spans(
+    text: &str,
+    match_indices: &[u32],
+    base_style: Style,
+    match_style: Style,
+) -> Vec<Span<'static>> {
     if match_indices.is_empty() {
-        return Line::from(Span::styled(text.to_string(), base_style));
+        return vec![Span::styled(text.to_string(), base_style)];
     }
-    let match_set: std::collections::HashSet<u32> = match_indices.into_iter().collect();
+    let mut matched = match_indices.to_vec();
+    matched.sort_unstable();
+    let mut matched = matched.into_iter().peekable();
     let mut spans = Vec::new();
-    for (i, c) in text.chars().enumerate() {
-        let style = if match_set.contains(&(i as u32)) {
-            match_style
-        } else {
-            base_style
-        };
-        spans.push(Span::styled(c.to_string(), style));
+    // Byte offset where the current run starts, and whether it is a match.
+    let mut run: Option<(usize, bool)> = None;
+    for (i, (offset, c)) in text.char_indices().enumerate() {
+        let i = u32::try_from(i).unwrap_or(u32::MAX);
+        while matched.next_if(|&m| m < i).is_some() {}
+        let is_match = matched.next_if_eq(&i).is_some();
+        if run.is_some() && c.width() == Some(0) {
+            continue;
+        }
+        match run {
+            Some((_, current)) if current == is_match => {}
+            Some((start, current)) => {
+                let style = if current { match_style } else { base_style };
+                spans.push(Span::styled(text[start..offset].to_string(), style));
+                run = Some((offset, is_match));
+            }
+            None => run = Some((offset, is_match)),
+        }
     }
-    Line::from(spans)
+    if let Some((start, current)) = run {
+        let style = if current { match_style } else { base_style };
+        spans.push(Span::styled(text[start..].to_string(), style));
+    }
+    spans
 }
 
-/// Build a Line with substring match highlighting (case-insensitive) for preview pane.
+/// A preview line with every occurrence of `query` in `match_style`, ignoring case unless
+/// `case` respects it. Matches are found and drawn by character, so text whose lowercase
+/// form has a different length in bytes keeps its highlight in place.
 fn build_preview_line_with_highlight(
     line: &str,
     query: &str,
+    case: CaseMode,
     base_style: Style,
     match_style: Style,
 ) -> Line<'static> {
-    if query.is_empty() {
-        return Line::from(Span::styled(line.to_string(), base_style));
-    }
-    let query_lower = query.to_lowercase();
-    let line_lower = line.to_lowercase();
-    let match_len = query_lower.len();
+    #[allow(clippy::cast_possible_truncation)]
+    let indices: Vec<u32> = find_all_text(line, query, case)
+        .into_iter()
+        .flatten()
+        .map(|i| i as u32)
+        .collect();
+    build_highlighted_line(line, &indices, base_style, match_style)
+}
 
+/// A preview line with every match of `re` in `match_style`.
+fn build_preview_line_with_regex(
+    line: &str,
+    re: &Regex,
+    base_style: Style,
+    match_style: Style,
+) -> Line<'static> {
     let mut spans = Vec::new();
-    let mut remaining = line;
-    let mut search_start = 0;
-
-    while let Some(rel_start) = line_lower[search_start..].find(&query_lower) {
-        let before_match = &remaining[..rel_start];
-        let matched = &remaining[rel_start..rel_start + match_len];
-        remaining = &remaining[rel_start + match_len..];
-        search_start += rel_start + match_len;
-
-        if !before_match.is_empty() {
-            spans.push(Span::styled(before_match.to_string(), base_style));
+    let mut end = 0;
+    for found in re.find_iter(line).filter(|m| !m.is_empty()) {
+        if found.start() > end {
+            spans.push(Span::styled(
+                line[end..found.start()].to_string(),
+                base_style,
+            ));
         }
-        spans.push(Span::styled(matched.to_string(), match_style));
+        spans.push(Span::styled(found.as_str().to_string(), match_style));
+        end = found.end();
     }
-    if !remaining.is_empty() {
-        spans.push(Span::styled(remaining.to_string(), base_style));
+    if end < line.len() || spans.is_empty() {
+        spans.push(Span::styled(line[end..].to_string(), base_style));
     }
+    Line::from(spans)
+}
 
-    if spans.is_empty() {
-        Line::from(Span::styled(line.to_string(), base_style))
-    } else {
-        Line::from(spans)
+/// Center a rect within area with given size.
+/// With `ui.dim_inactive`, patch the inside of the bordered pane at `area` with the
+/// inactive style unless it has the focus.
+fn dim_unless_focused(frame: &mut Frame, app: &App, area: Rect, focused: bool) {
+    if app.config.ui.dim_inactive && !focused {
+        let inner = area.inner(Margin::new(1, 1));
+        frame
+            .buffer_mut()
+            .set_style(inner, app.theme.inactive_style);
     }
 }
 
-/// Center a rect within area with given size.
 fn centered_rect(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let popup_width = area.width * percent_x / 100;
     let popup_height = area.height * percent_y / 100;
@@ -111,13 +176,18 @@ pub fn draw(frame: &mut Frame, app: &App) {
         draw_command_palette_popup(frame, app, area);
         return;
     }
-    if app.tag_explorer_active {
-        draw_tag_explorer_popup(frame, app, area);
-        return;
-    }
-    if app.task_view_active {
-        draw_task_view_popup(frame, app, area);
-        return;
+    match app.underlying_focus() {
+        Focus::TagExplorer => {
+            draw_tag_explorer_popup(frame, app, area);
+            draw_prompt_popup(frame, app, area);
+            return;
+        }
+        Focus::TaskView => {
+            draw_task_view_popup(frame, app, area);
+            draw_prompt_popup(frame, app, area);
+            return;
+        }
+        _ => {}
     }
     if app.focus == Focus::Rename {
         draw_rename_popup(frame, app, area);
@@ -131,6 +201,98 @@ pub fn draw(frame: &mut Frame, app: &App) {
         draw_delete_confirm_popup(frame, app, area);
         return;
     }
+    if app.focus == Focus::TagPrompt {
+        draw_tag_prompt_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::BufferPicker {
+        draw_buffer_picker_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::RecentFiles {
+        draw_recent_files_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::ReplacePrompt {
+        draw_replace_prompt_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::ReplaceReview {
+        draw_replace_review_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::SpellSuggest {
+        draw_spell_suggest_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::ConfigError {
+        draw_config_error_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::UserCommandConfirm {
+        draw_user_command_confirm_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::CommandOutput {
+        draw_command_output_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::ImagePathPrompt {
+        draw_image_path_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::GitRemotePrompt {
+        draw_git_remote_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::ExportScope {
+        draw_export_scope_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::ExportDir {
+        draw_export_dir_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::ExportSummary {
+        draw_export_summary_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::GraphOptions {
+        draw_graph_options_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::GraphPath {
+        draw_graph_path_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::AliasPicker {
+        draw_alias_picker_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::DiffView {
+        draw_diff_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::Messages {
+        draw_messages_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::RandomNote {
+        draw_random_note_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::KeybindingReport {
+        draw_keybinding_report_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::DuplicateConfirm {
+        draw_duplicate_confirm_popup(frame, app, area);
+        return;
+    }
+    if app.focus == Focus::MovePicker {
+        draw_move_picker_popup(frame, app, area);
+        return;
+    }
     if app.template_picker_active {
         draw_template_picker_popup(frame, app, area);
         return;
@@ -158,64 +320,257 @@ pub fn draw(frame: &mut Frame, app: &App) {
             .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
             .split(main_area);
 
-        let main_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(20),
-                Constraint::Percentage(40),
-                Constraint::Percentage(40),
-            ])
-            .split(vertical_chunks[0]);
-
-        draw_notes_list(frame, app, main_chunks[0]);
-        if app.editor_layout == EditorLayout::SplitVertical {
-            let editor_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-                .split(main_chunks[1]);
-            draw_editor_pane_at(frame, app, editor_chunks[0], app.active_tab);
-            if let Some(right_idx) = app.split_right_tab {
-                draw_editor_pane_at(frame, app, editor_chunks[1], right_idx);
-            }
-        } else {
-            draw_editor_pane(frame, app, main_chunks[1]);
-        }
-        draw_preview_pane(frame, app, main_chunks[2]);
+        draw_columns(frame, app, vertical_chunks[0]);
         draw_backlinks_pane(frame, app, vertical_chunks[1]);
     } else {
-        let main_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(20),
-                Constraint::Percentage(40),
-                Constraint::Percentage(40),
+        draw_columns(frame, app, main_area);
+    }
+
+    draw_footer(frame, app, chunks[3]);
+    draw_which_key_popup(frame, app, main_area);
+    draw_prompt_popup(frame, app, area);
+}
+
+/// Quick Capture or Add Task, drawn over the view they were opened from so capturing does not
+/// hide what you were doing.
+fn draw_prompt_popup(frame: &mut Frame, app: &App, area: Rect) {
+    match app.focus {
+        Focus::QuickCapture => draw_quick_capture_popup(frame, app, area),
+        Focus::AddTask => draw_add_task_popup(frame, app, area),
+        _ => {}
+    }
+}
+
+/// A prompt's text with the cursor in it: the character under the cursor in reverse video,
+/// or a reversed space after the text.
+fn input_spans(input: &TextInput, style: Style) -> Vec<Span<'_>> {
+    let (before, under, after) = input.split_at_cursor();
+    let cursor = under.map_or_else(|| " ".to_string(), String::from);
+    vec![
+        Span::styled(before, style),
+        Span::styled(cursor, style.add_modifier(Modifier::REVERSED)),
+        Span::styled(after, style),
+    ]
+}
+
+/// `prompt` followed by the text of `input` with its cursor.
+fn prompt_line<'a>(prompt: Span<'a>, input: &'a TextInput, style: Style) -> Line<'a> {
+    Line::from([vec![prompt], input_spans(input, style)].concat())
+}
+
+fn draw_random_note_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Open Random Note │ Tab scope │ Enter open ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 50, 20);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let scope = if app.random_in_current_dir {
+        "current directory"
+    } else {
+        "whole vault"
+    };
+    let lines = vec![
+        prompt_line(
+            Span::styled("Tag (optional): ", app.theme.help_text_style),
+            &app.random_tag,
+            app.theme.text_style,
+        ),
+        Line::from(vec![
+            Span::styled("From: ", app.theme.help_text_style),
+            Span::styled(scope, app.theme.highlight_style),
+        ]),
+    ];
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn draw_messages_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(format!(" Messages ({}) │ Esc close ", app.messages.len()))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 70, 60);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    // Newest first. Instants have no wall-clock time, so it is worked out from their age.
+    let now = chrono::Local::now();
+    let lines: Vec<Line> = app
+        .messages
+        .iter()
+        .rev()
+        .skip(app.messages_scroll)
+        .map(|(at, message)| {
+            let time = chrono::Duration::from_std(at.elapsed())
+                .map_or(now, |age| now - age)
+                .format("%H:%M:%S");
+            Line::from(vec![
+                Span::styled(format!("{time}  "), app.theme.help_text_style),
+                Span::styled(message.as_str(), app.theme.list_text_normal_style),
             ])
-            .split(main_area);
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
 
-        draw_notes_list(frame, app, main_chunks[0]);
-        if app.editor_layout == EditorLayout::SplitVertical {
-            let editor_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-                .split(main_chunks[1]);
-            draw_editor_pane_at(frame, app, editor_chunks[0], app.active_tab);
-            if let Some(right_idx) = app.split_right_tab {
-                draw_editor_pane_at(frame, app, editor_chunks[1], right_idx);
-            }
-        } else {
-            draw_editor_pane(frame, app, main_chunks[1]);
-        }
-        draw_preview_pane(frame, app, main_chunks[2]);
+fn draw_diff_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(format!(" {} │ Esc close ", app.diff_title))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 80, 80);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let lines: Vec<Line> = app
+        .diff_lines
+        .iter()
+        .skip(app.diff_scroll)
+        .take(usize::from(inner.height))
+        .map(|(kind, line)| {
+            let style = match kind {
+                DiffLineKind::Header => app.theme.header_style,
+                DiffLineKind::Hunk => app.theme.help_text_style,
+                DiffLineKind::Context => app.theme.text_style,
+                DiffLineKind::Added => app.theme.diff_add_style,
+                DiffLineKind::Removed => app.theme.diff_remove_style,
+            };
+            Line::from(Span::styled(line.as_str(), style))
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn draw_quick_capture_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(format!(
+            " Quick Capture → {} │ Enter save │ Esc cancel ",
+            app.config.capture_note.trim()
+        ))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 60, 15);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let content = prompt_line(
+        Span::styled("> ", app.theme.highlight_style),
+        &app.capture_input,
+        app.theme.text_style,
+    );
+    frame.render_widget(Paragraph::new(content), inner);
+}
+
+fn draw_add_task_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Add Task → daily note, or @note first │ Enter add │ Esc cancel ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 60, 15);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let content = prompt_line(
+        Span::styled("- [ ] ", app.theme.highlight_style),
+        &app.task_input,
+        app.theme.text_style,
+    );
+    frame.render_widget(Paragraph::new(content), inner);
+}
+
+/// Hint popup along the bottom of `area` listing the continuations of the pending key
+/// prefix, laid out in as many columns as fit.
+fn draw_which_key_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let mut hints = app.which_key_hints();
+    if hints.is_empty() {
+        return;
     }
+    hints.sort();
+    let entries: Vec<String> = hints
+        .iter()
+        .map(|(key, label)| format!("{key}: {label}"))
+        .collect();
+    let inner_width = area.width.saturating_sub(2).max(1) as usize;
+    let col_width = entries.iter().map(|e| e.chars().count()).max().unwrap_or(0) + 3;
+    let columns = (inner_width / col_width).max(1);
+    let rows = entries.len().div_ceil(columns);
+    let height = (rows as u16 + 2).min(area.height);
+    let popup_area = Rect::new(area.x, area.y + area.height - height, area.width, height);
 
-    draw_footer(frame, app, chunks[3]);
+    let block = Block::default()
+        .title(" Next key ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let lines: Vec<Line> = (0..rows)
+        .map(|row| {
+            let text: String = entries
+                .iter()
+                .skip(row)
+                .step_by(rows)
+                .map(|e| format!("{e:<col_width$}"))
+                .collect();
+            Line::from(Span::styled(
+                text.trim_end().to_string(),
+                app.theme.list_text_normal_style,
+            ))
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Draw the file list, editor, and (unless hidden) preview columns using the configured widths.
+fn draw_columns(frame: &mut Frame, app: &App, area: Rect) {
+    let mut constraints = vec![
+        Constraint::Percentage(app.list_width_percent),
+        Constraint::Percentage(app.editor_width_percent()),
+    ];
+    if app.show_preview {
+        constraints.push(Constraint::Percentage(app.preview_width_percent));
+    }
+    let main_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(constraints)
+        .split(area);
+
+    draw_notes_list(frame, app, main_chunks[0]);
+    draw_editor_area(frame, app, main_chunks[1]);
+    if app.show_preview {
+        draw_preview_pane(frame, app, main_chunks[2]);
+    }
 }
 
 fn draw_telescope_popup(frame: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
         .title(format!(
-            " {} │ Open File ",
-            app.get_key_display_string("search")
+            " {} │ {}{} │ {} {} │ {} {} ",
+            app.get_key_display_string("search"),
+            app.telescope_mode.title(),
+            if app.telescope_query.starts_with(REGEX_PREFIX) {
+                " (regex)"
+            } else {
+                ""
+            },
+            app.get_key_display_string("telescope_mode"),
+            app.telescope_mode.next().title(),
+            app.get_key_display_string("search_case"),
+            app.search_case.label()
         ))
         .borders(Borders::ALL)
         .border_type(border_type_from_config(&app.config.ui.border_style))
@@ -225,16 +580,65 @@ fn draw_telescope_popup(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(Clear, popup_area);
     frame.render_widget(block, popup_area);
 
-    let chunks = Layout::default()
+    let error_rows = u16::from(app.telescope_error.is_some());
+    let rows = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(1), Constraint::Min(1)])
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(error_rows),
+            Constraint::Min(1),
+        ])
         .split(inner);
+    let chunks = [rows[0], rows[2]];
 
-    let query_line = Line::from(vec![
+    let query_line = prompt_line(
         Span::styled("> ", app.theme.highlight_style),
-        Span::styled(&app.telescope_query, app.theme.text_style),
-    ]);
+        &app.telescope_query,
+        app.theme.text_style,
+    );
     frame.render_widget(Paragraph::new(query_line), chunks[0]);
+    if let Some(error) = &app.telescope_error {
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                format!("  {error}"),
+                app.theme.diff_remove_style,
+            )),
+            rows[1],
+        );
+    }
+
+    if app.telescope_mode == TelescopeMode::Buffers {
+        let items: Vec<ListItem> = app
+            .telescope_buffer_matches
+            .iter()
+            .enumerate()
+            .map(|(i, found)| {
+                let base_style = if i == app.telescope_selected {
+                    app.theme.list_text_selected_style
+                } else {
+                    app.theme.list_text_normal_style
+                };
+                // `tab:line: text`, the text without its indentation.
+                let indent = found.text.chars().take_while(|c| c.is_whitespace()).count();
+                let start = found.start.saturating_sub(indent);
+                let end = found.end.saturating_sub(indent);
+                let text: Vec<char> = found.text.chars().skip(indent).collect();
+                let piece = |from: usize, to: usize| text[from..to].iter().collect::<String>();
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{}:{}: ", found.tab, found.row + 1),
+                        app.theme.help_text_style,
+                    ),
+                    Span::styled(piece(0, start), base_style),
+                    Span::styled(piece(start, end), app.theme.search_match_style),
+                    Span::styled(piece(end, text.len()), base_style),
+                ]))
+            })
+            .collect();
+        frame.render_widget(List::new(items), chunks[1]);
+        app.telescope_rows.set(chunks[1].height);
+        return;
+    }
 
     let items: Vec<ListItem> = app
         .telescope_filtered
@@ -248,16 +652,13 @@ fn draw_telescope_popup(frame: &mut Frame, app: &App, area: Rect) {
             };
             let line = if !app.telescope_query.is_empty() && !app.telescope_query.starts_with('#') {
                 build_highlighted_line(
-                    &note.display,
-                    app.telescope_match_indices
-                        .get(i)
-                        .cloned()
-                        .unwrap_or_default(),
+                    &note.search_label(),
+                    app.telescope_highlights.indices(i),
                     base_style,
                     app.theme.search_match_style,
                 )
             } else {
-                Line::from(Span::styled(note.display.as_str(), base_style))
+                Line::from(Span::styled(note.label(), base_style))
             };
             ListItem::new(line)
         })
@@ -265,6 +666,7 @@ fn draw_telescope_popup(frame: &mut Frame, app: &App, area: Rect) {
 
     let list = List::new(items);
     frame.render_widget(list, chunks[1]);
+    app.telescope_rows.set(chunks[1].height);
 }
 
 fn draw_command_palette_popup(frame: &mut Frame, app: &App, area: Rect) {
@@ -286,23 +688,47 @@ fn draw_command_palette_popup(frame: &mut Frame, app: &App, area: Rect) {
         .constraints([Constraint::Length(1), Constraint::Min(1)])
         .split(inner);
 
-    let query_line = Line::from(vec![
+    let query_line = prompt_line(
         Span::styled("> ", app.theme.highlight_style),
-        Span::styled(&app.command_palette_query, app.theme.text_style),
-    ]);
+        &app.command_palette_query,
+        app.theme.text_style,
+    );
     frame.render_widget(Paragraph::new(query_line), chunks[0]);
 
+    let width = chunks[1].width as usize;
     let items: Vec<ListItem> = app
         .command_palette_filtered
         .iter()
         .enumerate()
-        .map(|(i, action)| {
+        .map(|(i, &item)| {
             let style = if i == app.command_palette_selected {
                 app.theme.list_text_selected_style
             } else {
                 app.theme.list_text_normal_style
             };
-            ListItem::new(Line::from(Span::styled(action.label(), style)))
+            let indices = app
+                .command_palette_match_indices
+                .get(i)
+                .map_or(&[][..], Vec::as_slice);
+            let label = app.palette_label(item);
+            let mut line =
+                build_highlighted_line(label, indices, style, app.theme.search_match_style);
+            let key = match item {
+                PaletteItem::Action(action) => action
+                    .key_name()
+                    .map(|name| app.get_key_display_string(name))
+                    .unwrap_or_default(),
+                PaletteItem::User(_) => String::new(),
+            };
+            if !key.is_empty() {
+                let used = label.chars().count() + key.chars().count();
+                let pad = width.saturating_sub(used).max(1);
+                line.spans.push(Span::styled(" ".repeat(pad), style));
+                // Keep the row's selection background behind the key hint.
+                line.spans
+                    .push(Span::styled(key, style.patch(app.theme.help_text_style)));
+            }
+            ListItem::new(line)
         })
         .collect();
 
@@ -324,10 +750,40 @@ fn draw_rename_popup(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(Clear, popup_area);
     frame.render_widget(block, popup_area);
 
-    let content = Line::from(vec![
+    let content = prompt_line(
         Span::styled("New name: ", app.theme.help_text_style),
-        Span::styled(&app.rename_input, app.theme.highlight_style),
-    ]);
+        &app.rename_input,
+        app.theme.highlight_style,
+    );
+    frame.render_widget(Paragraph::new(content), inner);
+}
+
+fn draw_tag_prompt_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let name = match app.tag_pending.as_slice() {
+        [path] => path
+            .file_name()
+            .map_or_else(String::new, |n| n.to_string_lossy().into_owned()),
+        notes => format!("{} marked notes", notes.len()),
+    };
+    let block = Block::default()
+        .title(format!(
+            " {} │ Add Tag to {} ",
+            app.get_key_display_string("list_tag"),
+            name
+        ))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 50, 15);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let content = prompt_line(
+        Span::styled("Tag: #", app.theme.help_text_style),
+        &app.tag_input,
+        app.theme.highlight_style,
+    );
     frame.render_widget(Paragraph::new(content), inner);
 }
 
@@ -342,10 +798,10 @@ fn draw_delete_confirm_popup(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(Clear, popup_area);
     frame.render_widget(block, popup_area);
 
-    let name = app
-        .delete_pending
-        .as_ref()
-        .map_or("?", |e| e.display.as_str());
+    let name = match app.delete_pending.as_slice() {
+        [entry] => entry.display.clone(),
+        entries => format!("{} marked entries", entries.len()),
+    };
     let content = Line::from(vec![
         Span::styled("Delete ", app.theme.help_text_style),
         Span::styled(name, app.theme.highlight_style),
@@ -354,8 +810,659 @@ fn draw_delete_confirm_popup(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(Paragraph::new(content), inner);
 }
 
-fn draw_tag_explorer_popup(frame: &mut Frame, app: &App, area: Rect) {
-    use crate::app::TagExplorerView;
+fn draw_buffer_picker_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Split │ Choose Buffer ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 40, 40);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let items: Vec<ListItem> = app
+        .buffer_picker_candidates()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, idx)| {
+            let buf = app.buffers.get(idx)?;
+            let style = if i == app.buffer_picker_selected {
+                app.theme.list_text_selected_style
+            } else {
+                app.theme.list_text_normal_style
+            };
+            Some(ListItem::new(Line::from(Span::styled(
+                buf.display_name(),
+                style,
+            ))))
+        })
+        .collect();
+
+    // Stateful so a selection below the popup's last row scrolls into view.
+    let mut state = ListState::default().with_selected(Some(app.buffer_picker_selected));
+    frame.render_stateful_widget(List::new(items), inner, &mut state);
+}
+
+fn draw_recent_files_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(format!(" Recent Files ({}) ", app.recent.paths.len()))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 60, 60);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let items: Vec<ListItem> = app
+        .recent
+        .paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let display = path
+                .strip_prefix(&app.notes_dir)
+                .map_or_else(|_| path.display().to_string(), |p| p.display().to_string());
+            let style = if i == app.recent_selected {
+                app.theme.list_text_selected_style
+            } else {
+                app.theme.list_text_normal_style
+            };
+            ListItem::new(Line::from(Span::styled(display, style)))
+        })
+        .collect();
+
+    let list = List::new(items);
+    frame.render_widget(list, inner);
+}
+
+fn draw_replace_prompt_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let title = if app.replace_scanning() {
+        " Replace in Vault │ Scanning... "
+    } else {
+        " Replace in Vault │ Tab switch field │ Enter continue "
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 60, 20);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let field = |label: &'static str, value, active: bool| {
+        let label = Span::styled(label, app.theme.help_text_style);
+        if active {
+            prompt_line(label, value, app.theme.highlight_style)
+        } else {
+            Line::from(vec![
+                label,
+                Span::styled(value.as_str(), app.theme.text_style),
+            ])
+        }
+    };
+    let content = vec![
+        field("Find:    ", &app.replace_pattern, !app.replace_editing_with),
+        field("Replace: ", &app.replace_with, app.replace_editing_with),
+    ];
+    frame.render_widget(Paragraph::new(content), inner);
+}
+
+fn draw_replace_review_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let total: usize = app
+        .replace_files
+        .iter()
+        .map(|f| f.enabled_lines().count())
+        .sum();
+    let mode = if app.replace_dry_run {
+        " [dry run]"
+    } else {
+        ""
+    };
+    let block = Block::default()
+        .title(format!(
+            " Replace: {total} lines{mode} │ {} toggle │ {} dry run │ Enter apply ",
+            app.get_key_display_string("list_mark"),
+            app.get_key_display_string("replace_dry_run"),
+        ))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 80, 80);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let check = |on: bool| if on { "[x] " } else { "[ ] " };
+    let rows = app.replace_rows();
+    let items: Vec<ListItem> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, &(fi, li))| {
+            let file = &app.replace_files[fi];
+            let selected = i == app.replace_selected;
+            let base = if selected {
+                app.theme.list_text_selected_style
+            } else {
+                app.theme.list_text_normal_style
+            };
+            match li {
+                None => {
+                    let display = file.path.strip_prefix(&app.notes_dir).map_or_else(
+                        |_| file.path.display().to_string(),
+                        |p| p.display().to_string(),
+                    );
+                    ListItem::new(Line::from(Span::styled(
+                        format!("{}{display} ({})", check(file.enabled), file.lines.len()),
+                        base.add_modifier(Modifier::BOLD),
+                    )))
+                }
+                Some(li) => {
+                    let line = &file.lines[li];
+                    let on = file.enabled && line.enabled;
+                    ListItem::new(vec![
+                        Line::from(Span::styled(
+                            format!(
+                                "  {}{:>4}: - {}",
+                                check(line.enabled),
+                                line.line + 1,
+                                line.before
+                            ),
+                            base,
+                        )),
+                        Line::from(Span::styled(
+                            format!("              + {}", line.after),
+                            if on {
+                                app.theme.highlight_style
+                            } else {
+                                app.theme.help_text_style
+                            },
+                        )),
+                    ])
+                }
+            }
+        })
+        .collect();
+
+    // Scroll so the selected row stays visible (match rows are two lines tall).
+    let height = inner.height as usize;
+    let row_height = |&(_, li): &(usize, Option<usize>)| if li.is_some() { 2 } else { 1 };
+    let mut start = 0;
+    let mut used: usize = rows
+        .iter()
+        .take(app.replace_selected + 1)
+        .map(row_height)
+        .sum();
+    while used > height && start < app.replace_selected {
+        used -= row_height(&rows[start]);
+        start += 1;
+    }
+    let list = List::new(items.into_iter().skip(start).collect::<Vec<_>>());
+    frame.render_widget(list, inner);
+}
+
+fn draw_spell_suggest_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Spelling Suggestions ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 30, 40);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let items: Vec<ListItem> = app
+        .spell_suggestions
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            let style = if i == app.spell_suggestion_selected {
+                app.theme.list_text_selected_style
+            } else {
+                app.theme.list_text_normal_style
+            };
+            ListItem::new(Line::from(Span::styled(word.as_str(), style)))
+        })
+        .collect();
+
+    let list = List::new(items);
+    frame.render_widget(list, inner);
+}
+
+fn draw_config_error_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Config Error ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 70, 50);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let mut lines: Vec<Line> = Vec::new();
+    for error in &app.config_errors {
+        lines.push(Line::from(Span::styled(
+            error.path.display().to_string(),
+            app.theme.highlight_style,
+        )));
+        lines.extend(
+            error
+                .message
+                .lines()
+                .map(|l| Line::from(Span::styled(l.to_string(), app.theme.text_style))),
+        );
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!("{} ", app.get_key_display_string("list_edit_config")),
+            app.theme.help_text_style,
+        ),
+        Span::styled("open file", app.theme.highlight_style),
+        Span::styled(
+            format!(" | {} ", app.get_key_display_string("escape")),
+            app.theme.help_text_style,
+        ),
+        Span::styled("continue with defaults", app.theme.highlight_style),
+    ]));
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn draw_keybinding_report_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(format!(
+            " Keybinding Problems ({}) ",
+            app.keybinding_report.len()
+        ))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 70, 50);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let lines: Vec<Line> = app
+        .keybinding_report
+        .iter()
+        .skip(app.keybinding_report_scroll)
+        .map(|problem| {
+            Line::from(Span::styled(
+                format!("• {problem}"),
+                app.theme.list_text_normal_style,
+            ))
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn draw_user_command_confirm_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Run Command ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 50, 15);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let name = app
+        .user_command_pending
+        .and_then(|i| app.config.commands.get(i))
+        .map_or("?", |c| c.name.as_str());
+    let content = Line::from(vec![
+        Span::styled("Run ", app.theme.help_text_style),
+        Span::styled(name, app.theme.highlight_style),
+        Span::styled("? [y/N] ", app.theme.help_text_style),
+    ]);
+    frame.render_widget(Paragraph::new(content), inner);
+}
+
+fn draw_command_output_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(cmd) = &app.user_command else { return };
+    let (title, hint) = match &cmd.finished {
+        None => (
+            format!(" {} {} ", cmd.spinner(), cmd.name),
+            format!(" {}: cancel ", app.get_key_display_string("escape")),
+        ),
+        Some(status) => (
+            format!(" {} │ {status} ", cmd.name),
+            format!(" {}: close ", app.get_key_display_string("enter")),
+        ),
+    };
+    let block = Block::default()
+        .title(title)
+        .title_bottom(Line::from(Span::styled(hint, app.theme.help_text_style)))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 70, 50);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    // The most recent lines that fit.
+    let height = inner.height as usize;
+    let start = cmd.output.len().saturating_sub(height);
+    let lines: Vec<Line> = cmd.output[start..]
+        .iter()
+        .map(|l| Line::from(Span::styled(l.clone(), app.theme.list_text_normal_style)))
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn draw_image_path_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Insert Image │ File to copy into assets ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 60, 15);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let content = prompt_line(
+        Span::styled("> ", app.theme.highlight_style),
+        &app.image_path_input,
+        app.theme.text_style,
+    );
+    frame.render_widget(Paragraph::new(content), inner);
+}
+
+fn draw_git_remote_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Git Init │ Remote URL for origin (Enter to skip) ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 60, 15);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let content = prompt_line(
+        Span::styled("> ", app.theme.highlight_style),
+        &app.git_remote_input,
+        app.theme.text_style,
+    );
+    frame.render_widget(Paragraph::new(content), inner);
+}
+
+fn draw_export_scope_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Export to HTML │ Choose Scope ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 40, 20);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let items: Vec<ListItem> = ExportScope::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, scope)| {
+            let style = if i == app.export_scope_selected {
+                app.theme.list_text_selected_style
+            } else {
+                app.theme.list_text_normal_style
+            };
+            ListItem::new(Line::from(Span::styled(scope.label(), style)))
+        })
+        .collect();
+    frame.render_widget(List::new(items), inner);
+}
+
+fn draw_export_dir_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Export to HTML │ Output Directory │ Enter export ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 60, 15);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let content = prompt_line(
+        Span::styled("> ", app.theme.highlight_style),
+        &app.export_dir,
+        app.theme.text_style,
+    );
+    frame.render_widget(Paragraph::new(content), inner);
+}
+
+fn draw_export_summary_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(summary) = &app.export_summary else {
+        return;
+    };
+    let block = Block::default()
+        .title(" Export to HTML │ Done ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 70, 50);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled(
+            format!("Exported {} files to ", summary.exported),
+            app.theme.help_text_style,
+        ),
+        Span::styled(
+            summary.out_dir.display().to_string(),
+            app.theme.highlight_style,
+        ),
+    ])];
+    if summary.skipped_links.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "All wiki links resolved",
+            app.theme.help_text_style,
+        )));
+    } else {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{} links skipped (target not in the export):",
+                summary.skipped_links.len()
+            ),
+            app.theme.help_text_style,
+        )));
+        lines.extend(
+            summary
+                .skipped_links
+                .iter()
+                .skip(app.export_summary_scroll)
+                .map(|link| {
+                    Line::from(Span::styled(
+                        format!("• {link}"),
+                        app.theme.list_text_normal_style,
+                    ))
+                }),
+        );
+    }
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn draw_graph_options_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Export Link Graph │ h/l change │ Enter continue ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 50, 20);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let options = &app.graph_options;
+    let neighborhood = match options.hops {
+        0 => "whole vault".to_string(),
+        1 => "current note + 1 hop".to_string(),
+        n => format!("current note + {n} hops"),
+    };
+    let rows = [
+        ("Format", options.format.label().to_string()),
+        (
+            "Orphan notes",
+            if options.exclude_orphans {
+                "exclude"
+            } else {
+                "include"
+            }
+            .to_string(),
+        ),
+        ("Notes", neighborhood),
+    ];
+    let items: Vec<ListItem> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, value))| {
+            let style = if i == app.graph_option_selected {
+                app.theme.list_text_selected_style
+            } else {
+                app.theme.list_text_normal_style
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{name:<14}"), style),
+                Span::styled(
+                    format!("‹ {value} ›"),
+                    style.patch(app.theme.highlight_style),
+                ),
+            ]))
+        })
+        .collect();
+    frame.render_widget(List::new(items), inner);
+}
+
+fn draw_graph_path_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Export Link Graph │ Output File │ Enter export ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 60, 15);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let content = prompt_line(
+        Span::styled("> ", app.theme.highlight_style),
+        &app.graph_path,
+        app.theme.text_style,
+    );
+    frame.render_widget(Paragraph::new(content), inner);
+}
+
+fn draw_alias_picker_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(format!(
+            " [[{}]] │ Several notes use this alias ",
+            app.alias_pending
+        ))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 60, 40);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let items: Vec<ListItem> = app
+        .alias_candidates
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let style = if i == app.alias_selected {
+                app.theme.list_text_selected_style
+            } else {
+                app.theme.list_text_normal_style
+            };
+            let rel = path.strip_prefix(&app.notes_dir).unwrap_or(path);
+            ListItem::new(Line::from(Span::styled(rel.display().to_string(), style)))
+        })
+        .collect();
+    frame.render_widget(List::new(items), inner);
+}
+
+fn draw_duplicate_confirm_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Duplicate ")
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 50, 15);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let name = app
+        .duplicate_pending
+        .as_ref()
+        .map_or("?", |e| e.display.as_str());
+    let content = Line::from(vec![
+        Span::styled("Copy directory ", app.theme.help_text_style),
+        Span::styled(name, app.theme.highlight_style),
+        Span::styled(" recursively? [y/N] ", app.theme.help_text_style),
+    ]);
+    frame.render_widget(Paragraph::new(content), inner);
+}
+
+fn draw_move_picker_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let name = match app.move_pending.as_slice() {
+        [entry] => entry.display.clone(),
+        entries => format!("{} marked entries", entries.len()),
+    };
+    let block = Block::default()
+        .title(format!(
+            " {} │ Move {} to... ",
+            app.get_key_display_string("list_move"),
+            name
+        ))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.list_border_active_style);
+    let popup_area = centered_rect(area, 50, 50);
+    let inner = block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    let items: Vec<ListItem> = app
+        .move_targets
+        .iter()
+        .enumerate()
+        .map(|(i, dir)| {
+            let display = dir.strip_prefix(&app.notes_dir).map_or_else(
+                |_| dir.display().to_string(),
+                |p| format!("/{}", p.display()),
+            );
+            let style = if i == app.move_target_selected {
+                app.theme
+                    .list_directory_style
+                    .patch(app.theme.list_text_selected_style)
+            } else {
+                app.theme.list_directory_style
+            };
+            ListItem::new(Line::from(Span::styled(display, style)))
+        })
+        .collect();
+
+    // Stateful so a selection below the popup's last row scrolls into view.
+    let mut state = ListState::default().with_selected(Some(app.move_target_selected));
+    frame.render_stateful_widget(List::new(items), inner, &mut state);
+}
+
+fn draw_tag_explorer_popup(frame: &mut Frame, app: &App, area: Rect) {
+    use crate::app::TagExplorerView;
 
     let popup_area = {
         let vertical = Layout::default()
@@ -411,17 +1518,20 @@ fn draw_tag_explorer_popup(frame: &mut Frame, app: &App, area: Rect) {
             .iter()
             .enumerate()
             .map(|(i, path)| {
-                let display = path
-                    .strip_prefix(&app.notes_dir)
-                    .map_or_else(
-                        |_| {
-                            path.file_name()
-                                .and_then(|n| n.to_str())
-                                .unwrap_or("")
-                                .to_string()
-                        },
-                        |p| p.display().to_string(),
-                    );
+                let display = app.note_title(path).map_or_else(
+                    || {
+                        path.strip_prefix(&app.notes_dir).map_or_else(
+                            |_| {
+                                path.file_name()
+                                    .and_then(|n| n.to_str())
+                                    .unwrap_or("")
+                                    .to_string()
+                            },
+                            |p| p.display().to_string(),
+                        )
+                    },
+                    str::to_string,
+                );
                 let style = if i == app.tag_file_selected {
                     app.theme.list_text_selected_style
                 } else {
@@ -468,53 +1578,94 @@ fn draw_task_view_popup(frame: &mut Frame, app: &App, area: Rect) {
 
     frame.render_widget(Clear, popup_area);
 
-    let items: Vec<ListItem> = app
-        .tasks
-        .iter()
-        .enumerate()
-        .map(|(i, task)| {
-            let rel_path = task
-                .path
-                .strip_prefix(&app.notes_dir)
-                .map_or_else(
-                    |_| {
-                        task.path
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("")
-                            .to_string()
-                    },
-                    |p| p.display().to_string(),
-                );
-            let display = if task.content.is_empty() {
-                format!("(empty) [{rel_path}]")
-            } else {
-                format!("{} [{}]", task.content, rel_path)
-            };
-            let style = if i == app.task_selected {
-                app.theme.list_text_selected_style
-            } else {
-                app.theme.list_text_normal_style
-            };
-            ListItem::new(Line::from(Span::styled(display, style)))
-        })
-        .collect();
+    let task_item = |i: usize, task: &TaskEntry| {
+        let rel_path = task.path.strip_prefix(&app.notes_dir).map_or_else(
+            |_| {
+                task.path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("")
+                    .to_string()
+            },
+            |p| p.display().to_string(),
+        );
+        let display = if task.content.is_empty() {
+            format!("(empty) [{rel_path}]")
+        } else {
+            format!("{} [{}]", task.content, rel_path)
+        };
+        let style = if i == app.task_selected && task.column == app.task_column {
+            app.theme.list_text_selected_style
+        } else {
+            app.theme.list_text_normal_style
+        };
+        ListItem::new(Line::from(Span::styled(display, style)))
+    };
+
+    let columns = &app.config.task_columns;
+    let column_keys = if columns.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "{}/{} column │ {} next column │ ",
+            app.get_key_display_string("move_left"),
+            app.get_key_display_string("move_right"),
+            app.get_key_display_string("task_advance"),
+        )
+    };
+    let block = Block::default()
+        .title(format!(
+            " Task Board ({} tasks) │ {}/{} move │ {}{} check │ {} open │ {} close ",
+            app.tasks.len(),
+            app.get_key_display_string("move_down"),
+            app.get_key_display_string("move_up"),
+            column_keys,
+            app.get_key_display_string("task_toggle"),
+            app.get_key_display_string("enter"),
+            app.get_key_display_string("escape")
+        ))
+        .borders(Borders::ALL)
+        .border_type(border_type_from_config(&app.config.ui.border_style))
+        .border_style(app.theme.border_style);
+    if columns.is_empty() {
+        let items: Vec<ListItem> = app
+            .tasks
+            .iter()
+            .enumerate()
+            .map(|(i, task)| task_item(i, task))
+            .collect();
+        frame.render_widget(List::new(items).block(block), popup_area);
+        return;
+    }
 
-    let list = List::new(items).block(
-        Block::default()
-            .title(format!(
-                " Task Board ({} tasks) │ {}/{} move │ {} open │ {} close ",
-                app.tasks.len(),
-                app.get_key_display_string("move_down"),
-                app.get_key_display_string("move_up"),
-                app.get_key_display_string("enter"),
-                app.get_key_display_string("escape")
-            ))
-            .borders(Borders::ALL)
-            .border_type(border_type_from_config(&app.config.ui.border_style))
-            .border_style(app.theme.border_style),
-    );
-    frame.render_widget(list, popup_area);
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+    #[allow(clippy::cast_possible_truncation)]
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Ratio(1, columns.len() as u32);
+            columns.len()
+        ])
+        .split(inner);
+    for (column, (name, &column_area)) in columns.iter().zip(areas.iter()).enumerate() {
+        let range = app.task_column_range(column);
+        let count = range.len();
+        let items: Vec<ListItem> = range.map(|i| task_item(i, &app.tasks[i])).collect();
+        let border_style = if column == app.task_column {
+            app.theme.list_border_active_style
+        } else {
+            app.theme.border_style
+        };
+        let list = List::new(items).block(
+            Block::default()
+                .title(format!(" #{} ({count}) ", name.trim_start_matches('#')))
+                .borders(Borders::ALL)
+                .border_type(border_type_from_config(&app.config.ui.border_style))
+                .border_style(border_style),
+        );
+        frame.render_widget(list, column_area);
+    }
 }
 
 fn draw_create_directory_popup(frame: &mut Frame, app: &App, area: Rect) {
@@ -531,10 +1682,11 @@ fn draw_create_directory_popup(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(Clear, popup_area);
     frame.render_widget(block, popup_area);
 
-    let content = Line::from(vec![
+    let content = prompt_line(
         Span::styled("New directory name: ", app.theme.help_text_style),
-        Span::styled(&app.directory_input, app.theme.highlight_style),
-    ]);
+        &app.directory_input,
+        app.theme.highlight_style,
+    );
     frame.render_widget(Paragraph::new(content), inner);
 }
 
@@ -572,7 +1724,44 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
     } else {
         "⚡ Oxid - TUI Note Editor"
     };
-    let header = Paragraph::new(title).style(app.theme.header_style).block(
+    let mut line = Line::from(title);
+    if !app.zen_mode {
+        let budget = (area.width as usize).saturating_sub(line.width() + 2);
+        line.push_span(Span::raw("  "));
+        for (i, segment) in fit_breadcrumb(&app.breadcrumb(), budget)
+            .into_iter()
+            .enumerate()
+        {
+            if i > 0 {
+                line.push_span(Span::styled(
+                    BREADCRUMB_SEPARATOR,
+                    app.theme.help_text_style,
+                ));
+            }
+            match segment {
+                Crumb::Folder {
+                    level,
+                    name,
+                    current,
+                } => {
+                    if level <= 9 {
+                        line.push_span(Span::styled(
+                            format!("{level} "),
+                            app.theme.help_text_style,
+                        ));
+                    }
+                    let style = if current {
+                        app.theme.highlight_style
+                    } else {
+                        app.theme.header_style
+                    };
+                    line.push_span(Span::styled(name, style));
+                }
+                Crumb::Elided => line.push_span(Span::styled("…", app.theme.help_text_style)),
+            }
+        }
+    }
+    let header = Paragraph::new(line).style(app.theme.header_style).block(
         Block::default()
             .borders(Borders::BOTTOM)
             .border_style(app.theme.border_style),
@@ -580,6 +1769,84 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(header, area);
 }
 
+const BREADCRUMB_SEPARATOR: &str = " › ";
+
+/// Shortest a folder name is cut down to (including the ellipsis) before folders are left out.
+const MIN_CRUMB_CHARS: usize = 5;
+
+/// One piece of the breadcrumb path.
+#[derive(Debug, PartialEq, Eq)]
+enum Crumb {
+    Folder {
+        level: usize,
+        name: String,
+        current: bool,
+    },
+    /// Folders left out after the root.
+    Elided,
+}
+
+/// The breadcrumb for the folder path `names` (vault root first) in `max_width` columns.
+/// Each folder is prefixed with its level digit (0-9), the key that jumps to it. The longest
+/// names are shortened in the middle first; if that is not enough, folders after the root
+/// are left out, always keeping the current one.
+fn fit_breadcrumb(names: &[String], max_width: usize) -> Vec<Crumb> {
+    let mut lens: Vec<usize> = names.iter().map(|n| n.chars().count()).collect();
+    // Folders 1..=hidden are shown as one "…".
+    let mut hidden = 0;
+    let width = |lens: &[usize], hidden: usize| {
+        let mut pieces = usize::from(hidden > 0);
+        let mut width = pieces;
+        for (level, len) in lens.iter().enumerate() {
+            if level == 0 || level > hidden {
+                pieces += 1;
+                width += len + if level <= 9 { 2 } else { 0 };
+            }
+        }
+        width + BREADCRUMB_SEPARATOR.chars().count() * pieces.saturating_sub(1)
+    };
+    while width(&lens, hidden) > max_width {
+        let longest = (0..lens.len())
+            .filter(|&level| level == 0 || level > hidden)
+            .max_by_key(|&level| lens[level])
+            .filter(|&level| lens[level] > MIN_CRUMB_CHARS);
+        if let Some(level) = longest {
+            lens[level] -= 1;
+        } else if hidden + 2 < lens.len() {
+            hidden += 1;
+        } else {
+            break;
+        }
+    }
+    let mut crumbs = Vec::new();
+    for (level, (name, len)) in names.iter().zip(lens).enumerate() {
+        if level > 0 && level <= hidden {
+            if level == 1 {
+                crumbs.push(Crumb::Elided);
+            }
+            continue;
+        }
+        crumbs.push(Crumb::Folder {
+            level,
+            name: truncate_middle(name, len),
+            current: level + 1 == names.len(),
+        });
+    }
+    crumbs
+}
+
+/// `text` cut to `max` characters by replacing its middle with an ellipsis.
+fn truncate_middle(text: &str, max: usize) -> String {
+    let len = text.chars().count();
+    if len <= max {
+        return text.to_string();
+    }
+    let keep = max.saturating_sub(1);
+    let head: String = text.chars().take(keep - keep / 2).collect();
+    let tail: String = text.chars().skip(len - keep / 2).collect();
+    format!("{head}…{tail}")
+}
+
 fn draw_tab_bar(frame: &mut Frame, app: &App, area: Rect) {
     let tab_spans: Vec<Span> = app
         .buffers
@@ -587,8 +1854,7 @@ fn draw_tab_bar(frame: &mut Frame, app: &App, area: Rect) {
         .enumerate()
         .flat_map(|(i, buf)| {
             let is_active = i == app.active_tab
-                || (app.editor_layout == EditorLayout::SplitVertical
-                    && app.split_right_tab == Some(i));
+                || (app.editor_layout.is_split() && app.split_right_tab() == Some(i));
             let is_focused = i == app.focused_buffer_index();
             let style = if is_focused {
                 app.theme.list_text_selected_style
@@ -631,6 +1897,8 @@ fn draw_notes_list(frame: &mut Frame, app: &App, area: Rect) {
         _ => list_border_style,
     };
 
+    let searching = app.mode == Mode::Search && !app.search_query.is_empty();
+    let today = tasks::today();
     let items: Vec<ListItem> = app
         .filtered_notes
         .iter()
@@ -649,42 +1917,87 @@ fn draw_notes_list(frame: &mut Frame, app: &App, area: Rect) {
             } else {
                 app.theme.list_text_normal_style
             };
+            let is_marked = app.marked.contains(&note.path);
+            let base_style = if is_marked {
+                base_style.patch(app.theme.highlight_style)
+            } else {
+                base_style
+            };
+            let mark = if is_marked { "* " } else { "" };
             let icon = app.file_icon(&note.path);
-            let display_text = format!("{}{}", icon, note.display);
-            let line = if app.mode == Mode::Search && !app.search_query.is_empty() {
-                #[allow(clippy::cast_possible_truncation)]
-            let offset = icon.chars().count() as u32;
-                let shifted: Vec<u32> = app
-                    .match_indices
-                    .get(i)
-                    .cloned()
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|idx| idx + offset)
-                    .collect();
-                build_highlighted_line(
-                    &display_text,
-                    shifted,
+            // The tree view indents entries under their folder and marks folders open or
+            // closed.
+            let mark = if app.tree_view && !searching {
+                let arrow = match (note.is_directory, app.expanded.contains(&note.path)) {
+                    (true, true) => "▾ ",
+                    (true, false) => "▸ ",
+                    (false, _) => "  ",
+                };
+                format!("{}{arrow}{mark}", "  ".repeat(note.depth))
+            } else {
+                mark.to_string()
+            };
+            if searching {
+                // Match indices are into the name, so the mark and icon get their own span
+                // whatever their width.
+                let indices = app.list_highlights.indices(i);
+                let mut spans = vec![Span::styled(format!("{mark}{icon}"), base_style)];
+                spans.extend(highlighted_spans(
+                    &note.search_label(),
+                    indices,
                     base_style,
                     app.theme.search_match_style,
-                )
+                ));
+                if !indices.is_empty() {
+                    return ListItem::new(Line::from(spans));
+                }
+                // Matched in the text only: say so, and show the line that matched.
+                spans.push(Span::styled(" \u{b6}", app.theme.help_text_style));
+                let mut lines = vec![Line::from(spans)];
+                if let Some((_, text)) = app.list_highlights.content(i) {
+                    lines.push(Line::from(Span::styled(
+                        format!("    {text}"),
+                        app.theme.help_text_style,
+                    )));
+                }
+                ListItem::new(lines)
             } else {
-                Line::from(Span::styled(display_text, base_style))
-            };
-            ListItem::new(line)
+                let mut spans = vec![Span::styled(
+                    format!("{}{}{}", mark, icon, note.label()),
+                    base_style,
+                )];
+                spans.extend(task_badge(app, &note.path, today));
+                if let Some(status) = app.git_file_status(&note.path) {
+                    spans.push(Span::styled(
+                        format!(" {}", status.marker()),
+                        app.theme.help_text_style,
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            }
         })
         .collect();
 
-    let list_title = if app.current_dir == app.notes_dir {
-        " Notes ".to_string()
+    let mut list_title = if !app.marked.is_empty() {
+        format!(" Notes ({} marked)", app.marked.len())
+    } else if app.current_dir == app.notes_dir {
+        " Notes".to_string()
     } else {
         format!(
-            " Notes ({}) ",
+            " Notes ({})",
             app.current_dir
                 .strip_prefix(&app.notes_dir)
                 .map_or_else(|_| app.current_dir.display().to_string(), |p| format!(".../{}", p.display()))
         )
     };
+    if searching {
+        list_title.push_str(&format!(
+            " {}/{}",
+            app.filtered_notes.len(),
+            app.all_notes.len()
+        ));
+    }
+    list_title.push(' ');
     let border_type = border_type_from_config(&app.config.ui.border_style);
     let list = List::new(items).block(
         Block::default()
@@ -693,7 +2006,53 @@ fn draw_notes_list(frame: &mut Frame, app: &App, area: Rect) {
             .border_type(border_type)
             .border_style(mode),
     );
-    frame.render_widget(list, area);
+    // The list scrolls from where it was only as far as needed to show the selection.
+    let mut state = ListState::default()
+        .with_offset(app.notes_scroll.get())
+        .with_selected(Some(app.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+    dim_unless_focused(frame, app, area, app.focus == Focus::List);
+    app.notes_scroll.set(state.offset());
+    app.notes_rows.set(area.height.saturating_sub(2));
+}
+
+/// ` ☑ 3/7` badge of the note at `path`, in the overdue style when a task of it was due
+/// before `today`; `None` for notes without tasks.
+fn task_badge(app: &App, path: &Path, today: NaiveDate) -> Option<Span<'static>> {
+    let counts = app.task_counts(path);
+    let style = if counts.overdue(today) {
+        app.theme.task_overdue_style
+    } else {
+        app.theme.task_badge_style
+    };
+    Some(Span::styled(format!(" {}", counts.badge()?), style))
+}
+
+/// Draw the editor column: a single pane, or two panes side by side / stacked when split.
+fn draw_editor_area(frame: &mut Frame, app: &App, area: Rect) {
+    let Some([first, second]) = split_pane_areas(app.editor_layout, area) else {
+        draw_editor_pane(frame, app, area);
+        return;
+    };
+    draw_editor_pane_at(frame, app, first, app.active_tab);
+    if let Some(right_idx) = app.split_right_tab() {
+        draw_editor_pane_at(frame, app, second, right_idx);
+    }
+}
+
+/// The left (or top) and right (or bottom) pane of a split editor column; `None` when the
+/// layout is not split.
+fn split_pane_areas(layout: EditorLayout, area: Rect) -> Option<[Rect; 2]> {
+    let direction = match layout {
+        EditorLayout::Single => return None,
+        EditorLayout::SplitVertical => Direction::Horizontal,
+        EditorLayout::SplitHorizontal => Direction::Vertical,
+    };
+    let chunks = Layout::default()
+        .direction(direction)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    Some([chunks[0], chunks[1]])
 }
 
 fn draw_editor_pane(frame: &mut Frame, app: &App, area: Rect) {
@@ -725,6 +2084,7 @@ fn draw_editor_pane_at(frame: &mut Frame, app: &App, area: Rect, buf_idx: usize)
                 )
                 .block(block);
             frame.render_widget(placeholder, area);
+            dim_unless_focused(frame, app, area, is_focused);
             return;
         }
     };
@@ -738,7 +2098,148 @@ fn draw_editor_pane_at(frame: &mut Frame, app: &App, area: Rect, buf_idx: usize)
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
-    frame.render_widget(&buf.textarea, inner);
+    // Line numbers take their digits plus two columns of margin.
+    let gutter = if app.config.editor.line_numbers {
+        buf.textarea.lines().len().to_string().len() as u16 + 2
+    } else {
+        0
+    };
+    buf.text_width.set(inner.width.saturating_sub(gutter));
+    buf.text_height.set(inner.height);
+    if app.config.editor.soft_wrap || app.focus_mode {
+        // Focus mode needs to know which screen row shows which line, so it always draws
+        // the text itself, wrapped.
+        let focused = app
+            .focus_mode
+            .then(|| buf.paragraph_around(buf.textarea.cursor().0));
+        draw_wrapped_text(frame, buf, inner, focused, app.theme.inactive_style);
+    } else {
+        frame.render_widget(&buf.textarea, inner);
+    }
+    // In a split, the pane with the cursor stays at full intensity while the other dims.
+    dim_unless_focused(frame, app, area, is_focused);
+}
+
+/// The editor text with lines wider than the pane wrapped into several rows (`soft_wrap`).
+/// The textarea still holds the text and cursor; this draws them the way its own widget
+/// does, with the same line number, cursor line, markdown pattern and cursor styles. Lines
+/// outside `focused`, when given, are patched with `dim`.
+fn draw_wrapped_text(
+    frame: &mut Frame,
+    buf: &EditorBuffer,
+    area: Rect,
+    focused: Option<Range<usize>>,
+    dim: Style,
+) {
+    let textarea = &buf.textarea;
+    let lines = textarea.lines();
+    let height = usize::from(area.height);
+    let (cursor_row, cursor_col) = textarea.cursor();
+    let cursor_piece = wrap::row_of(&buf.row_starts(cursor_row), cursor_col);
+    let top = wrapped_top(buf, (cursor_row, cursor_piece), height);
+    buf.wrap_top.set(top);
+
+    let tab_len = usize::from(textarea.tab_length());
+    let digits = lines.len().to_string().len();
+    let mut rows: Vec<Line> = Vec::with_capacity(height);
+    let (mut row, mut first_piece) = top;
+    while rows.len() < height && row < lines.len() {
+        let line = &lines[row];
+        let chars: Vec<char> = line.chars().collect();
+        let widths = wrap::char_widths(line, tab_len);
+        let styles = wrapped_line_styles(textarea, row, line);
+        let starts = buf.row_starts(row);
+        for (piece, &start) in starts.iter().enumerate().skip(first_piece) {
+            if rows.len() == height {
+                break;
+            }
+            let end = starts.get(piece + 1).copied().unwrap_or(chars.len());
+            let mut spans = Vec::new();
+            if let Some(style) = textarea.line_number_style() {
+                let number = if piece == 0 {
+                    format!(" {:>digits$} ", row + 1)
+                } else {
+                    " ".repeat(digits + 2)
+                };
+                spans.push(Span::styled(number, style));
+            }
+            let mut run = String::new();
+            for i in start..end {
+                if i > start && styles[i] != styles[i - 1] {
+                    spans.push(Span::styled(std::mem::take(&mut run), styles[i - 1]));
+                }
+                if chars[i] == '\t' {
+                    run.push_str(&" ".repeat(widths[i]));
+                } else {
+                    run.push(chars[i]);
+                }
+            }
+            if end > start {
+                spans.push(Span::styled(run, styles[end - 1]));
+            }
+            if row == cursor_row && piece == cursor_piece && cursor_col >= chars.len() {
+                spans.push(Span::styled(" ", textarea.cursor_style()));
+            }
+            if focused.as_ref().is_some_and(|lines| !lines.contains(&row)) {
+                for span in &mut spans {
+                    span.style = span.style.patch(dim);
+                }
+            }
+            rows.push(Line::from(spans));
+        }
+        row += 1;
+        first_piece = 0;
+    }
+    frame.render_widget(Paragraph::new(rows).style(textarea.style()), area);
+}
+
+/// The first display row that keeps `cursor` (line, row within it) on screen: the previous
+/// one while the cursor is still in view, else just enough scrolling to bring it back.
+fn wrapped_top(buf: &EditorBuffer, cursor: (usize, usize), height: usize) -> (usize, usize) {
+    let top = buf.wrap_top.get();
+    if top > cursor {
+        return cursor;
+    }
+    // The lowest top that still shows the cursor is `height - 1` rows above it.
+    let (mut row, mut piece) = cursor;
+    for _ in 1..height {
+        if piece > 0 {
+            piece -= 1;
+        } else if row > 0 {
+            row -= 1;
+            piece = buf.row_starts(row).len() - 1;
+        } else {
+            break;
+        }
+    }
+    top.max((row, piece))
+}
+
+/// Style of each character of line `row`, as the textarea widget would draw it.
+fn wrapped_line_styles(textarea: &tui_textarea::TextArea, row: usize, line: &str) -> Vec<Style> {
+    let (cursor_row, cursor_col) = textarea.cursor();
+    let base = if row == cursor_row {
+        textarea.cursor_line_style()
+    } else {
+        Style::default()
+    };
+    let mut styles = vec![base; line.chars().count()];
+    if let Some(pattern) = textarea.search_pattern() {
+        let offsets: Vec<usize> = line.char_indices().map(|(i, _)| i).collect();
+        for found in pattern.find_iter(line) {
+            let start = offsets.partition_point(|&i| i < found.start());
+            let end = offsets.partition_point(|&i| i < found.end());
+            for style in &mut styles[start..end] {
+                *style = style.patch(textarea.search_style());
+            }
+        }
+    }
+    if row == cursor_row {
+        if let Some(style) = styles.get_mut(cursor_col) {
+            *style = textarea.cursor_style();
+        }
+    }
+    styles
 }
 
 fn draw_preview_pane(frame: &mut Frame, app: &App, area: Rect) {
@@ -752,6 +2253,8 @@ fn draw_preview_pane(frame: &mut Frame, app: &App, area: Rect) {
         _ => preview_border_style,
     };
 
+    let mut scroll = 0;
+    let mut cache = app.preview_cache.borrow_mut();
     let content = if let Some(placeholder) = app.get_preview_placeholder() {
         vec![Line::from(Span::styled(
             placeholder,
@@ -770,29 +2273,104 @@ fn draw_preview_pane(frame: &mut Frame, app: &App, area: Rect) {
                 app.theme.preview_text_style.add_modifier(Modifier::ITALIC),
             ))]
         } else if !app.search_query.is_empty() {
-            preview_text
+            let (base, matched) = (app.theme.preview_text_style, app.theme.search_match_style);
+            let lines: Vec<Line> = preview_text
                 .lines()
-                .map(|l| {
-                    build_preview_line_with_highlight(
+                .map(|l| match &app.search_regex {
+                    Some(re) => build_preview_line_with_regex(l, re, base, matched),
+                    None => build_preview_line_with_highlight(
                         l,
-                        &app.search_query,
-                        app.theme.preview_text_style,
-                        app.theme.search_match_style,
-                    )
+                        app.search_terms(),
+                        app.search_case,
+                        base,
+                        matched,
+                    ),
                 })
-                .collect()
+                .collect();
+            // Bring the line that made the note match into view.
+            if app.focus != Focus::Editor {
+                if let Some((row, _)) = app.list_highlights.content(app.selected) {
+                    scroll = preview_scroll_to_line(&lines, *row, area);
+                }
+            }
+            lines
         } else {
-            render_markdown(&preview_text, &app.theme)
+            let (lines, source_map) = cache.render(
+                &preview_text,
+                &app.theme,
+                app.config.ui.preview_show_link_urls,
+                &|name| app.embedded_note(name),
+            );
+            if app.focus == Focus::Editor && app.config.ui.preview_follow_cursor {
+                let cursor_row = app.focused_buffer().map_or(0, |b| b.textarea.cursor().0);
+                scroll = preview_scroll_for_cursor(lines, source_map, cursor_row, area);
+            }
+            borrowed_lines(lines)
         }
     };
 
-    let paragraph = Paragraph::new(content).wrap(Wrap { trim: true }).block(
-        Block::default()
-            .title(" Preview ")
-            .borders(Borders::ALL)
-            .border_style(mode),
-    );
+    // The badge is for the note being previewed: the focused buffer's, else the selected one.
+    let previewed = match app.editing_path() {
+        Some(path) if app.focus == Focus::Editor => Some(path),
+        _ => app.filtered_notes.get(app.selected).map(|n| n.path.clone()),
+    };
+    let mut title = vec![Span::raw(" Preview")];
+    title.extend(previewed.and_then(|path| task_badge(app, &path, tasks::today())));
+    title.push(Span::raw(" "));
+    let paragraph = Paragraph::new(content)
+        .wrap(Wrap { trim: true })
+        .scroll((scroll, 0))
+        .block(
+            Block::default()
+                .title(Line::from(title))
+                .borders(Borders::ALL)
+                .border_style(mode),
+        );
     frame.render_widget(paragraph, area);
+    dim_unless_focused(frame, app, area, app.focus == Focus::Editor);
+}
+
+/// `lines` with their text borrowed, so the cached preview is drawn without copying it.
+fn borrowed_lines<'a>(lines: &'a [Line<'static>]) -> Vec<Line<'a>> {
+    lines
+        .iter()
+        .map(|line| Line {
+            spans: line
+                .spans
+                .iter()
+                .map(|span| Span::styled(span.content.as_ref(), span.style))
+                .collect(),
+            ..*line
+        })
+        .collect()
+}
+
+/// Scroll offset that centers the rendered line for `cursor_row` in the preview. Wrapped
+/// heights are estimated from line widths, so the result is approximate.
+fn preview_scroll_for_cursor(
+    lines: &[Line],
+    source_map: &[usize],
+    cursor_row: usize,
+    area: Rect,
+) -> u16 {
+    let target = source_map
+        .partition_point(|&src| src <= cursor_row)
+        .saturating_sub(1);
+    preview_scroll_to_line(lines, target, area)
+}
+
+/// Scroll offset that centers `lines[target]` in the preview, estimating wrapped heights
+/// from line widths.
+fn preview_scroll_to_line(lines: &[Line], target: usize, area: Rect) -> u16 {
+    let inner_width = area.width.saturating_sub(2).max(1) as usize;
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let rows_before: usize = lines[..target.min(lines.len())]
+        .iter()
+        .map(|l| l.width().div_ceil(inner_width).max(1))
+        .sum();
+    rows_before
+        .saturating_sub(inner_height / 2)
+        .min(u16::MAX as usize) as u16
 }
 
 fn draw_backlinks_pane(frame: &mut Frame, app: &App, area: Rect) {
@@ -807,9 +2385,9 @@ fn draw_backlinks_pane(frame: &mut Frame, app: &App, area: Rect) {
         .iter()
         .enumerate()
         .map(|(i, path)| {
-                let display = path
-                    .strip_prefix(&app.notes_dir)
-                    .map_or_else(
+            let display = app.note_title(path).map_or_else(
+                || {
+                    path.strip_prefix(&app.notes_dir).map_or_else(
                         |_| {
                             path.file_name()
                                 .and_then(|n| n.to_str())
@@ -817,7 +2395,10 @@ fn draw_backlinks_pane(frame: &mut Frame, app: &App, area: Rect) {
                                 .to_string()
                         },
                         |p| p.display().to_string(),
-                    );
+                    )
+                },
+                str::to_string,
+            );
             let style = if i == app.backlinks_selected {
                 app.theme.list_text_selected_style
             } else {
@@ -835,10 +2416,136 @@ fn draw_backlinks_pane(frame: &mut Frame, app: &App, area: Rect) {
             .border_style(border_style),
     );
     frame.render_widget(list, area);
+    dim_unless_focused(frame, app, area, app.focus == Focus::Backlinks);
+}
+
+/// Footer segment for the vault's git status: clean or dirty, and `↑2 ↓1` for commits
+/// ahead of and behind the upstream branch.
+fn git_status_span(git: &GitStatus, app: &App) -> Span<'static> {
+    let (state, style) = if git.is_dirty() {
+        ("Dirty", app.theme.highlight_style)
+    } else {
+        ("Clean", app.theme.statusbar_fg_style)
+    };
+    let mut text = format!("Git: {state}");
+    if git.ahead > 0 {
+        text.push_str(&format!(" \u{2191}{}", git.ahead));
+    }
+    if git.behind > 0 {
+        text.push_str(&format!(" \u{2193}{}", git.behind));
+    }
+    Span::styled(text, style)
 }
 
 fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
-    let (title, content) = if app.focus == Focus::Backlinks {
+    let (title, hints) = footer_hints(app);
+    let border_type = border_type_from_config(&app.config.ui.border_style);
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_type(border_type)
+        .border_style(app.theme.border_style)
+        .style(app.theme.statusbar_bg_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Each spacer starts a new group. The groups after the first keep their width and the
+    // first gets what they leave, so the hints are cut off before the cursor position.
+    let mut groups = vec![Line::default()];
+    let mut previous = None;
+    for segment in app.config.statusline.resolved() {
+        if segment == StatusSegment::Spacer {
+            groups.push(Line::default());
+            previous = None;
+            continue;
+        }
+        let spans = match segment {
+            StatusSegment::Hints => hints.clone(),
+            _ => segment_spans(app, segment, inner.width),
+        };
+        if spans.is_empty() {
+            continue;
+        }
+        let Some(line) = groups.last_mut() else {
+            continue;
+        };
+        if let Some(previous) = previous {
+            // The mode badge has its own padding.
+            let badge = previous == StatusSegment::Mode || segment == StatusSegment::Mode;
+            let separator = if badge { " " } else { " | " };
+            line.spans
+                .push(Span::styled(separator, app.theme.statusbar_fg_style));
+        }
+        line.spans.extend(spans);
+        previous = Some(segment);
+    }
+    let spacers = groups.len() as u16 - 1;
+    if spacers > 0 {
+        if let Some(last) = groups.last_mut().filter(|g| g.width() > 0) {
+            last.spans.push(Span::raw(" "));
+        }
+    }
+
+    let rest: u16 = groups[1..].iter().map(|g| g.width() as u16).sum();
+    let first = (groups[0].width() as u16).min(inner.width.saturating_sub(rest));
+    let free = inner.width.saturating_sub(first + rest);
+    let mut x = inner.x;
+    for (i, group) in groups.into_iter().enumerate() {
+        let width = if i == 0 {
+            first
+        } else {
+            // The spacers share the free width, the first ones taking the remainder.
+            let n = i as u16 - 1;
+            x += free / spacers + u16::from(n < free % spacers);
+            group.width() as u16
+        };
+        let width = width.min(inner.right().saturating_sub(x));
+        let area = Rect::new(x, inner.y, width, inner.height.min(1));
+        frame.render_widget(Paragraph::new(group), area);
+        x += width;
+    }
+}
+
+/// Spans of a footer segment other than the hints and spacers; empty when it has nothing to
+/// show, such as the cursor position outside the editor.
+fn segment_spans(app: &App, segment: StatusSegment, width: u16) -> Vec<Span<'_>> {
+    let in_editor = app.focus == Focus::Editor;
+    let span = match segment {
+        StatusSegment::Mode if in_editor => Some(mode_badge(app)),
+        StatusSegment::Position if in_editor => position_span(app, width / 2),
+        StatusSegment::File => app.focused_buffer().map(|buf| file_span(app, buf)),
+        StatusSegment::WordCount => app.focused_buffer().map(|buf| {
+            let words: usize = buf
+                .textarea
+                .lines()
+                .iter()
+                .map(|l| l.split_whitespace().count())
+                .sum();
+            let unit = if words == 1 { "word" } else { "words" };
+            Span::styled(format!("{words} {unit}"), app.theme.statusbar_fg_style)
+        }),
+        StatusSegment::Git => app.git.as_ref().map(|git| git_status_span(git, app)),
+        StatusSegment::Saved if app.save_indicator_until.is_some() => Some(Span::styled(
+            "Saved...",
+            app.theme.highlight_style.add_modifier(Modifier::ITALIC),
+        )),
+        // A fresh message expires after a few seconds (the full history is in Show
+        // Messages).
+        StatusSegment::Message => app.footer_message().map(|msg| {
+            Span::styled(
+                msg,
+                app.theme.statusbar_fg_style.add_modifier(Modifier::ITALIC),
+            )
+        }),
+        _ => None,
+    };
+    span.into_iter().collect()
+}
+
+/// Footer title and key hints for the focused pane, with the search or file name prompt
+/// while one is open.
+fn footer_hints(app: &App) -> (&'static str, Vec<Span<'_>>) {
+    if app.focus == Focus::Backlinks {
         (
             " Backlinks ",
             vec![
@@ -848,17 +2555,17 @@ fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
                         app.get_key_display_string("move_down"),
                         app.get_key_display_string("move_up")
                     ),
-                    app.theme.help_text_style,
+                    app.theme.statusbar_fg_style,
                 ),
                 Span::styled("navigate", app.theme.highlight_style),
                 Span::styled(
                     format!(" | {} ", app.get_key_display_string("enter")),
-                    app.theme.help_text_style,
+                    app.theme.statusbar_fg_style,
                 ),
                 Span::styled("open", app.theme.highlight_style),
                 Span::styled(
                     format!(" | {} ", app.get_key_display_string("escape")),
-                    app.theme.help_text_style,
+                    app.theme.statusbar_fg_style,
                 ),
                 Span::styled("back", app.theme.highlight_style),
             ],
@@ -873,17 +2580,17 @@ fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
                         app.get_key_display_string("editor_insert"),
                         app.get_key_display_string("editor_append")
                     ),
-                    app.theme.help_text_style,
+                    app.theme.statusbar_fg_style,
                 ),
                 Span::styled("insert", app.theme.highlight_style),
                 Span::styled(
                     format!(" | {} ", app.get_key_display_string("escape")),
-                    app.theme.help_text_style,
+                    app.theme.statusbar_fg_style,
                 ),
                 Span::styled("normal", app.theme.highlight_style),
                 Span::styled(
                     format!(" | {} ", app.get_key_display_string("editor_back")),
-                    app.theme.help_text_style,
+                    app.theme.statusbar_fg_style,
                 ),
                 Span::styled("back", app.theme.highlight_style),
             ],
@@ -895,22 +2602,22 @@ fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
                 vec![
                     Span::styled(
                         format!("{} ", app.get_key_display_string("search")),
-                        app.theme.help_text_style,
+                        app.theme.statusbar_fg_style,
                     ),
                     Span::styled("search", app.theme.highlight_style),
                     Span::styled(
                         format!(" | {} ", app.get_key_display_string("command_palette")),
-                        app.theme.help_text_style,
+                        app.theme.statusbar_fg_style,
                     ),
                     Span::styled("commands", app.theme.highlight_style),
                     Span::styled(
                         format!(" | {} ", app.get_key_display_string("list_rename")),
-                        app.theme.help_text_style,
+                        app.theme.statusbar_fg_style,
                     ),
                     Span::styled("rename", app.theme.highlight_style),
                     Span::styled(
                         format!(" | {} ", app.get_key_display_string("list_create_dir")),
-                        app.theme.help_text_style,
+                        app.theme.statusbar_fg_style,
                     ),
                     Span::styled("mkdir", app.theme.highlight_style),
                     Span::styled(
@@ -919,92 +2626,267 @@ fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
                             app.get_key_display_string("move_left_alt"),
                             app.get_key_display_string("move_left")
                         ),
-                        app.theme.help_text_style,
+                        app.theme.statusbar_fg_style,
                     ),
                     Span::styled("up", app.theme.highlight_style),
                     Span::styled(
                         format!(" | {} ", app.get_key_display_string("zen_mode")),
-                        app.theme.help_text_style,
+                        app.theme.statusbar_fg_style,
                     ),
                     Span::styled("zen", app.theme.highlight_style),
                     Span::styled(
                         format!(" | {} ", app.get_key_display_string("quit")),
-                        app.theme.help_text_style,
+                        app.theme.statusbar_fg_style,
                     ),
                     Span::styled("quit", app.theme.highlight_style),
                 ],
             ),
             Mode::Search => (
-                " Search ",
-                vec![
-                    Span::styled(&app.search_query, app.theme.highlight_style),
-                    Span::styled(
-                        format!(" | {} ", app.get_key_display_string("escape")),
-                        app.theme.help_text_style,
-                    ),
-                    Span::styled("back", app.theme.highlight_style),
-                    Span::styled(
-                        format!(" | {} ", app.get_key_display_string("enter")),
-                        app.theme.help_text_style,
-                    ),
-                    Span::styled("edit", app.theme.highlight_style),
-                ],
+                SearchMode::parse(&app.search_query).0.title(),
+                [
+                    input_spans(&app.search_query, app.theme.highlight_style),
+                    vec![
+                        Span::styled(
+                            app.search_error
+                                .as_ref()
+                                .map_or_else(String::new, |e| format!("  {e}")),
+                            app.theme.diff_remove_style,
+                        ),
+                        Span::styled(
+                            format!(" | {} ", app.get_key_display_string("search_case")),
+                            app.theme.statusbar_fg_style,
+                        ),
+                        Span::styled(app.search_case.label(), app.theme.highlight_style),
+                        Span::styled(
+                            format!(" | {} ", app.get_key_display_string("escape")),
+                            app.theme.statusbar_fg_style,
+                        ),
+                        Span::styled("back", app.theme.highlight_style),
+                        Span::styled(
+                            format!(" | {} ", app.get_key_display_string("enter")),
+                            app.theme.statusbar_fg_style,
+                        ),
+                        Span::styled("edit", app.theme.highlight_style),
+                    ],
+                ]
+                .concat(),
             ),
             Mode::Create => (
                 " New Note ",
-                vec![
-                    Span::styled("Filename: ", app.theme.help_text_style),
-                    Span::styled(&app.create_filename, app.theme.highlight_style),
-                    Span::styled(
-                        format!(" | {} ", app.get_key_display_string("enter")),
-                        app.theme.help_text_style,
-                    ),
-                    Span::styled("template", app.theme.highlight_style),
-                    Span::styled(
-                        format!(" | {} ", app.get_key_display_string("escape")),
-                        app.theme.help_text_style,
-                    ),
-                    Span::styled("cancel", app.theme.highlight_style),
-                ],
+                [
+                    vec![Span::styled("Filename: ", app.theme.statusbar_fg_style)],
+                    input_spans(&app.create_filename, app.theme.highlight_style),
+                    vec![
+                        Span::styled(
+                            format!(" | {} ", app.get_key_display_string("enter")),
+                            app.theme.statusbar_fg_style,
+                        ),
+                        Span::styled("template", app.theme.highlight_style),
+                        Span::styled(
+                            format!(" | {} ", app.get_key_display_string("escape")),
+                            app.theme.statusbar_fg_style,
+                        ),
+                        Span::styled("cancel", app.theme.highlight_style),
+                    ],
+                ]
+                .concat(),
             ),
         }
-    };
+    }
+}
 
-    let mut spans = content;
+/// Path of the focused buffer's note in the vault, with `[+]` while it has unsaved edits.
+fn file_span<'a>(app: &App, buf: &EditorBuffer) -> Span<'a> {
+    let name = buf
+        .path
+        .as_ref()
+        .and_then(|p| p.strip_prefix(&app.notes_dir).ok())
+        .map_or_else(|| buf.display_name(), |p| p.display().to_string());
+    let modified = if buf.dirty { " [+]" } else { "" };
+    Span::styled(format!("{name}{modified}"), app.theme.statusbar_fg_style)
+}
 
-    // Git status indicator (uses theme statusbar styles)
-    match app.git_status() {
-        GitStatus::Clean => {
-            spans.push(Span::styled(" | Git: Clean ", app.theme.statusbar_fg_style))
+/// `NORMAL` or `INSERT`, with a pending count.
+fn mode_badge(app: &App) -> Span<'static> {
+    match (app.editor_mode, app.normal_count) {
+        (EditorMode::Normal, Some(count)) => {
+            Span::styled(format!(" NORMAL {count} "), app.theme.mode_normal_style)
         }
-        GitStatus::Dirty => spans.push(Span::styled(" | Git: Dirty ", app.theme.highlight_style)),
-        GitStatus::Unknown => {}
+        (EditorMode::Normal, None) => Span::styled(" NORMAL ", app.theme.mode_normal_style),
+        (EditorMode::Insert, _) => Span::styled(" INSERT ", app.theme.mode_insert_style),
     }
+}
 
-    if app.save_indicator_until.is_some() {
-        spans.push(Span::styled(
-            " | Saved... ",
-            app.theme.highlight_style.add_modifier(Modifier::ITALIC),
-        ));
+/// `12:5/340 wrap` (line:column, line count, soft wrap) for the focused buffer, dropping the
+/// line count, the wrap state and then the position when it does not fit in `max_width`.
+fn position_span(app: &App, max_width: u16) -> Option<Span<'static>> {
+    let buf = app.focused_buffer()?;
+    let (row, col) = buf.textarea.cursor();
+    let wrap = if app.config.editor.soft_wrap {
+        "wrap"
+    } else {
+        "nowrap"
+    };
+    let candidates = [
+        format!(
+            "{}:{}/{} {wrap}",
+            row + 1,
+            col + 1,
+            buf.textarea.lines().len()
+        ),
+        format!("{}:{} {wrap}", row + 1, col + 1),
+        format!("{}:{}", row + 1, col + 1),
+    ];
+    let max_width = usize::from(max_width);
+    candidates
+        .into_iter()
+        .find(|position| position.len() <= max_width)
+        .map(|position| Span::styled(position, app.theme.statusbar_fg_style))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    fn runs(text: &str, indices: &[u32]) -> Vec<(String, bool)> {
+        let matched = Style::default().fg(Color::Red);
+        highlighted_spans(text, indices, Style::default(), matched)
+            .into_iter()
+            .map(|s| (s.content.into_owned(), s.style == matched))
+            .collect()
+    }
+
+    #[test]
+    fn highlighted_spans_coalesce_runs() {
+        let expected = vec![
+            ("a".to_string(), false),
+            ("bc".to_string(), true),
+            ("d".to_string(), false),
+            ("e".to_string(), true),
+            ("f".to_string(), false),
+        ];
+        assert_eq!(runs("abcdef", &[1, 2, 4]), expected);
+        // Unsorted and repeated indices give the same runs.
+        assert_eq!(runs("abcdef", &[4, 2, 1, 2]), expected);
+        // Indices count characters, not bytes, and ones past the end are ignored.
+        assert_eq!(
+            runs("äöü", &[1, 2, 9]),
+            vec![("ä".to_string(), false), ("öü".to_string(), true)]
+        );
+        assert_eq!(runs("abc", &[]), vec![("abc".to_string(), false)]);
+        // A combining accent stays in the span of the letter it sits on.
+        assert_eq!(
+            runs("cafe\u{301}s", &[3]),
+            vec![
+                ("caf".to_string(), false),
+                ("e\u{301}".to_string(), true),
+                ("s".to_string(), false)
+            ]
+        );
+        assert_eq!(
+            runs("👩\u{200d}💻 a", &[0, 2]),
+            vec![
+                ("👩\u{200d}💻".to_string(), true),
+                (" a".to_string(), false)
+            ]
+        );
     }
 
-    let mut lines = vec![Line::from(spans)];
+    fn preview_runs(line: &str, query: &str, case: CaseMode) -> Vec<(String, bool)> {
+        let matched = Style::default().fg(Color::Red);
+        build_preview_line_with_highlight(line, query, case, Style::default(), matched)
+            .spans
+            .into_iter()
+            .map(|s| (s.content.into_owned(), s.style == matched))
+            .collect()
+    }
 
-    if let Some(msg) = &app.message {
-        lines.push(Line::from(Span::styled(
-            msg.as_str(),
-            app.theme.text_style.add_modifier(Modifier::ITALIC),
-        )));
+    #[test]
+    fn preview_highlights_multibyte_text_by_character() {
+        let run = |text: &str, matched| (text.to_string(), matched);
+        // `İ` lowercases to two chars (three bytes), which used to shift the match.
+        assert_eq!(
+            preview_runs("İstanbul Café", "café", CaseMode::Ignore),
+            [run("İstanbul ", false), run("Café", true)]
+        );
+        assert_eq!(
+            preview_runs("straße STRASSE", "ß", CaseMode::Ignore),
+            [run("stra", false), run("ß", true), run("e STRASSE", false)]
+        );
+        assert_eq!(
+            preview_runs("東京と京都", "京", CaseMode::Ignore),
+            [
+                run("東", false),
+                run("京", true),
+                run("と", false),
+                run("京", true),
+                run("都", false)
+            ]
+        );
+        assert_eq!(
+            preview_runs("🚀 launch 🚀🚀", "🚀", CaseMode::Ignore),
+            [run("🚀", true), run(" launch ", false), run("🚀🚀", true)]
+        );
+        assert_eq!(
+            preview_runs("ÉCOLE école", "É", CaseMode::Sensitive),
+            [run("É", true), run("COLE école", false)]
+        );
+        assert_eq!(
+            preview_runs("naïve", "", CaseMode::Ignore),
+            [run("naïve", false)]
+        );
+        assert_eq!(
+            preview_runs("naïve", "naïve!", CaseMode::Ignore),
+            [run("naïve", false)]
+        );
     }
 
-    let border_type = border_type_from_config(&app.config.ui.border_style);
-    let footer = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
-        Block::default()
-            .title(title)
-            .borders(Borders::ALL)
-            .border_type(border_type)
-            .border_style(app.theme.border_style)
-            .style(app.theme.statusbar_bg_style),
-    );
-    frame.render_widget(footer, area);
+    fn crumb_text(crumbs: &[Crumb]) -> String {
+        crumbs
+            .iter()
+            .map(|c| match c {
+                Crumb::Folder { level, name, .. } => format!("{level} {name}"),
+                Crumb::Elided => "…".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(BREADCRUMB_SEPARATOR)
+    }
+
+    #[test]
+    fn breadcrumb_shortens_names_then_leaves_out_folders() {
+        let names: Vec<String> = ["notes", "projects", "2024-quarterly-reviews", "q3"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let full = "0 notes › 1 projects › 2 2024-quarterly-reviews › 3 q3";
+        assert_eq!(crumb_text(&fit_breadcrumb(&names, 80)), full);
+        assert_eq!(
+            crumb_text(&fit_breadcrumb(&names, 40)),
+            "0 notes › 1 projects › 2 2024…ews › 3 q3"
+        );
+        assert_eq!(
+            crumb_text(&fit_breadcrumb(&names, 22)),
+            "0 notes › … › 3 q3"
+        );
+        let crumbs = fit_breadcrumb(&names, 22);
+        assert!(matches!(
+            crumbs.last(),
+            Some(Crumb::Folder { current: true, .. })
+        ));
+    }
+
+    #[test]
+    fn split_panes_sit_side_by_side_or_stacked() {
+        let area = Rect::new(10, 2, 80, 30);
+        assert_eq!(split_pane_areas(EditorLayout::Single, area), None);
+        assert_eq!(
+            split_pane_areas(EditorLayout::SplitVertical, area),
+            Some([Rect::new(10, 2, 40, 30), Rect::new(50, 2, 40, 30)])
+        );
+        assert_eq!(
+            split_pane_areas(EditorLayout::SplitHorizontal, area),
+            Some([Rect::new(10, 2, 80, 15), Rect::new(10, 17, 80, 15)])
+        );
+    }
 }
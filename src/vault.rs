@@ -0,0 +1,315 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Vault walks with ignore patterns and symlink loop protection
+
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::{DirEntry, WalkDir};
+
+/// Ignore file read from the vault root, one pattern per line.
+pub const IGNORE_FILE_NAME: &str = ".oxidignore";
+
+/// Folder at the vault root that archived notes are moved to.
+pub const ARCHIVE_FOLDER: &str = "archive";
+
+/// Deepest directory level a walk descends to; anything below is skipped with a warning.
+pub const MAX_WALK_DEPTH: usize = 32;
+
+/// Glob patterns for paths every vault walk skips. A pattern without `/` matches a file or
+/// folder name anywhere (`.git`, `*.tmp`); one with `/` matches the path from the vault root
+/// (`archive/2020`). `*` and `?` stay within one path component, `**` crosses them.
+#[derive(Debug, Clone, Default)]
+pub struct Ignore {
+    root: PathBuf,
+    names: Vec<Regex>,
+    paths: Vec<Regex>,
+}
+
+impl Ignore {
+    /// Patterns from `patterns` (the config's `ignore` list) plus `.oxidignore` in `root`.
+    pub fn new(root: &Path, patterns: &[String]) -> Self {
+        let file = fs::read_to_string(root.join(IGNORE_FILE_NAME)).unwrap_or_default();
+        let from_file = file
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        let mut ignore = Self {
+            root: root.to_path_buf(),
+            ..Self::default()
+        };
+        for pattern in patterns.iter().map(|p| p.trim()).chain(from_file) {
+            let pattern = pattern.trim_end_matches('/');
+            if pattern.is_empty() {
+                continue;
+            }
+            let Some(re) = glob_regex(pattern.trim_start_matches('/')) else {
+                continue;
+            };
+            if pattern.contains('/') {
+                ignore.paths.push(re);
+            } else {
+                ignore.names.push(re);
+            }
+        }
+        ignore
+    }
+
+    /// Whether `path` is ignored by its name or its path from the vault root.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let rel = path.strip_prefix(&self.root).unwrap_or(path);
+        let name = rel
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        if self.names.iter().any(|re| re.is_match(&name)) {
+            return true;
+        }
+        let path = rel.to_string_lossy().replace('\\', "/");
+        self.paths.iter().any(|re| re.is_match(&path))
+    }
+
+    /// Whether `path` or any folder above it (up to the vault root) is ignored, for paths
+    /// that did not come from a walk.
+    pub fn excludes(&self, path: &Path) -> bool {
+        path.ancestors()
+            .take_while(|a| *a != self.root && a.starts_with(&self.root))
+            .any(|a| self.is_ignored(a))
+    }
+}
+
+/// Files and folders found by `walk`, and problems that cut parts of it short.
+#[derive(Debug, Default)]
+pub struct Walk {
+    /// Everything under the root (not the root itself), with symlinks followed.
+    pub entries: Vec<DirEntry>,
+    pub warnings: Vec<String>,
+}
+
+impl Walk {
+    /// Regular files (after following symlinks).
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.entries
+            .iter()
+            .filter(|e| e.file_type().is_file())
+            .map(DirEntry::path)
+    }
+
+    /// Markdown files.
+    pub fn markdown_files(&self) -> impl Iterator<Item = &Path> {
+        self.files()
+            .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+    }
+}
+
+/// Walk `root` (the vault or a folder in it), skipping ignored paths. Symlinks are followed; a link back into one of its
+/// own ancestors is skipped with a warning instead of being walked forever, and so is
+/// anything deeper than `MAX_WALK_DEPTH`.
+pub fn walk(root: &Path, ignore: &Ignore) -> Walk {
+    let mut walk = Walk::default();
+    let entries = WalkDir::new(root)
+        .follow_links(true)
+        .min_depth(1)
+        .max_depth(MAX_WALK_DEPTH)
+        .into_iter()
+        .filter_entry(|e| !ignore.is_ignored(e.path()));
+    for entry in entries {
+        match entry {
+            Ok(entry) => {
+                if entry.depth() == MAX_WALK_DEPTH && entry.file_type().is_dir() {
+                    walk.warnings.push(format!(
+                        "{} is nested more than {MAX_WALK_DEPTH} folders deep; not scanned",
+                        entry.path().display()
+                    ));
+                }
+                walk.entries.push(entry);
+            }
+            Err(e) if e.loop_ancestor().is_some() => {
+                let path = e.path().unwrap_or(root);
+                walk.warnings
+                    .push(format!("Symlink loop at {}; skipped", path.display()));
+            }
+            // Unreadable entries are skipped, as before.
+            Err(_) => {}
+        }
+    }
+    walk
+}
+
+/// Resolve a user-typed relative path (may contain `/`) against `base`.
+/// Returns None for absolute paths or paths that would escape `notes_dir`.
+pub fn resolve_path(notes_dir: &Path, base: &Path, input: &str) -> Option<PathBuf> {
+    use std::path::Component;
+    let mut resolved = base.to_path_buf();
+    for component in Path::new(input).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    if resolved == notes_dir || !resolved.starts_with(notes_dir) {
+        return None;
+    }
+    Some(resolved)
+}
+
+/// Create `dir` and its parents after verifying that its deepest existing ancestor
+/// (symlinks resolved) is still inside `notes_dir`.
+pub fn create_dirs(notes_dir: &Path, dir: &Path) -> anyhow::Result<()> {
+    let root = fs::canonicalize(notes_dir)?;
+    if let Some(existing) = dir.ancestors().find(|a| a.exists()) {
+        if !fs::canonicalize(existing)?.starts_with(&root) {
+            anyhow::bail!("Path must stay inside the notes directory");
+        }
+    }
+    fs::create_dir_all(dir).map_err(|e| anyhow::anyhow!("Failed to create directory: {e}"))
+}
+
+/// Anchored regex for a glob pattern.
+fn glob_regex(pattern: &str) -> Option<Regex> {
+    let mut re = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                re.push_str(".*");
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    Regex::new(&re).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Empty directory under the system temp dir, removed first if a previous run left it.
+    fn fixture(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("oxid-vault-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn rel_files(root: &Path, walk: &Walk) -> Vec<String> {
+        let mut files: Vec<String> = walk
+            .markdown_files()
+            .map(|p| p.strip_prefix(root).unwrap().display().to_string())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn skips_ignored_folders_from_config_and_ignore_file() {
+        let root = fixture("ignore");
+        for dir in [
+            ".obsidian",
+            "archive/2020",
+            "archive/2024",
+            "templates",
+            "notes",
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "a.md",
+            ".obsidian/workspace.md",
+            "archive/2020/old.md",
+            "archive/2024/new.md",
+            "templates/daily.md",
+            "notes/b.md",
+            "notes/scratch.tmp.md",
+        ] {
+            fs::write(root.join(file), "x").unwrap();
+        }
+        fs::write(
+            root.join(IGNORE_FILE_NAME),
+            "# comments and blank lines are skipped\n\ntemplates/\narchive/2020\n*.tmp.md\n",
+        )
+        .unwrap();
+
+        let ignore = Ignore::new(&root, &[".obsidian".to_string()]);
+        let walk = walk(&root, &ignore);
+        assert_eq!(
+            rel_files(&root, &walk),
+            ["a.md", "archive/2024/new.md", "notes/b.md"]
+        );
+        assert!(walk.warnings.is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn self_referencing_symlink_is_a_warning() {
+        let root = fixture("loop");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub/note.md"), "x").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("sub/back")).unwrap();
+
+        let walk = walk(&root, &Ignore::new(&root, &[]));
+        assert_eq!(rel_files(&root, &walk), ["sub/note.md"]);
+        assert_eq!(walk.warnings.len(), 1);
+        assert!(walk.warnings[0].contains("Symlink loop"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn typed_paths_stay_inside_the_vault() {
+        let vault = Path::new("/vault");
+        let sub = Path::new("/vault/sub");
+        assert_eq!(
+            resolve_path(vault, sub, "projects/alpha/plan.md"),
+            Some(PathBuf::from("/vault/sub/projects/alpha/plan.md"))
+        );
+        assert_eq!(
+            resolve_path(vault, sub, "./../other.md"),
+            Some(PathBuf::from("/vault/other.md"))
+        );
+        assert_eq!(resolve_path(vault, sub, "../../etc/passwd"), None);
+        assert_eq!(resolve_path(vault, sub, "a/../../../x.md"), None);
+        assert_eq!(resolve_path(vault, sub, ".."), None);
+        assert_eq!(resolve_path(vault, sub, "/etc/passwd"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn folders_are_not_created_through_links_out_of_the_vault() {
+        let root = fixture("create-dirs");
+        let (vault, outside) = (root.join("vault"), root.join("outside"));
+        fs::create_dir_all(&vault).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, vault.join("escape")).unwrap();
+
+        create_dirs(&vault, &vault.join("a/b")).unwrap();
+        assert!(vault.join("a/b").is_dir());
+        let err = create_dirs(&vault, &vault.join("escape/x")).unwrap_err();
+        assert_eq!(err.to_string(), "Path must stay inside the notes directory");
+        assert!(!outside.join("x").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn glob_patterns() {
+        let ignore = Ignore::new(
+            Path::new("/vault"),
+            &["*.bak".into(), "docs/**/draft?.md".into(), "/build".into()],
+        );
+        assert!(ignore.is_ignored(Path::new("/vault/x/y.bak")));
+        assert!(ignore.is_ignored(Path::new("/vault/docs/a/b/draft1.md")));
+        assert!(!ignore.is_ignored(Path::new("/vault/docs/a/b/draft10.md")));
+        assert!(ignore.is_ignored(Path::new("/vault/build")));
+        assert!(!ignore.is_ignored(Path::new("/vault/src/build")));
+    }
+}
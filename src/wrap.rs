@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Soft wrap: splitting editor lines into display rows
+
+use unicode_width::UnicodeWidthChar;
+
+/// Display columns of each character of `line`: a tab runs to the next multiple of
+/// `tab_len`, wide characters take two columns.
+pub fn char_widths(line: &str, tab_len: usize) -> Vec<usize> {
+    let tab_len = tab_len.max(1);
+    let mut col = 0;
+    line.chars()
+        .map(|c| {
+            let width = if c == '\t' {
+                tab_len - col % tab_len
+            } else {
+                c.width().unwrap_or(0)
+            };
+            col += width;
+            width
+        })
+        .collect()
+}
+
+/// Character index where each display row of `line` starts, for rows of `width` columns.
+/// Rows break after the last space that fits, or mid-word when a word alone is wider than
+/// the row. A space may hang past the end of a row rather than start the next one.
+pub fn row_starts(line: &str, width: usize, tab_len: usize) -> Vec<usize> {
+    let width = width.max(1);
+    let widths = char_widths(line, tab_len);
+    let mut starts = vec![0];
+    let mut used = 0;
+    let mut after_space = None;
+    for (i, c) in line.chars().enumerate() {
+        let row_start = *starts.last().unwrap_or(&0);
+        if used + widths[i] > width && i > row_start && c != ' ' {
+            let start = after_space.filter(|&s| s > row_start).unwrap_or(i);
+            starts.push(start);
+            used = widths[start..i].iter().sum();
+            after_space = None;
+        }
+        used += widths[i];
+        if c == ' ' {
+            after_space = Some(i + 1);
+        }
+    }
+    starts
+}
+
+/// The display row holding character `col` (the end of the line belongs to the last row).
+pub fn row_of(starts: &[usize], col: usize) -> usize {
+    starts.iter().rposition(|&s| s <= col).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaks_after_spaces_and_inside_long_words() {
+        assert_eq!(row_starts("", 10, 4), [0]);
+        assert_eq!(row_starts("one two three", 8, 4), [0, 8]);
+        // The space after "two" hangs at the end of the row.
+        assert_eq!(row_starts("one two three", 7, 4), [0, 8]);
+        assert_eq!(row_starts(&"x".repeat(25), 10, 4), [0, 10, 20]);
+        assert_eq!(row_starts("a verylongword", 5, 4), [0, 2, 7, 12]);
+    }
+
+    #[test]
+    fn counts_tabs_and_wide_characters() {
+        assert_eq!(char_widths("a\tb", 4), [1, 3, 1]);
+        assert_eq!(char_widths("日本", 4), [2, 2]);
+        assert_eq!(row_starts("日本語", 5, 4), [0, 2]);
+        assert_eq!(row_of(&[0, 8], 7), 0);
+        assert_eq!(row_of(&[0, 8], 13), 1);
+    }
+}
@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// oxid - Zettelkasten note IDs: timestamps that links can use instead of note names
+
+use chrono::format::{parse_and_remainder, Parsed, StrftimeItems};
+use chrono::Local;
+
+/// A new ID: the current time in the chrono `format` (e.g. `%Y%m%d%H%M` -> `202403141530`).
+pub fn new_id(format: &str) -> String {
+    Local::now().format(format).to_string()
+}
+
+/// The ID a note name starts with, and the rest of the name: `202403141530-Idea` gives
+/// `("202403141530", "Idea")`. The ID must read as `format`, be as long as a new ID, and be
+/// followed by the end of the name or a `-`, `_` or space.
+pub fn split_id<'a>(stem: &'a str, format: &str) -> Option<(&'a str, &'a str)> {
+    let mut parsed = Parsed::new();
+    let rest = parse_and_remainder(&mut parsed, stem, StrftimeItems::new(format)).ok()?;
+    let id = &stem[..stem.len() - rest.len()];
+    if id.is_empty() || id.len() != new_id(format).len() {
+        return None;
+    }
+    let name = match rest.chars().next() {
+        None => "",
+        Some('-' | '_' | ' ') => &rest[1..],
+        Some(_) => return None,
+    };
+    Some((id, name))
+}
+
+/// `content` with `id: <id>` added to its frontmatter, or in a new frontmatter block.
+pub fn with_frontmatter_id(content: &str, id: &str) -> String {
+    match content.strip_prefix("---\n") {
+        Some(rest) => format!("---\nid: {id}\n{rest}"),
+        None => format!("---\nid: {id}\n---\n{content}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_ids_that_match_the_format() {
+        let format = "%Y%m%d%H%M";
+        assert_eq!(
+            split_id("202403141530-Idea", format),
+            Some(("202403141530", "Idea"))
+        );
+        assert_eq!(split_id("202403141530", format), Some(("202403141530", "")));
+        assert_eq!(split_id("2024-05-01", format), None);
+        assert_eq!(split_id("20240314153", format), None);
+        assert_eq!(split_id("202403141530x", format), None);
+        assert_eq!(split_id("project", format), None);
+        assert_eq!(
+            split_id("2024-03-14 Idea", "%Y-%m-%d"),
+            Some(("2024-03-14", "Idea"))
+        );
+    }
+
+    #[test]
+    fn adds_ids_to_frontmatter() {
+        assert_eq!(
+            with_frontmatter_id("# Idea\n", "1"),
+            "---\nid: 1\n---\n# Idea\n"
+        );
+        assert_eq!(
+            with_frontmatter_id("---\ntags: [x]\n---\n", "1"),
+            "---\nid: 1\ntags: [x]\n---\n"
+        );
+    }
+}